@@ -14,6 +14,7 @@ import symbol.ExternSymbol;
 import symbol.ExternSymbolCreator;
 import serializer.Serializer;
 import ghidra.app.script.GhidraScript;
+import ghidra.program.model.address.Address;
 import ghidra.program.model.block.CodeBlock;
 import ghidra.program.model.block.CodeBlockIterator;
 import ghidra.program.model.block.CodeBlockReferenceIterator;
@@ -39,6 +40,23 @@ public class PcodeExtractor extends GhidraScript {
      */
     @Override
     protected void run() throws Exception { 
+        // The optional second script argument is the image base (in hexadecimal) that the program gets rebased to.
+        if (getScriptArgs().length > 1) {
+            currentProgram.setImageBase(toAddr(Long.parseUnsignedLong(getScriptArgs()[1], 16)), true);
+        }
+        // The further script arguments are the addresses (in hexadecimal) of entry points of bare-metal firmware.
+        // Since raw firmware contains no symbols, the functions at these addresses are created before extracting the P-Code.
+        if (getScriptArgs().length > 2) {
+            for (int i = 2; i < getScriptArgs().length; i++) {
+                Address entry = toAddr(Long.parseUnsignedLong(getScriptArgs()[i], 16));
+                disassemble(entry);
+                if (getFunctionAt(entry) == null) {
+                    createFunction(entry, null);
+                }
+                currentProgram.getSymbolTable().addExternalEntryPoint(entry);
+            }
+            analyzeChanges(currentProgram);
+        }
         HelperFunctions.monitor = getMonitor();
         HelperFunctions.ghidraProgram = currentProgram;
         HelperFunctions.funcMan = currentProgram.getFunctionManager();
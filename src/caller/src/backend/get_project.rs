@@ -1,21 +1,21 @@
 use cwe_checker_lib::{intermediate_representation::Project, utils::log::LogMessage};
 use cwe_checker_lib::pcode::Project as PcodeProject;
 
-pub fn get_ir_project(mut pcode_project: PcodeProject, binary: &[u8], quiet_flag: bool) -> Project{
+/// Convert the P-Code project into the internal representation.
+///
+/// Returns the log messages generated during the conversion together with the project.
+pub fn get_ir_project(mut pcode_project: PcodeProject, binary: &[u8]) -> (Project, Vec<LogMessage>) {
     pcode_project.normalize();
+    let mut logs = Vec::new();
     let project: Project = match cwe_checker_lib::utils::get_binary_base_address(binary) {
         Ok(binary_base_address) => pcode_project.into_ir_project(binary_base_address),
         Err(_err) => {
-            if !quiet_flag {
-                let log = LogMessage::new_info("Could not determine binary base address. Using base address of Ghidra output as fallback.");
-                println!("{}", log);
-            }
-            let mut project = pcode_project.into_ir_project(0);
-            // Setting the address_base_offset to zero is a hack, which worked for the tested PE files.
-            // But this hack will probably not work in general!
-            project.program.term.address_base_offset = 0;
-            project
+            // Without a base address from the binary we cannot detect whether Ghidra rebased the binary.
+            // So we assume that the addresses reported by Ghidra are the addresses of the loaded binary.
+            logs.push(LogMessage::new_info("Could not determine binary base address. Using base address of Ghidra output as fallback."));
+            let ghidra_base_address = u64::from_str_radix(&pcode_project.program.term.image_base, 16).unwrap_or(0);
+            pcode_project.into_ir_project(ghidra_base_address)
         }
     };
-    project
+    (project, logs)
 }
\ No newline at end of file
@@ -3,30 +3,65 @@ pub mod get_project;
 use get_project::get_ir_project;
 use use_ghidra::get_ghidra_result;
 use cwe_checker_lib::intermediate_representation::Project;
+use cwe_checker_lib::pcode::register_overrides;
+use cwe_checker_lib::utils::bare_metal::MemoryMap;
+use cwe_checker_lib::utils::log::LogMessage;
 use std::path::Path;
 
+/// Parse the output of the `p_code_extractor` plugin and apply the configured register overrides.
+/// Returns the log messages generated while parsing together with the parsed project.
+///
+/// Panics with a message describing where the output is truncated or which element is malformed,
+/// unless `allow_partial` is set and the rest of the output can still be used.
+fn parse_pcode_project(
+    reader: impl std::io::Read,
+    allow_partial: bool,
+    register_overrides: &register_overrides::Config,
+) -> (cwe_checker_lib::pcode::Project, Vec<LogMessage>) {
+    let (mut project_pcode, mut logs) = cwe_checker_lib::pcode::parse_project(reader, allow_partial)
+        .unwrap_or_else(|err| panic!("Error: Could not parse the Ghidra output: {:#}", err));
+    logs.append(&mut register_overrides.apply(&mut project_pcode));
+    (project_pcode, logs)
+}
+
 /// Execute the `p_code_extractor` plugin in ghidra and parse its output into the `Project` data structure.
-pub fn get_project_from_ghidra(binary_path: &Path, binary: &[u8], quiet_flag: bool) -> Project {
+/// Returns the log messages generated while parsing together with the project.
+///
+/// Ghidra is executed from the installation at `ghidra_path`.
+/// If `rebase` is set, Ghidra loads the binary at the given image base.
+/// If `project_dir` is set, the Ghidra project is kept in (or reused from) the given directory.
+/// If `save_path` is set, the output of the plugin is saved to the given file,
+/// so that it can be loaded again with [`get_project_from_file`] without running Ghidra.
+/// If `bare_metal` is set, the binary is imported as raw firmware with the given memory map and entry points.
+///
+/// Returns an error message if Ghidra or the plugin failed or if the output of the plugin could not be saved.
+pub fn get_project_from_ghidra(ghidra_path: &Path, binary_path: &Path, binary: &[u8], allow_partial: bool, register_overrides: &register_overrides::Config, rebase: Option<u64>, project_dir: Option<&Path>, save_path: Option<&Path>, bare_metal: Option<(&MemoryMap, &[u64])>) -> Result<(Project, Vec<LogMessage>), String> {
     
-    let (subprocess, fifo_path) = get_ghidra_result(binary_path);
+    let output_file = get_ghidra_result(ghidra_path, binary_path, binary, rebase, project_dir, bare_metal)?;
 
-    // Open the FIFO
-    let file = std::fs::File::open(&fifo_path).expect("Could not open FIFO.");
+    if let Some(save_path) = save_path {
+        if let Err(err) = std::fs::copy(output_file.path(), save_path) {
+            return Err(format!("Error: Could not save the output of the Ghidra plugin to {}: {}", save_path.display(), err));
+        }
+    }
 
-    let project_pcode: cwe_checker_lib::pcode::Project =
-        serde_json::from_reader(std::io::BufReader::new(file)).unwrap();
+    // Open the output of the Ghidra plugin. The temporary file is removed when `output_file` is dropped.
+    let file = std::fs::File::open(output_file.path()).expect("Could not open the output of the Ghidra plugin.");
 
-    subprocess.join().expect("ghidra subprocess error.");
-    get_ir_project(project_pcode, binary, quiet_flag) 
+    let (project_pcode, mut logs) = parse_pcode_project(std::io::BufReader::new(file), allow_partial, register_overrides);
+    let (project, mut conversion_logs) = get_ir_project(project_pcode, binary);
+    logs.append(&mut conversion_logs);
+    Ok((project, logs))
 }
 
 /// get project from a json file extracted by ghidra script
-pub fn get_project_from_file(file_path: &Path, binary: &[u8], quiet_flag: bool) -> Project {
-    // Open the FIFO
-    let file = std::fs::File::open(&file_path).expect("Could not open FIFO.");
+/// Returns the log messages generated while parsing together with the project.
+pub fn get_project_from_file(file_path: &Path, binary: &[u8], allow_partial: bool, register_overrides: &register_overrides::Config) -> (Project, Vec<LogMessage>) {
+    let file = std::fs::File::open(&file_path).expect("Could not open the input file.");
 
-    let project_pcode: cwe_checker_lib::pcode::Project =
-        serde_json::from_reader(std::io::BufReader::new(file)).unwrap();
+    let (project_pcode, mut logs) = parse_pcode_project(std::io::BufReader::new(file), allow_partial, register_overrides);
 
-    get_ir_project(project_pcode, binary, quiet_flag) 
+    let (project, mut conversion_logs) = get_ir_project(project_pcode, binary);
+    logs.append(&mut conversion_logs);
+    (project, logs)
 }
@@ -1,89 +1,268 @@
-use nix::{sys::stat, unistd};
-use std::{process::Command, thread::JoinHandle};
-use std::thread;
+use std::process::Command;
 use std::path::{Path, PathBuf};
+use cwe_checker_lib::utils::audit::get_sha256;
+use cwe_checker_lib::utils::bare_metal::MemoryMap;
+use cwe_checker_lib::utils::get_config_dir;
 
-pub fn get_ghidra_result(binary_path: &Path) -> (JoinHandle<()>, PathBuf) {
-    let ghidra_path: std::path::PathBuf = PathBuf::from(env!("GHIDRA_INSTALL_DIR"));
-    let headless_path = ghidra_path.join("support/analyzeHeadless");
+/// The environment variable containing the path to the Ghidra installation.
+const GHIDRA_PATH_VARIABLE: &str = "GHIDRA_INSTALL_DIR";
 
-    // Find the correct paths for temporary files.
+/// The path of the Ghidra headless analyzer relative to the Ghidra installation directory.
+#[cfg(not(windows))]
+const HEADLESS_ANALYZER_PATH: &str = "support/analyzeHeadless";
+
+/// The path of the Ghidra headless analyzer relative to the Ghidra installation directory.
+#[cfg(windows)]
+const HEADLESS_ANALYZER_PATH: &str = "support/analyzeHeadless.bat";
+
+/// Get the path to the Ghidra installation used by the cwe_checker.
+///
+/// The path is taken from the first of the following sources that is set:
+/// - the given path, e.g. from the `--ghidra-path` command line option,
+/// - the `GHIDRA_INSTALL_DIR` environment variable,
+/// - the `ghidra_path` entry of the `ghidra.json` file in the configuration directory,
+/// - the `GHIDRA_INSTALL_DIR` environment variable at compile time.
+///
+/// Returns an error message if no path is set or if the Ghidra headless analyzer is not found at the path.
+pub fn get_ghidra_install_dir(ghidra_path: Option<&str>) -> Result<PathBuf, String> {
+    let config_file = get_config_dir().join("ghidra.json");
+    let usage = format!(
+        "Set the path to the Ghidra installation with the --ghidra-path option, the {} environment variable or the ghidra_path entry of {}.",
+        GHIDRA_PATH_VARIABLE,
+        config_file.display()
+    );
+    let (ghidra_path, source) = if let Some(path) = ghidra_path {
+        (PathBuf::from(path), "the --ghidra-path option".to_string())
+    } else if let Some(path) = std::env::var_os(GHIDRA_PATH_VARIABLE).filter(|path| !path.is_empty()) {
+        (PathBuf::from(path), format!("the {} environment variable", GHIDRA_PATH_VARIABLE))
+    } else if let Some(path) = read_ghidra_path_from_config(&config_file) {
+        (path, config_file.display().to_string())
+    } else if let Some(path) = option_env!("GHIDRA_INSTALL_DIR") {
+        (PathBuf::from(path), "the build environment".to_string())
+    } else {
+        return Err(format!("Error: The path to the Ghidra installation is not set. {}", usage));
+    };
+    if !ghidra_path.join(HEADLESS_ANALYZER_PATH).is_file() {
+        return Err(format!(
+            "Error: The Ghidra headless analyzer was not found at {} (Ghidra installation path taken from {}). {}",
+            ghidra_path.join(HEADLESS_ANALYZER_PATH).display(),
+            source,
+            usage
+        ));
+    }
+    Ok(ghidra_path)
+}
+
+/// Read the `ghidra_path` entry of the given configuration file.
+/// Returns `None` if the file cannot be read or contains no such entry.
+fn read_ghidra_path_from_config(config_file: &Path) -> Option<PathBuf> {
+    let config: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(config_file).ok()?).ok()?;
+    config["ghidra_path"].as_str().map(PathBuf::from)
+}
+
+/// Execute Ghidra with the `PcodeExtractor.java` script on the given binary.
+/// Returns the temporary file that the script wrote its output to after Ghidra finished,
+/// or an error message (including the output of Ghidra) if Ghidra could not be executed or the script failed.
+///
+/// A regular file is used instead of a named pipe,
+/// so that the cwe_checker also runs on hosts without named pipes like Windows.
+///
+/// If `rebase` is set, Ghidra loads the binary at the given image base instead of the image base chosen by Ghidra.
+///
+/// If `project_dir` is set, the Ghidra project is kept in the given directory instead of being deleted after the analysis.
+/// If the directory already contains the project of the binary from a previous run,
+/// the project is reused and the auto-analysis of Ghidra is skipped.
+///
+/// If `bare_metal` is set, the binary is imported as raw firmware with the given memory map
+/// and functions are created at the given entry points.
+pub fn get_ghidra_result(
+    ghidra_path: &Path,
+    binary_path: &Path,
+    binary: &[u8],
+    rebase: Option<u64>,
+    project_dir: Option<&Path>,
+    bare_metal: Option<(&MemoryMap, &[u64])>,
+) -> Result<TemporaryFile, String> {
+    // Raw firmware is loaded at the load address of its memory map.
+    let rebase = rebase.or_else(|| bare_metal.map(|(memory_map, _)| memory_map.load_address));
+    let headless_path = ghidra_path.join(HEADLESS_ANALYZER_PATH);
+
+    let tmp_folder = get_tmp_folder();
+    let timestamp_suffix = get_timestamp_suffix();
+    let filename = binary_path
+        .file_name()
+        .ok_or_else(|| format!("Error: Invalid file name {}", binary_path.display()))?
+        .to_string_lossy()
+        .to_string();
+
+    // Create a unique name for the output file
+    let output_file = TemporaryFile {
+        path: tmp_folder.join(format!("pcode_{}_{}.json", std::process::id(), timestamp_suffix)),
+    };
+
+    let mut command = Command::new(&headless_path);
+    let mut is_import = true;
+    match project_dir {
+        Some(project_dir) => {
+            let project_name = get_kept_project_name(binary, rebase);
+            if project_dir.join(format!("{}.gpr", project_name)).is_file() {
+                command
+                    .arg(project_dir) // The folder containing the kept Ghidra project
+                    .arg(&project_name) // The name of the kept Ghidra project
+                    .arg("-process") // Process the already analyzed binary of the project
+                    .arg("-noanalysis") // Skip the auto-analysis, since it was already done when the project was created
+                    .arg("-readOnly"); // Do not save changes made by the script to the project
+                is_import = false;
+            } else {
+                command
+                    .arg(project_dir) // The folder where the kept Ghidra project should be stored
+                    .arg(&project_name) // The name of the kept Ghidra project
+                    .arg("-import") // Import a file into the Ghidra project
+                    .arg(binary_path); // File import path
+            }
+        }
+        None => {
+            command
+                .arg(&tmp_folder) // The folder where temporary files should be stored
+                .arg(format!("PcodeExtractor_{}_{}", filename, timestamp_suffix)) // The name of the temporary Ghidra Project.
+                .arg("-import") // Import a file into the Ghidra project
+                .arg(binary_path) // File import path
+                .arg("-deleteProject"); // Delete the temporary project after the script finished
+        }
+    }
+    if let Some((memory_map, _)) = bare_metal.filter(|_| is_import) {
+        command
+            .arg("-loader") // Raw firmware has no file format that Ghidra could detect
+            .arg("BinaryLoader")
+            .arg("-loader-baseAddr") // The address that the firmware is loaded to
+            .arg(format!("{:#x}", memory_map.load_address))
+            .arg("-processor") // The language ID of the CPU architecture
+            .arg(&memory_map.architecture);
+    }
+    command
+        .arg("-postScript") // Execute a script after standard analysis by Ghidra finished
+        .arg("PcodeExtractor.java") // Path to the PcodeExtractor.java
+        .arg(output_file.path()); // The path to the output file
+    if let Some(image_base) = rebase {
+        command.arg(format!("{:x}", image_base)); // The image base that the script rebases the program to
+    }
+    if let Some((_, entry_points)) = bare_metal {
+        for entry_point in entry_points {
+            command.arg(format!("{:x}", entry_point)); // The entry points that the script creates functions at
+        }
+    }
+    command
+        .arg("-analysisTimeoutPerFile") // Set a timeout for how long the standard analysis can run before getting aborted
+        .arg("3600"); // Timeout of one hour (=3600 seconds) // TODO: The post-script can detect that the timeout fired and react accordingly.
+    let output = match command.output() // Execute the command and catch its output.
+    {
+        Ok(output) => output,
+        Err(err) => return Err(format!("Error: Ghidra could not be executed:\n{}", err)),
+    };
+    let ghidra_output = || format!("{}\n{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+
+    if !output.status.success() {
+        return match output.status.code() {
+            Some(code) => Err(format!("{}\nExecution of Ghidra plugin failed with exit code {}", ghidra_output(), code)),
+            None => Err("Execution of Ghidra plugin failed: Process was terminated.".to_string()),
+        };
+    }
+    if !output_file.path().is_file() {
+        return Err(format!("{}\nExecution of Ghidra plugin failed: The plugin did not write any output.", ghidra_output()));
+    }
+
+    Ok(output_file)
+}
+
+/// Get the name of the kept Ghidra project of a binary.
+///
+/// The name contains the hash of the binary and the image base that the binary is rebased to,
+/// so that a project is only reused for the same binary analyzed at the same image base.
+fn get_kept_project_name(binary: &[u8], rebase: Option<u64>) -> String {
+    let hash = get_sha256(binary);
+    match rebase {
+        Some(image_base) => format!("PcodeExtractor_{}_{:x}", &hash[..32], image_base),
+        None => format!("PcodeExtractor_{}", &hash[..32]),
+    }
+}
+
+/// Get the default directory for kept Ghidra projects, i.e. the `ghidra_projects` folder in the cache directory of the cwe_checker.
+/// The directory is created if it does not exist yet.
+pub fn get_default_ghidra_project_dir() -> PathBuf {
+    let project_dirs = directories::ProjectDirs::from("", "", "cwe_checker")
+        .expect("Could not determine path for cached files");
+    let project_dir = project_dirs.cache_dir().join("ghidra_projects");
+    if !project_dir.exists() {
+        std::fs::create_dir_all(&project_dir).expect("Unable to create directory for Ghidra projects");
+    }
+    project_dir
+}
+
+/// Get the folder for temporary files. The folder is created if it does not exist yet.
+fn get_tmp_folder() -> PathBuf {
     let project_dirs = directories::ProjectDirs::from("", "", "cwe_checker")
         .expect("Could not determine path for temporary files");
     let tmp_folder = if let Some(folder) = project_dirs.runtime_dir() {
-        folder
+        folder.to_path_buf()
     } else {
-        Path::new("/tmp/cwe_checker")
+        std::env::temp_dir().join("cwe_checker")
     };
     if !tmp_folder.exists() {
-        std::fs::create_dir(tmp_folder).expect("Unable to create temporary folder");
+        std::fs::create_dir_all(&tmp_folder).expect("Unable to create temporary folder");
     }
-    // We add a timestamp suffix to file names
-    // so that if two instances of the cwe_checker are running in parallel on the same file
-    // they do not interfere with each other.
-    let timestamp_suffix = format!(
+    tmp_folder
+}
+
+/// Get a timestamp to add as suffix to the names of temporary files,
+/// so that if two instances of the cwe_checker are running in parallel on the same file
+/// they do not interfere with each other.
+fn get_timestamp_suffix() -> String {
+    format!(
         "{:?}",
         std::time::SystemTime::now()
             .duration_since(std::time::SystemTime::UNIX_EPOCH)
             .unwrap()
             .as_millis()
-    );
-    let filename = binary_path
-        .file_name()
-        .expect("Invalid file name")
-        .to_string_lossy()
-        .to_string();
+    )
+}
 
-    // Create a unique name for the pipe
-    let fifo_path = tmp_folder.join(format!("pcode_{}.pipe", timestamp_suffix));
+/// A temporary file that is removed when the struct is dropped.
+pub struct TemporaryFile {
+    path: PathBuf,
+}
 
-    // Create a new fifo and give read and write rights to the owner
-    if let Err(err) = unistd::mkfifo(&fifo_path, stat::Mode::from_bits(0o600).unwrap()) {
-        eprintln!("Error creating FIFO pipe: {}", err);
-        std::process::exit(101);
+impl TemporaryFile {
+    /// Write the given binary to a new temporary file, e.g. to import a binary read from stdin into Ghidra.
+    pub fn new_binary(binary: &[u8]) -> TemporaryFile {
+        let path = get_tmp_folder().join(format!(
+            "stdin_{}_{}",
+            std::process::id(),
+            get_timestamp_suffix()
+        ));
+        std::fs::write(&path, binary).expect("Unable to write temporary file");
+        TemporaryFile { path }
     }
 
-    let thread_fifo_path = fifo_path.clone();
-    let thread_file_path = binary_path.to_path_buf();
-    let thread_tmp_folder = tmp_folder.to_path_buf();
-    // Execute Ghidra in a new thread and return a Join Handle, so that the thread is only joined
-    // after the output has been read into the cwe_checker
-    let ghidra_subprocess = thread::spawn(move || {
-        let output = match Command::new(&headless_path)
-            .arg(&thread_tmp_folder) // The folder where temporary files should be stored
-            .arg(format!("PcodeExtractor_{}_{}", filename, timestamp_suffix)) // The name of the temporary Ghidra Project.
-            .arg("-import") // Import a file into the Ghidra project
-            .arg(thread_file_path) // File import path
-            .arg("-postScript") // Execute a script after standard analysis by Ghidra finished
-            .arg("PcodeExtractor.java") // Path to the PcodeExtractor.java
-            .arg(thread_fifo_path) // The path to the named pipe (fifo)
-            .arg("-deleteProject") // Delete the temporary project after the script finished
-            .arg("-analysisTimeoutPerFile") // Set a timeout for how long the standard analysis can run before getting aborted
-            .arg("3600") // Timeout of one hour (=3600 seconds) // TODO: The post-script can detect that the timeout fired and react accordingly.
-            .output() // Execute the command and catch its output.
-        {
-            Ok(output) => output,
-            Err(err) => {
-                eprintln!("Error: Ghidra could not be executed:\n{}", err);
-                std::process::exit(101);
-            }
-        };
+    /// Get the path of the temporary file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
 
-        if !output.status.success() {
-            match output.status.code() {
-                Some(code) => {
-                    eprintln!("{}", String::from_utf8(output.stdout).unwrap());
-                    eprintln!("{}", String::from_utf8(output.stderr).unwrap());
-                    eprintln!("Execution of Ghidra plugin failed with exit code {}", code);
-                    std::process::exit(101);
-                }
-                None => {
-                    eprintln!("Execution of Ghidra plugin failed: Process was terminated.");
-                    std::process::exit(101);
-                }
-            }
-        }
-    });
-    
-    (ghidra_subprocess, fifo_path.clone())
-}
\ No newline at end of file
+impl Drop for TemporaryFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+/// Get the version of the Ghidra installation used by the cwe_checker
+/// from the `application.version` entry of its `application.properties` file.
+/// Returns `None` if the version cannot be determined.
+pub fn get_ghidra_version(ghidra_path: &Path) -> Option<String> {
+    let properties_path = ghidra_path.join("Ghidra/application.properties");
+    let properties = std::fs::read_to_string(properties_path).ok()?;
+    properties
+        .lines()
+        .find_map(|line| line.strip_prefix("application.version="))
+        .map(|version| version.trim().to_string())
+}
@@ -1,64 +1,779 @@
 mod backend;
 
+use cwe_checker_lib::analysis::attack_surface::AttackSurfaceReport;
+use cwe_checker_lib::analysis::call_graph::{self, CallGraph, CallGraphFormat};
+use cwe_checker_lib::analysis::emulation;
+use cwe_checker_lib::analysis::fact_export::{FactBase, FactFormat};
+use cwe_checker_lib::analysis::function_matching::FunctionSignatures;
+use cwe_checker_lib::analysis::function_summary::FunctionSummaries;
 use cwe_checker_lib::analysis::graph;
-use cwe_checker_lib::utils::binary::RuntimeMemoryImage;
-use cwe_checker_lib::utils::log::print_all_messages;
-use cwe_checker_lib::utils::read_config_file;
-use cwe_checker_lib::AnalysisResults;
-use std::collections::HashSet;
-use std::path::PathBuf;
+use cwe_checker_lib::analysis::pointer_inference::checkpoint::CheckpointConfig;
+use cwe_checker_lib::analysis::pointer_inference::RunOptions;
+use cwe_checker_lib::utils::address_ranges::{self, AddressRange, AddressScope};
+use cwe_checker_lib::utils::artifact::{
+    read_artifact, read_binary_artifact, write_artifact, write_binary_artifact,
+};
+use cwe_checker_lib::utils::audit::AuditLog;
+use cwe_checker_lib::utils::bare_metal::MemoryMap;
+use cwe_checker_lib::utils::batch::{self, BatchSummary};
+use cwe_checker_lib::utils::binary::{HarvardConfig, RuntimeMemoryImage};
+use cwe_checker_lib::utils::cancellation::CancellationToken;
+use cwe_checker_lib::utils::chunking;
+use cwe_checker_lib::utils::components::{self, Components};
+use cwe_checker_lib::utils::config_validation;
+use cwe_checker_lib::utils::core_dump;
+use cwe_checker_lib::utils::deadline::{parse_duration, Deadline};
+use cwe_checker_lib::utils::deduplication::WarningDeduplicator;
+use cwe_checker_lib::utils::default_config;
+use cwe_checker_lib::utils::demangle::{demangle_log_messages, demangle_warnings};
+use cwe_checker_lib::utils::diff::ResultsDiff;
+use cwe_checker_lib::utils::entry_points;
+use cwe_checker_lib::utils::extern_stubs;
+use cwe_checker_lib::utils::fingerprint::add_fingerprints;
+use cwe_checker_lib::utils::function_selection;
+use cwe_checker_lib::utils::generated_code;
+use cwe_checker_lib::utils::ir_context::add_ir_context;
+use cwe_checker_lib::utils::localization::{MessageCatalog, DEFAULT_LANGUAGE};
+use cwe_checker_lib::utils::log::{
+    print_all_messages, CweWarning, FailOnPolicy, JsonOutput, LogMessage, OutputFormat, Severity,
+    StreamWriter, StreamedMessage, Verbosity, Verdict,
+};
+use cwe_checker_lib::utils::output_sinks::{self, new_output_sink, OutputSink};
+use cwe_checker_lib::utils::path_witness::{add_path_witnesses, write_dot_files};
+use cwe_checker_lib::utils::perf_stats::{self, PerfStats};
+use cwe_checker_lib::utils::report::generate_html_report;
+use cwe_checker_lib::utils::resource_budget::ResourceBudget;
+use cwe_checker_lib::utils::run_statistics::RunStatistics;
+use cwe_checker_lib::utils::sdk_models;
+use cwe_checker_lib::utils::suppression::SuppressionFile;
+use cwe_checker_lib::utils::syscalls;
+use cwe_checker_lib::utils::taint_coverage;
+use cwe_checker_lib::utils::toolchain::{self, identify_toolchains};
+use cwe_checker_lib::utils::{
+    apply_config_override, get_config_file_path, parse_config_file, read_config_file,
+};
+use cwe_checker_lib::{AnalysisResults, RequiredAnalysis};
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use structopt::StructOpt;
+use backend::use_ghidra::{
+    get_default_ghidra_project_dir, get_ghidra_install_dir, get_ghidra_version, TemporaryFile,
+};
 use backend::{get_project_from_file, get_project_from_ghidra};
-use cwe_checker_lib::intermediate_representation::Project;
+use cwe_checker_lib::intermediate_representation::{NormalizationConfig, Project};
+use cwe_checker_lib::pcode::register_overrides;
+use cwe_checker_lib::pcode::validation::ValidationReport;
 
 
-#[derive(Debug, StructOpt)]
+/// The binary path denoting that the binary is read from stdin.
+const STDIN_PATH: &str = "-";
+
+#[derive(Debug, Clone, StructOpt)]
 /// Find vulnerable patterns in binary executables
+///
+/// Use 'cwe_checker validate-pcode PROJECT' to check a saved output of the Ghidra plugin without running any analysis.
 struct CmdlineArgs {
     /// The path to the binary.
-    #[structopt(required_unless("module-versions"),  validator(check_file_existence))]
-    binary: Option<String>,
+    ///
+    /// Several binaries or directories can be given to analyze a batch of binaries, e.g. an unpacked firmware image.
+    /// Directories are searched recursively for ELF and PE files.
+    /// The results of each binary are then written to a file in the '--output-dir' directory.
+    ///
+    /// Use '-' to read a single binary from stdin.
+    #[structopt(required_unless_one(&["module-versions", "module-info", "generate-config", "diff"]),  validator(check_binary_path))]
+    binary: Vec<String>,
+
+    /// The directory for the results when analyzing a batch of binaries.
+    /// The results of each binary are written to a file named after the binary in the format of the '--format' option.
+    /// An aggregate summary of all binaries is written to 'summary.json' and printed.
+    /// The statistics of the whole batch (warnings per check and severity, analysis times and failure reasons)
+    /// are written to 'dashboard.json', e.g. for rendering a dashboard.
+    #[structopt(long, value_name = "DIR")]
+    output_dir: Option<String>,
 
     /// Path to a custom configuration file to use instead of the standard one.
+    /// Files with the extension '.toml' are read as TOML, all other files as JSON.
     #[structopt(long, short, validator(check_file_existence))]
     config: Option<String>,
 
-    /// Write the results to a file.
-    #[structopt(long, short)]
-    out: Option<String>,
+    /// Override a single value of the configuration file, given as 'KEY_PATH=VALUE', e.g. '--set Packer.entropy_threshold=7.5'.
+    /// The key path starts with the section name, followed by dot-separated keys of nested objects or indices of arrays.
+    /// The value is parsed as JSON if possible and used as a string otherwise.
+    /// Parameters in the 'parameters' list of a section can be set like keys, e.g. '--set CWE476.strict_call_policy=false'.
+    /// Can be given multiple times.
+    #[structopt(long, number_of_values = 1, value_name = "KEY_PATH=VALUE")]
+    set: Vec<String>,
+
+    /// Write the results to a file or another destination instead of printing them.
+    /// Can be given multiple times to write the results to several destinations or in different formats at once,
+    /// each given as 'DESTINATION:FORMAT', e.g. '--out report.sarif:sarif --out findings.json:json'.
+    /// Besides file paths the destination can be '-' for stdout, 'syslog' for the local syslog daemon,
+    /// 'syslog://HOST:PORT' for a syslog server or an 'http://' or 'https://' URL of a webhook receiving the results via POST.
+    /// Destinations given without a format use the format of the '--format' option, except for webhooks receiving the JSON output.
+    /// Further destinations can be given in the 'OutputSinks' section of the configuration file.
+    /// When streaming the results, they are only written to the first file.
+    #[structopt(long, short, number_of_values = 1, value_name = "DESTINATION[:FORMAT]")]
+    out: Vec<String>,
 
     /// path to a file generated by ghidra script
     #[structopt(long)]
     project: Option<String>,
 
+    /// Save the output of the Ghidra plugin to the given file.
+    /// The file can be analyzed again with '--project' without running Ghidra,
+    /// e.g. to cache the time-consuming Ghidra step in CI pipelines.
+    #[structopt(long, value_name = "PATH", conflicts_with = "project")]
+    save_project: Option<String>,
+
+    /// Load the intermediate representation of the binary from a file written by '--save-ir' instead of running Ghidra.
+    /// Loading the compact binary file is considerably faster than parsing the output of the Ghidra plugin.
+    #[structopt(
+        long,
+        value_name = "PATH",
+        conflicts_with_all(&["project", "save_project"]),
+        validator(check_file_existence)
+    )]
+    load_ir: Option<String>,
+
+    /// Save the intermediate representation of the binary in a compact binary format to the given file,
+    /// so that later runs can load it with '--load-ir'.
+    /// The file is only valid for the version of the cwe_checker that generated it.
+    #[structopt(long, value_name = "PATH", conflicts_with = "load_ir")]
+    save_ir: Option<String>,
+
+    /// Path to the Ghidra installation directory.
+    ///
+    /// Without this option the path is taken from the GHIDRA_INSTALL_DIR environment variable
+    /// or from the "ghidra_path" entry of the 'ghidra.json' file in the configuration directory.
+    #[structopt(long, value_name = "DIR", conflicts_with = "project")]
+    ghidra_path: Option<String>,
+
+    /// Keep the Ghidra project of the analyzed binary in the given directory
+    /// instead of deleting it after the analysis.
+    /// Later runs on the same binary reuse the project and skip the time-consuming auto-analysis of Ghidra.
+    #[structopt(long, value_name = "DIR", conflicts_with = "project")]
+    ghidra_project: Option<String>,
+
+    /// Keep the Ghidra project of the analyzed binary in the cache directory of the cwe_checker
+    /// and reuse it in later runs on the same binary.
+    /// Shorthand for '--ghidra-project' with the 'ghidra_projects' folder in the cache directory.
+    #[structopt(long, conflicts_with_all(&["project", "ghidra_project"]))]
+    keep_ghidra_project: bool,
+
+    /// Rebase the binary to the given image base (in hexadecimal, e.g. '0x400000') before the analysis.
+    /// All reported addresses then correspond to the binary loaded at this image base.
+    /// Without this option position-independent binaries are analyzed at the image base chosen by Ghidra.
+    #[structopt(
+        long,
+        value_name = "ADDRESS",
+        conflicts_with = "project",
+        validator(check_hex_address)
+    )]
+    rebase: Option<String>,
+
+    /// Analyze the binary as it was loaded into the process of the given ELF core dump.
+    ///
+    /// The binary is rebased to the image base at which it was loaded
+    /// and the memory contained in the core dump (e.g. relocated pointers and loaded libraries)
+    /// replaces the memory of the binary file for the analysis.
+    #[structopt(
+        long,
+        value_name = "CORE_FILE",
+        conflicts_with_all(&["project", "rebase"]),
+        validator(check_file_existence)
+    )]
+    core: Option<String>,
+
+    /// Analyze the binary as raw bare-metal firmware without ELF or PE headers.
+    ///
+    /// The given JSON file contains the memory map of the firmware:
+    /// the Ghidra language ID of the CPU architecture, the load address of the firmware, the RAM and ROM regions of the device
+    /// and the location of the vector table or the addresses of the entry points of the firmware.
+    #[structopt(
+        long,
+        value_name = "MEMORY_MAP",
+        conflicts_with_all(&["core", "rebase"]),
+        validator(check_file_existence)
+    )]
+    bare_metal: Option<String>,
+
     /// Specify a specific set of checks to be run as a comma separated list, e.g. 'CWE332,CWE476,CWE782'.
     ///
     /// Use the "--module-names" command line option to get a list of all valid check names.
     #[structopt(long, short)]
     partial: Option<String>,
 
-    /// Generate JSON output.
-    #[structopt(long, short)]
+    /// Skip the given checks as a comma separated list, e.g. 'CWE78,CWE467'.
+    ///
+    /// All other checks that would be run without this option are still run.
+    /// Use the "--module-names" command line option to get a list of all valid check names.
+    #[structopt(long)]
+    skip: Option<String>,
+
+    /// Additional entry points for the interprocedural analyses as a comma separated list
+    /// of function names or hexadecimal function start addresses, e.g. 'plugin_init,0x401000'.
+    ///
+    /// These are added to the entry points configured in the "EntryPoints" section of the configuration file.
+    #[structopt(long)]
+    entry_points: Option<String>,
+
+    /// Only analyze the given functions and the functions called by them.
+    /// The functions are given as a comma separated list of function names or hexadecimal function start addresses,
+    /// e.g. 'parse_header,0x401000'.
+    ///
+    /// The given functions become the entry points of the analysis.
+    /// Calls to functions that are not analyzed are treated like calls to unknown extern functions.
+    #[structopt(long)]
+    function: Option<String>,
+
+    /// Only analyze the given address ranges as a comma separated list, e.g. '0x400000-0x408000'.
+    ///
+    /// Functions starting outside of the ranges are not analyzed and warnings outside of the ranges are not reported.
+    /// The ranges are added to the included ranges configured in the "AddressRanges" section of the configuration file.
+    #[structopt(long, validator(check_address_ranges))]
+    include_range: Option<String>,
+
+    /// Exclude the given address ranges from the analysis as a comma separated list, e.g. '0x400000-0x408000'.
+    ///
+    /// The ranges are added to the excluded ranges configured in the "AddressRanges" section of the configuration file.
+    #[structopt(long, validator(check_address_ranges))]
+    exclude_range: Option<String>,
+
+    /// Time budget for the whole analysis, e.g. '30m', '90s' or '1h30m'.
+    ///
+    /// As the deadline approaches, the pointer inference reduces its precision to stabilize faster.
+    /// Analysis phases that cannot be finished in time are stopped early or skipped,
+    /// and the unfinished results of the pointer inference are discarded.
+    /// The results are annotated with log messages on what was skipped.
+    #[structopt(long, validator(check_duration))]
+    deadline: Option<String>,
+
+    /// Hard time limit for the whole analysis, e.g. '2h'.
+    ///
+    /// In contrast to the deadline, the time is not distributed among the analysis phases.
+    /// When the time is up, running computations are stopped and the remaining modules are skipped,
+    /// so that the results found so far are still reported.
+    /// The generation of the project by Ghidra is not interrupted.
+    #[structopt(long, validator(check_duration))]
+    timeout: Option<String>,
+
+    /// Time limit for each check module and for the pointer inference analysis, e.g. '10m'.
+    ///
+    /// Computations exceeding the limit are stopped and the results found so far are reported.
+    /// Modules without fixpoint computations always run to completion.
+    #[structopt(long, validator(check_duration))]
+    module_timeout: Option<String>,
+
+    /// Lift the limits of the resource budget for expensive checks like CWE78.
+    ///
+    /// Without this flag, expensive checks stop or skip computations exceeding the limits
+    /// configured in the "ResourceBudget" section of the configuration file.
+    #[structopt(long)]
+    thorough: bool,
+
+    /// Periodically save checkpoints of the pointer inference analysis to the given file.
+    ///
+    /// If the file already exists, the analysis is resumed from the checkpoint contained in it.
+    /// The file is removed after the analysis finished.
+    #[structopt(long)]
+    checkpoint: Option<String>,
+
+    /// Time between two checkpoints, e.g. '10m'.
+    #[structopt(long, default_value = "10m", validator(check_duration))]
+    checkpoint_interval: String,
+
+    /// Generate JSON output. Shorthand for '--format json'.
+    #[structopt(long, short, conflicts_with("format"))]
     json: bool,
 
-    /// Do not print log messages. This prevents polluting STDOUT for json output.
-    #[structopt(long, short)]
+    /// Write each warning and log message as a line of JSON (newline-delimited JSON) as soon as the module that found it finishes,
+    /// instead of printing all results at the end of the analysis.
+    /// Long-running checks (e.g. CWE78) additionally report their warnings as preliminary warnings as soon as they are found.
+    #[structopt(long, conflicts_with_all(&["json", "sarif", "format"]))]
+    stream: bool,
+
+    /// Generate output in the SARIF 2.1.0 format, e.g. for the upload to code scanning services.
+    /// Shorthand for '--format sarif'.
+    #[structopt(long, conflicts_with_all(&["json", "format"]))]
+    sarif: bool,
+
+    /// Only output CWE warnings with at least the given severity.
+    #[structopt(long, possible_values(&["info", "low", "medium", "high"]))]
+    min_severity: Option<String>,
+
+    /// Only output CWE warnings with at least the given verdict.
+    /// Warnings are 'proven' if the reported issue exists on all modeled paths
+    /// and 'possible' if it exists on some path of the over-approximated program behavior.
+    /// Use 'proven' to only output the proven warnings.
+    #[structopt(long, possible_values(&["possible", "proven"]))]
+    min_verdict: Option<String>,
+
+    /// Exit with exit code 1 if any reported warning matches the given policy, e.g. for use as a CI gate.
+    /// The policy is a comma separated list of a minimum severity and check names,
+    /// e.g. 'high', 'CWE416,CWE476' or 'medium,CWE78'.
+    /// Only warnings that are reported after filtering and suppression are considered.
+    #[structopt(
+        long,
+        value_name = "SEVERITY|CWE-LIST",
+        validator(check_fail_on_policy)
+    )]
+    fail_on: Option<String>,
+
+    /// The format of the CWE warnings in the output.
+    /// The text-plain format contains one line of the form '[CWExxx] (version) description' per warning.
+    /// The CSV format contains one row per warning, e.g. for the import into spreadsheets or ticketing systems.
+    /// The JSON format is a versioned object containing the warnings and the log messages,
+    /// whose schema is published in 'doc/json_output_schema.json'.
+    #[structopt(long, possible_values(&["text", "text-plain", "json", "csv", "sarif"]))]
+    format: Option<String>,
+
+    /// Do not report the warnings listed in the given suppression file.
+    #[structopt(long, validator(check_file_existence))]
+    suppress: Option<String>,
+
+    /// Write a baseline suppressing all warnings of this run to the given file.
+    /// The baseline is generated before the warnings listed in the '--suppress' file are removed.
+    #[structopt(long)]
+    write_baseline: Option<String>,
+
+    /// Write the signatures of all functions of the binary to the given file.
+    /// The signatures of two versions of a binary can be given to '--diff-functions'
+    /// to match the functions of the two versions.
+    #[structopt(long)]
+    export_functions: Option<String>,
+
+    /// Attach the terms of the intermediate representation around the location of each warning to the warning,
+    /// up to the given number of terms before and after the location.
+    /// The terms show which operation triggered the warning.
+    #[structopt(long, value_name = "TERMS")]
+    ir_context: Option<usize>,
+
+    /// Attach a witness path to each warning reporting a flow from a source to a sink,
+    /// i.e. the basic blocks and edges (with branch conditions) along a path from the source to the sink
+    /// in the control flow graph.
+    ///
+    /// If a directory is given, the witness of each warning is additionally written to a file in the DOT format of Graphviz,
+    /// named after the fingerprint of the warning.
+    #[structopt(long, value_name = "DIR")]
+    path_witness: Option<Option<String>>,
+
+    /// Additionally write a self-contained HTML report of all warnings and log messages to the given file.
+    #[structopt(long)]
+    report_html: Option<String>,
+
+    /// The language of the warning descriptions, given as a language code (e.g. 'de')
+    /// or as a path to a message catalog file.
+    ///
+    /// Only the descriptions are translated, all other fields of the warnings stay language-independent.
+    #[structopt(long, default_value = DEFAULT_LANGUAGE)]
+    lang: String,
+
+    /// Print counters of performance-relevant operations after the analysis
+    /// (domain joins, widenings, unstabilized fixpoint nodes, map clones, expression allocations).
+    /// The counters are only collected if the cwe_checker was built with the 'perf-stats' feature.
+    /// The counters are printed to STDERR, so that they do not interfere with the output of the results.
+    #[structopt(long)]
+    perf_counters: bool,
+
+    /// Print the runtime, the number of warnings and the peak memory usage of each module after the analysis,
+    /// together with the number of analyzed functions and the runtime of the pointer inference analysis.
+    /// The statistics are printed to STDERR, so that they do not interfere with the output of the results.
+    #[structopt(long)]
+    statistics: bool,
+
+    /// Write the audit log of the analysis run as JSON to the given file.
+    /// The audit log records the versions of the cwe_checker, of Ghidra and of the executed checks,
+    /// hashes of the configuration and of the analyzed files, the wall times of the analysis phases and the number of warnings.
+    /// It is always contained in the JSON output.
+    #[structopt(long)]
+    audit_log: Option<String>,
+
+    /// Write the statistics collected with '--statistics' as JSON to the given file instead of printing them.
+    #[structopt(long, requires("statistics"))]
+    statistics_out: Option<String>,
+
+    /// Continue with the successfully parsed part of the Ghidra output if it is truncated (e.g. because Ghidra crashed)
+    /// or contains malformed functions. The incomplete or malformed functions are removed from the analysis.
+    #[structopt(long)]
+    allow_truncated_input: bool,
+
+    /// Do not colorize the text output.
+    /// The text output is only colorized if it is printed to a terminal and the 'NO_COLOR' environment variable is not set.
+    #[structopt(long)]
+    no_color: bool,
+
+    /// Increase the verbosity of the log output.
+    ///
+    /// By default only error messages are printed.
+    /// Use '-v' to also print info messages, '-vv' to print debug messages
+    /// and '-vvv' to also print debug messages for single blocks and instructions.
+    /// The JSON output always contains all log messages.
+    #[structopt(long, short, parse(from_occurrences))]
+    verbose: u64,
+
+    /// Deprecated: Only print error messages, which is the default.
+    #[structopt(long, short, conflicts_with = "verbose")]
     quiet: bool,
 
+    /// Write a report on the attack surface of the binary as JSON to the given file,
+    /// i.e. which imported functions are reachable from the entry points
+    /// and which entry points are never called from inside the binary.
+    #[structopt(long)]
+    attack_surface: Option<String>,
+
+    /// Export the computed facts (call graph, points-to facts of the pointer inference and taint sources)
+    /// as relational tables to the given directory.
+    ///
+    /// The taint sources are configured in the "FactExport" section of the configuration file.
+    #[structopt(long)]
+    export_facts: Option<String>,
+
+    /// The file format of the exported facts, either 'souffle' (tab-separated .facts files) or 'csv'.
+    #[structopt(long, default_value = "souffle", validator(check_fact_format))]
+    fact_format: String,
+
+    /// Export the call graph of the binary to the given file.
+    /// Indirect calls are added to the call graph if the pointer inference analysis can resolve their targets.
+    #[structopt(long)]
+    export_call_graph: Option<String>,
+
+    /// The file format of the exported call graph, either 'json' or 'graphml'.
+    #[structopt(long, default_value = "json", validator(check_call_graph_format))]
+    call_graph_format: String,
+
+    /// Export a summary of the analysis results for each function as JSON to the given file,
+    /// e.g. for the selection of fuzzing targets.
+    /// The summaries list the reachable sinks, whether the parameters of a function may contain untrusted input
+    /// and the size of the largest stack buffer of each function.
+    ///
+    /// The taint sources and sinks are configured in the "FunctionSummaries" section of the configuration file.
+    #[structopt(long)]
+    export_function_summaries: Option<String>,
+
+    /// Print the control flow graph of the given function (name or TID) in the DOT format of Graphviz and then quit.
+    /// Use 'all' to print the whole interprocedural control flow graph.
+    #[structopt(long, value_name = "FUNCTION")]
+    dump_cfg: Option<String>,
+
     /// Prints out the version numbers of all known modules.
     #[structopt(long)]
     module_versions: bool,
 
-    /// Output for debugging purposes.
+    /// Prints out a description of each known module:
+    /// the checked CWEs, the version, the required analyses and the configuration keys read by the module.
+    /// With '--json' the descriptions are printed as JSON.
+    #[structopt(long)]
+    module_info: bool,
+
+    /// Write the configuration to the given file and then quit.
+    /// The file contains the installed configuration file (or the configuration given by '--config'),
+    /// supplemented by all sections and keys of the default configuration missing in it.
+    /// Each configuration key is documented by an additional '_comment_KEY' entry.
+    #[structopt(long, value_name = "FILE")]
+    generate_config: Option<String>,
+
+    /// Compare the JSON results of two analysis runs given as 'OLD NEW' and report new, fixed and persisting warnings.
+    /// Warnings are matched by their fingerprints. With '--json' the comparison is output as JSON.
+    #[structopt(long, number_of_values = 2, value_names = &["OLD", "NEW"], validator(check_file_existence))]
+    diff: Option<Vec<String>>,
+
+    /// The function signatures of the two binaries compared with '--diff', given as 'OLD NEW'.
+    /// Warnings whose fingerprints differ are then also matched if they are located in matched functions,
+    /// which is necessary to compare the results for functions of stripped binaries that changed between the versions.
+    #[structopt(long, requires = "diff", number_of_values = 2, value_names = &["OLD", "NEW"], validator(check_file_existence))]
+    diff_functions: Option<Vec<String>>,
+
+    /// Output for debugging purposes instead of running the checks.
+    /// Either 'pointer-inference' (print the results of the pointer inference analysis)
+    /// or 'passes' (print statistics on the normalization passes).
     /// The current behavior of this flag is unstable and subject to change.
-    #[structopt(long, hidden = true)]
-    debug: bool,
+    #[structopt(long, hidden = true, possible_values(&["pointer-inference", "passes"]))]
+    debug: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "cwe_checker validate-pcode")]
+/// Parse, normalize and check a saved output of the Ghidra plugin without running any analysis.
+///
+/// Reports functions that cannot be parsed, unknown P-Code operations,
+/// instructions with inconsistent operand sizes and registers missing in the register properties.
+/// Exits with a non-zero exit code if problems were found.
+struct ValidatePcodeArgs {
+    /// The path to the JSON file generated by the Ghidra plugin.
+    #[structopt(validator(check_file_existence))]
+    project: String,
+
+    /// Print the report as JSON.
+    #[structopt(long, short)]
+    json: bool,
 }
 
 fn main() {
+    if std::env::args_os()
+        .nth(1)
+        .map_or(false, |arg| arg == "validate-pcode")
+    {
+        // The subcommand name takes the place of the program name for the subcommand arguments.
+        validate_pcode(ValidatePcodeArgs::from_iter(std::env::args_os().skip(1)));
+        return;
+    }
     let cmdline_args = CmdlineArgs::from_args();
+    if cmdline_args.quiet {
+        eprintln!("Warning: The '--quiet' option is deprecated. Only error messages are printed by default.");
+    }
+
+    if let Some(ref result_files) = cmdline_args.diff {
+        diff_results(
+            &result_files[0],
+            &result_files[1],
+            cmdline_args.diff_functions.as_deref(),
+            cmdline_args.json,
+        );
+        return;
+    }
+    if let Some(ref config_path) = cmdline_args.generate_config {
+        generate_config(
+            config_path,
+            cmdline_args.config.as_deref(),
+            &cmdline_args.set,
+        );
+        return;
+    }
+    let is_batch = cmdline_args.binary.len() > 1
+        || cmdline_args
+            .binary
+            .first()
+            .map_or(false, |path| std::path::Path::new(path).is_dir());
+    if is_batch {
+        if cmdline_args.binary.iter().any(|path| path == STDIN_PATH) {
+            panic!(
+                "Error: A binary can only be read from stdin if it is the only binary to analyze."
+            );
+        }
+        run_batch(cmdline_args);
+        return;
+    }
+    let fail_on: Option<FailOnPolicy> = cmdline_args
+        .fail_on
+        .as_ref()
+        .map(|policy| policy.parse().unwrap());
+    let cwes = run_with_ghidra(cmdline_args);
+    if let Some(policy) = fail_on {
+        if policy.matches_any(&cwes) {
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Analyze each binary of a batch and write the results of each binary to a file in the output directory.
+/// A failed analysis of a binary does not stop the analysis of the remaining binaries.
+/// The aggregate summary of all binaries is written to the output directory and printed.
+/// The aggregated statistics of the batch (e.g. for a dashboard) are written to the output directory, too.
+fn run_batch(args: CmdlineArgs) {
+    let per_binary_options = [
+        ("--out", !args.out.is_empty()),
+        ("--stream", args.stream),
+        ("--project", args.project.is_some()),
+        ("--save-project", args.save_project.is_some()),
+        ("--load-ir", args.load_ir.is_some()),
+        ("--save-ir", args.save_ir.is_some()),
+        ("--write-baseline", args.write_baseline.is_some()),
+        ("--export-functions", args.export_functions.is_some()),
+        ("--report-html", args.report_html.is_some()),
+        ("--attack-surface", args.attack_surface.is_some()),
+        ("--export-facts", args.export_facts.is_some()),
+        ("--export-call-graph", args.export_call_graph.is_some()),
+        (
+            "--export-function-summaries",
+            args.export_function_summaries.is_some(),
+        ),
+        ("--checkpoint", args.checkpoint.is_some()),
+        ("--statistics-out", args.statistics_out.is_some()),
+        ("--audit-log", args.audit_log.is_some()),
+        ("--dump-cfg", args.dump_cfg.is_some()),
+    ];
+    for (option, is_set) in per_binary_options.iter() {
+        if *is_set {
+            panic!(
+                "Error: {} cannot be used when analyzing a batch of binaries.",
+                option
+            );
+        }
+    }
+    let output_dir = PathBuf::from(
+        args.output_dir
+            .as_ref()
+            .expect("Error: Analyzing a batch of binaries requires the --output-dir option."),
+    );
+    let binaries = batch::collect_binaries(&args.binary)
+        .unwrap_or_else(|err| panic!("Error while collecting the binaries: {}", err));
+    let format = get_format_name(&args);
+    let extension = match format {
+        "text" | "text-plain" => "txt",
+        format => format,
+    };
+    let fail_on: Option<FailOnPolicy> = args.fail_on.as_ref().map(|policy| policy.parse().unwrap());
+    let mut fail = false;
+    let mut summary = BatchSummary::new();
+    for (binary_path, relative_path) in binaries {
+        let result_path = output_dir.join(format!("{}.{}", relative_path.display(), extension));
+        if let Some(parent) = result_path.parent() {
+            std::fs::create_dir_all(parent)
+                .unwrap_or_else(|err| panic!("Error while creating the output directory: {}", err));
+        }
+        let mut binary_args = args.clone();
+        binary_args.binary = vec![binary_path.display().to_string()];
+        binary_args.out = vec![format!("{}:{}", result_path.display(), format)];
+        if Verbosity::from_occurrences(args.verbose) >= Verbosity::Info {
+            eprintln!("Analyzing {}", binary_path.display());
+        }
+        let start_time = std::time::Instant::now();
+        match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            run_with_ghidra(binary_args)
+        })) {
+            Ok(cwes) => {
+                if let Some(ref policy) = fail_on {
+                    fail |= policy.matches_any(&cwes);
+                }
+                summary.add_result(&binary_path, &result_path, &cwes, start_time.elapsed());
+            }
+            Err(panic) => {
+                let message = panic
+                    .downcast_ref::<String>()
+                    .cloned()
+                    .or_else(|| {
+                        panic
+                            .downcast_ref::<&str>()
+                            .map(|message| message.to_string())
+                    })
+                    .unwrap_or_else(|| "Unknown error".to_string());
+                summary.add_error(&binary_path, message, start_time.elapsed());
+            }
+        }
+    }
+    let file = std::fs::File::create(output_dir.join("summary.json")).unwrap();
+    write_artifact(&summary, std::io::BufWriter::new(file)).unwrap();
+    let file = std::fs::File::create(output_dir.join("dashboard.json")).unwrap();
+    write_artifact(&summary.get_dashboard(), std::io::BufWriter::new(file)).unwrap();
+    println!("{}", summary);
+    if fail {
+        std::process::exit(1);
+    }
+}
 
-    run_with_ghidra(cmdline_args);
+/// Get the name of the output format selected by the command line arguments.
+fn get_format_name(args: &CmdlineArgs) -> &str {
+    if args.sarif {
+        "sarif"
+    } else if args.json {
+        "json"
+    } else {
+        args.format.as_deref().unwrap_or("text")
+    }
+}
+
+/// Validate a saved output of the Ghidra plugin and print the validation report.
+fn validate_pcode(args: ValidatePcodeArgs) {
+    let file = std::io::BufReader::new(std::fs::File::open(&args.project).unwrap());
+    let report = ValidationReport::validate(file)
+        .unwrap_or_else(|err| panic!("Error while validating {}: {:#}", args.project, err));
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        print!("{}", report);
+    }
+    if !report.is_ok() {
+        std::process::exit(1);
+    }
+}
+
+/// Print the differences between the warnings of two analysis runs.
+/// If the paths to the function signatures of both binaries are given, they are used to match the functions of the binaries.
+fn diff_results(
+    old_results_path: &str,
+    new_results_path: &str,
+    function_signature_paths: Option<&[String]>,
+    emit_json: bool,
+) {
+    let read_warnings = |path: &str| -> Vec<CweWarning> {
+        let file = std::io::BufReader::new(std::fs::File::open(path).unwrap());
+        let results: serde_json::Value = serde_json::from_reader(file)
+            .unwrap_or_else(|err| panic!("Error while parsing the results in {}: {}", path, err));
+        JsonOutput::read_warnings(results)
+            .unwrap_or_else(|err| panic!("Error while parsing the results in {}: {}", path, err))
+    };
+    let read_functions = |path: &str| -> FunctionSignatures {
+        let file = std::io::BufReader::new(std::fs::File::open(path).unwrap());
+        read_artifact(file).unwrap_or_else(|err| {
+            panic!(
+                "Error while reading the function signatures in {}: {}",
+                path, err
+            )
+        })
+    };
+    let old_warnings = read_warnings(old_results_path);
+    let new_warnings = read_warnings(new_results_path);
+    let diff = match function_signature_paths {
+        Some(paths) => ResultsDiff::with_function_matching(
+            &old_warnings,
+            &new_warnings,
+            &read_functions(&paths[0]),
+            &read_functions(&paths[1]),
+        ),
+        None => ResultsDiff::new(&old_warnings, &new_warnings),
+    };
+    if emit_json {
+        println!("{}", serde_json::to_string_pretty(&diff).unwrap());
+    } else {
+        print!("{}", diff);
+    }
+}
+
+/// Write the configuration with a comment on each configuration key to the given path.
+/// The configuration is read from the given file or else from the installed configuration file.
+/// If no configuration file is installed, the default configuration is written.
+/// The overrides given by '--set' are applied to the written configuration.
+fn generate_config(output_path: &str, config_path: Option<&str>, overrides: &[String]) {
+    let mut config = match config_path {
+        Some(path) => parse_config_file(Path::new(path))
+            .unwrap_or_else(|err| panic!("Parsing of the configuration file failed: {}", err)),
+        None if get_config_file_path("config.json").exists() => read_config_file("config.json"),
+        None => default_config::get_default_config(),
+    };
+    apply_config_overrides(&mut config, overrides);
+    default_config::add_missing_defaults(&mut config);
+    default_config::add_key_comments(&mut config);
+    std::fs::write(
+        output_path,
+        serde_json::to_string_pretty(&config).unwrap() + "\n",
+    )
+    .unwrap_or_else(|err| panic!("Error while writing the configuration file: {}", err));
+    println!("[cwe_checker] Configuration written to {}", output_path);
+}
+
+/// Apply the configuration overrides given by the '--set' command line option.
+fn apply_config_overrides(config: &mut serde_json::Value, overrides: &[String]) {
+    for assignment in overrides {
+        apply_config_override(config, assignment)
+            .unwrap_or_else(|err| panic!("Error while overriding the configuration: {}", err));
+    }
+}
+
+/// Check the existence of a file or directory
+fn check_path_existence(path: String) -> Result<(), String> {
+    std::fs::metadata(&path)
+        .map(|_| ())
+        .map_err(|err| format!("{}: {}", path, err))
+}
+
+/// Check the existence of a binary path. The path `-` denotes stdin.
+fn check_binary_path(path: String) -> Result<(), String> {
+    if path == STDIN_PATH {
+        Ok(())
+    } else {
+        check_path_existence(path)
+    }
 }
 
 /// Check the existence of a file
@@ -73,8 +788,85 @@ fn check_file_existence(file_path: String) -> Result<(), String> {
     }
 }
 
+/// Check that a duration string can be parsed
+fn check_duration(duration: String) -> Result<(), String> {
+    parse_duration(&duration)
+        .map(|_| ())
+        .map_err(|err| format!("{}", err))
+}
+
+/// Check that a hexadecimal address can be parsed
+fn check_hex_address(address: String) -> Result<(), String> {
+    parse_hex_address(&address)
+        .map(|_| ())
+        .map_err(|err| format!("{}", err))
+}
+
+/// Parse a hexadecimal address with an optional '0x' prefix.
+fn parse_hex_address(address: &str) -> Result<u64, std::num::ParseIntError> {
+    let address = address.trim();
+    let address = address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"))
+        .unwrap_or(address);
+    u64::from_str_radix(address, 16)
+}
+
+/// Check that a comma separated list of address ranges can be parsed
+fn check_address_ranges(ranges: String) -> Result<(), String> {
+    for range in ranges.split(',').filter(|range| !range.is_empty()) {
+        range
+            .parse::<AddressRange>()
+            .map_err(|err| format!("{}", err))?;
+    }
+    Ok(())
+}
+
+/// Check that the format of the exported facts is known
+fn check_fact_format(format: String) -> Result<(), String> {
+    format
+        .parse::<FactFormat>()
+        .map(|_| ())
+        .map_err(|err| format!("{}", err))
+}
+
+/// Check that the format of the exported call graph is known
+fn check_call_graph_format(format: String) -> Result<(), String> {
+    format
+        .parse::<CallGraphFormat>()
+        .map(|_| ())
+        .map_err(|err| format!("{}", err))
+}
+
+/// Check that a policy for the exit code can be parsed
+fn check_fail_on_policy(policy: String) -> Result<(), String> {
+    policy
+        .parse::<FailOnPolicy>()
+        .map(|_| ())
+        .map_err(|err| format!("{}", err))
+}
+
 /// Run the cwe_checker with Ghidra as its backend.
-fn run_with_ghidra(args: CmdlineArgs) {
+///
+/// Returns the reported CWE warnings, i.e. the warnings remaining after filtering and suppression.
+fn run_with_ghidra(args: CmdlineArgs) -> Vec<CweWarning> {
+    let start_time = std::time::Instant::now();
+    let format = get_format_name(&args).to_string();
+    // The deadline includes the time needed by Ghidra to generate the project.
+    let deadline = args
+        .deadline
+        .as_ref()
+        .map(|duration| Deadline::parse(duration).unwrap());
+    // Like the deadline, the timeout includes the time needed by Ghidra.
+    let run_cancellation = match args.timeout {
+        Some(ref timeout) => CancellationToken::new()
+            .child_with_deadline(start_time + parse_duration(timeout).unwrap()),
+        None => CancellationToken::new(),
+    };
+    let module_timeout = args
+        .module_timeout
+        .as_ref()
+        .map(|timeout| parse_duration(timeout).unwrap());
     let mut modules = cwe_checker_lib::get_modules();
     if args.module_versions {
         // Only print the module versions and then quit.
@@ -82,50 +874,330 @@ fn run_with_ghidra(args: CmdlineArgs) {
         for module in modules.iter() {
             println!("{}", module);
         }
-        return;
+        return Vec::new();
+    }
+    if args.module_info {
+        // Only print the module descriptions and then quit.
+        if args.json {
+            let infos: Vec<serde_json::Value> = modules
+                .iter()
+                .map(|module| {
+                    serde_json::json!({
+                        "name": module.name,
+                        "version": module.version,
+                        "cwes": module.cwes,
+                        "description": module.description,
+                        "required_analyses": module
+                            .required_analyses
+                            .iter()
+                            .map(|analysis| analysis.to_string())
+                            .collect::<Vec<_>>(),
+                        "config_keys": module.config_keys,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&infos).unwrap());
+        } else {
+            for module in modules.iter() {
+                println!("{}", module.get_info());
+            }
+        }
+        return Vec::new();
     }
 
     // Get the configuration file
-    let config: serde_json::Value = if let Some(config_path) = args.config {
-        let file = std::io::BufReader::new(std::fs::File::open(config_path).unwrap());
-        serde_json::from_reader(file).expect("Parsing of the configuration file failed")
+    let mut config: serde_json::Value = if let Some(config_path) = args.config {
+        parse_config_file(Path::new(&config_path))
+            .unwrap_or_else(|err| panic!("Parsing of the configuration file failed: {}", err))
     } else {
         read_config_file("config.json")
     };
+    apply_config_overrides(&mut config, &args.set);
+    let mut audit = AuditLog::new(std::env::args().collect(), &config);
+    // Add the functions of the configured SDK models to the symbol lists of the checks.
+    let sdk_models_config: sdk_models::Config =
+        serde_json::from_value(config["SdkModels"].clone()).unwrap_or_default();
+    sdk_models_config.apply_to_config(&mut config);
+    // Add the modelled syscalls to the symbol lists of the checks.
+    let syscalls_config: syscalls::Config =
+        serde_json::from_value(config["Syscalls"].clone()).unwrap_or_default();
+    syscalls_config.apply_to_config(&mut config);
+
+    // Load the message catalog before the analysis, so that an unknown language is reported early.
+    let message_catalog = if args.lang != DEFAULT_LANGUAGE {
+        Some(
+            MessageCatalog::load(&args.lang)
+                .unwrap_or_else(|err| panic!("Error while loading message catalog: {}", err)),
+        )
+    } else {
+        None
+    };
+
+    // Expensive checks run within the configured resource budget unless the `--thorough` flag is set.
+    let resource_budget: ResourceBudget = if args.thorough {
+        ResourceBudget::default()
+    } else {
+        serde_json::from_value(config["ResourceBudget"].clone()).unwrap_or_default()
+    };
 
     // Filter the modules to be executed if the `--partial` parameter is set.
     if let Some(ref partial_module_list) = args.partial {
         filter_modules_for_partial_run(&mut modules, partial_module_list);
-    } else {
-        // TODO: CWE78 is disabled on a standard run for now,
-        // because it uses up huge amounts of RAM and computation time on some binaries.
-        modules = modules
-            .into_iter()
-            .filter(|module| module.name != "CWE78")
-            .collect();
     }
-
-    let binary_file_path = PathBuf::from(args.binary.unwrap());
-    let binary: Vec<u8> = std::fs::read(&binary_file_path).unwrap_or_else(|_| {
+    // Remove the modules specified by the `--skip` parameter.
+    if let Some(ref skipped_module_list) = args.skip {
+        remove_skipped_modules(&mut modules, skipped_module_list);
+    }
+    // Validate the configuration before the analysis, so that errors are reported with their location in the configuration.
+    let mut config_logs = Vec::new();
+    let mut config_errors = Vec::new();
+    for issue in config_validation::validate_config(&config, &modules) {
+        if issue.is_fatal() {
+            config_errors.push(format!("  {}", issue));
+        } else {
+            config_logs.push(LogMessage::new_info(format!("{}", issue)).source("Configuration"));
+        }
+    }
+    if !config_errors.is_empty() {
         panic!(
-            "Error: Could not read from file path {}",
-            binary_file_path.display()
+            "Error: Invalid configuration:\n{}",
+            config_errors.join("\n")
+        );
+    }
+
+    // A binary read from stdin is written to a temporary file for the import by Ghidra.
+    let (binary_file_path, binary_name, binary, _stdin_file) = if args.binary[0] == STDIN_PATH {
+        let mut binary = Vec::new();
+        std::io::stdin()
+            .read_to_end(&mut binary)
+            .unwrap_or_else(|err| panic!("Error: Could not read the binary from stdin: {}", err));
+        let stdin_file = TemporaryFile::new_binary(&binary);
+        (
+            stdin_file.path().to_path_buf(),
+            "stdin".to_string(),
+            binary,
+            Some(stdin_file),
         )
+    } else {
+        let binary_file_path = PathBuf::from(&args.binary[0]);
+        let binary: Vec<u8> = std::fs::read(&binary_file_path).unwrap_or_else(|_| {
+            panic!(
+                "Error: Could not read from file path {}",
+                binary_file_path.display()
+            )
+        });
+        let binary_name = binary_file_path.display().to_string();
+        (binary_file_path, binary_name, binary, None)
+    };
+    audit.add_file(&binary_name, &binary);
+    let core_file: Option<Vec<u8>> = args.core.as_ref().map(|core_path| {
+        let core = std::fs::read(core_path)
+            .unwrap_or_else(|_| panic!("Error: Could not read from file path {}", core_path));
+        audit.add_file(core_path, &core);
+        core
     });
+    let memory_map: Option<MemoryMap> = args.bare_metal.as_ref().map(|memory_map_path| {
+        let memory_map_file = std::fs::read(memory_map_path)
+            .unwrap_or_else(|_| panic!("Error: Could not read from file path {}", memory_map_path));
+        audit.add_file(memory_map_path, &memory_map_file);
+        serde_json::from_slice(&memory_map_file)
+            .unwrap_or_else(|err| panic!("Error while parsing the memory map: {}", err))
+    });
+    let bare_metal_entry_points: Vec<u64> = match memory_map {
+        Some(ref memory_map) => memory_map
+            .get_entry_points(&binary)
+            .unwrap_or_else(|err| panic!("Error while reading the vector table: {}", err)),
+        None => Vec::new(),
+    };
+    // For core dumps the binary is rebased to the image base at which it was loaded into the process.
+    let rebase = match core_file {
+        Some(ref core) => {
+            let binary_file_name = if args.binary[0] == STDIN_PATH {
+                None
+            } else {
+                binary_file_path
+                    .file_name()
+                    .map(|file_name| file_name.to_string_lossy().to_string())
+            };
+            Some(
+                core_dump::get_image_base(&binary, binary_file_name.as_deref(), core)
+                    .unwrap_or_else(|err| panic!("Error while reading the core dump: {}", err)),
+            )
+        }
+        None => args
+            .rebase
+            .as_deref()
+            .map(|address| parse_hex_address(address).unwrap()),
+    };
 
-    let mut project: Project;
-
-    if let Some(project_file_path) = args.project {
-        let project_file_path = PathBuf::from(project_file_path);
-        project = get_project_from_file(&project_file_path, &binary[..], args.quiet);
-    } else {
-        project = get_project_from_ghidra(&binary_file_path, &binary[..], args.quiet);
+    let register_overrides_config: register_overrides::Config =
+        serde_json::from_value(config["RegisterOverrides"].clone()).unwrap_or_default();
+    let project_generation_start_time = std::time::Instant::now();
+    let (mut project, mut parsing_logs): (Project, Vec<LogMessage>) =
+        if let Some(ref ir_path) = args.load_ir {
+            let ir_file = std::fs::read(ir_path).unwrap();
+            audit.add_file(ir_path, &ir_file);
+            let project = read_binary_artifact(&ir_file[..])
+                .unwrap_or_else(|err| panic!("Error while reading the IR project: {}", err));
+            let log = LogMessage::new_info(format!("Loaded the IR project from {}", ir_path));
+            (project, vec![log])
+        } else if let Some(project_file_path) = args.project {
+            let project_file_path = PathBuf::from(project_file_path);
+            if let Ok(project_file) = std::fs::read(&project_file_path) {
+                audit.add_file(&project_file_path.display().to_string(), &project_file);
+            }
+            get_project_from_file(
+                &project_file_path,
+                &binary[..],
+                args.allow_truncated_input,
+                &register_overrides_config,
+            )
+        } else {
+            let ghidra_path = get_ghidra_install_dir(args.ghidra_path.as_deref())
+                .unwrap_or_else(|err| panic!("{}", err));
+            audit.ghidra_version = get_ghidra_version(&ghidra_path);
+            let ghidra_project_dir = if args.keep_ghidra_project {
+                Some(get_default_ghidra_project_dir())
+            } else {
+                args.ghidra_project.as_ref().map(|project_dir| {
+                    std::fs::create_dir_all(project_dir).unwrap_or_else(|err| {
+                        panic!("Error while creating the Ghidra project directory: {}", err)
+                    });
+                    PathBuf::from(project_dir)
+                })
+            };
+            get_project_from_ghidra(
+                &ghidra_path,
+                &binary_file_path,
+                &binary[..],
+                args.allow_truncated_input,
+                &register_overrides_config,
+                rebase,
+                ghidra_project_dir.as_deref(),
+                args.save_project.as_deref().map(Path::new),
+                memory_map
+                    .as_ref()
+                    .map(|memory_map| (memory_map, &bare_metal_entry_points[..])),
+            )
+            // In batch mode the panic is caught and the error is recorded in the batch summary.
+            .unwrap_or_else(|err| panic!("{}", err))
+        };
+    audit.add_phase(
+        "Project generation",
+        project_generation_start_time.elapsed(),
+    );
+    if let Some(ref ir_path) = args.save_ir {
+        let file = std::fs::File::create(ir_path).unwrap();
+        write_binary_artifact(&project, std::io::BufWriter::new(file))
+            .unwrap_or_else(|err| panic!("Error while writing the IR project: {}", err));
     }
+    // Identify the toolchain that produced the binary to apply toolchain-specific tuning.
+    let toolchains_config: toolchain::Config =
+        serde_json::from_value(config["Toolchains"].clone()).unwrap_or_default();
+    let toolchains = identify_toolchains(&binary[..], &project);
+    let toolchain_profile = toolchains_config.get_profile(&toolchains);
+    let mut toolchain_logs: Vec<LogMessage> = toolchains
+        .iter()
+        .map(|toolchain| LogMessage::new_info(format!("Identified toolchain {}", toolchain)))
+        .collect();
+    // Replace the modelled SDK functions contained in the binary by extern symbols.
+    let mut sdk_model_logs = sdk_models_config.apply_to_project(&mut project);
+    // Replace syscall instructions with known syscall numbers by calls to their libc wrappers.
+    sdk_model_logs.append(&mut syscalls_config.apply_to_project(&mut project));
+    // Copy the behavior of calls to unmodeled extern functions to the analyses
+    // and add the extern functions returning untrusted input to the taint sources.
+    let extern_stubs_config: extern_stubs::Config =
+        serde_json::from_value(config["ExternStubs"].clone()).unwrap_or_default();
+    extern_stubs_config.apply_to_config(&project, &mut config);
+    // Remove the functions outside of the configured address ranges from the project.
+    let mut address_ranges_config: address_ranges::Config =
+        serde_json::from_value(config["AddressRanges"].clone()).unwrap_or_default();
+    for (ranges_param, ranges) in vec![
+        (&args.include_range, &mut address_ranges_config.include),
+        (&args.exclude_range, &mut address_ranges_config.exclude),
+    ] {
+        if let Some(ranges_param) = ranges_param {
+            ranges.extend(
+                ranges_param
+                    .split(',')
+                    .filter(|range| !range.is_empty())
+                    .map(|range| range.to_string()),
+            );
+        }
+    }
+    let address_scope = AddressScope::new(&address_ranges_config)
+        .unwrap_or_else(|err| panic!("Error while parsing address ranges: {}", err));
+    let mut scope_logs = address_scope.restrict_project(&mut project);
+    // Remove the functions not reachable from the selected functions from the project.
+    if let Some(ref function_param) = args.function {
+        let selection: Vec<String> = function_param
+            .split(',')
+            .filter(|function| !function.is_empty())
+            .map(|function| function.to_string())
+            .collect();
+        scope_logs.append(
+            &mut function_selection::restrict_project_to_functions(&mut project, &selection)
+                .unwrap_or_else(|err| panic!("Error while selecting functions: {}", err)),
+        );
+    }
+
     // Normalize the project and gather log messages generated from it.
-    let mut all_logs = project.normalize();
+    let mut normalization_config: NormalizationConfig =
+        serde_json::from_value(config["Normalization"].clone()).unwrap_or_default();
+    toolchain_profile.apply_to_normalization(&mut normalization_config);
+    let normalization_start_time = std::time::Instant::now();
+    let (mut normalization_logs, pass_statistics) =
+        project.normalize_with_config(&normalization_config);
+    audit.add_phase("Normalization", normalization_start_time.elapsed());
+    let mut all_logs = Vec::new();
+    all_logs.append(&mut parsing_logs);
+    all_logs.append(&mut toolchain_logs);
+    all_logs.append(&mut normalization_logs);
+    all_logs.append(&mut scope_logs);
+    all_logs.append(&mut sdk_model_logs);
+    all_logs.append(&mut config_logs);
+    if args.debug.as_deref() == Some("passes") {
+        for statistics in pass_statistics {
+            println!("{}", statistics);
+        }
+        return Vec::new();
+    }
 
-    // Generate the representation of the runtime memory image of the binary
-    let mut runtime_memory_image = RuntimeMemoryImage::new(&binary).unwrap_or_else(|err| {
+    // Add the configured additional entry points to the project.
+    let mut entry_points_config: entry_points::Config =
+        serde_json::from_value(config["EntryPoints"].clone()).unwrap_or_default();
+    if let Some(ref entry_points_param) = args.entry_points {
+        entry_points_config.symbols.extend(
+            entry_points_param
+                .split(',')
+                .filter(|symbol| !symbol.is_empty())
+                .map(|symbol| symbol.to_string()),
+        );
+    }
+    entry_points_config.symbols.extend(
+        bare_metal_entry_points
+            .iter()
+            .map(|address| format!("{:x}", address)),
+    );
+    all_logs.append(&mut entry_points::add_entry_points(
+        &mut project,
+        &binary,
+        &entry_points_config,
+    ));
+
+    if let Some(ref attack_surface_path) = args.attack_surface {
+        let report = AttackSurfaceReport::new(&project, &binary);
+        let file = std::fs::File::create(attack_surface_path).unwrap();
+        write_artifact(&report, std::io::BufWriter::new(file)).unwrap();
+    }
+
+    // Generate the representation of the runtime memory image of the binary.
+    // For bare-metal firmware the memory image is generated from the memory map.
+    let mut runtime_memory_image = match memory_map {
+        Some(ref memory_map) => RuntimeMemoryImage::new_bare_metal(&binary, memory_map),
+        None => RuntimeMemoryImage::new(&binary),
+    }
+    .unwrap_or_else(|err| {
         panic!("Error while generating runtime memory image: {}", err);
     });
     if project.program.term.address_base_offset != 0 {
@@ -133,62 +1205,546 @@ fn run_with_ghidra(args: CmdlineArgs) {
         // so that other analyses do not have to adjust their addresses.
         runtime_memory_image.add_global_memory_offset(project.program.term.address_base_offset);
     }
-    // Generate the control flow graph of the program
-    let extern_sub_tids = project
-        .program
-        .term
-        .extern_symbols
-        .iter()
-        .map(|symbol| symbol.tid.clone())
-        .collect();
-    let control_flow_graph = graph::get_program_cfg(&project.program, extern_sub_tids);
+    if let Some(ref core) = core_file {
+        let number_of_segments = runtime_memory_image
+            .add_core_dump_segments(core)
+            .unwrap_or_else(|err| panic!("Error while reading the core dump: {}", err));
+        all_logs.push(
+            LogMessage::new_info(format!(
+                "Added {} memory segments of the core dump to the memory image.",
+                number_of_segments
+            ))
+            .source("Core Dump"),
+        );
+        for mapped_file in core_dump::get_mapped_files(core)
+            .unwrap_or_default()
+            .iter()
+            .filter(|mapped_file| mapped_file.file_offset == 0)
+        {
+            all_logs.push(
+                LogMessage::new_info(format!(
+                    "{} was mapped at {:#x}.",
+                    mapped_file.path, mapped_file.start
+                ))
+                .source("Core Dump"),
+            );
+        }
+    }
+    let harvard_config: HarvardConfig =
+        serde_json::from_value(config["HarvardArchitectures"].clone()).unwrap_or_default();
+    if let Some(address_spaces) = harvard_config.architectures.get(&project.cpu_architecture) {
+        runtime_memory_image.separate_address_spaces(address_spaces.data_space_offset);
+    }
+    // Resolve obfuscated call targets and strings through emulation.
+    // Strings decoded in global memory are added to the memory image.
+    let emulation_config: emulation::Config =
+        serde_json::from_value(config["Emulation"].clone()).unwrap_or_default();
+    all_logs.append(&mut emulation::resolve_obfuscation(
+        &mut project,
+        &mut runtime_memory_image,
+        &emulation_config,
+    ));
+    // Split very large binaries into chunks of compilation units that are analyzed separately.
+    // Outputs that need the analysis results of the whole program disable the chunking.
+    let chunking_config: chunking::Config =
+        serde_json::from_value(config["Chunking"].clone()).unwrap_or_default();
+    let whole_program_output = args.dump_cfg.is_some()
+        || args.export_facts.is_some()
+        || args.export_call_graph.is_some()
+        || args.export_function_summaries.is_some()
+        || args.debug.is_some();
+    let chunks = if whole_program_output {
+        Vec::new()
+    } else {
+        let compilation_units = chunking::get_compilation_units(&binary).unwrap_or_default();
+        chunking_config.get_chunks(&project, &compilation_units)
+    };
+    if !chunks.is_empty() {
+        all_logs.push(
+            LogMessage::new_info(format!(
+                "Analyzing the program in {} chunks of compilation units.",
+                chunks.len()
+            ))
+            .source("Chunking"),
+        );
+    }
+    let mut statistics = RunStatistics::new(project.program.term.subs.len());
 
-    let analysis_results = AnalysisResults::new(
-        &binary,
-        &runtime_memory_image,
-        &control_flow_graph,
-        &project,
-    );
+    // Prepare the filtering and annotation of the CWE-warnings.
+    let min_severity: Option<Severity> = args
+        .min_severity
+        .as_ref()
+        .map(|min_severity| min_severity.parse().unwrap());
+    let min_verdict: Option<Verdict> = args
+        .min_verdict
+        .as_ref()
+        .map(|min_verdict| min_verdict.parse().unwrap());
+    let components_config: components::Config =
+        serde_json::from_value(config["Components"].clone()).unwrap_or_default();
+    let components = Components::new(&components_config)
+        .unwrap_or_else(|err| panic!("Error while parsing components: {}", err));
+    let mut generated_code_config: generated_code::Config =
+        serde_json::from_value(config["GeneratedCode"].clone()).unwrap_or_default();
+    toolchain_profile.apply_to_generated_code(&mut generated_code_config);
+    // A missing section uses the default sinks, but an invalid section must not silently drop configured sinks.
+    let output_sinks_config: output_sinks::Config = if config["OutputSinks"].is_null() {
+        output_sinks::Config::default()
+    } else {
+        serde_json::from_value(config["OutputSinks"].clone())
+            .unwrap_or_else(|err| panic!("Error while parsing the output sinks: {}", err))
+    };
+    let suppression_file: Option<SuppressionFile> =
+        args.suppress.as_ref().map(|suppression_path| {
+            let file = std::fs::File::open(suppression_path).unwrap();
+            read_artifact(std::io::BufReader::new(file))
+                .unwrap_or_else(|err| panic!("Error while reading the suppression file: {}", err))
+        });
+    let base_offset = project.program.term.address_base_offset;
+    let ir_context_radius = args.ir_context;
+    let path_witness = args.path_witness.clone();
+    let write_baseline = args.write_baseline.is_some();
+    let mut baseline_cwes = Vec::new();
+    let mut number_of_suppressed = 0;
+    let mut number_of_toolchain_false_positives = 0;
+    let mut deduplicator = WarningDeduplicator::new();
+    // Filter and annotate the CWE-warnings of a module.
+    // Warnings in compiler-generated functions are removed or down-ranked before filtering by severity and verdict.
+    // Known false positives of the identified toolchains are removed.
+    // Duplicates of warnings of the same module or of previous modules are removed.
+    // Mangled C++ and Rust names are demangled after computing the fingerprints.
+    // The baseline contains the warnings before the suppressed warnings are removed.
+    // The warning descriptions are translated if another language than the default one is requested.
+    let mut process_warnings = |cwes: &mut Vec<CweWarning>| {
+        address_scope.filter_warnings(cwes, base_offset);
+        generated_code_config.process_warnings(&project, cwes);
+        number_of_toolchain_false_positives += toolchain_profile.filter_warnings(cwes);
+        if let Some(min_severity) = min_severity {
+            cwes.retain(|cwe| cwe.severity >= min_severity);
+        }
+        if let Some(min_verdict) = min_verdict {
+            cwes.retain(|cwe| cwe.verdict >= min_verdict);
+        }
+        deduplicator.deduplicate(&project, cwes);
+        components.annotate_warnings(&project, cwes);
+        add_fingerprints(&project, cwes);
+        if let Some(radius) = ir_context_radius {
+            add_ir_context(&project, cwes, radius);
+        }
+        demangle_warnings(cwes);
+        if write_baseline {
+            baseline_cwes.extend(cwes.iter().cloned());
+        }
+        if let Some(ref suppression_file) = suppression_file {
+            number_of_suppressed += suppression_file.filter_warnings(cwes);
+        }
+        if let Some(ref catalog) = message_catalog {
+            catalog.localize_warnings(cwes);
+        }
+    };
 
-    let modules_depending_on_pointer_inference = vec!["CWE78", "CWE476", "Memory"];
-    let pointer_inference_results = if modules
-        .iter()
-        .any(|module| modules_depending_on_pointer_inference.contains(&module.name))
-    {
-        Some(analysis_results.compute_pointer_inference(&config["Memory"]))
+    // When streaming, the messages are written as newline-delimited JSON as soon as they are available.
+    // Long-running modules additionally report preliminary warnings while they are still running.
+    let stream_writer = if args.stream {
+        if args.out.len() > 1 {
+            panic!("Error: The streamed output can only be written to one destination.");
+        }
+        let destination = args.out.first().map(String::as_str).unwrap_or("-");
+        let writer = output_sinks::open_stream_destination(destination)
+            .unwrap_or_else(|err| panic!("Error: Invalid output destination: {}", err));
+        Some(StreamWriter::spawn(writer))
     } else {
         None
     };
-    let analysis_results =
-        analysis_results.set_pointer_inference(pointer_inference_results.as_ref());
+    let verbosity = Verbosity::from_occurrences(args.verbose);
+    let stream_message = |message: StreamedMessage| {
+        if let Some(ref stream_writer) = stream_writer {
+            if let StreamedMessage::Log(ref log) = message {
+                if !verbosity.shows(log) {
+                    return;
+                }
+            }
+            stream_writer.write(message);
+        }
+    };
+    demangle_log_messages(&mut all_logs);
+    for log in all_logs.iter() {
+        stream_message(StreamedMessage::Log(log.clone()));
+    }
+
+    // When analyzing in chunks, the binary-wide modules run only once on the whole program.
+    let (binary_wide_modules, chunk_modules): (Vec<&cwe_checker_lib::CweModule>, Vec<_>) =
+        modules.iter().copied().partition(|module| {
+            !chunks.is_empty() && chunking::BINARY_WIDE_MODULES.contains(&module.name)
+        });
+    let mut all_cwes = Vec::new();
+    // The unfiltered warnings of each module, needed for the coverage diagnostics of all chunks.
+    let mut unfiltered_cwes: Vec<(&str, Vec<CweWarning>)> = Vec::new();
+    // Analyze the whole program or each of its chunks.
+    for chunk_index in 0..chunks.len().max(1) {
+        let chunk_project;
+        let analyzed_project: &Project = if chunks.is_empty() {
+            &project
+        } else {
+            chunk_project = chunking::restrict_project_to_chunk(&project, &chunks[chunk_index]);
+            &chunk_project
+        };
+        // Each chunk may use an equal share of the remaining time.
+        let chunk_deadline = deadline
+            .map(|deadline| deadline.portion(1.0 / (chunks.len().max(1) - chunk_index) as f64));
 
-    // Print debug and then return.
-    // Right now there is only one debug printing function.
-    // When more debug printing modes exist, this behaviour will change!
-    if args.debug {
-        cwe_checker_lib::analysis::pointer_inference::run(
-            &project,
+        // Generate the control flow graph of the program
+        let extern_sub_tids = analyzed_project
+            .program
+            .term
+            .extern_symbols
+            .iter()
+            .map(|symbol| symbol.tid.clone())
+            .collect();
+        let control_flow_graph = graph::get_program_cfg(&analyzed_project.program, extern_sub_tids);
+        if let Some(ref function) = args.dump_cfg {
+            let function = if function == "all" {
+                None
+            } else {
+                Some(function.as_str())
+            };
+            match graph::export_dot(&control_flow_graph, function) {
+                Ok(dot) => print!("{}", dot),
+                Err(err) => panic!("Error while exporting the control flow graph: {}", err),
+            }
+            return Vec::new();
+        }
+
+        let analysis_results = AnalysisResults::new(
+            &binary,
             &runtime_memory_image,
             &control_flow_graph,
-            serde_json::from_value(config["Memory"].clone()).unwrap(),
-            true,
+            &analyzed_project,
+        )
+        .set_budget(resource_budget)
+        .set_warning_sender(
+            stream_writer
+                .as_ref()
+                .map(|stream_writer| stream_writer.get_warning_sender()),
         );
-        return;
-    }
 
-    // Execute the modules and collect their logs and CWE-warnings.
-    let mut all_cwes = Vec::new();
-    for module in modules {
-        let (mut logs, mut cwes) = (module.run)(&analysis_results, &config[&module.name]);
+        let pointer_inference_results = if args.export_facts.is_some()
+            || args.export_call_graph.is_some()
+            || args.export_function_summaries.is_some()
+            || chunk_modules
+                .iter()
+                .any(|module| module.requires(RequiredAnalysis::PointerInference))
+        {
+            // The pointer inference may use the bigger part of the remaining time,
+            // since most of the checks depending on it are cheap once it is computed.
+            let pi_deadline = chunk_deadline.map(|deadline| deadline.portion(0.6));
+            let checkpoint = args.checkpoint.as_ref().map(|path| CheckpointConfig {
+                path: PathBuf::from(path),
+                interval: parse_duration(&args.checkpoint_interval).unwrap(),
+            });
+            let pi_cancellation = match module_timeout {
+                Some(timeout) => {
+                    run_cancellation.child_with_deadline(std::time::Instant::now() + timeout)
+                }
+                None => run_cancellation.child(),
+            };
+            let options = RunOptions {
+                deadline: pi_deadline,
+                cancellation: Some(pi_cancellation.clone()),
+                checkpoint,
+            };
+            let pi_start_time = std::time::Instant::now();
+            let pi_results = analysis_results.compute_pointer_inference(&config["Memory"], options);
+            statistics.pointer_inference_runtime = Some(
+                statistics.pointer_inference_runtime.unwrap_or(0.0)
+                    + pi_start_time.elapsed().as_secs_f64(),
+            );
+            audit.add_phase("Pointer inference", pi_start_time.elapsed());
+            if let Some(pi_deadline) = pi_deadline {
+                if pi_deadline.is_expired() {
+                    let log = LogMessage::new_info(
+                        "Deadline: The pointer inference analysis was stopped before it finished. Its results may be incomplete.",
+                    );
+                    stream_message(StreamedMessage::Log(log.clone()));
+                    all_logs.push(log);
+                }
+            }
+            if pi_cancellation.is_cancelled() {
+                let log = LogMessage::new_info(
+                    "Timeout: The pointer inference analysis was stopped before it finished. Its results may be incomplete.",
+                );
+                stream_message(StreamedMessage::Log(log.clone()));
+                all_logs.push(log);
+            }
+            Some(pi_results)
+        } else {
+            None
+        };
+        let analysis_results = analysis_results
+            .clone()
+            .set_pointer_inference(pointer_inference_results.as_ref());
+
+        if let Some(ref fact_directory) = args.export_facts {
+            let fact_base = FactBase::new(
+                &analyzed_project,
+                pointer_inference_results.as_ref(),
+                &runtime_memory_image,
+                &serde_json::from_value(config["FactExport"].clone()).unwrap_or_default(),
+            );
+            fact_base
+                .write_to_directory(
+                    &PathBuf::from(fact_directory),
+                    args.fact_format.parse().unwrap(),
+                )
+                .unwrap_or_else(|err| panic!("Error while exporting facts: {}", err));
+        }
+
+        if let Some(ref summaries_path) = args.export_function_summaries {
+            let summaries = FunctionSummaries::new(
+                &analyzed_project,
+                pointer_inference_results.as_ref(),
+                &runtime_memory_image,
+                &serde_json::from_value(config["FunctionSummaries"].clone()).unwrap_or_default(),
+            );
+            let file = std::fs::File::create(summaries_path).unwrap();
+            write_artifact(&summaries, std::io::BufWriter::new(file)).unwrap();
+        }
+
+        if let Some(ref call_graph_path) = args.export_call_graph {
+            let indirect_call_targets = match pointer_inference_results.as_ref() {
+                Some(pi_results) => {
+                    call_graph::resolve_indirect_call_targets(&analyzed_project.program, pi_results)
+                }
+                None => HashMap::new(),
+            };
+            let call_graph =
+                CallGraph::with_indirect_calls(&analyzed_project.program, &indirect_call_targets);
+            std::fs::write(
+                call_graph_path,
+                call_graph.export(args.call_graph_format.parse().unwrap()),
+            )
+            .unwrap_or_else(|err| panic!("Error while exporting the call graph: {}", err));
+        }
+
+        // Print debug and then return.
+        if args.debug.as_deref() == Some("pointer-inference") {
+            cwe_checker_lib::analysis::pointer_inference::run(
+                &analyzed_project,
+                &runtime_memory_image,
+                &control_flow_graph,
+                serde_json::from_value(config["Memory"].clone()).unwrap(),
+                RunOptions::default(),
+                true,
+            );
+            return Vec::new();
+        }
+
+        // Execute the modules and collect their logs and CWE-warnings.
+        let mut module_start_time = std::time::Instant::now();
+        let mut handle_module_results =
+            |module: &cwe_checker_lib::CweModule,
+             control_flow_graph: &graph::Graph,
+             mut logs: Vec<LogMessage>,
+             mut cwes: Vec<CweWarning>| {
+                let module_runtime = module_start_time.elapsed();
+                statistics.add_module(module.name, module_runtime, cwes.len());
+                audit.add_module(module.name, module.version, module_runtime, cwes.len());
+                match unfiltered_cwes
+                    .iter_mut()
+                    .find(|(name, _)| *name == module.name)
+                {
+                    Some((_, module_cwes)) => module_cwes.extend(cwes.iter().cloned()),
+                    None => unfiltered_cwes.push((module.name, cwes.clone())),
+                }
+                process_warnings(&mut cwes);
+                if let Some(ref witness_dir) = path_witness {
+                    add_path_witnesses(control_flow_graph, &mut cwes);
+                    if let Some(witness_dir) = witness_dir {
+                        write_dot_files(Path::new(witness_dir), &cwes).unwrap_or_else(|err| {
+                            panic!("Error while writing the witness paths: {}", err)
+                        });
+                    }
+                }
+                demangle_log_messages(&mut logs);
+                for log in logs.iter() {
+                    stream_message(StreamedMessage::Log(log.clone()));
+                }
+                for cwe in cwes.iter() {
+                    stream_message(StreamedMessage::Warning(cwe.clone()));
+                }
+                all_logs.extend(logs);
+                all_cwes.append(&mut cwes);
+                module_start_time = std::time::Instant::now();
+            };
+        if chunk_index == 0 && !binary_wide_modules.is_empty() {
+            // The binary-wide modules do not use the control flow graph,
+            // so an empty graph is given to them instead of the graph of the whole program.
+            let empty_graph = graph::Graph::default();
+            let binary_wide_results =
+                AnalysisResults::new(&binary, &runtime_memory_image, &empty_graph, &project)
+                    .set_budget(resource_budget)
+                    .set_warning_sender(
+                        stream_writer
+                            .as_ref()
+                            .map(|stream_writer| stream_writer.get_warning_sender()),
+                    );
+            let cancellation = match deadline {
+                Some(deadline) => run_cancellation.child_with_deadline(deadline.instant()),
+                None => run_cancellation.child(),
+            };
+            cwe_checker_lib::run_modules_with_callback(
+                &binary_wide_modules,
+                &binary_wide_results,
+                &config,
+                &cancellation,
+                module_timeout,
+                |module, logs, cwes| handle_module_results(module, &empty_graph, logs, cwes),
+            );
+        }
+        let cancellation = match chunk_deadline {
+            Some(deadline) => run_cancellation.child_with_deadline(deadline.instant()),
+            None => run_cancellation.child(),
+        };
+        cwe_checker_lib::run_modules_with_callback(
+            &chunk_modules,
+            &analysis_results,
+            &config,
+            &cancellation,
+            module_timeout,
+            |module, logs, cwes| handle_module_results(module, &control_flow_graph, logs, cwes),
+        );
+    }
+    // The coverage diagnostics are computed once from the unfiltered warnings of all chunks.
+    for (module_name, cwes) in unfiltered_cwes.iter() {
+        let mut logs = taint_coverage::get_coverage_logs(&project, &config, module_name, cwes);
+        for log in logs.iter() {
+            stream_message(StreamedMessage::Log(log.clone()));
+        }
         all_logs.append(&mut logs);
-        all_cwes.append(&mut cwes);
+    }
+    // Write the baseline and report the number of suppressed warnings.
+    if let Some(ref baseline_path) = args.write_baseline {
+        let file = std::fs::File::create(baseline_path).unwrap();
+        write_artifact(
+            &SuppressionFile::from_warnings(&baseline_cwes),
+            std::io::BufWriter::new(file),
+        )
+        .unwrap();
+    }
+    if let Some(ref functions_path) = args.export_functions {
+        let file = std::fs::File::create(functions_path).unwrap();
+        write_artifact(
+            &FunctionSignatures::new(&project, &all_cwes),
+            std::io::BufWriter::new(file),
+        )
+        .unwrap();
+    }
+    if deduplicator.get_number_of_duplicates() > 0 {
+        let log = LogMessage::new_info(format!(
+            "Removed {} duplicate warnings.",
+            deduplicator.get_number_of_duplicates()
+        ));
+        stream_message(StreamedMessage::Log(log.clone()));
+        all_logs.push(log);
+    }
+    if number_of_toolchain_false_positives > 0 {
+        let log = LogMessage::new_info(format!(
+            "Removed {} known false positives of the identified toolchains.",
+            number_of_toolchain_false_positives
+        ));
+        stream_message(StreamedMessage::Log(log.clone()));
+        all_logs.push(log);
+    }
+    if args.suppress.is_some() {
+        let log = LogMessage::new_info(format!(
+            "Suppressed {} warnings listed in the suppression file.",
+            number_of_suppressed
+        ));
+        stream_message(StreamedMessage::Log(log.clone()));
+        all_logs.push(log);
     }
 
-    // Print the results of the modules.
-    if args.quiet {
-        all_logs = Vec::new(); // Suppress all log messages since the `--quiet` flag is set.
+    if let Some(ref report_path) = args.report_html {
+        let title = format!("cwe_checker report for {}", binary_name);
+        std::fs::write(
+            report_path,
+            generate_html_report(&title, &all_logs, &all_cwes),
+        )
+        .unwrap_or_else(|err| panic!("Error while writing the HTML report: {}", err));
+    }
+
+    audit.finish(start_time.elapsed(), all_cwes.len());
+    if let Some(ref audit_log_path) = args.audit_log {
+        let file = std::fs::File::create(audit_log_path).unwrap();
+        write_artifact(&audit, std::io::BufWriter::new(file)).unwrap();
+    }
+
+    if let Some(stream_writer) = stream_writer {
+        stream_writer
+            .finish()
+            .unwrap_or_else(|err| panic!("Error while writing the output: {}", err));
     }
-    print_all_messages(all_logs, all_cwes, args.out.as_deref(), args.json);
+
+    // Print the results of the modules unless they were already streamed.
+    let mut output_failed = false;
+    if !args.stream {
+        let no_color = args.no_color;
+        // Sinks given without a format use the format of the '--format' option.
+        let get_output_format =
+            |format_name: Option<&str>, to_stdout: bool| match format_name.unwrap_or(&format) {
+                "json" => OutputFormat::Json(Some(Box::new(audit.clone()))),
+                "csv" => OutputFormat::Csv,
+                "sarif" => OutputFormat::Sarif(binary_name.clone()),
+                "text-plain" => OutputFormat::PlainText,
+                _ => OutputFormat::Text {
+                    color: !no_color
+                        && to_stdout
+                        && atty::is(atty::Stream::Stdout)
+                        && std::env::var_os("NO_COLOR").is_none(),
+                },
+            };
+        // Without an '--out' option the results are printed to stdout.
+        let default_sink = vec!["-".to_string()];
+        let command_line_sinks = if args.out.is_empty() {
+            &default_sink
+        } else {
+            &args.out
+        };
+        let sinks: Vec<Box<dyn OutputSink>> = command_line_sinks
+            .iter()
+            .chain(output_sinks_config.sinks.iter())
+            .map(|specification| {
+                new_output_sink(specification, &output_sinks_config, &get_output_format)
+                    .unwrap_or_else(|err| panic!("Error: Invalid output sink: {}", err))
+            })
+            .collect();
+        let sink_errors = print_all_messages(&all_logs, &all_cwes, &sinks, verbosity);
+        for err in sink_errors.iter() {
+            eprintln!("Error while writing the output: {}", err);
+        }
+        output_failed = !sink_errors.is_empty();
+    }
+    if args.perf_counters {
+        if perf_stats::is_enabled() {
+            eprintln!("{}", PerfStats::snapshot());
+        } else {
+            eprintln!("Performance counters are not available. Build the cwe_checker with the 'perf-stats' feature to collect them.");
+        }
+    }
+    if args.statistics {
+        statistics.finish(start_time.elapsed());
+        match args.statistics_out {
+            Some(ref statistics_path) => std::fs::write(
+                statistics_path,
+                serde_json::to_string_pretty(&statistics).unwrap(),
+            )
+            .unwrap_or_else(|err| panic!("Error while writing the statistics: {}", err)),
+            None => eprintln!("{}", statistics),
+        }
+    }
+    if output_failed {
+        std::process::exit(1);
+    }
+    all_cwes
 }
 
 /// Only keep the modules specified by the `--partial` parameter in the `modules` list.
@@ -212,6 +1768,27 @@ fn filter_modules_for_partial_run(
         .collect();
 }
 
+/// Remove the modules specified by the `--skip` parameter from the `modules` list.
+/// The parameter is a comma-separated list of module names, e.g. 'CWE78,CWE467'.
+/// The names are validated against all known modules,
+/// so that skipping a module that is not part of the current run is not an error.
+fn remove_skipped_modules(modules: &mut Vec<&cwe_checker_lib::CweModule>, skip_param: &str) {
+    let known_modules = cwe_checker_lib::get_modules();
+    let module_names: HashSet<&str> = skip_param
+        .split(',')
+        .filter(|module_name| !module_name.is_empty())
+        .collect();
+    for module_name in module_names.iter() {
+        if !known_modules
+            .iter()
+            .any(|module| module.name == *module_name)
+        {
+            panic!("Error: {} is not a valid module name.", module_name)
+        }
+    }
+    modules.retain(|module| !module_names.contains(module.name));
+}
+
 
 
 
@@ -1,24 +1,273 @@
-use super::{AbstractDomain, HasTop, RegisterDomain, SizedDomain};
+use super::{
+    AbstractDomain, HasTop, KnownBits, MergeMode, NumericApproximation, NumericDomainKind,
+    RegisterDomain, SizedDomain, StridedInterval,
+};
 use crate::intermediate_representation::*;
 use crate::prelude::*;
 
 /// The `BitvectorDomain` is a simple abstract domain describing a bitvector of known length.
 ///
-/// As values it can only assume a known bitvector or *Top(bytesize)*.
+/// As values it can assume a known bitvector, *Top(bytesize)*
+/// or a bitvector where only the lower bytes are known.
+/// Depending on the [numeric domain](super::NumericDomainKind) selected by the [merge mode](super::MergeMode)
+/// values that are not exactly known may also be approximated by strided intervals or by their known bits.
+/// Operations on approximated values use the approximation of their operands.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
 pub enum BitvectorDomain {
     Top(ByteSize),
     Value(Bitvector),
+    /// A bitvector of the given bytesize, where only the lower bytes are known.
+    /// The known lower bytes are always strictly smaller than the whole bitvector.
+    ///
+    /// This represents e.g. the value of `EAX` after a write to `AL`,
+    /// where the P-Code contains `EAX = PIECE(SUBPIECE(EAX, 1, 3), AL)` and the upper bytes are unknown.
+    Partial(ByteSize, Bitvector),
+    /// A value contained in a (strided) interval.
+    /// Only used if intervals or strided intervals are selected as numeric domain.
+    Interval(StridedInterval),
+    /// A value of which only some bits are known.
+    /// Only used if known bits are selected as numeric domain.
+    KnownBits(KnownBits),
+}
+
+impl BitvectorDomain {
+    /// Create a bitvector of the given size where only the lower bytes are known.
+    /// If all bytes are known, a `Value` is returned and if no byte is known, *Top* is returned.
+    fn new_partial(bytesize: ByteSize, low_bytes: Option<Bitvector>) -> BitvectorDomain {
+        match low_bytes {
+            Some(low_bytes) if ByteSize::from(low_bytes.width()) == bytesize => {
+                BitvectorDomain::Value(low_bytes)
+            }
+            Some(low_bytes) => BitvectorDomain::Partial(bytesize, low_bytes),
+            None => BitvectorDomain::Top(bytesize),
+        }
+    }
+
+    /// Get the lower bytes of the bitvector that are known.
+    /// For a `Value` this is the whole bitvector.
+    fn get_known_low_bytes(&self) -> Option<&Bitvector> {
+        match self {
+            BitvectorDomain::Value(bitvec) | BitvectorDomain::Partial(_, bitvec) => Some(bitvec),
+            BitvectorDomain::Top(_)
+            | BitvectorDomain::Interval(_)
+            | BitvectorDomain::KnownBits(_) => None,
+        }
+    }
+
+    /// Get the lowest `bytesize` bytes of the known lower bytes of the bitvector.
+    /// Returns `None` if less than `bytesize` bytes are known or `bytesize` is zero.
+    fn get_known_low_bytes_truncated(&self, bytesize: ByteSize) -> Option<Bitvector> {
+        let low_bytes = self.get_known_low_bytes()?;
+        if bytesize == ByteSize::new(0) || ByteSize::from(low_bytes.width()) < bytesize {
+            None
+        } else {
+            Some(
+                low_bytes
+                    .clone()
+                    .into_truncate(apint::BitWidth::from(bytesize))
+                    .unwrap(),
+            )
+        }
+    }
+
+    /// Get the number of known lower bytes.
+    fn get_known_low_bytesize(&self) -> ByteSize {
+        self.get_known_low_bytes()
+            .map(|low_bytes| ByteSize::from(low_bytes.width()))
+            .unwrap_or_else(|| ByteSize::new(0))
+    }
+
+    /// Evaluate a binary operation where at least one of the operands is not completely known.
+    ///
+    /// For operations where the lower bytes of the result only depend on the lower bytes of the operands
+    /// (e.g. additions or bitwise operations), the known lower bytes are preserved.
+    /// Note that this is not the case for comparisons, shifts or divisions.
+    fn bin_op_with_unknown_bytes(&self, op: BinOpType, rhs: &Self) -> Self {
+        use BinOpType::*;
+        let result_size = self.bin_op_bytesize(op, rhs);
+        match op {
+            Piece => match rhs {
+                BitvectorDomain::Value(rhs_bitvec) => {
+                    let low_bytes = match self.get_known_low_bytes() {
+                        Some(lhs_low_bytes) => BitvectorDomain::Value(lhs_low_bytes.clone())
+                            .bin_op(Piece, rhs)
+                            .get_known_low_bytes()
+                            .cloned(),
+                        None => Some(rhs_bitvec.clone()),
+                    };
+                    BitvectorDomain::new_partial(result_size, low_bytes)
+                }
+                _ => BitvectorDomain::new_partial(result_size, rhs.get_known_low_bytes().cloned()),
+            },
+            IntAnd => {
+                // A mask that only keeps known bytes yields a known result.
+                for (value, mask) in [(self, rhs), (rhs, self)].iter() {
+                    if let BitvectorDomain::Value(mask) = mask {
+                        let known_bytesize = value.get_known_low_bytesize();
+                        if known_bytesize > ByteSize::new(0)
+                            && mask
+                                .clone()
+                                .into_checked_lshr(known_bytesize.as_bit_length())
+                                .unwrap()
+                                .is_zero()
+                        {
+                            let known_value = value
+                                .get_known_low_bytes()
+                                .unwrap()
+                                .clone()
+                                .into_zero_extend(mask.width())
+                                .unwrap();
+                            return BitvectorDomain::Value(known_value & mask);
+                        }
+                    }
+                }
+                self.bin_op_on_known_low_bytes(op, rhs)
+            }
+            IntAdd | IntSub | IntMult | IntOr | IntXOr => self.bin_op_on_known_low_bytes(op, rhs),
+            _ => BitvectorDomain::new_top(result_size),
+        }
+    }
+
+    /// Evaluate the binary operation on the lower bytes that are known for both operands.
+    /// Only valid for operations where the lower bytes of the result only depend on the lower bytes of the operands.
+    fn bin_op_on_known_low_bytes(&self, op: BinOpType, rhs: &Self) -> Self {
+        let known_bytesize =
+            std::cmp::min(self.get_known_low_bytesize(), rhs.get_known_low_bytesize());
+        match (
+            self.get_known_low_bytes_truncated(known_bytesize),
+            rhs.get_known_low_bytes_truncated(known_bytesize),
+        ) {
+            (Some(lhs_low_bytes), Some(rhs_low_bytes)) => {
+                let low_bytes = BitvectorDomain::Value(lhs_low_bytes)
+                    .bin_op(op, &BitvectorDomain::Value(rhs_low_bytes));
+                BitvectorDomain::new_partial(
+                    self.bytesize(),
+                    low_bytes.get_known_low_bytes().cloned(),
+                )
+            }
+            _ => BitvectorDomain::new_top(self.bytesize()),
+        }
+    }
+
+    /// Get the numeric domain to use for an operation on `self` and `other`.
+    ///
+    /// If one of the values is already approximated, the numeric domain of the approximation is used.
+    /// Otherwise the given numeric domain is used.
+    fn get_numeric_domain(&self, other: &Self, kind: NumericDomainKind) -> NumericDomainKind {
+        use BitvectorDomain::*;
+        match (self, other) {
+            (Interval(_), _) | (_, Interval(_)) if kind == NumericDomainKind::Intervals => {
+                NumericDomainKind::Intervals
+            }
+            (Interval(_), _) | (_, Interval(_)) => NumericDomainKind::StridedIntervals,
+            (KnownBits(_), _) | (_, KnownBits(_)) => NumericDomainKind::KnownBits,
+            _ => kind,
+        }
+    }
+
+    /// Merge two values using the approximation of the numeric domain given by the merge mode.
+    /// Returns `None` if the numeric domain cannot approximate the merged value.
+    fn merge_with_numeric_domain(&self, other: &Self, mode: MergeMode) -> Option<Self> {
+        fn merge_approximations<T: NumericApproximation>(
+            lhs: &BitvectorDomain,
+            rhs: &BitvectorDomain,
+            kind: NumericDomainKind,
+            widen: bool,
+        ) -> Option<BitvectorDomain> {
+            let lhs = T::from_bitvector_domain(lhs)?;
+            let rhs = T::from_bitvector_domain(rhs)?;
+            let merged = if widen {
+                lhs.widen(&rhs)?
+            } else {
+                lhs.join(&rhs)?
+            };
+            Some(merged.adjust_to(kind).into_bitvector_domain())
+        }
+        match self.get_numeric_domain(other, mode.numeric_domain) {
+            NumericDomainKind::Constants => None,
+            kind @ NumericDomainKind::Intervals | kind @ NumericDomainKind::StridedIntervals => {
+                merge_approximations::<StridedInterval>(self, other, kind, mode.widen)
+            }
+            NumericDomainKind::KnownBits => merge_approximations::<KnownBits>(
+                self,
+                other,
+                NumericDomainKind::KnownBits,
+                mode.widen,
+            ),
+        }
+    }
+
+    /// Evaluate a binary operation using the approximation of the given numeric domain.
+    /// Returns `None` if the numeric domain cannot approximate the result.
+    fn bin_op_with_numeric_domain(
+        &self,
+        op: BinOpType,
+        rhs: &Self,
+        kind: NumericDomainKind,
+    ) -> Option<Self> {
+        fn approximate_bin_op<T: NumericApproximation>(
+            lhs: &BitvectorDomain,
+            op: BinOpType,
+            rhs: &BitvectorDomain,
+            kind: NumericDomainKind,
+        ) -> Option<BitvectorDomain> {
+            let lhs = T::from_bitvector_domain(lhs)?;
+            let rhs = T::from_bitvector_domain(rhs)?;
+            Some(
+                lhs.bin_op(op, &rhs)?
+                    .adjust_to(kind)
+                    .into_bitvector_domain(),
+            )
+        }
+        match kind {
+            NumericDomainKind::Constants => None,
+            NumericDomainKind::Intervals | NumericDomainKind::StridedIntervals => {
+                approximate_bin_op::<StridedInterval>(self, op, rhs, kind)
+            }
+            NumericDomainKind::KnownBits => approximate_bin_op::<KnownBits>(self, op, rhs, kind),
+        }
+    }
+
+    /// If the value is exactly known or contained in a strided interval with at most `max_len` values,
+    /// return all possible values.
+    pub fn try_to_value_set(&self, max_len: u64) -> Option<Vec<Bitvector>> {
+        match self {
+            BitvectorDomain::Value(bitvec) => Some(vec![bitvec.clone()]),
+            BitvectorDomain::Interval(interval) => interval.try_to_values(max_len),
+            _ => None,
+        }
+    }
 }
 
 impl AbstractDomain for BitvectorDomain {
-    /// merge two values. Returns *Top* if the values are not equal.
+    /// Merge two values without approximating exactly known values.
     fn merge(&self, other: &Self) -> Self {
+        self.merge_with_mode(other, MergeMode::default())
+    }
+
+    /// Merge two values.
+    /// If the values are not equal, they are approximated by the numeric domain of the merge mode if possible.
+    /// Otherwise only the lower bytes on which both values agree are kept.
+    /// Returns *Top* if there are no such bytes.
+    fn merge_with_mode(&self, other: &Self, mode: MergeMode) -> Self {
         if self == other {
-            self.clone()
-        } else {
-            self.top()
+            return self.clone();
+        }
+        if let Some(merged) = self.merge_with_numeric_domain(other, mode) {
+            return merged;
         }
+        let mut common_bytesize = std::cmp::min(
+            self.get_known_low_bytesize(),
+            other.get_known_low_bytesize(),
+        );
+        while common_bytesize > ByteSize::new(0) {
+            let low_bytes = self.get_known_low_bytes_truncated(common_bytesize);
+            if low_bytes == other.get_known_low_bytes_truncated(common_bytesize) {
+                return BitvectorDomain::new_partial(self.bytesize(), low_bytes);
+            }
+            common_bytesize = common_bytesize - ByteSize::new(1);
+        }
+        self.top()
     }
 
     /// Check if the value is *Top*.
@@ -39,8 +288,10 @@ impl SizedDomain for BitvectorDomain {
     fn bytesize(&self) -> ByteSize {
         use BitvectorDomain::*;
         match self {
-            Top(bytesize) => *bytesize,
+            Top(bytesize) | Partial(bytesize, _) => *bytesize,
             Value(bitvec) => bitvec.width().into(),
+            Self::Interval(interval) => interval.bytesize(),
+            Self::KnownBits(known_bits) => known_bits.bytesize(),
         }
     }
 
@@ -220,7 +471,13 @@ impl RegisterDomain for BitvectorDomain {
                     BitvectorDomain::new_top(self.bytesize())
                 }
             },
-            _ => BitvectorDomain::new_top(self.bin_op_bytesize(op, rhs)),
+            _ => self
+                .bin_op_with_numeric_domain(
+                    op,
+                    rhs,
+                    self.get_numeric_domain(rhs, NumericDomainKind::Constants),
+                )
+                .unwrap_or_else(|| self.bin_op_with_unknown_bytes(op, rhs)),
         }
     }
 
@@ -242,8 +499,14 @@ impl RegisterDomain for BitvectorDomain {
                 | FloatNaN => BitvectorDomain::new_top(self.bytesize()),
             }
         } else {
-            match op {
-                BoolNegate => BitvectorDomain::new_top(ByteSize::new(1)),
+            match (self, op) {
+                (BitvectorDomain::Partial(bytesize, low_bytes), Int2Comp)
+                | (BitvectorDomain::Partial(bytesize, low_bytes), IntNegate) => {
+                    // The lower bytes of the result only depend on the lower bytes of the input.
+                    let result = BitvectorDomain::Value(low_bytes.clone()).un_op(op);
+                    BitvectorDomain::new_partial(*bytesize, result.get_known_low_bytes().cloned())
+                }
+                (_, BoolNegate) => BitvectorDomain::new_top(ByteSize::new(1)),
                 _ => BitvectorDomain::new_top(self.bytesize()),
             }
         }
@@ -260,6 +523,17 @@ impl RegisterDomain for BitvectorDomain {
                     .into_truncate(size.as_bit_length())
                     .unwrap(),
             )
+        } else if let BitvectorDomain::Partial(_, low_bytes) = self {
+            let known_bytesize = ByteSize::from(low_bytes.width());
+            if low_byte >= known_bytesize {
+                BitvectorDomain::new_top(size)
+            } else if low_byte + size <= known_bytesize {
+                BitvectorDomain::Value(low_bytes.clone()).subpiece(low_byte, size)
+            } else {
+                let known_part = BitvectorDomain::Value(low_bytes.clone())
+                    .subpiece(low_byte, known_bytesize - low_byte);
+                BitvectorDomain::new_partial(size, known_part.get_known_low_bytes().cloned())
+            }
         } else {
             BitvectorDomain::new_top(size)
         }
@@ -289,6 +563,22 @@ impl RegisterDomain for BitvectorDomain {
                 ),
                 Int2Float | Float2Float | Trunc => BitvectorDomain::new_top(width),
             }
+        } else if let BitvectorDomain::Partial(_, low_bytes) = self {
+            use CastOpType::*;
+            match kind {
+                // Extensions do not change the lower bytes.
+                IntZExt | IntSExt => BitvectorDomain::Partial(width, low_bytes.clone()),
+                _ => BitvectorDomain::new_top(width),
+            }
+        } else if let BitvectorDomain::Interval(interval) = self {
+            let extended_interval = match kind {
+                CastOpType::IntSExt => interval.sign_extend(width),
+                CastOpType::IntZExt => interval.zero_extend(width),
+                _ => None,
+            };
+            extended_interval
+                .map(BitvectorDomain::Interval)
+                .unwrap_or_else(|| BitvectorDomain::new_top(width))
         } else {
             BitvectorDomain::new_top(width)
         }
@@ -330,7 +620,10 @@ impl std::convert::TryFrom<&BitvectorDomain> for Bitvector {
     fn try_from(bitvec_domain: &BitvectorDomain) -> Result<Bitvector, ()> {
         match bitvec_domain {
             BitvectorDomain::Value(bitvec) => Ok(bitvec.clone()),
-            BitvectorDomain::Top(_) => Err(()),
+            BitvectorDomain::Top(_)
+            | BitvectorDomain::Partial(..)
+            | BitvectorDomain::Interval(_)
+            | BitvectorDomain::KnownBits(_) => Err(()),
         }
     }
 }
@@ -345,6 +638,15 @@ impl std::fmt::Display for BitvectorDomain {
                 bitvector,
                 bitvector.width().to_usize()
             ),
+            Self::Partial(bytesize, low_bytes) => write!(
+                formatter,
+                "Partial:u{}(0x{:016x}:u{:?})",
+                bytesize.as_bit_length(),
+                low_bytes,
+                low_bytes.width().to_usize()
+            ),
+            Self::Interval(interval) => write!(formatter, "Interval:{}", interval),
+            Self::KnownBits(known_bits) => write!(formatter, "{}", known_bits),
         }
     }
 }
@@ -427,6 +729,58 @@ mod tests {
         );
         assert!(!bv(17).is_top());
         assert!(BitvectorDomain::new_top(ByteSize::new(8)).is_top());
+        // Values agreeing on the lower bytes keep them.
+        assert_eq!(
+            bv(0x1234).merge(&bv(0x5634)),
+            BitvectorDomain::Partial(ByteSize::new(8), Bitvector::from_u8(0x34))
+        );
+    }
+
+    #[test]
+    fn partially_known_values() {
+        use BinOpType::*;
+        use CastOpType::*;
+        let low_byte = BitvectorDomain::Value(Bitvector::from_u8(0x42));
+        let upper_bytes = BitvectorDomain::new_top(ByteSize::new(3));
+        // EAX = PIECE(SUBPIECE(EAX, 1, 3), AL)
+        let eax = upper_bytes.bin_op(Piece, &low_byte);
+        assert_eq!(
+            eax,
+            BitvectorDomain::Partial(ByteSize::new(4), Bitvector::from_u8(0x42))
+        );
+        assert_eq!(eax.bytesize(), ByteSize::new(4));
+        assert!(!eax.is_top());
+        assert_eq!(eax.subpiece(ByteSize::new(0), ByteSize::new(1)), low_byte);
+        assert!(eax.subpiece(ByteSize::new(0), ByteSize::new(2)).is_top());
+        assert!(eax.subpiece(ByteSize::new(1), ByteSize::new(1)).is_top());
+
+        // AH = 0x13, i.e. EAX = PIECE(PIECE(SUBPIECE(EAX, 2, 2), AH), SUBPIECE(EAX, 0, 1))
+        let ah = BitvectorDomain::Value(Bitvector::from_u8(0x13));
+        let eax = BitvectorDomain::new_top(ByteSize::new(2))
+            .bin_op(Piece, &ah)
+            .bin_op(Piece, &eax.subpiece(ByteSize::new(0), ByteSize::new(1)));
+        assert_eq!(
+            eax.subpiece(ByteSize::new(0), ByteSize::new(2)),
+            BitvectorDomain::Value(Bitvector::from_u16(0x1342))
+        );
+
+        // Operations whose lower result bytes only depend on the lower bytes of the operands
+        let one = BitvectorDomain::Value(Bitvector::from_u32(1));
+        assert_eq!(
+            eax.bin_op(IntAdd, &one),
+            BitvectorDomain::Partial(ByteSize::new(4), Bitvector::from_u16(0x1343))
+        );
+        assert_eq!(
+            eax.bin_op(IntAnd, &BitvectorDomain::Value(Bitvector::from_u32(0xff))),
+            BitvectorDomain::Value(Bitvector::from_u32(0x42))
+        );
+        assert!(eax.bin_op(IntRight, &one).is_top());
+        assert!(eax.bin_op(IntEqual, &one).is_top());
+        assert_eq!(
+            eax.cast(IntZExt, ByteSize::new(8))
+                .subpiece(ByteSize::new(0), ByteSize::new(1)),
+            low_byte
+        );
     }
 
     #[test]
@@ -453,4 +807,43 @@ mod tests {
             BitvectorDomain::Value(Bitvector::from_i64(-1))
         );
     }
+
+    #[test]
+    fn numeric_domain_approximations() {
+        use NumericDomainKind::*;
+        let interval = StridedInterval::new(ByteSize::new(8), -16, -8, 8).unwrap();
+        let merged = bv(-16).merge_with_mode(&bv(-8), MergeMode::new(StridedIntervals));
+        assert_eq!(merged, BitvectorDomain::Interval(interval.clone()));
+        assert_eq!(bv(-16).merge(&bv(-8)), bv(-16).top());
+        // Values that are already approximated keep their approximation.
+        assert_eq!(
+            merged.merge(&bv(0)),
+            BitvectorDomain::Interval(StridedInterval::new(ByteSize::new(8), -16, 0, 8).unwrap())
+        );
+        assert_eq!(
+            merged.merge_with_mode(&bv(0), MergeMode::new(StridedIntervals).widening()),
+            BitvectorDomain::Interval(
+                StridedInterval::new(ByteSize::new(8), -16, i64::MAX - 7, 8).unwrap()
+            )
+        );
+        assert_eq!(
+            merged.try_to_value_set(2),
+            Some(vec![Bitvector::from_i64(-16), Bitvector::from_i64(-8)])
+        );
+        assert_eq!(
+            merged.bin_op(BinOpType::IntAdd, &bv(8)),
+            BitvectorDomain::Interval(StridedInterval::new(ByteSize::new(8), -8, 0, 8).unwrap())
+        );
+        assert_eq!(
+            BitvectorDomain::Interval(StridedInterval::new(ByteSize::new(4), -4, 4, 8).unwrap())
+                .cast(CastOpType::IntSExt, ByteSize::new(8)),
+            BitvectorDomain::Interval(StridedInterval::new(ByteSize::new(8), -4, 4, 8).unwrap())
+        );
+        assert_eq!(
+            bv(0x10).merge_with_mode(&bv(0x18), MergeMode::new(KnownBits)),
+            BitvectorDomain::KnownBits(
+                super::super::KnownBits::new(ByteSize::new(8), !0x8, 0x10).unwrap()
+            )
+        );
+    }
 }
@@ -1,5 +1,6 @@
 use super::{
-    AbstractDomain, AbstractIdentifier, HasTop, PointerDomain, RegisterDomain, SizedDomain,
+    AbstractDomain, AbstractIdentifier, HasTop, MergeMode, PointerDomain, RegisterDomain,
+    SizedDomain,
 };
 use crate::intermediate_representation::*;
 use crate::prelude::*;
@@ -38,6 +39,15 @@ impl<T: RegisterDomain> DataDomain<T> {
         }
     }
 
+    /// Return `true` if `self` is a pointer with the given abstract ID as one of its targets.
+    pub fn references_id(&self, id: &AbstractIdentifier) -> bool {
+        if let Self::Pointer(pointer) = self {
+            pointer.targets().contains_key(id)
+        } else {
+            false
+        }
+    }
+
     /// If *self* is a pointer, remove all provided IDs from the target list of it.
     /// If this would leave the pointer without any targets, replace it with *Top*.
     pub fn remove_ids(&mut self, ids_to_remove: &BTreeSet<AbstractIdentifier>) {
@@ -144,6 +154,10 @@ impl<T: RegisterDomain> RegisterDomain for DataDomain<T> {
             | (_, FloatSub, _)
             | (_, FloatMult, _)
             | (_, FloatDiv, _) => Self::new_top(self.bytesize()),
+            (Top(_), Piece, Value(right)) | (Pointer(_), Piece, Value(right)) => {
+                // The upper bytes are unknown, but the lower bytes are still known.
+                Value(T::new_top(self.bytesize()).bin_op(Piece, right))
+            }
             (_, Piece, _) => Self::new_top(self.bytesize() + rhs.bytesize()),
         }
     }
@@ -183,11 +197,18 @@ impl<T: RegisterDomain> RegisterDomain for DataDomain<T> {
 impl<T: RegisterDomain> AbstractDomain for DataDomain<T> {
     // Merge `self` with `other`.
     fn merge(&self, other: &Self) -> Self {
+        self.merge_with_mode(other, MergeMode::default())
+    }
+
+    /// Merge `self` with `other`, passing the merge mode on to the merged pointer offsets or values.
+    fn merge_with_mode(&self, other: &Self, mode: MergeMode) -> Self {
         use DataDomain::*;
         match (self, other) {
             (Top(bytesize), _) | (_, Top(bytesize)) => Top(*bytesize),
-            (Pointer(pointer1), Pointer(pointer2)) => Pointer(pointer1.merge(pointer2)),
-            (Value(val1), Value(val2)) => Value(val1.merge(val2)),
+            (Pointer(pointer1), Pointer(pointer2)) => {
+                Pointer(pointer1.merge_with_mode(pointer2, mode))
+            }
+            (Value(val1), Value(val2)) => Value(val1.merge_with_mode(val2, mode)),
             (Pointer(_), Value(_)) | (Value(_), Pointer(_)) => Top(self.bytesize()),
         }
     }
@@ -314,6 +335,17 @@ mod tests {
         let two = Data::Value(BitvectorDomain::Value(Bitvector::from_i32(2)));
         let concat = new_value((1 << 32) + 2);
         assert_eq!(one.bin_op(Piece, &two), concat);
+
+        // Writing a sub-register of an unknown or pointer value keeps the written lower bytes.
+        let low_byte = Data::Value(BitvectorDomain::Value(Bitvector::from_u8(7)));
+        for upper_bytes in [Data::new_top(ByteSize::new(7)), pointer.clone()].iter() {
+            let pieced = upper_bytes.bin_op(Piece, &low_byte);
+            assert_eq!(pieced.bytesize(), upper_bytes.bytesize() + ByteSize::new(1));
+            assert_eq!(
+                pieced.subpiece(ByteSize::new(0), ByteSize::new(1)),
+                low_byte
+            );
+        }
     }
 
     #[test]
@@ -26,6 +26,9 @@ use std::sync::Arc;
 /// The time identifier is given by a `Tid`.
 /// If it is the `Tid` of a basic block, then it describes the point in time *before* execution of the first instruction in the block.
 /// If it is the `Tid` of a `Def` or `Jmp`, then it describes the point in time *after* the execution of the `Def` or `Jmp`.
+///
+/// The identifier data is shared through an `Arc` and never modified,
+/// so identifiers are cheap to clone and can be sent between threads.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord, Deref)]
 #[deref(forward)]
 pub struct AbstractIdentifier(Arc<AbstractIdentifierData>);
@@ -42,6 +45,16 @@ impl AbstractIdentifier {
     pub fn new(time: Tid, location: AbstractLocation) -> AbstractIdentifier {
         AbstractIdentifier(Arc::new(AbstractIdentifierData { time, location }))
     }
+
+    /// Get the time component of the identifier, i.e. the term identifier of the program point it refers to.
+    pub fn get_tid(&self) -> &Tid {
+        &self.0.time
+    }
+
+    /// Get the location component of the identifier.
+    pub fn get_location(&self) -> &AbstractLocation {
+        &self.0.location
+    }
 }
 
 impl std::fmt::Display for AbstractIdentifier {
@@ -1,6 +1,7 @@
-use super::{AbstractDomain, HasTop, SizedDomain};
+use super::{AbstractDomain, HasTop, MergeMode, SizedDomain};
 use crate::intermediate_representation::ByteSize;
 use crate::prelude::*;
+use crate::utils::perf_stats::{self, Counter};
 use apint::{Int, Width};
 use derive_more::Deref;
 use serde::{Deserialize, Serialize};
@@ -20,6 +21,8 @@ use std::sync::Arc;
 /// Thus an empty memory region actually represents the *Top* element of its abstract domain.
 ///
 /// To allow cheap cloning of a `MemRegion`, the actual data is wrapped inside an `Arc`.
+/// Mutable access clones the data if it is shared (see `Arc::make_mut`),
+/// so changes to a memory region are never visible through its clones, even if they live in other threads.
 #[derive(Serialize, Deserialize, Debug, Hash, Clone, PartialEq, Eq, Deref)]
 #[deref(forward)]
 pub struct MemRegion<T: AbstractDomain + SizedDomain + HasTop + std::fmt::Debug>(
@@ -28,18 +31,26 @@ pub struct MemRegion<T: AbstractDomain + SizedDomain + HasTop + std::fmt::Debug>
 
 impl<T: AbstractDomain + SizedDomain + HasTop + std::fmt::Debug> DerefMut for MemRegion<T> {
     fn deref_mut(&mut self) -> &mut MemRegionData<T> {
+        if perf_stats::is_enabled() && Arc::strong_count(&self.0) > 1 {
+            perf_stats::increment(Counter::MapClones);
+        }
         Arc::make_mut(&mut self.0)
     }
 }
 
 impl<T: AbstractDomain + SizedDomain + HasTop + std::fmt::Debug> AbstractDomain for MemRegion<T> {
+    /// Merge two memory regions without approximating exactly known values.
+    fn merge(&self, other: &Self) -> Self {
+        self.merge_with_mode(other, MergeMode::default())
+    }
+
     /// Short-circuting the `MemRegionData::merge` function if `self==other`,
     /// to prevent unneccessary cloning.
-    fn merge(&self, other: &Self) -> Self {
+    fn merge_with_mode(&self, other: &Self, mode: MergeMode) -> Self {
         if self == other {
             self.clone()
         } else {
-            MemRegion(Arc::new(self.0.merge(&other.0)))
+            MemRegion(Arc::new(self.0.merge(&other.0, mode)))
         }
     }
 
@@ -164,9 +175,9 @@ impl<T: AbstractDomain + SizedDomain + HasTop + std::fmt::Debug> MemRegionData<T
 
     /// Merge two memory regions.
     ///
-    /// Values at the same position and with the same size get merged via their merge function.
+    /// Values at the same position and with the same size get merged via their merge function using the given merge mode.
     /// Other values are *not* added to the merged region, because they could be anything in at least one of the two regions.
-    pub fn merge(&self, other: &MemRegionData<T>) -> MemRegionData<T> {
+    pub fn merge(&self, other: &MemRegionData<T>, mode: MergeMode) -> MemRegionData<T> {
         assert_eq!(self.address_bytesize, other.address_bytesize);
 
         let mut merged_values: BTreeMap<i64, T> = BTreeMap::new();
@@ -175,7 +186,7 @@ impl<T: AbstractDomain + SizedDomain + HasTop + std::fmt::Debug> MemRegionData<T
         for (pos_left, elem_left) in self.values.iter() {
             if let Some((_pos_right, elem_right)) = other.values.get_key_value(pos_left) {
                 if elem_left.bytesize() == elem_right.bytesize() {
-                    let merged_val = elem_left.merge(&elem_right);
+                    let merged_val = elem_left.merge_with_mode(&elem_right, mode);
                     if !merged_val.is_top() {
                         // we discard top()-values, as they don't contain information
                         merged_values.insert(*pos_left, merged_val);
@@ -18,6 +18,12 @@ pub use data::*;
 mod mem_region;
 pub use mem_region::*;
 
+mod resource;
+pub use resource::*;
+
+mod numeric;
+pub use numeric::*;
+
 /// The main trait describing an abstract domain.
 ///
 /// Each abstract domain is partially ordered and has a maximal element (which can be generated by `top()`).
@@ -25,6 +31,15 @@ pub use mem_region::*;
 pub trait AbstractDomain: Sized + Eq + Clone {
     fn merge(&self, other: &Self) -> Self;
 
+    /// Merge two values like `merge`, but approximate merged values as given by the merge mode.
+    /// If widening is requested, `self` should be the older of the two values.
+    ///
+    /// Domains that (transitively) contain values of the `BitvectorDomain` should pass the merge mode on to them.
+    /// The default implementation ignores the merge mode.
+    fn merge_with_mode(&self, other: &Self, _mode: MergeMode) -> Self {
+        self.merge(other)
+    }
+
     /// Returns whether the element represents the top element or not.
     fn is_top(&self) -> bool;
 }
@@ -0,0 +1,671 @@
+//! Approximations of values that are not exactly known, refining the [`BitvectorDomain`].
+//!
+//! Which approximation is used is selected at runtime through the [`NumericDomainKind`],
+//! so that the trade-off between precision and analysis cost can be configured per target
+//! without recompiling the cwe_checker.
+//! The numeric domain is passed to the merge operations of the abstract domains as part of a [`MergeMode`]
+//! by the context of a fixpoint computation (see [`AbstractDomain::merge_with_mode`](super::AbstractDomain::merge_with_mode)),
+//! so different analyses can use different numeric domains at the same time.
+//! Each approximation implements the [`NumericApproximation`] trait,
+//! through which the `BitvectorDomain` merges values and evaluates operations on them.
+//!
+//! The approximations are only used for values of at most 8 bytes.
+//! Larger values are still represented by *Top* if they are not exactly known.
+
+use super::BitvectorDomain;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+
+/// The numeric domains that can be selected to approximate values that are not exactly known.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum NumericDomainKind {
+    /// Only exactly known values (and values with known lower bytes) are tracked.
+    /// This is the cheapest domain.
+    Constants,
+    /// Values are approximated by intervals of signed integers.
+    Intervals,
+    /// Values are approximated by intervals of signed integers together with a stride,
+    /// e.g. the offsets of the elements of an array.
+    StridedIntervals,
+    /// Values are approximated by the bits whose value is known, e.g. after masking operations.
+    KnownBits,
+}
+
+impl Default for NumericDomainKind {
+    fn default() -> NumericDomainKind {
+        NumericDomainKind::Constants
+    }
+}
+
+/// Determines how values that are not exactly known are approximated when merging abstract values.
+///
+/// The numeric domain only decides whether exactly known values are approximated when they are merged.
+/// Values that are already approximated (e.g. by an interval) are always merged using their approximation.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub struct MergeMode {
+    /// The numeric domain used to approximate merged values.
+    pub numeric_domain: NumericDomainKind,
+    /// If set, approximations are extrapolated beyond the merged values,
+    /// so that successive merges of growing values stabilize after finitely many steps.
+    /// Fixpoint computations set this for nodes that did not stabilize after several updates.
+    pub widen: bool,
+}
+
+impl MergeMode {
+    /// Create a new merge mode for the given numeric domain without widening.
+    pub fn new(numeric_domain: NumericDomainKind) -> MergeMode {
+        MergeMode {
+            numeric_domain,
+            widen: false,
+        }
+    }
+
+    /// Return the same merge mode with widening enabled.
+    pub fn widening(self) -> MergeMode {
+        MergeMode {
+            widen: true,
+            ..self
+        }
+    }
+}
+
+/// An approximation of values that are not exactly known.
+///
+/// Exactly known values are represented by `BitvectorDomain::Value`,
+/// so that code working on exactly known values does not need to know about the approximations.
+pub trait NumericApproximation: Sized {
+    /// Convert the value to the approximation.
+    /// Returns `None` if the value cannot be represented by the approximation.
+    fn from_bitvector_domain(value: &BitvectorDomain) -> Option<Self>;
+
+    /// Convert the approximation to a value of the `BitvectorDomain`.
+    /// Exactly known values are converted to `BitvectorDomain::Value`.
+    fn into_bitvector_domain(self) -> BitvectorDomain;
+
+    /// Compute an approximation containing the values of both approximations.
+    /// Returns `None` if the result would be *Top*.
+    fn join(&self, other: &Self) -> Option<Self>;
+
+    /// Compute an approximation containing the values of both approximations,
+    /// where `self` is the previous value and `other` the new value of e.g. a loop variable.
+    /// Returns `None` if the result would be *Top*.
+    ///
+    /// In contrast to `join`, successive widenings have to stabilize after finitely many steps.
+    /// The default implementation just joins the values,
+    /// which is sufficient for approximations without infinite ascending chains.
+    fn widen(&self, other: &Self) -> Option<Self> {
+        self.join(other)
+    }
+
+    /// Compute the result of the binary operation.
+    /// Returns `None` if the operation is not supported by the approximation.
+    fn bin_op(&self, op: BinOpType, rhs: &Self) -> Option<Self>;
+
+    /// Adjust the approximation to the given numeric domain kind.
+    fn adjust_to(self, _kind: NumericDomainKind) -> Self {
+        self
+    }
+}
+
+/// Convert a bitvector of at most 8 bytes to a signed integer.
+fn to_signed(bitvector: &Bitvector) -> Option<i64> {
+    if bitvector.width().to_usize() > 64 {
+        return None;
+    }
+    apint::Int::from(bitvector.clone()).try_to_i64().ok()
+}
+
+/// Convert a bitvector of at most 8 bytes to an unsigned integer.
+fn to_unsigned(bitvector: &Bitvector) -> Option<u64> {
+    if bitvector.width().to_usize() > 64 {
+        return None;
+    }
+    bitvector.try_to_u64().ok()
+}
+
+/// Get the mask containing all bits of a value of the given bytesize.
+fn width_mask(bytesize: ByteSize) -> u64 {
+    match bytesize.as_bit_length() {
+        bits if bits >= 64 => u64::MAX,
+        bits => (1u64 << bits) - 1,
+    }
+}
+
+/// Get the smallest and the largest signed integer of the given bytesize.
+/// Returns `None` if the bytesize is zero or larger than 8 bytes.
+fn signed_bounds(bytesize: ByteSize) -> Option<(i64, i64)> {
+    match bytesize.as_bit_length() {
+        0 => None,
+        bits if bits < 64 => {
+            let max = (1i64 << (bits - 1)) - 1;
+            Some((-max - 1, max))
+        }
+        64 => Some((i64::MIN, i64::MAX)),
+        _ => None,
+    }
+}
+
+/// Get the greatest common divisor of two numbers, where the divisor of 0 and `x` is `x`.
+fn gcd(mut first: u64, mut second: u64) -> u64 {
+    while second != 0 {
+        let remainder = first % second;
+        first = second;
+        second = remainder;
+    }
+    first
+}
+
+/// An interval `[start, end]` of signed integers of a fixed bytesize,
+/// containing only the values `start + k * stride`.
+///
+/// For plain intervals the stride is 1.
+/// An interval containing only one value has stride 0.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct StridedInterval {
+    bytesize: ByteSize,
+    start: i64,
+    end: i64,
+    stride: u64,
+}
+
+impl StridedInterval {
+    /// Create a new strided interval.
+    /// Returns `None` if the bounds do not fit into signed integers of the given bytesize.
+    ///
+    /// The stride is normalized, so that `end` is contained in the interval.
+    pub fn new(bytesize: ByteSize, start: i64, end: i64, stride: u64) -> Option<StridedInterval> {
+        let (min, max) = signed_bounds(bytesize)?;
+        if start > end || start < min || end > max {
+            return None;
+        }
+        let length = end.wrapping_sub(start) as u64;
+        let stride = if length == 0 { 0 } else { gcd(stride, length) };
+        Some(StridedInterval {
+            bytesize,
+            start,
+            end,
+            stride,
+        })
+    }
+
+    /// Create a new strided interval from bounds given as `i128`.
+    /// Returns `None` if the bounds overflow the bytesize.
+    fn from_i128(
+        bytesize: ByteSize,
+        start: i128,
+        end: i128,
+        stride: u64,
+    ) -> Option<StridedInterval> {
+        if start < i128::from(i64::MIN) || end > i128::from(i64::MAX) {
+            return None;
+        }
+        StridedInterval::new(bytesize, start as i64, end as i64, stride)
+    }
+
+    /// Returns `true` if all values of `other` are contained in `self`.
+    pub fn contains(&self, other: &StridedInterval) -> bool {
+        if other.start < self.start || other.end > self.end {
+            return false;
+        }
+        if self.stride == 0 {
+            return true;
+        }
+        let offset = other.start.wrapping_sub(self.start) as u64;
+        offset % self.stride == 0 && other.stride % self.stride == 0
+    }
+
+    /// Return the bytesize of the values contained in the interval.
+    pub fn bytesize(&self) -> ByteSize {
+        self.bytesize
+    }
+
+    /// Return the smallest value contained in the interval.
+    pub fn start(&self) -> i64 {
+        self.start
+    }
+
+    /// Return the largest value contained in the interval.
+    pub fn end(&self) -> i64 {
+        self.end
+    }
+
+    /// Return the number of values contained in the interval.
+    pub fn len(&self) -> u64 {
+        match self.stride {
+            0 => 1,
+            stride => (self.end.wrapping_sub(self.start) as u64) / stride + 1,
+        }
+    }
+
+    /// Returns `false`, since intervals always contain at least one value.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Return the values contained in the interval if there are at most `max_len` of them.
+    pub fn try_to_values(&self, max_len: u64) -> Option<Vec<Bitvector>> {
+        if self.len() > max_len {
+            return None;
+        }
+        let step = std::cmp::max(self.stride, 1) as i64;
+        let mut values = Vec::new();
+        let mut value = self.start;
+        for _ in 0..self.len() {
+            values.push(
+                Bitvector::from_i64(value)
+                    .into_truncate(apint::BitWidth::from(self.bytesize))
+                    .unwrap(),
+            );
+            value = value.wrapping_add(step);
+        }
+        Some(values)
+    }
+
+    /// Sign-extend the values of the interval to the given bytesize.
+    pub fn sign_extend(&self, bytesize: ByteSize) -> Option<StridedInterval> {
+        StridedInterval::new(bytesize, self.start, self.end, self.stride)
+    }
+
+    /// Zero-extend the values of the interval to the given bytesize.
+    /// Returns `None` if the interval contains negative values.
+    pub fn zero_extend(&self, bytesize: ByteSize) -> Option<StridedInterval> {
+        if self.start < 0 {
+            return None;
+        }
+        StridedInterval::new(bytesize, self.start, self.end, self.stride)
+    }
+}
+
+impl NumericApproximation for StridedInterval {
+    fn from_bitvector_domain(value: &BitvectorDomain) -> Option<StridedInterval> {
+        match value {
+            BitvectorDomain::Value(bitvector) => {
+                let value = to_signed(bitvector)?;
+                StridedInterval::new(value_bytesize(bitvector), value, value, 0)
+            }
+            BitvectorDomain::Interval(interval) => Some(interval.clone()),
+            _ => None,
+        }
+    }
+
+    fn into_bitvector_domain(self) -> BitvectorDomain {
+        if self.stride == 0 {
+            BitvectorDomain::Value(
+                Bitvector::from_i64(self.start)
+                    .into_truncate(apint::BitWidth::from(self.bytesize))
+                    .unwrap(),
+            )
+        } else {
+            BitvectorDomain::Interval(self)
+        }
+    }
+
+    /// Compute the smallest strided interval containing both intervals.
+    ///
+    /// The stride of the result divides the strides of both intervals
+    /// and the distance between their start values.
+    fn join(&self, other: &StridedInterval) -> Option<StridedInterval> {
+        if self.bytesize != other.bytesize {
+            return None;
+        }
+        if self.contains(other) {
+            return Some(self.clone());
+        }
+        if other.contains(self) {
+            return Some(other.clone());
+        }
+        let start_distance = (i128::from(self.start) - i128::from(other.start)).abs() as u64;
+        let stride = gcd(gcd(self.stride, other.stride), start_distance);
+        StridedInterval::new(
+            self.bytesize,
+            std::cmp::min(self.start, other.start),
+            std::cmp::max(self.end, other.end),
+            stride,
+        )
+    }
+
+    /// Join the intervals and extend each bound of the result that grows compared to `self`
+    /// to the smallest resp. largest value of the bytesize that is compatible with the stride.
+    ///
+    /// Since both bounds can only be extended once and the stride can only decrease to a divisor,
+    /// successive widenings stabilize after finitely many steps.
+    fn widen(&self, other: &StridedInterval) -> Option<StridedInterval> {
+        let joined = self.join(other)?;
+        if joined == *self {
+            return Some(joined);
+        }
+        let (min, max) = signed_bounds(self.bytesize)?;
+        let step = i128::from(std::cmp::max(joined.stride, 1));
+        let (mut start, mut end) = (i128::from(joined.start), i128::from(joined.end));
+        if joined.start < self.start {
+            start -= (start - i128::from(min)) / step * step;
+        }
+        if joined.end > self.end {
+            end += (i128::from(max) - end) / step * step;
+        }
+        if step == 1 && start == i128::from(min) && end == i128::from(max) {
+            return None;
+        }
+        StridedInterval::from_i128(self.bytesize, start, end, joined.stride)
+    }
+
+    fn bin_op(&self, op: BinOpType, rhs: &StridedInterval) -> Option<StridedInterval> {
+        use BinOpType::*;
+        let (lhs_start, lhs_end) = (i128::from(self.start), i128::from(self.end));
+        let (rhs_start, rhs_end) = (i128::from(rhs.start), i128::from(rhs.end));
+        match op {
+            IntAdd if self.bytesize == rhs.bytesize => StridedInterval::from_i128(
+                self.bytesize,
+                lhs_start + rhs_start,
+                lhs_end + rhs_end,
+                gcd(self.stride, rhs.stride),
+            ),
+            IntSub if self.bytesize == rhs.bytesize => StridedInterval::from_i128(
+                self.bytesize,
+                lhs_start - rhs_end,
+                lhs_end - rhs_start,
+                gcd(self.stride, rhs.stride),
+            ),
+            IntMult if self.bytesize == rhs.bytesize && rhs.stride == 0 => {
+                let (first, second) = (lhs_start * rhs_start, lhs_end * rhs_start);
+                StridedInterval::from_i128(
+                    self.bytesize,
+                    std::cmp::min(first, second),
+                    std::cmp::max(first, second),
+                    self.stride.checked_mul(rhs_start.abs() as u64)?,
+                )
+            }
+            IntLeft if rhs.stride == 0 && rhs.start >= 0 && rhs.start < 63 => {
+                let factor = 1i128 << rhs.start;
+                StridedInterval::from_i128(
+                    self.bytesize,
+                    lhs_start * factor,
+                    lhs_end * factor,
+                    self.stride.checked_mul(1u64 << rhs.start)?,
+                )
+            }
+            _ => None,
+        }
+    }
+
+    /// For plain intervals the stride is replaced by 1.
+    fn adjust_to(self, kind: NumericDomainKind) -> StridedInterval {
+        if kind == NumericDomainKind::Intervals && self.stride > 1 {
+            StridedInterval { stride: 1, ..self }
+        } else {
+            self
+        }
+    }
+}
+
+impl std::fmt::Display for StridedInterval {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "{}[{}, {}]:i{}",
+            self.stride,
+            self.start,
+            self.end,
+            self.bytesize.as_bit_length()
+        )
+    }
+}
+
+/// A value of a fixed bytesize of which only some bits are known.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct KnownBits {
+    bytesize: ByteSize,
+    /// The bits whose value is known are set in the mask.
+    mask: u64,
+    /// The values of the known bits. All unknown bits are zero.
+    value: u64,
+}
+
+impl KnownBits {
+    /// Create a new value with the given known bits.
+    /// Returns `None` if no bit is known or if the bytesize is larger than 8 bytes.
+    pub fn new(bytesize: ByteSize, mask: u64, value: u64) -> Option<KnownBits> {
+        if bytesize.as_bit_length() == 0 || bytesize.as_bit_length() > 64 {
+            return None;
+        }
+        let mask = mask & width_mask(bytesize);
+        if mask == 0 {
+            return None;
+        }
+        Some(KnownBits {
+            bytesize,
+            mask,
+            value: value & mask,
+        })
+    }
+
+    /// Return the bytesize of the value.
+    pub fn bytesize(&self) -> ByteSize {
+        self.bytesize
+    }
+
+    /// Get the mask of the known bits that are zero.
+    fn known_zeros(&self) -> u64 {
+        self.mask & !self.value
+    }
+
+    /// Get the mask of the known bits that are one.
+    fn known_ones(&self) -> u64 {
+        self.mask & self.value
+    }
+
+    /// Get the mask of the lower bits up to the lowest unknown bit.
+    fn known_low_bits(&self) -> u64 {
+        match (!self.mask).trailing_zeros() {
+            bits if bits >= 64 => u64::MAX,
+            bits => (1u64 << bits) - 1,
+        }
+    }
+}
+
+impl NumericApproximation for KnownBits {
+    fn from_bitvector_domain(value: &BitvectorDomain) -> Option<KnownBits> {
+        match value {
+            BitvectorDomain::Value(bitvector) => {
+                let bytesize = value_bytesize(bitvector);
+                KnownBits::new(bytesize, width_mask(bytesize), to_unsigned(bitvector)?)
+            }
+            BitvectorDomain::KnownBits(known_bits) => Some(known_bits.clone()),
+            _ => None,
+        }
+    }
+
+    fn into_bitvector_domain(self) -> BitvectorDomain {
+        if self.mask == width_mask(self.bytesize) {
+            BitvectorDomain::Value(
+                Bitvector::from_u64(self.value)
+                    .into_truncate(apint::BitWidth::from(self.bytesize))
+                    .unwrap(),
+            )
+        } else {
+            BitvectorDomain::KnownBits(self)
+        }
+    }
+
+    /// Only the bits known in both values and with the same value in both values stay known.
+    /// Since the number of known bits only decreases, successive joins always terminate.
+    fn join(&self, other: &KnownBits) -> Option<KnownBits> {
+        if self.bytesize != other.bytesize {
+            return None;
+        }
+        KnownBits::new(
+            self.bytesize,
+            self.mask & other.mask & !(self.value ^ other.value),
+            self.value,
+        )
+    }
+
+    fn bin_op(&self, op: BinOpType, rhs: &KnownBits) -> Option<KnownBits> {
+        use BinOpType::*;
+        match op {
+            IntAnd if self.bytesize == rhs.bytesize => {
+                let ones = self.known_ones() & rhs.known_ones();
+                let zeros = self.known_zeros() | rhs.known_zeros();
+                KnownBits::new(self.bytesize, ones | zeros, ones)
+            }
+            IntOr if self.bytesize == rhs.bytesize => {
+                let ones = self.known_ones() | rhs.known_ones();
+                let zeros = self.known_zeros() & rhs.known_zeros();
+                KnownBits::new(self.bytesize, ones | zeros, ones)
+            }
+            IntXOr if self.bytesize == rhs.bytesize => {
+                KnownBits::new(self.bytesize, self.mask & rhs.mask, self.value ^ rhs.value)
+            }
+            IntAdd | IntSub if self.bytesize == rhs.bytesize => {
+                // The lower bits of the result are known up to the lowest unknown bit of the operands.
+                let low_bits = self.known_low_bits() & rhs.known_low_bits();
+                let value = if op == IntAdd {
+                    self.value.wrapping_add(rhs.value)
+                } else {
+                    self.value.wrapping_sub(rhs.value)
+                };
+                KnownBits::new(self.bytesize, low_bits, value)
+            }
+            IntLeft if rhs.mask == width_mask(rhs.bytesize) && rhs.value < 64 => {
+                let shifted_in_zeros = (1u64 << rhs.value) - 1;
+                KnownBits::new(
+                    self.bytesize,
+                    (self.mask << rhs.value) | shifted_in_zeros,
+                    self.value << rhs.value,
+                )
+            }
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for KnownBits {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "KnownBits:u{}(mask 0x{:x}, value 0x{:x})",
+            self.bytesize.as_bit_length(),
+            self.mask,
+            self.value
+        )
+    }
+}
+
+/// Get the bytesize of a bitvector.
+fn value_bytesize(bitvector: &Bitvector) -> ByteSize {
+    bitvector.width().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interval(start: i64, end: i64, stride: u64) -> StridedInterval {
+        StridedInterval::new(ByteSize::new(8), start, end, stride).unwrap()
+    }
+
+    fn bv(value: i64) -> BitvectorDomain {
+        BitvectorDomain::Value(Bitvector::from_i64(value))
+    }
+
+    #[test]
+    fn strided_interval_join() {
+        let first = StridedInterval::from_bitvector_domain(&bv(-16)).unwrap();
+        let second = StridedInterval::from_bitvector_domain(&bv(8)).unwrap();
+        let joined = first.join(&second).unwrap();
+        assert_eq!(joined, interval(-16, 8, 24));
+        assert_eq!(joined.len(), 2);
+        // Joining an interval with a value it does not contain yields the covering interval.
+        assert_eq!(joined.join(&interval(0, 0, 0)), Some(interval(-16, 8, 8)));
+        assert_eq!(
+            joined.join(&interval(32, 32, 0)),
+            Some(interval(-16, 32, 24))
+        );
+        assert_eq!(joined.join(&interval(8, 8, 0)), Some(joined.clone()));
+        assert_eq!(
+            joined.clone().adjust_to(NumericDomainKind::Intervals),
+            StridedInterval {
+                stride: 1,
+                ..joined
+            }
+        );
+    }
+
+    #[test]
+    fn strided_interval_widen() {
+        let loop_offsets = interval(0, 8, 8);
+        let widened = loop_offsets.widen(&interval(16, 16, 0)).unwrap();
+        assert_eq!(widened, interval(0, i64::MAX - 7, 8));
+        assert_eq!(widened.widen(&interval(24, 24, 0)), Some(widened.clone()));
+        assert_eq!(
+            loop_offsets.widen(&interval(-8, -8, 0)),
+            Some(interval(i64::MIN, 8, 8))
+        );
+        assert_eq!(
+            loop_offsets.widen(&interval(4, 4, 0)),
+            Some(interval(0, 8, 4))
+        );
+        // Extending both bounds of a plain interval yields Top.
+        assert_eq!(interval(0, 1, 1).widen(&interval(-1, 2, 1)), None);
+        let small = StridedInterval::new(ByteSize::new(1), 0, 4, 4).unwrap();
+        assert_eq!(
+            small.widen(&StridedInterval::new(ByteSize::new(1), 8, 8, 0).unwrap()),
+            StridedInterval::new(ByteSize::new(1), 0, 124, 4)
+        );
+    }
+
+    #[test]
+    fn strided_interval_operations() {
+        let array_offsets = interval(0, 12, 4);
+        assert_eq!(
+            array_offsets.bin_op(BinOpType::IntAdd, &interval(-16, -16, 0)),
+            Some(interval(-16, -4, 4))
+        );
+        assert_eq!(
+            interval(0, 3, 1).bin_op(BinOpType::IntLeft, &interval(2, 2, 0)),
+            Some(array_offsets.clone())
+        );
+        assert_eq!(
+            array_offsets.try_to_values(4).unwrap(),
+            vec![
+                Bitvector::from_i64(0),
+                Bitvector::from_i64(4),
+                Bitvector::from_i64(8),
+                Bitvector::from_i64(12)
+            ]
+        );
+        assert_eq!(array_offsets.try_to_values(3), None);
+        let overflowing = StridedInterval::new(ByteSize::new(1), 100, 120, 1).unwrap();
+        assert_eq!(overflowing.bin_op(BinOpType::IntAdd, &overflowing), None);
+        assert_eq!(
+            interval(4, 4, 0).into_bitvector_domain(),
+            BitvectorDomain::Value(Bitvector::from_i64(4))
+        );
+    }
+
+    #[test]
+    fn known_bits() {
+        let aligned = KnownBits::new(ByteSize::new(8), 0xf, 0).unwrap();
+        let one = KnownBits::from_bitvector_domain(&bv(1)).unwrap();
+        let sum = aligned.bin_op(BinOpType::IntAdd, &one).unwrap();
+        assert_eq!(sum, KnownBits::new(ByteSize::new(8), 0xf, 1).unwrap());
+        assert_eq!(
+            sum.bin_op(
+                BinOpType::IntAnd,
+                &KnownBits::from_bitvector_domain(&bv(0xf)).unwrap()
+            )
+            .unwrap()
+            .into_bitvector_domain(),
+            bv(1)
+        );
+        assert_eq!(
+            aligned.join(&sum),
+            Some(KnownBits::new(ByteSize::new(8), 0xe, 0).unwrap())
+        );
+        assert_eq!(
+            aligned.join(&KnownBits::new(ByteSize::new(8), 0xf, 0xf).unwrap()),
+            None
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use super::{AbstractDomain, AbstractIdentifier, RegisterDomain, SizedDomain};
+use super::{AbstractDomain, AbstractIdentifier, MergeMode, RegisterDomain, SizedDomain};
 use crate::intermediate_representation::{BinOpType, ByteSize};
 use crate::prelude::*;
 use std::collections::BTreeMap;
@@ -21,10 +21,18 @@ impl<T: RegisterDomain> AbstractDomain for PointerDomain<T> {
     /// The merged pointer contains all targets of `self` and `other`.
     /// For targets, that are contained in both, the offsets are merged.
     fn merge(&self, other: &Self) -> Self {
+        self.merge_with_mode(other, MergeMode::default())
+    }
+
+    /// Merge two pointers, merging the offsets of common targets according to the merge mode.
+    fn merge_with_mode(&self, other: &Self, mode: MergeMode) -> Self {
         let mut merged_map = self.0.clone();
         for (location, offset) in other.0.iter() {
             if merged_map.contains_key(location) {
-                merged_map.insert(location.clone(), merged_map[location].merge(offset));
+                merged_map.insert(
+                    location.clone(),
+                    merged_map[location].merge_with_mode(offset, mode),
+                );
             } else {
                 merged_map.insert(location.clone(), offset.clone());
             }
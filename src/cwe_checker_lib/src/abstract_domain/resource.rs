@@ -0,0 +1,246 @@
+//! Abstract domains for tracking resources like file descriptors and `FILE*` streams.
+//!
+//! A resource is identified by the TID of the call that created it,
+//! e.g. the call to `open` or `fopen`.
+
+use super::AbstractDomain;
+use crate::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The kind of a tracked resource.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub enum ResourceKind {
+    /// A file descriptor, e.g. returned by `open` or `socket`.
+    FileDescriptor,
+    /// A `FILE*` stream, e.g. returned by `fopen`.
+    Stream,
+}
+
+/// The state of a tracked resource.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub enum ResourceState {
+    /// The resource is open on all paths.
+    Open,
+    /// The resource was closed on all paths.
+    Closed,
+    /// The resource was closed on some, but not all paths.
+    MaybeClosed,
+}
+
+impl ResourceState {
+    /// Returns `true` if the resource may have been closed on some path.
+    pub fn may_be_closed(&self) -> bool {
+        !matches!(self, ResourceState::Open)
+    }
+
+    /// Returns `true` if the resource may still be open on some path.
+    pub fn may_be_open(&self) -> bool {
+        !matches!(self, ResourceState::Closed)
+    }
+}
+
+impl AbstractDomain for ResourceState {
+    /// Merging two different states yields `MaybeClosed`.
+    fn merge(&self, other: &Self) -> Self {
+        if self == other {
+            *self
+        } else {
+            ResourceState::MaybeClosed
+        }
+    }
+
+    /// The `MaybeClosed` state is the *Top* element of the domain.
+    fn is_top(&self) -> bool {
+        matches!(self, ResourceState::MaybeClosed)
+    }
+}
+
+/// A tracked resource, i.e. its kind together with its current state.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Resource {
+    /// The kind of the resource.
+    pub kind: ResourceKind,
+    /// The state of the resource.
+    pub state: ResourceState,
+}
+
+/// The set of resources a value may refer to,
+/// represented by the TIDs of the calls that created the resources.
+///
+/// The empty set represents values that do not refer to any tracked resource.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct ResourceSet(BTreeSet<Tid>);
+
+impl ResourceSet {
+    /// Create a set containing only the resource created at the given call.
+    pub fn new(resource: Tid) -> ResourceSet {
+        ResourceSet(std::iter::once(resource).collect())
+    }
+
+    /// Returns `true` if the value does not refer to any tracked resource.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterate over the identifiers of the contained resources.
+    pub fn iter(&self) -> impl Iterator<Item = &Tid> {
+        self.0.iter()
+    }
+
+    /// Return the identifier of the resource if the set contains exactly one resource.
+    pub fn get_unique(&self) -> Option<&Tid> {
+        if self.0.len() == 1 {
+            self.0.iter().next()
+        } else {
+            None
+        }
+    }
+}
+
+impl AbstractDomain for ResourceSet {
+    /// The merge is the union of the two sets.
+    fn merge(&self, other: &Self) -> Self {
+        ResourceSet(self.0.union(&other.0).cloned().collect())
+    }
+
+    /// The domain has no *Top* element, since only a finite number of resources is tracked.
+    fn is_top(&self) -> bool {
+        false
+    }
+}
+
+/// A map from resource identifiers to the kind and state of the corresponding resources.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct ResourceStateMap(BTreeMap<Tid, Resource>);
+
+impl ResourceStateMap {
+    /// Add a new open resource created by the call with the given TID.
+    /// An already existing resource with the same identifier gets overwritten,
+    /// since the call created a new instance of it.
+    pub fn add(&mut self, resource: Tid, kind: ResourceKind) {
+        self.0.insert(
+            resource,
+            Resource {
+                kind,
+                state: ResourceState::Open,
+            },
+        );
+    }
+
+    /// Get the kind and state of the given resource.
+    pub fn get(&self, resource: &Tid) -> Option<&Resource> {
+        self.0.get(resource)
+    }
+
+    /// Iterate over all tracked resources.
+    pub fn iter(&self) -> impl Iterator<Item = (&Tid, &Resource)> {
+        self.0.iter()
+    }
+
+    /// Mark the resources in the given set as closed.
+    ///
+    /// If the set contains more than one resource,
+    /// the contained resources are only marked as possibly closed.
+    pub fn close(&mut self, resources: &ResourceSet) {
+        let is_unique = resources.get_unique().is_some();
+        for id in resources.iter() {
+            if let Some(resource) = self.0.get_mut(id) {
+                resource.state = if is_unique {
+                    ResourceState::Closed
+                } else {
+                    resource.state.merge(&ResourceState::Closed)
+                };
+            }
+        }
+    }
+
+    /// Return the identifiers of all resources in the given set that may have been closed already.
+    pub fn get_possibly_closed(&self, resources: &ResourceSet) -> Vec<Tid> {
+        resources
+            .iter()
+            .filter(|id| {
+                self.0
+                    .get(id)
+                    .map(|resource| resource.state.may_be_closed())
+                    .unwrap_or(false)
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+impl AbstractDomain for ResourceStateMap {
+    /// Resources contained in both maps get their states merged.
+    /// Resources contained in only one of the maps are kept as they are,
+    /// since they were not yet created on the paths represented by the other map.
+    fn merge(&self, other: &Self) -> Self {
+        let mut merged = self.clone();
+        for (id, other_resource) in other.0.iter() {
+            merged
+                .0
+                .entry(id.clone())
+                .and_modify(|resource| resource.state = resource.state.merge(&other_resource.state))
+                .or_insert(*other_resource);
+        }
+        merged
+    }
+
+    /// The domain has no *Top* element.
+    fn is_top(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resource_state_merge() {
+        use ResourceState::*;
+        assert_eq!(Open.merge(&Open), Open);
+        assert_eq!(Closed.merge(&Closed), Closed);
+        assert_eq!(Open.merge(&Closed), MaybeClosed);
+        assert!(MaybeClosed.is_top());
+        assert!(MaybeClosed.may_be_closed() && MaybeClosed.may_be_open());
+        assert!(!Open.may_be_closed());
+        assert!(!Closed.may_be_open());
+    }
+
+    #[test]
+    fn resource_state_map() {
+        let mut map = ResourceStateMap::default();
+        map.add(Tid::new("open"), ResourceKind::FileDescriptor);
+        map.add(Tid::new("fopen"), ResourceKind::Stream);
+        let both = ResourceSet::new(Tid::new("open")).merge(&ResourceSet::new(Tid::new("fopen")));
+        assert_eq!(both.get_unique(), None);
+
+        let mut weakly_closed = map.clone();
+        weakly_closed.close(&both);
+        assert_eq!(
+            weakly_closed.get(&Tid::new("open")).unwrap().state,
+            ResourceState::MaybeClosed
+        );
+
+        let mut closed = map.clone();
+        closed.close(&ResourceSet::new(Tid::new("open")));
+        assert_eq!(
+            closed.get(&Tid::new("open")).unwrap().state,
+            ResourceState::Closed
+        );
+        assert_eq!(closed.get_possibly_closed(&both), vec![Tid::new("open")]);
+
+        let merged = closed.merge(&map);
+        assert_eq!(
+            merged.get(&Tid::new("open")).unwrap().state,
+            ResourceState::MaybeClosed
+        );
+        assert_eq!(
+            merged.get(&Tid::new("fopen")).unwrap().state,
+            ResourceState::Open
+        );
+        assert!(merged
+            .get_possibly_closed(&ResourceSet::default())
+            .is_empty());
+    }
+}
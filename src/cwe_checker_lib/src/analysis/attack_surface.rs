@@ -0,0 +1,115 @@
+//! Generate a report on the attack surface of a binary.
+//!
+//! The report lists which imported functions are actually reachable from the entry points of the binary
+//! and which are merely linked to it.
+//! Furthermore, it lists the exported functions (as given by the dynamic symbol table of ELF files or the export table of PE files)
+//! that are never called from inside the binary itself,
+//! i.e. functions that can only be reached from outside of the binary.
+//!
+//! Reachability is computed on the [call graph](crate::analysis::call_graph) of the program.
+//! Since indirect calls are not contained in the call graph,
+//! imported functions that are only called indirectly are reported as unreachable.
+
+use crate::analysis::call_graph::CallGraph;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::artifact::VersionedArtifact;
+use crate::utils::binary::get_exported_function_addresses;
+use crate::utils::entry_points::find_sub_by_address;
+use std::collections::HashSet;
+
+/// A report on the attack surface of a binary.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct AttackSurfaceReport {
+    /// Names of imported functions that are reachable from an entry point through direct calls.
+    pub reachable_imports: Vec<String>,
+    /// Names of imported functions that are linked to the binary
+    /// but not reachable from any entry point through direct calls.
+    pub unreachable_imports: Vec<String>,
+    /// Names of exported functions that are not called by any other function of the binary.
+    pub uncalled_exports: Vec<String>,
+}
+
+impl VersionedArtifact for AttackSurfaceReport {
+    const KIND: &'static str = "attack_surface_report";
+    const VERSION: u32 = 1;
+}
+
+impl AttackSurfaceReport {
+    /// Generate the attack surface report for the given project.
+    /// The exported functions are read from the given binary.
+    /// If the binary format does not support exports (e.g. for bare-metal firmware), no uncalled exports are reported.
+    pub fn new(project: &Project, binary: &[u8]) -> AttackSurfaceReport {
+        let exported_addresses = get_exported_function_addresses(binary).unwrap_or_default();
+        AttackSurfaceReport::from_exported_addresses(project, &exported_addresses)
+    }
+
+    /// Generate the attack surface report for the given project and the addresses of its exported functions.
+    /// The addresses are given as specified in the binary, i.e. without the offset added by Ghidra.
+    fn from_exported_addresses(
+        project: &Project,
+        exported_addresses: &[u64],
+    ) -> AttackSurfaceReport {
+        let program = &project.program.term;
+        let call_graph = CallGraph::new(&project.program);
+        let reachable = call_graph.get_reachable_functions(&program.entry_points);
+
+        let mut report = AttackSurfaceReport::default();
+        for symbol in program.extern_symbols.iter() {
+            if reachable.contains(&symbol.tid) {
+                report.reachable_imports.push(symbol.name.clone());
+            } else {
+                report.unreachable_imports.push(symbol.name.clone());
+            }
+        }
+        let exported_subs: HashSet<Tid> = exported_addresses
+            .iter()
+            .filter_map(|address| {
+                find_sub_by_address(project, address + program.address_base_offset)
+            })
+            .collect();
+        for sub in program.subs.iter() {
+            if exported_subs.contains(&sub.tid) && !call_graph.has_callers(&sub.tid) {
+                report.uncalled_exports.push(sub.term.name.clone());
+            }
+        }
+        report.reachable_imports.sort();
+        report.unreachable_imports.sort();
+        report.uncalled_exports.sort();
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attack_surface_report() {
+        let mut project = Project::mock_empty();
+        project.program = crate::analysis::call_graph::tests::mock_program();
+        project
+            .program
+            .term
+            .extern_symbols
+            .push(ExternSymbol::mock_with_name("unused_symbol"));
+        for (sub, address) in project
+            .program
+            .term
+            .subs
+            .iter_mut()
+            .zip(["1000", "2000", "3000"].iter())
+        {
+            sub.tid.address = address.to_string();
+        }
+
+        // The entry point `main` is not exported, while the exported `func` is called by `main`.
+        let report = AttackSurfaceReport::from_exported_addresses(&project, &[0x2000, 0x3000]);
+        assert_eq!(report.reachable_imports, vec!["mock_symbol".to_string()]);
+        assert_eq!(
+            report.unreachable_imports,
+            vec!["unused_symbol".to_string()]
+        );
+        assert_eq!(report.uncalled_exports, vec!["unused".to_string()]);
+    }
+}
@@ -0,0 +1,272 @@
+//! A generic intraprocedural dataflow analysis on the basic blocks of a single function.
+//!
+//! Checks that only need the control flow inside of a function
+//! describe their dataflow problem through the [`Problem`] trait and solve it with [`compute`].
+//! The problem is solved with the fixpoint algorithm of the [`fixpoint`](crate::analysis::fixpoint) module
+//! on a graph whose nodes are the blocks of the function.
+//!
+//! If the computation does not stabilize after [`MAX_STEPS`] updates of a block,
+//! no (intermediate) results are returned.
+//! Instead [`compute`] returns a log message that the caller should add to its logs,
+//! so that non-converging functions do not get skipped silently.
+
+use super::fixpoint::{Computation, Context};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::LogMessage;
+use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
+use std::collections::{BTreeSet, HashMap};
+
+/// The maximum number of updates of the value of a single block.
+pub const MAX_STEPS: u64 = 100;
+
+/// The direction in which the values flow through the control flow graph of the function.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Direction {
+    /// The values flow from each block to its successors.
+    Forward,
+    /// The values flow from each block to its predecessors.
+    Backward,
+}
+
+/// A dataflow problem on the basic blocks of a function.
+pub trait Problem {
+    /// The type of the values assigned to each block.
+    type Value: PartialEq + Eq + Clone;
+
+    /// Merge the values of two different paths.
+    fn merge(&self, value1: &Self::Value, value2: &Self::Value) -> Self::Value;
+
+    /// Compute the value that flows along the control flow edge from `block` to its successor block `successor`.
+    ///
+    /// For forward problems `value` is the value of `block` and the result gets merged into the value of `successor`.
+    /// For backward problems `value` is the value of `successor` and the result gets merged into the value of `block`.
+    /// If several jumps of `block` lead to `successor`, the result has to account for all of them.
+    /// Return `None` if no value flows along the edge.
+    fn update_edge(
+        &self,
+        value: &Self::Value,
+        block: &Term<Blk>,
+        successor: &Term<Blk>,
+    ) -> Option<Self::Value>;
+
+    /// Get the TIDs of the blocks that the control flow may reach after the given block.
+    /// Targets outside of the function are ignored by the analysis.
+    ///
+    /// The default implementation returns the targets of branches and the return targets of calls.
+    fn get_successors<'a>(&self, block: &'a Term<Blk>) -> Vec<&'a Tid> {
+        get_successor_blocks(block)
+    }
+}
+
+/// Get the TIDs of the blocks that the control flow may reach after the given block,
+/// i.e. the targets of branches and the return targets of calls.
+pub fn get_successor_blocks(block: &Term<Blk>) -> Vec<&Tid> {
+    block
+        .term
+        .jmps
+        .iter()
+        .filter_map(|jmp| match &jmp.term {
+            Jmp::Branch(target) | Jmp::CBranch { target, .. } => Some(target),
+            Jmp::Call { return_, .. }
+            | Jmp::CallInd { return_, .. }
+            | Jmp::CallOther { return_, .. } => return_.as_ref(),
+            Jmp::BranchInd(_) | Jmp::Return(_) => None,
+        })
+        .collect()
+}
+
+/// The context of the fixpoint computation for a [`Problem`].
+struct FixpointContext<'a, 'b, P: Problem> {
+    /// The graph of the blocks of the function.
+    /// For backward problems the edges point from the successor to the predecessor block.
+    graph: DiGraph<&'a Term<Blk>, ()>,
+    problem: &'b P,
+    direction: Direction,
+}
+
+impl<'a, 'b, P: Problem> Context for FixpointContext<'a, 'b, P> {
+    type EdgeLabel = ();
+    type NodeLabel = &'a Term<Blk>;
+    type NodeValue = P::Value;
+
+    fn get_graph(&self) -> &DiGraph<&'a Term<Blk>, ()> {
+        &self.graph
+    }
+
+    fn merge(&self, val1: &P::Value, val2: &P::Value) -> P::Value {
+        self.problem.merge(val1, val2)
+    }
+
+    fn update_edge(&self, value: &P::Value, edge: EdgeIndex) -> Option<P::Value> {
+        let (start, end) = self.graph.edge_endpoints(edge).unwrap();
+        match self.direction {
+            Direction::Forward => {
+                self.problem
+                    .update_edge(value, self.graph[start], self.graph[end])
+            }
+            Direction::Backward => {
+                self.problem
+                    .update_edge(value, self.graph[end], self.graph[start])
+            }
+        }
+    }
+}
+
+/// Solve the dataflow problem on the blocks of the given function,
+/// starting with the given values for the given blocks.
+///
+/// Returns the values of all blocks that got a value during the computation.
+/// If the computation does not stabilize, a log message is returned instead.
+pub fn compute<P: Problem>(
+    problem: &P,
+    sub: &Term<Sub>,
+    direction: Direction,
+    start_values: impl IntoIterator<Item = (Tid, P::Value)>,
+) -> Result<HashMap<Tid, P::Value>, LogMessage> {
+    let mut graph = DiGraph::new();
+    let nodes: HashMap<&Tid, NodeIndex> = sub
+        .term
+        .blocks
+        .iter()
+        .map(|block| (&block.tid, graph.add_node(block)))
+        .collect();
+    for block in sub.term.blocks.iter() {
+        // Several jumps to the same block only result in one edge.
+        let successors: BTreeSet<&Tid> = problem.get_successors(block).into_iter().collect();
+        for successor_node in successors.into_iter().filter_map(|tid| nodes.get(tid)) {
+            match direction {
+                Direction::Forward => graph.add_edge(nodes[&block.tid], *successor_node, ()),
+                Direction::Backward => graph.add_edge(*successor_node, nodes[&block.tid], ()),
+            };
+        }
+    }
+    let mut computation = Computation::new(
+        FixpointContext {
+            graph,
+            problem,
+            direction,
+        },
+        None,
+    );
+    for (tid, value) in start_values {
+        if let Some(node) = nodes.get(&tid) {
+            computation.set_node_value(*node, value);
+        }
+    }
+    computation.compute_with_max_steps(MAX_STEPS);
+    if !computation.has_stabilized() {
+        return Err(LogMessage::new_debug(format!(
+            "Dataflow analysis of {} did not stabilize after {} steps per block",
+            sub.term.name, MAX_STEPS
+        ))
+        .location(sub.tid.clone()));
+    }
+    let graph = computation.get_graph();
+    Ok(computation
+        .node_values()
+        .iter()
+        .map(|(node, value)| (graph[*node].tid.clone(), value.clone()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts the blocks on the longest path to each block.
+    /// The values do not stabilize for functions containing loops.
+    struct PathLength;
+
+    impl Problem for PathLength {
+        type Value = u64;
+
+        fn merge(&self, value1: &u64, value2: &u64) -> u64 {
+            std::cmp::max(*value1, *value2)
+        }
+
+        fn update_edge(
+            &self,
+            value: &u64,
+            _block: &Term<Blk>,
+            _successor: &Term<Blk>,
+        ) -> Option<u64> {
+            Some(value + 1)
+        }
+    }
+
+    /// `start` branches to `middle` and `end` and `middle` calls a function returning to `end`.
+    fn mock_sub() -> Term<Sub> {
+        let mut sub = Sub::mock("func");
+        sub.term.blocks = vec![
+            Blk::mock_with_jmp(
+                "start",
+                Jmp::CBranch {
+                    target: Tid::new("end"),
+                    condition: Expression::Const(Bitvector::from_u8(0)),
+                },
+            ),
+            Blk::mock_with_jmp(
+                "middle",
+                Jmp::Call {
+                    target: Tid::new("callee"),
+                    return_: Some(Tid::new("end")),
+                },
+            ),
+            Blk::mock_with_jmp("end", Jmp::Return(Expression::Var(Variable::mock("RA", 8)))),
+        ];
+        sub.term.blocks[0].term.jmps.push(Term {
+            tid: Tid::new("start_fallthrough"),
+            term: Jmp::Branch(Tid::new("middle")),
+        });
+        sub
+    }
+
+    #[test]
+    fn forward_and_backward() {
+        let sub = mock_sub();
+        let values = compute(
+            &PathLength,
+            &sub,
+            Direction::Forward,
+            vec![(Tid::new("start"), 0)],
+        )
+        .unwrap();
+        assert_eq!(values[&Tid::new("middle")], 1);
+        assert_eq!(values[&Tid::new("end")], 2);
+
+        let values = compute(
+            &PathLength,
+            &sub,
+            Direction::Backward,
+            vec![(Tid::new("end"), 0)],
+        )
+        .unwrap();
+        assert_eq!(values[&Tid::new("middle")], 1);
+        assert_eq!(values[&Tid::new("start")], 2);
+
+        // Blocks that are not reachable from the start values get no value.
+        let values = compute(
+            &PathLength,
+            &sub,
+            Direction::Forward,
+            vec![(Tid::new("middle"), 0)],
+        )
+        .unwrap();
+        assert_eq!(values.len(), 2);
+        assert!(values.get(&Tid::new("start")).is_none());
+    }
+
+    #[test]
+    fn non_stabilizing_computation() {
+        let mut sub = mock_sub();
+        sub.term.blocks[2].term.jmps[0].term = Jmp::Branch(Tid::new("start"));
+        let result = compute(
+            &PathLength,
+            &sub,
+            Direction::Forward,
+            vec![(Tid::new("start"), 0)],
+        );
+        assert_eq!(result.unwrap_err().location, Some(Tid::new("func")));
+    }
+}
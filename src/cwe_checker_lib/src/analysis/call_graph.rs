@@ -0,0 +1,445 @@
+//! Generate call graphs out of a program term.
+//!
+//! The nodes of a call graph are the functions of the program and the extern symbols linked to the program.
+//! For each direct call instruction an edge from the calling function to the called function is added.
+//! Indirect calls are only represented in the call graph if their targets were resolved beforehand,
+//! e.g. with [`resolve_indirect_call_targets`] from the results of the pointer inference analysis.
+//!
+//! Call graphs can be exported as JSON or GraphML for the use in other tools.
+
+use super::graph::Node;
+use super::interprocedural_fixpoint_generic::NodeValue;
+use super::pointer_inference::{Data, PointerInference};
+use crate::abstract_domain::BitvectorDomain;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::{Dfs, EdgeRef};
+use petgraph::Direction;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// The node type of a call graph.
+#[derive(Serialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum CallGraphNode<'a> {
+    /// A function contained in the program.
+    Sub(&'a Term<Sub>),
+    /// An extern symbol, i.e. a function dynamically linked from another binary.
+    Extern(&'a ExternSymbol),
+}
+
+impl<'a> CallGraphNode<'a> {
+    /// Get the term identifier of the function represented by the node.
+    pub fn get_tid(&self) -> &'a Tid {
+        match self {
+            CallGraphNode::Sub(sub) => &sub.tid,
+            CallGraphNode::Extern(symbol) => &symbol.tid,
+        }
+    }
+
+    /// Get the name of the function represented by the node.
+    pub fn get_name(&self) -> &'a str {
+        match self {
+            CallGraphNode::Sub(sub) => &sub.term.name,
+            CallGraphNode::Extern(symbol) => &symbol.name,
+        }
+    }
+}
+
+impl<'a> std::fmt::Display for CallGraphNode<'a> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "{} ({})", self.get_name(), self.get_tid())
+    }
+}
+
+/// A call graph of a program.
+///
+/// Each edge is labeled with the call instruction that it represents.
+pub struct CallGraph<'a> {
+    graph: DiGraph<CallGraphNode<'a>, &'a Term<Jmp>>,
+    tid_to_node_map: HashMap<Tid, NodeIndex>,
+}
+
+impl<'a> CallGraph<'a> {
+    /// Build the call graph for a program term.
+    pub fn new(program: &'a Term<Program>) -> CallGraph<'a> {
+        CallGraph::with_indirect_calls(program, &HashMap::new())
+    }
+
+    /// Build the call graph for a program term
+    /// including edges for the indirect calls whose targets are given in the `indirect_call_targets` map.
+    /// The map maps the TIDs of indirect call instructions to the TIDs of their targets.
+    pub fn with_indirect_calls(
+        program: &'a Term<Program>,
+        indirect_call_targets: &HashMap<Tid, Tid>,
+    ) -> CallGraph<'a> {
+        let mut graph = DiGraph::new();
+        let mut tid_to_node_map = HashMap::new();
+        for symbol in program.term.extern_symbols.iter() {
+            let node = graph.add_node(CallGraphNode::Extern(symbol));
+            tid_to_node_map.insert(symbol.tid.clone(), node);
+        }
+        for sub in program.term.subs.iter() {
+            // Some functions may also be marked as extern symbols.
+            // For them we keep the extern symbol node.
+            if !tid_to_node_map.contains_key(&sub.tid) {
+                let node = graph.add_node(CallGraphNode::Sub(sub));
+                tid_to_node_map.insert(sub.tid.clone(), node);
+            }
+        }
+        for sub in program.term.subs.iter() {
+            let caller_node = tid_to_node_map[&sub.tid];
+            for block in sub.term.blocks.iter() {
+                for jmp in block.term.jmps.iter() {
+                    let target = match &jmp.term {
+                        Jmp::Call { target, .. } => target,
+                        Jmp::CallInd { .. } => match indirect_call_targets.get(&jmp.tid) {
+                            Some(target) => target,
+                            None => continue,
+                        },
+                        _ => continue,
+                    };
+                    if let Some(callee_node) = tid_to_node_map.get(target) {
+                        graph.add_edge(caller_node, *callee_node, jmp);
+                    }
+                }
+            }
+        }
+        CallGraph {
+            graph,
+            tid_to_node_map,
+        }
+    }
+
+    /// Get the underlying graph.
+    pub fn get_graph(&self) -> &DiGraph<CallGraphNode<'a>, &'a Term<Jmp>> {
+        &self.graph
+    }
+
+    /// Get the node index of the function with the given TID.
+    pub fn get_node_index(&self, tid: &Tid) -> Option<NodeIndex> {
+        self.tid_to_node_map.get(tid).copied()
+    }
+
+    /// Get the TIDs of all functions that are reachable through direct calls
+    /// starting from the given functions (including the starting functions themselves).
+    pub fn get_reachable_functions(&self, start_tids: &[Tid]) -> HashSet<Tid> {
+        let mut reachable = HashSet::new();
+        for start_node in start_tids
+            .iter()
+            .filter_map(|tid| self.tid_to_node_map.get(tid))
+        {
+            let mut dfs = Dfs::new(&self.graph, *start_node);
+            while let Some(node) = dfs.next(&self.graph) {
+                reachable.insert(self.graph[node].get_tid().clone());
+            }
+        }
+        reachable
+    }
+
+    /// Return whether the function with the given TID is called by at least one other function.
+    /// Recursive calls of a function to itself are not counted.
+    pub fn has_callers(&self, tid: &Tid) -> bool {
+        if let Some(node) = self.tid_to_node_map.get(tid) {
+            self.graph
+                .neighbors_directed(*node, Direction::Incoming)
+                .any(|caller| caller != *node)
+        } else {
+            false
+        }
+    }
+
+    /// Get the TIDs of all functions that directly call the function with the given TID.
+    /// Recursive calls of a function to itself are not counted.
+    pub fn get_callers(&self, tid: &Tid) -> Vec<Tid> {
+        let node = match self.tid_to_node_map.get(tid) {
+            Some(node) => *node,
+            None => return Vec::new(),
+        };
+        let callers: BTreeSet<Tid> = self
+            .graph
+            .neighbors_directed(node, Direction::Incoming)
+            .filter(|caller| *caller != node)
+            .map(|caller| self.graph[caller].get_tid().clone())
+            .collect();
+        callers.into_iter().collect()
+    }
+
+    /// Export the call graph in the given format.
+    pub fn export(&self, format: CallGraphFormat) -> String {
+        match format {
+            CallGraphFormat::Json => serde_json::to_string_pretty(&self.to_export()).unwrap(),
+            CallGraphFormat::GraphMl => self.to_graphml(),
+        }
+    }
+
+    /// Convert the call graph into a serializable list of nodes and edges.
+    fn to_export(&self) -> CallGraphExport {
+        let nodes = self
+            .graph
+            .node_indices()
+            .map(|node| {
+                let function = self.graph[node];
+                CallGraphExportNode {
+                    tid: function.get_tid().to_string(),
+                    name: function.get_name().to_string(),
+                    address: function.get_tid().address.clone(),
+                    is_extern: matches!(function, CallGraphNode::Extern(_)),
+                }
+            })
+            .collect();
+        let edges = self
+            .graph
+            .edge_references()
+            .map(|edge| CallGraphExportEdge {
+                caller: self.graph[edge.source()].get_tid().to_string(),
+                callee: self.graph[edge.target()].get_tid().to_string(),
+                call_site: edge.weight().tid.to_string(),
+                is_indirect: matches!(edge.weight().term, Jmp::CallInd { .. }),
+            })
+            .collect();
+        CallGraphExport { nodes, edges }
+    }
+
+    /// Generate the GraphML representation of the call graph.
+    fn to_graphml(&self) -> String {
+        let export = self.to_export();
+        let mut graphml = String::from(GRAPHML_HEADER);
+        for node in export.nodes.iter() {
+            graphml.push_str(&format!(
+                "    <node id=\"{}\">\n      <data key=\"name\">{}</data>\n      <data key=\"address\">{}</data>\n      <data key=\"extern\">{}</data>\n    </node>\n",
+                escape_xml(&node.tid),
+                escape_xml(&node.name),
+                escape_xml(&node.address),
+                node.is_extern
+            ));
+        }
+        for edge in export.edges.iter() {
+            graphml.push_str(&format!(
+                "    <edge source=\"{}\" target=\"{}\">\n      <data key=\"call_site\">{}</data>\n      <data key=\"indirect\">{}</data>\n    </edge>\n",
+                escape_xml(&edge.caller),
+                escape_xml(&edge.callee),
+                escape_xml(&edge.call_site),
+                edge.is_indirect
+            ));
+        }
+        graphml.push_str("  </graph>\n</graphml>\n");
+        graphml
+    }
+}
+
+/// The file format of an exported call graph.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum CallGraphFormat {
+    /// A JSON object containing the lists of nodes and edges.
+    Json,
+    /// The XML-based GraphML format, which can be read by most graph tools.
+    GraphMl,
+}
+
+impl std::str::FromStr for CallGraphFormat {
+    type Err = Error;
+
+    fn from_str(format: &str) -> Result<CallGraphFormat, Error> {
+        match format.to_lowercase().as_str() {
+            "json" => Ok(CallGraphFormat::Json),
+            "graphml" => Ok(CallGraphFormat::GraphMl),
+            _ => Err(anyhow!("Unknown call graph format: {}", format)),
+        }
+    }
+}
+
+/// The JSON representation of a call graph.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+struct CallGraphExport {
+    nodes: Vec<CallGraphExportNode>,
+    edges: Vec<CallGraphExportEdge>,
+}
+
+/// A function in the JSON representation of a call graph.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+struct CallGraphExportNode {
+    tid: String,
+    name: String,
+    address: String,
+    is_extern: bool,
+}
+
+/// A call in the JSON representation of a call graph.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+struct CallGraphExportEdge {
+    caller: String,
+    callee: String,
+    call_site: String,
+    is_indirect: bool,
+}
+
+/// The start of a GraphML file up to the first node, including the declarations of the node and edge attributes.
+const GRAPHML_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="name" for="node" attr.name="name" attr.type="string"/>
+  <key id="address" for="node" attr.name="address" attr.type="string"/>
+  <key id="extern" for="node" attr.name="extern" attr.type="boolean"/>
+  <key id="call_site" for="edge" attr.name="call_site" attr.type="string"/>
+  <key id="indirect" for="edge" attr.name="indirect" attr.type="boolean"/>
+  <graph id="call_graph" edgedefault="directed">
+"#;
+
+/// Escape the special characters of XML in the given text.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+/// Resolve the targets of indirect calls through the results of the pointer inference analysis.
+///
+/// An indirect call is resolved if its target evaluates to a unique absolute address
+/// that is the address of a function or an extern symbol of the program.
+/// Returns a map from the TIDs of the resolved call instructions to the TIDs of their targets.
+pub fn resolve_indirect_call_targets(
+    program: &Term<Program>,
+    pointer_inference: &PointerInference,
+) -> HashMap<Tid, Tid> {
+    let mut function_addresses: HashMap<u64, &Tid> = HashMap::new();
+    for sub in program.term.subs.iter() {
+        if let Ok(address) = u64::from_str_radix(&sub.tid.address, 16) {
+            function_addresses.insert(address, &sub.tid);
+        }
+    }
+    for symbol in program.term.extern_symbols.iter() {
+        for address in symbol.addresses.iter() {
+            if let Ok(address) = u64::from_str_radix(address, 16) {
+                function_addresses.insert(address, &symbol.tid);
+            }
+        }
+    }
+    let mut call_targets = HashMap::new();
+    let graph = pointer_inference.get_graph();
+    for node in graph.node_indices() {
+        let (block, state) = match (graph[node], pointer_inference.get_node_value(node)) {
+            (Node::BlkEnd(block, _sub), Some(NodeValue::Value(state))) => (block, state),
+            _ => continue,
+        };
+        for jmp in block.term.jmps.iter() {
+            if let Jmp::CallInd { target, .. } = &jmp.term {
+                if let Ok(Data::Value(BitvectorDomain::Value(address))) = state.eval(target) {
+                    if let Some(target_tid) = address
+                        .try_to_u64()
+                        .ok()
+                        .and_then(|address| function_addresses.get(&address))
+                    {
+                        call_targets.insert(jmp.tid.clone(), (*target_tid).clone());
+                    }
+                }
+            }
+        }
+    }
+    call_targets
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// Create a mock program where `main` calls `func` and `func` calls the extern symbol `mock_symbol`.
+    /// The function `unused` is not called by any function.
+    pub fn mock_program() -> Term<Program> {
+        let mut program = Program::mock_empty();
+        let mut main = Sub::mock("main");
+        let mut main_block = Blk::mock();
+        main_block.term.jmps.push(Term {
+            tid: Tid::new("call_func"),
+            term: Jmp::Call {
+                target: Tid::new("func"),
+                return_: None,
+            },
+        });
+        main.term.blocks.push(main_block);
+        let mut func = Sub::mock("func");
+        let mut func_block = Blk::mock();
+        func_block.term.jmps.push(Term {
+            tid: Tid::new("call_extern"),
+            term: Jmp::Call {
+                target: Tid::new("mock_symbol"),
+                return_: None,
+            },
+        });
+        func.term.blocks.push(func_block);
+        program.subs = vec![main, func, Sub::mock("unused")];
+        program.extern_symbols = vec![ExternSymbol::mock()];
+        program.entry_points = vec![Tid::new("main"), Tid::new("unused")];
+        Term {
+            tid: Tid::new("program"),
+            term: program,
+        }
+    }
+
+    #[test]
+    fn call_graph_construction() {
+        let program = mock_program();
+        let call_graph = CallGraph::new(&program);
+        assert_eq!(call_graph.get_graph().node_count(), 4);
+        assert_eq!(call_graph.get_graph().edge_count(), 2);
+
+        let reachable = call_graph.get_reachable_functions(&[Tid::new("main")]);
+        assert_eq!(reachable.len(), 3);
+        assert!(reachable.contains(&Tid::new("mock_symbol")));
+        assert!(!reachable.contains(&Tid::new("unused")));
+
+        assert!(call_graph.has_callers(&Tid::new("func")));
+        assert!(!call_graph.has_callers(&Tid::new("main")));
+        assert_eq!(
+            call_graph.get_callers(&Tid::new("mock_symbol")),
+            vec![Tid::new("func")]
+        );
+        assert!(call_graph.get_callers(&Tid::new("main")).is_empty());
+    }
+
+    #[test]
+    fn indirect_calls_and_export() {
+        let mut program = mock_program();
+        program.term.subs[2].term.blocks.push(Blk::mock());
+        program.term.subs[2].term.blocks[0].term.jmps.push(Term {
+            tid: Tid::new("call_indirect"),
+            term: Jmp::CallInd {
+                target: Expression::Var(Variable::mock("RAX", 8)),
+                return_: None,
+            },
+        });
+        let mut indirect_call_targets = HashMap::new();
+        indirect_call_targets.insert(Tid::new("call_indirect"), Tid::new("func"));
+        let call_graph = CallGraph::with_indirect_calls(&program, &indirect_call_targets);
+        assert_eq!(call_graph.get_graph().edge_count(), 3);
+        assert!(call_graph
+            .get_reachable_functions(&[Tid::new("unused")])
+            .contains(&Tid::new("mock_symbol")));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&call_graph.export(CallGraphFormat::Json)).unwrap();
+        assert_eq!(json["nodes"].as_array().unwrap().len(), 4);
+        let edges = json["edges"].as_array().unwrap();
+        assert_eq!(edges.len(), 3);
+        assert_eq!(
+            edges
+                .iter()
+                .filter(|edge| edge["is_indirect"] == true)
+                .count(),
+            1
+        );
+
+        let graphml = call_graph.export(CallGraphFormat::GraphMl);
+        assert!(graphml.starts_with("<?xml"));
+        assert_eq!(graphml.matches("<node ").count(), 4);
+        assert_eq!(graphml.matches("<edge ").count(), 3);
+        assert!(graphml.ends_with("</graphml>\n"));
+    }
+}
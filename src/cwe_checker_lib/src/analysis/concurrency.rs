@@ -0,0 +1,231 @@
+//! Modeling of atomic operations and concurrency primitives.
+//!
+//! This module contains the knowledge about
+//! - `CallOther` instructions that Ghidra generates for atomic memory accesses and memory barriers,
+//! - the standard POSIX functions for mutexes and thread creation,
+//!
+//! so that analyses do not have to treat them as opaque operations.
+//! Furthermore, it can find functions that are started in a new thread via `pthread_create`.
+//! These are additional entry points of the program that are never called directly.
+
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::symbol_utils::{get_callsites, get_symbol_map};
+
+/// Descriptions of `CallOther` instructions that Ghidra generates for atomic memory accesses,
+/// exclusive monitors and memory barriers.
+///
+/// Their semantics regarding registers and memory is completely described by the surrounding P-Code instructions.
+/// Thus they can be treated as fall-through jumps to their return target.
+pub const ATOMIC_CALL_OTHER_DESCRIPTIONS: &[&str] = &[
+    // x86 `LOCK` prefix
+    "LOCK",
+    "UNLOCK",
+    // ARM exclusive monitors and barriers
+    "ClearExclusiveLocal",
+    "ExclusiveAccess",
+    "hasExclusiveAccess",
+    "DataMemoryBarrier",
+    "DataSynchronizationBarrier",
+    "InstructionSynchronizationBarrier",
+    // AArch64 exclusive monitors
+    "ExclusiveMonitorPass",
+    "ExclusiveMonitorsStatus",
+    // MIPS and PowerPC synchronization instructions
+    "SYNC",
+    "sync",
+    "lwsync",
+    "isync",
+];
+
+/// Returns `true` if the jump is a `CallOther` instruction representing an atomic memory access marker
+/// or a memory barrier, whose effects on registers and memory are fully described by the surrounding instructions.
+pub fn is_atomic_call_other(jmp: &Jmp) -> bool {
+    match jmp {
+        Jmp::CallOther { description, .. } => ATOMIC_CALL_OTHER_DESCRIPTIONS
+            .iter()
+            .any(|atomic_description| description == atomic_description),
+        _ => false,
+    }
+}
+
+/// The concurrency primitives with known semantics.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum ConcurrencyPrimitive {
+    /// Initialization of the lock object given as first parameter.
+    MutexInit,
+    /// Locking of the lock object given as first parameter.
+    /// Blocks until the lock is acquired.
+    MutexLock,
+    /// Locking of the lock object given as first parameter, which may fail without blocking.
+    /// The return value indicates whether the lock was acquired.
+    MutexTryLock,
+    /// Unlocking of the lock object given as first parameter.
+    MutexUnlock,
+    /// Destruction of the lock object given as first parameter.
+    MutexDestroy,
+    /// Creation of a new thread.
+    /// The third parameter is the start routine of the thread and the fourth parameter its argument.
+    ThreadCreate,
+    /// Waiting for the termination of a thread.
+    ThreadJoin,
+}
+
+impl ConcurrencyPrimitive {
+    /// Get the concurrency primitive implemented by the extern symbol with the given name, if there is one.
+    pub fn from_symbol_name(name: &str) -> Option<ConcurrencyPrimitive> {
+        use ConcurrencyPrimitive::*;
+        match name {
+            "pthread_mutex_init" => Some(MutexInit),
+            "pthread_mutex_lock" => Some(MutexLock),
+            "pthread_mutex_trylock" | "pthread_mutex_timedlock" => Some(MutexTryLock),
+            "pthread_mutex_unlock" => Some(MutexUnlock),
+            "pthread_mutex_destroy" => Some(MutexDestroy),
+            "pthread_create" => Some(ThreadCreate),
+            "pthread_join" => Some(ThreadJoin),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the primitive is an operation on a lock object.
+    /// For these the lock object is passed as the first parameter
+    /// and its content is only changed in ways that are irrelevant to other analyses.
+    pub fn is_mutex_operation(&self) -> bool {
+        use ConcurrencyPrimitive::*;
+        matches!(
+            self,
+            MutexInit | MutexLock | MutexTryLock | MutexUnlock | MutexDestroy
+        )
+    }
+}
+
+/// A function that is started in a new thread.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct ThreadEntryPoint {
+    /// The TID of the function that runs in the new thread.
+    pub start_routine: Tid,
+    /// The TID of the call instruction that creates the thread.
+    pub creation_site: Tid,
+}
+
+/// Find all functions that are started as new threads through calls to `pthread_create`.
+///
+/// Only start routines whose address is directly assigned to the corresponding parameter register
+/// in the block containing the call are found.
+/// Parameters passed on the stack (e.g. on x86_32) are not supported yet.
+pub fn find_thread_entry_points(project: &Project) -> Vec<ThreadEntryPoint> {
+    let symbol_map = get_symbol_map(project, &["pthread_create".to_string()]);
+    let mut entry_points = Vec::new();
+    for sub in project.program.term.subs.iter() {
+        for (block, call, symbol) in get_callsites(sub, &symbol_map) {
+            let start_routine_register = match symbol.parameters.get(2) {
+                Some(Arg::Register(var)) => var.name.clone(),
+                Some(Arg::Stack { .. }) => continue,
+                None => match symbol
+                    .get_calling_convention(project)
+                    .parameter_register
+                    .get(2)
+                {
+                    Some(register_name) => register_name.clone(),
+                    None => continue,
+                },
+            };
+            if let Some(address) = get_constant_assigned_to_register(block, &start_routine_register)
+            {
+                if let Some(start_routine) = project
+                    .program
+                    .term
+                    .subs
+                    .iter()
+                    .find(|sub| u64::from_str_radix(&sub.tid.address, 16) == Ok(address))
+                {
+                    entry_points.push(ThreadEntryPoint {
+                        start_routine: start_routine.tid.clone(),
+                        creation_site: call.tid.clone(),
+                    });
+                }
+            }
+        }
+    }
+    entry_points
+}
+
+/// Get the constant value that the last write to the given register in the block assigns to it.
+/// Returns `None` if the last write to the register does not assign a constant value.
+fn get_constant_assigned_to_register(block: &Term<Blk>, register_name: &str) -> Option<u64> {
+    block
+        .term
+        .defs
+        .iter()
+        .rev()
+        .find_map(|def| match &def.term {
+            Def::Assign { var, value } if var.name == register_name => match value {
+                Expression::Const(constant) => Some(constant.try_to_u64().ok()),
+                _ => Some(None),
+            },
+            Def::Load { var, .. } if var.name == register_name => Some(None),
+            _ => None,
+        })
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thread_entry_points() {
+        let mut project = Project::mock_empty();
+        let mut pthread_create = ExternSymbol::mock_with_name("pthread_create");
+        pthread_create.parameters = vec![
+            Arg::Register(Variable::mock("RDI", 8)),
+            Arg::Register(Variable::mock("RSI", 8)),
+            Arg::Register(Variable::mock("RDX", 8)),
+            Arg::Register(Variable::mock("RCX", 8)),
+        ];
+        project.program.term.extern_symbols.push(pthread_create);
+
+        let mut thread_func = Sub::mock("thread_func");
+        thread_func.tid.address = "00101189".to_string();
+        let thread_func_tid = thread_func.tid.clone();
+        let mut main = Sub::mock("main");
+        let mut block = Blk::mock();
+        block.term.defs.push(Def::assign(
+            "load_start_routine",
+            Variable::mock("RDX", 8),
+            Expression::const_from_i64(0x101189),
+        ));
+        block.term.jmps.push(Term {
+            tid: Tid::new("call_pthread_create"),
+            term: Jmp::Call {
+                target: Tid::new("pthread_create"),
+                return_: None,
+            },
+        });
+        main.term.blocks.push(block);
+        project.program.term.subs = vec![main, thread_func];
+
+        let entry_points = find_thread_entry_points(&project);
+        assert_eq!(
+            entry_points,
+            vec![ThreadEntryPoint {
+                start_routine: thread_func_tid,
+                creation_site: Tid::new("call_pthread_create"),
+            }]
+        );
+    }
+
+    #[test]
+    fn atomic_call_other() {
+        let lock = Jmp::CallOther {
+            description: "LOCK".to_string(),
+            return_: None,
+        };
+        let syscall = Jmp::CallOther {
+            description: "syscall".to_string(),
+            return_: None,
+        };
+        assert!(is_atomic_call_other(&lock));
+        assert!(!is_atomic_call_other(&syscall));
+    }
+}
@@ -0,0 +1,779 @@
+//! A bounded concrete emulator for resolving lightweight obfuscation.
+//!
+//! Binaries sometimes hide call targets and strings behind simple obfuscation,
+//! e.g. function pointers that are XOR-ed with a constant key
+//! or strings that are decrypted (or assembled character by character on the stack) right before their use.
+//! Such values are invisible to the static analyses and thus to all checks.
+//!
+//! The emulator executes the IR of each function starting at its entry point with concrete values.
+//! Initially all registers except the stack pointer are unknown.
+//! The stack pointer is set to an artificial stack address,
+//! so that accesses to the stack frame can be emulated.
+//! Memory that was not written during the emulation is read from the runtime memory image of the binary,
+//! using the initial contents of the image even for writeable segments.
+//! Conditional branches are only followed if their condition is known,
+//! direct calls to functions of the binary are followed into the callee
+//! and calls to extern functions clobber all registers that are not callee-saved.
+//! The emulation of a function stops after a configurable number of steps
+//! or as soon as the control flow or the target of a memory write becomes unknown.
+//! Additionally, each block is emulated separately to resolve values computed from constants inside the block.
+//!
+//! The results of the emulation are used to
+//! - replace indirect calls whose target could be resolved to a known function with direct calls,
+//! - report strings that were written to memory during the emulation,
+//! but are not contained in the memory image of the binary.
+//! Strings written to the stack frame (e.g. stack strings assembled character by character)
+//! are reported together with their offset in the stack frame.
+//! For strings decoded in global memory the single-byte XOR key is reported if the string was decoded with one.
+//! - add the strings decoded in global memory to the memory image,
+//! so that checks reading constant strings from global memory (e.g. format strings) see the decoded strings
+//! instead of the encoded contents of the binary.
+
+use crate::abstract_domain::{AbstractDomain, BitvectorDomain, RegisterDomain, SizedDomain};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::binary::RuntimeMemoryImage;
+use crate::utils::log::LogMessage;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// The artificial address that the stack pointer points to at the start of the emulation.
+const STACK_BASE: u64 = 0x7ff0_0000;
+/// Addresses within this distance of [`STACK_BASE`] are considered to be addresses of the stack frame.
+const STACK_REGION_SIZE: u64 = 0x10_0000;
+
+/// The configuration of the emulation.
+///
+/// The configuration is read from the `Emulation` section of the configuration file.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Config {
+    /// If set to `false`, the emulation is not run.
+    pub enabled: bool,
+    /// The maximal number of executed instructions per emulated function.
+    pub max_steps: usize,
+    /// The minimal length of reported strings.
+    pub min_string_length: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            enabled: true,
+            max_steps: 2000,
+            min_string_length: 4,
+        }
+    }
+}
+
+/// The concrete state of the emulation.
+pub struct Emulator<'a> {
+    project: &'a Project,
+    memory_image: &'a RuntimeMemoryImage,
+    /// The values of registers. Registers missing from the map have unknown values.
+    registers: HashMap<Variable, BitvectorDomain>,
+    /// All bytes written during the emulation. `None` marks bytes with unknown values.
+    memory: BTreeMap<u64, Option<u8>>,
+}
+
+impl<'a> Emulator<'a> {
+    /// Create a new emulator state where only the value of the stack pointer is known.
+    pub fn new(project: &'a Project, memory_image: &'a RuntimeMemoryImage) -> Emulator<'a> {
+        let mut emulator = Emulator {
+            project,
+            memory_image,
+            registers: HashMap::new(),
+            memory: BTreeMap::new(),
+        };
+        emulator.reset_registers();
+        emulator
+    }
+
+    /// Set all registers to unknown values except for the stack pointer,
+    /// which is set to the artificial stack address.
+    fn reset_registers(&mut self) {
+        let stack_register = &self.project.stack_pointer_register;
+        self.registers.clear();
+        self.registers.insert(
+            stack_register.clone(),
+            BitvectorDomain::Value(
+                Bitvector::from_u64(STACK_BASE)
+                    .into_zero_resize(apint::BitWidth::from(stack_register.size)),
+            ),
+        );
+    }
+
+    /// Get the value of a register.
+    pub fn get_register(&self, var: &Variable) -> BitvectorDomain {
+        self.registers
+            .get(var)
+            .cloned()
+            .unwrap_or_else(|| BitvectorDomain::new_top(var.size))
+    }
+
+    /// Set the value of a register.
+    fn set_register(&mut self, var: &Variable, value: BitvectorDomain) {
+        if value.is_top() {
+            self.registers.remove(var);
+        } else {
+            self.registers.insert(var.clone(), value);
+        }
+    }
+
+    /// Evaluate the value of an expression in the current state.
+    pub fn eval(&self, expression: &Expression) -> BitvectorDomain {
+        use Expression::*;
+        match expression {
+            Var(variable) => self.get_register(variable),
+            Const(bitvector) => BitvectorDomain::Value(bitvector.clone()),
+            BinOp { op, lhs, rhs } => {
+                if *op == BinOpType::IntXOr && lhs == rhs {
+                    // the result of `x XOR x` is always zero.
+                    return BitvectorDomain::Value(Bitvector::zero(apint::BitWidth::from(
+                        lhs.bytesize(),
+                    )));
+                }
+                self.eval(lhs).bin_op(*op, &self.eval(rhs))
+            }
+            UnOp { op, arg } => self.eval(arg).un_op(*op),
+            Cast { op, size, arg } => self.eval(arg).cast(*op, *size),
+            Unknown {
+                description: _,
+                size,
+            } => BitvectorDomain::new_top(*size),
+            Subpiece {
+                low_byte,
+                size,
+                arg,
+            } => self.eval(arg).subpiece(*low_byte, *size),
+        }
+    }
+
+    /// Get the value of the byte at the given address.
+    /// Bytes not written during the emulation are read from the memory image.
+    fn get_byte(&self, address: u64) -> Option<u8> {
+        if let Some(byte) = self.memory.get(&address) {
+            return *byte;
+        }
+        self.get_image_byte(address)
+    }
+
+    /// Get the value of the byte at the given address in the memory image.
+    fn get_image_byte(&self, address: u64) -> Option<u8> {
+        self.memory_image
+            .iter_segments()
+            .find(|(base_address, bytes)| {
+                address >= *base_address && address - base_address < bytes.len() as u64
+            })
+            .map(|(base_address, bytes)| bytes[(address - base_address) as usize])
+    }
+
+    /// Read a value of the given size from memory.
+    /// Returns *Top* if at least one of the bytes is unknown.
+    pub fn read(&self, address: u64, size: ByteSize) -> BitvectorDomain {
+        let num_bytes = u64::from(size);
+        if num_bytes == 0 || num_bytes > 8 {
+            return BitvectorDomain::new_top(size);
+        }
+        let mut value: u64 = 0;
+        for index in 0..num_bytes {
+            let byte_address = if self.memory_image.is_little_endian_byte_order() {
+                address.wrapping_add(num_bytes - 1 - index)
+            } else {
+                address.wrapping_add(index)
+            };
+            match self.get_byte(byte_address) {
+                Some(byte) => value = (value << 8) | u64::from(byte),
+                None => return BitvectorDomain::new_top(size),
+            }
+        }
+        BitvectorDomain::Value(
+            Bitvector::from_u64(value).into_zero_resize(apint::BitWidth::from(size)),
+        )
+    }
+
+    /// Write a value to memory. Unknown values mark the written bytes as unknown.
+    fn write(&mut self, address: u64, value: &BitvectorDomain) {
+        let num_bytes = u64::from(value.bytesize());
+        let known_value = match value {
+            BitvectorDomain::Value(bitvec) if num_bytes <= 8 => bitvec.try_to_u64().ok(),
+            _ => None,
+        };
+        for index in 0..num_bytes {
+            let shift = if self.memory_image.is_little_endian_byte_order() {
+                index
+            } else {
+                num_bytes - 1 - index
+            };
+            let byte = known_value.map(|value| (value >> (8 * shift)) as u8);
+            self.memory.insert(address.wrapping_add(index), byte);
+        }
+    }
+
+    /// Execute a `Def` term.
+    ///
+    /// Returns an error if the target address of a store is unknown,
+    /// since the memory state cannot be tracked afterwards.
+    pub fn execute_def(&mut self, def: &Term<Def>) -> Result<(), Error> {
+        match &def.term {
+            Def::Assign { var, value } => {
+                let value = self.eval(value);
+                self.set_register(var, value);
+            }
+            Def::Load { var, address } => {
+                let value = match self.eval(address) {
+                    BitvectorDomain::Value(address) => match address.try_to_u64() {
+                        Ok(address) => self.read(address, var.size),
+                        Err(_) => BitvectorDomain::new_top(var.size),
+                    },
+                    _ => BitvectorDomain::new_top(var.size),
+                };
+                self.set_register(var, value);
+            }
+            Def::Store { address, value } => {
+                let address = match self.eval(address) {
+                    BitvectorDomain::Value(address) => address.try_to_u64().ok(),
+                    _ => None,
+                };
+                match address {
+                    Some(address) => {
+                        let value = self.eval(value);
+                        self.write(address, &value);
+                    }
+                    None => return Err(anyhow!("Store to unknown address")),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Evaluate the target address of an indirect jump or call.
+    fn eval_target(&self, target: &Expression) -> Option<u64> {
+        match self.eval(target) {
+            BitvectorDomain::Value(address) => address.try_to_u64().ok(),
+            _ => None,
+        }
+    }
+
+    /// Set all registers that are not callee-saved in the standard calling convention to unknown values.
+    fn clobber_caller_saved_registers(&mut self) {
+        let stack_register = &self.project.stack_pointer_register;
+        let callee_saved_registers: &[String] = match self.project.get_standard_calling_convention()
+        {
+            Some(cconv) => &cconv.callee_saved_register,
+            None => &[],
+        };
+        self.registers
+            .retain(|var, _| var == stack_register || callee_saved_registers.contains(&var.name));
+    }
+
+    /// Find all strings written to memory during the emulation
+    /// that differ from the contents of the memory image at the same address.
+    ///
+    /// A string is a sequence of at least `min_length` printable ASCII characters.
+    fn find_written_strings(&self, min_length: usize) -> Vec<(u64, String)> {
+        let mut strings = Vec::new();
+        let mut current: Option<(u64, String)> = None;
+        let mut last_address = None;
+        for (address, byte) in self.memory.iter() {
+            let is_continuation =
+                last_address.map(|last: u64| last.wrapping_add(1)) == Some(*address);
+            last_address = Some(*address);
+            let character = match byte {
+                Some(byte) if (byte.is_ascii_graphic() || *byte == b' ') => Some(*byte as char),
+                _ => None,
+            };
+            match (character, current.as_mut()) {
+                (Some(character), Some((_, string))) if is_continuation => string.push(character),
+                (Some(character), _) => {
+                    strings.extend(current.take());
+                    current = Some((*address, character.to_string()));
+                }
+                (None, _) => strings.extend(current.take()),
+            }
+        }
+        strings.extend(current.take());
+        strings
+            .into_iter()
+            .filter(|(address, string)| {
+                string.len() >= min_length && !self.is_contained_in_memory_image(*address, string)
+            })
+            .collect()
+    }
+
+    /// Determine where a string written during the emulation originates from.
+    ///
+    /// Strings in the artificial stack region are stack strings.
+    /// For other strings the contents of the memory image at the same address are compared with the string
+    /// to detect whether it was decoded in place with a single-byte XOR key.
+    fn get_string_origin(&self, address: u64, string: &str) -> StringOrigin {
+        if address.wrapping_sub(STACK_BASE - STACK_REGION_SIZE) < 2 * STACK_REGION_SIZE {
+            return StringOrigin::Stack {
+                offset: address.wrapping_sub(STACK_BASE) as i64,
+            };
+        }
+        let mut keys = string.bytes().enumerate().map(|(index, byte)| {
+            self.get_image_byte(address.wrapping_add(index as u64))
+                .map(|image_byte| image_byte ^ byte)
+        });
+        match keys.next() {
+            Some(Some(key)) if key != 0 && keys.all(|other_key| other_key == Some(key)) => {
+                StringOrigin::XorDecoded { key }
+            }
+            _ => StringOrigin::Decoded,
+        }
+    }
+
+    /// Check whether the memory image contains the given string at the given address.
+    fn is_contained_in_memory_image(&self, address: u64, string: &str) -> bool {
+        self.memory_image
+            .iter_segments()
+            .any(|(base_address, bytes)| {
+                address >= base_address
+                    && bytes
+                        .get((address - base_address) as usize..)
+                        .map(|bytes| bytes.starts_with(string.as_bytes()))
+                        .unwrap_or(false)
+            })
+    }
+}
+
+/// The origin of a string written to memory during the emulation.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum StringOrigin {
+    /// A string written to the stack frame,
+    /// given by its offset relative to the stack pointer at the start of the emulated function.
+    Stack {
+        /// The offset of the string in the stack frame.
+        offset: i64,
+    },
+    /// A string decoded in place in global memory with a single-byte XOR key.
+    XorDecoded {
+        /// The key of the XOR decoding.
+        key: u8,
+    },
+    /// Any other string written to global memory.
+    Decoded,
+}
+
+/// A string written to memory during the emulation of a function.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct RecoveredString {
+    /// The TID of the emulated function.
+    pub function: Tid,
+    /// The address of the string in the emulated memory.
+    pub address: u64,
+    /// The recovered string.
+    pub string: String,
+    /// The origin of the string.
+    pub origin: StringOrigin,
+}
+
+/// The results of the emulation.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct EmulationResults {
+    /// The resolved target addresses of indirect calls.
+    /// Calls that were resolved to different targets are mapped to `None`.
+    pub call_targets: HashMap<Tid, Option<u64>>,
+    /// Strings written to memory during the emulation of the functions.
+    pub strings: Vec<RecoveredString>,
+}
+
+impl EmulationResults {
+    /// Add a resolved target of an indirect call.
+    fn add_call_target(&mut self, call: &Tid, target: u64) {
+        let entry = self
+            .call_targets
+            .entry(call.clone())
+            .or_insert(Some(target));
+        if *entry != Some(target) {
+            *entry = None;
+        }
+    }
+}
+
+/// Emulate the function from its entry point
+/// and add the resolved call targets and the written strings to the results.
+fn emulate_function(
+    project: &Project,
+    memory_image: &RuntimeMemoryImage,
+    sub: &Term<Sub>,
+    config: &Config,
+    results: &mut EmulationResults,
+) {
+    let mut emulator = Emulator::new(project, memory_image);
+    let mut call_stack: Vec<Tid> = Vec::new();
+    let mut current_sub = sub;
+    let mut current_block = match sub.term.blocks.first() {
+        Some(block) => block,
+        None => return,
+    };
+    let mut steps = 0;
+    'emulation: while steps < config.max_steps {
+        for def in current_block.term.defs.iter() {
+            if emulator.execute_def(def).is_err() {
+                break 'emulation;
+            }
+        }
+        steps += current_block.term.defs.len() + 1;
+        let mut next_block = None;
+        for jmp in current_block.term.jmps.iter() {
+            match &jmp.term {
+                Jmp::Branch(target) => next_block = Some(target.clone()),
+                Jmp::CBranch { target, condition } => match emulator.eval(condition) {
+                    BitvectorDomain::Value(condition) if condition.is_zero() => continue,
+                    BitvectorDomain::Value(_) => next_block = Some(target.clone()),
+                    _ => break 'emulation,
+                },
+                Jmp::Call { target, return_ } => {
+                    let callee_block = project
+                        .program
+                        .term
+                        .subs
+                        .iter()
+                        .find(|sub| sub.tid == *target)
+                        .and_then(|callee| Some((callee, callee.term.blocks.first()?)));
+                    if let (Some(return_), Some((callee, callee_block))) = (return_, callee_block) {
+                        call_stack.push(return_.clone());
+                        current_sub = callee;
+                        current_block = callee_block;
+                        continue 'emulation;
+                    }
+                    emulator.clobber_caller_saved_registers();
+                    next_block = return_.clone();
+                }
+                Jmp::CallInd { target, return_ } => {
+                    if let Some(address) = emulator.eval_target(target) {
+                        results.add_call_target(&jmp.tid, address);
+                    }
+                    emulator.clobber_caller_saved_registers();
+                    next_block = return_.clone();
+                }
+                Jmp::Return(_) => next_block = call_stack.pop(),
+                Jmp::BranchInd(_) | Jmp::CallOther { .. } => (),
+            }
+            break;
+        }
+        current_block =
+            match next_block.and_then(|block_tid| find_block(project, current_sub, &block_tid)) {
+                Some((sub, block)) => {
+                    current_sub = sub;
+                    block
+                }
+                None => break,
+            };
+    }
+    for (address, string) in emulator.find_written_strings(config.min_string_length) {
+        results.strings.push(RecoveredString {
+            function: sub.tid.clone(),
+            origin: emulator.get_string_origin(address, &string),
+            address,
+            string,
+        });
+    }
+}
+
+/// Find the block with the given TID.
+/// The block is searched in the given function first and then in all other functions.
+fn find_block<'a>(
+    project: &'a Project,
+    sub: &'a Term<Sub>,
+    block_tid: &Tid,
+) -> Option<(&'a Term<Sub>, &'a Term<Blk>)> {
+    std::iter::once(sub)
+        .chain(project.program.term.subs.iter())
+        .find_map(|sub| {
+            sub.term
+                .blocks
+                .iter()
+                .find(|block| block.tid == *block_tid)
+                .map(|block| (sub, block))
+        })
+}
+
+/// Emulate each block of the function separately
+/// and add the resolved targets of indirect calls at the end of the blocks to the results.
+fn emulate_blocks(
+    project: &Project,
+    memory_image: &RuntimeMemoryImage,
+    sub: &Term<Sub>,
+    results: &mut EmulationResults,
+) {
+    for block in sub.term.blocks.iter() {
+        let mut emulator = Emulator::new(project, memory_image);
+        if block
+            .term
+            .defs
+            .iter()
+            .any(|def| emulator.execute_def(def).is_err())
+        {
+            continue;
+        }
+        for jmp in block.term.jmps.iter() {
+            if let Jmp::CallInd { target, .. } = &jmp.term {
+                if let Some(address) = emulator.eval_target(target) {
+                    results.add_call_target(&jmp.tid, address);
+                }
+            }
+        }
+    }
+}
+
+/// Run the emulation on all functions of the project.
+pub fn emulate(
+    project: &Project,
+    memory_image: &RuntimeMemoryImage,
+    config: &Config,
+) -> EmulationResults {
+    let mut results = EmulationResults::default();
+    for sub in project.program.term.subs.iter() {
+        emulate_blocks(project, memory_image, sub, &mut results);
+        emulate_function(project, memory_image, sub, config, &mut results);
+    }
+    results
+}
+
+/// Run the emulation and replace indirect calls with resolved targets by direct calls.
+/// Strings decoded in global memory are added to the memory image.
+///
+/// Returns log messages for the replaced calls and for the strings found during the emulation.
+#[must_use]
+pub fn resolve_obfuscation(
+    project: &mut Project,
+    memory_image: &mut RuntimeMemoryImage,
+    config: &Config,
+) -> Vec<LogMessage> {
+    if !config.enabled {
+        return Vec::new();
+    }
+    let results = emulate(project, memory_image, config);
+    let mut logs = Vec::new();
+    let mut function_addresses: HashMap<u64, Tid> = HashMap::new();
+    for sub in project.program.term.subs.iter() {
+        if let Ok(address) = u64::from_str_radix(&sub.tid.address, 16) {
+            function_addresses.insert(address, sub.tid.clone());
+        }
+    }
+    for symbol in project.program.term.extern_symbols.iter() {
+        for address in symbol.addresses.iter() {
+            if let Ok(address) = u64::from_str_radix(address, 16) {
+                function_addresses.insert(address, symbol.tid.clone());
+            }
+        }
+    }
+    for sub in project.program.term.subs.iter_mut() {
+        for block in sub.term.blocks.iter_mut() {
+            for jmp in block.term.jmps.iter_mut() {
+                let resolved_target = results
+                    .call_targets
+                    .get(&jmp.tid)
+                    .cloned()
+                    .flatten()
+                    .and_then(|address| function_addresses.get(&address));
+                let (target, return_) = match (&jmp.term, resolved_target) {
+                    (Jmp::CallInd { return_, .. }, Some(target)) => {
+                        (target.clone(), return_.clone())
+                    }
+                    _ => continue,
+                };
+                logs.push(
+                    LogMessage::new_debug(format!(
+                        "Resolved indirect call to {} through emulation",
+                        target
+                    ))
+                    .location(jmp.tid.clone())
+                    .source("Emulation"),
+                );
+                jmp.term = Jmp::Call { target, return_ };
+            }
+        }
+    }
+    let mut added_strings = HashSet::new();
+    for recovered in results.strings {
+        let text = match recovered.origin {
+            StringOrigin::Stack { offset } => format!(
+                "Found stack string \"{}\" at stack offset {} through emulation",
+                recovered.string, offset
+            ),
+            StringOrigin::XorDecoded { key } => format!(
+                "Found string \"{}\" at {:08x} decoded with XOR key {:#04x} through emulation",
+                recovered.string, recovered.address, key
+            ),
+            StringOrigin::Decoded => format!(
+                "Found string \"{}\" at {:08x} through emulation",
+                recovered.string, recovered.address
+            ),
+        };
+        logs.push(
+            LogMessage::new_info(text)
+                .location(recovered.function)
+                .source("Emulation"),
+        );
+        if !matches!(recovered.origin, StringOrigin::Stack { .. })
+            && added_strings.insert(recovered.address)
+        {
+            memory_image.add_recovered_string(recovered.address, &recovered.string);
+        }
+    }
+    logs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn def(name: &str, def: Def) -> Term<Def> {
+        Term {
+            tid: Tid::new(name),
+            term: def,
+        }
+    }
+
+    fn bitvec(value: u64, size: u64) -> Expression {
+        Expression::Const(
+            Bitvector::from_u64(value).into_zero_resize(apint::BitWidth::from(ByteSize::new(size))),
+        )
+    }
+
+    fn xor(lhs: Expression, rhs: Expression) -> Expression {
+        Expression::BinOp {
+            op: BinOpType::IntXOr,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        }
+    }
+
+    fn stack_address(offset: u64) -> Expression {
+        Expression::BinOp {
+            op: BinOpType::IntSub,
+            lhs: Box::new(Expression::Var(Variable::mock("RSP", 8u64))),
+            rhs: Box::new(bitvec(offset, 8)),
+        }
+    }
+
+    fn mock_project() -> Project {
+        let mut project = Project::mock_empty();
+        let mut block = Blk::mock();
+        // A stack string assembled from obfuscated constants.
+        block.term.defs = vec![
+            def(
+                "store_string",
+                Def::Store {
+                    address: stack_address(16),
+                    value: xor(bitvec(0x6463_6261 ^ 0x1111_1111, 4), bitvec(0x1111_1111, 4)),
+                },
+            ),
+            def(
+                "store_zero",
+                Def::Store {
+                    address: stack_address(12),
+                    value: bitvec(0, 1),
+                },
+            ),
+            // A function pointer decrypted with a constant key.
+            def(
+                "load_pointer",
+                Def::Load {
+                    var: Variable::mock("EAX", 4u64),
+                    address: bitvec(0x1000, 8),
+                },
+            ),
+            def(
+                "decrypt_pointer",
+                Def::Assign {
+                    var: Variable::mock("EAX", 4u64),
+                    value: xor(
+                        Expression::Var(Variable::mock("EAX", 4u64)),
+                        bitvec(0xb3b2_b1b0 ^ 0x0040_1000, 4),
+                    ),
+                },
+            ),
+        ];
+        block.term.jmps.push(Term {
+            tid: Tid::new("call"),
+            term: Jmp::CallInd {
+                target: Expression::Var(Variable::mock("EAX", 4u64)),
+                return_: None,
+            },
+        });
+        let mut sub = Sub::mock("main");
+        sub.term.blocks.push(block);
+        let mut callee = Sub::mock("callee");
+        callee.tid.address = "00401000".to_string();
+        project.program.term.subs = vec![sub, callee];
+        project
+    }
+
+    #[test]
+    fn concrete_memory_access() {
+        let project = Project::mock_empty();
+        let memory_image = RuntimeMemoryImage::mock();
+        let mut emulator = Emulator::new(&project, &memory_image);
+        assert_eq!(
+            emulator.read(0x1000, ByteSize::new(2)),
+            BitvectorDomain::Value(Bitvector::from_u16(0xb1b0))
+        );
+        // Writeable segments are read with their initial contents.
+        assert_eq!(
+            emulator.read(0x2000, ByteSize::new(1)),
+            BitvectorDomain::Value(Bitvector::from_u8(0))
+        );
+        emulator.write(0x2000, &BitvectorDomain::Value(Bitvector::from_u16(0x4142)));
+        assert_eq!(
+            emulator.read(0x2000, ByteSize::new(2)),
+            BitvectorDomain::Value(Bitvector::from_u16(0x4142))
+        );
+        emulator.write(0x2001, &BitvectorDomain::new_top(ByteSize::new(1)));
+        assert!(emulator.read(0x2000, ByteSize::new(2)).is_top());
+        assert!(emulator.read(0x3000, ByteSize::new(1)).is_top());
+    }
+
+    #[test]
+    fn obfuscation_resolution() {
+        let mut project = mock_project();
+        let mut memory_image = RuntimeMemoryImage::mock();
+        let logs = resolve_obfuscation(&mut project, &mut memory_image, &Config::default());
+        assert_eq!(
+            project.program.term.subs[0].term.blocks[0].term.jmps[0].term,
+            Jmp::Call {
+                target: project.program.term.subs[1].tid.clone(),
+                return_: None
+            }
+        );
+        assert_eq!(logs.len(), 2);
+        assert_eq!(
+            logs[1].text,
+            "Found stack string \"abcd\" at stack offset -16 through emulation"
+        );
+    }
+
+    #[test]
+    fn xor_decoded_strings() {
+        let project = Project::mock_empty();
+        let mut memory_image = RuntimeMemoryImage::mock();
+        let mut emulator = Emulator::new(&project, &memory_image);
+        // The contents of the memory image XOR-ed with the key 0xd1.
+        emulator.write(
+            0x1000,
+            &BitvectorDomain::Value(Bitvector::from_u32(0x6263_6061)),
+        );
+        let strings = emulator.find_written_strings(4);
+        assert_eq!(strings, vec![(0x1000, "a`cb".to_string())]);
+        assert_eq!(
+            emulator.get_string_origin(0x1000, "a`cb"),
+            StringOrigin::XorDecoded { key: 0xd1 }
+        );
+        assert_eq!(
+            emulator.get_string_origin(0x2000, "abcd"),
+            StringOrigin::Decoded
+        );
+        assert_eq!(
+            emulator.get_string_origin(STACK_BASE + 8, "abcd"),
+            StringOrigin::Stack { offset: 8 }
+        );
+
+        memory_image.add_recovered_string(0x1000, "a`cb");
+        let (bytes, index) = memory_image
+            .get_ro_data_pointer_at_address(&Bitvector::from_u64(0x1000))
+            .unwrap();
+        assert_eq!(&bytes[index..], b"a`cb\0");
+    }
+}
@@ -0,0 +1,443 @@
+//! Export analysis results as a relational fact base.
+//!
+//! The facts computed by the cwe_checker (call graph, points-to information of the pointer inference analysis
+//! and sources of untrusted input) are written as one table per relation,
+//! either as tab-separated `.facts` files that can be read directly by Soufflé
+//! or as CSV files with a header row.
+//! This allows prototyping new queries with external tools (e.g. Datalog engines)
+//! before implementing them as a check module.
+//!
+//! The exported relations are:
+//! - `function(function, name)` and `extern_function(function, name)`
+//! - `entry_point(function)`
+//! - `block(block, function)`
+//! - `call(caller, call_site, callee)` for direct calls
+//!   and `indirect_call(caller, call_site)` for calls with unknown target
+//! - `points_to(block, register, object, offset)` for register values at the start of a block
+//! - `param_points_to(call_site, index, object, offset)` for parameters of calls to extern functions
+//! - `object(object, time, location)` for the abstract identifiers of memory objects
+//! - `taint_source(call_site, symbol)` for calls to functions reading untrusted input
+//! - `struct_field(object, offset, size)` and `object_size(object, size)` for the recovered structure layouts
+//!   of memory objects (see the [`struct_layout`](crate::analysis::struct_layout) module)
+//!
+//! Offsets that are not exactly known are exported as `Top`.
+
+use super::interprocedural_fixpoint_generic::NodeValue;
+use super::pointer_inference::{Data, PointerInference};
+use super::struct_layout::{self, StructLayouts};
+use crate::abstract_domain::{AbstractIdentifier, BitvectorDomain};
+use crate::analysis::graph::Node;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::binary::RuntimeMemoryImage;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// The configuration of the fact export.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct Config {
+    /// Names of extern functions whose calls are exported as sources of untrusted input.
+    pub taint_sources: Vec<String>,
+    /// The configuration of the recovery of structure layouts.
+    #[serde(default)]
+    pub struct_layout: struct_layout::Config,
+}
+
+/// The file format of the exported fact base.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum FactFormat {
+    /// Tab-separated files without header with the file extension `.facts`, as expected by Soufflé.
+    Souffle,
+    /// Comma-separated files with a header row and the file extension `.csv`.
+    Csv,
+}
+
+impl std::str::FromStr for FactFormat {
+    type Err = Error;
+
+    fn from_str(format: &str) -> Result<FactFormat, Error> {
+        match format.to_lowercase().as_str() {
+            "souffle" | "facts" => Ok(FactFormat::Souffle),
+            "csv" => Ok(FactFormat::Csv),
+            _ => Err(anyhow!("Unknown fact format: {}", format)),
+        }
+    }
+}
+
+/// A single relation, i.e. a table with named columns.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Relation {
+    columns: &'static [&'static str],
+    rows: BTreeSet<Vec<String>>,
+}
+
+/// A collection of relations containing the exported facts.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct FactBase {
+    relations: BTreeMap<&'static str, Relation>,
+}
+
+impl FactBase {
+    /// Generate the fact base for the given project.
+    /// If the pointer inference results are given, the points-to facts are also generated.
+    pub fn new(
+        project: &Project,
+        pointer_inference: Option<&PointerInference>,
+        runtime_memory_image: &RuntimeMemoryImage,
+        config: &Config,
+    ) -> FactBase {
+        let mut fact_base = FactBase::default();
+        fact_base.add_program_facts(project, config);
+        if let Some(pointer_inference) = pointer_inference {
+            fact_base.add_points_to_facts(project, pointer_inference, runtime_memory_image);
+            let struct_layouts = StructLayouts::compute(
+                project,
+                pointer_inference,
+                runtime_memory_image,
+                &config.struct_layout,
+            );
+            fact_base.add_struct_layout_facts(&struct_layouts);
+        }
+        fact_base
+    }
+
+    /// Get the rows of the relation with the given name.
+    pub fn get_rows(&self, relation: &str) -> Option<&BTreeSet<Vec<String>>> {
+        self.relations.get(relation).map(|relation| &relation.rows)
+    }
+
+    /// Add a row to the relation with the given name.
+    /// The relation is created if it does not exist yet.
+    fn add_fact(
+        &mut self,
+        relation: &'static str,
+        columns: &'static [&'static str],
+        row: Vec<String>,
+    ) {
+        debug_assert_eq!(columns.len(), row.len());
+        self.relations
+            .entry(relation)
+            .or_insert_with(|| Relation {
+                columns,
+                rows: BTreeSet::new(),
+            })
+            .rows
+            .insert(row);
+    }
+
+    /// Add the facts about functions, blocks and calls, which can be generated from the program itself.
+    fn add_program_facts(&mut self, project: &Project, config: &Config) {
+        let program = &project.program.term;
+        for symbol in program.extern_symbols.iter() {
+            self.add_fact(
+                "extern_function",
+                &["function", "name"],
+                vec![symbol.tid.to_string(), symbol.name.clone()],
+            );
+        }
+        for entry_point in program.entry_points.iter() {
+            self.add_fact("entry_point", &["function"], vec![entry_point.to_string()]);
+        }
+        for sub in program.subs.iter() {
+            self.add_fact(
+                "function",
+                &["function", "name"],
+                vec![sub.tid.to_string(), sub.term.name.clone()],
+            );
+            for block in sub.term.blocks.iter() {
+                self.add_fact(
+                    "block",
+                    &["block", "function"],
+                    vec![block.tid.to_string(), sub.tid.to_string()],
+                );
+                for jmp in block.term.jmps.iter() {
+                    match &jmp.term {
+                        Jmp::Call { target, .. } => {
+                            self.add_fact(
+                                "call",
+                                &["caller", "call_site", "callee"],
+                                vec![sub.tid.to_string(), jmp.tid.to_string(), target.to_string()],
+                            );
+                            if let Some(symbol) = program
+                                .extern_symbols
+                                .iter()
+                                .find(|symbol| symbol.tid == *target)
+                            {
+                                if config.taint_sources.contains(&symbol.name) {
+                                    self.add_fact(
+                                        "taint_source",
+                                        &["call_site", "symbol"],
+                                        vec![jmp.tid.to_string(), symbol.name.clone()],
+                                    );
+                                }
+                            }
+                        }
+                        Jmp::CallInd { .. } => self.add_fact(
+                            "indirect_call",
+                            &["caller", "call_site"],
+                            vec![sub.tid.to_string(), jmp.tid.to_string()],
+                        ),
+                        _ => (),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Add the points-to facts computed by the pointer inference analysis.
+    fn add_points_to_facts(
+        &mut self,
+        project: &Project,
+        pointer_inference: &PointerInference,
+        runtime_memory_image: &RuntimeMemoryImage,
+    ) {
+        let graph = pointer_inference.get_graph();
+        for node in graph.node_indices() {
+            let state = match pointer_inference.get_node_value(node) {
+                Some(NodeValue::Value(state)) => state,
+                _ => continue,
+            };
+            match graph[node] {
+                Node::BlkStart(block, _sub) => {
+                    for (register, data) in state.get_all_register_values() {
+                        for (object, offset) in get_pointer_targets(data) {
+                            self.add_object_fact(object);
+                            self.add_fact(
+                                "points_to",
+                                &["block", "register", "object", "offset"],
+                                vec![
+                                    block.tid.to_string(),
+                                    register.name.clone(),
+                                    object.to_string(),
+                                    offset,
+                                ],
+                            );
+                        }
+                    }
+                }
+                Node::BlkEnd(block, _sub) => {
+                    for jmp in block.term.jmps.iter() {
+                        if let Jmp::Call { target, .. } = &jmp.term {
+                            if let Some(symbol) = project
+                                .program
+                                .term
+                                .extern_symbols
+                                .iter()
+                                .find(|symbol| symbol.tid == *target)
+                            {
+                                let parameter_count = if symbol.parameters.is_empty() {
+                                    symbol
+                                        .get_calling_convention(project)
+                                        .parameter_register
+                                        .len()
+                                } else {
+                                    symbol.parameters.len()
+                                };
+                                for index in 0..parameter_count {
+                                    if let Ok(data) = state.eval_nth_parameter(
+                                        project,
+                                        symbol,
+                                        index,
+                                        runtime_memory_image,
+                                    ) {
+                                        for (object, offset) in get_pointer_targets(&data) {
+                                            self.add_object_fact(object);
+                                            self.add_fact(
+                                                "param_points_to",
+                                                &["call_site", "index", "object", "offset"],
+                                                vec![
+                                                    jmp.tid.to_string(),
+                                                    index.to_string(),
+                                                    object.to_string(),
+                                                    offset,
+                                                ],
+                                            );
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                Node::CallReturn { .. } | Node::CallSource { .. } => (),
+            }
+        }
+    }
+
+    /// Add the facts describing the recovered structure layouts of memory objects.
+    fn add_struct_layout_facts(&mut self, struct_layouts: &StructLayouts) {
+        for (object, layout) in struct_layouts.iter() {
+            self.add_object_fact(object);
+            for (offset, size) in layout.get_fields() {
+                self.add_fact(
+                    "struct_field",
+                    &["object", "offset", "size"],
+                    vec![
+                        object.to_string(),
+                        offset.to_string(),
+                        u64::from(*size).to_string(),
+                    ],
+                );
+            }
+            if let Some(size) = layout.get_object_size() {
+                self.add_fact(
+                    "object_size",
+                    &["object", "size"],
+                    vec![object.to_string(), size.to_string()],
+                );
+            }
+        }
+    }
+
+    /// Add the fact describing the given abstract identifier of a memory object.
+    fn add_object_fact(&mut self, object: &AbstractIdentifier) {
+        self.add_fact(
+            "object",
+            &["object", "time", "location"],
+            vec![
+                object.to_string(),
+                object.get_tid().to_string(),
+                object.get_location().to_string(),
+            ],
+        );
+    }
+
+    /// Write all relations to the given directory, one file per relation.
+    /// The directory is created if it does not exist.
+    pub fn write_to_directory(&self, directory: &Path, format: FactFormat) -> Result<(), Error> {
+        std::fs::create_dir_all(directory)?;
+        for (name, relation) in self.relations.iter() {
+            let (file_name, content) = match format {
+                FactFormat::Souffle => (format!("{}.facts", name), relation.to_souffle()),
+                FactFormat::Csv => (format!("{}.csv", name), relation.to_csv()),
+            };
+            std::fs::write(directory.join(file_name), content)?;
+        }
+        Ok(())
+    }
+}
+
+impl Relation {
+    /// Generate the content of a tab-separated Soufflé fact file.
+    /// Tabs and line breaks inside of fields are replaced by spaces.
+    fn to_souffle(&self) -> String {
+        let mut content = String::new();
+        for row in self.rows.iter() {
+            let fields: Vec<String> = row
+                .iter()
+                .map(|field| field.replace(|c| c == '\t' || c == '\n' || c == '\r', " "))
+                .collect();
+            content.push_str(&fields.join("\t"));
+            content.push('\n');
+        }
+        content
+    }
+
+    /// Generate the content of a CSV file with a header row.
+    fn to_csv(&self) -> String {
+        let mut content = self.columns.join(",");
+        content.push('\n');
+        for row in self.rows.iter() {
+            let fields: Vec<String> = row.iter().map(|field| escape_csv_field(field)).collect();
+            content.push_str(&fields.join(","));
+            content.push('\n');
+        }
+        content
+    }
+}
+
+/// Quote a CSV field if it contains characters with special meaning in CSV.
+fn escape_csv_field(field: &str) -> String {
+    if field.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Get the targets of a pointer together with their offsets formatted for the export.
+fn get_pointer_targets(data: &Data) -> Vec<(&AbstractIdentifier, String)> {
+    match data {
+        Data::Pointer(pointer) => pointer
+            .targets()
+            .iter()
+            .map(|(object, offset)| {
+                let offset = match offset {
+                    BitvectorDomain::Value(offset) => match offset.try_to_i64() {
+                        Ok(offset) => offset.to_string(),
+                        Err(_) => "Top".to_string(),
+                    },
+                    BitvectorDomain::Top(_)
+                    | BitvectorDomain::Partial(..)
+                    | BitvectorDomain::Interval(_)
+                    | BitvectorDomain::KnownBits(_) => "Top".to_string(),
+                };
+                (object, offset)
+            })
+            .collect(),
+        Data::Top(_) | Data::Value(_) => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_project() -> Project {
+        let mut project = Project::mock_empty();
+        project
+            .program
+            .term
+            .extern_symbols
+            .push(ExternSymbol::mock_with_name("read"));
+        let mut main = Sub::mock("main");
+        let mut block = Blk::mock();
+        block.term.jmps.push(Term {
+            tid: Tid::new("call_read"),
+            term: Jmp::Call {
+                target: Tid::new("read"),
+                return_: None,
+            },
+        });
+        main.term.blocks.push(block);
+        project.program.term.subs.push(main);
+        project.program.term.entry_points.push(Tid::new("main"));
+        project
+    }
+
+    #[test]
+    fn program_facts() {
+        let project = mock_project();
+        let config = Config {
+            taint_sources: vec!["read".to_string()],
+            ..Config::default()
+        };
+        let mut fact_base = FactBase::default();
+        fact_base.add_program_facts(&project, &config);
+        let calls = fact_base.get_rows("call").unwrap();
+        assert_eq!(calls.len(), 1);
+        assert!(calls.contains(&vec![
+            "main".to_string(),
+            "call_read".to_string(),
+            "read".to_string()
+        ]));
+        let taint_sources = fact_base.get_rows("taint_source").unwrap();
+        assert!(taint_sources.contains(&vec!["call_read".to_string(), "read".to_string()]));
+        assert_eq!(fact_base.get_rows("entry_point").unwrap().len(), 1);
+        assert!(fact_base.get_rows("indirect_call").is_none());
+    }
+
+    #[test]
+    fn file_formats() {
+        let mut rows = BTreeSet::new();
+        rows.insert(vec!["a,b".to_string(), "c\td".to_string()]);
+        let relation = Relation {
+            columns: &["first", "second"],
+            rows,
+        };
+        assert_eq!(relation.to_souffle(), "a,b\tc d\n");
+        assert_eq!(relation.to_csv(), "first,second\n\"a,b\",c\td\n");
+        assert_eq!("CSV".parse::<FactFormat>().unwrap(), FactFormat::Csv);
+        assert!("xml".parse::<FactFormat>().is_err());
+    }
+}
@@ -34,10 +34,17 @@
 //! The `Computation` object also contains methods to actually run the fixpoint computation after the starting values are set
 //! and methods to retrieve the results of the computation.
 
+use crate::utils::cancellation::CancellationToken;
+use crate::utils::perf_stats::{self, Counter};
 use fnv::FnvHashMap;
 use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
-use petgraph::visit::EdgeRef;
-use std::collections::{BTreeMap, BTreeSet};
+use petgraph::visit::{DfsPostOrder, EdgeRef, VisitMap};
+use petgraph::Direction;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+/// The number of changes of a node value after which new values are merged into it
+/// using the `widen` function of the context instead of the `merge` function.
+pub const WIDENING_DELAY: u64 = 3;
 
 /// The context of a fixpoint computation.
 ///
@@ -57,6 +64,16 @@ pub trait Context {
     /// This function describes how to merge two values
     fn merge(&self, val1: &Self::NodeValue, val2: &Self::NodeValue) -> Self::NodeValue;
 
+    /// Merge the new value `new_value` into the value `old_value` of a node
+    /// that was already changed `WIDENING_DELAY` times.
+    ///
+    /// Contexts whose values may form infinite ascending chains should extrapolate the merged value here,
+    /// so that the fixpoint computation stabilizes.
+    /// The default implementation just merges the values.
+    fn widen(&self, old_value: &Self::NodeValue, new_value: &Self::NodeValue) -> Self::NodeValue {
+        self.merge(new_value, old_value)
+    }
+
     /// This function describes how the value at the end node of an edge is computed from the value at the start node of the edge.
     /// The function can return None to indicate that no end value gets generated through this edge.
     /// E.g. In a control flow graph, if the edge cannot be taken for the given start value, this function should return None.
@@ -95,17 +112,17 @@ pub struct Computation<T: Context> {
     default_value: Option<T::NodeValue>,
     /// The internal map containing all known node values.
     node_values: FnvHashMap<NodeIndex, T::NodeValue>,
+    /// Maps a node index to the number of times its value was changed by merging new values into it.
+    value_changes: Vec<u64>,
 }
 
 impl<T: Context> Computation<T> {
     /// Create a new fixpoint computation from a fixpoint problem, the corresponding graph
     /// and a default value for all nodes if one should exists.
     pub fn new(fp_context: T, default_value: Option<T::NodeValue>) -> Self {
-        let graph = fp_context.get_graph();
-        // order the nodes in weak topological order
-        let sorted_nodes: Vec<NodeIndex> = petgraph::algo::kosaraju_scc(&graph)
+        // Nodes earlier in the weak topological order get higher priorities.
+        let sorted_nodes: Vec<NodeIndex> = get_weak_topological_order(fp_context.get_graph())
             .into_iter()
-            .flatten()
             .rev()
             .collect();
         let mut node_to_index = BTreeMap::new();
@@ -121,6 +138,7 @@ impl<T: Context> Computation<T> {
             }
         }
         Computation {
+            value_changes: vec![0; sorted_nodes.len()],
             fp_context,
             node_priority_list,
             priority_to_node_list: sorted_nodes,
@@ -146,10 +164,18 @@ impl<T: Context> Computation<T> {
     }
 
     /// Merge the value at a node with some new value.
+    /// If the value of the node already changed `WIDENING_DELAY` times, the values are widened instead.
     fn merge_node_value(&mut self, node: NodeIndex, value: T::NodeValue) {
         if let Some(old_value) = self.node_values.get(&node) {
-            let merged_value = self.fp_context.merge(&value, old_value);
+            perf_stats::increment(Counter::DomainJoins);
+            let merged_value = if self.value_changes[node.index()] >= WIDENING_DELAY {
+                perf_stats::increment(Counter::Widenings);
+                self.fp_context.widen(old_value, &value)
+            } else {
+                self.fp_context.merge(&value, old_value)
+            };
             if merged_value != *old_value {
+                self.value_changes[node.index()] += 1;
                 self.set_node_value(node, merged_value);
             }
         } else {
@@ -188,16 +214,83 @@ impl<T: Context> Computation<T> {
     /// Each node will be visited at most max_steps times.
     /// If a node does not stabilize after max_steps visits, the end result will not be a fixpoint but only an intermediate result of a fixpoint computation.
     pub fn compute_with_max_steps(&mut self, max_steps: u64) {
+        self.compute_with_max_steps_and_deadline(max_steps, None)
+    }
+
+    /// Compute the fixpoint of the fixpoint problem like `compute_with_max_steps`,
+    /// but stop the computation early if the given deadline is reached.
+    /// Nodes whose computation was not finished before the deadline remain in the worklist,
+    /// i.e. the computation is not marked as stabilized.
+    pub fn compute_with_max_steps_and_deadline(
+        &mut self,
+        max_steps: u64,
+        deadline: Option<std::time::Instant>,
+    ) {
+        match deadline {
+            Some(deadline) => {
+                let cancellation = CancellationToken::new().child_with_deadline(deadline);
+                self.compute_with_checkpoints(max_steps, Some(&cancellation), None)
+            }
+            None => self.compute_with_checkpoints(max_steps, None, None),
+        }
+    }
+
+    /// Compute the fixpoint of the fixpoint problem like `compute_with_max_steps`,
+    /// but stop the computation early if the given cancellation token gets cancelled.
+    /// Nodes whose computation was not finished before the cancellation remain in the worklist,
+    /// i.e. the computation is not marked as stabilized.
+    pub fn compute_with_max_steps_and_cancellation(
+        &mut self,
+        max_steps: u64,
+        cancellation: &CancellationToken,
+    ) {
+        self.compute_with_checkpoints(max_steps, Some(cancellation), None)
+    }
+
+    /// Compute the fixpoint of the fixpoint problem like `compute_with_max_steps_and_cancellation`.
+    /// If `checkpoint` is set, the given callback function is called periodically
+    /// after the given time interval has passed
+    /// so that the caller can save the intermediate state of the computation.
+    /// When the callback function is called, the worklist contains all nodes that are not yet stabilized.
+    pub fn compute_with_checkpoints(
+        &mut self,
+        max_steps: u64,
+        cancellation: Option<&CancellationToken>,
+        mut checkpoint: Option<(std::time::Duration, &mut dyn FnMut(&Self))>,
+    ) {
         let mut steps = vec![0; self.fp_context.get_graph().node_count()];
         let mut non_stabilized_nodes = BTreeSet::new();
+        let mut last_checkpoint = std::time::Instant::now();
         while let Some(priority) = self.worklist.iter().next_back().cloned() {
+            if let Some(cancellation) = cancellation {
+                if cancellation.is_cancelled() {
+                    non_stabilized_nodes.append(&mut self.worklist);
+                    break;
+                }
+            }
+            if let Some((interval, callback)) = checkpoint.as_mut() {
+                if last_checkpoint.elapsed() >= *interval {
+                    // Nodes that exceeded `max_steps` are temporarily added to the worklist,
+                    // so that the callback sees all non-stabilized nodes.
+                    let added_nodes: Vec<usize> = non_stabilized_nodes
+                        .iter()
+                        .filter(|priority| self.worklist.insert(**priority))
+                        .copied()
+                        .collect();
+                    callback(self);
+                    for priority in added_nodes {
+                        self.worklist.remove(&priority);
+                    }
+                    last_checkpoint = std::time::Instant::now();
+                }
+            }
             let priority = self.worklist.take(&priority).unwrap();
             let node = self.priority_to_node_list[priority];
             if steps[node.index()] < max_steps {
                 steps[node.index()] += 1;
                 self.update_node(node);
-            } else {
-                non_stabilized_nodes.insert(priority);
+            } else if non_stabilized_nodes.insert(priority) {
+                perf_stats::increment(Counter::UnstabilizedNodes);
             }
         }
         // After the algorithm finished, the new worklist is the list of non-stabilized nodes
@@ -214,6 +307,20 @@ impl<T: Context> Computation<T> {
         }
     }
 
+    /// Replace the node values and the worklist of the computation,
+    /// e.g. to resume a computation from a previously saved intermediate state.
+    pub fn restore_state(
+        &mut self,
+        node_values: FnvHashMap<NodeIndex, T::NodeValue>,
+        worklist: &[NodeIndex],
+    ) {
+        self.node_values = node_values;
+        self.worklist = worklist
+            .iter()
+            .map(|node| self.node_priority_list[node.index()])
+            .collect();
+    }
+
     /// Get a reference to the internal map where one can look up the current values of all nodes
     pub fn node_values(&self) -> &FnvHashMap<NodeIndex, T::NodeValue> {
         &self.node_values
@@ -229,6 +336,12 @@ impl<T: Context> Computation<T> {
         &self.fp_context
     }
 
+    /// Get a mutable reference to the underlying context object,
+    /// e.g. to change analysis parameters between two calls to the computation.
+    pub fn get_context_mut(&mut self) -> &mut T {
+        &mut self.fp_context
+    }
+
     /// Returns `True` if the computation has stabilized, i.e. the internal worklist is empty.
     pub fn has_stabilized(&self) -> bool {
         self.worklist.is_empty()
@@ -241,6 +354,72 @@ impl<T: Context> Computation<T> {
             .map(|priority| self.priority_to_node_list[*priority])
             .collect()
     }
+
+    /// Remove the values of all nodes that may still change, i.e. of the nodes in the worklist
+    /// and of all nodes reachable from them, and mark the computation as stabilized.
+    ///
+    /// The remaining node values are not changed by further steps of the computation.
+    /// Thus they are final, whereas the removed values were only intermediate results.
+    /// Returns the nodes whose values were removed.
+    pub fn discard_non_stabilized_nodes(&mut self) -> Vec<NodeIndex> {
+        let mut unstable_nodes = self.get_worklist();
+        let mut visited: HashSet<NodeIndex> = unstable_nodes.iter().copied().collect();
+        let mut stack = unstable_nodes.clone();
+        while let Some(node) = stack.pop() {
+            for neighbor in self.fp_context.get_graph().neighbors(node) {
+                if visited.insert(neighbor) {
+                    stack.push(neighbor);
+                    unstable_nodes.push(neighbor);
+                }
+            }
+        }
+        self.worklist.clear();
+        unstable_nodes
+            .into_iter()
+            .filter(|node| self.node_values.remove(node).is_some())
+            .collect()
+    }
+}
+
+/// Order the nodes of the graph for the worklist of a fixpoint computation.
+///
+/// The strongly connected components of the graph are ordered topologically,
+/// so that the values of a component are stabilized before they flow into later components.
+/// Within each component the nodes are ordered in reverse postorder of a depth-first search,
+/// so that, apart from the targets of back edges (e.g. loop heads), each node is evaluated after its predecessors.
+/// Compared to an arbitrary order within the components this considerably reduces
+/// the number of redundant re-evaluations of nodes in loops.
+fn get_weak_topological_order<N, E>(graph: &DiGraph<N, E>) -> Vec<NodeIndex> {
+    // Start the depth-first search at the nodes without predecessors
+    // and then at all nodes that were not reached from them.
+    let (roots, others): (Vec<NodeIndex>, Vec<NodeIndex>) =
+        graph.node_indices().partition(|node| {
+            graph
+                .neighbors_directed(*node, Direction::Incoming)
+                .next()
+                .is_none()
+        });
+    let mut postorder = Vec::with_capacity(graph.node_count());
+    let mut dfs = DfsPostOrder::empty(graph);
+    for start_node in roots.into_iter().chain(others) {
+        if !dfs.discovered.is_visited(&start_node) {
+            dfs.move_to(start_node);
+            while let Some(node) = dfs.next(graph) {
+                postorder.push(node);
+            }
+        }
+    }
+    let mut reverse_postorder_index = vec![0; graph.node_count()];
+    for (index, node) in postorder.iter().rev().enumerate() {
+        reverse_postorder_index[node.index()] = index;
+    }
+    // The strongly connected components are returned in reverse topological order.
+    let mut components = petgraph::algo::tarjan_scc(graph);
+    components.reverse();
+    for component in components.iter_mut() {
+        component.sort_unstable_by_key(|node| reverse_postorder_index[node.index()]);
+    }
+    components.into_iter().flatten().collect()
 }
 
 #[cfg(test)]
@@ -290,4 +469,185 @@ mod tests {
         assert_eq!(30, *solution.get_node_value(NodeIndex::new(9)).unwrap());
         assert_eq!(0, *solution.get_node_value(NodeIndex::new(5)).unwrap());
     }
+
+    /// Counts the iterations of a loop, where widening jumps to the maximal value.
+    struct WideningContext {
+        graph: DiGraph<(), u64>,
+    }
+
+    impl Context for WideningContext {
+        type EdgeLabel = u64;
+        type NodeLabel = ();
+        type NodeValue = u64;
+
+        fn get_graph(&self) -> &DiGraph<(), u64> {
+            &self.graph
+        }
+
+        fn merge(&self, val1: &Self::NodeValue, val2: &Self::NodeValue) -> Self::NodeValue {
+            std::cmp::max(*val1, *val2)
+        }
+
+        fn widen(&self, old_value: &Self::NodeValue, new_value: &Self::NodeValue) -> u64 {
+            if new_value > old_value {
+                u64::MAX
+            } else {
+                *old_value
+            }
+        }
+
+        fn update_edge(&self, value: &Self::NodeValue, edge: EdgeIndex) -> Option<Self::NodeValue> {
+            Some(value.saturating_add(*self.graph.edge_weight(edge).unwrap()))
+        }
+    }
+
+    #[test]
+    fn widening() {
+        let mut graph: DiGraph<(), u64> = DiGraph::new();
+        let entry = graph.add_node(());
+        let loop_head = graph.add_node(());
+        graph.add_edge(entry, loop_head, 0);
+        graph.add_edge(loop_head, loop_head, 1);
+
+        let mut solution = Computation::new(WideningContext { graph }, None);
+        solution.set_node_value(entry, 0);
+        solution.compute_with_max_steps(20);
+        assert!(solution.has_stabilized());
+        assert_eq!(*solution.get_node_value(loop_head).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn weak_topological_order() {
+        let mut graph: DiGraph<(), u64> = DiGraph::new();
+        let loop_body = graph.add_node(());
+        let exit = graph.add_node(());
+        let loop_head = graph.add_node(());
+        let entry = graph.add_node(());
+        graph.add_edge(entry, loop_head, 0);
+        graph.add_edge(loop_head, loop_body, 0);
+        graph.add_edge(loop_body, loop_head, 0);
+        graph.add_edge(loop_head, exit, 0);
+        assert_eq!(
+            get_weak_topological_order(&graph),
+            vec![entry, loop_head, loop_body, exit]
+        );
+    }
+
+    #[test]
+    fn fixpoint_with_expired_deadline() {
+        let mut graph: DiGraph<(), u64> = DiGraph::new();
+        for _i in 0..101 {
+            graph.add_node(());
+        }
+        for i in 0..100 {
+            graph.add_edge(NodeIndex::new(i), NodeIndex::new(i + 1), i as u64 % 10 + 1);
+        }
+
+        let mut solution = Computation::new(FPContext { graph }, None);
+        solution.set_node_value(NodeIndex::new(0), 0);
+        solution.compute_with_max_steps_and_deadline(20, Some(std::time::Instant::now()));
+
+        assert!(!solution.has_stabilized());
+        assert!(solution.get_node_value(NodeIndex::new(100)).is_none());
+    }
+
+    #[test]
+    fn fixpoint_with_cancellation() {
+        let mut graph: DiGraph<(), u64> = DiGraph::new();
+        for _i in 0..101 {
+            graph.add_node(());
+        }
+        for i in 0..100 {
+            graph.add_edge(NodeIndex::new(i), NodeIndex::new(i + 1), 1);
+        }
+
+        let cancellation = CancellationToken::new();
+        let mut solution = Computation::new(
+            FPContext {
+                graph: graph.clone(),
+            },
+            None,
+        );
+        solution.set_node_value(NodeIndex::new(0), 0);
+        solution.compute_with_max_steps_and_cancellation(20, &cancellation);
+        assert!(solution.has_stabilized());
+
+        cancellation.cancel();
+        let mut solution = Computation::new(FPContext { graph }, None);
+        solution.set_node_value(NodeIndex::new(0), 0);
+        solution.compute_with_max_steps_and_cancellation(20, &cancellation);
+        assert!(!solution.has_stabilized());
+        assert!(solution.get_node_value(NodeIndex::new(100)).is_none());
+    }
+
+    #[test]
+    fn discarding_non_stabilized_nodes() {
+        let mut graph: DiGraph<(), u64> = DiGraph::new();
+        for _i in 0..5 {
+            graph.add_node(());
+        }
+        graph.add_edge(NodeIndex::new(0), NodeIndex::new(1), 1);
+        graph.add_edge(NodeIndex::new(1), NodeIndex::new(2), 1);
+        graph.add_edge(NodeIndex::new(3), NodeIndex::new(4), 1);
+
+        let mut solution = Computation::new(FPContext { graph }, None);
+        solution.set_node_value(NodeIndex::new(0), 5);
+        solution.set_node_value(NodeIndex::new(3), 0);
+        solution.compute_with_max_steps(20);
+        solution.set_node_value(NodeIndex::new(0), 0);
+        let mut discarded_nodes = solution.discard_non_stabilized_nodes();
+        discarded_nodes.sort();
+        assert_eq!(
+            discarded_nodes,
+            vec![NodeIndex::new(0), NodeIndex::new(1), NodeIndex::new(2)]
+        );
+        assert!(solution.has_stabilized());
+        assert_eq!(1, *solution.get_node_value(NodeIndex::new(4)).unwrap());
+    }
+
+    #[test]
+    fn fixpoint_resumed_from_checkpoint() {
+        let mut graph: DiGraph<(), u64> = DiGraph::new();
+        for _i in 0..101 {
+            graph.add_node(());
+        }
+        for i in 0..100 {
+            graph.add_edge(NodeIndex::new(i), NodeIndex::new(i + 1), 1);
+        }
+
+        let mut solution = Computation::new(
+            FPContext {
+                graph: graph.clone(),
+            },
+            None,
+        );
+        solution.set_node_value(NodeIndex::new(0), 0);
+        let mut snapshot = None;
+        let mut save_snapshot = |computation: &Computation<FPContext>| {
+            if snapshot.is_none() {
+                snapshot = Some((
+                    computation.node_values().clone(),
+                    computation.get_worklist(),
+                ));
+            }
+        };
+        solution.compute_with_checkpoints(
+            20,
+            None,
+            Some((std::time::Duration::from_secs(0), &mut save_snapshot)),
+        );
+        let (node_values, worklist) = snapshot.unwrap();
+        assert!(!worklist.is_empty());
+
+        let mut resumed_solution = Computation::new(FPContext { graph }, None);
+        resumed_solution.restore_state(node_values, &worklist);
+        resumed_solution.compute_with_max_steps(20);
+        assert_eq!(
+            100,
+            *resumed_solution
+                .get_node_value(NodeIndex::new(100))
+                .unwrap()
+        );
+        assert_eq!(100, *solution.get_node_value(NodeIndex::new(100)).unwrap());
+    }
 }
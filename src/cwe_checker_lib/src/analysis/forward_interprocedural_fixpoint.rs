@@ -35,6 +35,13 @@ pub trait Context<'a> {
     /// Merge two node values.
     fn merge(&self, value1: &Self::Value, value2: &Self::Value) -> Self::Value;
 
+    /// Merge the new value `new_value` into the value `old_value` of a node that did not stabilize after several changes
+    /// (see [`fixpoint::Context::widen`](crate::analysis::fixpoint::Context::widen)).
+    /// The default implementation just merges the values.
+    fn widen(&self, old_value: &Self::Value, new_value: &Self::Value) -> Self::Value {
+        self.merge(new_value, old_value)
+    }
+
     /// Transition function for `Def` terms.
     /// The transition function for a basic block is computed
     /// by iteratively applying this function to the starting value for each `Def` term in the basic block.
@@ -100,6 +107,11 @@ impl<'a, T: Context<'a>> GeneralizedContext<'a, T> {
     pub fn get_context(&self) -> &T {
         &self.context
     }
+
+    /// Get a mutable reference to the interprocedural context object.
+    pub fn get_context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
 }
 
 impl<'a, T: Context<'a>> GeneralFPContext for GeneralizedContext<'a, T> {
@@ -114,26 +126,12 @@ impl<'a, T: Context<'a>> GeneralFPContext for GeneralizedContext<'a, T> {
 
     /// Merge two values using the merge function from the interprocedural context object.
     fn merge(&self, val1: &Self::NodeValue, val2: &Self::NodeValue) -> Self::NodeValue {
-        use NodeValue::*;
-        match (val1, val2) {
-            (Value(value1), Value(value2)) => Value(self.context.merge(value1, value2)),
-            (
-                CallFlowCombinator {
-                    call_stub: call1,
-                    interprocedural_flow: return1,
-                },
-                CallFlowCombinator {
-                    call_stub: call2,
-                    interprocedural_flow: return2,
-                },
-            ) => CallFlowCombinator {
-                call_stub: merge_option(call1, call2, |v1, v2| self.context.merge(v1, v2)),
-                interprocedural_flow: merge_option(return1, return2, |v1, v2| {
-                    self.context.merge(v1, v2)
-                }),
-            },
-            _ => panic!("Malformed CFG in fixpoint computation"),
-        }
+        merge_node_values(val1, val2, |v1, v2| self.context.merge(v1, v2))
+    }
+
+    /// Widen two values using the widen function from the interprocedural context object.
+    fn widen(&self, old_value: &Self::NodeValue, new_value: &Self::NodeValue) -> Self::NodeValue {
+        merge_node_values(old_value, new_value, |v1, v2| self.context.widen(v1, v2))
     }
 
     /// Forward edge transition function.
@@ -212,6 +210,32 @@ impl<'a, T: Context<'a>> GeneralFPContext for GeneralizedContext<'a, T> {
     }
 }
 
+/// Merge two node values using the given function to merge the contained values.
+fn merge_node_values<T: PartialEq + Eq + Clone>(
+    val1: &NodeValue<T>,
+    val2: &NodeValue<T>,
+    merge: impl Fn(&T, &T) -> T,
+) -> NodeValue<T> {
+    use NodeValue::*;
+    match (val1, val2) {
+        (Value(value1), Value(value2)) => Value(merge(value1, value2)),
+        (
+            CallFlowCombinator {
+                call_stub: call1,
+                interprocedural_flow: return1,
+            },
+            CallFlowCombinator {
+                call_stub: call2,
+                interprocedural_flow: return2,
+            },
+        ) => CallFlowCombinator {
+            call_stub: merge_option(call1, call2, &merge),
+            interprocedural_flow: merge_option(return1, return2, &merge),
+        },
+        _ => panic!("Malformed CFG in fixpoint computation"),
+    }
+}
+
 /// Generate a new computation from the corresponding context and an optional default value for nodes.
 pub fn create_computation<'a, T: Context<'a>>(
     problem: T,
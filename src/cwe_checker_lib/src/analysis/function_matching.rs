@@ -0,0 +1,387 @@
+//! Matching of the functions of two versions of a binary.
+//!
+//! Functions are identified by their addresses, which change between versions of a binary.
+//! To pair the functions of an old and a new version, a signature of each function is computed (see [`FunctionSignature`]).
+//! The functions are then matched in several rounds, each round using a different property of the signatures.
+//! In each round only functions not matched in previous rounds are considered
+//! and two functions are only matched if their property is unique among the remaining functions of both versions:
+//! 1. Functions with the same name, if the name was not generated by Ghidra (like `FUN_00101234` for stripped binaries).
+//! 2. Functions with the same normalized IR, i.e. functions whose instructions only differ in addresses and constants.
+//! 3. Functions with the same shape of the control flow graph, independent of the instructions in the basic blocks.
+//! 4. Functions calling the same set of named functions.
+//!
+//! The comparison of analysis runs (see [`crate::utils::diff`]) uses the matching
+//! to pair warnings in functions that changed between the two versions.
+
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::artifact::VersionedArtifact;
+use crate::utils::fingerprint::{
+    find_locations, fingerprint, get_function_names, get_location_key, get_structural_hash,
+    is_generated_name,
+};
+use crate::utils::log::CweWarning;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::hash::Hasher;
+
+/// The properties of a function used to match it with the functions of another version of the binary.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct FunctionSignature {
+    /// The name of the function.
+    pub name: String,
+    /// The address of the function.
+    pub address: String,
+    /// The hash of the normalized IR of the function (see [`crate::utils::fingerprint`]).
+    pub structural_hash: String,
+    /// The hash of the shape of the control flow graph of the function.
+    pub cfg_hash: String,
+    /// The sorted names of the functions called by the function.
+    /// Functions with names generated by Ghidra are not included.
+    pub callees: Vec<String>,
+    /// The fingerprints of the warnings located in the function.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warning_fingerprints: Vec<String>,
+}
+
+/// The signatures of all functions of a binary.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct FunctionSignatures {
+    /// The signatures in the order of the functions in the program.
+    pub functions: Vec<FunctionSignature>,
+}
+
+impl VersionedArtifact for FunctionSignatures {
+    const KIND: &'static str = "function_signatures";
+    const VERSION: u32 = 1;
+}
+
+impl FunctionSignatures {
+    /// Compute the signatures of all functions of the project.
+    ///
+    /// The fingerprints of the given warnings are added to the signatures of the functions containing them.
+    pub fn new(project: &Project, warnings: &[CweWarning]) -> FunctionSignatures {
+        let function_names = get_function_names(project);
+        let mut functions: Vec<FunctionSignature> = project
+            .program
+            .term
+            .subs
+            .iter()
+            .map(|sub| FunctionSignature {
+                name: sub.term.name.clone(),
+                address: sub.tid.address.clone(),
+                structural_hash: format!("{:016x}", get_structural_hash(sub, &function_names)),
+                cfg_hash: format!("{:016x}", get_cfg_hash(sub)),
+                callees: get_callee_names(sub, &function_names),
+                warning_fingerprints: Vec::new(),
+            })
+            .collect();
+        let function_indices: HashMap<&Tid, usize> = project
+            .program
+            .term
+            .subs
+            .iter()
+            .enumerate()
+            .map(|(index, sub)| (&sub.tid, index))
+            .collect();
+        let locations = find_locations(project, warnings);
+        for warning in warnings {
+            if let Some(location) = get_location_key(warning).and_then(|key| locations.get(key)) {
+                functions[function_indices[&location.sub.tid]]
+                    .warning_fingerprints
+                    .push(fingerprint(warning));
+            }
+        }
+        FunctionSignatures { functions }
+    }
+
+    /// Map the fingerprints of all warnings contained in the signatures
+    /// to the address of the function containing the warning.
+    pub fn get_warning_functions(&self) -> HashMap<&str, &str> {
+        self.functions
+            .iter()
+            .flat_map(|function| {
+                function
+                    .warning_fingerprints
+                    .iter()
+                    .map(move |warning_fingerprint| {
+                        (warning_fingerprint.as_str(), function.address.as_str())
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Compute a hash of the shape of the control flow graph of the function.
+///
+/// For each block the kinds of its jumps and the indices of their target blocks are hashed.
+/// Calls are hashed by their kind only, so that the hash does not depend on the call targets.
+fn get_cfg_hash(sub: &Term<Sub>) -> u64 {
+    let block_indices: HashMap<&Tid, usize> = sub
+        .term
+        .blocks
+        .iter()
+        .enumerate()
+        .map(|(index, block)| (&block.tid, index))
+        .collect();
+    let mut hasher = fnv::FnvHasher::default();
+    let hash_target = |hasher: &mut fnv::FnvHasher, target: Option<&Tid>| match target
+        .and_then(|target| block_indices.get(target))
+    {
+        Some(index) => hasher.write_usize(*index),
+        None => hasher.write_u8(0xff),
+    };
+    for block in sub.term.blocks.iter() {
+        hasher.write_u8(0);
+        for jmp in block.term.jmps.iter() {
+            match &jmp.term {
+                Jmp::Branch(target) => {
+                    hasher.write_u8(1);
+                    hash_target(&mut hasher, Some(target));
+                }
+                Jmp::CBranch { target, .. } => {
+                    hasher.write_u8(2);
+                    hash_target(&mut hasher, Some(target));
+                }
+                Jmp::BranchInd(_) => hasher.write_u8(3),
+                Jmp::Call { return_, .. }
+                | Jmp::CallInd { return_, .. }
+                | Jmp::CallOther { return_, .. } => {
+                    hasher.write_u8(4);
+                    hash_target(&mut hasher, return_.as_ref());
+                }
+                Jmp::Return(_) => hasher.write_u8(5),
+            }
+        }
+    }
+    hasher.finish()
+}
+
+/// Get the sorted names of the functions called by the function,
+/// excluding functions with names generated by Ghidra.
+fn get_callee_names(sub: &Term<Sub>, function_names: &HashMap<&Tid, &str>) -> Vec<String> {
+    let mut callees = BTreeSet::new();
+    for block in sub.term.blocks.iter() {
+        for jmp in block.term.jmps.iter() {
+            if let Jmp::Call { target, .. } = &jmp.term {
+                if let Some(name) = function_names.get(target) {
+                    if !is_generated_name(name) {
+                        callees.insert(name.to_string());
+                    }
+                }
+            }
+        }
+    }
+    callees.into_iter().collect()
+}
+
+/// The property of the function signatures used to match two functions.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMethod {
+    /// The functions have the same (non-generated) name.
+    Name,
+    /// The functions have the same normalized IR.
+    Structure,
+    /// The control flow graphs of the functions have the same shape.
+    ControlFlow,
+    /// The functions call the same named functions.
+    Callees,
+}
+
+impl MatchMethod {
+    /// The match methods in the order in which they are applied.
+    const ALL: [MatchMethod; 4] = [
+        MatchMethod::Name,
+        MatchMethod::Structure,
+        MatchMethod::ControlFlow,
+        MatchMethod::Callees,
+    ];
+
+    /// Get the property of the signature compared by the match method.
+    /// Returns `None` if the signature has no meaningful value for the property.
+    fn get_key(self, signature: &FunctionSignature) -> Option<String> {
+        match self {
+            MatchMethod::Name if is_generated_name(&signature.name) => None,
+            MatchMethod::Name => Some(signature.name.clone()),
+            MatchMethod::Structure => Some(signature.structural_hash.clone()),
+            MatchMethod::ControlFlow => Some(signature.cfg_hash.clone()),
+            MatchMethod::Callees if signature.callees.is_empty() => None,
+            MatchMethod::Callees => Some(signature.callees.join(",")),
+        }
+    }
+}
+
+/// A pair of matched functions.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct FunctionMatch {
+    /// The address of the function in the old version of the binary.
+    pub old: String,
+    /// The address of the function in the new version of the binary.
+    pub new: String,
+    /// The property that was used to match the functions.
+    pub method: MatchMethod,
+}
+
+/// The result of matching the functions of an old and a new version of a binary.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct FunctionMatching {
+    /// The matched pairs of functions.
+    pub matches: Vec<FunctionMatch>,
+    /// The addresses of the functions of the old version without a match.
+    pub unmatched_old: Vec<String>,
+    /// The addresses of the functions of the new version without a match.
+    pub unmatched_new: Vec<String>,
+}
+
+impl FunctionMatching {
+    /// Match the functions of the old and the new version of a binary.
+    pub fn new(old: &FunctionSignatures, new: &FunctionSignatures) -> FunctionMatching {
+        let mut unmatched_old: Vec<&FunctionSignature> = old.functions.iter().collect();
+        let mut unmatched_new: Vec<&FunctionSignature> = new.functions.iter().collect();
+        let mut matches = Vec::new();
+        for method in MatchMethod::ALL.iter() {
+            let old_keys = get_unique_keys(&unmatched_old, *method);
+            let new_keys = get_unique_keys(&unmatched_new, *method);
+            let mut matched_old = HashSet::new();
+            let mut matched_new = HashSet::new();
+            for old_function in unmatched_old.iter() {
+                let key = match method.get_key(old_function) {
+                    Some(key) if old_keys.contains_key(&key) => key,
+                    _ => continue,
+                };
+                if let Some(new_function) = new_keys.get(&key) {
+                    matches.push(FunctionMatch {
+                        old: old_function.address.clone(),
+                        new: new_function.address.clone(),
+                        method: *method,
+                    });
+                    matched_old.insert(old_function.address.as_str());
+                    matched_new.insert(new_function.address.as_str());
+                }
+            }
+            unmatched_old.retain(|function| !matched_old.contains(function.address.as_str()));
+            unmatched_new.retain(|function| !matched_new.contains(function.address.as_str()));
+        }
+        FunctionMatching {
+            matches,
+            unmatched_old: unmatched_old
+                .iter()
+                .map(|function| function.address.clone())
+                .collect(),
+            unmatched_new: unmatched_new
+                .iter()
+                .map(|function| function.address.clone())
+                .collect(),
+        }
+    }
+
+    /// Get the address of the function of the old version matched to the function of the new version at the given address.
+    pub fn get_old_function(&self, new_address: &str) -> Option<&str> {
+        self.matches
+            .iter()
+            .find(|function_match| function_match.new == new_address)
+            .map(|function_match| function_match.old.as_str())
+    }
+}
+
+/// Map the keys of the given match method that occur exactly once among the signatures
+/// to the corresponding signature.
+fn get_unique_keys<'a>(
+    signatures: &[&'a FunctionSignature],
+    method: MatchMethod,
+) -> HashMap<String, &'a FunctionSignature> {
+    let mut keys: HashMap<String, Option<&'a FunctionSignature>> = HashMap::new();
+    for signature in signatures {
+        if let Some(key) = method.get_key(signature) {
+            keys.entry(key)
+                .and_modify(|value| *value = None)
+                .or_insert(Some(*signature));
+        }
+    }
+    keys.into_iter()
+        .filter_map(|(key, signature)| signature.map(|signature| (key, signature)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signature(name: &str, address: &str, structure: &str, cfg: &str) -> FunctionSignature {
+        FunctionSignature {
+            name: name.to_string(),
+            address: address.to_string(),
+            structural_hash: structure.to_string(),
+            cfg_hash: cfg.to_string(),
+            callees: Vec::new(),
+            warning_fingerprints: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn function_matching() {
+        let old = FunctionSignatures {
+            functions: vec![
+                signature("main", "1000", "s1", "c1"),
+                signature("FUN_00001100", "1100", "s2", "c2"),
+                signature("FUN_00001200", "1200", "s3", "c3"),
+                signature("FUN_00001300", "1300", "s4", "c4"),
+                signature("FUN_00001400", "1400", "s5", "c4"),
+            ],
+        };
+        let new = FunctionSignatures {
+            functions: vec![
+                signature("main", "2000", "s6", "c6"),
+                signature("FUN_00002100", "2100", "s2", "c2"),
+                signature("FUN_00002200", "2200", "s7", "c3"),
+                signature("FUN_00002300", "2300", "s8", "c4"),
+                signature("FUN_00002400", "2400", "s9", "c4"),
+            ],
+        };
+        let matching = FunctionMatching::new(&old, &new);
+        assert_eq!(
+            matching.matches,
+            vec![
+                FunctionMatch {
+                    old: "1000".to_string(),
+                    new: "2000".to_string(),
+                    method: MatchMethod::Name,
+                },
+                FunctionMatch {
+                    old: "1100".to_string(),
+                    new: "2100".to_string(),
+                    method: MatchMethod::Structure,
+                },
+                FunctionMatch {
+                    old: "1200".to_string(),
+                    new: "2200".to_string(),
+                    method: MatchMethod::ControlFlow,
+                },
+            ]
+        );
+        // Functions with ambiguous properties are not matched.
+        assert_eq!(matching.unmatched_old, vec!["1300", "1400"]);
+        assert_eq!(matching.unmatched_new, vec!["2300", "2400"]);
+        assert_eq!(matching.get_old_function("2200"), Some("1200"));
+        assert_eq!(matching.get_old_function("2300"), None);
+    }
+
+    #[test]
+    fn signatures_of_project() {
+        let mut project = Project::mock_empty();
+        project.program = crate::analysis::call_graph::tests::mock_program();
+        let warnings = vec![CweWarning::new("CWE676", "0.1", "description")
+            .tids(vec![format!("{}", project.program.term.subs[0].tid)])];
+        let signatures = FunctionSignatures::new(&project, &warnings);
+        assert_eq!(signatures.functions.len(), 3);
+        assert_eq!(
+            signatures.functions[0].warning_fingerprints,
+            vec![fingerprint(&warnings[0])]
+        );
+        assert_eq!(signatures.get_warning_functions().len(), 1);
+        // The signatures of a project match themselves.
+        let matching = FunctionMatching::new(&signatures, &signatures);
+        assert_eq!(matching.matches.len(), 3);
+        assert!(matching.unmatched_old.is_empty());
+    }
+}
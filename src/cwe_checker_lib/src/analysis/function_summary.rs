@@ -0,0 +1,283 @@
+//! Per-function summaries of the analysis results.
+//!
+//! The summaries list properties of each function that are interesting for the selection of fuzzing targets,
+//! independently of whether a check generated a warning for the function:
+//! - the sinks (e.g. `strcpy` or `system`) that are reachable through (direct or resolved indirect) calls from the function,
+//! - whether the function reads untrusted input itself and whether its parameters may contain untrusted input,
+//! - the size of the largest stack buffer of the function.
+//!
+//! All properties are over-approximations computed on the call graph
+//! and (if available) the results of the pointer inference analysis.
+//! A function is assumed to receive tainted parameters
+//! if it is reachable through calls from a function that calls a taint source.
+//! The stack buffers of a function are approximated by the stack offsets whose address is computed in the function,
+//! each reaching up to the next accessed stack offset.
+
+use super::call_graph::{resolve_indirect_call_targets, CallGraph};
+use super::interprocedural_fixpoint_generic::NodeValue;
+use super::pointer_inference::{Data, PointerInference, State};
+use crate::abstract_domain::BitvectorDomain;
+use crate::analysis::graph::Node;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::artifact::VersionedArtifact;
+use crate::utils::binary::RuntimeMemoryImage;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// The configuration of the function summaries.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct Config {
+    /// Names of extern functions reading untrusted input.
+    pub taint_sources: Vec<String>,
+    /// Names of extern functions that are dangerous to call with untrusted input, e.g. `strcpy` or `system`.
+    pub sinks: Vec<String>,
+}
+
+/// The summary of the analysis results for a single function.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct FunctionSummary {
+    /// The name of the function.
+    pub name: String,
+    /// The address of the function.
+    pub address: String,
+    /// Names of the sinks that are reachable through calls from the function.
+    pub reachable_sinks: Vec<String>,
+    /// Whether the function directly calls a taint source.
+    pub reads_untrusted_input: bool,
+    /// Whether the parameters of the function may contain untrusted input,
+    /// i.e. whether the function is reachable through calls from a function reading untrusted input.
+    pub tainted_parameters: bool,
+    /// The size in bytes of the largest stack buffer of the function.
+    /// `None` if no stack buffer was found or if the pointer inference analysis was not computed.
+    pub max_stack_buffer_size: Option<u64>,
+}
+
+/// The summaries of all functions of a program.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct FunctionSummaries {
+    /// The summaries in the order of the functions in the program.
+    pub functions: Vec<FunctionSummary>,
+}
+
+impl VersionedArtifact for FunctionSummaries {
+    const KIND: &'static str = "function_summaries";
+    const VERSION: u32 = 1;
+}
+
+impl FunctionSummaries {
+    /// Compute the summaries of all functions of the project.
+    ///
+    /// If the results of the pointer inference analysis are given,
+    /// they are used to resolve indirect calls and to compute the sizes of stack buffers.
+    pub fn new(
+        project: &Project,
+        pointer_inference: Option<&PointerInference>,
+        runtime_memory_image: &RuntimeMemoryImage,
+        config: &Config,
+    ) -> FunctionSummaries {
+        let program = &project.program.term;
+        let indirect_call_targets = match pointer_inference {
+            Some(pointer_inference) => {
+                resolve_indirect_call_targets(&project.program, pointer_inference)
+            }
+            None => HashMap::new(),
+        };
+        let call_graph = CallGraph::with_indirect_calls(&project.program, &indirect_call_targets);
+        let symbol_names: HashMap<&Tid, &str> = program
+            .extern_symbols
+            .iter()
+            .map(|symbol| (&symbol.tid, symbol.name.as_str()))
+            .collect();
+        let taint_source_tids: HashSet<&Tid> = program
+            .extern_symbols
+            .iter()
+            .filter(|symbol| config.taint_sources.contains(&symbol.name))
+            .map(|symbol| &symbol.tid)
+            .collect();
+        let reading_functions: Vec<&Term<Sub>> = program
+            .subs
+            .iter()
+            .filter(|sub| calls_any_of(sub, &taint_source_tids))
+            .collect();
+        let mut tainted_functions = HashSet::new();
+        for sub in reading_functions.iter() {
+            tainted_functions.extend(
+                call_graph
+                    .get_reachable_functions(&[sub.tid.clone()])
+                    .into_iter()
+                    .filter(|tid| *tid != sub.tid),
+            );
+        }
+        let stack_offsets = match pointer_inference {
+            Some(pointer_inference) => collect_stack_offsets(
+                pointer_inference,
+                &project.stack_pointer_register,
+                runtime_memory_image,
+            ),
+            None => HashMap::new(),
+        };
+
+        let functions = program
+            .subs
+            .iter()
+            .map(|sub| {
+                let mut reachable_sinks: Vec<String> = call_graph
+                    .get_reachable_functions(&[sub.tid.clone()])
+                    .iter()
+                    .filter_map(|tid| symbol_names.get(tid).copied())
+                    .filter(|name| config.sinks.iter().any(|sink| sink == name))
+                    .map(|name| name.to_string())
+                    .collect();
+                reachable_sinks.sort();
+                FunctionSummary {
+                    name: sub.term.name.clone(),
+                    address: sub.tid.address.clone(),
+                    reachable_sinks,
+                    reads_untrusted_input: reading_functions
+                        .iter()
+                        .any(|reading_sub| reading_sub.tid == sub.tid),
+                    tainted_parameters: tainted_functions.contains(&sub.tid),
+                    max_stack_buffer_size: stack_offsets
+                        .get(&sub.tid)
+                        .and_then(StackOffsets::get_max_buffer_size),
+                }
+            })
+            .collect();
+        FunctionSummaries { functions }
+    }
+
+    /// Get the summary of the function with the given name.
+    pub fn get(&self, name: &str) -> Option<&FunctionSummary> {
+        self.functions.iter().find(|summary| summary.name == name)
+    }
+}
+
+/// Returns `true` if the function directly calls one of the functions with the given TIDs.
+fn calls_any_of(sub: &Term<Sub>, targets: &HashSet<&Tid>) -> bool {
+    sub.term.blocks.iter().any(|block| {
+        block.term.jmps.iter().any(|jmp| match &jmp.term {
+            Jmp::Call { target, .. } => targets.contains(target),
+            _ => false,
+        })
+    })
+}
+
+/// The exactly known offsets into the stack frame of a function that are used by the function.
+/// The offsets are relative to the stack pointer on function entry.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+struct StackOffsets {
+    /// Offsets accessed by load or store instructions.
+    accessed: BTreeSet<i64>,
+    /// Offsets whose address is computed and assigned to a register, e.g. to pass it to another function.
+    address_taken: BTreeSet<i64>,
+}
+
+impl StackOffsets {
+    /// Get the size of the largest stack buffer.
+    ///
+    /// Each offset of the local stack frame whose address is taken is assumed to be the start of a buffer
+    /// reaching up to the next accessed or address-taken offset (or the stack pointer on function entry).
+    fn get_max_buffer_size(&self) -> Option<u64> {
+        self.address_taken
+            .iter()
+            .filter(|offset| **offset < 0)
+            .map(|offset| {
+                let next_offset = self
+                    .accessed
+                    .range(offset + 1..)
+                    .chain(self.address_taken.range(offset + 1..))
+                    .min()
+                    .map_or(0, |next_offset| (*next_offset).min(0));
+                (next_offset - offset) as u64
+            })
+            .max()
+    }
+}
+
+/// Collect the used offsets into the stack frames of all functions from the results of the pointer inference analysis.
+fn collect_stack_offsets(
+    pointer_inference: &PointerInference,
+    stack_pointer: &Variable,
+    runtime_memory_image: &RuntimeMemoryImage,
+) -> HashMap<Tid, StackOffsets> {
+    let mut stack_offsets: HashMap<Tid, StackOffsets> = HashMap::new();
+    let graph = pointer_inference.get_graph();
+    for node in graph.node_indices() {
+        let (block, sub, state) = match (graph[node], pointer_inference.get_node_value(node)) {
+            (Node::BlkStart(block, sub), Some(NodeValue::Value(state))) => (block, sub, state),
+            _ => continue,
+        };
+        let offsets = stack_offsets.entry(sub.tid.clone()).or_default();
+        let mut state = state.clone();
+        for def in block.term.defs.iter() {
+            match &def.term {
+                Def::Load { var, address } => {
+                    offsets.accessed.extend(get_stack_offset(&state, address));
+                    let _ = state.handle_load(var, address, runtime_memory_image);
+                }
+                Def::Store { address, value } => {
+                    offsets.accessed.extend(get_stack_offset(&state, address));
+                    let _ = state.handle_store(address, value, runtime_memory_image);
+                }
+                Def::Assign { var, value } => {
+                    // Copies of the stack pointer and adjustments of the stack pointer itself are no address computations.
+                    if !matches!(value, Expression::Var(_)) && var != stack_pointer {
+                        offsets
+                            .address_taken
+                            .extend(get_stack_offset(&state, value));
+                    }
+                    let _ = state.handle_register_assign(var, value);
+                }
+            }
+        }
+    }
+    stack_offsets
+}
+
+/// If the expression evaluates to a pointer with exactly known offset into the current stack frame, return the offset.
+fn get_stack_offset(state: &State, expression: &Expression) -> Option<i64> {
+    if let Ok(Data::Pointer(pointer)) = state.eval(expression) {
+        if let Some(BitvectorDomain::Value(offset)) = pointer.targets().get(&state.stack_id) {
+            return offset.try_to_i64().ok();
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::call_graph::tests::mock_program;
+
+    #[test]
+    fn summaries_without_pointer_inference() {
+        let mut project = Project::mock_empty();
+        project.program = mock_program();
+        let config = Config {
+            taint_sources: vec!["mock_symbol".to_string()],
+            sinks: vec!["mock_symbol".to_string()],
+        };
+        let summaries =
+            FunctionSummaries::new(&project, None, &RuntimeMemoryImage::mock(), &config);
+        assert_eq!(summaries.functions.len(), 3);
+        let main = summaries.get("main").unwrap();
+        assert_eq!(main.reachable_sinks, vec!["mock_symbol".to_string()]);
+        assert!(!main.reads_untrusted_input);
+        assert!(!main.tainted_parameters);
+        assert_eq!(main.max_stack_buffer_size, None);
+        let func = summaries.get("func").unwrap();
+        assert!(func.reads_untrusted_input);
+        assert!(!func.tainted_parameters);
+        assert!(summaries.get("unused").unwrap().reachable_sinks.is_empty());
+    }
+
+    #[test]
+    fn stack_buffer_sizes() {
+        let offsets = StackOffsets {
+            accessed: vec![-0x118, -0x10, -8].into_iter().collect(),
+            address_taken: vec![-0x110, -0x10, 8].into_iter().collect(),
+        };
+        assert_eq!(offsets.get_max_buffer_size(), Some(0x100));
+        assert_eq!(StackOffsets::default().get_max_buffer_size(), None);
+    }
+}
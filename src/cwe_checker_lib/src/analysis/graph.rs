@@ -42,6 +42,7 @@
 //! The artificial *CallReturn* nodes enable enriching the information flowing through a return edge
 //! with information recovered from the corresponding callsite during a fixpoint computation.
 
+use crate::analysis::concurrency::is_atomic_call_other;
 use crate::intermediate_representation::*;
 use crate::prelude::*;
 use petgraph::graph::{DiGraph, NodeIndex};
@@ -89,6 +90,22 @@ impl<'a> Node<'a> {
             }
         }
     }
+
+    /// Get the function containing the node.
+    /// For `CallReturn` and `CallSource` nodes this is the calling function.
+    pub fn get_sub(&self) -> &'a Term<Sub> {
+        use Node::*;
+        match self {
+            BlkStart(_blk, sub) | BlkEnd(_blk, sub) => sub,
+            CallReturn {
+                call: (_blk, sub), ..
+            }
+            | CallSource {
+                source: (_blk, sub),
+                ..
+            } => sub,
+        }
+    }
 }
 
 impl<'a> std::fmt::Display for Node<'a> {
@@ -306,6 +323,24 @@ impl<'a> GraphBuilder<'a> {
                         .add_edge(source, return_to_node, Edge::ExternCallStub(jump));
                 }
             }
+            Jmp::CallOther {
+                description: _,
+                return_: Some(return_tid),
+            } if is_atomic_call_other(&jump.term) => {
+                // Atomic markers and memory barriers have no side effects
+                // besides the surrounding instructions, so we treat them as fall-through jumps.
+                let target_node = if let Some((target_node, _)) = self
+                    .jump_targets
+                    .get(&(return_tid.clone(), sub_term.tid.clone()))
+                {
+                    *target_node
+                } else {
+                    let target_block = self.program.term.find_block(return_tid).unwrap();
+                    self.add_block(target_block, sub_term).0
+                };
+                self.graph
+                    .add_edge(source, target_node, Edge::Jump(jump, untaken_conditional));
+            }
             Jmp::CallOther {
                 description: _,
                 return_: _,
@@ -419,6 +454,79 @@ pub fn get_program_cfg(program: &Term<Program>, extern_subs: HashSet<Tid>) -> Gr
     builder.build()
 }
 
+/// Get the function that a node belongs to.
+/// For `CallReturn` and `CallSource` nodes this is the calling function.
+fn get_node_sub<'a>(node: &Node<'a>) -> &'a Term<Sub> {
+    match node {
+        Node::BlkStart(_, sub) | Node::BlkEnd(_, sub) => sub,
+        Node::CallReturn { call, .. } => call.1,
+        Node::CallSource { source, .. } => source.1,
+    }
+}
+
+/// Get the label of an edge for the DOT export, consisting of the edge kind and the TID of the corresponding jump.
+fn get_edge_label(edge: &Edge) -> String {
+    match edge {
+        Edge::Block => "Block".to_string(),
+        Edge::Jump(jump, _) => format!("Jump {}", jump.tid),
+        Edge::Call(call) => format!("Call {}", call.tid),
+        Edge::ExternCallStub(call) => format!("ExternCallStub {}", call.tid),
+        Edge::CRCallStub => "CRCallStub".to_string(),
+        Edge::CRReturnStub => "CRReturnStub".to_string(),
+        Edge::CallCombine(call) => format!("CallCombine {}", call.tid),
+        Edge::ReturnCombine(call) => format!("ReturnCombine {}", call.tid),
+    }
+}
+
+/// Export the control flow graph in the DOT format of Graphviz.
+///
+/// If a function name or TID is given, only the nodes belonging to this function
+/// and the edges between them are exported.
+/// Nodes are labeled with their block TIDs and edges with their kind and the TID of the corresponding jump.
+/// Returns an error if the given function is not contained in the graph.
+pub fn export_dot(graph: &Graph, function: Option<&str>) -> Result<String, Error> {
+    let is_exported = |node: NodeIndex| match function {
+        Some(function) => {
+            let sub = get_node_sub(&graph[node]);
+            sub.term.name == function || sub.tid.to_string() == function
+        }
+        None => true,
+    };
+    let nodes: Vec<NodeIndex> = graph
+        .node_indices()
+        .filter(|node| is_exported(*node))
+        .collect();
+    if nodes.is_empty() {
+        if let Some(function) = function {
+            return Err(anyhow!(
+                "Function {} not found in the control flow graph",
+                function
+            ));
+        }
+    }
+    let mut dot = String::from("digraph cfg {\n    node [shape=box];\n");
+    for node in nodes {
+        dot.push_str(&format!(
+            "    n{} [label=\"{}\"];\n",
+            node.index(),
+            graph[node].to_string().replace('"', "\\\"")
+        ));
+    }
+    for edge in graph.edge_indices() {
+        let (start, end) = graph.edge_endpoints(edge).unwrap();
+        if is_exported(start) && is_exported(end) {
+            dot.push_str(&format!(
+                "    n{} -> n{} [label=\"{}\"];\n",
+                start.index(),
+                end.index(),
+                get_edge_label(&graph[edge]).replace('"', "\\\"")
+            ));
+        }
+    }
+    dot.push_str("}\n");
+    Ok(dot)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -514,4 +622,56 @@ mod tests {
         assert_eq!(graph.node_count(), 16);
         assert_eq!(graph.edge_count(), 20);
     }
+
+    #[test]
+    fn dot_export() {
+        let program = mock_program();
+        let graph = get_program_cfg(&program, HashSet::new());
+        let dot = export_dot(&graph, None).unwrap();
+        assert!(dot.starts_with("digraph cfg {"));
+        assert_eq!(dot.matches(" -> ").count(), 20);
+        assert!(dot.contains("[label=\"Call call\"]"));
+
+        let dot = export_dot(&graph, Some("sub2")).unwrap();
+        assert!(dot.contains("BlkStart @ sub2_blk1"));
+        assert!(!dot.contains("BlkStart @ sub1_blk1"));
+        assert!(dot.contains("[label=\"Block\"]"));
+        assert!(export_dot(&graph, Some("unknown")).is_err());
+    }
+
+    #[test]
+    fn atomic_call_other_fall_through() {
+        let lock_term = Term {
+            tid: Tid::new("lock"),
+            term: Jmp::CallOther {
+                description: "LOCK".to_string(),
+                return_: Some(Tid::new("blk2")),
+            },
+        };
+        let blk1 = Term {
+            tid: Tid::new("blk1"),
+            term: Blk {
+                defs: Vec::new(),
+                jmps: vec![lock_term],
+            },
+        };
+        let blk2 = Term {
+            tid: Tid::new("blk2"),
+            term: Blk {
+                defs: Vec::new(),
+                jmps: Vec::new(),
+            },
+        };
+        let mut sub = Sub::mock("sub");
+        sub.term.blocks = vec![blk1, blk2];
+        let mut program = Program::mock_empty();
+        program.subs = vec![sub];
+        let program = Term {
+            tid: Tid::new("program"),
+            term: program,
+        };
+        let graph = get_program_cfg(&program, HashSet::new());
+        assert_eq!(graph.node_count(), 4);
+        assert_eq!(graph.edge_count(), 3);
+    }
 }
@@ -1,6 +1,16 @@
+pub mod attack_surface;
 pub mod backward_interprocedural_fixpoint;
+pub mod block_dataflow;
+pub mod call_graph;
+pub mod concurrency;
+pub mod emulation;
+pub mod fact_export;
 pub mod fixpoint;
 pub mod forward_interprocedural_fixpoint;
+pub mod function_matching;
+pub mod function_summary;
 pub mod graph;
 pub mod interprocedural_fixpoint_generic;
 pub mod pointer_inference;
+pub mod resource_tracking;
+pub mod struct_layout;
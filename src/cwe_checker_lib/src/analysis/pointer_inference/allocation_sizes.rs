@@ -0,0 +1,231 @@
+//! Sizes of heap objects at their allocation sites.
+//!
+//! For each call to an allocation function the possible sizes (in bytes) of the allocated object
+//! are computed from the values of the size parameters in the state before the call.
+//! The size of objects allocated by `calloc` is the product of the number of elements and the element size.
+//! For calls to `realloc` the second parameter determines the size of the resized object
+//! and the objects that may get resized by the call are recorded,
+//! so that checks can compare the new size with the sizes of the original objects.
+//!
+//! Checks can query the size of a heap object through [`AnalysisResults::get_allocation_size`](crate::AnalysisResults::get_allocation_size).
+
+use super::{Data, PointerInference};
+use crate::abstract_domain::{AbstractIdentifier, BitvectorDomain};
+use crate::analysis::graph::Edge;
+use crate::analysis::interprocedural_fixpoint_generic::NodeValue;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use petgraph::visit::EdgeRef;
+use std::collections::HashMap;
+
+/// The possible sizes in bytes of the heap objects allocated at an allocation site.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct AllocationSize {
+    /// A lower bound of the size.
+    pub min: u64,
+    /// An upper bound of the size or `None` if the size is not bounded.
+    pub max: Option<u64>,
+    /// The heap objects that may get resized by the allocation, e.g. for calls to `realloc`.
+    pub resized_objects: Vec<AbstractIdentifier>,
+}
+
+impl AllocationSize {
+    /// Create an allocation size with the given bounds.
+    pub fn new(min: u64, max: Option<u64>) -> AllocationSize {
+        AllocationSize {
+            min,
+            max,
+            resized_objects: Vec::new(),
+        }
+    }
+
+    /// Create an allocation size about which nothing is known.
+    pub fn unknown() -> AllocationSize {
+        AllocationSize::new(0, None)
+    }
+
+    /// Get the size if it is exactly known.
+    pub fn get_exact_size(&self) -> Option<u64> {
+        match self.max {
+            Some(max) if max == self.min => Some(max),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the object may be smaller than the given number of bytes.
+    pub fn may_be_smaller_than(&self, size: u64) -> bool {
+        self.min < size
+    }
+
+    /// Returns `true` if the object may be larger than the given number of bytes.
+    pub fn may_be_larger_than(&self, size: u64) -> bool {
+        self.max.map(|max| max > size).unwrap_or(true)
+    }
+
+    /// Compute the possible sizes of a buffer with `self` elements of size `element_size`.
+    /// If the size may exceed the maximal value of the given bytesize, the result is unknown.
+    fn multiply(&self, element_size: &AllocationSize, bytesize: ByteSize) -> AllocationSize {
+        let max = self
+            .max
+            .zip(element_size.max)
+            .and_then(|(max, element_max)| max.checked_mul(element_max))
+            .filter(|max| *max <= get_max_value(bytesize));
+        match max {
+            Some(max) => AllocationSize::new(self.min * element_size.min, Some(max)),
+            None => AllocationSize::unknown(),
+        }
+    }
+
+    /// Merge with the sizes of another allocation.
+    fn merge(&self, other: &AllocationSize) -> AllocationSize {
+        let mut resized_objects = self.resized_objects.clone();
+        for id in other.resized_objects.iter() {
+            if !resized_objects.contains(id) {
+                resized_objects.push(id.clone());
+            }
+        }
+        resized_objects.sort();
+        AllocationSize {
+            min: self.min.min(other.min),
+            max: self
+                .max
+                .zip(other.max)
+                .map(|(max, other_max)| max.max(other_max)),
+            resized_objects,
+        }
+    }
+}
+
+impl std::fmt::Display for AllocationSize {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match (self.get_exact_size(), self.max) {
+            (Some(size), _) => write!(formatter, "{}", size),
+            (None, Some(max)) => write!(formatter, "[{}, {}]", self.min, max),
+            (None, None) => write!(formatter, "[{}, unbounded]", self.min),
+        }
+    }
+}
+
+/// Compute the sizes of the heap objects allocated at each call to an allocation function.
+///
+/// Returns a map from the TIDs of the calls to the sizes of the allocated objects.
+pub(super) fn compute_allocation_sizes(
+    pointer_inference: &PointerInference,
+) -> HashMap<Tid, AllocationSize> {
+    let context = pointer_inference.get_context();
+    let graph = pointer_inference.get_graph();
+    let mut allocation_sizes = HashMap::new();
+    for edge in graph.edge_references() {
+        if let Edge::ExternCallStub(call) = edge.weight() {
+            if let Jmp::Call { target, .. } = &call.term {
+                let extern_symbol = match context.extern_symbol_map.get(target) {
+                    Some(symbol) if context.allocation_symbols.contains(&symbol.name) => symbol,
+                    _ => continue,
+                };
+                let state = match pointer_inference.get_node_value(edge.source()) {
+                    Some(NodeValue::Value(state)) => state,
+                    _ => continue,
+                };
+                let size_bytesize = context.project.get_pointer_bytesize();
+                let parameter_size = |index: usize| {
+                    state
+                        .eval_nth_parameter(
+                            context.project,
+                            extern_symbol,
+                            index,
+                            context.runtime_memory_image,
+                        )
+                        .map(|value| get_size_bounds(&value))
+                        .unwrap_or_else(|_| AllocationSize::unknown())
+                };
+                let size = match extern_symbol.name.as_str() {
+                    "calloc" => parameter_size(0).multiply(&parameter_size(1), size_bytesize),
+                    "realloc" => {
+                        let mut size = parameter_size(1);
+                        if let Ok(Data::Pointer(pointer)) = state.eval_nth_parameter(
+                            context.project,
+                            extern_symbol,
+                            0,
+                            context.runtime_memory_image,
+                        ) {
+                            size.resized_objects = pointer.ids().cloned().collect();
+                        }
+                        size
+                    }
+                    _ => parameter_size(0),
+                };
+                let size = match allocation_sizes.get(&call.tid) {
+                    Some(other_size) => size.merge(other_size),
+                    None => size,
+                };
+                allocation_sizes.insert(call.tid.clone(), size);
+            }
+        }
+    }
+    allocation_sizes
+}
+
+/// Get the bounds of a size parameter.
+/// Sizes that may be negative when interpreted as signed integers are unknown.
+fn get_size_bounds(value: &Data) -> AllocationSize {
+    match value {
+        Data::Value(BitvectorDomain::Value(size)) => match size.try_to_u64() {
+            Ok(size) => AllocationSize::new(size, Some(size)),
+            Err(_) => AllocationSize::unknown(),
+        },
+        Data::Value(BitvectorDomain::Interval(interval)) if interval.start() >= 0 => {
+            AllocationSize::new(interval.start() as u64, Some(interval.end() as u64))
+        }
+        _ => AllocationSize::unknown(),
+    }
+}
+
+/// Get the maximal unsigned value of the given bytesize.
+fn get_max_value(bytesize: ByteSize) -> u64 {
+    match bytesize.as_bit_length() {
+        bits if bits >= 64 => u64::MAX,
+        bits => (1u64 << bits) - 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abstract_domain::StridedInterval;
+
+    #[test]
+    fn allocation_size_bounds() {
+        let exact = get_size_bounds(&Data::Value(Bitvector::from_u64(16).into()));
+        assert_eq!(exact.get_exact_size(), Some(16));
+        let interval = get_size_bounds(&Data::Value(BitvectorDomain::Interval(
+            StridedInterval::new(ByteSize::new(8), 8, 64, 8).unwrap(),
+        )));
+        assert_eq!(interval, AllocationSize::new(8, Some(64)));
+        assert!(interval.may_be_smaller_than(16));
+        assert!(!interval.may_be_larger_than(64));
+        assert_eq!(
+            get_size_bounds(&Data::Value(BitvectorDomain::Interval(
+                StridedInterval::new(ByteSize::new(8), -1, 64, 1).unwrap(),
+            ))),
+            AllocationSize::unknown()
+        );
+
+        let product = interval.multiply(&exact, ByteSize::new(8));
+        assert_eq!(product, AllocationSize::new(128, Some(1024)));
+        assert_eq!(
+            interval.multiply(&AllocationSize::new(0, Some(u64::MAX)), ByteSize::new(8)),
+            AllocationSize::unknown()
+        );
+        assert_eq!(
+            AllocationSize::new(1 << 20, Some(1 << 20)).multiply(
+                &AllocationSize::new(1 << 20, Some(1 << 20)),
+                ByteSize::new(4)
+            ),
+            AllocationSize::unknown()
+        );
+
+        let merged = exact.merge(&AllocationSize::unknown());
+        assert_eq!(merged, AllocationSize::unknown());
+        assert_eq!(format!("{}", product), "[128, 1024]");
+    }
+}
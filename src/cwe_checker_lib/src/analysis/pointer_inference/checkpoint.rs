@@ -0,0 +1,188 @@
+//! Save and restore intermediate states of the pointer inference computation.
+//!
+//! For big binaries the pointer inference analysis may run for several hours.
+//! To not lose all progress on an interruption,
+//! the state of the fixpoint computation can be saved to disk periodically
+//! and a later run of the analysis can resume the computation from the saved checkpoint.
+//!
+//! A checkpoint is only valid for the same binary, Ghidra project and analysis version.
+//! Since the control flow graph is not saved in the checkpoint,
+//! we check that at least the number of nodes of the graph and the analysis version match.
+//! Checkpoint files are stamped with a format version (see [`crate::utils::artifact`]),
+//! so that checkpoints of older formats can be upgraded or rejected on load.
+
+use super::{State, VERSION};
+use crate::analysis::fixpoint::{Computation, Context};
+use crate::analysis::interprocedural_fixpoint_generic::NodeValue;
+use crate::prelude::*;
+use crate::utils::artifact::{read_artifact, write_artifact, VersionedArtifact};
+use fnv::FnvHashMap;
+use petgraph::graph::NodeIndex;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Configuration for saving checkpoints of the pointer inference computation.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct CheckpointConfig {
+    /// The file that checkpoints are written to.
+    /// If the file already exists when the analysis starts,
+    /// the computation is resumed from the checkpoint contained in it.
+    pub path: PathBuf,
+    /// The time between two checkpoints.
+    pub interval: Duration,
+}
+
+/// A saved intermediate state of the pointer inference fixpoint computation.
+///
+/// The type parameter is the type of the saved node values,
+/// so that checkpoints can be saved without cloning the node values.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Checkpoint<V> {
+    /// The version of the pointer inference analysis that generated the checkpoint.
+    version: String,
+    /// The number of nodes in the control flow graph.
+    node_count: usize,
+    /// The index of the computation phase that was running when the checkpoint was generated.
+    phase: usize,
+    /// The node values, indexed by the node index in the control flow graph.
+    node_values: Vec<(usize, V)>,
+    /// The node indices of all nodes that are not yet stabilized.
+    worklist: Vec<usize>,
+}
+
+impl<V> VersionedArtifact for Checkpoint<V> {
+    const KIND: &'static str = "pointer_inference_checkpoint";
+    const VERSION: u32 = 2;
+
+    /// Checkpoints written before the introduction of version stamps have the same format as version 1.
+    /// Version 2 added the shared caller stack IDs to the states.
+    /// Since the field defaults to an empty map, version 1 checkpoints can be read without changes.
+    fn upgrade(version: u32, content: serde_json::Value) -> Result<serde_json::Value, Error> {
+        match version {
+            0 | 1 => Ok(content),
+            _ => Err(anyhow!("Unknown checkpoint format version {}", version)),
+        }
+    }
+}
+
+impl<'a> Checkpoint<&'a NodeValue<State>> {
+    /// Generate a checkpoint referencing the current node values of the computation.
+    pub fn new<T>(computation: &'a Computation<T>, phase: usize) -> Self
+    where
+        T: Context<NodeValue = NodeValue<State>>,
+    {
+        Checkpoint {
+            version: VERSION.to_string(),
+            node_count: computation.get_graph().node_count(),
+            phase,
+            node_values: computation
+                .node_values()
+                .iter()
+                .map(|(node, value)| (node.index(), value))
+                .collect(),
+            worklist: computation
+                .get_worklist()
+                .into_iter()
+                .map(|node| node.index())
+                .collect(),
+        }
+    }
+
+    /// Write the checkpoint to the given file.
+    ///
+    /// The checkpoint is first written to a temporary file which then replaces the target file,
+    /// so that an interruption during writing does not corrupt an older checkpoint.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+        let temp_path = PathBuf::from(temp_path);
+        let file = std::io::BufWriter::new(std::fs::File::create(&temp_path)?);
+        write_artifact(self, file)?;
+        std::fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+}
+
+impl Checkpoint<NodeValue<State>> {
+    /// Read a checkpoint from the given file.
+    /// Checkpoints in older formats are upgraded to the current format.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        read_artifact(file)
+    }
+
+    /// Restore the state of the computation from the checkpoint.
+    /// Returns the index of the computation phase to resume.
+    ///
+    /// Returns an error if the checkpoint was not generated for the same control flow graph
+    /// or by a different version of the analysis.
+    pub fn restore<T>(self, computation: &mut Computation<T>) -> Result<usize, Error>
+    where
+        T: Context<NodeValue = NodeValue<State>>,
+    {
+        if self.version != VERSION {
+            return Err(anyhow!(
+                "Checkpoint was generated by analysis version {}",
+                self.version
+            ));
+        }
+        let node_count = computation.get_graph().node_count();
+        if self.node_count != node_count {
+            return Err(anyhow!(
+                "Checkpoint does not match the control flow graph of the binary"
+            ));
+        }
+        if self
+            .node_values
+            .iter()
+            .map(|(index, _)| index)
+            .chain(self.worklist.iter())
+            .any(|index| *index >= node_count)
+        {
+            return Err(anyhow!("Checkpoint contains invalid node indices"));
+        }
+        let node_values: FnvHashMap<NodeIndex, NodeValue<State>> = self
+            .node_values
+            .into_iter()
+            .map(|(index, value)| (NodeIndex::new(index), value))
+            .collect();
+        let worklist: Vec<NodeIndex> = self.worklist.into_iter().map(NodeIndex::new).collect();
+        computation.restore_state(node_values, &worklist);
+        Ok(self.phase)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intermediate_representation::Variable;
+
+    #[test]
+    fn read_checkpoint_without_shared_caller_stack_ids() {
+        let state = State::new(&Variable::mock("RSP", 8), Tid::new("func"));
+        let checkpoint = Checkpoint {
+            version: VERSION.to_string(),
+            node_count: 1,
+            phase: 0,
+            node_values: vec![(0, NodeValue::Value(state))],
+            worklist: Vec::new(),
+        };
+        let mut serialized = Vec::new();
+        write_artifact(&checkpoint, &mut serialized).unwrap();
+        let mut content: serde_json::Value = serde_json::from_slice(&serialized).unwrap();
+        let restored: Checkpoint<NodeValue<State>> =
+            read_artifact(content.to_string().as_bytes()).unwrap();
+        assert!(restored == checkpoint);
+
+        // Version 1 checkpoints do not contain the shared caller stack IDs.
+        content["cwe_checker_artifact"]["version"] = 1.into();
+        content["node_values"][0][1]["Value"]
+            .as_object_mut()
+            .unwrap()
+            .remove("shared_caller_stack_ids")
+            .unwrap();
+        let restored: Checkpoint<NodeValue<State>> =
+            read_artifact(content.to_string().as_bytes()).unwrap();
+        assert!(restored == checkpoint);
+    }
+}
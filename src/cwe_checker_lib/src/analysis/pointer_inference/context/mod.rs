@@ -2,10 +2,12 @@ use super::object::ObjectType;
 use crate::analysis::graph::Graph;
 use crate::intermediate_representation::*;
 use crate::prelude::*;
+use crate::utils::extern_stubs::{self, is_unresolved};
 use crate::utils::log::*;
 use crate::{abstract_domain::*, utils::binary::RuntimeMemoryImage};
 use std::collections::{BTreeMap, BTreeSet};
 
+use super::precision_loss::PrecisionLossRecorder;
 use super::state::State;
 use super::{Config, Data, VERSION};
 
@@ -36,6 +38,14 @@ pub struct Context<'a> {
     pub allocation_symbols: Vec<String>,
     /// Names of `free`-like extern functions.
     pub deallocation_symbols: Vec<String>,
+    /// The behavior of calls to extern functions that are not modeled by the analysis.
+    pub extern_stubs: extern_stubs::Config,
+    /// The maximal number of targets of a pointer. Pointers with more targets are replaced by *Top*.
+    pub max_pointer_targets: Option<usize>,
+    /// The numeric domain used to approximate values that are not exactly known when merging states.
+    pub numeric_domain: NumericDomainKind,
+    /// The program points at which pointers with too many targets were replaced by *Top*.
+    pub precision_losses: PrecisionLossRecorder,
 }
 
 impl<'a> Context<'a> {
@@ -60,6 +70,34 @@ impl<'a> Context<'a> {
             log_collector,
             allocation_symbols: config.allocation_symbols,
             deallocation_symbols: config.deallocation_symbols,
+            extern_stubs: config.extern_stubs,
+            max_pointer_targets: config.max_pointer_targets,
+            numeric_domain: config.numeric_domain,
+            precision_losses: PrecisionLossRecorder::default(),
+        }
+    }
+
+    /// Returns the number of targets of the value
+    /// if it is a pointer with more targets than the configured maximum.
+    fn get_excess_pointer_targets(&self, value: &Data) -> Option<usize> {
+        match (value, self.max_pointer_targets) {
+            (Data::Pointer(pointer), Some(max_targets))
+                if pointer.targets().len() > max_targets =>
+            {
+                Some(pointer.targets().len())
+            }
+            _ => None,
+        }
+    }
+
+    /// Replace the value of the register by *Top* if it is a pointer with more targets than the configured maximum.
+    /// The precision loss is recorded for the given definition.
+    fn limit_register_pointer_targets(&self, state: &mut State, var: &Variable, def_tid: &Tid) {
+        if let Ok(value) = state.get_register(var) {
+            if let Some(number_of_targets) = self.get_excess_pointer_targets(&value) {
+                state.set_register(var, Data::new_top(var.size));
+                self.precision_losses.record(def_tid, number_of_targets);
+            }
         }
     }
 
@@ -173,21 +211,26 @@ impl<'a> Context<'a> {
                             if let Err(possible_double_frees) =
                                 new_state.mark_mem_object_as_freed(&pointer)
                             {
-                                let warning = CweWarning {
-                                    name: "CWE415".to_string(),
-                                    version: VERSION.to_string(),
-                                    addresses: vec![call.tid.address.clone()],
-                                    tids: vec![format!("{}", call.tid)],
-                                    symbols: Vec::new(),
-                                    other: vec![possible_double_frees
-                                        .into_iter()
-                                        .map(|(id, err)| format!("{}: {}", id, err))
-                                        .collect()],
-                                    description: format!(
+                                let warning = CweWarning::new(
+                                    "CWE415",
+                                    VERSION,
+                                    format!(
                                         "(Double Free) Object may have been freed before at {}",
                                         call.tid.address
                                     ),
-                                };
+                                )
+                                .severity(Severity::High)
+                                .addresses(vec![call.tid.address.clone()])
+                                .tids(vec![format!("{}", call.tid)])
+                                .other(vec![possible_double_frees
+                                    .into_iter()
+                                    .map(|(id, err)| format!("{}: {}", id, err))
+                                    .collect()])
+                                .remediation(Remediation::new(
+                                    415,
+                                    "Make sure that each allocated object is freed exactly once, e.g. by setting pointers to NULL after freeing them.",
+                                ))
+                                .exploitability(ExploitabilityHint::new(Impact::MemoryWrite));
                                 let _ = self.log_collector.send(LogThreadMsg::Cwe(warning));
                             }
                         } else {
@@ -228,18 +271,19 @@ impl<'a> Context<'a> {
             ) {
                 Ok(value) => {
                     if state.memory.is_dangling_pointer(&value, true) {
-                        let warning = CweWarning {
-                            name: "CWE416".to_string(),
-                            version: VERSION.to_string(),
-                            addresses: vec![call.tid.address.clone()],
-                            tids: vec![format!("{}", call.tid)],
-                            symbols: Vec::new(),
-                            other: Vec::new(),
-                            description: format!(
+                        let warning = CweWarning::new(
+                            "CWE416",
+                            VERSION,
+                            format!(
                                 "(Use After Free) Call to {} may access freed memory at {}",
                                 extern_symbol.name, call.tid.address
                             ),
-                        };
+                        )
+                        .severity(Severity::High)
+                        .addresses(vec![call.tid.address.clone()])
+                        .tids(vec![format!("{}", call.tid)])
+                        .remediation(use_after_free_remediation())
+                        .exploitability(ExploitabilityHint::new(Impact::MemoryWrite));
                         let _ = self.log_collector.send(LogThreadMsg::Cwe(warning));
                     }
                 }
@@ -295,14 +339,21 @@ impl<'a> Context<'a> {
     }
 
     /// Handle an extern symbol call, whose concrete effect on the state is unknown.
-    /// Basically, we assume that the call may write to all memory objects and register that is has access to.
+    ///
+    /// The effect is approximated by the configured stub behavior of the extern symbol.
+    /// By default, we assume that the call may write to all memory objects and register that is has access to.
     fn handle_generic_extern_call(
         &self,
         state: &State,
-        mut new_state: State,
         call: &Term<Jmp>,
         extern_symbol: &ExternSymbol,
     ) -> State {
+        let behavior = self.extern_stubs.get_behavior(&extern_symbol.name);
+        let calling_conv = extern_symbol.get_calling_convention(&self.project);
+        let mut new_state = state.clone();
+        new_state.clear_clobbered_register(behavior, calling_conv);
+        // Adjust stack register value (for x86 architecture).
+        self.adjust_stack_register_on_extern_call(state, &mut new_state);
         self.log_debug(
             new_state.clear_stack_parameter(
                 extern_symbol,
@@ -311,9 +362,11 @@ impl<'a> Context<'a> {
             ),
             Some(&call.tid),
         );
-        let calling_conv = extern_symbol.get_calling_convention(&self.project);
+        if !behavior.write_to_parameters {
+            return new_state;
+        }
         let mut possible_referenced_ids = BTreeSet::new();
-        if extern_symbol.parameters.is_empty() && extern_symbol.return_values.is_empty() {
+        if is_unresolved(extern_symbol) {
             // We assume here that we do not know the parameters and approximate them by all possible parameter registers.
             // This approximation is wrong if the function is known but has neither parameters nor return values.
             // We cannot distinguish these two cases yet.
@@ -344,18 +397,46 @@ impl<'a> Context<'a> {
         new_state
     }
 
+    /// Handle a call to a lock operation like `pthread_mutex_lock`.
+    ///
+    /// Lock operations only change the internal state of the lock object,
+    /// which is irrelevant for the pointer inference.
+    /// Thus, in contrast to generic extern calls, we do not assume that they write to the memory objects
+    /// referenced by their parameters.
+    fn handle_mutex_operation(
+        &self,
+        mut new_state: State,
+        call: &Term<Jmp>,
+        extern_symbol: &ExternSymbol,
+    ) -> State {
+        self.log_debug(
+            new_state.clear_stack_parameter(
+                extern_symbol,
+                &self.project.stack_pointer_register,
+                self.runtime_memory_image,
+            ),
+            Some(&call.tid),
+        );
+        new_state
+    }
+
     /// Handle a generic call whose target function is unknown.
     ///
     /// This function just assumes that the target of the call uses a reasonable standard calling convention
-    /// and that it may access (and write to) all parameter registers of this calling convention.
+    /// and that it behaves according to the default stub behavior for extern functions.
+    /// By default, it may access (and write to) all parameter registers of this calling convention.
     /// We also assume that the function does not use any parameters saved on the stack,
     /// which may greatly reduce correctness of the analysis for the x86_32 architecture.
     fn handle_call_to_generic_unknown_function(&self, state_before_call: &State) -> Option<State> {
         if let Some(calling_conv) = self.project.get_standard_calling_convention() {
+            let behavior = &self.extern_stubs.default;
             let mut new_state = state_before_call.clone();
-            new_state.clear_non_callee_saved_register(&calling_conv.callee_saved_register[..]);
+            new_state.clear_clobbered_register(behavior, calling_conv);
             // Adjust stack register value (for x86 architecture).
             self.adjust_stack_register_on_extern_call(state_before_call, &mut new_state);
+            if !behavior.write_to_parameters {
+                return Some(new_state);
+            }
 
             let mut possible_referenced_ids = BTreeSet::new();
             for parameter_register_name in calling_conv.parameter_register.iter() {
@@ -401,5 +482,13 @@ impl<'a> Context<'a> {
     }
 }
 
+/// The remediation advice for use-after-free warnings (CWE-416).
+fn use_after_free_remediation() -> Remediation {
+    Remediation::new(
+        416,
+        "Make sure that no pointer to an object is used after the object was freed, e.g. by setting pointers to NULL after freeing them.",
+    )
+}
+
 #[cfg(test)]
 mod tests;
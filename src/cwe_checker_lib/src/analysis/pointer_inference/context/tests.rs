@@ -95,6 +95,10 @@ fn mock_project() -> (Project, Config) {
         Config {
             allocation_symbols: vec!["malloc".into()],
             deallocation_symbols: vec!["free".into()],
+            main_functions: vec!["main".into()],
+            numeric_domain: NumericDomainKind::Constants,
+            max_pointer_targets: None,
+            extern_stubs: extern_stubs::Config::default(),
         },
     )
 }
@@ -279,6 +283,41 @@ fn context_problem_implementation() {
         .is_top());
 }
 
+#[test]
+fn extern_stub_behavior() {
+    use crate::analysis::forward_interprocedural_fixpoint::Context as IpFpContext;
+    let (project, mut config) = mock_project();
+    config.extern_stubs.symbols.insert(
+        "other".to_string(),
+        extern_stubs::StubBehavior {
+            clobber_caller_saved: false,
+            ..extern_stubs::StubBehavior::default()
+        },
+    );
+    let runtime_memory_image = RuntimeMemoryImage::mock();
+    let graph = crate::analysis::graph::get_program_cfg(&project.program, HashSet::new());
+    let (log_sender, _log_receiver) = crossbeam_channel::unbounded();
+    let context = Context::new(&project, &runtime_memory_image, &graph, config, log_sender);
+    let mut state = State::new(&register("RSP"), Tid::new("main"));
+    state.set_register(&register("other_reg"), Data::Value(bv(42)));
+    state.set_register(&register("RDX"), Data::Value(bv(13)));
+
+    // Only the return register is clobbered by the call.
+    let state_after_call = context
+        .update_call_stub(&state, &call_term("extern_other"))
+        .unwrap();
+    assert_eq!(
+        state_after_call
+            .get_register(&register("other_reg"))
+            .unwrap(),
+        Data::Value(bv(42))
+    );
+    assert!(state_after_call
+        .get_register(&register("RDX"))
+        .unwrap()
+        .is_top());
+}
+
 #[test]
 fn update_return() {
     use crate::analysis::forward_interprocedural_fixpoint::Context as IpFpContext;
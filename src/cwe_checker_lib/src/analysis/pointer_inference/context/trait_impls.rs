@@ -1,4 +1,5 @@
 use super::*;
+use crate::analysis::concurrency::ConcurrencyPrimitive;
 
 impl<'a> crate::analysis::forward_interprocedural_fixpoint::Context<'a> for Context<'a> {
     type Value = State;
@@ -8,42 +9,65 @@ impl<'a> crate::analysis::forward_interprocedural_fixpoint::Context<'a> for Cont
         &self.graph
     }
 
-    /// Merge two state values.
+    /// Merge two state values, approximating values with the configured numeric domain.
     fn merge(&self, value1: &State, value2: &State) -> State {
-        value1.merge(value2)
+        value1.merge_with_mode(value2, MergeMode::new(self.numeric_domain))
+    }
+
+    /// Merge two state values, extrapolating approximated values that changed compared to the old value.
+    fn widen(&self, old_value: &State, new_value: &State) -> State {
+        old_value.merge_with_mode(new_value, MergeMode::new(self.numeric_domain).widening())
     }
 
     /// Update the state according to the effects of the given `Def` term.
     fn update_def(&self, state: &Self::Value, def: &Term<Def>) -> Option<Self::Value> {
         // first check for use-after-frees
         if state.contains_access_of_dangling_memory(&def.term) {
-            let warning = CweWarning {
-                name: "CWE416".to_string(),
-                version: VERSION.to_string(),
-                addresses: vec![def.tid.address.clone()],
-                tids: vec![format!("{}", def.tid)],
-                symbols: Vec::new(),
-                other: Vec::new(),
-                description: format!(
+            let warning = CweWarning::new(
+                "CWE416",
+                VERSION,
+                format!(
                     "(Use After Free) Access through a dangling pointer at {}",
                     def.tid.address
                 ),
-            };
+            )
+            .severity(Severity::High)
+            .addresses(vec![def.tid.address.clone()])
+            .tids(vec![format!("{}", def.tid)])
+            .remediation(use_after_free_remediation())
+            .exploitability(ExploitabilityHint::new(Impact::MemoryWrite));
             let _ = self.log_collector.send(LogThreadMsg::Cwe(warning));
         }
 
         match &def.term {
             Def::Store { address, value } => {
                 let mut new_state = state.clone();
-                self.log_debug(
-                    new_state.handle_store(address, value, self.runtime_memory_image),
-                    Some(&def.tid),
-                );
+                let excess_targets = state
+                    .eval(value)
+                    .ok()
+                    .and_then(|data| self.get_excess_pointer_targets(&data));
+                if let Some(number_of_targets) = excess_targets {
+                    self.precision_losses.record(&def.tid, number_of_targets);
+                    self.log_debug(
+                        new_state.write_to_address(
+                            address,
+                            &Data::new_top(value.bytesize()),
+                            self.runtime_memory_image,
+                        ),
+                        Some(&def.tid),
+                    );
+                } else {
+                    self.log_debug(
+                        new_state.handle_store(address, value, self.runtime_memory_image),
+                        Some(&def.tid),
+                    );
+                }
                 Some(new_state)
             }
             Def::Assign { var, value } => {
                 let mut new_state = state.clone();
                 self.log_debug(new_state.handle_register_assign(var, value), Some(&def.tid));
+                self.limit_register_pointer_targets(&mut new_state, var, &def.tid);
                 Some(new_state)
             }
             Def::Load { var, address } => {
@@ -52,6 +76,7 @@ impl<'a> crate::analysis::forward_interprocedural_fixpoint::Context<'a> for Cont
                     new_state.handle_load(var, address, &self.runtime_memory_image),
                     Some(&def.tid),
                 );
+                self.limit_register_pointer_targets(&mut new_state, var, &def.tid);
                 Some(new_state)
             }
         }
@@ -134,6 +159,7 @@ impl<'a> crate::analysis::forward_interprocedural_fixpoint::Context<'a> for Cont
             // set the list of caller stack ids to only this caller id
             callee_state.caller_stack_ids = BTreeSet::new();
             callee_state.caller_stack_ids.insert(new_caller_stack_id);
+            callee_state.shared_caller_stack_ids = BTreeMap::new();
             // Remove non-referenced objects and objects, only the caller knows about, from the state.
             callee_state.ids_known_to_caller = BTreeSet::new();
             callee_state.remove_unreferenced_objects();
@@ -182,10 +208,17 @@ impl<'a> crate::analysis::forward_interprocedural_fixpoint::Context<'a> for Cont
             };
 
         let original_caller_stack_id = &state_before_call.stack_id;
-        let caller_stack_id = AbstractIdentifier::new(
+        let callsite_id = AbstractIdentifier::new(
             call_term.tid.clone(),
             AbstractLocation::from_var(&self.project.stack_pointer_register).unwrap(),
         );
+        // If the callsite shares the context of another callsite,
+        // then the caller stack frame is represented by the caller stack ID of the other callsite.
+        let caller_stack_id = state_before_return
+            .shared_caller_stack_ids
+            .get(&callsite_id)
+            .cloned()
+            .unwrap_or(callsite_id);
         let callee_stack_id = &state_before_return.stack_id;
         let stack_offset_on_call = self.get_current_stack_offset(state_before_call);
 
@@ -224,6 +257,8 @@ impl<'a> crate::analysis::forward_interprocedural_fixpoint::Context<'a> for Cont
         );
         state_after_return.stack_id = original_caller_stack_id.clone();
         state_after_return.caller_stack_ids = state_before_call.caller_stack_ids.clone();
+        state_after_return.shared_caller_stack_ids =
+            state_before_call.shared_caller_stack_ids.clone();
         state_after_return.ids_known_to_caller = state_before_call.ids_known_to_caller.clone();
 
         state_after_return.readd_caller_objects(state_before_call);
@@ -283,7 +318,14 @@ impl<'a> crate::analysis::forward_interprocedural_fixpoint::Context<'a> for Cont
                 free_like_fn if self.deallocation_symbols.iter().any(|x| x == free_like_fn) => {
                     Some(self.mark_parameter_object_as_freed(state, new_state, call, extern_symbol))
                 }
-                _ => Some(self.handle_generic_extern_call(state, new_state, call, extern_symbol)),
+                mutex_fn
+                    if ConcurrencyPrimitive::from_symbol_name(mutex_fn)
+                        .map(|primitive| primitive.is_mutex_operation())
+                        .unwrap_or(false) =>
+                {
+                    Some(self.handle_mutex_operation(new_state, call, extern_symbol))
+                }
+                _ => Some(self.handle_generic_extern_call(state, call, extern_symbol)),
             }
         } else {
             panic!("Extern symbol not found.");
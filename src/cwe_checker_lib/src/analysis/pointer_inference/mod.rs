@@ -19,9 +19,12 @@ use super::interprocedural_fixpoint_generic::NodeValue;
 use crate::analysis::graph::{Graph, Node};
 use crate::intermediate_representation::*;
 use crate::prelude::*;
+use crate::utils::cancellation::CancellationToken;
+use crate::utils::deadline::Deadline;
+use crate::utils::extern_stubs;
 use crate::utils::log::*;
 use crate::{
-    abstract_domain::{BitvectorDomain, DataDomain},
+    abstract_domain::{AbstractIdentifier, BitvectorDomain, DataDomain, NumericDomainKind},
     utils::binary::RuntimeMemoryImage,
 };
 use petgraph::graph::NodeIndex;
@@ -29,21 +32,47 @@ use petgraph::visit::IntoNodeReferences;
 use petgraph::Direction;
 use std::collections::HashMap;
 
+pub mod allocation_sizes;
+pub mod checkpoint;
 mod context;
 pub mod object;
 mod object_list;
+pub mod precision_loss;
 mod state;
 
+use allocation_sizes::AllocationSize;
+use checkpoint::{Checkpoint, CheckpointConfig};
 use context::Context;
+use precision_loss::PrecisionLossSite;
 pub use state::State;
 
 /// The version number of the analysis.
 const VERSION: &str = "0.1";
 
+/// The share of the time until the deadline of the computation that is spent with full precision.
+/// If the computation has not stabilized afterwards, the precision is reduced for the remaining time.
+const FULL_PRECISION_TIME_SHARE: f64 = 0.5;
+
+/// The maximal number of pointer targets when the precision of the computation is reduced
+/// because the deadline approaches.
+const DEGRADED_MAX_POINTER_TARGETS: usize = 4;
+
 pub static CWE_MODULE: crate::CweModule = crate::CweModule {
     name: "Memory",
     version: VERSION,
+    cwes: &[415, 416],
+    description: "Memory management errors, e.g. double frees and uses after free.",
+    required_analyses: &[crate::RequiredAnalysis::PointerInference],
+    config_keys: &[
+        "allocation_symbols",
+        "deallocation_symbols",
+        "main_functions",
+        "numeric_domain",
+        "max_pointer_targets",
+        "extern_stubs",
+    ],
     run: extract_pi_analysis_results,
+    validate_config: Some(crate::utils::config_validation::deserialize_section::<Config>),
 };
 
 /// The abstract domain type for representing register values.
@@ -60,12 +89,59 @@ pub struct Config {
     /// Note that the analysis currently does not detect mismatching allocation-deallocation pairs,
     /// i.e. it cannot distinguish between memory allocated by `malloc` and memory allocated by `new`.
     deallocation_symbols: Vec<String>,
+    /// Names of functions whose parameters are modeled as the `argc`, `argv` and `envp` parameters of a `main` function
+    /// when the function is an entry point of the analysis.
+    #[serde(default = "default_main_functions")]
+    main_functions: Vec<String>,
+    /// The numeric domain used to approximate values that are not exactly known,
+    /// e.g. `intervals` to track the possible offsets of array accesses.
+    /// More precise domains increase the runtime of the analysis.
+    #[serde(default)]
+    numeric_domain: NumericDomainKind,
+    /// The maximal number of targets of a pointer.
+    /// Pointers with more targets are replaced by *Top* and the precision loss is reported.
+    /// If not set, the number of targets is not limited.
+    #[serde(default)]
+    max_pointer_targets: Option<usize>,
+    /// The behavior of calls to extern functions that are not modeled by the analysis.
+    /// Copied from the `ExternStubs` configuration section.
+    #[serde(default)]
+    extern_stubs: extern_stubs::Config,
+}
+
+/// The default value for the `main_functions` configuration parameter.
+fn default_main_functions() -> Vec<String> {
+    vec!["main".to_string()]
+}
+
+/// Options controlling the runtime behaviour of the pointer inference computation.
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// If set, the computation is stopped early when the deadline is reached.
+    pub deadline: Option<Deadline>,
+    /// If set, the computation is stopped early when the token is cancelled.
+    /// The results computed until then are still returned.
+    pub cancellation: Option<CancellationToken>,
+    /// If set, checkpoints of the computation are saved periodically
+    /// and the computation is resumed from an existing checkpoint.
+    pub checkpoint: Option<CheckpointConfig>,
 }
 
 /// A wrapper struct for the pointer inference computation object.
 pub struct PointerInference<'a> {
     computation: Computation<GeneralizedContext<'a, Context<'a>>>,
     log_collector: crossbeam_channel::Sender<LogThreadMsg>,
+    cancellation: CancellationToken,
+    /// The deadline of the computation, used to reduce the precision as the deadline approaches.
+    deadline: Option<Deadline>,
+    checkpoint_config: Option<CheckpointConfig>,
+    /// The index of the current phase of `compute_with_speculative_entry_points`.
+    phase: usize,
+    /// Names of functions whose parameters are modeled as the parameters of a `main` function.
+    main_functions: Vec<String>,
+    /// The sizes of the heap objects allocated at each allocation site.
+    /// Computed after the fixpoint computation finished.
+    allocation_sizes: HashMap<Tid, AllocationSize>,
     pub collected_logs: (Vec<LogMessage>, Vec<CweWarning>),
 }
 
@@ -78,6 +154,7 @@ impl<'a> PointerInference<'a> {
         config: Config,
         log_sender: crossbeam_channel::Sender<LogThreadMsg>,
     ) -> PointerInference<'a> {
+        let main_functions = config.main_functions.clone();
         let context = Context::new(
             project,
             runtime_memory_image,
@@ -128,23 +205,157 @@ impl<'a> PointerInference<'a> {
         for (sub_tid, start_node_index) in entry_sub_to_entry_node_map.into_iter() {
             fixpoint_computation.set_node_value(
                 start_node_index,
-                super::interprocedural_fixpoint_generic::NodeValue::Value(State::new(
-                    &project.stack_pointer_register,
-                    sub_tid,
-                )),
+                super::interprocedural_fixpoint_generic::NodeValue::Value(
+                    create_entry_point_state(project, subs[&sub_tid], &main_functions),
+                ),
             );
         }
         PointerInference {
             computation: fixpoint_computation,
             log_collector: log_sender,
+            cancellation: CancellationToken::new(),
+            deadline: None,
+            checkpoint_config: None,
+            phase: 0,
+            main_functions,
+            allocation_sizes: HashMap::new(),
             collected_logs: (Vec::new(), Vec::new()),
         }
     }
 
     /// Compute the fixpoint of the pointer inference analysis.
     /// Has a `max_steps` bound for the fixpoint algorithm to prevent infinite loops.
+    /// If the computation gets cancelled (e.g. because a deadline is reached), it stops early.
+    /// If checkpoints are configured, they are saved periodically during the computation.
+    ///
+    /// If a deadline is set, the computation runs with full precision only for a part of the remaining time.
+    /// If it has not stabilized by then, the precision is reduced (see [`Self::reduce_precision`])
+    /// and the computation continues until it stabilizes or the deadline is reached.
     pub fn compute(&mut self) {
-        self.computation.compute_with_max_steps(100); // TODO: make max_steps configurable!
+        match self.deadline {
+            Some(deadline) if !self.is_precision_reduced() => {
+                let full_precision_cancellation = self
+                    .cancellation
+                    .child_with_deadline(deadline.portion(FULL_PRECISION_TIME_SHARE).instant());
+                self.compute_until_cancelled(&full_precision_cancellation);
+                if !self.computation.has_stabilized() && !self.is_cancelled() {
+                    self.reduce_precision();
+                    self.compute_until_cancelled(&self.cancellation.clone());
+                }
+            }
+            _ => self.compute_until_cancelled(&self.cancellation.clone()),
+        }
+    }
+
+    /// Compute the fixpoint until it stabilizes or the given token gets cancelled.
+    fn compute_until_cancelled(&mut self, cancellation: &CancellationToken) {
+        let max_steps = 100; // TODO: make max_steps configurable!
+        if let Some(config) = self.checkpoint_config.clone() {
+            let phase = self.phase;
+            let log_collector = self.log_collector.clone();
+            let mut save_checkpoint =
+                |computation: &Computation<GeneralizedContext<'a, Context<'a>>>| {
+                    if let Err(err) = Checkpoint::new(computation, phase).save(&config.path) {
+                        let _ = log_collector.send(LogThreadMsg::Log(LogMessage::new_info(
+                            format!("Pointer Inference: Could not save checkpoint: {}", err),
+                        )));
+                    }
+                };
+            self.computation.compute_with_checkpoints(
+                max_steps,
+                Some(cancellation),
+                Some((config.interval, &mut save_checkpoint)),
+            );
+        } else {
+            self.computation
+                .compute_with_max_steps_and_cancellation(max_steps, cancellation);
+        }
+    }
+
+    /// Returns `true` if the precision of the computation was reduced because the deadline approached.
+    fn is_precision_reduced(&self) -> bool {
+        self.get_context().max_pointer_targets == Some(DEGRADED_MAX_POINTER_TARGETS)
+    }
+
+    /// Reduce the precision of the computation so that it stabilizes faster:
+    /// Pointers with more than [`DEGRADED_MAX_POINTER_TARGETS`] targets are replaced by *Top*,
+    /// which keeps the states (and thus the analysis contexts of called functions) small.
+    fn reduce_precision(&mut self) {
+        let context = self.computation.get_context_mut().get_context_mut();
+        context.max_pointer_targets = Some(
+            context
+                .max_pointer_targets
+                .map_or(DEGRADED_MAX_POINTER_TARGETS, |max_targets| {
+                    max_targets.min(DEGRADED_MAX_POINTER_TARGETS)
+                }),
+        );
+        self.log_info(format!(
+            "Pointer Inference: Deadline approaching. Reduced precision by limiting the number of pointer targets to {}.",
+            DEGRADED_MAX_POINTER_TARGETS
+        ));
+    }
+
+    /// Remove the intermediate results of all functions whose computation did not stabilize,
+    /// so that the analyses using the results only see final states.
+    /// The affected functions are treated as not analyzed and listed in a log message.
+    fn discard_unfinished_functions(&mut self) {
+        let discarded_nodes = self.computation.discard_non_stabilized_nodes();
+        if discarded_nodes.is_empty() {
+            return;
+        }
+        let graph = self.computation.get_graph();
+        let mut function_names: Vec<&str> = discarded_nodes
+            .iter()
+            .map(|node| graph[*node].get_sub().term.name.as_str())
+            .collect();
+        function_names.sort_unstable();
+        function_names.dedup();
+        let message = format!(
+            "Pointer Inference: Computation stopped early. Skipped the unfinished analysis of {} functions: {}",
+            function_names.len(),
+            function_names.join(", ")
+        );
+        self.log_info(message);
+    }
+
+    /// Set a deadline for the computation.
+    /// Computation phases that would exceed the deadline are stopped early or skipped.
+    pub fn set_deadline(&mut self, deadline: Deadline) {
+        self.cancellation = self.cancellation.child_with_deadline(deadline.instant());
+        self.deadline = Some(deadline);
+    }
+
+    /// Set a cancellation token for the computation.
+    /// If the token gets cancelled, the computation stops early and the remaining phases are skipped.
+    ///
+    /// Note that this replaces a previously set deadline.
+    pub fn set_cancellation_token(&mut self, cancellation: CancellationToken) {
+        self.cancellation = cancellation;
+    }
+
+    /// Periodically save checkpoints of the computation to the file given in the config.
+    /// If the file already exists, the state of the computation is restored from it.
+    pub fn set_checkpoint_config(&mut self, config: CheckpointConfig) {
+        if config.path.exists() {
+            match Checkpoint::load(&config.path)
+                .and_then(|checkpoint| checkpoint.restore(&mut self.computation))
+            {
+                Ok(phase) => {
+                    self.phase = phase;
+                    self.log_info("Pointer Inference: Resumed computation from checkpoint.");
+                }
+                Err(err) => self.log_info(format!(
+                    "Pointer Inference: Could not resume from checkpoint: {}",
+                    err
+                )),
+            }
+        }
+        self.checkpoint_config = Some(config);
+    }
+
+    /// Returns `true` if the computation was cancelled, e.g. because its deadline has expired.
+    fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
     }
 
     /// Print results serialized as YAML to stdout
@@ -191,10 +402,28 @@ impl<'a> PointerInference<'a> {
         self.computation.get_context().get_context()
     }
 
+    /// Get the program points at which pointers with too many targets were replaced by *Top*,
+    /// sorted by the number of replacements in descending order.
+    pub fn get_precision_losses(&self) -> Vec<PrecisionLossSite> {
+        self.get_context().precision_losses.get_sites()
+    }
+
     pub fn get_node_value(&self, node_id: NodeIndex) -> Option<&NodeValue<State>> {
         self.computation.get_node_value(node_id)
     }
 
+    /// Get the possible sizes of the given heap object at its allocation site.
+    /// Returns `None` if the object was not allocated by a call to an allocation function.
+    pub fn get_allocation_size(&self, object_id: &AbstractIdentifier) -> Option<&AllocationSize> {
+        self.allocation_sizes.get(object_id.get_tid())
+    }
+
+    /// Get the sizes of the heap objects allocated at all allocation sites,
+    /// indexed by the TIDs of the calls to the allocation functions.
+    pub fn get_allocation_sizes(&self) -> &HashMap<Tid, AllocationSize> {
+        &self.allocation_sizes
+    }
+
     /// Add speculative entry points to the fixpoint algorithm state.
     ///
     /// Since indirect jumps and calls are not handled yet (TODO: change that),
@@ -242,16 +471,12 @@ impl<'a> PointerInference<'a> {
             new_entry_points.len()
         ));
         for entry in new_entry_points {
-            let sub_tid = start_block_to_sub_map
-                [&self.computation.get_graph()[entry].get_block().tid]
-                .tid
-                .clone();
+            let sub = start_block_to_sub_map[&self.computation.get_graph()[entry].get_block().tid];
             self.computation.set_node_value(
                 entry,
-                super::interprocedural_fixpoint_generic::NodeValue::Value(State::new(
-                    &project.stack_pointer_register,
-                    sub_tid,
-                )),
+                super::interprocedural_fixpoint_generic::NodeValue::Value(
+                    create_entry_point_state(project, sub, &self.main_functions),
+                ),
             );
         }
     }
@@ -281,20 +506,62 @@ impl<'a> PointerInference<'a> {
         let _ = self.log_collector.send(LogThreadMsg::Log(log_msg));
     }
 
+    /// Send an info log message to the log collector.
+    fn log_info(&self, msg: impl Into<String>) {
+        let log_msg = LogMessage::new_info(msg.into());
+        let _ = self.log_collector.send(LogThreadMsg::Log(log_msg));
+    }
+
     /// Compute the results of the pointer inference fixpoint algorithm.
     /// Successively adds more functions as possible entry points
     /// to increase code coverage.
+    ///
+    /// If the computation is cancelled (e.g. because a deadline is reached), the remaining phases are skipped
+    /// and the intermediate results of functions whose computation did not stabilize are discarded.
+    /// If checkpoints are configured, a checkpoint is saved when the computation is interrupted
+    /// and the checkpoint file is removed when the computation finishes.
     pub fn compute_with_speculative_entry_points(&mut self, project: &Project) {
-        self.compute();
-        self.count_blocks_with_state();
-        // Now compute again with speculative entry points added
-        self.add_speculative_entry_points(project, true);
-        self.compute();
-        self.count_blocks_with_state();
-        // Now compute again with all missed functions as additional entry points
-        self.add_speculative_entry_points(project, false);
-        self.compute();
-        self.count_blocks_with_state();
+        while self.phase <= 2 {
+            match self.phase {
+                0 => (),
+                // Now compute again with speculative entry points added
+                1 => self.add_speculative_entry_points(project, true),
+                // Now compute again with all missed functions as additional entry points
+                _ => self.add_speculative_entry_points(project, false),
+            }
+            self.compute();
+            self.count_blocks_with_state();
+            if self.is_cancelled() {
+                break;
+            }
+            self.phase += 1;
+        }
+        if self.phase < 1 {
+            self.log_info(
+                "Pointer Inference: Computation stopped early. Skipped analysis of speculative entry points.",
+            );
+        }
+        if self.phase < 2 {
+            self.log_info(
+                "Pointer Inference: Computation stopped early. Skipped analysis of functions not reachable from entry points.",
+            );
+        }
+        if let Some(config) = &self.checkpoint_config {
+            if self.phase <= 2 {
+                if let Err(err) = Checkpoint::new(&self.computation, self.phase).save(&config.path)
+                {
+                    self.log_info(format!(
+                        "Pointer Inference: Could not save checkpoint: {}",
+                        err
+                    ));
+                }
+            } else {
+                let _ = std::fs::remove_file(&config.path);
+            }
+        }
+        if self.phase <= 2 {
+            self.discard_unfinished_functions();
+        }
 
         if !self.computation.has_stabilized() {
             let worklist_size = self.computation.get_worklist().len();
@@ -394,15 +661,30 @@ impl<'a> PointerInference<'a> {
     }
 }
 
+/// Create the state at the start of a function that is an entry point of the analysis.
+/// If the function is a `main` function, its `argc`, `argv` and `envp` parameters are modeled in the state.
+fn create_entry_point_state(
+    project: &Project,
+    sub: &Term<Sub>,
+    main_functions: &[String],
+) -> State {
+    let mut state = State::new(&project.stack_pointer_register, sub.tid.clone());
+    if main_functions.contains(&sub.term.name) {
+        state.add_main_function_parameters(project, &sub.tid);
+    }
+    state
+}
+
 /// The entry point for the memory analysis check.
 /// Does not actually compute anything
 /// but just extracts the results of the already computed pointer inference analysis.
 pub fn extract_pi_analysis_results(
     analysis_results: &AnalysisResults,
     _analysis_params: &serde_json::Value,
-) -> (Vec<LogMessage>, Vec<CweWarning>) {
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
     let pi_anaylsis = analysis_results.pointer_inference.unwrap();
-    pi_anaylsis.collected_logs.clone()
+    let (logs, cwes) = pi_anaylsis.collected_logs.clone();
+    (logs, cwes, false)
 }
 
 /// Compute the pointer inference analysis and return its results.
@@ -414,6 +696,7 @@ pub fn run<'a>(
     runtime_memory_image: &'a RuntimeMemoryImage,
     control_flow_graph: &'a Graph<'a>,
     config: Config,
+    options: RunOptions,
     print_debug: bool,
 ) -> PointerInference<'a> {
     let logging_thread = LogThread::spawn(collect_all_logs);
@@ -425,8 +708,18 @@ pub fn run<'a>(
         config,
         logging_thread.get_msg_sender(),
     );
+    if let Some(cancellation) = options.cancellation {
+        computation.set_cancellation_token(cancellation);
+    }
+    if let Some(deadline) = options.deadline {
+        computation.set_deadline(deadline);
+    }
+    if let Some(checkpoint_config) = options.checkpoint {
+        computation.set_checkpoint_config(checkpoint_config);
+    }
 
     computation.compute_with_speculative_entry_points(project);
+    computation.allocation_sizes = allocation_sizes::compute_allocation_sizes(&computation);
 
     if print_debug {
         computation.print_compact_json();
@@ -434,6 +727,17 @@ pub fn run<'a>(
 
     // save the logs and CWE warnings
     computation.collected_logs = logging_thread.collect();
+    if let Some(max_pointer_targets) = computation.get_context().max_pointer_targets {
+        let mut precision_loss_logs = precision_loss::get_summary_logs(
+            &computation.get_precision_losses(),
+            max_pointer_targets,
+            10,
+        );
+        computation
+            .collected_logs
+            .0
+            .append(&mut precision_loss_logs);
+    }
     computation
 }
 
@@ -488,6 +792,10 @@ mod tests {
             let config = Config {
                 allocation_symbols: vec!["malloc".to_string()],
                 deallocation_symbols: vec!["free".to_string()],
+                main_functions: vec!["main".to_string()],
+                numeric_domain: NumericDomainKind::Constants,
+                max_pointer_targets: None,
+                extern_stubs: extern_stubs::Config::default(),
             };
             let (log_sender, _) = crossbeam_channel::unbounded();
             PointerInference::new(project, mem_image, graph, config, log_sender)
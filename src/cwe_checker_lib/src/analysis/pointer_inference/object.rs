@@ -1,19 +1,30 @@
 use super::Data;
 use crate::abstract_domain::*;
 use crate::prelude::*;
+use crate::utils::perf_stats::{self, Counter};
 use derive_more::Deref;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
 use std::ops::DerefMut;
 use std::sync::Arc;
 
+/// The maximal number of possible offsets of a write access with inexact offset
+/// for which the values at all possible offsets are updated instead of forgetting the whole memory of the object.
+const MAX_OFFSET_SET_SIZE: u64 = 16;
+
 /// A wrapper struct wrapping `AbstractObjectInfo` in an `Arc`.
+///
+/// Like for `MemRegion`, mutable access clones the shared data first,
+/// so abstract objects can be shared between states of different threads.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Deref)]
 #[deref(forward)]
 pub struct AbstractObject(Arc<AbstractObjectInfo>);
 
 impl DerefMut for AbstractObject {
     fn deref_mut(&mut self) -> &mut AbstractObjectInfo {
+        if perf_stats::is_enabled() && Arc::strong_count(&self.0) > 1 {
+            perf_stats::increment(Counter::MapClones);
+        }
         Arc::make_mut(&mut self.0)
     }
 }
@@ -24,12 +35,31 @@ impl AbstractObject {
         AbstractObject(Arc::new(AbstractObjectInfo::new(type_, address_bytesize)))
     }
 
+    /// Create a new abstract object representing arrays of unknown length.
+    /// See [`AbstractObjectInfo::new_array`] for more information.
+    pub fn new_array(
+        type_: ObjectType,
+        address_bytesize: ByteSize,
+        element_value: Option<Data>,
+    ) -> AbstractObject {
+        AbstractObject(Arc::new(AbstractObjectInfo::new_array(
+            type_,
+            address_bytesize,
+            element_value,
+        )))
+    }
+
     /// Short-circuits the `AbstractObjectInfo::merge` function if `self==other`.
     pub fn merge(&self, other: &Self) -> Self {
+        self.merge_with_mode(other, MergeMode::default())
+    }
+
+    /// Short-circuits the `AbstractObjectInfo::merge_with_mode` function if `self==other`.
+    pub fn merge_with_mode(&self, other: &Self, mode: MergeMode) -> Self {
         if self == other {
             self.clone()
         } else {
-            AbstractObject(Arc::new(self.0.merge(other)))
+            AbstractObject(Arc::new(self.0.merge_with_mode(other, mode)))
         }
     }
 }
@@ -47,6 +77,10 @@ pub struct AbstractObjectInfo {
     type_: Option<ObjectType>,
     /// The actual content of the memory object
     memory: MemRegion<Data>,
+    /// The value of all elements of an array object that were not explicitly written to.
+    /// Used for arrays of unknown length where all elements have the same abstract value.
+    #[serde(default)]
+    element_value: Option<Data>,
 }
 
 impl AbstractObjectInfo {
@@ -58,14 +92,63 @@ impl AbstractObjectInfo {
             state: Some(ObjectState::Alive),
             type_: Some(type_),
             memory: MemRegion::new(address_bytesize),
+            element_value: None,
         }
     }
 
+    /// Create a new abstract object representing one or more arrays of unknown length.
+    ///
+    /// If an element value is given, reading an element of the array that was not explicitly written to
+    /// returns the element value instead of an unknown value.
+    /// Array objects are marked as non-unique,
+    /// since writes to them may not overwrite all values represented by the element value.
+    pub fn new_array(
+        type_: ObjectType,
+        address_bytesize: ByteSize,
+        element_value: Option<Data>,
+    ) -> AbstractObjectInfo {
+        let mut object = AbstractObjectInfo::new(type_, address_bytesize);
+        object.is_unique = false;
+        if let Some(element_value) = element_value {
+            object
+                .pointer_targets
+                .extend(element_value.referenced_ids());
+            object.element_value = Some(element_value);
+        }
+        object
+    }
+
     /// Read the value at the given offset of the given size (in bits, not bytes) inside the memory region.
+    ///
+    /// For array objects the element value is returned
+    /// if the position was not written to and corresponds to an array element of the given size.
     pub fn get_value(&self, offset: Bitvector, bytesize: ByteSize) -> Data {
+        if let Some(element_value) = &self.element_value {
+            let is_element_position = offset
+                .try_to_i64()
+                .map(|offset| offset >= 0 && offset % u64::from(bytesize) as i64 == 0)
+                .unwrap_or(false);
+            if element_value.bytesize() == bytesize
+                && is_element_position
+                && self.memory.get_unsized(offset.clone()).is_none()
+            {
+                return element_value.clone();
+            }
+        }
         self.memory.get(offset, bytesize)
     }
 
+    /// Merge the given value into the element value of an array object.
+    /// Used for writes to unknown positions of the array.
+    fn merge_into_element_value(&mut self, value: &Data) {
+        self.element_value = match &self.element_value {
+            Some(element_value) if element_value.bytesize() == value.bytesize() => {
+                Some(element_value.merge(value))
+            }
+            _ => None,
+        };
+    }
+
     /// Write a value at the given offset to the memory region.
     ///
     /// If the abstract object is not unique (i.e. may represent more than one actual object),
@@ -79,13 +162,18 @@ impl AbstractObjectInfo {
                 self.memory.add(value, concrete_offset.clone());
             } else {
                 let merged_value = self
-                    .memory
-                    .get(concrete_offset.clone(), value.bytesize())
+                    .get_value(concrete_offset.clone(), value.bytesize())
                     .merge(&value);
                 self.memory.add(merged_value, concrete_offset.clone());
             };
+        } else if let Some(offsets) = offset.try_to_value_set(MAX_OFFSET_SET_SIZE) {
+            // The value may be written to any of the possible offsets.
+            for concrete_offset in offsets {
+                self.merge_value(value.clone(), &BitvectorDomain::Value(concrete_offset));
+            }
         } else {
             self.memory = MemRegion::new(self.memory.get_address_bytesize());
+            self.merge_into_element_value(&value);
         }
         Ok(())
     }
@@ -97,12 +185,16 @@ impl AbstractObjectInfo {
         };
         if let BitvectorDomain::Value(ref concrete_offset) = offset {
             let merged_value = self
-                .memory
-                .get(concrete_offset.clone(), value.bytesize())
+                .get_value(concrete_offset.clone(), value.bytesize())
                 .merge(&value);
             self.memory.add(merged_value, concrete_offset.clone());
+        } else if let Some(offsets) = offset.try_to_value_set(MAX_OFFSET_SET_SIZE) {
+            for concrete_offset in offsets {
+                self.merge_value(value.clone(), &BitvectorDomain::Value(concrete_offset));
+            }
         } else {
             self.memory = MemRegion::new(self.memory.get_address_bytesize());
+            self.merge_into_element_value(&value);
         }
     }
 
@@ -120,6 +212,9 @@ impl AbstractObjectInfo {
         for data in self.memory.values() {
             referenced_ids.append(&mut data.referenced_ids())
         }
+        if let Some(element_value) = &self.element_value {
+            referenced_ids.append(&mut element_value.referenced_ids());
+        }
         referenced_ids
     }
 
@@ -135,6 +230,9 @@ impl AbstractObjectInfo {
             elem.replace_abstract_id(old_id, new_id, offset_adjustment);
         }
         self.memory.clear_top_values();
+        if let Some(element_value) = self.element_value.as_mut() {
+            element_value.replace_abstract_id(old_id, new_id, offset_adjustment);
+        }
         if self.pointer_targets.get(&old_id).is_some() {
             self.pointer_targets.remove(&old_id);
             self.pointer_targets.insert(new_id.clone());
@@ -162,6 +260,9 @@ impl AbstractObjectInfo {
             value.remove_ids(ids_to_remove);
         }
         self.memory.clear_top_values(); // In case the previous operation left *Top* values in the memory struct.
+        if let Some(element_value) = self.element_value.as_mut() {
+            element_value.remove_ids(ids_to_remove);
+        }
     }
 
     /// Get the state of the memory object.
@@ -179,6 +280,7 @@ impl AbstractObjectInfo {
     /// which may include writing pointers to targets from the `additional_targets` set to the object.
     pub fn assume_arbitrary_writes(&mut self, additional_targets: &BTreeSet<AbstractIdentifier>) {
         self.memory = MemRegion::new(self.memory.get_address_bytesize());
+        self.element_value = None;
         self.pointer_targets
             .extend(additional_targets.iter().cloned());
     }
@@ -227,6 +329,11 @@ impl AbstractObjectInfo {
 impl AbstractDomain for AbstractObjectInfo {
     /// Merge two abstract objects
     fn merge(&self, other: &Self) -> Self {
+        self.merge_with_mode(other, MergeMode::default())
+    }
+
+    /// Merge two abstract objects, passing the merge mode on to the contained values.
+    fn merge_with_mode(&self, other: &Self, mode: MergeMode) -> Self {
         AbstractObjectInfo {
             pointer_targets: self
                 .pointer_targets
@@ -236,7 +343,13 @@ impl AbstractDomain for AbstractObjectInfo {
             is_unique: self.is_unique && other.is_unique,
             state: same_or_none(&self.state, &other.state),
             type_: same_or_none(&self.type_, &other.type_),
-            memory: self.memory.merge(&other.memory),
+            memory: self.memory.merge_with_mode(&other.memory, mode),
+            element_value: match (&self.element_value, &other.element_value) {
+                (Some(left), Some(right)) if left.bytesize() == right.bytesize() => {
+                    Some(left.merge_with_mode(right, mode))
+                }
+                _ => None,
+            },
         }
     }
 
@@ -271,6 +384,9 @@ impl AbstractObjectInfo {
             "memory".to_string(),
             serde_json::Value::Object(memory.collect()),
         ));
+        if let Some(element_value) = &self.element_value {
+            elements.push(("element_value".to_string(), element_value.to_json_compact()));
+        }
         serde_json::Value::Object(elements.into_iter().collect())
     }
 }
@@ -309,6 +425,7 @@ mod tests {
             state: Some(ObjectState::Alive),
             type_: Some(ObjectType::Heap),
             memory: MemRegion::new(ByteSize::new(8)),
+            element_value: None,
         };
         AbstractObject(Arc::new(obj_info))
     }
@@ -328,6 +445,26 @@ mod tests {
         )
     }
 
+    #[test]
+    fn write_to_offset_interval() {
+        let mut object = new_abstract_object();
+        object.set_value(new_data(1), &bv(0)).unwrap();
+        object.set_value(new_data(1), &bv(16)).unwrap();
+        object.set_value(new_data(2), &bv(32)).unwrap();
+        let offsets = StridedInterval::new(ByteSize::new(8), 0, 16, 16).unwrap();
+        object
+            .set_value(new_data(1), &BitvectorDomain::Interval(offsets))
+            .unwrap();
+        assert_eq!(
+            object.get_value(Bitvector::from_i64(16), ByteSize::new(8)),
+            new_data(1)
+        );
+        assert_eq!(
+            object.get_value(Bitvector::from_i64(32), ByteSize::new(8)),
+            new_data(2)
+        );
+    }
+
     #[test]
     fn abstract_object() {
         let mut object = new_abstract_object();
@@ -429,4 +566,41 @@ mod tests {
                 .collect()
         );
     }
+
+    #[test]
+    fn array_object() {
+        let string_id = new_id("time1", "RAX");
+        let element = Data::Pointer(PointerDomain::new(string_id.clone(), bv(0)));
+        let mut object =
+            AbstractObject::new_array(ObjectType::Heap, ByteSize::new(8), Some(element.clone()));
+        assert!(!object.is_unique);
+        assert!(object
+            .get_referenced_ids_overapproximation()
+            .contains(&string_id));
+        assert_eq!(
+            object.get_value(Bitvector::from_i64(16), ByteSize::new(8)),
+            element
+        );
+        assert_eq!(
+            object.get_value(Bitvector::from_i64(4), ByteSize::new(8)),
+            Data::Top(ByteSize::new(8))
+        );
+        assert_eq!(
+            object.get_value(Bitvector::from_i64(-8), ByteSize::new(8)),
+            Data::Top(ByteSize::new(8))
+        );
+
+        object.set_value(new_data(0), &bv(8)).unwrap();
+        assert_eq!(
+            object.get_value(Bitvector::from_i64(8), ByteSize::new(8)),
+            new_data(0).merge(&element)
+        );
+        object
+            .set_value(new_data(0), &BitvectorDomain::new_top(ByteSize::new(8)))
+            .unwrap();
+        assert_eq!(
+            object.get_value(Bitvector::from_i64(8), ByteSize::new(8)),
+            element.merge(&new_data(0))
+        );
+    }
 }
@@ -2,6 +2,7 @@ use super::object::*;
 use super::Data;
 use crate::abstract_domain::*;
 use crate::prelude::*;
+use crate::utils::shared_map::SharedMap;
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
 
@@ -18,7 +19,9 @@ pub struct AbstractObjectList {
     /// This offset determines where the zero offset corresponding to the abstract identifier inside the object is.
     /// Note that this offset may be a `Top` element
     /// if the exact offset corresponding to the identifier is unknown.
-    objects: BTreeMap<AbstractIdentifier, (AbstractObject, BitvectorDomain)>,
+    ///
+    /// The map is shared between clones of the object list until one of them is modified.
+    objects: SharedMap<AbstractIdentifier, (AbstractObject, BitvectorDomain)>,
 }
 
 impl AbstractObjectList {
@@ -37,7 +40,9 @@ impl AbstractObjectList {
                 Bitvector::zero(apint::BitWidth::from(address_bytesize)).into(),
             ),
         );
-        AbstractObjectList { objects }
+        AbstractObjectList {
+            objects: objects.into(),
+        }
     }
 
     /// Check the state of a memory object at a given address.
@@ -139,9 +144,14 @@ impl AbstractObjectList {
         offset_adjustment: &BitvectorDomain,
     ) {
         let negative_offset = -offset_adjustment.clone();
-        for (object, _) in self.objects.values_mut() {
-            object.replace_abstract_id(old_id, new_id, &negative_offset);
-        }
+        self.objects.update_values(
+            |(object, _)| {
+                object
+                    .get_referenced_ids_overapproximation()
+                    .contains(old_id)
+            },
+            |(object, _)| object.replace_abstract_id(old_id, new_id, &negative_offset),
+        );
         if let Some((object, old_offset)) = self.objects.remove(old_id) {
             let new_offset = old_offset + offset_adjustment.clone();
             self.objects.insert(new_id.clone(), (object, new_offset));
@@ -175,6 +185,29 @@ impl AbstractObjectList {
         }
     }
 
+    /// Add a new abstract object representing one or more arrays of unknown length to the object list.
+    /// The offset of the object is set to zero.
+    ///
+    /// If an element value is given, all array elements that were not explicitly written to have this value.
+    /// If an object with the same ID already exists, it is merged with the newly created object.
+    pub fn add_array_object(
+        &mut self,
+        object_id: AbstractIdentifier,
+        type_: ObjectType,
+        address_bytesize: ByteSize,
+        element_value: Option<Data>,
+    ) {
+        let new_object = AbstractObject::new_array(type_, address_bytesize, element_value);
+        let zero_offset: BitvectorDomain =
+            Bitvector::zero(apint::BitWidth::from(address_bytesize)).into();
+        if let Some((object, offset)) = self.objects.get_mut(&object_id) {
+            *object = object.merge(&new_object);
+            *offset = offset.merge(&zero_offset);
+        } else {
+            self.objects.insert(object_id, (new_object, zero_offset));
+        }
+    }
+
     /// Return all IDs that may be referenced by the memory object pointed to by the given ID.
     /// The returned set is an overapproximation of the actual referenced IDs.
     pub fn get_referenced_ids_overapproximation(
@@ -291,9 +324,14 @@ impl AbstractObjectList {
                 self.objects.remove(id);
             }
         }
-        for (object, _) in self.objects.values_mut() {
-            object.remove_ids(ids_to_remove);
-        }
+        self.objects.update_values(
+            |(object, _)| {
+                !object
+                    .get_referenced_ids_overapproximation()
+                    .is_disjoint(ids_to_remove)
+            },
+            |(object, _)| object.remove_ids(ids_to_remove),
+        );
     }
 
     // Return the object type of a memory object.
@@ -318,11 +356,19 @@ impl AbstractDomain for AbstractObjectList {
     /// to decide, how to correctly represent and handle cases,
     /// where more than one ID should point to the same object.
     fn merge(&self, other: &Self) -> Self {
+        self.merge_with_mode(other, MergeMode::default())
+    }
+
+    /// Merge two abstract object lists, passing the merge mode on to the merged objects.
+    fn merge_with_mode(&self, other: &Self, mode: MergeMode) -> Self {
+        if self.objects.ptr_eq(&other.objects) {
+            return self.clone();
+        }
         let mut merged_objects = self.objects.clone();
         for (id, (other_object, other_offset)) in other.objects.iter() {
             if let Some((object, offset)) = merged_objects.get_mut(id) {
-                *object = object.merge(other_object);
-                *offset = offset.merge(other_offset);
+                *object = object.merge_with_mode(other_object, mode);
+                *offset = offset.merge_with_mode(other_offset, mode);
             } else {
                 merged_objects.insert(id.clone(), (other_object.clone(), other_offset.clone()));
             }
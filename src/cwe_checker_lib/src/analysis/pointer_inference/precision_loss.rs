@@ -0,0 +1,131 @@
+//! Tracking of precision losses caused by pointers with too many possible targets.
+//!
+//! The target sets of pointers grow with each merge of states in which the pointers point to different objects.
+//! Pointers with very large target sets slow down the analysis and are rarely useful for the checks,
+//! so pointers with more targets than the configured maximum are replaced by *Top*.
+//! Each replacement is recorded together with the definition responsible for it.
+//! The sites with the most replacements are reported after the analysis,
+//! so that one can, for example, model the involved functions or adjust the maximum.
+
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::LogMessage;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// A program point at which pointers with too many targets were replaced by *Top*.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct PrecisionLossSite {
+    /// The TID of the definition whose result was replaced.
+    pub tid: Tid,
+    /// The number of replacements at the definition during the fixpoint computation.
+    pub occurrences: u64,
+    /// The largest number of targets of a replaced pointer.
+    pub max_targets: usize,
+}
+
+/// Collects the precision losses of a pointer inference computation.
+///
+/// The recorder is shared between all threads working on the computation.
+#[derive(Debug, Default)]
+pub struct PrecisionLossRecorder {
+    /// Maps the TIDs of definitions to the number of replacements and the largest number of targets.
+    sites: Mutex<HashMap<Tid, (u64, usize)>>,
+}
+
+impl PrecisionLossRecorder {
+    /// Record that a pointer with the given number of targets was replaced by *Top* at the definition.
+    pub fn record(&self, def_tid: &Tid, number_of_targets: usize) {
+        let mut sites = self.sites.lock().unwrap();
+        let (occurrences, max_targets) = sites.entry(def_tid.clone()).or_insert((0, 0));
+        *occurrences += 1;
+        *max_targets = (*max_targets).max(number_of_targets);
+    }
+
+    /// Get all recorded sites sorted by their number of replacements in descending order.
+    pub fn get_sites(&self) -> Vec<PrecisionLossSite> {
+        let mut sites: Vec<PrecisionLossSite> = self
+            .sites
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(tid, (occurrences, max_targets))| PrecisionLossSite {
+                tid: tid.clone(),
+                occurrences: *occurrences,
+                max_targets: *max_targets,
+            })
+            .collect();
+        sites.sort_by(|site, other| {
+            other
+                .occurrences
+                .cmp(&site.occurrences)
+                .then_with(|| site.tid.cmp(&other.tid))
+        });
+        sites
+    }
+}
+
+/// Generate log messages summarizing the precision losses.
+///
+/// The first message gives the total number of replacements,
+/// followed by one message for each of the `max_reported_sites` sites with the most replacements.
+/// Returns no log messages if no precision loss was recorded.
+pub fn get_summary_logs(
+    sites: &[PrecisionLossSite],
+    max_pointer_targets: usize,
+    max_reported_sites: usize,
+) -> Vec<LogMessage> {
+    if sites.is_empty() {
+        return Vec::new();
+    }
+    let total: u64 = sites.iter().map(|site| site.occurrences).sum();
+    let mut logs = vec![LogMessage::new_info(format!(
+        "Precision loss: Pointers with more than {} targets were replaced by Top {} times at {} program points.",
+        max_pointer_targets,
+        total,
+        sites.len()
+    ))
+    .source("Pointer Inference")];
+    for site in sites.iter().take(max_reported_sites) {
+        logs.push(
+            LogMessage::new_info(format!(
+                "Precision loss: Pointers replaced by Top {} times with up to {} targets.",
+                site.occurrences, site.max_targets
+            ))
+            .location(site.tid.clone())
+            .source("Pointer Inference"),
+        );
+    }
+    logs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn precision_loss_summary() {
+        let recorder = PrecisionLossRecorder::default();
+        recorder.record(&Tid::new("def_1"), 20);
+        recorder.record(&Tid::new("def_2"), 17);
+        recorder.record(&Tid::new("def_2"), 30);
+        let sites = recorder.get_sites();
+        assert_eq!(
+            sites[0],
+            PrecisionLossSite {
+                tid: Tid::new("def_2"),
+                occurrences: 2,
+                max_targets: 30,
+            }
+        );
+        assert_eq!(sites[1].tid, Tid::new("def_1"));
+        let logs = get_summary_logs(&sites, 16, 1);
+        assert_eq!(logs.len(), 2);
+        assert_eq!(
+            logs[0].text,
+            "Precision loss: Pointers with more than 16 targets were replaced by Top 3 times at 2 program points."
+        );
+        assert_eq!(logs[1].location, Some(Tid::new("def_2")));
+        assert!(get_summary_logs(&[], 16, 10).is_empty());
+    }
+}
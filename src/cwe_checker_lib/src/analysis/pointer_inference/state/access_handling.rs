@@ -27,6 +27,11 @@ impl State {
         })
     }
 
+    /// Get the values of all registers for which some value is known.
+    pub fn get_all_register_values(&self) -> &im::OrdMap<Variable, Data> {
+        &self.register
+    }
+
     /// Set the value of a register.
     ///
     /// Returns an error if the variable is not a register.
@@ -93,7 +98,11 @@ impl State {
                         Err(err) => Err(err),
                     }
                 }
-                Data::Value(BitvectorDomain::Top(_)) | Data::Top(_) => Ok(()),
+                Data::Value(BitvectorDomain::Top(_))
+                | Data::Value(BitvectorDomain::Partial(..))
+                | Data::Value(BitvectorDomain::Interval(_))
+                | Data::Value(BitvectorDomain::KnownBits(_))
+                | Data::Top(_) => Ok(()),
             }
         }
     }
@@ -140,7 +149,17 @@ impl State {
         size: ByteSize,
         global_memory: &RuntimeMemoryImage,
     ) -> Result<Data, Error> {
-        let address = self.adjust_pointer_for_read(&self.eval(address)?);
+        self.load_value_from_address(&self.eval(address)?, size, global_memory)
+    }
+
+    /// Return the data of the given size stored at the given (already evaluated) address.
+    pub fn load_value_from_address(
+        &self,
+        address: &Data,
+        size: ByteSize,
+        global_memory: &RuntimeMemoryImage,
+    ) -> Result<Data, Error> {
+        let address = self.adjust_pointer_for_read(address);
         match address {
             Data::Value(BitvectorDomain::Value(address_bitvector)) => {
                 let loaded_value = global_memory.read(&address_bitvector, size)?;
@@ -150,7 +169,11 @@ impl State {
                     Ok(Data::Value(loaded_value))
                 }
             }
-            Data::Value(BitvectorDomain::Top(_)) | Data::Top(_) => Ok(Data::new_top(size)),
+            Data::Value(BitvectorDomain::Top(_))
+            | Data::Value(BitvectorDomain::Partial(..))
+            | Data::Value(BitvectorDomain::Interval(_))
+            | Data::Value(BitvectorDomain::KnownBits(_))
+            | Data::Top(_) => Ok(Data::new_top(size)),
             Data::Pointer(_) => Ok(self.memory.get_value(&address, size)?),
         }
     }
@@ -194,7 +217,10 @@ impl State {
                                 new_targets.insert(id.clone(), offset.clone());
                             }
                         }
-                        BitvectorDomain::Top(_bytesize) => {
+                        BitvectorDomain::Top(_)
+                        | BitvectorDomain::Partial(..)
+                        | BitvectorDomain::Interval(_)
+                        | BitvectorDomain::KnownBits(_) => {
                             for caller_id in self.caller_stack_ids.iter() {
                                 new_targets.insert(caller_id.clone(), offset.clone());
                             }
@@ -257,6 +283,34 @@ impl State {
         }
     }
 
+    /// Evaluate the value of the parameter with the given index of a call to the given extern symbol.
+    ///
+    /// If the parameters of the extern symbol are unknown,
+    /// the parameter registers of its calling convention are used instead.
+    pub fn eval_nth_parameter(
+        &self,
+        project: &Project,
+        extern_symbol: &ExternSymbol,
+        index: usize,
+        global_memory: &RuntimeMemoryImage,
+    ) -> Result<Data, Error> {
+        if extern_symbol.parameters.is_empty() {
+            let register_name = extern_symbol
+                .get_calling_convention(project)
+                .parameter_register
+                .get(index)
+                .ok_or_else(|| anyhow!("Parameter register not found"))?;
+            self.get_register_by_name(register_name)
+                .ok_or_else(|| anyhow!("Parameter register not found"))
+        } else {
+            let parameter = extern_symbol
+                .parameters
+                .get(index)
+                .ok_or_else(|| anyhow!("Parameter not found"))?;
+            self.eval_parameter_arg(parameter, &project.stack_pointer_register, global_memory)
+        }
+    }
+
     /// Check if an expression contains a use-after-free
     pub fn contains_access_of_dangling_memory(&self, def: &Def) -> bool {
         match def {
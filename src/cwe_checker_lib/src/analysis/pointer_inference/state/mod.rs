@@ -1,19 +1,32 @@
+use super::object::ObjectType;
 use super::object_list::AbstractObjectList;
 use super::Data;
 use crate::abstract_domain::*;
 use crate::intermediate_representation::*;
 use crate::prelude::*;
 use crate::utils::binary::RuntimeMemoryImage;
+use crate::utils::extern_stubs::StubBehavior;
+use crate::utils::shared_map::SharedMap;
+use std::borrow::Cow;
 use std::collections::{BTreeMap, BTreeSet};
 
 mod access_handling;
 
+/// The maximal number of caller stack frames of a state
+/// that are checked for whether a new callsite can share their context.
+///
+/// Each check clones, renames and merges the whole state,
+/// so the limit is kept small to bound the cost of merging states of functions with many callsites.
+const MAX_SHARED_CONTEXT_CANDIDATES: usize = 4;
+
 /// Contains all information known about the state of a program at a specific point of time.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct State {
     /// Maps a register variable to the data known about its content.
     /// A variable not contained in the map has value `Data::Top(..)`, i.e. nothing is known about its content.
-    register: BTreeMap<Variable, Data>,
+    ///
+    /// The map is shared between clones of the state until one of them is modified.
+    register: SharedMap<Variable, Data>,
     /// The list of all known memory objects.
     pub memory: AbstractObjectList,
     /// The abstract identifier of the current stack frame.
@@ -26,6 +39,18 @@ pub struct State {
     /// Writes to the current stack frame with offset >= 0 are written to *all* caller stack frames.
     /// Reads to the current stack frame with offset >= 0 are handled as merge-read from all caller stack frames.
     pub caller_stack_ids: BTreeSet<AbstractIdentifier>,
+    /// IDs of callsites that share the context of another callsite,
+    /// mapped to the ID in `caller_stack_ids` that represents the caller stack frames of both callsites.
+    ///
+    /// A callsite shares the context of another callsite
+    /// if its state at the start of the function is already contained in the state of the other callsite
+    /// (after renaming the caller stack IDs).
+    /// This prevents an explosion of the number of caller stack frames for small functions called from many callsites
+    /// with identical abstract arguments, e.g. helper functions of C++ template instantiations.
+    ///
+    /// The field is missing in states serialized by older versions, where it is empty.
+    #[serde(default)]
+    pub shared_caller_stack_ids: BTreeMap<AbstractIdentifier, AbstractIdentifier>,
     /// All IDs of objects that are known to some caller.
     /// This is an overapproximation of all object IDs that may have been passed as parameters to the function.
     /// The corresponding objects are not allowed to be deleted (even if no pointer to them exists anymore)
@@ -55,14 +80,76 @@ impl State {
             .into(),
         );
         State {
-            register,
+            register: register.into(),
             memory: AbstractObjectList::from_stack_id(stack_id.clone(), stack_register.size),
             stack_id,
             caller_stack_ids: BTreeSet::new(),
+            shared_caller_stack_ids: BTreeMap::new(),
             ids_known_to_caller: BTreeSet::new(),
         }
     }
 
+    /// Model the `argc`, `argv` and `envp` parameters of a `main` function in the state.
+    ///
+    /// `argv` and `envp` are NUL-terminated arrays of pointers to strings and their length is unknown.
+    /// Each array is represented by an array object whose elements point to a non-unique object
+    /// representing all strings contained in the array.
+    /// Since the content of these strings is controlled by the user,
+    /// taint analyses can treat the string objects as sources of untrusted input.
+    /// Note that the terminating `NULL` pointers of the arrays are not represented by the element values.
+    ///
+    /// Only parameters passed in registers of the standard calling convention of the project are modeled.
+    /// `argc` remains an unknown value.
+    pub fn add_main_function_parameters(&mut self, project: &Project, function_tid: &Tid) {
+        let calling_convention = match project.get_standard_calling_convention() {
+            Some(calling_convention) => calling_convention,
+            None => return,
+        };
+        let pointer_bytesize = project.get_pointer_bytesize();
+        for parameter_name in calling_convention.parameter_register.iter().skip(1).take(2) {
+            let parameter = Variable {
+                name: parameter_name.clone(),
+                size: pointer_bytesize,
+                is_temp: false,
+            };
+            let array_id = AbstractIdentifier::new(
+                function_tid.clone(),
+                AbstractLocation::from_var(&parameter).unwrap(),
+            );
+            let strings_id = AbstractIdentifier::new(
+                function_tid.clone(),
+                AbstractLocation::Pointer(
+                    parameter_name.clone(),
+                    AbstractMemoryLocation::Location {
+                        offset: 0,
+                        size: u64::from(pointer_bytesize) as usize,
+                    },
+                ),
+            );
+            self.memory.add_array_object(
+                strings_id.clone(),
+                ObjectType::Heap,
+                pointer_bytesize,
+                None,
+            );
+            let string_pointer = PointerDomain::new(
+                strings_id,
+                Bitvector::zero(apint::BitWidth::from(pointer_bytesize)).into(),
+            );
+            self.memory.add_array_object(
+                array_id.clone(),
+                ObjectType::Heap,
+                pointer_bytesize,
+                Some(string_pointer.into()),
+            );
+            let array_pointer = PointerDomain::new(
+                array_id,
+                Bitvector::zero(apint::BitWidth::from(pointer_bytesize)).into(),
+            );
+            self.set_register(&parameter, array_pointer.into());
+        }
+    }
+
     /// Clear all non-callee-saved registers from the state.
     /// This automatically also removes all virtual registers.
     /// The parameter is a list of callee-saved register names.
@@ -84,6 +171,22 @@ impl State {
         self.register = register;
     }
 
+    /// Clear all registers from the state that are clobbered by a call with the given stub behavior
+    /// to a function with the given calling convention.
+    pub fn clear_clobbered_register(
+        &mut self,
+        behavior: &StubBehavior,
+        calling_convention: &CallingConvention,
+    ) {
+        let register = self
+            .register
+            .iter()
+            .filter(|(register, _)| !behavior.clobbers_register(&register.name, calling_convention))
+            .map(|(register, value)| (register.clone(), value.clone()))
+            .collect();
+        self.register = register;
+    }
+
     /// Mark those parameter values of an extern function call, that are passed on the stack,
     /// as unknown data (since the function may modify them).
     pub fn clear_stack_parameter(
@@ -126,9 +229,12 @@ impl State {
         new_id: &AbstractIdentifier,
         offset_adjustment: &BitvectorDomain,
     ) {
-        for register_data in self.register.values_mut() {
-            register_data.replace_abstract_id(old_id, new_id, &(-offset_adjustment.clone()));
-        }
+        self.register.update_values(
+            |register_data| register_data.references_id(old_id),
+            |register_data| {
+                register_data.replace_abstract_id(old_id, new_id, &(-offset_adjustment.clone()))
+            },
+        );
         self.memory
             .replace_abstract_id(old_id, new_id, offset_adjustment);
         if &self.stack_id == old_id {
@@ -249,24 +355,33 @@ impl State {
     ///
     /// Example: At the start of a basic block no virtual registers should be alive.
     pub fn remove_virtual_register(&mut self) {
-        self.register = self
-            .register
-            .clone()
-            .into_iter()
-            .filter(|(register, _value)| !register.is_temp)
-            .collect();
+        // Only rebuild the map if necessary, so that it stays shared with clones of the state otherwise.
+        if self.register.keys().any(|register| register.is_temp) {
+            self.register = self
+                .register
+                .iter()
+                .filter(|(register, _value)| !register.is_temp)
+                .map(|(register, value)| (register.clone(), value.clone()))
+                .collect();
+        }
     }
 
     /// Recursively remove all `caller_stack_ids` not corresponding to the given caller.
     pub fn remove_other_caller_stack_ids(&mut self, caller_id: &AbstractIdentifier) {
         let mut ids_to_remove = self.caller_stack_ids.clone();
         ids_to_remove.remove(caller_id);
-        for register_value in self.register.values_mut() {
-            register_value.remove_ids(&ids_to_remove);
-        }
+        self.register.update_values(
+            |register_value| {
+                ids_to_remove
+                    .iter()
+                    .any(|id| register_value.references_id(id))
+            },
+            |register_value| register_value.remove_ids(&ids_to_remove),
+        );
         self.memory.remove_ids(&ids_to_remove);
         self.caller_stack_ids = BTreeSet::new();
         self.caller_stack_ids.insert(caller_id.clone());
+        self.shared_caller_stack_ids = BTreeMap::new();
         self.ids_known_to_caller = self
             .ids_known_to_caller
             .difference(&ids_to_remove)
@@ -328,20 +443,54 @@ impl State {
 }
 
 impl AbstractDomain for State {
-    /// Merge two states
+    /// Merge two states.
+    ///
+    /// If one of the states is the state at the start of a function for a callsite unknown to the other state
+    /// and the callsite can share the context of a callsite known to the other state,
+    /// the callsite is added to the shared caller stack IDs instead of adding another caller stack frame.
     fn merge(&self, other: &Self) -> Self {
+        self.merge_with_mode(other, MergeMode::default())
+    }
+
+    /// Merge two states like `merge`, passing the merge mode on to the merged values.
+    fn merge_with_mode(&self, other: &Self, mode: MergeMode) -> Self {
         assert_eq!(self.stack_id, other.stack_id);
-        let mut merged_register = BTreeMap::new();
-        for (register, other_value) in other.register.iter() {
-            if let Some(value) = self.register.get(register) {
-                let merged_value = value.merge(other_value);
-                if !merged_value.is_top() {
-                    // We only have to keep non-*Top* elements.
-                    merged_register.insert(register.clone(), merged_value);
+        if let Some(merged_state) = self
+            .merge_into_shared_context(other, mode)
+            .or_else(|| other.merge_into_shared_context(self, mode))
+        {
+            return merged_state;
+        }
+        self.with_shared_caller_stack_ids_of(other)
+            .merge_without_context_sharing(&other.with_shared_caller_stack_ids_of(self), mode)
+    }
+
+    /// A state has no *Top* element
+    fn is_top(&self) -> bool {
+        false
+    }
+}
+
+impl State {
+    /// Merge two states without checking whether callsites can share their contexts.
+    fn merge_without_context_sharing(&self, other: &State, mode: MergeMode) -> State {
+        // Shared register maps are equal, so merging them would yield the same map again.
+        let merged_register = if self.register.ptr_eq(&other.register) {
+            self.register.clone()
+        } else {
+            let mut merged_register = BTreeMap::new();
+            for (register, other_value) in other.register.iter() {
+                if let Some(value) = self.register.get(register) {
+                    let merged_value = value.merge_with_mode(other_value, mode);
+                    if !merged_value.is_top() {
+                        // We only have to keep non-*Top* elements.
+                        merged_register.insert(register.clone(), merged_value);
+                    }
                 }
             }
-        }
-        let merged_memory_objects = self.memory.merge(&other.memory);
+            merged_register.into()
+        };
+        let merged_memory_objects = self.memory.merge_with_mode(&other.memory, mode);
         State {
             register: merged_register,
             memory: merged_memory_objects,
@@ -351,6 +500,12 @@ impl AbstractDomain for State {
                 .union(&other.caller_stack_ids)
                 .cloned()
                 .collect(),
+            shared_caller_stack_ids: self
+                .shared_caller_stack_ids
+                .iter()
+                .chain(other.shared_caller_stack_ids.iter())
+                .map(|(id, representative_id)| (id.clone(), representative_id.clone()))
+                .collect(),
             ids_known_to_caller: self
                 .ids_known_to_caller
                 .union(&other.ids_known_to_caller)
@@ -359,9 +514,77 @@ impl AbstractDomain for State {
         }
     }
 
-    /// A state has no *Top* element
-    fn is_top(&self) -> bool {
-        false
+    /// If `self` is the state at the start of a function for a single callsite unknown to `other`
+    /// and the state is already contained in `other` after renaming the caller stack ID of the callsite
+    /// to the ID of a callsite known to `other`,
+    /// then return `other` with the callsite of `self` sharing the context of the known callsite.
+    fn merge_into_shared_context(&self, other: &State, mode: MergeMode) -> Option<State> {
+        if self.caller_stack_ids.len() != 1 || !self.shared_caller_stack_ids.is_empty() {
+            return None;
+        }
+        let callsite_id = self.caller_stack_ids.iter().next().unwrap();
+        if other.caller_stack_ids.contains(callsite_id)
+            || other.shared_caller_stack_ids.contains_key(callsite_id)
+        {
+            return None;
+        }
+        for representative_id in other
+            .caller_stack_ids
+            .iter()
+            .take(MAX_SHARED_CONTEXT_CANDIDATES)
+        {
+            let mut renamed_state = self.clone();
+            renamed_state.rename_caller_stack_id(callsite_id, representative_id);
+            if renamed_state.merge_without_context_sharing(other, mode) == *other {
+                let mut merged_state = other.clone();
+                merged_state
+                    .shared_caller_stack_ids
+                    .insert(callsite_id.clone(), representative_id.clone());
+                return Some(merged_state);
+            }
+        }
+        None
+    }
+
+    /// Rename all caller stack IDs of `self` that share the context of another callsite according to `other`
+    /// to the ID representing the shared context.
+    fn with_shared_caller_stack_ids_of(&self, other: &State) -> Cow<State> {
+        let shared_ids: Vec<(&AbstractIdentifier, &AbstractIdentifier)> = self
+            .caller_stack_ids
+            .iter()
+            .filter_map(|id| {
+                other
+                    .shared_caller_stack_ids
+                    .get(id)
+                    .map(|representative_id| (id, representative_id))
+            })
+            .collect();
+        if shared_ids.is_empty() {
+            return Cow::Borrowed(self);
+        }
+        let mut state = self.clone();
+        for (id, representative_id) in shared_ids {
+            state.rename_caller_stack_id(id, representative_id);
+            state
+                .shared_caller_stack_ids
+                .insert(id.clone(), representative_id.clone());
+        }
+        Cow::Owned(state)
+    }
+
+    /// Replace the caller stack ID `old_id` with `new_id`.
+    /// Since both IDs represent the caller stack frame at the moment of the call,
+    /// no offset adjustment is necessary.
+    fn rename_caller_stack_id(&mut self, old_id: &AbstractIdentifier, new_id: &AbstractIdentifier) {
+        let address_bytesize = match new_id.get_location() {
+            AbstractLocation::Register(_, bytesize) => *bytesize,
+            AbstractLocation::Pointer(..) => panic!("Caller stack IDs are always register IDs."),
+        };
+        self.replace_abstract_id(
+            old_id,
+            new_id,
+            &Bitvector::zero(apint::BitWidth::from(address_bytesize)).into(),
+        );
     }
 }
 
@@ -392,6 +615,20 @@ impl State {
                     .collect(),
             ),
         );
+        state_map.insert(
+            "shared_caller_stack_ids".into(),
+            Value::Object(
+                self.shared_caller_stack_ids
+                    .iter()
+                    .map(|(id, representative_id)| {
+                        (
+                            format!("{}", id),
+                            Value::String(format!("{}", representative_id)),
+                        )
+                    })
+                    .collect(),
+            ),
+        );
         state_map.insert(
             "ids_known_to_caller".into(),
             Value::Array(
@@ -353,6 +353,54 @@ fn merge_callee_stack_to_caller_stack() {
     assert_eq!(state.memory.get_all_object_ids().len(), 1);
 }
 
+#[test]
+fn share_contexts_of_callsites() {
+    use super::super::object::ObjectType;
+    let callee_state = |callsite: &str, parameter_offset: i64| {
+        let mut state = State::new(&register("RSP"), Tid::new("callee"));
+        let caller_id = new_id(callsite, "RSP");
+        state.memory.add_abstract_object(
+            caller_id.clone(),
+            bv(0),
+            ObjectType::Stack,
+            ByteSize::new(8),
+        );
+        state.caller_stack_ids.insert(caller_id.clone());
+        state.ids_known_to_caller.insert(caller_id.clone());
+        state.set_register(
+            &register("RDI"),
+            PointerDomain::new(caller_id, bv(parameter_offset)).into(),
+        );
+        state
+    };
+    let state_a = callee_state("callsite_a", -8);
+    let state_b = callee_state("callsite_b", -8);
+    let state_c = callee_state("callsite_c", -16);
+    // Callsites with identical abstract arguments share their context.
+    let merged_state = state_b.merge(&state_a);
+    assert_eq!(merged_state.caller_stack_ids.len(), 1);
+    assert_eq!(
+        merged_state
+            .shared_caller_stack_ids
+            .get(&new_id("callsite_b", "RSP")),
+        Some(&new_id("callsite_a", "RSP"))
+    );
+    assert_eq!(merged_state.memory.get_all_object_ids().len(), 2);
+    // Callsites with different abstract arguments do not share their context.
+    let merged_state = state_c.merge(&merged_state);
+    assert_eq!(merged_state.caller_stack_ids.len(), 2);
+    assert_eq!(merged_state.shared_caller_stack_ids.len(), 1);
+    assert_eq!(merged_state.memory.get_all_object_ids().len(), 3);
+    // Merging the state of a callsite sharing a context again does not change the merged state.
+    assert_eq!(state_b.merge(&merged_state), merged_state);
+    // The shared contexts survive a serialization round trip.
+    let serialized = serde_json::to_value(&merged_state).unwrap();
+    assert_eq!(
+        serde_json::from_value::<State>(serialized).unwrap(),
+        merged_state
+    );
+}
+
 #[test]
 fn remove_and_restore_callee_saved_register() {
     let mut state = State::new(&register("RSP"), Tid::new("func_tid"));
@@ -484,3 +532,52 @@ fn global_mem_access() {
         )
         .is_err());
 }
+
+#[test]
+fn main_function_parameters() {
+    let global_memory = RuntimeMemoryImage::mock();
+    let mut project = Project::mock_empty();
+    let mut cconv = CallingConvention::mock();
+    cconv.parameter_register = vec!["RDI".into(), "RSI".into(), "RDX".into()];
+    project.calling_conventions.push(cconv);
+    let mut state = State::new(&register("RSP"), Tid::new("main"));
+    state.add_main_function_parameters(&project, &Tid::new("main"));
+
+    assert_eq!(
+        state.get_register(&register("RSI")).unwrap(),
+        PointerDomain::new(new_id("main", "RSI"), bv(0)).into()
+    );
+    assert!(state.get_register(&register("RDI")).unwrap().is_top());
+    // Load argv[1] and then the first character of the string.
+    state
+        .handle_load(&register("RAX"), &reg_add("RSI", 8), &global_memory)
+        .unwrap();
+    let strings_id = AbstractIdentifier::new(
+        Tid::new("main"),
+        AbstractLocation::Pointer(
+            "RSI".into(),
+            AbstractMemoryLocation::Location { offset: 0, size: 8 },
+        ),
+    );
+    assert_eq!(
+        state.get_register(&register("RAX")).unwrap(),
+        PointerDomain::new(strings_id.clone(), bv(0)).into()
+    );
+    state
+        .handle_load(&register("RCX"), &reg_add("RDX", 16), &global_memory)
+        .unwrap();
+    let envp_strings_id = AbstractIdentifier::new(
+        Tid::new("main"),
+        AbstractLocation::Pointer(
+            "RDX".into(),
+            AbstractMemoryLocation::Location { offset: 0, size: 8 },
+        ),
+    );
+    assert_eq!(
+        state.get_register(&register("RCX")).unwrap(),
+        PointerDomain::new(envp_strings_id, bv(0)).into()
+    );
+    // The string objects are kept alive as long as the arrays are referenced.
+    state.remove_unreferenced_objects();
+    assert!(state.memory.get_all_object_ids().contains(&strings_id));
+}
@@ -0,0 +1,822 @@
+//! Tracking of file descriptors and `FILE*` streams.
+//!
+//! This analysis computes for each call to an extern symbol
+//! which of its parameters may refer to which file descriptors or streams
+//! and whether these resources may already have been closed at the call.
+//! It also computes the states of all resources at the exits of each function.
+//! The results are shared by checks for resource leaks, for uses of closed resources
+//! and for time-of-check-time-of-use bugs.
+//!
+//! Resources are identified by the TID of the call to the extern symbol that created them
+//! (see [`ResourceSet`](crate::abstract_domain::ResourceSet)).
+//! Their values are tracked through registers and through the stack frame of the current function.
+//! Accesses to the stack frame are resolved using the results of the
+//! [Pointer Inference analysis](crate::analysis::pointer_inference).
+//!
+//! The analysis is intraprocedural:
+//! Resources passed to a function as parameters or returned from internal functions are not tracked
+//! and calls to internal functions are assumed to leave the states of all tracked resources unchanged.
+
+use crate::abstract_domain::{
+    AbstractDomain, BitvectorDomain, ResourceKind, ResourceSet, ResourceState, ResourceStateMap,
+};
+use crate::analysis::block_dataflow::{self, Direction};
+use crate::analysis::graph::Node;
+use crate::analysis::interprocedural_fixpoint_generic::NodeValue;
+use crate::analysis::pointer_inference::{Data, PointerInference, State as PointerInferenceState};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::binary::RuntimeMemoryImage;
+use crate::utils::cancellation::CancellationToken;
+use crate::utils::extern_stubs;
+use crate::utils::log::LogMessage;
+use std::collections::{BTreeMap, HashMap};
+
+/// The configuration struct for the resource tracking.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(default)]
+pub struct Config {
+    /// Extern symbols returning a newly created file descriptor.
+    pub file_descriptor_symbols: Vec<String>,
+    /// Extern symbols returning a newly created `FILE*` stream.
+    pub stream_symbols: Vec<String>,
+    /// Extern symbols closing the resource given to them as their first parameter.
+    pub close_symbols: Vec<String>,
+    /// The behavior of calls to all other extern symbols.
+    /// Copied from the `ExternStubs` configuration section.
+    pub extern_stubs: extern_stubs::Config,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        let to_strings = |symbols: &[&str]| symbols.iter().map(|s| s.to_string()).collect();
+        Config {
+            file_descriptor_symbols: to_strings(&[
+                "open", "open64", "openat", "creat", "socket", "accept", "dup", "dup2",
+            ]),
+            stream_symbols: to_strings(&[
+                "fopen", "fopen64", "fdopen", "freopen", "popen", "tmpfile",
+            ]),
+            close_symbols: to_strings(&["close", "fclose", "pclose"]),
+            extern_stubs: extern_stubs::Config::default(),
+        }
+    }
+}
+
+/// The resource information at a call to an extern symbol.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct CallSiteInfo {
+    /// The resources that the parameters of the extern symbol may refer to,
+    /// in the order of the parameters of the symbol.
+    pub parameters: Vec<ResourceSet>,
+    /// The states of all resources of the function directly before the call.
+    pub resources: ResourceStateMap,
+}
+
+impl CallSiteInfo {
+    /// Return the identifiers of all resources that the parameters may refer to
+    /// and that may have been closed before the call.
+    pub fn get_possibly_closed_parameters(&self) -> Vec<Tid> {
+        let mut closed: Vec<Tid> = self
+            .parameters
+            .iter()
+            .flat_map(|param| self.resources.get_possibly_closed(param))
+            .collect();
+        closed.sort();
+        closed.dedup();
+        closed
+    }
+
+    /// Returns `true` if a parameter refers to exactly one resource
+    /// and this resource was closed on all paths to the call.
+    pub fn has_definitely_closed_parameter(&self) -> bool {
+        self.parameters.iter().any(|param| {
+            param
+                .get_unique()
+                .and_then(|id| self.resources.get(id))
+                .map_or(false, |resource| resource.state == ResourceState::Closed)
+        })
+    }
+}
+
+/// The results of the resource tracking for all functions of a project.
+#[derive(Debug, Default, Clone)]
+pub struct ResourceTracking {
+    /// The resource information for each call to an extern symbol.
+    call_sites: HashMap<Tid, CallSiteInfo>,
+    /// The states of the resources of each function at its return instructions.
+    exit_states: HashMap<Tid, ResourceStateMap>,
+    /// Set to `true` if the computation was stopped before all functions were analyzed.
+    interrupted: bool,
+    /// Log messages for the functions where the computation did not stabilize.
+    logs: Vec<LogMessage>,
+}
+
+impl ResourceTracking {
+    /// Compute the resource tracking for all functions of the project.
+    ///
+    /// If the cancellation token gets cancelled, the remaining functions are not analyzed
+    /// and the results are marked as interrupted (see [`ResourceTracking::was_interrupted`]).
+    pub fn compute(
+        project: &Project,
+        pointer_inference: &PointerInference,
+        global_memory: &RuntimeMemoryImage,
+        config: &Config,
+        cancellation: &CancellationToken,
+    ) -> ResourceTracking {
+        let context = Context::new(project, config);
+        let graph = pointer_inference.get_graph();
+        let mut stack_infos_per_sub: HashMap<Tid, HashMap<Tid, StackInfo>> = HashMap::new();
+        for node in graph.node_indices() {
+            if let Node::BlkStart(block, sub) = graph[node] {
+                if let Some(NodeValue::Value(state)) = pointer_inference.get_node_value(node) {
+                    let stack_info =
+                        StackInfo::compute(project, block, state.clone(), global_memory);
+                    stack_infos_per_sub
+                        .entry(sub.tid.clone())
+                        .or_insert_with(HashMap::new)
+                        .insert(block.tid.clone(), stack_info);
+                }
+            }
+        }
+        let empty_stack_infos = HashMap::new();
+        let mut results = ResourceTracking::default();
+        for sub in project.program.term.subs.iter() {
+            if cancellation.is_cancelled() {
+                results.interrupted = true;
+                break;
+            }
+            let stack_infos = stack_infos_per_sub
+                .get(&sub.tid)
+                .unwrap_or(&empty_stack_infos);
+            context.compute_function(sub, stack_infos, &mut results);
+        }
+        results
+    }
+
+    /// Get the resource information for the call with the given TID.
+    /// Only calls to extern symbols in reachable blocks have associated information.
+    pub fn get_call_site(&self, call_tid: &Tid) -> Option<&CallSiteInfo> {
+        self.call_sites.get(call_tid)
+    }
+
+    /// Iterate over the resource information of all calls to extern symbols.
+    pub fn iter_call_sites(&self) -> impl Iterator<Item = (&Tid, &CallSiteInfo)> {
+        self.call_sites.iter()
+    }
+
+    /// Get the states of the resources of the given function at its return instructions.
+    /// Returns `None` if no return instruction of the function is reachable.
+    pub fn get_exit_resources(&self, sub_tid: &Tid) -> Option<&ResourceStateMap> {
+        self.exit_states.get(sub_tid)
+    }
+
+    /// Returns `true` if the computation was stopped before all functions were analyzed,
+    /// so that the results may be incomplete.
+    pub fn was_interrupted(&self) -> bool {
+        self.interrupted
+    }
+
+    /// Get the log messages generated during the computation,
+    /// e.g. for functions where the computation did not stabilize and thus have no results.
+    pub fn get_log_messages(&self) -> &[LogMessage] {
+        &self.logs
+    }
+}
+
+#[cfg(test)]
+impl ResourceTracking {
+    /// Create resource tracking results containing only the given call site information.
+    pub fn mock(call_sites: HashMap<Tid, CallSiteInfo>) -> ResourceTracking {
+        ResourceTracking {
+            call_sites,
+            exit_states: HashMap::new(),
+            interrupted: false,
+            logs: Vec::new(),
+        }
+    }
+}
+
+/// Information about the accesses to the current stack frame in a block,
+/// computed from the Pointer Inference state at the start of the block.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+struct StackInfo {
+    /// The stack offsets accessed by load and store instructions.
+    /// The offset is `None` if the instruction may access the stack frame at an unknown offset.
+    /// Instructions that do not access the stack frame are not contained in the map.
+    accesses: HashMap<Tid, Option<i64>>,
+    /// The offset of the stack pointer in the current stack frame at the end of the block if it is known.
+    stack_pointer_at_end: Option<i64>,
+}
+
+impl StackInfo {
+    /// Compute the stack accesses of the block by stepping through its `Def` instructions.
+    fn compute(
+        project: &Project,
+        block: &Term<Blk>,
+        mut state: PointerInferenceState,
+        global_memory: &RuntimeMemoryImage,
+    ) -> StackInfo {
+        let mut accesses = HashMap::new();
+        for def in block.term.defs.iter() {
+            match &def.term {
+                Def::Store { address, value } => {
+                    match get_stack_offset(&state, address) {
+                        Ok(Some(offset)) => {
+                            accesses.insert(def.tid.clone(), Some(offset));
+                        }
+                        Ok(None) => (),
+                        Err(_) => {
+                            accesses.insert(def.tid.clone(), None);
+                        }
+                    }
+                    let _ = state.handle_store(address, value, global_memory);
+                }
+                Def::Load { var, address } => {
+                    match get_stack_offset(&state, address) {
+                        Ok(Some(offset)) => {
+                            accesses.insert(def.tid.clone(), Some(offset));
+                        }
+                        Ok(None) => (),
+                        Err(_) => {
+                            accesses.insert(def.tid.clone(), None);
+                        }
+                    }
+                    let _ = state.handle_load(var, address, global_memory);
+                }
+                Def::Assign { var, value } => {
+                    let _ = state.handle_register_assign(var, value);
+                }
+            }
+        }
+        let stack_pointer = Expression::Var(project.stack_pointer_register.clone());
+        StackInfo {
+            accesses,
+            stack_pointer_at_end: get_stack_offset(&state, &stack_pointer).unwrap_or(None),
+        }
+    }
+}
+
+/// Evaluate the address expression in the given state.
+/// Return `Ok(Some(offset))` if the address is an exactly known offset into the current stack frame,
+/// `Ok(None)` if the address does not point to the current stack frame
+/// and an error if the address may point to the current stack frame at an unknown offset.
+fn get_stack_offset(
+    state: &PointerInferenceState,
+    address: &Expression,
+) -> Result<Option<i64>, Error> {
+    match state.eval(address) {
+        Ok(Data::Pointer(pointer)) => {
+            if !pointer.targets().contains_key(&state.stack_id) {
+                return Ok(None);
+            }
+            if pointer.targets().len() == 1 {
+                if let Some(BitvectorDomain::Value(offset)) = pointer.targets().get(&state.stack_id)
+                {
+                    if let Ok(offset) = offset.try_to_i64() {
+                        return Ok(Some(offset));
+                    }
+                }
+            }
+            Err(anyhow!("Unknown stack offset"))
+        }
+        Ok(_) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// The state of the resource tracking at a specific program point.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+struct State {
+    /// The resources that the values of registers may refer to.
+    registers: BTreeMap<String, ResourceSet>,
+    /// The resources that values on the current stack frame may refer to,
+    /// indexed by their offset in the stack frame, together with the size of the values.
+    stack: BTreeMap<i64, (ByteSize, ResourceSet)>,
+    /// The states of all resources created in the function so far.
+    resources: ResourceStateMap,
+}
+
+impl State {
+    /// Evaluate which resources the value of the expression may refer to.
+    ///
+    /// Only expressions that (partially) copy register values keep the resources of the register.
+    fn eval(&self, expression: &Expression) -> ResourceSet {
+        match expression {
+            Expression::Var(var) => self.registers.get(&var.name).cloned().unwrap_or_default(),
+            Expression::Cast { arg, .. } => self.eval(arg),
+            Expression::Subpiece { low_byte, arg, .. } if *low_byte == ByteSize::new(0) => {
+                self.eval(arg)
+            }
+            Expression::BinOp {
+                op: BinOpType::Piece,
+                rhs,
+                ..
+            } => self.eval(rhs),
+            _ => ResourceSet::default(),
+        }
+    }
+
+    /// Set the resources of the given register.
+    fn set_register(&mut self, var: &Variable, value: ResourceSet) {
+        if value.is_empty() {
+            self.registers.remove(&var.name);
+        } else {
+            self.registers.insert(var.name.clone(), value);
+        }
+    }
+
+    /// Get the resources of the value at the given stack offset.
+    fn get_stack_value(&self, offset: i64) -> ResourceSet {
+        self.stack
+            .get(&offset)
+            .map(|(_, value)| value.clone())
+            .unwrap_or_default()
+    }
+
+    /// Write the value to the given stack offset, overwriting all values overlapping with it.
+    fn set_stack_value(&mut self, offset: i64, size: ByteSize, value: ResourceSet) {
+        let end = offset + u64::from(size) as i64;
+        self.stack.retain(|other_offset, (other_size, _)| {
+            *other_offset >= end || other_offset + u64::from(*other_size) as i64 <= offset
+        });
+        if !value.is_empty() {
+            self.stack.insert(offset, (size, value));
+        }
+    }
+
+    /// Apply the effects of the `Def` instruction to the state.
+    /// If the stack information for the containing block is unknown,
+    /// every memory access is assumed to access the stack frame at an unknown offset.
+    fn handle_def(&mut self, def: &Term<Def>, stack_info: Option<&StackInfo>) {
+        let stack_access = match stack_info {
+            Some(stack_info) => stack_info.accesses.get(&def.tid).cloned(),
+            None => Some(None),
+        };
+        match &def.term {
+            Def::Assign { var, value } => {
+                let value = self.eval(value);
+                self.set_register(var, value);
+            }
+            Def::Load { var, .. } => {
+                let value = match stack_access {
+                    Some(Some(offset)) => self.get_stack_value(offset),
+                    _ => ResourceSet::default(),
+                };
+                self.set_register(var, value);
+            }
+            Def::Store { value, .. } => match stack_access {
+                Some(Some(offset)) => {
+                    let resources = self.eval(value);
+                    self.set_stack_value(offset, value.bytesize(), resources);
+                }
+                Some(None) => self.stack.clear(),
+                None => (),
+            },
+        }
+    }
+
+    /// Remove all registers from the state that are clobbered by a call with the given stub behavior
+    /// to a function with the given calling convention.
+    fn clear_clobbered_registers(
+        &mut self,
+        behavior: &extern_stubs::StubBehavior,
+        calling_convention: &CallingConvention,
+    ) {
+        self.registers
+            .retain(|name, _| !behavior.clobbers_register(name, calling_convention));
+    }
+
+    /// Remove all registers that are not callee-saved in the given calling convention from the state.
+    /// If no calling convention is given, all registers are removed.
+    fn clear_non_callee_saved_registers(&mut self, calling_convention: Option<&CallingConvention>) {
+        match calling_convention {
+            Some(cconv) => self
+                .registers
+                .retain(|name, _| cconv.callee_saved_register.contains(name)),
+            None => self.registers.clear(),
+        }
+    }
+}
+
+impl AbstractDomain for State {
+    /// Merge the resources of registers, stack values and the resource states.
+    /// Stack values with different sizes at the same offset are removed.
+    fn merge(&self, other: &Self) -> Self {
+        let mut registers = self.registers.clone();
+        for (name, value) in other.registers.iter() {
+            let merged = match registers.get(name) {
+                Some(self_value) => self_value.merge(value),
+                None => value.clone(),
+            };
+            registers.insert(name.clone(), merged);
+        }
+        let mut stack = self.stack.clone();
+        for (offset, (size, value)) in other.stack.iter() {
+            match stack.get(offset) {
+                Some((self_size, self_value)) if self_size == size => {
+                    let merged = self_value.merge(value);
+                    stack.insert(*offset, (*size, merged));
+                }
+                Some(_) => {
+                    stack.remove(offset);
+                }
+                None => {
+                    stack.insert(*offset, (*size, value.clone()));
+                }
+            }
+        }
+        State {
+            registers,
+            stack,
+            resources: self.resources.merge(&other.resources),
+        }
+    }
+
+    /// The state has no *Top* element.
+    fn is_top(&self) -> bool {
+        false
+    }
+}
+
+/// The context of the resource tracking containing information shared by all functions.
+struct Context<'a> {
+    project: &'a Project,
+    config: &'a Config,
+    extern_symbols: HashMap<&'a Tid, &'a ExternSymbol>,
+}
+
+impl<'a> Context<'a> {
+    /// Create a new context.
+    fn new(project: &'a Project, config: &'a Config) -> Context<'a> {
+        let extern_symbols = project
+            .program
+            .term
+            .extern_symbols
+            .iter()
+            .map(|symbol| (&symbol.tid, symbol))
+            .collect();
+        Context {
+            project,
+            config,
+            extern_symbols,
+        }
+    }
+
+    /// Compute which resources the parameters of the extern symbol may refer to.
+    /// If the parameters of the symbol are unknown,
+    /// the parameter registers of its calling convention are used instead.
+    fn eval_parameters(
+        &self,
+        state: &State,
+        symbol: &ExternSymbol,
+        stack_info: Option<&StackInfo>,
+    ) -> Vec<ResourceSet> {
+        if symbol.parameters.is_empty() {
+            return symbol
+                .get_calling_convention(self.project)
+                .parameter_register
+                .iter()
+                .map(|name| state.registers.get(name).cloned().unwrap_or_default())
+                .collect();
+        }
+        symbol
+            .parameters
+            .iter()
+            .map(|param| match param {
+                Arg::Register(var) => state.eval(&Expression::Var(var.clone())),
+                Arg::Stack { offset, .. } => stack_info
+                    .and_then(|info| info.stack_pointer_at_end)
+                    .map(|stack_pointer| state.get_stack_value(stack_pointer + offset))
+                    .unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// Apply the effects of a call to the extern symbol to the state.
+    fn handle_extern_call(
+        &self,
+        state: &mut State,
+        call_tid: &Tid,
+        symbol: &ExternSymbol,
+        parameters: &[ResourceSet],
+    ) {
+        if self.config.close_symbols.contains(&symbol.name) {
+            if let Some(resources) = parameters.get(0) {
+                state.resources.close(resources);
+            }
+        }
+        let calling_convention = symbol.get_calling_convention(self.project);
+        let kind = if self.config.file_descriptor_symbols.contains(&symbol.name) {
+            ResourceKind::FileDescriptor
+        } else if self.config.stream_symbols.contains(&symbol.name) {
+            ResourceKind::Stream
+        } else {
+            if self.config.close_symbols.contains(&symbol.name) {
+                state.clear_non_callee_saved_registers(Some(calling_convention));
+            } else {
+                state.clear_clobbered_registers(
+                    self.config.extern_stubs.get_behavior(&symbol.name),
+                    calling_convention,
+                );
+            }
+            return;
+        };
+        state.clear_non_callee_saved_registers(Some(calling_convention));
+        if let Ok(return_register) = symbol.get_unique_return_register() {
+            state.resources.add(call_tid.clone(), kind);
+            state.set_register(return_register, ResourceSet::new(call_tid.clone()));
+        }
+    }
+
+    /// Compute the states at the successors of the given block, given the state at the start of the block.
+    ///
+    /// If `results` is provided, the resource information at calls to extern symbols
+    /// and the resource states at return instructions are added to it.
+    fn compute_successor_states(
+        &self,
+        sub: &Term<Sub>,
+        block: &Term<Blk>,
+        mut state: State,
+        stack_info: Option<&StackInfo>,
+        mut results: Option<&mut ResourceTracking>,
+    ) -> Vec<(Tid, State)> {
+        for def in block.term.defs.iter() {
+            state.handle_def(def, stack_info);
+        }
+        let mut successors = Vec::new();
+        for jmp in block.term.jmps.iter() {
+            match &jmp.term {
+                Jmp::Branch(target) | Jmp::CBranch { target, .. } => {
+                    successors.push((target.clone(), state.clone()))
+                }
+                Jmp::Call { target, return_ } => {
+                    let mut call_state = state.clone();
+                    if let Some(symbol) = self.extern_symbols.get(target) {
+                        let parameters = self.eval_parameters(&state, symbol, stack_info);
+                        self.handle_extern_call(&mut call_state, &jmp.tid, symbol, &parameters);
+                        if let Some(results) = results.as_mut() {
+                            results.call_sites.insert(
+                                jmp.tid.clone(),
+                                CallSiteInfo {
+                                    parameters,
+                                    resources: state.resources.clone(),
+                                },
+                            );
+                        }
+                    } else {
+                        call_state.clear_non_callee_saved_registers(
+                            self.project.get_standard_calling_convention(),
+                        );
+                    }
+                    if let Some(return_target) = return_ {
+                        successors.push((return_target.clone(), call_state));
+                    }
+                }
+                Jmp::CallInd { return_, .. } | Jmp::CallOther { return_, .. } => {
+                    if let Some(return_target) = return_ {
+                        let mut call_state = state.clone();
+                        call_state.clear_non_callee_saved_registers(
+                            self.project.get_standard_calling_convention(),
+                        );
+                        successors.push((return_target.clone(), call_state));
+                    }
+                }
+                Jmp::Return(_) => {
+                    if let Some(results) = results.as_mut() {
+                        let exit_state = match results.exit_states.get(&sub.tid) {
+                            Some(exit_state) => exit_state.merge(&state.resources),
+                            None => state.resources.clone(),
+                        };
+                        results.exit_states.insert(sub.tid.clone(), exit_state);
+                    }
+                }
+                Jmp::BranchInd(_) => (),
+            }
+        }
+        successors
+    }
+
+    /// Compute the fixpoint of the resource tracking for the function
+    /// and add the results for the function to `results`.
+    ///
+    /// If the fixpoint computation does not stabilize, no results are added for the function.
+    /// Instead a log message is added to `results`.
+    fn compute_function(
+        &self,
+        sub: &Term<Sub>,
+        stack_infos: &HashMap<Tid, StackInfo>,
+        results: &mut ResourceTracking,
+    ) {
+        let entry_block = match sub.term.blocks.first() {
+            Some(block) => block,
+            None => return,
+        };
+        let problem = FunctionProblem {
+            context: self,
+            sub,
+            stack_infos,
+        };
+        match block_dataflow::compute(
+            &problem,
+            sub,
+            Direction::Forward,
+            vec![(entry_block.tid.clone(), State::default())],
+        ) {
+            Ok(states_at_block_start) => {
+                for block in sub.term.blocks.iter() {
+                    if let Some(state) = states_at_block_start.get(&block.tid) {
+                        self.compute_successor_states(
+                            sub,
+                            block,
+                            state.clone(),
+                            stack_infos.get(&block.tid),
+                            Some(results),
+                        );
+                    }
+                }
+            }
+            Err(log) => results.logs.push(log.source("Resource Tracking")),
+        }
+    }
+}
+
+/// The dataflow problem of the resource tracking for a single function.
+/// The value of a block is the state at the start of the block.
+struct FunctionProblem<'a, 'b> {
+    context: &'a Context<'b>,
+    sub: &'a Term<Sub>,
+    stack_infos: &'a HashMap<Tid, StackInfo>,
+}
+
+impl<'a, 'b> block_dataflow::Problem for FunctionProblem<'a, 'b> {
+    type Value = State;
+
+    fn merge(&self, value1: &State, value2: &State) -> State {
+        value1.merge(value2)
+    }
+
+    fn update_edge(
+        &self,
+        value: &State,
+        block: &Term<Blk>,
+        successor: &Term<Blk>,
+    ) -> Option<State> {
+        let mut merged_state: Option<State> = None;
+        for (target, state) in self.context.compute_successor_states(
+            self.sub,
+            block,
+            value.clone(),
+            self.stack_infos.get(&block.tid),
+            None,
+        ) {
+            if target == successor.tid {
+                merged_state = Some(match merged_state {
+                    Some(merged_state) => merged_state.merge(&state),
+                    None => state,
+                });
+            }
+        }
+        merged_state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abstract_domain::ResourceState;
+
+    fn assign(name: &str, var: &str, value: &str) -> Term<Def> {
+        Term {
+            tid: Tid::new(name),
+            term: Def::Assign {
+                var: Variable::mock(var, 8u64),
+                value: Expression::var(value),
+            },
+        }
+    }
+
+    fn call(target: &str, return_: &str) -> Jmp {
+        Jmp::Call {
+            target: Tid::new(target),
+            return_: Some(Tid::new(return_)),
+        }
+    }
+
+    fn mock_project() -> Project {
+        let mut project = Project::mock_empty();
+        project.calling_conventions = vec![CallingConvention::mock()];
+        for name in ["open", "close", "read"].iter() {
+            project
+                .program
+                .term
+                .extern_symbols
+                .push(ExternSymbol::mock_with_name(*name));
+        }
+        project
+    }
+
+    #[test]
+    fn register_tracking() {
+        let project = mock_project();
+        let config = Config::default();
+        let context = Context::new(&project, &config);
+        let mut sub = Sub::mock("func");
+        sub.term.blocks = vec![
+            Blk::mock_with_defs_and_jmp("entry", Vec::new(), call("open", "after_open")),
+            Blk::mock_with_defs_and_jmp(
+                "after_open",
+                vec![assign("def_1", "RBP", "RAX"), assign("def_2", "RDI", "RBP")],
+                Jmp::CBranch {
+                    target: Tid::new("close_block"),
+                    condition: Expression::var("RCX"),
+                },
+            ),
+            Blk::mock_with_defs_and_jmp("close_block", Vec::new(), call("close", "read_block")),
+            Blk::mock_with_defs_and_jmp(
+                "read_block",
+                vec![assign("def_3", "RDI", "RBP")],
+                call("read", "exit"),
+            ),
+            Blk::mock_with_defs_and_jmp("exit", Vec::new(), Jmp::Return(Expression::var("RSP"))),
+        ];
+        // The fall-through edge of the conditional branch skips the call to `close`.
+        sub.term.blocks[1].term.jmps.push(Term {
+            tid: Tid::new("after_open_branch"),
+            term: Jmp::Branch(Tid::new("read_block")),
+        });
+        let mut results = ResourceTracking::default();
+        context.compute_function(&sub, &HashMap::new(), &mut results);
+
+        let open_call = Tid::new("entry_jmp");
+        let close_site = results.get_call_site(&Tid::new("close_block_jmp")).unwrap();
+        assert_eq!(
+            close_site.parameters,
+            vec![ResourceSet::new(open_call.clone())]
+        );
+        assert!(close_site.get_possibly_closed_parameters().is_empty());
+        assert!(!close_site.has_definitely_closed_parameter());
+
+        let read_site = results.get_call_site(&Tid::new("read_block_jmp")).unwrap();
+        assert_eq!(
+            read_site.parameters,
+            vec![ResourceSet::new(open_call.clone())]
+        );
+        assert_eq!(
+            read_site.resources.get(&open_call).unwrap().state,
+            ResourceState::MaybeClosed
+        );
+        assert_eq!(
+            read_site.get_possibly_closed_parameters(),
+            vec![open_call.clone()]
+        );
+        assert!(!read_site.has_definitely_closed_parameter());
+        assert_eq!(
+            results
+                .get_exit_resources(&Tid::new("func"))
+                .unwrap()
+                .get(&open_call)
+                .unwrap()
+                .kind,
+            ResourceKind::FileDescriptor
+        );
+    }
+
+    #[test]
+    fn stack_tracking() {
+        let mut state = State::default();
+        state.set_register(
+            &Variable::mock("RAX", 8u64),
+            ResourceSet::new(Tid::new("fd")),
+        );
+        let store = Term {
+            tid: Tid::new("store"),
+            term: Def::Store {
+                address: Expression::var("RSP").plus_const(-8),
+                value: Expression::var("RAX"),
+            },
+        };
+        let load = Term {
+            tid: Tid::new("load"),
+            term: Def::Load {
+                var: Variable::mock("RBX", 8u64),
+                address: Expression::var("RSP").plus_const(-8),
+            },
+        };
+        let mut stack_info = StackInfo::default();
+        stack_info.accesses.insert(Tid::new("store"), Some(-8));
+        stack_info.accesses.insert(Tid::new("load"), Some(-8));
+        state.handle_def(&store, Some(&stack_info));
+        state.handle_def(&load, Some(&stack_info));
+        assert_eq!(
+            state.eval(&Expression::var("RBX").subpiece(ByteSize::new(0), ByteSize::new(4))),
+            ResourceSet::new(Tid::new("fd"))
+        );
+        // Overlapping writes remove the old value.
+        state.set_stack_value(-4, ByteSize::new(4), ResourceSet::default());
+        assert!(state.get_stack_value(-8).is_empty());
+        // Without stack information loads are unknown.
+        state.handle_def(&load, None);
+        assert!(state.eval(&Expression::var("RBX")).is_empty());
+    }
+}
@@ -0,0 +1,428 @@
+//! Heuristic recovery of structure layouts from memory accesses.
+//!
+//! Memory accesses through pointers with exactly known offsets (as computed by the pointer inference analysis)
+//! are grouped by the memory object that they access.
+//! The offsets and sizes of these accesses approximate the fields of the structure stored in the object.
+//! If the object was allocated by a call to an allocation function with a constant size parameter,
+//! the size of the object is also known.
+//!
+//! Checks can use the recovered layouts to give more precise descriptions of memory accesses,
+//! e.g. "8 bytes at offset 0x14 of a 0x18-byte object" instead of raw pointer arithmetic.
+//!
+//! Stack frames are ignored, since their layout does not correspond to a structure type.
+//! Since only observed accesses are taken into account,
+//! fields that are never accessed directly (e.g. because they are only copied with `memcpy`) are missing from the layouts.
+
+use super::interprocedural_fixpoint_generic::NodeValue;
+use super::pointer_inference::object::ObjectType;
+use super::pointer_inference::{Data, PointerInference, State};
+use crate::abstract_domain::{AbstractIdentifier, BitvectorDomain};
+use crate::analysis::graph::Node;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::binary::RuntimeMemoryImage;
+use std::collections::{BTreeMap, HashMap};
+
+/// The configuration of the structure layout recovery.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Config {
+    /// Names of allocation functions mapped to the indices of their size parameters.
+    /// The size of the allocated object is the product of the values of all size parameters.
+    pub allocation_symbols: BTreeMap<String, Vec<usize>>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        let allocation_symbols = [
+            ("malloc", vec![0]),
+            ("calloc", vec![0, 1]),
+            ("realloc", vec![1]),
+            ("xmalloc", vec![0]),
+        ];
+        Config {
+            allocation_symbols: allocation_symbols
+                .iter()
+                .map(|(name, indices)| (name.to_string(), indices.clone()))
+                .collect(),
+        }
+    }
+}
+
+/// The kind of a memory access.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum AccessKind {
+    /// A read access.
+    Load,
+    /// A write access.
+    Store,
+}
+
+/// A memory access to an exactly known offset of a (non-stack) memory object.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct FieldAccess {
+    /// The accessed memory object.
+    pub object: AbstractIdentifier,
+    /// The offset of the access relative to the start of the object.
+    pub offset: i64,
+    /// The number of accessed bytes.
+    pub size: ByteSize,
+    /// Whether the access reads or writes memory.
+    pub kind: AccessKind,
+}
+
+/// The recovered layout of a structure stored in a memory object.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct StructLayout {
+    /// The offsets of the fields mapped to their sizes.
+    /// If a field is accessed with different sizes, the largest size is used.
+    fields: BTreeMap<i64, ByteSize>,
+    /// The size of the object if it is known.
+    object_size: Option<u64>,
+}
+
+impl StructLayout {
+    /// Get the offsets and sizes of all fields of the structure.
+    pub fn get_fields(&self) -> &BTreeMap<i64, ByteSize> {
+        &self.fields
+    }
+
+    /// Get the size of the object if it is known.
+    pub fn get_object_size(&self) -> Option<u64> {
+        self.object_size
+    }
+
+    /// Add a field to the layout.
+    fn add_field(&mut self, offset: i64, size: ByteSize) {
+        let field_size = self.fields.entry(offset).or_insert(size);
+        if *field_size < size {
+            *field_size = size;
+        }
+    }
+
+    /// Get the offset and size of the field containing the byte at the given offset.
+    pub fn get_field_containing(&self, offset: i64) -> Option<(i64, ByteSize)> {
+        let (field_offset, field_size) = self.fields.range(..=offset).next_back()?;
+        if *field_offset + u64::from(*field_size) as i64 > offset {
+            Some((*field_offset, *field_size))
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if an access of the given size at the given offset is not contained in the object.
+    /// If the size of the object is unknown, only accesses before the start of the object are detected.
+    pub fn is_out_of_bounds(&self, offset: i64, size: ByteSize) -> bool {
+        match self.object_size {
+            _ if offset < 0 => true,
+            Some(object_size) => offset as u64 + u64::from(size) > object_size,
+            None => false,
+        }
+    }
+
+    /// Generate a human-readable description of an access of the given size at the given offset of the object.
+    pub fn describe_access(&self, offset: i64, size: ByteSize) -> String {
+        let offset = if offset < 0 {
+            format!("-{:#x}", -(offset as i128))
+        } else {
+            format!("{:#x}", offset)
+        };
+        match self.object_size {
+            Some(object_size) => format!(
+                "{} bytes at offset {} of a {:#x}-byte object",
+                u64::from(size),
+                offset,
+                object_size
+            ),
+            None => format!(
+                "{} bytes at offset {} of an object of unknown size",
+                u64::from(size),
+                offset
+            ),
+        }
+    }
+}
+
+/// The recovered structure layouts of all memory objects of a program.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct StructLayouts {
+    /// The recovered layouts of the memory objects.
+    layouts: BTreeMap<AbstractIdentifier, StructLayout>,
+    /// The field accesses of each `Def` term accessing memory.
+    accesses: HashMap<Tid, Vec<FieldAccess>>,
+}
+
+impl StructLayouts {
+    /// Recover the structure layouts of all memory objects from the results of the pointer inference analysis.
+    pub fn compute(
+        project: &Project,
+        pointer_inference: &PointerInference,
+        runtime_memory_image: &RuntimeMemoryImage,
+        config: &Config,
+    ) -> StructLayouts {
+        let mut struct_layouts = StructLayouts::default();
+        let graph = pointer_inference.get_graph();
+        for node in graph.node_indices() {
+            if let (Node::BlkStart(block, _sub), Some(NodeValue::Value(state))) =
+                (graph[node], pointer_inference.get_node_value(node))
+            {
+                struct_layouts.add_block_accesses(block, state.clone(), runtime_memory_image);
+            }
+        }
+        let allocation_sizes =
+            compute_allocation_sizes(project, pointer_inference, runtime_memory_image, config);
+        for (object, layout) in struct_layouts.layouts.iter_mut() {
+            if let Some(Some(size)) = allocation_sizes.get(object.get_tid()) {
+                layout.object_size = Some(*size);
+            }
+        }
+        struct_layouts
+    }
+
+    /// Get the recovered layout of the given memory object.
+    pub fn get_layout(&self, object: &AbstractIdentifier) -> Option<&StructLayout> {
+        self.layouts.get(object)
+    }
+
+    /// Get the field accesses of the `Def` term with the given TID.
+    pub fn get_accesses(&self, def_tid: &Tid) -> &[FieldAccess] {
+        self.accesses
+            .get(def_tid)
+            .map(|accesses| &accesses[..])
+            .unwrap_or(&[])
+    }
+
+    /// Iterate over all memory objects and their recovered layouts.
+    pub fn iter(&self) -> impl Iterator<Item = (&AbstractIdentifier, &StructLayout)> {
+        self.layouts.iter()
+    }
+
+    /// Add the field accesses of all `Def` terms in the block,
+    /// starting with the given pointer inference state at the start of the block.
+    fn add_block_accesses(
+        &mut self,
+        block: &Term<Blk>,
+        mut state: State,
+        runtime_memory_image: &RuntimeMemoryImage,
+    ) {
+        for def in block.term.defs.iter() {
+            match &def.term {
+                Def::Load { var, address } => {
+                    self.add_access(&def.tid, &state, address, var.size, AccessKind::Load);
+                    let _ = state.handle_load(var, address, runtime_memory_image);
+                }
+                Def::Store { address, value } => {
+                    self.add_access(
+                        &def.tid,
+                        &state,
+                        address,
+                        value.bytesize(),
+                        AccessKind::Store,
+                    );
+                    let _ = state.handle_store(address, value, runtime_memory_image);
+                }
+                Def::Assign { var, value } => {
+                    let _ = state.handle_register_assign(var, value);
+                }
+            }
+        }
+    }
+
+    /// Add the accesses to all non-stack objects with exactly known offsets
+    /// that the given address may point to.
+    fn add_access(
+        &mut self,
+        def_tid: &Tid,
+        state: &State,
+        address: &Expression,
+        size: ByteSize,
+        kind: AccessKind,
+    ) {
+        if let Ok(Data::Pointer(pointer)) = state.eval(address) {
+            for (object, offset) in pointer.targets() {
+                if let Ok(Some(ObjectType::Stack)) = state.memory.get_object_type(object) {
+                    continue;
+                }
+                if let BitvectorDomain::Value(offset) = offset {
+                    if let Ok(offset) = offset.try_to_i64() {
+                        self.layouts
+                            .entry(object.clone())
+                            .or_default()
+                            .add_field(offset, size);
+                        let access = FieldAccess {
+                            object: object.clone(),
+                            offset,
+                            size,
+                            kind,
+                        };
+                        let accesses = self.accesses.entry(def_tid.clone()).or_default();
+                        if !accesses.contains(&access) {
+                            accesses.push(access);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Compute the sizes of the objects allocated by calls to the allocation functions given in the configuration.
+///
+/// The result maps the TIDs of the call sites to the allocated size.
+/// The size is `None` if the size parameters are not exactly known constants
+/// or if different sizes were computed for the same call site.
+fn compute_allocation_sizes(
+    project: &Project,
+    pointer_inference: &PointerInference,
+    runtime_memory_image: &RuntimeMemoryImage,
+    config: &Config,
+) -> HashMap<Tid, Option<u64>> {
+    let mut allocation_sizes: HashMap<Tid, Option<u64>> = HashMap::new();
+    let graph = pointer_inference.get_graph();
+    for node in graph.node_indices() {
+        let (block, state) = match (graph[node], pointer_inference.get_node_value(node)) {
+            (Node::BlkEnd(block, _sub), Some(NodeValue::Value(state))) => (block, state),
+            _ => continue,
+        };
+        for jmp in block.term.jmps.iter() {
+            if let Jmp::Call { target, .. } = &jmp.term {
+                let symbol = match project.program.term.extern_symbols.iter().find(|symbol| {
+                    symbol.tid == *target && config.allocation_symbols.contains_key(&symbol.name)
+                }) {
+                    Some(symbol) => symbol,
+                    None => continue,
+                };
+                let mut size = Some(1u64);
+                for index in config.allocation_symbols[&symbol.name].iter() {
+                    let parameter_value = match state.eval_nth_parameter(
+                        project,
+                        symbol,
+                        *index,
+                        runtime_memory_image,
+                    ) {
+                        Ok(Data::Value(BitvectorDomain::Value(value))) => value.try_to_u64().ok(),
+                        _ => None,
+                    };
+                    size = size
+                        .zip(parameter_value)
+                        .and_then(|(size, value)| size.checked_mul(value));
+                }
+                allocation_sizes
+                    .entry(jmp.tid.clone())
+                    .and_modify(|old_size| {
+                        if *old_size != size {
+                            *old_size = None
+                        }
+                    })
+                    .or_insert(size);
+            }
+        }
+    }
+    allocation_sizes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abstract_domain::{AbstractLocation, PointerDomain};
+
+    fn mock_heap_object_state() -> (State, AbstractIdentifier) {
+        let rax = Variable::mock("RAX", 8u64);
+        let mut state = State::new(&Variable::mock("RSP", 8u64), Tid::new("func"));
+        let object = AbstractIdentifier::new(
+            Tid::new("malloc_call"),
+            AbstractLocation::from_var(&rax).unwrap(),
+        );
+        state.memory.add_abstract_object(
+            object.clone(),
+            Bitvector::zero(64.into()).into(),
+            ObjectType::Heap,
+            ByteSize::new(8),
+        );
+        state.set_register(
+            &rax,
+            Data::Pointer(PointerDomain::new(
+                object.clone(),
+                Bitvector::zero(64.into()).into(),
+            )),
+        );
+        (state, object)
+    }
+
+    #[test]
+    fn field_accesses() {
+        let (state, object) = mock_heap_object_state();
+        let defs = vec![
+            Term {
+                tid: Tid::new("store"),
+                term: Def::Store {
+                    address: Expression::var("RAX").plus_const(0x10),
+                    value: Expression::const_from_i64(0),
+                },
+            },
+            Term {
+                tid: Tid::new("load"),
+                term: Def::Load {
+                    var: Variable::mock("RBX", 4u64),
+                    address: Expression::var("RAX").plus_const(4),
+                },
+            },
+            Term {
+                tid: Tid::new("stack_store"),
+                term: Def::Store {
+                    address: Expression::var("RSP").plus_const(-8),
+                    value: Expression::const_from_i64(0),
+                },
+            },
+        ];
+        let mut block = Blk::mock();
+        block.term.defs = defs;
+        let mut struct_layouts = StructLayouts::default();
+        struct_layouts.add_block_accesses(&block, state, &RuntimeMemoryImage::mock());
+
+        let layout = struct_layouts.get_layout(&object).unwrap();
+        assert_eq!(layout.get_fields().len(), 2);
+        assert_eq!(layout.get_fields().get(&0x10), Some(&ByteSize::new(8)));
+        assert_eq!(layout.get_fields().get(&4), Some(&ByteSize::new(4)));
+        assert_eq!(
+            struct_layouts.get_accesses(&Tid::new("store")),
+            &[FieldAccess {
+                object: object.clone(),
+                offset: 0x10,
+                size: ByteSize::new(8),
+                kind: AccessKind::Store,
+            }]
+        );
+        assert!(struct_layouts
+            .get_accesses(&Tid::new("stack_store"))
+            .is_empty());
+        assert_eq!(struct_layouts.iter().count(), 1);
+    }
+
+    #[test]
+    fn layout_queries() {
+        let mut layout = StructLayout::default();
+        layout.add_field(0, ByteSize::new(4));
+        layout.add_field(0x10, ByteSize::new(4));
+        layout.add_field(0x10, ByteSize::new(8));
+        assert_eq!(layout.get_field_containing(2), Some((0, ByteSize::new(4))));
+        assert_eq!(layout.get_field_containing(6), None);
+        assert_eq!(
+            layout.get_field_containing(0x17),
+            Some((0x10, ByteSize::new(8)))
+        );
+        assert!(!layout.is_out_of_bounds(0x14, ByteSize::new(8)));
+        assert!(layout.is_out_of_bounds(-4, ByteSize::new(4)));
+        assert_eq!(
+            layout.describe_access(0x14, ByteSize::new(8)),
+            "8 bytes at offset 0x14 of an object of unknown size"
+        );
+
+        layout.object_size = Some(0x18);
+        assert!(layout.is_out_of_bounds(0x14, ByteSize::new(8)));
+        assert!(!layout.is_out_of_bounds(0x10, ByteSize::new(8)));
+        assert_eq!(
+            layout.describe_access(0x14, ByteSize::new(8)),
+            "8 bytes at offset 0x14 of a 0x18-byte object"
+        );
+    }
+}
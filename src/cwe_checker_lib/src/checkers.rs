@@ -1,12 +1,23 @@
+pub mod analysis_gaps;
+pub mod call_policy;
+pub mod cwe_1327;
+pub mod cwe_1395;
 pub mod cwe_190;
 pub mod cwe_215;
 pub mod cwe_243;
+pub mod cwe_321;
 pub mod cwe_332;
 pub mod cwe_367;
 pub mod cwe_426;
 pub mod cwe_467;
 pub mod cwe_476;
 pub mod cwe_560;
+pub mod cwe_563;
+pub mod cwe_667;
+pub mod cwe_672;
 pub mod cwe_676;
+pub mod cwe_686;
 pub mod cwe_78;
 pub mod cwe_782;
+pub mod dead_parameters;
+pub mod packer;
@@ -0,0 +1,339 @@
+//! This module reports regions of the binary that the lifter could not model faithfully.
+//!
+//! The analyses of the cwe_checker silently analyze around instructions whose semantics are unknown,
+//! e.g. by treating their results as arbitrary values or by assuming that control flow simply continues after them.
+//! Bugs inside such regions cannot be found and values flowing through them are lost.
+//! The warnings of this module make these blind spots explicit,
+//! so that an audit knows which parts of the binary were not (or only partially) analyzed.
+//!
+//! ## How the check works
+//!
+//! For each function the following gaps are reported:
+//! - Instructions that the disassembler does not support (i.e. `CALLOTHER` instructions marked as unimplemented).
+//! - Clusters of `CALLOTHER` instructions with side effects unknown to the analyses,
+//! which usually indicate inline assembly (e.g. special purpose or vector instructions).
+//! `CALLOTHER` instructions with known side effects (e.g. syscalls) are ignored.
+//! - Functions where a large share of the assignments contains unknown values (`Expression::Unknown`).
+//! - Indirect jumps, whose targets are not followed by the control flow graph of the analyses.
+//! If such a jump ends the first block of a function, the function is reported as a trampoline,
+//! i.e. a function that only forwards control flow to an unknown target.
+//!
+//! ## False Positives
+//!
+//! - Special purpose instructions without effect on the checked properties (e.g. memory barriers or hints)
+//! that are not contained in the list of known side effects.
+//!
+//! ## False Negatives
+//!
+//! - Instructions whose semantics are modeled incorrectly (instead of not at all) by the lifter.
+//! - Code that the disassembler did not find at all.
+
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::components::matches_pattern;
+use crate::utils::log::{CweWarning, LogMessage, Severity};
+use crate::CweModule;
+
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "AnalysisGaps",
+    version: "0.1",
+    cwes: &[],
+    description: "Regions that the lifter could not model faithfully, e.g. unsupported instructions or inline assembly.",
+    required_analyses: &[],
+    config_keys: &["known_side_effects", "min_cluster_size", "max_cluster_distance", "max_unknown_density", "min_unknown_defs"],
+    run: check_cwe,
+    validate_config: Some(crate::utils::config_validation::deserialize_section::<Config>),
+};
+
+/// The description that the lifter uses for instructions it does not support.
+const UNIMPLEMENTED_DESCRIPTION: &str = "unimplemented";
+
+/// The prefix of the description of indirect jumps that were converted to `CALLOTHER` instructions
+/// because their target could not be resolved.
+const UNRESOLVED_JUMP_PREFIX: &str = "Unresolved jump";
+
+/// The maximal number of assignments in the first block of a function ending with an unresolved indirect jump
+/// for the function to be reported as a trampoline.
+const MAX_TRAMPOLINE_DEFS: usize = 4;
+
+/// The configuration struct of the check.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Config {
+    /// Name patterns (which may contain `*` as a wildcard) of `CALLOTHER` descriptions
+    /// whose side effects are known to the analyses, e.g. syscalls.
+    known_side_effects: Vec<String>,
+    /// `CALLOTHER` instructions with unknown side effects are only reported
+    /// if at least this number of them form a cluster.
+    min_cluster_size: usize,
+    /// The maximal distance in bytes between two `CALLOTHER` instructions of the same cluster.
+    max_cluster_distance: u64,
+    /// Functions are reported if the share of assignments containing unknown values exceeds this threshold.
+    max_unknown_density: f64,
+    /// Functions with less assignments containing unknown values are not reported.
+    min_unknown_defs: usize,
+}
+
+/// Return whether the expression contains an unknown value.
+fn contains_unknown(expression: &Expression) -> bool {
+    use Expression::*;
+    match expression {
+        Var(_) | Const(_) => false,
+        Unknown { .. } => true,
+        BinOp { lhs, rhs, .. } => contains_unknown(lhs) || contains_unknown(rhs),
+        UnOp { arg, .. } | Cast { arg, .. } | Subpiece { arg, .. } => contains_unknown(arg),
+    }
+}
+
+/// Return whether the definition contains an unknown value.
+fn def_contains_unknown(def: &Def) -> bool {
+    match def {
+        Def::Load { address, .. } => contains_unknown(address),
+        Def::Store { address, value } => contains_unknown(address) || contains_unknown(value),
+        Def::Assign { value, .. } => contains_unknown(value),
+    }
+}
+
+/// Parse the address of a TID.
+fn parse_address(tid: &Tid) -> Option<u64> {
+    u64::from_str_radix(tid.address.trim_start_matches("0x"), 16).ok()
+}
+
+/// Split the (address-sorted) TIDs into clusters of TIDs with a maximal distance of `max_distance` bytes between them.
+fn get_clusters(mut tids: Vec<&Tid>, max_distance: u64) -> Vec<Vec<&Tid>> {
+    tids.sort_by_key(|tid| parse_address(tid));
+    let mut clusters: Vec<Vec<&Tid>> = Vec::new();
+    let mut last_address: Option<u64> = None;
+    for tid in tids {
+        let address = parse_address(tid);
+        match (last_address, address, clusters.last_mut()) {
+            (Some(last), Some(current), Some(cluster)) if current - last <= max_distance => {
+                cluster.push(tid)
+            }
+            _ => clusters.push(vec![tid]),
+        }
+        last_address = address;
+    }
+    clusters
+}
+
+/// Generate the warning for a gap of the given kind in a function.
+fn generate_warning(sub: &Term<Sub>, kind: &str, tids: &[&Tid], description: String) -> CweWarning {
+    let mut addresses: Vec<String> = tids.iter().map(|tid| tid.address.clone()).collect();
+    addresses.dedup();
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Analysis Gap) {} in function {} at {}. The analysis results for this region may be incomplete.",
+            description,
+            sub.term.name,
+            addresses.join(", ")
+        ),
+    )
+    .severity(Severity::Info)
+    .tids(tids.iter().map(|tid| format!("{}", tid)).collect())
+    .addresses(addresses)
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![vec!["gap_kind".to_string(), kind.to_string()]])
+}
+
+/// Generate the warnings for the gaps of the given function.
+fn check_sub(sub: &Term<Sub>, config: &Config) -> Vec<CweWarning> {
+    let mut warnings = Vec::new();
+    let mut unimplemented = Vec::new();
+    let mut unknown_side_effects = Vec::new();
+    let mut unresolved_jumps = Vec::new();
+    let mut unknown_defs = Vec::new();
+    let mut number_of_assignments = 0;
+    for block in sub.term.blocks.iter() {
+        for def in block.term.defs.iter() {
+            number_of_assignments += 1;
+            if def_contains_unknown(&def.term) {
+                unknown_defs.push(&def.tid);
+            }
+        }
+        for jmp in block.term.jmps.iter() {
+            match &jmp.term {
+                Jmp::CallOther { description, .. } if description == UNIMPLEMENTED_DESCRIPTION => {
+                    unimplemented.push(&jmp.tid)
+                }
+                Jmp::CallOther { description, .. }
+                    if description.starts_with(UNRESOLVED_JUMP_PREFIX) =>
+                {
+                    unresolved_jumps.push(&jmp.tid)
+                }
+                Jmp::CallOther { description, .. } => {
+                    if !config
+                        .known_side_effects
+                        .iter()
+                        .any(|pattern| matches_pattern(pattern, description))
+                    {
+                        unknown_side_effects.push(&jmp.tid)
+                    }
+                }
+                Jmp::BranchInd(_) => unresolved_jumps.push(&jmp.tid),
+                _ => (),
+            }
+        }
+    }
+    if !unimplemented.is_empty() {
+        warnings.push(generate_warning(
+            sub,
+            "unimplemented_instruction",
+            &unimplemented,
+            format!(
+                "{} instructions not supported by the disassembler",
+                unimplemented.len()
+            ),
+        ));
+    }
+    for cluster in get_clusters(unknown_side_effects, config.max_cluster_distance) {
+        if cluster.len() >= config.min_cluster_size {
+            warnings.push(generate_warning(
+                sub,
+                "unknown_side_effects",
+                &cluster,
+                format!(
+                    "Cluster of {} instructions with unknown side effects (possibly inline assembly)",
+                    cluster.len()
+                ),
+            ));
+        }
+    }
+    if unknown_defs.len() >= config.min_unknown_defs && number_of_assignments > 0 {
+        let density = unknown_defs.len() as f64 / number_of_assignments as f64;
+        if density > config.max_unknown_density {
+            warnings.push(
+                generate_warning(
+                    sub,
+                    "unknown_values",
+                    &unknown_defs,
+                    format!(
+                        "{} of {} assignments with unknown values",
+                        unknown_defs.len(),
+                        number_of_assignments
+                    ),
+                )
+                .other(vec![
+                    vec!["gap_kind".to_string(), "unknown_values".to_string()],
+                    vec!["density".to_string(), format!("{:.2}", density)],
+                ]),
+            );
+        }
+    }
+    if !unresolved_jumps.is_empty() {
+        let is_trampoline = sub.term.blocks.first().map_or(false, |block| {
+            block.term.defs.len() <= MAX_TRAMPOLINE_DEFS
+                && block
+                    .term
+                    .jmps
+                    .iter()
+                    .any(|jmp| unresolved_jumps.contains(&&jmp.tid))
+        });
+        if is_trampoline {
+            warnings.push(generate_warning(
+                sub,
+                "trampoline",
+                &unresolved_jumps,
+                "Trampoline to an unresolved jump target".to_string(),
+            ));
+        } else {
+            warnings.push(generate_warning(
+                sub,
+                "unresolved_jump",
+                &unresolved_jumps,
+                format!("{} unresolved indirect jumps", unresolved_jumps.len()),
+            ));
+        }
+    }
+    warnings
+}
+
+/// Run the check.
+///
+/// Reports unsupported instructions, clusters of instructions with unknown side effects,
+/// functions with a high density of unknown values and unresolved indirect jumps.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
+    let config: Config = serde_json::from_value(cwe_params.clone()).unwrap();
+    let warnings = analysis_results
+        .project
+        .program
+        .term
+        .subs
+        .iter()
+        .flat_map(|sub| check_sub(sub, &config))
+        .collect();
+    (Vec::new(), warnings, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_config() -> Config {
+        Config {
+            known_side_effects: vec!["syscall".to_string(), "LOCK".to_string()],
+            min_cluster_size: 2,
+            max_cluster_distance: 0x10,
+            max_unknown_density: 0.5,
+            min_unknown_defs: 1,
+        }
+    }
+
+    fn call_other(address: &str, description: &str) -> Term<Jmp> {
+        let mut tid = Tid::new(format!("jmp_{}", address));
+        tid.address = address.to_string();
+        Term {
+            tid,
+            term: Jmp::CallOther {
+                description: description.to_string(),
+                return_: None,
+            },
+        }
+    }
+
+    #[test]
+    fn call_other_gaps() {
+        let mut sub = Sub::mock("func");
+        let mut block = Blk::mock();
+        block.term.jmps = vec![
+            call_other("1000", "cpuid"),
+            call_other("1004", "rdtsc"),
+            call_other("1008", "syscall"),
+            call_other("2000", "xgetbv"),
+            call_other("2004", UNIMPLEMENTED_DESCRIPTION),
+        ];
+        sub.term.blocks.push(block);
+        let warnings = check_sub(&sub, &mock_config());
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].addresses, vec!["2004".to_string()]);
+        assert_eq!(
+            warnings[1].addresses,
+            vec!["1000".to_string(), "1004".to_string()]
+        );
+    }
+
+    #[test]
+    fn unknown_value_density() {
+        let mut sub = Sub::mock("func");
+        let mut block = Blk::mock();
+        block.term.defs = vec![Def::assign(
+            "def_1",
+            Variable::mock("RAX", 8),
+            Expression::Unknown {
+                description: "unknown".to_string(),
+                size: ByteSize::new(8),
+            },
+        )];
+        sub.term.blocks.push(block);
+        let warnings = check_sub(&sub, &mock_config());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].other[0],
+            vec!["gap_kind".to_string(), "unknown_values".to_string()]
+        );
+    }
+}
@@ -0,0 +1,455 @@
+//! This module implements configurable "must call" policies.
+//!
+//! Many API usage rules have the form
+//! "every call to function A must be preceded (or followed) by a call to function B on all paths",
+//! e.g. a cipher context has to be initialized with `EVP_EncryptInit_ex` before `EVP_EncryptUpdate` is called.
+//! Such rules can be added to the `CallPolicy` section of the configuration file
+//! without writing a dedicated check module.
+//!
+//! ## How the check works
+//!
+//! For rules of the type `preceded` a forward "must" dataflow analysis computes
+//! for each basic block whether one of the required functions was called on all paths to the block.
+//! The analysis is interprocedural in the sense that a function called from a program point,
+//! where a required function was already called on all paths, starts with this knowledge.
+//! For the entry points of the program nothing is assumed to be called before.
+//!
+//! For rules of the type `followed` a backward "must" dataflow analysis computes
+//! for each basic block whether one of the required functions is called on all paths from the block
+//! to a return instruction of the function.
+//! Paths ending in a call to a non-returning function are not considered.
+//!
+//! ## False Positives
+//!
+//! - Infeasible paths.
+//! - Required functions that are called indirectly or inside called functions (other than the required functions themselves).
+//! - For `followed` rules: the required function is called by a caller after the current function returns.
+//!
+//! ## False Negatives
+//!
+//! - Calls through function pointers are not checked.
+//! - The rules do not check whether the calls operate on the same objects.
+//! - Functions where the dataflow analysis does not stabilize are not checked.
+//! A log message is generated for them.
+
+use crate::analysis::block_dataflow::{self, Direction};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage, Remediation, Severity};
+use crate::CweModule;
+use std::collections::{HashMap, HashSet};
+
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CallPolicy",
+    version: "0.1",
+    cwes: &[],
+    description: "Violations of configurable policies requiring calls to some functions before or after calls to others.",
+    required_analyses: &[],
+    config_keys: &["rules"],
+    run: check_cwe,
+    validate_config: Some(crate::utils::config_validation::deserialize_section::<Config>),
+};
+
+/// The configured policy rules read from *config.json*.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Config {
+    rules: Vec<Rule>,
+}
+
+/// Whether the required call has to happen before or after the call to the trigger function.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Order {
+    /// One of the required functions has to be called on all paths to a call to a trigger function.
+    Preceded,
+    /// One of the required functions has to be called on all paths from a call to a trigger function
+    /// to the return of the function containing the call.
+    Followed,
+}
+
+/// A single policy rule.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Rule {
+    /// A short name of the rule that is shown in the warnings.
+    name: String,
+    /// Calls to these functions are checked by the rule.
+    trigger_symbols: Vec<String>,
+    /// On all paths at least one of these functions has to be called before or after a trigger function.
+    required_symbols: Vec<String>,
+    /// Whether the required call has to happen before or after the trigger call.
+    order: Order,
+    /// The number of the CWE that the warnings of this rule should be reported as.
+    /// If not set, warnings are reported with the name of the check module.
+    #[serde(default)]
+    cwe: Option<u32>,
+}
+
+/// Get the target of the direct call at the end of the block, if there is one.
+fn get_call_target(block: &Term<Blk>) -> Option<&Tid> {
+    block.term.jmps.iter().find_map(|jmp| match &jmp.term {
+        Jmp::Call { target, .. } => Some(target),
+        _ => None,
+    })
+}
+
+/// Returns `true` if the block ends with a direct call to one of the required functions.
+fn calls_required_function(block: &Term<Blk>, required_tids: &HashSet<&Tid>) -> bool {
+    get_call_target(block)
+        .map(|target| required_tids.contains(target))
+        .unwrap_or(false)
+}
+
+/// The forward dataflow problem for rules of the type `preceded`.
+/// The value of a block is `true` if one of the required functions
+/// was called on all paths from the function start to the start of the block.
+struct RequiredCallBefore<'a, 'b> {
+    required_tids: &'a HashSet<&'b Tid>,
+}
+
+impl<'a, 'b> block_dataflow::Problem for RequiredCallBefore<'a, 'b> {
+    type Value = bool;
+
+    fn merge(&self, value1: &bool, value2: &bool) -> bool {
+        *value1 && *value2
+    }
+
+    fn update_edge(&self, value: &bool, block: &Term<Blk>, _successor: &Term<Blk>) -> Option<bool> {
+        Some(*value || calls_required_function(block, self.required_tids))
+    }
+}
+
+/// The backward dataflow problem for rules of the type `followed`.
+/// The value of a block is `true` if one of the required functions
+/// is called on all paths from the end of the block to a return instruction.
+struct RequiredCallAfter<'a, 'b> {
+    required_tids: &'a HashSet<&'b Tid>,
+}
+
+impl<'a, 'b> block_dataflow::Problem for RequiredCallAfter<'a, 'b> {
+    type Value = bool;
+
+    fn merge(&self, value1: &bool, value2: &bool) -> bool {
+        *value1 && *value2
+    }
+
+    fn update_edge(&self, value: &bool, _block: &Term<Blk>, successor: &Term<Blk>) -> Option<bool> {
+        Some(*value || calls_required_function(successor, self.required_tids))
+    }
+}
+
+/// Compute for each block of the function whether one of the required functions
+/// was called on all paths from the function start to the start of the block,
+/// given whether this holds at the function start.
+/// Returns a log message if the computation does not stabilize.
+fn compute_required_call_at_block_start(
+    sub: &Term<Sub>,
+    required_tids: &HashSet<&Tid>,
+    called_at_entry: bool,
+) -> Result<HashMap<Tid, bool>, LogMessage> {
+    let start_values = sub
+        .term
+        .blocks
+        .iter()
+        .enumerate()
+        .map(|(index, block)| (block.tid.clone(), index != 0 || called_at_entry));
+    block_dataflow::compute(
+        &RequiredCallBefore { required_tids },
+        sub,
+        Direction::Forward,
+        start_values,
+    )
+}
+
+/// Compute for each block of the function whether one of the required functions
+/// is called on all paths from the end of the block to a return instruction.
+/// Returns a log message if the computation does not stabilize.
+fn compute_required_call_after_block_end(
+    sub: &Term<Sub>,
+    required_tids: &HashSet<&Tid>,
+) -> Result<HashMap<Tid, bool>, LogMessage> {
+    let start_values = sub.term.blocks.iter().map(|block| {
+        let is_return_block = block
+            .term
+            .jmps
+            .iter()
+            .any(|jmp| matches!(jmp.term, Jmp::Return(_)));
+        (block.tid.clone(), !is_return_block)
+    });
+    block_dataflow::compute(
+        &RequiredCallAfter { required_tids },
+        sub,
+        Direction::Backward,
+        start_values,
+    )
+}
+
+/// Compute for each function whether one of the required functions is called on all paths to the function start.
+///
+/// Entry points and functions without known callers start with the assumption that nothing was called before.
+/// Call sites in functions where the dataflow analysis does not stabilize are assumed to not be preceded by a required call.
+fn compute_required_call_at_function_entry(
+    program: &Term<Program>,
+    required_tids: &HashSet<&Tid>,
+) -> HashMap<Tid, bool> {
+    let mut has_callers = HashSet::new();
+    for sub in program.term.subs.iter() {
+        for block in sub.term.blocks.iter() {
+            if let Some(target) = get_call_target(block) {
+                has_callers.insert(target.clone());
+            }
+        }
+    }
+    let mut called_at_entry: HashMap<Tid, bool> = program
+        .term
+        .subs
+        .iter()
+        .map(|sub| {
+            let is_root =
+                program.term.entry_points.contains(&sub.tid) || !has_callers.contains(&sub.tid);
+            (sub.tid.clone(), !is_root)
+        })
+        .collect();
+    let subs: HashMap<&Tid, &Term<Sub>> = program
+        .term
+        .subs
+        .iter()
+        .map(|sub| (&sub.tid, sub))
+        .collect();
+    // The values only change from `true` to `false`, so the computation terminates.
+    let mut worklist: Vec<&Tid> = subs.keys().copied().collect();
+    while let Some(sub_tid) = worklist.pop() {
+        let sub = subs[sub_tid];
+        // The log message for a non-stabilizing function is generated when the function itself is checked.
+        let called_at_start =
+            compute_required_call_at_block_start(sub, required_tids, called_at_entry[sub_tid]).ok();
+        for block in sub.term.blocks.iter() {
+            if let Some(target) = get_call_target(block) {
+                let is_called_before = called_at_start
+                    .as_ref()
+                    .map_or(false, |called_at_start| called_at_start[&block.tid]);
+                match called_at_entry.get_mut(target) {
+                    Some(value) if *value && !is_called_before => {
+                        *value = false;
+                        worklist.push(target);
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+    called_at_entry
+}
+
+/// Get the TIDs of the extern symbols and functions with the given names.
+fn get_tids_of_symbols<'a>(program: &'a Term<Program>, names: &[String]) -> HashSet<&'a Tid> {
+    let extern_tids = program
+        .term
+        .extern_symbols
+        .iter()
+        .filter(|symbol| names.contains(&symbol.name))
+        .map(|symbol| &symbol.tid);
+    let sub_tids = program
+        .term
+        .subs
+        .iter()
+        .filter(|sub| names.contains(&sub.term.name))
+        .map(|sub| &sub.tid);
+    extern_tids.chain(sub_tids).collect()
+}
+
+/// Check a single rule and return the TIDs of the calls violating it together with the containing function.
+/// Also returns log messages for the functions where the dataflow analysis did not stabilize.
+fn check_rule<'a>(
+    program: &'a Term<Program>,
+    rule: &Rule,
+) -> (Vec<(&'a Term<Sub>, &'a Tid)>, Vec<LogMessage>) {
+    let trigger_tids = get_tids_of_symbols(program, &rule.trigger_symbols);
+    let required_tids = get_tids_of_symbols(program, &rule.required_symbols);
+    if trigger_tids.is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+    let called_at_entry = match rule.order {
+        Order::Preceded => compute_required_call_at_function_entry(program, &required_tids),
+        Order::Followed => HashMap::new(),
+    };
+    let mut violations = Vec::new();
+    let mut logs = Vec::new();
+    for sub in program.term.subs.iter() {
+        let is_required_call_made = match rule.order {
+            Order::Preceded => {
+                compute_required_call_at_block_start(sub, &required_tids, called_at_entry[&sub.tid])
+            }
+            Order::Followed => compute_required_call_after_block_end(sub, &required_tids),
+        };
+        let is_required_call_made = match is_required_call_made {
+            Ok(values) => values,
+            Err(log) => {
+                logs.push(log.source(CWE_MODULE.name));
+                continue;
+            }
+        };
+        for block in sub.term.blocks.iter() {
+            for jmp in block.term.jmps.iter() {
+                if let Jmp::Call { target, .. } = &jmp.term {
+                    if trigger_tids.contains(target) && !is_required_call_made[&block.tid] {
+                        violations.push((sub, &jmp.tid));
+                    }
+                }
+            }
+        }
+    }
+    (violations, logs)
+}
+
+/// Generate the CWE warning for a violation of a rule.
+fn generate_cwe_warning(rule: &Rule, sub: &Term<Sub>, call_tid: &Tid) -> CweWarning {
+    let name = match rule.cwe {
+        Some(cwe) => format!("CWE{}", cwe),
+        None => CWE_MODULE.name.to_string(),
+    };
+    let order = match rule.order {
+        Order::Preceded => "preceded",
+        Order::Followed => "followed",
+    };
+    let required_symbols = rule.required_symbols.join(", ");
+    let mut warning = CweWarning::new(
+        name,
+        CWE_MODULE.version,
+        format!(
+            "(Call Policy Violation: {}) Call at {} in {} is not {} by a call to {} on all paths",
+            rule.name, call_tid.address, sub.term.name, order, required_symbols
+        ),
+    )
+    .severity(Severity::Medium)
+    .tids(vec![format!("{}", call_tid)])
+    .addresses(vec![call_tid.address.clone()])
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![vec!["rule".to_string(), rule.name.clone()]]);
+    if let Some(cwe) = rule.cwe {
+        warning = warning.remediation(Remediation::new(
+            cwe,
+            format!(
+                "Make sure that {} is called on all paths {} the call.",
+                required_symbols,
+                match rule.order {
+                    Order::Preceded => "before",
+                    Order::Followed => "after",
+                }
+            ),
+        ));
+    }
+    warning
+}
+
+/// Run the check for all configured rules.
+/// The remaining rules are skipped if the module cancellation token gets cancelled.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
+    let config: Config = serde_json::from_value(cwe_params.clone()).unwrap();
+    let program = &analysis_results.project.program;
+    let mut logs = Vec::new();
+    let mut cwe_warnings = Vec::new();
+    for rule in config.rules.iter() {
+        if analysis_results.cancellation.is_cancelled() {
+            return (logs, cwe_warnings, true);
+        }
+        let (violations, rule_logs) = check_rule(program, rule);
+        for (sub, call_tid) in violations {
+            cwe_warnings.push(generate_cwe_warning(rule, sub, call_tid));
+        }
+        logs.extend(rule_logs);
+    }
+    (logs, cwe_warnings, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(target: &str, return_block: &str) -> Jmp {
+        Jmp::Call {
+            target: Tid::new(target),
+            return_: Some(Tid::new(return_block)),
+        }
+    }
+
+    fn return_jmp() -> Jmp {
+        Jmp::Return(Expression::Var(Variable::mock("RA", 8)))
+    }
+
+    /// `main` calls `init` only on one path and then calls `use` and `func`.
+    /// `func` calls `use` again.
+    fn mock_program() -> Term<Program> {
+        let mut main = Sub::mock("main");
+        main.term.blocks = vec![
+            Blk::mock_with_jmp(
+                "main_start",
+                Jmp::CBranch {
+                    target: Tid::new("main_use"),
+                    condition: Expression::Const(Bitvector::from_u8(0)),
+                },
+            ),
+            Blk::mock_with_jmp("main_init", call("init", "main_use")),
+            Blk::mock_with_jmp("main_use", call("use", "main_call_func")),
+            Blk::mock_with_jmp("main_call_func", call("func", "main_return")),
+            Blk::mock_with_jmp("main_return", return_jmp()),
+        ];
+        main.term.blocks[0].term.jmps.push(Term {
+            tid: Tid::new("main_start_fallthrough"),
+            term: Jmp::Branch(Tid::new("main_init")),
+        });
+        let mut func = Sub::mock("func");
+        func.term.blocks = vec![
+            Blk::mock_with_jmp("func_init", call("init", "func_use")),
+            Blk::mock_with_jmp("func_use", call("use", "func_return")),
+            Blk::mock_with_jmp("func_return", return_jmp()),
+        ];
+        let mut program = Program::mock_empty();
+        program.subs = vec![main, func];
+        program.extern_symbols = vec![
+            ExternSymbol::mock_with_name("init"),
+            ExternSymbol::mock_with_name("use"),
+        ];
+        program.entry_points = vec![Tid::new("main")];
+        Term {
+            tid: Tid::new("program"),
+            term: program,
+        }
+    }
+
+    fn mock_rule(order: Order) -> Rule {
+        Rule {
+            name: "test_rule".to_string(),
+            trigger_symbols: vec!["use".to_string()],
+            required_symbols: vec!["init".to_string()],
+            order,
+            cwe: None,
+        }
+    }
+
+    #[test]
+    fn preceded_rule() {
+        let program = mock_program();
+        let (violations, logs) = check_rule(&program, &mock_rule(Order::Preceded));
+        assert!(logs.is_empty());
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].0.term.name, "main");
+        assert_eq!(*violations[0].1, Tid::new("main_use_jmp"));
+    }
+
+    #[test]
+    fn followed_rule() {
+        let program = mock_program();
+        let mut rule = mock_rule(Order::Followed);
+        rule.trigger_symbols = vec!["init".to_string()];
+        rule.required_symbols = vec!["use".to_string()];
+        assert!(check_rule(&program, &rule).0.is_empty());
+
+        rule.trigger_symbols = vec!["use".to_string()];
+        rule.required_symbols = vec!["init".to_string()];
+        let (violations, _) = check_rule(&program, &rule);
+        assert_eq!(violations.len(), 2);
+    }
+}
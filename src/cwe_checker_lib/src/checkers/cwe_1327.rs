@@ -0,0 +1,540 @@
+//! This module implements checks for the misuse of the BSD socket API:
+//! - CWE-1327: Binding to an Unrestricted IP Address,
+//! - CWE-605: Multiple Binds to the Same Port,
+//! - CWE-306: Missing Authentication for Critical Function (heuristic).
+//!
+//! Network services of embedded devices often listen on all network interfaces
+//! and accept connections without any kind of authentication.
+//!
+//! See <https://cwe.mitre.org/data/definitions/1327.html>,
+//! <https://cwe.mitre.org/data/definitions/605.html>
+//! and <https://cwe.mitre.org/data/definitions/306.html> for detailed descriptions.
+//!
+//! ## How the check works
+//!
+//! Using the results of the [Pointer Inference analysis](crate::analysis::pointer_inference)
+//! the parameters of calls to `bind`, `setsockopt` and `accept` are evaluated.
+//! - If the IPv4 or IPv6 address in the socket address given to `bind` is the wildcard address (i.e. `0.0.0.0` or `::`)
+//! and the port is a constant, a CWE-1327 warning is generated.
+//! - If `setsockopt` is used to enable the `SO_REUSEPORT` socket option,
+//! other sockets of the same user may bind to the same port and receive a share of the incoming connections.
+//! A CWE-605 warning is generated.
+//! `SO_REUSEADDR` is not reported, since on Linux it only allows to rebind a port with connections in the `TIME_WAIT` state
+//! and does not allow binding to a port that a listening socket is already bound to.
+//! - If no function reachable (through direct calls) from the function calling `accept` or from one of its direct callers
+//! calls one of the authentication-related functions configured in the configuration file,
+//! a CWE-306 warning is generated.
+//!
+//! ## False Positives
+//!
+//! - Services that are intended to be reachable on all network interfaces.
+//! - Authentication implemented without calls to any of the configured functions,
+//! in functions that are only called indirectly
+//! or in functions that are not reachable from the accepting function or its direct callers.
+//! Since the CWE-306 check is a heuristic, its warnings have only medium severity.
+//!
+//! ## False Negatives
+//!
+//! - Socket addresses whose content is not tracked by the Pointer Inference analysis,
+//! e.g. if the port is computed by a (non-inlined) call to `htons`.
+//! - Accepted connections that are handled in other threads or processes are assumed to be unauthenticated
+//! only if no authentication function is reachable from the accepting function.
+//! - The socket option constants are only known for Linux.
+//! - Enabling `SO_REUSEADDR` is never reported, even on systems where it allows multiple binds to the same port.
+
+use crate::abstract_domain::{BitvectorDomain, RegisterDomain, SizedDomain};
+use crate::analysis::call_graph::CallGraph;
+use crate::analysis::graph::Node;
+use crate::analysis::interprocedural_fixpoint_generic::NodeValue;
+use crate::analysis::pointer_inference::{Data, State};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{
+    CweWarning, ExploitabilityHint, Impact, LogMessage, Remediation, Severity,
+};
+use crate::{CweModule, RequiredAnalysis};
+use std::collections::{HashMap, HashSet};
+
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE1327",
+    version: "0.2",
+    cwes: &[1327, 605, 306],
+    description: "Misuse of the BSD socket API, e.g. binding to an unrestricted IP address.",
+    required_analyses: &[RequiredAnalysis::PointerInference],
+    config_keys: &["authentication_symbols"],
+    run: check_cwe,
+    validate_config: Some(crate::utils::config_validation::deserialize_section::<Config>),
+};
+
+/// The names of authentication-related functions read from *config.json*.
+/// If none of them is reachable from a function calling `accept`, the accepted connection is assumed to be unauthenticated.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Config {
+    authentication_symbols: Vec<String>,
+}
+
+/// The address family of IPv4 socket addresses.
+const AF_INET: u64 = 2;
+/// The address family of IPv6 socket addresses on Linux.
+const AF_INET6: u64 = 10;
+
+/// Get the Linux values of the constants `SOL_SOCKET` and `SO_REUSEPORT`
+/// for the given CPU architecture.
+fn get_socket_option_constants(cpu_architecture: &str) -> (u64, u64) {
+    if cpu_architecture.starts_with("MIPS") {
+        (0xffff, 0x200)
+    } else {
+        (1, 15)
+    }
+}
+
+/// Get the exactly known value of `data`, if it is a value and not a pointer.
+fn get_known_value(data: &Data) -> Option<u64> {
+    match data {
+        Data::Value(BitvectorDomain::Value(value)) => value.try_to_u64().ok(),
+        _ => None,
+    }
+}
+
+/// Load the value of the given size from the given offset relative to the given pointer.
+fn load_from_pointer(
+    state: &State,
+    pointer: &Data,
+    offset: u64,
+    size: u64,
+    analysis_results: &AnalysisResults,
+) -> Option<Data> {
+    let offset: Data = Bitvector::from_u64(offset)
+        .into_zero_resize(apint::BitWidth::from(pointer.bytesize()))
+        .into();
+    let address = pointer.bin_op(BinOpType::IntAdd, &offset);
+    state
+        .load_value_from_address(
+            &address,
+            ByteSize::new(size),
+            analysis_results.runtime_memory_image,
+        )
+        .ok()
+}
+
+/// Check whether the socket address given to a call to `bind` is the wildcard address with a constant port.
+/// Returns the port on success.
+fn get_constant_port_of_wildcard_bind(
+    state: &State,
+    bind_symbol: &ExternSymbol,
+    analysis_results: &AnalysisResults,
+) -> Option<u16> {
+    let socket_address = state
+        .eval_nth_parameter(
+            analysis_results.project,
+            bind_symbol,
+            1,
+            analysis_results.runtime_memory_image,
+        )
+        .ok()?;
+    let load =
+        |offset, size| load_from_pointer(state, &socket_address, offset, size, analysis_results);
+    let family = get_known_value(&load(0, 2)?)?;
+    // The port is stored in network byte order.
+    let port = get_known_value(&load(2, 2)?)? as u16;
+    let port = if analysis_results
+        .runtime_memory_image
+        .is_little_endian_byte_order()
+    {
+        port.swap_bytes()
+    } else {
+        port
+    };
+    let is_wildcard_address = match family {
+        AF_INET => get_known_value(&load(4, 4)?)? == 0,
+        AF_INET6 => get_known_value(&load(8, 8)?)? == 0 && get_known_value(&load(16, 8)?)? == 0,
+        _ => false,
+    };
+    if is_wildcard_address && port != 0 {
+        Some(port)
+    } else {
+        None
+    }
+}
+
+/// Check whether a call to `setsockopt` enables the `SO_REUSEPORT` option.
+fn enables_port_reuse(
+    state: &State,
+    setsockopt_symbol: &ExternSymbol,
+    analysis_results: &AnalysisResults,
+) -> bool {
+    get_enabled_socket_option(state, setsockopt_symbol, analysis_results).map_or(
+        false,
+        |(level, option_name)| {
+            let (sol_socket, so_reuseport) =
+                get_socket_option_constants(&analysis_results.project.cpu_architecture);
+            level == sol_socket && option_name == so_reuseport
+        },
+    )
+}
+
+/// Get the level and the name of the socket option that a call to `setsockopt` enables,
+/// if all of them are exactly known.
+fn get_enabled_socket_option(
+    state: &State,
+    setsockopt_symbol: &ExternSymbol,
+    analysis_results: &AnalysisResults,
+) -> Option<(u64, u64)> {
+    let project = analysis_results.project;
+    let memory_image = analysis_results.runtime_memory_image;
+    let level = get_known_value(
+        &state
+            .eval_nth_parameter(project, setsockopt_symbol, 1, memory_image)
+            .ok()?,
+    )?;
+    let option_name = get_known_value(
+        &state
+            .eval_nth_parameter(project, setsockopt_symbol, 2, memory_image)
+            .ok()?,
+    )?;
+    let option_value_pointer = state
+        .eval_nth_parameter(project, setsockopt_symbol, 3, memory_image)
+        .ok()?;
+    let option_value = get_known_value(&load_from_pointer(
+        state,
+        &option_value_pointer,
+        0,
+        4,
+        analysis_results,
+    )?)?;
+    if option_value == 0 {
+        None
+    } else {
+        Some((level, option_name))
+    }
+}
+
+/// Check whether one of the given authentication functions is reachable through direct calls
+/// from the function calling `accept` or from one of its direct callers.
+///
+/// Servers often accept the connection in a small helper function
+/// and authenticate the peer in its caller, which is why the callers are searched, too.
+fn is_authentication_reachable(
+    call_graph: &CallGraph,
+    accepting_sub: &Tid,
+    authentication_tids: &HashSet<&Tid>,
+) -> bool {
+    let mut start_tids = call_graph.get_callers(accepting_sub);
+    start_tids.push(accepting_sub.clone());
+    call_graph
+        .get_reachable_functions(&start_tids)
+        .iter()
+        .any(|tid| authentication_tids.contains(tid))
+}
+
+/// Generate the CWE warning for a bind to the wildcard address.
+fn generate_wildcard_bind_warning(sub: &Term<Sub>, call_tid: &Tid, port: u16) -> CweWarning {
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Binding to an Unrestricted IP Address) Socket bound to all network interfaces on port {} at {} in {}",
+            port, call_tid.address, sub.term.name
+        ),
+    )
+    .severity(Severity::Medium)
+    .tids(vec![format!("{}", call_tid)])
+    .addresses(vec![call_tid.address.clone()])
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![vec!["port".to_string(), port.to_string()]])
+    .remediation(Remediation::new(
+        1327,
+        "Bind the socket only to the network interfaces where the service is needed, e.g. to the loopback address for local services. Make the listening address configurable.",
+    ))
+    .exploitability(
+        ExploitabilityHint::new(Impact::InformationExposure).attacker_controlled(true),
+    )
+}
+
+/// Generate the CWE warning for a call to `setsockopt` enabling `SO_REUSEPORT`.
+fn generate_port_reuse_warning(sub: &Term<Sub>, call_tid: &Tid) -> CweWarning {
+    let option = "SO_REUSEPORT";
+    CweWarning::new(
+        "CWE605",
+        CWE_MODULE.version,
+        format!(
+            "(Multiple Binds to the Same Port) Socket option {} enabled at {} in {}",
+            option, call_tid.address, sub.term.name
+        ),
+    )
+    .severity(Severity::Low)
+    .tids(vec![format!("{}", call_tid)])
+    .addresses(vec![call_tid.address.clone()])
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![vec!["socket_option".to_string(), option.to_string()]])
+    .remediation(Remediation::new(
+        605,
+        format!(
+            "Do not enable {} on sockets of network services unless it is required. Where supported, use exclusive binding (e.g. SO_EXCLUSIVEADDRUSE on Windows).",
+            option
+        ),
+    ))
+}
+
+/// Generate the CWE warning for a call to `accept` without any reachable authentication function.
+fn generate_missing_authentication_warning(sub: &Term<Sub>, call_tid: &Tid) -> CweWarning {
+    CweWarning::new(
+        "CWE306",
+        CWE_MODULE.version,
+        format!(
+            "(Missing Authentication for Critical Function) Connection accepted at {} in {} without a reachable call to an authentication function",
+            call_tid.address, sub.term.name
+        ),
+    )
+    .severity(Severity::Medium)
+    .tids(vec![format!("{}", call_tid)])
+    .addresses(vec![call_tid.address.clone()])
+    .symbols(vec![sub.term.name.clone()])
+    .remediation(Remediation::new(
+        306,
+        "Authenticate the peer of every accepted connection before processing its requests, e.g. through mutually authenticated TLS.",
+    ))
+    .exploitability(ExploitabilityHint::new(Impact::CodeExecution).attacker_controlled(true))
+}
+
+/// Run the CWE check.
+/// For each call to `bind`, `setsockopt` and `accept` we evaluate its parameters
+/// with the Pointer Inference state at the call site.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
+    let config: Config = serde_json::from_value(cwe_params.clone()).unwrap();
+    let project = analysis_results.project;
+    let program = &project.program;
+    let authentication_tids: HashSet<&Tid> = program
+        .term
+        .extern_symbols
+        .iter()
+        .filter(|symbol| config.authentication_symbols.contains(&symbol.name))
+        .map(|symbol| &symbol.tid)
+        .collect();
+    let extern_symbols: HashMap<&Tid, &ExternSymbol> = program
+        .term
+        .extern_symbols
+        .iter()
+        .map(|symbol| (&symbol.tid, symbol))
+        .collect();
+    let call_graph = CallGraph::new(program);
+    let pointer_inference_results = analysis_results.pointer_inference.unwrap();
+    let graph = pointer_inference_results.get_graph();
+
+    let mut cwe_warnings = Vec::new();
+    let mut checked_accept_calls = HashSet::new();
+    for node in graph.node_indices() {
+        let (block, sub) = match graph[node] {
+            Node::BlkEnd(block, sub) => (block, sub),
+            _ => continue,
+        };
+        let state = match pointer_inference_results.get_node_value(node) {
+            Some(NodeValue::Value(state)) => state,
+            _ => continue,
+        };
+        for jmp in block.term.jmps.iter() {
+            let symbol = match &jmp.term {
+                Jmp::Call { target, .. } => match extern_symbols.get(target) {
+                    Some(symbol) => *symbol,
+                    None => continue,
+                },
+                _ => continue,
+            };
+            match symbol.name.as_str() {
+                "bind" => {
+                    if let Some(port) =
+                        get_constant_port_of_wildcard_bind(state, symbol, analysis_results)
+                    {
+                        cwe_warnings.push(generate_wildcard_bind_warning(sub, &jmp.tid, port));
+                    }
+                }
+                "setsockopt" => {
+                    if enables_port_reuse(state, symbol, analysis_results) {
+                        cwe_warnings.push(generate_port_reuse_warning(sub, &jmp.tid));
+                    }
+                }
+                "accept" | "accept4" => {
+                    // The same call may be contained in several nodes of the graph.
+                    if !checked_accept_calls.insert(jmp.tid.clone()) {
+                        continue;
+                    }
+                    if !is_authentication_reachable(&call_graph, &sub.tid, &authentication_tids) {
+                        cwe_warnings.push(generate_missing_authentication_warning(sub, &jmp.tid));
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+    cwe_warnings.sort_by(|a, b| a.addresses.cmp(&b.addresses).then(a.name.cmp(&b.name)));
+    cwe_warnings.dedup();
+    (Vec::new(), cwe_warnings, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::call_graph::tests::mock_program;
+    use crate::analysis::graph::Graph;
+    use crate::utils::binary::RuntimeMemoryImage;
+
+    #[test]
+    fn socket_option_constants() {
+        assert_eq!(get_socket_option_constants("x86_64"), (1, 15));
+        assert_eq!(get_socket_option_constants("MIPS_32"), (0xffff, 0x200));
+    }
+
+    #[test]
+    fn known_values() {
+        assert_eq!(
+            get_known_value(&Data::Value(Bitvector::from_u16(2).into())),
+            Some(2)
+        );
+        assert_eq!(get_known_value(&Data::new_top(ByteSize::new(2))), None);
+    }
+
+    /// Create a symbol with the parameters of the x86_64 System V calling convention.
+    fn mock_socket_symbol(name: &str) -> ExternSymbol {
+        let mut symbol = ExternSymbol::mock_with_name(name);
+        symbol.parameters = ["RDI", "RSI", "RDX", "RCX", "R8"]
+            .iter()
+            .map(|register| Arg::mock_register(*register))
+            .collect();
+        symbol
+    }
+
+    /// Create a state where `RSI` points to an IPv4 socket address for the given port and address.
+    fn mock_bind_state(port: u16, address: u32, memory_image: &RuntimeMemoryImage) -> State {
+        let rsp = Variable::mock("RSP", 8);
+        let socket_address = Expression::Var(rsp.clone()).plus_const(-16);
+        let mut state = State::new(&rsp, Tid::new("func"));
+        let store = |state: &mut State, offset: i64, value: Bitvector| {
+            state
+                .handle_store(
+                    &socket_address.clone().plus_const(offset),
+                    &Expression::Const(value),
+                    memory_image,
+                )
+                .unwrap();
+        };
+        store(&mut state, 0, Bitvector::from_u16(AF_INET as u16));
+        // The mocked memory image is little endian and the port is given in network byte order.
+        store(&mut state, 2, Bitvector::from_u16(port.swap_bytes()));
+        store(&mut state, 4, Bitvector::from_u32(address));
+        state
+            .handle_register_assign(&Variable::mock("RSI", 8), &socket_address)
+            .unwrap();
+        state
+    }
+
+    /// Create a state for a call to `setsockopt` enabling the given option on the `SOL_SOCKET` level.
+    fn mock_setsockopt_state(option_name: u64, memory_image: &RuntimeMemoryImage) -> State {
+        let rsp = Variable::mock("RSP", 8);
+        let option_value = Expression::Var(rsp.clone()).plus_const(-8);
+        let mut state = State::new(&rsp, Tid::new("func"));
+        state
+            .handle_store(
+                &option_value,
+                &Expression::Const(Bitvector::from_u32(1)),
+                memory_image,
+            )
+            .unwrap();
+        let assign = |state: &mut State, register: &str, value: Expression| {
+            state
+                .handle_register_assign(&Variable::mock(register, 8), &value)
+                .unwrap();
+        };
+        assign(&mut state, "RSI", Expression::Const(Bitvector::from_u64(1)));
+        assign(
+            &mut state,
+            "RDX",
+            Expression::Const(Bitvector::from_u64(option_name)),
+        );
+        assign(&mut state, "RCX", option_value);
+        state
+    }
+
+    #[test]
+    fn wildcard_bind() {
+        let project = Project::mock_empty();
+        let memory_image = RuntimeMemoryImage::mock();
+        let graph = Graph::new();
+        let analysis_results = AnalysisResults::new(&[], &memory_image, &graph, &project);
+        let bind = mock_socket_symbol("bind");
+
+        let state = mock_bind_state(8080, 0, &memory_image);
+        assert_eq!(
+            get_constant_port_of_wildcard_bind(&state, &bind, &analysis_results),
+            Some(8080)
+        );
+        // 127.0.0.1 in network byte order
+        let state = mock_bind_state(8080, 0x0100_007f, &memory_image);
+        assert_eq!(
+            get_constant_port_of_wildcard_bind(&state, &bind, &analysis_results),
+            None
+        );
+        // Port 0 lets the operating system choose a free port.
+        let state = mock_bind_state(0, 0, &memory_image);
+        assert_eq!(
+            get_constant_port_of_wildcard_bind(&state, &bind, &analysis_results),
+            None
+        );
+    }
+
+    #[test]
+    fn port_reuse() {
+        let project = Project::mock_empty();
+        let memory_image = RuntimeMemoryImage::mock();
+        let graph = Graph::new();
+        let analysis_results = AnalysisResults::new(&[], &memory_image, &graph, &project);
+        let setsockopt = mock_socket_symbol("setsockopt");
+
+        let state = mock_setsockopt_state(15, &memory_image);
+        assert!(enables_port_reuse(&state, &setsockopt, &analysis_results));
+        // SO_REUSEADDR is not reported.
+        let state = mock_setsockopt_state(2, &memory_image);
+        assert!(!enables_port_reuse(&state, &setsockopt, &analysis_results));
+    }
+
+    #[test]
+    fn authentication_search() {
+        let mut program = mock_program();
+        // `func` calls `mock_symbol`, which takes the place of `accept` here.
+        // `main` calls `func` and the authentication function.
+        program.term.subs[0].term.blocks[0].term.jmps.push(Term {
+            tid: Tid::new("call_authenticate"),
+            term: Jmp::Call {
+                target: Tid::new("authenticate"),
+                return_: None,
+            },
+        });
+        program
+            .term
+            .extern_symbols
+            .push(ExternSymbol::mock_with_name("authenticate"));
+        let call_graph = CallGraph::new(&program);
+
+        let authenticate_tid = Tid::new("authenticate");
+        let authentication_tids: HashSet<&Tid> = vec![&authenticate_tid].into_iter().collect();
+        // The authentication function is only reachable from the caller of `func`.
+        assert!(is_authentication_reachable(
+            &call_graph,
+            &Tid::new("func"),
+            &authentication_tids
+        ));
+        assert!(!is_authentication_reachable(
+            &call_graph,
+            &Tid::new("func"),
+            &HashSet::new()
+        ));
+        // `unused` has no callers and does not call the authentication function.
+        assert!(!is_authentication_reachable(
+            &call_graph,
+            &Tid::new("unused"),
+            &authentication_tids
+        ));
+    }
+}
@@ -0,0 +1,297 @@
+//! This module implements a check for CWE-1395: Dependency on Vulnerable Third-Party Component.
+//!
+//! Firmware images often contain outdated versions of common libraries and tools
+//! with publicly known vulnerabilities.
+//! Knowing the versions of the included components helps to prioritize the other findings for the binary.
+//!
+//! See <https://cwe.mitre.org/data/definitions/1395.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! All segments of the memory image are scanned for the version strings of the libraries configured in the configuration file,
+//! e.g. `OpenSSL 1.0.1e` or `BusyBox v1.19.4`.
+//! If a CVE mapping file is configured, the found versions are matched against it
+//! and a warning listing the CVEs is generated for each match.
+//! Optionally, informational warnings are generated for all found versions.
+//!
+//! The CVE mapping file is a JSON file containing a list of entries of the form
+//! `{"library": "OpenSSL", "versions": ["1.0.1", "1.0.1a", "1.0.1b"], "cves": ["CVE-2014-0160"]}`.
+//! A version ending with `*` matches all versions starting with the part before the `*`.
+//!
+//! ## False Positives
+//!
+//! - Version strings of components that are not actually used by the binary.
+//! - Vendor backports of security fixes that do not change the version string.
+//!
+//! ## False Negatives
+//!
+//! - Libraries that do not contain version strings or whose version strings are not configured.
+//! - Vulnerable versions missing from the CVE mapping file.
+
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage, Remediation, Severity};
+use crate::CweModule;
+use std::collections::BTreeMap;
+
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE1395",
+    version: "0.1",
+    cwes: &[1395],
+    description: "Dependency on vulnerable versions of third-party libraries.",
+    required_analyses: &[],
+    config_keys: &["libraries", "report_all_versions", "cve_mapping_file"],
+    run: check_cwe,
+    validate_config: Some(crate::utils::config_validation::deserialize_section::<Config>),
+};
+
+/// The maximal length of a version number.
+const MAX_VERSION_LENGTH: usize = 32;
+
+/// A library together with the marker that precedes the version number in its version string.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct LibraryMarker {
+    /// The name of the library.
+    name: String,
+    /// The text directly preceding the version number, e.g. `OpenSSL `.
+    marker: String,
+}
+
+/// The configuration struct of the check.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Config {
+    /// The libraries whose version strings are searched for.
+    libraries: Vec<LibraryMarker>,
+    /// If set, all found versions are reported, even if no CVEs are known for them.
+    report_all_versions: bool,
+    /// The path to an optional CVE mapping file.
+    #[serde(default)]
+    cve_mapping_file: Option<String>,
+}
+
+/// An entry of the CVE mapping file.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct CveMapping {
+    /// The name of the library as given in the configuration of the check.
+    library: String,
+    /// The affected versions. Versions ending with `*` are prefixes of the affected versions.
+    versions: Vec<String>,
+    /// The CVE identifiers of the known vulnerabilities of the affected versions.
+    cves: Vec<String>,
+}
+
+impl CveMapping {
+    /// Returns `true` if the mapping applies to the given library version.
+    fn matches(&self, library: &str, version: &str) -> bool {
+        self.library == library
+            && self
+                .versions
+                .iter()
+                .any(|affected| match affected.strip_suffix('*') {
+                    Some(prefix) => version.starts_with(prefix),
+                    None => version == affected,
+                })
+    }
+}
+
+/// Read the version number at the start of `bytes`.
+///
+/// A version number starts with a digit, contains at least one dot
+/// and consists of alphanumeric characters, dots, dashes and underscores.
+fn read_version(bytes: &[u8]) -> Option<String> {
+    if !bytes.get(0)?.is_ascii_digit() {
+        return None;
+    }
+    let length = bytes
+        .iter()
+        .take(MAX_VERSION_LENGTH + 1)
+        .take_while(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'.' | b'-' | b'_'))
+        .count();
+    if length > MAX_VERSION_LENGTH {
+        return None;
+    }
+    let version = std::str::from_utf8(&bytes[..length])
+        .ok()?
+        .trim_end_matches(&['.', '-', '_'][..]);
+    if version.contains('.') {
+        Some(version.to_string())
+    } else {
+        None
+    }
+}
+
+/// Find the versions of all configured libraries in the given memory segment.
+/// The found versions are added to `versions` together with their addresses.
+fn find_versions(
+    libraries: &[LibraryMarker],
+    base_address: u64,
+    bytes: &[u8],
+    versions: &mut BTreeMap<(String, String), Vec<u64>>,
+) {
+    for library in libraries.iter() {
+        let marker = library.marker.as_bytes();
+        if marker.is_empty() {
+            continue;
+        }
+        for (offset, window) in bytes.windows(marker.len()).enumerate() {
+            if window != marker {
+                continue;
+            }
+            if let Some(version) = read_version(&bytes[offset + marker.len()..]) {
+                versions
+                    .entry((library.name.clone(), version))
+                    .or_insert_with(Vec::new)
+                    .push(base_address + offset as u64);
+            }
+        }
+    }
+}
+
+/// Generate the warning for a found library version
+/// together with the CVEs known for this version.
+fn generate_cwe_warning(
+    library: &str,
+    version: &str,
+    addresses: &[u64],
+    cves: &[&str],
+) -> CweWarning {
+    let addresses: Vec<String> = addresses
+        .iter()
+        .map(|address| format!("{:08x}", address))
+        .collect();
+    let description = if cves.is_empty() {
+        format!(
+            "(Dependency on Vulnerable Third-Party Component) Found version string of {} {} at {}",
+            library, version, addresses[0]
+        )
+    } else {
+        format!(
+            "(Dependency on Vulnerable Third-Party Component) {} {} found at {} is affected by {}",
+            library,
+            version,
+            addresses[0],
+            cves.join(", ")
+        )
+    };
+    let mut other = vec![
+        vec!["library".to_string(), library.to_string()],
+        vec!["version".to_string(), version.to_string()],
+    ];
+    if !cves.is_empty() {
+        let mut cve_list = vec!["cves".to_string()];
+        cve_list.extend(cves.iter().map(|cve| cve.to_string()));
+        other.push(cve_list);
+    }
+    CweWarning::new(CWE_MODULE.name, CWE_MODULE.version, description)
+        .severity(Severity::Medium)
+        .addresses(addresses)
+        .symbols(vec![library.to_string()])
+        .other(other)
+        .remediation(Remediation::new(
+            1395,
+            format!(
+                "Update {} to a maintained version without known vulnerabilities and check whether the vulnerable functionality is used by the binary.",
+                library
+            ),
+        ))
+}
+
+/// Read the CVE mapping file at the given path.
+fn read_cve_mappings(path: &str) -> Result<Vec<CveMapping>, Error> {
+    let file = std::fs::read_to_string(path)
+        .map_err(|err| anyhow!("Could not read CVE mapping file {}: {}", path, err))?;
+    Ok(serde_json::from_str(&file)?)
+}
+
+/// Run the check.
+///
+/// The memory image is scanned for version strings of the configured libraries.
+/// Warnings are generated for versions with known CVEs
+/// and, if configured, for all other found versions.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
+    let config: Config = serde_json::from_value(cwe_params.clone()).unwrap();
+    let mut logs = Vec::new();
+    let cve_mappings = match &config.cve_mapping_file {
+        Some(path) => read_cve_mappings(path).unwrap_or_else(|err| {
+            logs.push(LogMessage::new_error(format!("{}", err)).source(CWE_MODULE.name));
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+    let mut versions = BTreeMap::new();
+    for (base_address, bytes) in analysis_results.runtime_memory_image.iter_segments() {
+        find_versions(&config.libraries, base_address, bytes, &mut versions);
+    }
+    let mut cwe_warnings = Vec::new();
+    for ((library, version), addresses) in versions.iter() {
+        let cves: Vec<&str> = cve_mappings
+            .iter()
+            .filter(|mapping| mapping.matches(library, version))
+            .flat_map(|mapping| mapping.cves.iter().map(|cve| cve.as_str()))
+            .collect();
+        if !cves.is_empty() || config.report_all_versions {
+            cwe_warnings.push(generate_cwe_warning(library, version, addresses, &cves));
+        }
+    }
+    (logs, cwe_warnings, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_parsing() {
+        assert_eq!(
+            read_version(b"1.0.1e 11 Feb 2013"),
+            Some("1.0.1e".to_string())
+        );
+        assert_eq!(read_version(b"1.19.4. "), Some("1.19.4".to_string()));
+        assert_eq!(read_version(b"v1.19.4"), None);
+        assert_eq!(read_version(b"2013\0"), None);
+    }
+
+    #[test]
+    fn version_detection() {
+        let libraries = vec![
+            LibraryMarker {
+                name: "OpenSSL".to_string(),
+                marker: "OpenSSL ".to_string(),
+            },
+            LibraryMarker {
+                name: "BusyBox".to_string(),
+                marker: "BusyBox v".to_string(),
+            },
+        ];
+        let segment =
+            b"\0OpenSSL 1.0.1e 11 Feb 2013\0BusyBox v1.19.4 (2013-02-22)\0OpenSSL 1.0.1e\0";
+        let mut versions = BTreeMap::new();
+        find_versions(&libraries, 0x1000, segment, &mut versions);
+        assert_eq!(versions.len(), 2);
+        assert_eq!(
+            versions[&("OpenSSL".to_string(), "1.0.1e".to_string())],
+            vec![0x1001, 0x1039]
+        );
+        assert_eq!(
+            versions[&("BusyBox".to_string(), "1.19.4".to_string())],
+            vec![0x101c]
+        );
+
+        let mapping = CveMapping {
+            library: "OpenSSL".to_string(),
+            versions: vec!["1.0.1".to_string(), "1.0.1*".to_string()],
+            cves: vec!["CVE-2014-0160".to_string()],
+        };
+        assert!(mapping.matches("OpenSSL", "1.0.1e"));
+        assert!(!mapping.matches("OpenSSL", "1.0.2"));
+        assert!(!mapping.matches("BusyBox", "1.0.1"));
+
+        let warning = generate_cwe_warning("OpenSSL", "1.0.1e", &[0x1001], &["CVE-2014-0160"]);
+        assert_eq!(
+            warning.description,
+            "(Dependency on Vulnerable Third-Party Component) OpenSSL 1.0.1e found at 00001001 is affected by CVE-2014-0160"
+        );
+    }
+}
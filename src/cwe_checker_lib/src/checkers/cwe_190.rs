@@ -30,14 +30,21 @@
 
 use crate::intermediate_representation::*;
 use crate::prelude::*;
-use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::log::{
+    CweWarning, ExploitabilityHint, Impact, LogMessage, Remediation, Severity,
+};
 use crate::utils::symbol_utils::{get_callsites, get_symbol_map};
 use crate::CweModule;
 
 pub static CWE_MODULE: CweModule = CweModule {
     name: "CWE190",
     version: "0.1",
+    cwes: &[190],
+    description: "Integer overflows in the size arguments of memory allocation functions.",
+    required_analyses: &[],
+    config_keys: &["symbols"],
     run: check_cwe,
+    validate_config: Some(crate::utils::config_validation::deserialize_section::<Config>),
 };
 
 /// The configuration struct.
@@ -91,9 +98,18 @@ fn generate_cwe_warning(callsite: &Tid, called_symbol: &ExternSymbol) -> CweWarn
             "(Integer Overflow or Wraparound) Potential overflow due to multiplication before call to {} at {}",
             called_symbol.name, callsite.address
         ))
+        .severity(Severity::High)
         .tids(vec![format!("{}", callsite)])
         .addresses(vec![callsite.address.clone()])
         .symbols(vec![called_symbol.name.clone()])
+        .remediation(Remediation::new(
+            190,
+            format!(
+                "Check the operands of the multiplication for overflow before passing the result to {}, e.g. by using calloc or __builtin_mul_overflow.",
+                called_symbol.name
+            ),
+        ))
+        .exploitability(ExploitabilityHint::new(Impact::MemoryWrite))
 }
 
 /// Run the CWE check.
@@ -102,7 +118,7 @@ fn generate_cwe_warning(callsite: &Tid, called_symbol: &ExternSymbol) -> CweWarn
 pub fn check_cwe(
     analysis_results: &AnalysisResults,
     cwe_params: &serde_json::Value,
-) -> (Vec<LogMessage>, Vec<CweWarning>) {
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
     let project = analysis_results.project;
     let config: Config = serde_json::from_value(cwe_params.clone()).unwrap();
     let mut cwe_warnings = Vec::new();
@@ -115,5 +131,5 @@ pub fn check_cwe(
         }
     }
 
-    (Vec::new(), cwe_warnings)
+    (Vec::new(), cwe_warnings, false)
 }
@@ -19,13 +19,20 @@
 //! None known.
 
 use crate::prelude::*;
-use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::log::{
+    CweWarning, ExploitabilityHint, Impact, LogMessage, Remediation, Severity,
+};
 use crate::CweModule;
 
 pub static CWE_MODULE: CweModule = CweModule {
     name: "CWE215",
     version: "0.2",
+    cwes: &[215],
+    description: "Information exposure through debug information contained in the binary.",
+    required_analyses: &[],
+    config_keys: &[],
     run: check_cwe,
+    validate_config: None,
 };
 
 /// Run the check.
@@ -35,7 +42,7 @@ pub static CWE_MODULE: CweModule = CweModule {
 pub fn check_cwe(
     analysis_results: &AnalysisResults,
     _cwe_params: &serde_json::Value,
-) -> (Vec<LogMessage>, Vec<CweWarning>) {
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
     let binary = analysis_results.binary;
 
     match goblin::Object::parse(binary) {
@@ -47,24 +54,30 @@ pub fn check_cwe(
                             CWE_MODULE.name,
                             CWE_MODULE.version,
                             "(Information Exposure Through Debug Information) The binary contains debug symbols."
-                        );
-                        return (Vec::new(), vec![cwe_warning]);
+                        )
+                        .severity(Severity::Info)
+                        .remediation(Remediation::new(
+                            215,
+                            "Strip the debug information from release builds, e.g. with `strip --strip-debug`.",
+                        ))
+                        .exploitability(ExploitabilityHint::new(Impact::InformationExposure));
+                        return (Vec::new(), vec![cwe_warning], false);
                     }
                 }
             }
-            (Vec::new(), Vec::new())
+            (Vec::new(), Vec::new(), false)
         }
         Ok(_) => {
             let info_log = LogMessage::new_info(
                 "File type not supported. Currently this check only supports ELF files.",
             )
             .source(CWE_MODULE.name);
-            (vec![info_log], Vec::new())
+            (vec![info_log], Vec::new(), false)
         }
         Err(err) => {
             let err_log = LogMessage::new_error(format!("Error while parsing binary: {}", err))
                 .source(CWE_MODULE.name);
-            (vec![err_log], Vec::new())
+            (vec![err_log], Vec::new(), false)
         }
     }
 }
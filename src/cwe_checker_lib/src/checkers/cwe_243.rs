@@ -29,14 +29,19 @@ use crate::analysis::graph::Node;
 use crate::intermediate_representation::*;
 use crate::prelude::*;
 use crate::utils::graph_utils::is_sink_call_reachable_from_source_call;
-use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::log::{CweWarning, LogMessage, Remediation, Severity};
 use crate::utils::symbol_utils::find_symbol;
 use crate::CweModule;
 
 pub static CWE_MODULE: CweModule = CweModule {
     name: "CWE243",
     version: "0.2",
+    cwes: &[243],
+    description: "Creation of a chroot jail without changing the working directory.",
+    required_analyses: &[],
+    config_keys: &["priviledge_dropping_functions"],
     run: check_cwe,
+    validate_config: Some(crate::utils::config_validation::deserialize_section::<Config>),
 };
 
 /// The configuration struct contains the list of functions
@@ -98,9 +103,20 @@ fn generate_cwe_warning(sub: &Term<Sub>, callsite: &Tid) -> CweWarning {
             "(The program utilizes chroot without dropping privileges and/or changing the directory) at {} ({})",
             callsite.address, sub.term.name
         ))
+        .severity(Severity::Medium)
         .tids(vec![format!("{}", callsite)])
         .addresses(vec![callsite.address.clone()])
         .symbols(vec![sub.term.name.clone()])
+        .remediation(
+            Remediation::new(
+                243,
+                format!(
+                    "Call chdir(\"/\") directly after chroot in {} and drop privileges afterwards.",
+                    sub.term.name
+                ),
+            )
+            .reference("http://www.unixwiz.net/techtips/chroot-practices.html"),
+        )
 }
 
 /// Run the check.
@@ -114,7 +130,7 @@ fn generate_cwe_warning(sub: &Term<Sub>, callsite: &Tid) -> CweWarning {
 pub fn check_cwe(
     analysis_results: &AnalysisResults,
     cwe_params: &serde_json::Value,
-) -> (Vec<LogMessage>, Vec<CweWarning>) {
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
     let project = analysis_results.project;
     let graph = analysis_results.control_flow_graph;
 
@@ -133,7 +149,7 @@ pub fn check_cwe(
 
     let chroot_tid = match find_symbol(&project.program, "chroot") {
         Some((tid, _)) => tid.clone(),
-        None => return (Vec::new(), Vec::new()), // chroot is never called by the program
+        None => return (Vec::new(), Vec::new(), false), // chroot is never called by the program
     };
 
     let mut cwe_warnings = Vec::new();
@@ -174,5 +190,5 @@ pub fn check_cwe(
         }
     }
 
-    (Vec::new(), cwe_warnings)
+    (Vec::new(), cwe_warnings, false)
 }
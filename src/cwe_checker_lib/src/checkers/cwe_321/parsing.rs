@@ -0,0 +1,459 @@
+//! Minimal parsers for PEM and DER encoded keys and certificates.
+//!
+//! Only the parts of the encodings needed to extract the metadata reported by the check
+//! (key type, key size and expiry date) are parsed.
+//! The parsers are strict about the structure of the parsed elements,
+//! since they are applied to arbitrary bytes of the memory image.
+
+/// DER tag of an INTEGER.
+const TAG_INTEGER: u8 = 0x02;
+/// DER tag of a BIT STRING.
+const TAG_BIT_STRING: u8 = 0x03;
+/// DER tag of an OCTET STRING.
+const TAG_OCTET_STRING: u8 = 0x04;
+/// DER tag of an OBJECT IDENTIFIER.
+const TAG_OID: u8 = 0x06;
+/// DER tag of an UTCTime.
+const TAG_UTC_TIME: u8 = 0x17;
+/// DER tag of a GeneralizedTime.
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+/// DER tag of a SEQUENCE.
+const TAG_SEQUENCE: u8 = 0x30;
+/// DER tag of the explicitly tagged version field of a certificate.
+const TAG_CONTEXT_0: u8 = 0xa0;
+
+/// The object identifier of RSA keys.
+const OID_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+/// The object identifier of elliptic curve keys.
+const OID_EC: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+/// The object identifier of DSA keys.
+const OID_DSA: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x38, 0x04, 0x01];
+/// The object identifier of Ed25519 keys.
+const OID_ED25519: &[u8] = &[0x2b, 0x65, 0x70];
+/// The object identifier of X25519 keys.
+const OID_X25519: &[u8] = &[0x2b, 0x65, 0x6e];
+
+/// The object identifiers of common elliptic curves together with their key sizes in bits.
+const EC_CURVES: &[(&[u8], u64)] = &[
+    (&[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07], 256),
+    (&[0x2b, 0x81, 0x04, 0x00, 0x0a], 256),
+    (&[0x2b, 0x81, 0x04, 0x00, 0x22], 384),
+    (&[0x2b, 0x81, 0x04, 0x00, 0x23], 521),
+];
+
+/// Metadata of a key or certificate.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Metadata {
+    /// The type of the (public) key, e.g. `RSA` or `EC`.
+    pub key_type: Option<String>,
+    /// The size of the key in bits.
+    pub key_size: Option<u64>,
+    /// The end of the validity period of a certificate in the format `YYYY-MM-DD`.
+    pub expiry: Option<String>,
+}
+
+impl std::fmt::Display for Metadata {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        match (&self.key_type, self.key_size) {
+            (Some(key_type), Some(size)) => parts.push(format!("{} {} bit", key_type, size)),
+            (Some(key_type), None) => parts.push(key_type.clone()),
+            (None, Some(size)) => parts.push(format!("{} bit", size)),
+            (None, None) => parts.push("unknown key type".to_string()),
+        }
+        if let Some(expiry) = &self.expiry {
+            parts.push(format!("expires {}", expiry));
+        }
+        write!(formatter, "{}", parts.join(", "))
+    }
+}
+
+/// A DER element, i.e. its tag together with its content.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Element<'a> {
+    tag: u8,
+    content: &'a [u8],
+}
+
+/// Parse the DER element at the start of `bytes`.
+/// Returns the element and the number of bytes it occupies (including its header).
+fn parse_element(bytes: &[u8]) -> Option<(Element, usize)> {
+    let tag = *bytes.get(0)?;
+    let first_length_byte = *bytes.get(1)?;
+    let (length, header_length) = if first_length_byte < 0x80 {
+        (first_length_byte as usize, 2)
+    } else {
+        let num_length_bytes = (first_length_byte & 0x7f) as usize;
+        if num_length_bytes == 0 || num_length_bytes > 3 {
+            return None;
+        }
+        let mut length = 0usize;
+        for byte in bytes.get(2..2 + num_length_bytes)? {
+            length = (length << 8) | *byte as usize;
+        }
+        (length, 2 + num_length_bytes)
+    };
+    let content = bytes.get(header_length..header_length + length)?;
+    Some((Element { tag, content }, header_length + length))
+}
+
+/// Parse the content of a constructed element into the list of its child elements.
+/// Returns `None` if the content is not a valid concatenation of DER elements.
+fn parse_children(content: &[u8]) -> Option<Vec<Element>> {
+    let mut children = Vec::new();
+    let mut remaining = content;
+    while !remaining.is_empty() {
+        let (element, length) = parse_element(remaining)?;
+        children.push(element);
+        remaining = &remaining[length..];
+    }
+    Some(children)
+}
+
+/// Parse the children of the given element if it has the expected tag.
+fn parse_children_with_tag<'a>(element: &Element<'a>, tag: u8) -> Option<Vec<Element<'a>>> {
+    if element.tag == tag {
+        parse_children(element.content)
+    } else {
+        None
+    }
+}
+
+/// Return the length of the DER-encoded sequence at the start of `bytes`
+/// if the length is encoded in a valid way and the sequence fits into `bytes`.
+pub fn get_sequence_length(bytes: &[u8]) -> Option<usize> {
+    match parse_element(bytes)? {
+        (
+            Element {
+                tag: TAG_SEQUENCE, ..
+            },
+            length,
+        ) => Some(length),
+        _ => None,
+    }
+}
+
+/// Return the number of significant bits of the given DER integer.
+fn get_integer_bit_length(element: &Element) -> Option<u64> {
+    if element.tag != TAG_INTEGER {
+        return None;
+    }
+    let first_nonzero = element.content.iter().position(|byte| *byte != 0)?;
+    let significant = &element.content[first_nonzero..];
+    Some((significant.len() as u64 - 1) * 8 + (8 - significant[0].leading_zeros() as u64))
+}
+
+/// Returns `true` if the element is a small integer with the given value.
+fn is_small_integer(element: &Element, value: u8) -> bool {
+    element.tag == TAG_INTEGER && element.content == [value]
+}
+
+/// Get the key size in bits of the elliptic curve with the given object identifier.
+fn get_curve_size(oid: &Element) -> Option<u64> {
+    if oid.tag != TAG_OID {
+        return None;
+    }
+    EC_CURVES
+        .iter()
+        .find(|(curve_oid, _)| *curve_oid == oid.content)
+        .map(|(_, size)| *size)
+}
+
+/// Parse an algorithm identifier and return the key type
+/// together with the key size if the size is determined by the algorithm parameters.
+fn parse_algorithm_identifier(element: &Element) -> Option<(String, Option<u64>)> {
+    let children = parse_children_with_tag(element, TAG_SEQUENCE)?;
+    let oid = children.get(0).filter(|oid| oid.tag == TAG_OID)?;
+    let (key_type, key_size) = match oid.content {
+        OID_RSA => ("RSA", None),
+        OID_EC => ("EC", children.get(1).and_then(get_curve_size)),
+        OID_DSA => ("DSA", None),
+        OID_ED25519 => ("Ed25519", Some(256)),
+        OID_X25519 => ("X25519", Some(256)),
+        _ => ("unknown", None),
+    };
+    Some((key_type.to_string(), key_size))
+}
+
+/// Parse the children of an RSA private key in PKCS#1 format.
+fn parse_pkcs1_rsa_key(children: &[Element]) -> Option<Metadata> {
+    // version, modulus, public exponent, private exponent, two primes, two exponents, coefficient
+    if children.len() != 9
+        || !is_small_integer(&children[0], 0)
+        || children.iter().any(|child| child.tag != TAG_INTEGER)
+    {
+        return None;
+    }
+    Some(Metadata {
+        key_type: Some("RSA".to_string()),
+        key_size: get_integer_bit_length(&children[1]),
+        expiry: None,
+    })
+}
+
+/// Parse the children of a DSA private key in the OpenSSL format.
+fn parse_dsa_key(children: &[Element]) -> Option<Metadata> {
+    // version, p, q, g, public key, private key
+    if children.len() != 6
+        || !is_small_integer(&children[0], 0)
+        || children.iter().any(|child| child.tag != TAG_INTEGER)
+    {
+        return None;
+    }
+    Some(Metadata {
+        key_type: Some("DSA".to_string()),
+        key_size: get_integer_bit_length(&children[1]),
+        expiry: None,
+    })
+}
+
+/// Parse the children of an elliptic curve private key in SEC1 format.
+fn parse_sec1_ec_key(children: &[Element]) -> Option<Metadata> {
+    if children.len() < 2
+        || !is_small_integer(&children[0], 1)
+        || children[1].tag != TAG_OCTET_STRING
+        || !(16..=66).contains(&children[1].content.len())
+    {
+        return None;
+    }
+    let curve_size = children
+        .iter()
+        .find(|child| child.tag == TAG_CONTEXT_0)
+        .and_then(|parameters| parse_element(parameters.content))
+        .and_then(|(oid, _)| get_curve_size(&oid));
+    Some(Metadata {
+        key_type: Some("EC".to_string()),
+        key_size: curve_size.or_else(|| Some(children[1].content.len() as u64 * 8)),
+        expiry: None,
+    })
+}
+
+/// Parse the children of a private key in PKCS#8 format.
+fn parse_pkcs8_key(children: &[Element]) -> Option<Metadata> {
+    if children.len() < 3
+        || !(is_small_integer(&children[0], 0) || is_small_integer(&children[0], 1))
+        || children[2].tag != TAG_OCTET_STRING
+    {
+        return None;
+    }
+    let (key_type, mut key_size) = parse_algorithm_identifier(&children[1])?;
+    if key_size.is_none() {
+        let (inner_key, _) = parse_element(children[2].content)?;
+        let inner_children = parse_children_with_tag(&inner_key, TAG_SEQUENCE);
+        key_size = match (key_type.as_str(), inner_children) {
+            ("RSA", Some(inner_children)) => {
+                parse_pkcs1_rsa_key(&inner_children).and_then(|metadata| metadata.key_size)
+            }
+            ("EC", Some(inner_children)) => {
+                parse_sec1_ec_key(&inner_children).and_then(|metadata| metadata.key_size)
+            }
+            _ => None,
+        };
+    }
+    Some(Metadata {
+        key_type: Some(key_type),
+        key_size,
+        expiry: None,
+    })
+}
+
+/// Parse a DER-encoded private key in PKCS#1 (RSA), SEC1 (EC), OpenSSL (DSA) or PKCS#8 format.
+/// Returns `None` if the bytes are not a private key in one of these formats.
+pub fn parse_private_key(der: &[u8]) -> Option<Metadata> {
+    let (key, length) = parse_element(der)?;
+    if length != der.len() {
+        return None;
+    }
+    let children = parse_children_with_tag(&key, TAG_SEQUENCE)?;
+    parse_pkcs1_rsa_key(&children)
+        .or_else(|| parse_dsa_key(&children))
+        .or_else(|| parse_sec1_ec_key(&children))
+        .or_else(|| parse_pkcs8_key(&children))
+}
+
+/// Format the given UTCTime or GeneralizedTime as `YYYY-MM-DD`.
+fn parse_time(element: &Element) -> Option<String> {
+    let time = std::str::from_utf8(element.content).ok()?;
+    let (year, rest) = match element.tag {
+        TAG_UTC_TIME => {
+            let year: u32 = time.get(0..2)?.parse().ok()?;
+            let year = if year >= 50 { 1900 + year } else { 2000 + year };
+            (year, time.get(2..)?)
+        }
+        TAG_GENERALIZED_TIME => (time.get(0..4)?.parse().ok()?, time.get(4..)?),
+        _ => return None,
+    };
+    let month: u32 = rest.get(0..2)?.parse().ok()?;
+    let day: u32 = rest.get(2..4)?.parse().ok()?;
+    Some(format!("{:04}-{:02}-{:02}", year, month, day))
+}
+
+/// Parse the public key of a certificate given as a `SubjectPublicKeyInfo` element.
+fn parse_subject_public_key_info(element: &Element) -> Option<Metadata> {
+    let children = parse_children_with_tag(element, TAG_SEQUENCE)?;
+    let (key_type, mut key_size) = parse_algorithm_identifier(children.get(0)?)?;
+    let public_key = children.get(1).filter(|key| key.tag == TAG_BIT_STRING)?;
+    if key_type == "RSA" {
+        // The first byte of the bit string contains the number of unused bits.
+        key_size = public_key
+            .content
+            .get(1..)
+            .and_then(|key| parse_element(key))
+            .and_then(|(key, _)| parse_children_with_tag(&key, TAG_SEQUENCE))
+            .and_then(|key| key.get(0).and_then(get_integer_bit_length));
+    }
+    Some(Metadata {
+        key_type: Some(key_type),
+        key_size,
+        expiry: None,
+    })
+}
+
+/// Parse a DER-encoded X.509 certificate (version 3).
+/// Returns `None` if the bytes are not a certificate.
+pub fn parse_certificate(der: &[u8]) -> Option<Metadata> {
+    let (certificate, length) = parse_element(der)?;
+    if length != der.len() {
+        return None;
+    }
+    let children = parse_children_with_tag(&certificate, TAG_SEQUENCE)?;
+    if children.len() != 3 {
+        return None;
+    }
+    let tbs_certificate = parse_children_with_tag(&children[0], TAG_SEQUENCE)?;
+    // version, serial number, signature algorithm, issuer, validity, subject, public key, ...
+    if tbs_certificate.len() < 7 || tbs_certificate[0].tag != TAG_CONTEXT_0 {
+        return None;
+    }
+    let validity = parse_children_with_tag(&tbs_certificate[4], TAG_SEQUENCE)?;
+    let expiry = parse_time(validity.get(1)?)?;
+    let mut metadata = parse_subject_public_key_info(&tbs_certificate[6])?;
+    metadata.expiry = Some(expiry);
+    Some(metadata)
+}
+
+/// Decode Base64-encoded data, ignoring whitespace.
+/// Returns `None` if the data contains characters not belonging to the Base64 alphabet.
+pub fn decode_base64(data: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(data.len() * 3 / 4);
+    let mut buffer: u32 = 0;
+    let mut num_bits = 0;
+    for byte in data.iter() {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            b' ' | b'\t' | b'\r' | b'\n' => continue,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | value as u32;
+        num_bits += 6;
+        if num_bits >= 8 {
+            num_bits -= 8;
+            decoded.push((buffer >> num_bits) as u8);
+        }
+    }
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encode a DER element with the given tag and content.
+    fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut encoded = vec![tag];
+        if content.len() < 0x80 {
+            encoded.push(content.len() as u8);
+        } else {
+            encoded.extend_from_slice(&[0x82, (content.len() >> 8) as u8, content.len() as u8]);
+        }
+        encoded.extend_from_slice(content);
+        encoded
+    }
+
+    fn sequence(children: &[Vec<u8>]) -> Vec<u8> {
+        der(TAG_SEQUENCE, &children.concat())
+    }
+
+    #[test]
+    fn base64() {
+        assert_eq!(decode_base64(b"TWFu"), Some(b"Man".to_vec()));
+        assert_eq!(decode_base64(b"TW\nE="), Some(b"Ma".to_vec()));
+        assert_eq!(decode_base64(b"TWE-"), None);
+    }
+
+    #[test]
+    fn rsa_private_key() {
+        let mut modulus = vec![0x00, 0x80];
+        modulus.extend_from_slice(&[0xab; 127]);
+        let mut children = vec![der(TAG_INTEGER, &[0]), der(TAG_INTEGER, &modulus)];
+        for _ in 0..7 {
+            children.push(der(TAG_INTEGER, &[0x03]));
+        }
+        let key = sequence(&children);
+        assert_eq!(get_sequence_length(&key), Some(key.len()));
+        let metadata = parse_private_key(&key).unwrap();
+        assert_eq!(metadata.key_type.as_deref(), Some("RSA"));
+        assert_eq!(metadata.key_size, Some(1024));
+        assert_eq!(format!("{}", metadata), "RSA 1024 bit");
+
+        let pkcs8_key = sequence(&[
+            der(TAG_INTEGER, &[0]),
+            sequence(&[der(TAG_OID, OID_RSA), der(0x05, &[])]),
+            der(TAG_OCTET_STRING, &key),
+        ]);
+        assert_eq!(parse_private_key(&pkcs8_key), Some(metadata));
+        assert_eq!(parse_private_key(&key[..key.len() - 1]), None);
+        assert_eq!(parse_certificate(&key), None);
+    }
+
+    #[test]
+    fn ec_private_key() {
+        let key = sequence(&[
+            der(TAG_INTEGER, &[1]),
+            der(TAG_OCTET_STRING, &[0x42; 32]),
+            der(TAG_CONTEXT_0, &der(TAG_OID, EC_CURVES[2].0)),
+        ]);
+        let metadata = parse_private_key(&key).unwrap();
+        assert_eq!(metadata.key_type.as_deref(), Some("EC"));
+        assert_eq!(metadata.key_size, Some(384));
+    }
+
+    #[test]
+    fn certificate() {
+        let public_key = sequence(&[
+            sequence(&[der(TAG_OID, OID_EC), der(TAG_OID, EC_CURVES[0].0)]),
+            der(TAG_BIT_STRING, &[0x00, 0x04, 0x01, 0x02]),
+        ]);
+        let tbs_certificate = sequence(&[
+            der(TAG_CONTEXT_0, &der(TAG_INTEGER, &[2])),
+            der(TAG_INTEGER, &[0x12, 0x34]),
+            sequence(&[der(
+                TAG_OID,
+                &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02],
+            )]),
+            sequence(&[]),
+            sequence(&[
+                der(TAG_UTC_TIME, b"200101000000Z"),
+                der(TAG_GENERALIZED_TIME, b"20301231235959Z"),
+            ]),
+            sequence(&[]),
+            public_key,
+        ]);
+        let certificate = sequence(&[
+            tbs_certificate,
+            sequence(&[der(
+                TAG_OID,
+                &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02],
+            )]),
+            der(TAG_BIT_STRING, &[0x00, 0x01]),
+        ]);
+        let metadata = parse_certificate(&certificate).unwrap();
+        assert_eq!(format!("{}", metadata), "EC 256 bit, expires 2030-12-31");
+        assert_eq!(parse_private_key(&certificate), None);
+    }
+}
@@ -21,14 +21,20 @@
 //! - It is not checked whether the seeding function gets called before the random number generator function.
 
 use crate::prelude::*;
-use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::log::{CweWarning, LogMessage, Remediation, Severity};
 use crate::utils::symbol_utils::find_symbol;
 use crate::CweModule;
 
 pub static CWE_MODULE: CweModule = CweModule {
     name: "CWE332",
     version: "0.1",
+    cwes: &[332],
+    description:
+        "Insufficient entropy in pseudo-random number generators, e.g. rand() without seeding.",
+    required_analyses: &[],
+    config_keys: &["pairs"],
     run: check_cwe,
+    validate_config: Some(crate::utils::config_validation::deserialize_section::<Config>),
 };
 
 /// The configuration struct contains pairs of symbol names,
@@ -51,13 +57,21 @@ fn generate_cwe_warning(secure_initializer_func: &str, rand_func: &str) -> CweWa
             rand_func, secure_initializer_func
         ),
     )
+    .severity(Severity::Low)
+    .remediation(Remediation::new(
+        332,
+        format!(
+            "Seed the random number generator with {} before calling {} or use a cryptographically secure source of randomness.",
+            secure_initializer_func, rand_func
+        ),
+    ))
 }
 
 /// Run the CWE check. See the module-level description for more information.
 pub fn check_cwe(
     analysis_results: &AnalysisResults,
     cwe_params: &serde_json::Value,
-) -> (Vec<LogMessage>, Vec<CweWarning>) {
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
     let project = analysis_results.project;
     let config: Config = serde_json::from_value(cwe_params.clone()).unwrap();
     let mut cwe_warnings = Vec::new();
@@ -69,5 +83,5 @@ pub fn check_cwe(
             cwe_warnings.push(generate_cwe_warning(secure_initializer_func, rand_func));
         }
     }
-    (Vec::new(), cwe_warnings)
+    (Vec::new(), cwe_warnings, false)
 }
@@ -26,7 +26,7 @@ use crate::analysis::graph::{Edge, Node};
 use crate::intermediate_representation::Jmp;
 use crate::prelude::*;
 use crate::utils::graph_utils::is_sink_call_reachable_from_source_call;
-use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::log::{CweWarning, LogMessage, Remediation, Severity};
 use crate::CweModule;
 use petgraph::visit::EdgeRef;
 use std::collections::HashMap;
@@ -34,14 +34,19 @@ use std::collections::HashMap;
 pub static CWE_MODULE: CweModule = CweModule {
     name: "CWE367",
     version: "0.1",
+    cwes: &[367],
+    description: "Time-of-check time-of-use race conditions between pairs of functions.",
+    required_analyses: &[],
+    config_keys: &["pairs"],
     run: check_cwe,
+    validate_config: Some(crate::utils::config_validation::deserialize_section::<Config>),
 };
 
 /// The configuration struct contains pairs of the form `(source_symbol, sink_symbol)`.
 /// The `source_symbol` corresponds to a check-call and the `sink_symbol` corresponds to a use-call.
 /// An execution path from a source call to a sink call corresponds to a possible Time-of-check Time-of-use Race Condition.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
-struct Config {
+pub struct Config {
     pairs: Vec<(String, String)>,
 }
 
@@ -60,16 +65,24 @@ fn generate_cwe_warning(
             "(Time-of-check Time-of-use Race Condition) '{}' is reachable from '{}' at {} ({}). This could lead to a TOCTOU.",
             sink, source, sink_callsite.address, sub_name
         ))
+        .severity(Severity::Medium)
         .tids(vec![format!("{}", source_callsite), format!("{}", sink_callsite)])
         .addresses(vec![source_callsite.address, sink_callsite.address])
         .symbols(vec![source.into(), sink.into()])
+        .remediation(Remediation::new(
+            367,
+            format!(
+                "Do not check the file with {} before using it with {}. Open the file first and perform the checks on the opened file descriptor instead, e.g. with fstat.",
+                source, sink
+            ),
+        ))
 }
 
 /// Run the check. See the module-level documentation for more information.
 pub fn check_cwe(
     analysis_results: &AnalysisResults,
     cwe_params: &serde_json::Value,
-) -> (Vec<LogMessage>, Vec<CweWarning>) {
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
     let config: Config = serde_json::from_value(cwe_params.clone()).unwrap();
     let project = analysis_results.project;
     let graph = analysis_results.control_flow_graph;
@@ -120,5 +133,5 @@ pub fn check_cwe(
         }
     }
 
-    (Vec::new(), cwe_warnings)
+    (Vec::new(), cwe_warnings, false)
 }
@@ -33,7 +33,7 @@
 
 use crate::intermediate_representation::*;
 use crate::prelude::*;
-use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::log::{CweWarning, LogMessage, Remediation, Severity};
 use crate::utils::symbol_utils::{find_symbol, get_calls_to_symbols};
 use crate::CweModule;
 use std::collections::HashMap;
@@ -41,7 +41,12 @@ use std::collections::HashMap;
 pub static CWE_MODULE: CweModule = CweModule {
     name: "CWE426",
     version: "0.1",
+    cwes: &[426],
+    description: "Untrusted search path when changing privileges.",
+    required_analyses: &[],
+    config_keys: &["symbols"],
     run: check_cwe,
+    validate_config: Some(crate::utils::config_validation::deserialize_section::<Config>),
 };
 
 /// Function symbols read from *config.json*.
@@ -61,9 +66,17 @@ fn generate_cwe_warning(sub: &Term<Sub>) -> CweWarning {
             sub.term.name, sub.tid.address
         ),
     )
+    .severity(Severity::Medium)
     .tids(vec![format!("{}", sub.tid)])
     .addresses(vec![sub.tid.address.clone()])
     .symbols(vec![sub.term.name.clone()])
+    .remediation(Remediation::new(
+        426,
+        format!(
+            "Do not call system() in {} after changing privileges. Use the exec family of functions with absolute paths and a sanitized environment instead.",
+            sub.term.name
+        ),
+    ))
 }
 
 /// Run the CWE check.
@@ -72,7 +85,7 @@ fn generate_cwe_warning(sub: &Term<Sub>) -> CweWarning {
 pub fn check_cwe(
     analysis_results: &AnalysisResults,
     cwe_params: &serde_json::Value,
-) -> (Vec<LogMessage>, Vec<CweWarning>) {
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
     let project = analysis_results.project;
     let config: Config = serde_json::from_value(cwe_params.clone()).unwrap();
     let mut cwe_warnings = Vec::new();
@@ -95,5 +108,5 @@ pub fn check_cwe(
             }
         }
     }
-    (Vec::new(), cwe_warnings)
+    (Vec::new(), cwe_warnings, false)
 }
@@ -25,14 +25,21 @@ use crate::analysis::pointer_inference::State;
 use crate::intermediate_representation::*;
 use crate::prelude::*;
 use crate::utils::binary::RuntimeMemoryImage;
-use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::log::{
+    CweWarning, ExploitabilityHint, Impact, LogMessage, Remediation, Severity,
+};
 use crate::utils::symbol_utils::{get_callsites, get_symbol_map};
 use crate::CweModule;
 
 pub static CWE_MODULE: CweModule = CweModule {
     name: "CWE467",
     version: "0.2",
+    cwes: &[467],
+    description: "Use of sizeof() on a pointer type for the size argument of functions.",
+    required_analyses: &[],
+    config_keys: &["symbols"],
     run: check_cwe,
+    validate_config: Some(crate::utils::config_validation::deserialize_section::<Config>),
 };
 
 /// Function symbols read from *config.json*.
@@ -99,8 +106,17 @@ fn generate_cwe_warning(jmp: &Term<Jmp>, extern_symbol: &ExternSymbol) -> CweWar
             jmp.tid.address, extern_symbol.name
         ),
     )
+    .severity(Severity::Low)
     .tids(vec![format!("{}", jmp.tid)])
     .addresses(vec![jmp.tid.address.clone()])
+    .remediation(Remediation::new(
+        467,
+        format!(
+            "Pass the size of the pointed-to data (e.g. sizeof(*ptr)) instead of the size of the pointer to {}.",
+            extern_symbol.name
+        ),
+    ))
+    .exploitability(ExploitabilityHint::new(Impact::MemoryWrite))
 }
 
 /// Execute the CWE check.
@@ -111,7 +127,7 @@ fn generate_cwe_warning(jmp: &Term<Jmp>, extern_symbol: &ExternSymbol) -> CweWar
 pub fn check_cwe(
     analysis_results: &AnalysisResults,
     cwe_params: &serde_json::Value,
-) -> (Vec<LogMessage>, Vec<CweWarning>) {
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
     let project = analysis_results.project;
     let config: Config = serde_json::from_value(cwe_params.clone()).unwrap();
     let mut cwe_warnings = Vec::new();
@@ -129,5 +145,5 @@ pub fn check_cwe(
             }
         }
     }
-    (Vec::new(), cwe_warnings)
+    (Vec::new(), cwe_warnings, false)
 }
@@ -35,6 +35,8 @@
 //! for the return value being NULL or something else
 //! - For functions with more than one return value we do not distinguish between
 //! the return values.
+//! - If the module timeout is exceeded, the remaining taint sources are not analyzed completely.
+//! - If the resource budget is exceeded, the analysis of taint sources is stopped early or skipped.
 
 use crate::analysis::forward_interprocedural_fixpoint::create_computation;
 use crate::analysis::forward_interprocedural_fixpoint::Context as _;
@@ -43,7 +45,8 @@ use crate::analysis::interprocedural_fixpoint_generic::NodeValue;
 use crate::intermediate_representation::*;
 use crate::prelude::*;
 use crate::utils::log::{CweWarning, LogMessage};
-use crate::CweModule;
+use crate::utils::resource_budget::BudgetTracker;
+use crate::{CweModule, RequiredAnalysis};
 use petgraph::visit::EdgeRef;
 use std::collections::HashMap;
 
@@ -59,7 +62,12 @@ use context::*;
 pub static CWE_MODULE: CweModule = CweModule {
     name: "CWE476",
     version: "0.3",
+    cwes: &[476],
+    description: "NULL pointer dereferences of unchecked return values of functions.",
+    required_analyses: &[RequiredAnalysis::PointerInference],
+    config_keys: &["symbols"],
     run: check_cwe,
+    validate_config: Some(crate::utils::config_validation::deserialize_section::<Config>),
 };
 
 /// The configuration struct
@@ -76,7 +84,7 @@ pub struct Config {
 pub fn check_cwe(
     analysis_results: &AnalysisResults,
     cwe_params: &serde_json::Value,
-) -> (Vec<LogMessage>, Vec<CweWarning>) {
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
     let project = analysis_results.project;
     let pointer_inference_results = analysis_results.pointer_inference.unwrap();
 
@@ -91,6 +99,11 @@ pub fn check_cwe(
         cwe_sender,
     );
 
+    let mut cwe_warnings = HashMap::new();
+    let mut budget_tracker = BudgetTracker::new(
+        analysis_results.budget,
+        analysis_results.cancellation.clone(),
+    );
     for edge in general_context.get_graph().edge_references() {
         if let Edge::ExternCallStub(jmp) = edge.weight() {
             if let Jmp::Call { target, .. } = &jmp.term {
@@ -116,20 +129,29 @@ pub fn check_cwe(
                             pi_state_at_taint_source.as_ref(),
                         )),
                     );
-                    computation.compute_with_max_steps(100);
+                    budget_tracker.compute(&mut computation, 100, &current_sub.tid);
+                    // Report new warnings right away, since the whole check may run for a long time.
+                    for cwe in cwe_receiver.try_iter() {
+                        match &cwe.addresses[..] {
+                            [taint_source_address, ..] => {
+                                if !cwe_warnings.contains_key(taint_source_address) {
+                                    analysis_results.report_warning(&cwe);
+                                }
+                                cwe_warnings.insert(taint_source_address.clone(), cwe);
+                            }
+                            _ => panic!(),
+                        }
+                    }
                 }
             }
         }
     }
 
-    let mut cwe_warnings = HashMap::new();
-    for cwe in cwe_receiver.try_iter() {
-        match &cwe.addresses[..] {
-            [taint_source_address, ..] => cwe_warnings.insert(taint_source_address.clone(), cwe),
-            _ => panic!(),
-        };
-    }
     let cwe_warnings = cwe_warnings.into_iter().map(|(_, cwe)| cwe).collect();
 
-    (Vec::new(), cwe_warnings)
+    (
+        budget_tracker.get_logs(CWE_MODULE.name),
+        cwe_warnings,
+        budget_tracker.was_interrupted(),
+    )
 }
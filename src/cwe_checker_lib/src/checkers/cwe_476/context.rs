@@ -9,7 +9,7 @@ use crate::analysis::pointer_inference::PointerInference as PointerInferenceComp
 use crate::analysis::pointer_inference::State as PointerInferenceState;
 use crate::intermediate_representation::*;
 use crate::utils::binary::RuntimeMemoryImage;
-use crate::utils::log::CweWarning;
+use crate::utils::log::{CweWarning, ExploitabilityHint, Impact, Remediation, Severity};
 use petgraph::graph::NodeIndex;
 use petgraph::visit::IntoNodeReferences;
 use std::collections::HashMap;
@@ -166,9 +166,15 @@ impl<'a> Context<'a> {
         let cwe_warning = CweWarning::new(CWE_MODULE.name, CWE_MODULE.version,
             format!("(NULL Pointer Dereference) There is no check if the return value is NULL at {} ({}).",
             taint_source.tid.address, taint_source_name))
+            .severity(Severity::Medium)
             .addresses(vec![taint_source.tid.address.clone(), taint_access_location.address.clone()])
             .tids(vec![format!("{}", taint_source.tid), format!("{}", taint_access_location)])
-            .symbols(vec![taint_source_name]);
+            .symbols(vec![taint_source_name.clone()])
+            .remediation(Remediation::new(476, format!(
+                "Check the return value of {} for NULL before it is used to access memory.",
+                taint_source_name
+            )))
+            .exploitability(ExploitabilityHint::new(Impact::DenialOfService));
         let _ = self.cwe_collector.send(cwe_warning);
     }
 
@@ -115,13 +115,11 @@ impl State {
     pub fn eval(&self, expression: &Expression) -> Taint {
         match expression {
             Expression::Const(_) => Taint::Top(expression.bytesize()),
-            Expression::Var(var) => {
-                if self.register_taint.get(var).is_some() {
-                    Taint::Tainted(var.size)
-                } else {
-                    Taint::Top(var.size)
-                }
-            }
+            Expression::Var(var) => self
+                .register_taint
+                .get(var)
+                .copied()
+                .unwrap_or(Taint::Top(var.size)),
             Expression::BinOp { op, lhs, rhs } => {
                 let lhs_taint = self.eval(lhs);
                 let rhs_taint = self.eval(rhs);
@@ -494,4 +492,42 @@ mod tests {
         };
         assert!(state.eval(&expr).is_top());
     }
+
+    #[test]
+    fn eval_sub_register_expression() {
+        let (mut state, _pi_state) = State::mock_with_pi_state();
+        // Overwrite the lowest byte of RBX with the lowest byte of RAX.
+        let expr = Expression::BinOp {
+            op: BinOpType::Piece,
+            lhs: Box::new(Expression::Subpiece {
+                low_byte: ByteSize::new(1),
+                size: ByteSize::new(7),
+                arg: Box::new(Expression::Var(register("RBX"))),
+            }),
+            rhs: Box::new(Expression::Subpiece {
+                low_byte: ByteSize::new(0),
+                size: ByteSize::new(1),
+                arg: Box::new(Expression::Var(register("RAX"))),
+            }),
+        };
+        let taint = state.eval(&expr);
+        assert_eq!(
+            taint,
+            Taint::PartiallyTainted(ByteSize::new(8), 0b0000_0001)
+        );
+        state.set_register_taint(&register("RBX"), taint);
+
+        let low_byte = Expression::Subpiece {
+            low_byte: ByteSize::new(0),
+            size: ByteSize::new(1),
+            arg: Box::new(Expression::Var(register("RBX"))),
+        };
+        assert!(state.eval(&low_byte).is_tainted());
+        let high_byte = Expression::Subpiece {
+            low_byte: ByteSize::new(1),
+            size: ByteSize::new(1),
+            arg: Box::new(Expression::Var(register("RBX"))),
+        };
+        assert!(state.eval(&high_byte).is_top());
+    }
 }
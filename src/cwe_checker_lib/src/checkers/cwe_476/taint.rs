@@ -3,8 +3,16 @@ use crate::intermediate_representation::*;
 use crate::prelude::*;
 use std::fmt::Display;
 
+/// The maximal bytesize of values for which the taint is tracked separately for each byte.
+/// For larger values only the taint of the value as a whole is tracked.
+const MAX_BYTE_GRANULAR_SIZE: u64 = 64;
+
 /// An abstract domain representing a value that is either tainted or not.
 ///
+/// The taint of values up to a size of 64 bytes is tracked with byte granularity,
+/// so that extracting or combining sub-registers through `Subpiece` and `Piece` operations
+/// does neither lose the taint nor taint the untainted parts of a value.
+///
 /// Note that the [merge](Taint::merge)-function does not respect the partial order
 /// that is implied by the naming scheme of the variants!
 /// In fact the whole analysis does not enforce any partial order for this domain.
@@ -15,6 +23,9 @@ use std::fmt::Display;
 pub enum Taint {
     /// A tainted value of a particular bytesize.
     Tainted(ByteSize),
+    /// A value of a particular bytesize where only some bytes are tainted.
+    /// The i-th bit of the mask is set if the i-th byte (counting from the least significant byte) is tainted.
+    PartiallyTainted(ByteSize, u64),
     /// An untainted value of a particular bytesize
     Top(ByteSize),
 }
@@ -24,6 +35,9 @@ impl Display for Taint {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Tainted(size) => write!(f, "Tainted:{}", size),
+            Self::PartiallyTainted(size, mask) => {
+                write!(f, "PartiallyTainted:{}:{:#x}", size, mask)
+            }
             Self::Top(size) => write!(f, "Top:{}", size),
         }
     }
@@ -31,11 +45,16 @@ impl Display for Taint {
 
 impl AbstractDomain for Taint {
     /// The result of merging two `Taint` values is tainted if at least one input was tainted.
+    /// For partially tainted values the tainted bytes of both inputs are tainted in the result.
     fn merge(&self, other: &Self) -> Self {
         use Taint::*;
         match (self, other) {
             (Tainted(size), _) | (_, Tainted(size)) => Tainted(*size),
-            _ => Top(self.bytesize()),
+            (Top(size), Top(_)) => Top(*size),
+            _ => Taint::from_byte_mask(
+                self.bytesize(),
+                self.get_byte_mask() | other.get_byte_mask(),
+            ),
         }
     }
 
@@ -49,7 +68,7 @@ impl SizedDomain for Taint {
     /// The size in bytes of the `Taint` value.
     fn bytesize(&self) -> ByteSize {
         match self {
-            Self::Tainted(size) | Self::Top(size) => *size,
+            Self::Tainted(size) | Self::PartiallyTainted(size, _) | Self::Top(size) => *size,
         }
     }
 
@@ -68,35 +87,70 @@ impl HasTop for Taint {
 
 impl RegisterDomain for Taint {
     /// The result of a binary operation is tainted if at least one input value was tainted.
+    ///
+    /// `Piece` operations and bitwise operations do not mix the bytes of their inputs,
+    /// so for them the taint is tracked for each byte of the result separately.
     fn bin_op(&self, op: BinOpType, rhs: &Self) -> Self {
-        match (self, rhs) {
-            (Self::Tainted(_), _) | (_, Self::Tainted(_)) => {
-                Self::Tainted(self.bin_op_bytesize(op, rhs))
+        use BinOpType::*;
+        let size = self.bin_op_bytesize(op, rhs);
+        match op {
+            Piece if u64::from(size) <= MAX_BYTE_GRANULAR_SIZE => Taint::from_byte_mask(
+                size,
+                (self.get_byte_mask() << u64::from(rhs.bytesize())) | rhs.get_byte_mask(),
+            ),
+            IntAnd | IntOr | IntXOr => {
+                Taint::from_byte_mask(size, self.get_byte_mask() | rhs.get_byte_mask())
+            }
+            _ => {
+                if self.is_tainted() || rhs.is_tainted() {
+                    Self::Tainted(size)
+                } else {
+                    Self::Top(size)
+                }
             }
-            _ => Self::Top(self.bin_op_bytesize(op, rhs)),
         }
     }
 
     /// The result of a unary operation is tainted if the input was tainted.
-    fn un_op(&self, _op: UnOpType) -> Self {
-        *self
+    /// Only for bitwise negation the taint is tracked for each byte separately.
+    fn un_op(&self, op: UnOpType) -> Self {
+        match op {
+            UnOpType::IntNegate => *self,
+            _ => self.to_whole_value_taint(self.bytesize()),
+        }
     }
 
     /// A subpiece of a tainted value is again tainted.
-    fn subpiece(&self, _low_byte: ByteSize, size: ByteSize) -> Self {
-        if let Self::Tainted(_) = self {
-            Self::Tainted(size)
-        } else {
-            Self::Top(size)
+    /// For partially tainted values only the tainted bytes contained in the subpiece remain tainted.
+    fn subpiece(&self, low_byte: ByteSize, size: ByteSize) -> Self {
+        match self {
+            Self::Tainted(_) => Self::Tainted(size),
+            Self::PartiallyTainted(_, mask) => Taint::from_byte_mask(
+                size,
+                mask.checked_shr(u64::from(low_byte) as u32).unwrap_or(0),
+            ),
+            Self::Top(_) => Self::Top(size),
         }
     }
 
     /// The result of a cast operation is tainted if the input was tainted.
-    fn cast(&self, _kind: CastOpType, width: ByteSize) -> Self {
-        if let Self::Tainted(_) = self {
-            Self::Tainted(width)
-        } else {
-            Self::Top(width)
+    /// For zero- and sign-extensions the taint of the extended bytes is tracked separately.
+    fn cast(&self, kind: CastOpType, width: ByteSize) -> Self {
+        let mask = self.get_byte_mask();
+        match kind {
+            CastOpType::IntZExt if u64::from(self.bytesize()) <= MAX_BYTE_GRANULAR_SIZE => {
+                Taint::from_byte_mask(width, mask)
+            }
+            CastOpType::IntSExt if u64::from(self.bytesize()) <= MAX_BYTE_GRANULAR_SIZE => {
+                // The extended bytes are copies of the sign bit contained in the most significant byte.
+                let highest_byte = 1u64 << (u64::from(self.bytesize()) - 1);
+                if mask & highest_byte != 0 {
+                    Taint::from_byte_mask(width, mask | !get_full_mask(self.bytesize()))
+                } else {
+                    Taint::from_byte_mask(width, mask)
+                }
+            }
+            _ => self.to_whole_value_taint(width),
         }
     }
 }
@@ -104,7 +158,80 @@ impl RegisterDomain for Taint {
 impl Taint {
     /// Checks whether the given value is in fact tainted.
     pub fn is_tainted(&self) -> bool {
-        matches!(self, Taint::Tainted(_))
+        matches!(self, Taint::Tainted(_) | Taint::PartiallyTainted(..))
+    }
+
+    /// Create a new `Taint` value of the given size where exactly the bytes marked in the given mask are tainted.
+    /// The i-th bit of the mask corresponds to the i-th byte of the value, counting from the least significant byte.
+    ///
+    /// Values larger than 64 bytes are marked as tainted as a whole if at least one byte is tainted.
+    pub fn from_byte_mask(size: ByteSize, mask: u64) -> Taint {
+        let full_mask = get_full_mask(size);
+        let mask = mask & full_mask;
+        if mask == 0 {
+            Taint::Top(size)
+        } else if mask == full_mask || u64::from(size) > MAX_BYTE_GRANULAR_SIZE {
+            Taint::Tainted(size)
+        } else {
+            Taint::PartiallyTainted(size, mask)
+        }
+    }
+
+    /// Get the mask of tainted bytes of the value.
+    /// The i-th bit of the mask is set if the i-th byte (counting from the least significant byte) is tainted.
+    pub fn get_byte_mask(&self) -> u64 {
+        match self {
+            Taint::Tainted(size) => get_full_mask(*size),
+            Taint::PartiallyTainted(_, mask) => *mask,
+            Taint::Top(_) => 0,
+        }
+    }
+
+    /// Get the taint of the input of a `Subpiece` operation with the given low byte and input size
+    /// that corresponds to `self` being the taint of the output of the operation.
+    ///
+    /// This is used to propagate taint backwards through `Subpiece` operations.
+    pub fn get_subpiece_input_taint(&self, low_byte: ByteSize, input_size: ByteSize) -> Taint {
+        if u64::from(input_size) > MAX_BYTE_GRANULAR_SIZE {
+            return self.to_whole_value_taint(input_size);
+        }
+        Taint::from_byte_mask(input_size, self.get_byte_mask() << u64::from(low_byte))
+    }
+
+    /// Get the taints of the two inputs of a `Piece` operation with the given input sizes
+    /// that correspond to `self` being the taint of the output of the operation.
+    ///
+    /// This is used to propagate taint backwards through `Piece` operations.
+    pub fn get_piece_input_taints(&self, lhs_size: ByteSize, rhs_size: ByteSize) -> (Taint, Taint) {
+        if u64::from(self.bytesize()) > MAX_BYTE_GRANULAR_SIZE {
+            return (
+                self.to_whole_value_taint(lhs_size),
+                self.to_whole_value_taint(rhs_size),
+            );
+        }
+        let mask = self.get_byte_mask();
+        (
+            Taint::from_byte_mask(lhs_size, mask >> u64::from(rhs_size)),
+            Taint::from_byte_mask(rhs_size, mask),
+        )
+    }
+
+    /// Return a taint value of the given size that is tainted as a whole if any byte of `self` is tainted.
+    fn to_whole_value_taint(&self, size: ByteSize) -> Taint {
+        if self.is_tainted() {
+            Taint::Tainted(size)
+        } else {
+            Taint::Top(size)
+        }
+    }
+}
+
+/// Get a byte mask where all bytes of a value of the given size are marked.
+fn get_full_mask(size: ByteSize) -> u64 {
+    if u64::from(size) >= 64 {
+        u64::MAX
+    } else {
+        (1u64 << u64::from(size)) - 1
     }
 }
 
@@ -132,4 +259,57 @@ mod tests {
         assert_eq!(taint.subpiece(ByteSize::new(0), ByteSize::new(4)), taint);
         assert_eq!(top.cast(CastOpType::IntZExt, ByteSize::new(4)), top);
     }
+
+    #[test]
+    fn byte_granular_taint() {
+        use crate::intermediate_representation::*;
+        let taint = Taint::Tainted(ByteSize::new(1));
+        let top = Taint::Top(ByteSize::new(3));
+        let word = top.bin_op(BinOpType::Piece, &taint);
+        assert_eq!(word, Taint::PartiallyTainted(ByteSize::new(4), 0b0001));
+        assert_eq!(
+            word.subpiece(ByteSize::new(0), ByteSize::new(1)),
+            Taint::Tainted(ByteSize::new(1))
+        );
+        assert_eq!(
+            word.subpiece(ByteSize::new(1), ByteSize::new(2)),
+            Taint::Top(ByteSize::new(2))
+        );
+        assert_eq!(
+            word.bin_op(BinOpType::IntAnd, &Taint::Top(ByteSize::new(4))),
+            word
+        );
+        assert_eq!(
+            word.bin_op(BinOpType::IntAdd, &Taint::Top(ByteSize::new(4))),
+            Taint::Tainted(ByteSize::new(4))
+        );
+        assert_eq!(
+            word.cast(CastOpType::IntZExt, ByteSize::new(8)),
+            Taint::PartiallyTainted(ByteSize::new(8), 0b0001)
+        );
+        assert_eq!(
+            taint.cast(CastOpType::IntSExt, ByteSize::new(4)),
+            Taint::Tainted(ByteSize::new(4))
+        );
+        assert_eq!(
+            word.merge(&Taint::PartiallyTainted(ByteSize::new(4), 0b1110)),
+            Taint::Tainted(ByteSize::new(4))
+        );
+        assert!(word.is_tainted());
+        assert_eq!(
+            taint.get_subpiece_input_taint(ByteSize::new(1), ByteSize::new(4)),
+            Taint::PartiallyTainted(ByteSize::new(4), 0b0010)
+        );
+        assert_eq!(
+            word.get_piece_input_taints(ByteSize::new(3), ByteSize::new(1)),
+            (
+                Taint::Top(ByteSize::new(3)),
+                Taint::Tainted(ByteSize::new(1))
+            )
+        );
+        assert_eq!(
+            Taint::from_byte_mask(ByteSize::new(128), 1),
+            Taint::Tainted(ByteSize::new(128))
+        );
+    }
 }
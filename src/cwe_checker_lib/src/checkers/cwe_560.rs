@@ -27,14 +27,19 @@ use crate::analysis::pointer_inference::State;
 use crate::intermediate_representation::*;
 use crate::prelude::*;
 use crate::utils::binary::RuntimeMemoryImage;
-use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::log::{CweWarning, LogMessage, Remediation, Severity, Verdict};
 use crate::utils::symbol_utils::{get_callsites, get_symbol_map};
 use crate::CweModule;
 
 pub static CWE_MODULE: CweModule = CweModule {
     name: "CWE560",
     version: "0.2",
+    cwes: &[560],
+    description: "Use of umask() with chmod-style arguments.",
+    required_analyses: &[],
+    config_keys: &[],
     run: check_cwe,
+    validate_config: None,
 };
 
 pub static UPPER_BOUND_CORRECT_UMASK_ARG_VALUE: u64 = 100;
@@ -85,12 +90,18 @@ fn is_chmod_style_arg(arg: u64) -> bool {
 fn generate_cwe_warning(sub: &Term<Sub>, jmp: &Term<Jmp>, permission_const: u64) -> CweWarning {
     CweWarning::new(CWE_MODULE.name, CWE_MODULE.version,
         format!("(Use of umask() with chmod-style Argument) Function {} calls umask with argument {:#o}", sub.term.name, permission_const))
+        .severity(Severity::Low)
+        .verdict(Verdict::Proven)
         .tids(vec![format!("{}", jmp.tid)])
         .addresses(vec![jmp.tid.address.clone()])
         .other(vec![vec![
             "umask_arg".to_string(),
             format!("{:#o}", permission_const),
         ]])
+        .remediation(Remediation::new(
+            560,
+            "Pass the permission bits that should be removed (e.g. 0o022) to umask instead of the permissions that new files should have.",
+        ))
 }
 
 /// Execute the CWE check.
@@ -103,7 +114,7 @@ fn generate_cwe_warning(sub: &Term<Sub>, jmp: &Term<Jmp>, permission_const: u64)
 pub fn check_cwe(
     analysis_results: &AnalysisResults,
     _cwe_params: &serde_json::Value,
-) -> (Vec<LogMessage>, Vec<CweWarning>) {
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
     let project = analysis_results.project;
     let mut cwes = Vec::new();
     let mut log_messages = Vec::new();
@@ -136,5 +147,5 @@ pub fn check_cwe(
         }
     }
 
-    (log_messages, cwes)
+    (log_messages, cwes, false)
 }
@@ -0,0 +1,729 @@
+//! This module implements a check for CWE-563: Assignment to Variable without Use.
+//!
+//! If a buffer containing sensitive data is scrubbed (e.g. with `memset`) before it goes out of scope
+//! but never read afterwards, the scrubbing is a dead store.
+//! Optimizing compilers may remove such dead stores (see CWE-14),
+//! which leaves the sensitive data in memory.
+//!
+//! See <https://cwe.mitre.org/data/definitions/563.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! Using the results of the [Pointer Inference analysis](crate::analysis::pointer_inference)
+//! we compute for each load and store instruction whether it accesses a known offset of the current stack frame.
+//! Calls to scrubbing functions (see `SCRUBBING_FUNCTIONS`) are modeled as stores to their destination buffer.
+//! Then a backward liveness analysis on the stack bytes of each function is computed.
+//! Calls to scrubbing functions that the compiler may remove (e.g. `memset`),
+//! that overwrite a local buffer (i.e. at negative stack offsets)
+//! and for which no overwritten byte is live after the call are reported.
+//!
+//! ## False Positives
+//!
+//! - Stack accesses through pointers that the Pointer Inference analysis could not track
+//! are not recognized as reads of the stack frame.
+//!
+//! ## False Negatives
+//!
+//! - Every call to a function that is not a scrubbing function is assumed to read the whole stack frame,
+//! since pointers to the stack frame may be passed to the callee.
+//! So scrubbing calls followed by another call are never reported.
+//! - Scrubbing calls whose destination or size is not exactly known are not checked.
+//! - Scrubbing of heap or global memory is not checked.
+//! - Blocks without a known Pointer Inference state are assumed to read the whole stack frame.
+
+use crate::abstract_domain::BitvectorDomain;
+use crate::analysis::fixpoint::{Computation, Context};
+use crate::analysis::graph::Node;
+use crate::analysis::interprocedural_fixpoint_generic::NodeValue;
+use crate::analysis::pointer_inference::{Data, State};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::binary::RuntimeMemoryImage;
+use crate::utils::log::{CweWarning, LogMessage, Remediation, Severity};
+use crate::{CweModule, RequiredAnalysis};
+use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
+use std::collections::{BTreeSet, HashMap};
+
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE563",
+    version: "0.2",
+    cwes: &[563],
+    description: "Scrubbing of local buffers whose values are never read afterwards.",
+    required_analyses: &[RequiredAnalysis::PointerInference],
+    config_keys: &[],
+    run: check_cwe,
+    validate_config: None,
+};
+
+/// An extern function that overwrites a memory buffer.
+struct ScrubbingFunction {
+    /// The name of the extern function.
+    name: &'static str,
+    /// The index of the parameter containing the pointer to the overwritten buffer.
+    buffer_parameter: usize,
+    /// The index of the parameter containing the number of overwritten bytes.
+    size_parameter: usize,
+    /// Whether a compiler may remove calls to the function if the buffer is not read afterwards.
+    removable: bool,
+}
+
+/// The extern functions that are modeled as stores to their destination buffer.
+static SCRUBBING_FUNCTIONS: [ScrubbingFunction; 4] = [
+    ScrubbingFunction {
+        name: "memset",
+        buffer_parameter: 0,
+        size_parameter: 2,
+        removable: true,
+    },
+    ScrubbingFunction {
+        name: "bzero",
+        buffer_parameter: 0,
+        size_parameter: 1,
+        removable: true,
+    },
+    ScrubbingFunction {
+        name: "explicit_bzero",
+        buffer_parameter: 0,
+        size_parameter: 1,
+        removable: false,
+    },
+    ScrubbingFunction {
+        name: "memset_s",
+        buffer_parameter: 0,
+        size_parameter: 3,
+        removable: false,
+    },
+];
+
+/// A memory access relevant for the liveness analysis.
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum StackAccess {
+    /// A store to the given offset of the current stack frame.
+    Store { offset: i64, size: ByteSize },
+    /// A call to a scrubbing function that the compiler may remove,
+    /// overwriting the given region of the current stack frame.
+    Scrub {
+        tid: Tid,
+        symbol: String,
+        offset: i64,
+        size: ByteSize,
+    },
+    /// A load from the given offset of the current stack frame.
+    Load { offset: i64, size: ByteSize },
+    /// A load from the current stack frame where the exact offset is unknown.
+    UnknownLoad,
+}
+
+/// Maps the TIDs of the extern symbols of scrubbing functions to the symbol and the scrubbing function.
+type ScrubbingSymbols<'a> = HashMap<Tid, (&'a ExternSymbol, &'static ScrubbingFunction)>;
+
+/// A dead call to a scrubbing function, given by the TID of the call,
+/// the name of the scrubbing function and the stack offset of the scrubbed buffer.
+type DeadScrub = (Tid, String, i64);
+
+/// The set of bytes of the current stack frame that may still be read.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+struct LiveStackBytes {
+    /// If set, every byte of the stack frame is assumed to be live.
+    all: bool,
+    /// The offsets of the live bytes.
+    bytes: BTreeSet<i64>,
+}
+
+impl LiveStackBytes {
+    /// Create a set where every byte of the stack frame is live.
+    fn new_all() -> LiveStackBytes {
+        LiveStackBytes {
+            all: true,
+            bytes: BTreeSet::new(),
+        }
+    }
+
+    /// Add all bytes of `other` to `self`.
+    fn merge(&mut self, other: &LiveStackBytes) {
+        self.all |= other.all;
+        self.bytes.extend(other.bytes.iter().copied());
+    }
+
+    /// Return whether at least one byte of the given memory region is live.
+    fn is_live(&self, offset: i64, size: ByteSize) -> bool {
+        self.all
+            || self
+                .bytes
+                .range(offset..offset + u64::from(size) as i64)
+                .next()
+                .is_some()
+    }
+
+    /// Mark the bytes of the given memory region as live.
+    fn add(&mut self, offset: i64, size: ByteSize) {
+        self.bytes.extend(offset..offset + u64::from(size) as i64);
+    }
+
+    /// Mark the bytes of the given memory region as overwritten, i.e. not live.
+    fn remove(&mut self, offset: i64, size: ByteSize) {
+        for byte in offset..offset + u64::from(size) as i64 {
+            self.bytes.remove(&byte);
+        }
+    }
+}
+
+/// The context of the backward liveness analysis of a single function.
+///
+/// The graph contains one node for each block of the function
+/// and an edge from each block to each of its predecessors,
+/// so that the fixpoint computation propagates the live bytes against the control flow.
+/// The value of a node are the live bytes at the start of the block.
+struct LivenessContext<'a> {
+    graph: DiGraph<&'a Term<Blk>, ()>,
+    block_accesses: &'a HashMap<Tid, Vec<StackAccess>>,
+}
+
+impl<'a> Context for LivenessContext<'a> {
+    type EdgeLabel = ();
+    type NodeLabel = &'a Term<Blk>;
+    type NodeValue = LiveStackBytes;
+
+    fn get_graph(&self) -> &DiGraph<&'a Term<Blk>, ()> {
+        &self.graph
+    }
+
+    /// The bytes that are live at the start of at least one successor block are live.
+    fn merge(&self, val1: &LiveStackBytes, val2: &LiveStackBytes) -> LiveStackBytes {
+        let mut merged = val1.clone();
+        merged.merge(val2);
+        merged
+    }
+
+    /// Compute the live bytes at the start of the predecessor block
+    /// from the live bytes at the start of the successor block.
+    fn update_edge(&self, value: &LiveStackBytes, edge: EdgeIndex) -> Option<LiveStackBytes> {
+        let (_, predecessor) = self.graph.edge_endpoints(edge).unwrap();
+        let block = self.graph[predecessor];
+        Some(compute_live_bytes_at_block_start(
+            self.block_accesses.get(&block.tid),
+            value.clone(),
+            None,
+        ))
+    }
+}
+
+/// Evaluate the address expression in the given state.
+/// Return `Ok(Some(offset))` if the address is an exactly known offset into the current stack frame,
+/// `Ok(None)` if the address does not point to the current stack frame
+/// and an error if the address may point to the current stack frame at an unknown offset.
+fn get_stack_offset(state: &State, address: &Expression) -> Result<Option<i64>, Error> {
+    match state.eval(address) {
+        Ok(Data::Pointer(pointer)) => {
+            if !pointer.targets().contains_key(&state.stack_id) {
+                return Ok(None);
+            }
+            if pointer.targets().len() == 1 {
+                if let Some(BitvectorDomain::Value(offset)) = pointer.targets().get(&state.stack_id)
+                {
+                    if let Ok(offset) = offset.try_to_i64() {
+                        return Ok(Some(offset));
+                    }
+                }
+            }
+            Err(anyhow!("Unknown stack offset"))
+        }
+        Ok(_) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Get the scrubbing function and the extern symbol for all extern symbols of the project that are scrubbing functions.
+fn get_scrubbing_symbols(project: &Project) -> ScrubbingSymbols {
+    let mut scrubbing_symbols = HashMap::new();
+    for symbol in project.program.term.extern_symbols.iter() {
+        if let Some(function) = SCRUBBING_FUNCTIONS
+            .iter()
+            .find(|function| function.name == symbol.name)
+        {
+            scrubbing_symbols.insert(symbol.tid.clone(), (symbol, function));
+        }
+    }
+    scrubbing_symbols
+}
+
+/// Compute the region of the current stack frame overwritten by a call to the given scrubbing function.
+/// Returns `None` if the destination is not an exactly known offset into the current stack frame
+/// or if the number of overwritten bytes is not exactly known.
+fn get_scrubbed_region(
+    project: &Project,
+    state: &State,
+    symbol: &ExternSymbol,
+    function: &ScrubbingFunction,
+    global_memory: &RuntimeMemoryImage,
+) -> Option<(i64, ByteSize)> {
+    let buffer =
+        state.eval_nth_parameter(project, symbol, function.buffer_parameter, global_memory);
+    let offset = match buffer {
+        Ok(Data::Pointer(pointer)) if pointer.targets().len() == 1 => {
+            match pointer.targets().get(&state.stack_id) {
+                Some(BitvectorDomain::Value(offset)) => offset.try_to_i64().ok()?,
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    match state.eval_nth_parameter(project, symbol, function.size_parameter, global_memory) {
+        Ok(Data::Value(BitvectorDomain::Value(size))) => match size.try_to_u64() {
+            Ok(size) if size > 0 => Some((offset, ByteSize::new(size))),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Compute the stack accesses of all `Def` instructions in the block
+/// and of a call to a scrubbing function at the end of the block,
+/// starting with the given Pointer Inference state at the start of the block.
+fn compute_stack_accesses(
+    project: &Project,
+    block: &Term<Blk>,
+    mut state: State,
+    scrubbing_symbols: &ScrubbingSymbols,
+    global_memory: &RuntimeMemoryImage,
+) -> Vec<StackAccess> {
+    let mut accesses = Vec::new();
+    for def in block.term.defs.iter() {
+        match &def.term {
+            Def::Store { address, value } => {
+                if let Ok(Some(offset)) = get_stack_offset(&state, address) {
+                    accesses.push(StackAccess::Store {
+                        offset,
+                        size: value.bytesize(),
+                    });
+                }
+                let _ = state.handle_store(address, value, global_memory);
+            }
+            Def::Load { var, address } => {
+                match get_stack_offset(&state, address) {
+                    Ok(Some(offset)) => accesses.push(StackAccess::Load {
+                        offset,
+                        size: var.size,
+                    }),
+                    Ok(None) => (),
+                    Err(_) => accesses.push(StackAccess::UnknownLoad),
+                }
+                let _ = state.handle_load(var, address, global_memory);
+            }
+            Def::Assign { var, value } => {
+                let _ = state.handle_register_assign(var, value);
+            }
+        }
+    }
+    for jmp in block.term.jmps.iter() {
+        if let Jmp::Call { target, .. } = &jmp.term {
+            if let Some((symbol, function)) = scrubbing_symbols.get(target) {
+                if let Some((offset, size)) =
+                    get_scrubbed_region(project, &state, symbol, function, global_memory)
+                {
+                    if function.removable {
+                        accesses.push(StackAccess::Scrub {
+                            tid: jmp.tid.clone(),
+                            symbol: symbol.name.clone(),
+                            offset,
+                            size,
+                        });
+                    } else {
+                        accesses.push(StackAccess::Store { offset, size });
+                    }
+                }
+            }
+        }
+    }
+    accesses
+}
+
+/// Get the blocks of the function that control flow may continue with after the given block
+/// and the live stack bytes at the end of the block that do not come from these blocks.
+///
+/// Calls to scrubbing functions return to the caller without reading the stack frame.
+fn get_block_successors(
+    block: &Term<Blk>,
+    scrubbing_symbols: &ScrubbingSymbols,
+) -> (Vec<Tid>, LiveStackBytes) {
+    if block.term.jmps.is_empty() {
+        // The control flow is unknown, so we have to assume that everything is read afterwards.
+        return (Vec::new(), LiveStackBytes::new_all());
+    }
+    let mut successors = Vec::new();
+    for jmp in block.term.jmps.iter() {
+        match &jmp.term {
+            Jmp::Branch(target) | Jmp::CBranch { target, .. } => successors.push(target.clone()),
+            // The stack frame gets discarded on return.
+            Jmp::Return(_) => (),
+            Jmp::Call {
+                target,
+                return_: Some(return_target),
+            } if scrubbing_symbols.contains_key(target) => successors.push(return_target.clone()),
+            // Callees may read from the stack frame through pointers given to them.
+            Jmp::BranchInd(_) | Jmp::Call { .. } | Jmp::CallInd { .. } | Jmp::CallOther { .. } => {
+                return (Vec::new(), LiveStackBytes::new_all())
+            }
+        }
+    }
+    (successors, LiveStackBytes::default())
+}
+
+/// Compute the live stack bytes at the start of the block given the live bytes at the end of the block.
+/// If `dead_scrubs` is provided, all calls to removable scrubbing functions
+/// overwriting local buffers that are not read afterwards are added to it.
+fn compute_live_bytes_at_block_start(
+    accesses: Option<&Vec<StackAccess>>,
+    mut live: LiveStackBytes,
+    mut dead_scrubs: Option<&mut Vec<DeadScrub>>,
+) -> LiveStackBytes {
+    let accesses = match accesses {
+        Some(accesses) => accesses,
+        None => return LiveStackBytes::new_all(),
+    };
+    for access in accesses.iter().rev() {
+        match access {
+            StackAccess::Store { offset, size } => live.remove(*offset, *size),
+            StackAccess::Scrub {
+                tid,
+                symbol,
+                offset,
+                size,
+            } => {
+                if *offset < 0 && !live.is_live(*offset, *size) {
+                    if let Some(dead_scrubs) = dead_scrubs.as_mut() {
+                        dead_scrubs.push((tid.clone(), symbol.clone(), *offset));
+                    }
+                }
+                live.remove(*offset, *size);
+            }
+            StackAccess::Load { offset, size } => live.add(*offset, *size),
+            StackAccess::UnknownLoad => live.all = true,
+        }
+    }
+    live
+}
+
+/// Compute the dead calls to removable scrubbing functions of a function
+/// given the stack accesses of its blocks.
+///
+/// Blocks without known stack accesses are treated as if they may read the whole stack frame.
+fn compute_dead_scrubs(
+    sub: &Term<Sub>,
+    block_accesses: &HashMap<Tid, Vec<StackAccess>>,
+    scrubbing_symbols: &ScrubbingSymbols,
+) -> Vec<DeadScrub> {
+    let mut graph = DiGraph::new();
+    let nodes: HashMap<Tid, NodeIndex> = sub
+        .term
+        .blocks
+        .iter()
+        .map(|block| (block.tid.clone(), graph.add_node(block)))
+        .collect();
+    let mut block_ends = Vec::new();
+    for block in sub.term.blocks.iter() {
+        let (successors, mut live_at_end) = get_block_successors(block, scrubbing_symbols);
+        let mut successor_nodes = Vec::new();
+        for successor in successors {
+            match nodes.get(&successor) {
+                Some(successor_node) => {
+                    graph.add_edge(*successor_node, nodes[&block.tid], ());
+                    successor_nodes.push(*successor_node);
+                }
+                // Jumps to other functions may read the stack frame.
+                None => live_at_end = LiveStackBytes::new_all(),
+            }
+        }
+        block_ends.push((block, successor_nodes, live_at_end));
+    }
+    let mut computation = Computation::new(
+        LivenessContext {
+            graph,
+            block_accesses,
+        },
+        None,
+    );
+    for (block, _, live_at_end) in block_ends.iter() {
+        let live_at_start = compute_live_bytes_at_block_start(
+            block_accesses.get(&block.tid),
+            live_at_end.clone(),
+            None,
+        );
+        computation.set_node_value(nodes[&block.tid], live_at_start);
+    }
+    // The liveness analysis is monotone on a finite lattice, so the computation always stabilizes.
+    computation.compute();
+
+    let mut dead_scrubs = Vec::new();
+    for (block, successor_nodes, mut live_at_end) in block_ends {
+        for successor_node in successor_nodes {
+            if let Some(live_at_successor_start) = computation.get_node_value(successor_node) {
+                live_at_end.merge(live_at_successor_start);
+            }
+        }
+        compute_live_bytes_at_block_start(
+            block_accesses.get(&block.tid),
+            live_at_end,
+            Some(&mut dead_scrubs),
+        );
+    }
+    dead_scrubs
+}
+
+/// Generate the CWE warning for a detected instance of the CWE.
+fn generate_cwe_warning(sub: &Term<Sub>, call_tid: &Tid, symbol: &str, offset: i64) -> CweWarning {
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Assignment to Variable without Use) The buffer at stack offset {} scrubbed by {} at {} in {} is never read afterwards, so the compiler may remove the scrubbing",
+            offset, symbol, call_tid.address, sub.term.name
+        ),
+    )
+    .severity(Severity::Low)
+    .tids(vec![format!("{}", call_tid)])
+    .addresses(vec![call_tid.address.clone()])
+    .symbols(vec![sub.term.name.clone(), symbol.to_string()])
+    .other(vec![vec!["stack_offset".to_string(), offset.to_string()]])
+    .remediation(
+        Remediation::new(
+            563,
+            format!(
+                "Scrub the buffer in {} with a function that cannot be optimized away by the compiler, e.g. explicit_bzero or memset_s.",
+                sub.term.name
+            ),
+        )
+        .reference("https://cwe.mitre.org/data/definitions/14.html"),
+    )
+}
+
+/// Run the CWE check.
+/// For each function we compute the calls to removable scrubbing functions
+/// whose scrubbed local buffer is never read afterwards
+/// and generate a CWE warning for each such call.
+/// The check stops early if the module cancellation token gets cancelled.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    _cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
+    let project = analysis_results.project;
+    let pointer_inference_results = analysis_results.pointer_inference.unwrap();
+    let scrubbing_symbols = get_scrubbing_symbols(project);
+    if scrubbing_symbols.is_empty() {
+        return (Vec::new(), Vec::new(), false);
+    }
+    let graph = pointer_inference_results.get_graph();
+    let mut block_accesses_per_sub: HashMap<Tid, HashMap<Tid, Vec<StackAccess>>> = HashMap::new();
+    for node in graph.node_indices() {
+        if let Node::BlkStart(block, sub) = graph[node] {
+            if let Some(NodeValue::Value(state)) = pointer_inference_results.get_node_value(node) {
+                let accesses = compute_stack_accesses(
+                    project,
+                    block,
+                    state.clone(),
+                    &scrubbing_symbols,
+                    analysis_results.runtime_memory_image,
+                );
+                block_accesses_per_sub
+                    .entry(sub.tid.clone())
+                    .or_insert_with(HashMap::new)
+                    .insert(block.tid.clone(), accesses);
+            }
+        }
+    }
+    let mut cwe_warnings = Vec::new();
+    for sub in project.program.term.subs.iter() {
+        if analysis_results.cancellation.is_cancelled() {
+            return (Vec::new(), cwe_warnings, true);
+        }
+        if let Some(block_accesses) = block_accesses_per_sub.get(&sub.tid) {
+            for (call_tid, symbol, offset) in
+                compute_dead_scrubs(sub, block_accesses, &scrubbing_symbols)
+            {
+                cwe_warnings.push(generate_cwe_warning(sub, &call_tid, &symbol, offset));
+            }
+        }
+    }
+    (Vec::new(), cwe_warnings, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_memset() -> ExternSymbol {
+        let mut memset = ExternSymbol::mock_with_name("memset");
+        memset.parameters = vec![
+            Arg::mock_register("RDI"),
+            Arg::mock_register("RSI"),
+            Arg::mock_register("RDX"),
+        ];
+        memset
+    }
+
+    fn scrub(name: &str, offset: i64) -> StackAccess {
+        StackAccess::Scrub {
+            tid: Tid::new(name),
+            symbol: "memset".to_string(),
+            offset,
+            size: ByteSize::new(16),
+        }
+    }
+
+    fn call_memset(return_: &str) -> Jmp {
+        Jmp::Call {
+            target: Tid::new("memset"),
+            return_: Some(Tid::new(return_)),
+        }
+    }
+
+    #[test]
+    fn scrubbing_call_accesses() {
+        let project = Project::mock_empty();
+        let memset = mock_memset();
+        let scrubbing_symbols: ScrubbingSymbols =
+            vec![(memset.tid.clone(), (&memset, &SCRUBBING_FUNCTIONS[0]))]
+                .into_iter()
+                .collect();
+        let block = Blk::mock_with_defs_and_jmp(
+            "blk",
+            vec![
+                Def::store(
+                    "secret",
+                    Expression::Var(Variable::mock("RSP", 8)).plus_const(-32),
+                    Expression::Const(Bitvector::from_i64(42)),
+                ),
+                Def::assign(
+                    "buffer",
+                    Variable::mock("RDI", 8),
+                    Expression::Var(Variable::mock("RSP", 8)).plus_const(-32),
+                ),
+                Def::assign(
+                    "size",
+                    Variable::mock("RDX", 8),
+                    Expression::Const(Bitvector::from_i64(16)),
+                ),
+            ],
+            call_memset("after"),
+        );
+        let state = State::new(&Variable::mock("RSP", 8), Tid::new("func"));
+        let accesses = compute_stack_accesses(
+            &project,
+            &block,
+            state,
+            &scrubbing_symbols,
+            &RuntimeMemoryImage::mock(),
+        );
+        assert_eq!(
+            accesses,
+            vec![
+                StackAccess::Store {
+                    offset: -32,
+                    size: ByteSize::new(8)
+                },
+                StackAccess::Scrub {
+                    tid: block.term.jmps[0].tid.clone(),
+                    symbol: "memset".to_string(),
+                    offset: -32,
+                    size: ByteSize::new(16)
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn dead_scrub_detection() {
+        let memset = mock_memset();
+        let scrubbing_symbols: ScrubbingSymbols =
+            vec![(memset.tid.clone(), (&memset, &SCRUBBING_FUNCTIONS[0]))]
+                .into_iter()
+                .collect();
+        let mut sub = Sub::mock("func");
+        sub.term.blocks = vec![
+            Blk::mock_with_jmp("blk1", call_memset("blk2")),
+            Blk::mock_with_jmp("blk2", Jmp::Branch(Tid::new("blk3"))),
+            Blk::mock_with_jmp(
+                "blk3",
+                Jmp::Return(Expression::Const(Bitvector::zero(64.into()))),
+            ),
+        ];
+        let mut block_accesses = HashMap::new();
+        // The buffer is scrubbed with memset and never read afterwards.
+        block_accesses.insert(
+            Tid::new("blk1"),
+            vec![
+                StackAccess::Store {
+                    offset: -32,
+                    size: ByteSize::new(8),
+                },
+                scrub("scrub_secret", -32),
+            ],
+        );
+        // A scrubbed buffer that is read afterwards is not reported.
+        block_accesses.insert(
+            Tid::new("blk2"),
+            vec![
+                scrub("scrub_read", -16),
+                StackAccess::Load {
+                    offset: -8,
+                    size: ByteSize::new(4),
+                },
+            ],
+        );
+        block_accesses.insert(Tid::new("blk3"), vec![]);
+        let dead_scrubs = compute_dead_scrubs(&sub, &block_accesses, &scrubbing_symbols);
+        assert_eq!(
+            dead_scrubs,
+            vec![(Tid::new("scrub_secret"), "memset".to_string(), -32)]
+        );
+
+        // A read of the buffer after the memset call makes the scrubbing live.
+        block_accesses
+            .get_mut(&Tid::new("blk3"))
+            .unwrap()
+            .push(StackAccess::Load {
+                offset: -24,
+                size: ByteSize::new(4),
+            });
+        let dead_scrubs = compute_dead_scrubs(&sub, &block_accesses, &scrubbing_symbols);
+        assert!(dead_scrubs.is_empty());
+
+        // Other calls are assumed to read the whole stack frame.
+        sub.term.blocks[0] = Blk::mock_with_jmp(
+            "blk1",
+            Jmp::Call {
+                target: Tid::new("other_function"),
+                return_: Some(Tid::new("blk2")),
+            },
+        );
+        block_accesses.get_mut(&Tid::new("blk3")).unwrap().clear();
+        let dead_scrubs = compute_dead_scrubs(&sub, &block_accesses, &scrubbing_symbols);
+        assert!(dead_scrubs.is_empty());
+    }
+
+    #[test]
+    fn liveness_in_loops() {
+        let memset = mock_memset();
+        let scrubbing_symbols: ScrubbingSymbols =
+            vec![(memset.tid.clone(), (&memset, &SCRUBBING_FUNCTIONS[0]))]
+                .into_iter()
+                .collect();
+        let mut sub = Sub::mock("func");
+        sub.term.blocks = vec![
+            Blk::mock_with_jmp("loop_body", call_memset("loop_head")),
+            Blk::mock_with_jmp("loop_head", Jmp::Branch(Tid::new("loop_body"))),
+        ];
+        let mut block_accesses = HashMap::new();
+        // The scrubbed buffer is read in the next loop iteration.
+        block_accesses.insert(Tid::new("loop_body"), vec![scrub("scrub", -16)]);
+        block_accesses.insert(
+            Tid::new("loop_head"),
+            vec![StackAccess::Load {
+                offset: -16,
+                size: ByteSize::new(8),
+            }],
+        );
+        let dead_scrubs = compute_dead_scrubs(&sub, &block_accesses, &scrubbing_symbols);
+        assert!(dead_scrubs.is_empty());
+    }
+}
@@ -0,0 +1,501 @@
+//! This module implements a check for CWE-667: Improper Locking
+//! and for CWE-764: Multiple Locks of a Critical Resource.
+//!
+//! Locks that are not released on every path through a function (e.g. because of an early return on an error path)
+//! can lead to deadlocks once another thread tries to acquire the lock.
+//! Locking a non-recursive lock twice in the same thread immediately deadlocks the thread.
+//!
+//! See <https://cwe.mitre.org/data/definitions/667.html>
+//! and <https://cwe.mitre.org/data/definitions/764.html> for detailed descriptions.
+//!
+//! ## How the check works
+//!
+//! Using the results of the [Pointer Inference analysis](crate::analysis::pointer_inference)
+//! the lock object parameter of each call to a lock or unlock function is evaluated to an abstract lock object,
+//! i.e. a (known) offset into an abstract memory object or a global address.
+//! Then for each function an intraprocedural dataflow analysis computes
+//! which locks may be held and which locks are held on all paths at each block.
+//! - If a lock is acquired that is already held on all paths to the call, a CWE-764 warning is generated.
+//! - If a lock may still be held when the function returns,
+//! but the function releases the same lock on some other path,
+//! a CWE-667 warning is generated for the corresponding lock call.
+//!
+//! The lock and unlock functions can be configured in the configuration file.
+//!
+//! ## False Positives
+//!
+//! - Infeasible paths, e.g. if locking and unlocking are both guarded by the same condition.
+//! - Recursive locks that are intentionally acquired several times.
+//!
+//! ## False Negatives
+//!
+//! - The analysis is intraprocedural,
+//! so locks acquired or released in called functions (other than the configured lock functions) are not tracked.
+//! - Lock objects that the Pointer Inference cannot track are ignored.
+//! A call to an unlock function with an unknown lock object is assumed to release all held locks.
+//! - Functions that acquire a lock without releasing it on any path (e.g. lock wrapper functions) are not reported.
+//! - Functions where the dataflow analysis does not stabilize are not checked.
+//! A log message is generated for them.
+//! - Calls to `trylock`-like functions are ignored, since their success depends on the return value.
+
+use crate::abstract_domain::{AbstractIdentifier, BitvectorDomain};
+use crate::analysis::block_dataflow::{self, Direction};
+use crate::analysis::graph::Node;
+use crate::analysis::interprocedural_fixpoint_generic::NodeValue;
+use crate::analysis::pointer_inference::{Data, State};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage, Remediation, Severity};
+use crate::{CweModule, RequiredAnalysis};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE667",
+    version: "0.1",
+    cwes: &[667, 764],
+    description: "Improper locking, e.g. locks that are not released on all paths.",
+    required_analyses: &[RequiredAnalysis::PointerInference],
+    config_keys: &["lock_symbols", "unlock_symbols"],
+    run: check_cwe,
+    validate_config: Some(crate::utils::config_validation::deserialize_section::<Config>),
+};
+
+/// The names of the lock and unlock functions read from *config.json*.
+/// The lock object has to be the first parameter of these functions.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Config {
+    lock_symbols: Vec<String>,
+    unlock_symbols: Vec<String>,
+}
+
+/// An abstract lock object.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+enum LockObject {
+    /// A lock at the given offset of an abstract memory object.
+    Object(AbstractIdentifier, i64),
+    /// A lock at the given global address.
+    Global(u64),
+}
+
+impl std::fmt::Display for LockObject {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LockObject::Object(id, offset) => write!(formatter, "{} + {}", id, offset),
+            LockObject::Global(address) => write!(formatter, "0x{:x}", address),
+        }
+    }
+}
+
+/// A call to a lock or unlock function.
+/// The lock object is `None` if it could not be determined.
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum LockEvent {
+    Lock(Tid, Option<LockObject>),
+    Unlock(Option<LockObject>),
+}
+
+/// The locks held at a specific program point.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+struct LockState {
+    /// The locks that are held on all paths to the program point.
+    must_hold: BTreeSet<LockObject>,
+    /// The locks that are held on at least one path to the program point,
+    /// together with the TIDs of the lock calls that acquired them.
+    may_hold: BTreeMap<LockObject, BTreeSet<Tid>>,
+}
+
+impl LockState {
+    /// Merge two lock states of different paths.
+    fn merge(&self, other: &LockState) -> LockState {
+        let mut may_hold = self.may_hold.clone();
+        for (lock, call_tids) in other.may_hold.iter() {
+            may_hold
+                .entry(lock.clone())
+                .or_insert_with(BTreeSet::new)
+                .extend(call_tids.iter().cloned());
+        }
+        LockState {
+            must_hold: self
+                .must_hold
+                .intersection(&other.must_hold)
+                .cloned()
+                .collect(),
+            may_hold,
+        }
+    }
+
+    /// Update the state according to the given lock event.
+    /// Returns `true` if the event locks a lock that is already held on all paths.
+    fn handle_event(&mut self, event: &LockEvent) -> bool {
+        match event {
+            LockEvent::Lock(call_tid, Some(lock)) => {
+                let is_double_lock = !self.must_hold.insert(lock.clone());
+                let call_tids = self.may_hold.entry(lock.clone()).or_default();
+                call_tids.clear();
+                call_tids.insert(call_tid.clone());
+                is_double_lock
+            }
+            LockEvent::Lock(_, None) => false,
+            LockEvent::Unlock(Some(lock)) => {
+                self.must_hold.remove(lock);
+                self.may_hold.remove(lock);
+                false
+            }
+            LockEvent::Unlock(None) => {
+                *self = LockState::default();
+                false
+            }
+        }
+    }
+}
+
+/// The results of the dataflow analysis for one function.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+struct LockAnalysisResult {
+    /// The TIDs of lock calls that lock a lock which is already held, together with the lock object.
+    double_locks: Vec<(Tid, LockObject)>,
+    /// The TIDs of lock calls whose lock may not be released before the function returns,
+    /// together with the lock object.
+    missing_unlocks: Vec<(Tid, LockObject)>,
+}
+
+/// Get the lock object that the first parameter of the call points to.
+fn get_lock_object(
+    project: &Project,
+    state: &State,
+    extern_symbol: &ExternSymbol,
+    analysis_results: &AnalysisResults,
+) -> Option<LockObject> {
+    let data = state
+        .eval_nth_parameter(
+            project,
+            extern_symbol,
+            0,
+            analysis_results.runtime_memory_image,
+        )
+        .ok()?;
+    match data {
+        Data::Pointer(pointer) if pointer.targets().len() == 1 => {
+            let (id, offset) = pointer.targets().iter().next().unwrap();
+            match offset {
+                BitvectorDomain::Value(offset) => {
+                    Some(LockObject::Object(id.clone(), offset.try_to_i64().ok()?))
+                }
+                BitvectorDomain::Top(_)
+                | BitvectorDomain::Partial(..)
+                | BitvectorDomain::Interval(_)
+                | BitvectorDomain::KnownBits(_) => None,
+            }
+        }
+        Data::Value(BitvectorDomain::Value(address)) => {
+            Some(LockObject::Global(address.try_to_u64().ok()?))
+        }
+        _ => None,
+    }
+}
+
+/// Compute the lock event of the call at the end of each block of a function.
+fn compute_lock_events(
+    analysis_results: &AnalysisResults,
+    config: &Config,
+) -> HashMap<Tid, HashMap<Tid, LockEvent>> {
+    let project = analysis_results.project;
+    let extern_symbols: HashMap<&Tid, &ExternSymbol> = project
+        .program
+        .term
+        .extern_symbols
+        .iter()
+        .map(|symbol| (&symbol.tid, symbol))
+        .collect();
+    let pointer_inference_results = analysis_results.pointer_inference.unwrap();
+    let graph = pointer_inference_results.get_graph();
+    let mut events_per_sub: HashMap<Tid, HashMap<Tid, LockEvent>> = HashMap::new();
+    for node in graph.node_indices() {
+        if let Node::BlkEnd(block, sub) = graph[node] {
+            for jmp in block.term.jmps.iter() {
+                let symbol = match &jmp.term {
+                    Jmp::Call { target, .. } => match extern_symbols.get(target) {
+                        Some(symbol) => symbol,
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+                let is_lock = config.lock_symbols.contains(&symbol.name);
+                if !is_lock && !config.unlock_symbols.contains(&symbol.name) {
+                    continue;
+                }
+                let lock_object = match pointer_inference_results.get_node_value(node) {
+                    Some(NodeValue::Value(state)) => {
+                        get_lock_object(project, state, symbol, analysis_results)
+                    }
+                    _ => None,
+                };
+                let event = if is_lock {
+                    LockEvent::Lock(jmp.tid.clone(), lock_object)
+                } else {
+                    LockEvent::Unlock(lock_object)
+                };
+                events_per_sub
+                    .entry(sub.tid.clone())
+                    .or_insert_with(HashMap::new)
+                    .insert(block.tid.clone(), event);
+            }
+        }
+    }
+    events_per_sub
+}
+
+/// The dataflow problem of the lock analysis of a single function.
+/// The value of a block is the lock state at the start of the block.
+struct LockProblem<'a> {
+    lock_events: &'a HashMap<Tid, LockEvent>,
+}
+
+impl<'a> block_dataflow::Problem for LockProblem<'a> {
+    type Value = LockState;
+
+    fn merge(&self, value1: &LockState, value2: &LockState) -> LockState {
+        value1.merge(value2)
+    }
+
+    fn update_edge(
+        &self,
+        value: &LockState,
+        block: &Term<Blk>,
+        _successor: &Term<Blk>,
+    ) -> Option<LockState> {
+        let mut state = value.clone();
+        if let Some(event) = self.lock_events.get(&block.tid) {
+            state.handle_event(event);
+        }
+        Some(state)
+    }
+}
+
+/// Run the lock dataflow analysis on a function given the lock events of its blocks.
+/// Returns a log message if the analysis does not stabilize.
+fn analyze_function(
+    sub: &Term<Sub>,
+    lock_events: &HashMap<Tid, LockEvent>,
+) -> Result<LockAnalysisResult, LogMessage> {
+    let entry_block = match sub.term.blocks.first() {
+        Some(block) => block,
+        None => return Ok(LockAnalysisResult::default()),
+    };
+    let state_at_block_start = block_dataflow::compute(
+        &LockProblem { lock_events },
+        sub,
+        Direction::Forward,
+        vec![(entry_block.tid.clone(), LockState::default())],
+    )?;
+
+    let unlocked_objects: BTreeSet<&LockObject> = lock_events
+        .values()
+        .filter_map(|event| match event {
+            LockEvent::Unlock(Some(lock)) => Some(lock),
+            _ => None,
+        })
+        .collect();
+    let mut result = LockAnalysisResult::default();
+    let mut missing_unlocks = BTreeSet::new();
+    for block in sub.term.blocks.iter() {
+        let mut state = match state_at_block_start.get(&block.tid) {
+            Some(state) => state.clone(),
+            None => continue,
+        };
+        if let Some(event) = lock_events.get(&block.tid) {
+            if state.handle_event(event) {
+                if let LockEvent::Lock(call_tid, Some(lock)) = event {
+                    result.double_locks.push((call_tid.clone(), lock.clone()));
+                }
+            }
+        }
+        if block
+            .term
+            .jmps
+            .iter()
+            .any(|jmp| matches!(jmp.term, Jmp::Return(_)))
+        {
+            for (lock, call_tids) in state.may_hold.iter() {
+                if unlocked_objects.contains(lock) {
+                    for call_tid in call_tids {
+                        missing_unlocks.insert((call_tid.clone(), lock.clone()));
+                    }
+                }
+            }
+        }
+    }
+    result.missing_unlocks = missing_unlocks.into_iter().collect();
+    Ok(result)
+}
+
+/// Generate the CWE warning for a lock that may not be released before the function returns.
+fn generate_missing_unlock_warning(
+    sub: &Term<Sub>,
+    call_tid: &Tid,
+    lock: &LockObject,
+) -> CweWarning {
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Improper Locking) Lock acquired at {} in {} may not be released before the function returns",
+            call_tid.address, sub.term.name
+        ),
+    )
+    .severity(Severity::Medium)
+    .tids(vec![format!("{}", call_tid)])
+    .addresses(vec![call_tid.address.clone()])
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![vec!["lock_object".to_string(), lock.to_string()]])
+    .remediation(Remediation::new(
+        667,
+        format!(
+            "Release the lock on every path through {}, including early returns on error paths. A single exit path that releases all held locks avoids this class of errors.",
+            sub.term.name
+        ),
+    ))
+}
+
+/// Generate the CWE warning for a lock that is acquired while it is already held.
+fn generate_double_lock_warning(sub: &Term<Sub>, call_tid: &Tid, lock: &LockObject) -> CweWarning {
+    CweWarning::new(
+        "CWE764",
+        CWE_MODULE.version,
+        format!(
+            "(Multiple Locks of a Critical Resource) Lock at {} in {} is already held",
+            call_tid.address, sub.term.name
+        ),
+    )
+    .severity(Severity::Medium)
+    .tids(vec![format!("{}", call_tid)])
+    .addresses(vec![call_tid.address.clone()])
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![vec!["lock_object".to_string(), lock.to_string()]])
+    .remediation(Remediation::new(
+        764,
+        "Do not acquire a non-recursive lock that is already held by the current thread. Release the lock first or restructure the code so that the lock is only acquired once.",
+    ))
+}
+
+/// Run the CWE check.
+/// For each function we compute the lock events of calls to lock and unlock functions
+/// and run a dataflow analysis on the held locks.
+/// The check stops early if the module cancellation token gets cancelled.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
+    let config: Config = serde_json::from_value(cwe_params.clone()).unwrap();
+    let events_per_sub = compute_lock_events(analysis_results, &config);
+    let mut logs = Vec::new();
+    let mut cwe_warnings = Vec::new();
+    for sub in analysis_results.project.program.term.subs.iter() {
+        if analysis_results.cancellation.is_cancelled() {
+            return (logs, cwe_warnings, true);
+        }
+        if let Some(lock_events) = events_per_sub.get(&sub.tid) {
+            let result = match analyze_function(sub, lock_events) {
+                Ok(result) => result,
+                Err(log) => {
+                    logs.push(log.source(CWE_MODULE.name));
+                    continue;
+                }
+            };
+            for (call_tid, lock) in result.double_locks.iter() {
+                cwe_warnings.push(generate_double_lock_warning(sub, call_tid, lock));
+            }
+            for (call_tid, lock) in result.missing_unlocks.iter() {
+                cwe_warnings.push(generate_missing_unlock_warning(sub, call_tid, lock));
+            }
+        }
+    }
+    (logs, cwe_warnings, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(return_block: &str) -> Jmp {
+        Jmp::Call {
+            target: Tid::new("lock_symbol"),
+            return_: Some(Tid::new(return_block)),
+        }
+    }
+
+    fn return_jmp() -> Jmp {
+        Jmp::Return(Expression::Const(Bitvector::zero(64.into())))
+    }
+
+    #[test]
+    fn missing_unlock_on_early_return() {
+        let lock = LockObject::Global(0x4000);
+        let mut sub = Sub::mock("func");
+        sub.term.blocks = vec![
+            Blk::mock_with_jmp("lock", call("check")),
+            Blk::mock_with_jmp(
+                "check",
+                Jmp::CBranch {
+                    target: Tid::new("early_return"),
+                    condition: Expression::Const(Bitvector::from_u8(0)),
+                },
+            ),
+            Blk::mock_with_jmp("early_return", return_jmp()),
+        ];
+        // The `check` block needs a fall-through jump to the unlock block.
+        sub.term.blocks[1].term.jmps.push(Term {
+            tid: Tid::new("check_fallthrough"),
+            term: Jmp::Branch(Tid::new("unlock")),
+        });
+        sub.term
+            .blocks
+            .push(Blk::mock_with_jmp("unlock", call("exit")));
+        sub.term
+            .blocks
+            .push(Blk::mock_with_jmp("exit", return_jmp()));
+
+        let mut lock_events = HashMap::new();
+        lock_events.insert(
+            Tid::new("lock"),
+            LockEvent::Lock(Tid::new("lock_jmp"), Some(lock.clone())),
+        );
+        lock_events.insert(Tid::new("unlock"), LockEvent::Unlock(Some(lock.clone())));
+        let result = analyze_function(&sub, &lock_events).unwrap();
+        assert!(result.double_locks.is_empty());
+        assert_eq!(
+            result.missing_unlocks,
+            vec![(Tid::new("lock_jmp"), lock.clone())]
+        );
+
+        // Without the early return no warning is generated.
+        sub.term.blocks[1].term.jmps.remove(0);
+        let result = analyze_function(&sub, &lock_events).unwrap();
+        assert!(result.missing_unlocks.is_empty());
+    }
+
+    #[test]
+    fn double_lock() {
+        let lock = LockObject::Global(0x4000);
+        let mut sub = Sub::mock("func");
+        sub.term.blocks = vec![
+            Blk::mock_with_jmp("lock1", call("lock2")),
+            Blk::mock_with_jmp("lock2", call("unlock")),
+            Blk::mock_with_jmp("unlock", call("exit")),
+            Blk::mock_with_jmp("exit", return_jmp()),
+        ];
+        let mut lock_events = HashMap::new();
+        lock_events.insert(
+            Tid::new("lock1"),
+            LockEvent::Lock(Tid::new("lock1_jmp"), Some(lock.clone())),
+        );
+        lock_events.insert(
+            Tid::new("lock2"),
+            LockEvent::Lock(Tid::new("lock2_jmp"), Some(lock.clone())),
+        );
+        lock_events.insert(Tid::new("unlock"), LockEvent::Unlock(Some(lock.clone())));
+        let result = analyze_function(&sub, &lock_events).unwrap();
+        assert_eq!(result.double_locks, vec![(Tid::new("lock2_jmp"), lock)]);
+        assert!(result.missing_unlocks.is_empty());
+    }
+}
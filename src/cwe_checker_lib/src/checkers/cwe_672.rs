@@ -0,0 +1,255 @@
+//! This module implements a check for CWE-672: Operation on a Resource after Expiration or Release
+//! and for CWE-1341: Multiple Releases of Same Resource or Handle.
+//!
+//! Reading from or writing to a file descriptor or stream that was already closed
+//! either fails or, if the descriptor number was reused in the meantime, accesses an unrelated file or socket.
+//! Closing the same descriptor twice may close a descriptor that was reopened by another thread in the meantime.
+//!
+//! See <https://cwe.mitre.org/data/definitions/672.html>
+//! and <https://cwe.mitre.org/data/definitions/1341.html> for detailed descriptions.
+//!
+//! ## How the check works
+//!
+//! Using the results of the [resource tracking](crate::analysis::resource_tracking)
+//! we check for each call to one of the configured I/O functions (e.g. `read`, `write` or `send`)
+//! whether one of its parameters may refer to a file descriptor or stream
+//! that was closed on some path to the call.
+//! If so, a CWE-672 warning is generated.
+//! For calls to functions closing a resource (e.g. `close` or `fclose`) a CWE-1341 warning is generated instead.
+//! The warning is marked as proven if the parameter refers to a unique resource that was closed on all paths to the call.
+//!
+//! ## False Positives
+//!
+//! - Paths through the function that are infeasible, e.g. because the condition for closing the resource
+//! is the same as the condition for the later use.
+//! - Descriptors that are reopened in a way not covered by the configured symbols.
+//!
+//! ## False Negatives
+//!
+//! - The resource tracking is intraprocedural,
+//! so resources closed or used in other functions than the one that created them are not tracked.
+//! - Resources stored outside of registers and the current stack frame are not tracked.
+
+use crate::analysis::resource_tracking::{self, ResourceTracking};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage, Remediation, Severity, Verdict};
+use crate::{CweModule, RequiredAnalysis};
+use std::collections::HashMap;
+
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE672",
+    version: "0.1",
+    cwes: &[672, 1341],
+    description: "Operations on resources after their expiration or release.",
+    required_analyses: &[RequiredAnalysis::PointerInference],
+    config_keys: &["use_symbols", "resources"],
+    run: check_cwe,
+    validate_config: Some(crate::utils::config_validation::deserialize_section::<Config>),
+};
+
+/// The configuration struct for the check.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Extern symbols that read from or write to the resources given to them as parameters.
+    use_symbols: Vec<String>,
+    /// The symbols creating and closing resources.
+    #[serde(default)]
+    resources: resource_tracking::Config,
+}
+
+/// Generate the CWE warning for a call that may use or close an already closed resource.
+fn generate_cwe_warning(
+    sub: &Term<Sub>,
+    call_tid: &Tid,
+    symbol: &ExternSymbol,
+    closed_resources: &[Tid],
+    is_close: bool,
+) -> CweWarning {
+    let (name, cwe, description, advice) = if is_close {
+        (
+            "CWE1341",
+            1341,
+            format!(
+                "(Multiple Releases of Same Resource or Handle) Call to {} at {} in {} may close a resource that was already closed",
+                symbol.name, call_tid.address, sub.term.name
+            ),
+            format!(
+                "Make sure that {} is called exactly once for each resource, e.g. by invalidating the handle (setting it to -1 or NULL) after it was closed.",
+                symbol.name
+            ),
+        )
+    } else {
+        (
+            CWE_MODULE.name,
+            672,
+            format!(
+                "(Operation on a Resource after Expiration or Release) Call to {} at {} in {} may use a resource that was already closed",
+                symbol.name, call_tid.address, sub.term.name
+            ),
+            format!(
+                "Make sure that the resource given to {} is still open on all paths to the call, e.g. by invalidating the handle after it was closed and checking it before use.",
+                symbol.name
+            ),
+        )
+    };
+    let creation_sites = closed_resources
+        .iter()
+        .map(|tid| tid.address.clone())
+        .collect::<Vec<_>>()
+        .join(",");
+    CweWarning::new(name, CWE_MODULE.version, description)
+        .severity(Severity::Medium)
+        .tids(vec![format!("{}", call_tid)])
+        .addresses(vec![call_tid.address.clone()])
+        .symbols(vec![sub.term.name.clone(), symbol.name.clone()])
+        .other(vec![vec!["resource_creation".to_string(), creation_sites]])
+        .remediation(Remediation::new(cwe, advice))
+}
+
+/// Check the calls to extern symbols in all functions of the project for uses of already closed resources.
+fn check_calls(
+    project: &Project,
+    resource_tracking: &ResourceTracking,
+    config: &Config,
+) -> Vec<CweWarning> {
+    let extern_symbols: HashMap<&Tid, &ExternSymbol> = project
+        .program
+        .term
+        .extern_symbols
+        .iter()
+        .map(|symbol| (&symbol.tid, symbol))
+        .collect();
+    let mut cwe_warnings = Vec::new();
+    for sub in project.program.term.subs.iter() {
+        for block in sub.term.blocks.iter() {
+            for jmp in block.term.jmps.iter() {
+                let symbol = match &jmp.term {
+                    Jmp::Call { target, .. } => match extern_symbols.get(target) {
+                        Some(symbol) => *symbol,
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+                let is_close = config.resources.close_symbols.contains(&symbol.name);
+                if !is_close && !config.use_symbols.contains(&symbol.name) {
+                    continue;
+                }
+                let call_site = match resource_tracking.get_call_site(&jmp.tid) {
+                    Some(call_site) => call_site,
+                    None => continue,
+                };
+                let closed_resources = call_site.get_possibly_closed_parameters();
+                if !closed_resources.is_empty() {
+                    let verdict = if call_site.has_definitely_closed_parameter() {
+                        Verdict::Proven
+                    } else {
+                        Verdict::Possible
+                    };
+                    cwe_warnings.push(
+                        generate_cwe_warning(sub, &jmp.tid, symbol, &closed_resources, is_close)
+                            .verdict(verdict),
+                    );
+                }
+            }
+        }
+    }
+    cwe_warnings
+}
+
+/// Run the CWE check.
+/// For each call to one of the configured I/O functions or to a function closing a resource,
+/// we check whether the resources given to the call may already have been closed on some path.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
+    let config: Config = serde_json::from_value(cwe_params.clone()).unwrap();
+    let resource_tracking = ResourceTracking::compute(
+        analysis_results.project,
+        analysis_results.pointer_inference.unwrap(),
+        analysis_results.runtime_memory_image,
+        &config.resources,
+        &analysis_results.cancellation,
+    );
+    let cwe_warnings = check_calls(analysis_results.project, &resource_tracking, &config);
+    (
+        resource_tracking.get_log_messages().to_vec(),
+        cwe_warnings,
+        resource_tracking.was_interrupted(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abstract_domain::{ResourceKind, ResourceSet, ResourceStateMap};
+    use crate::analysis::resource_tracking::CallSiteInfo;
+
+    fn mock_call(name: &str, target: &str) -> Term<Jmp> {
+        Term {
+            tid: Tid::new(name),
+            term: Jmp::Call {
+                target: Tid::new(target),
+                return_: None,
+            },
+        }
+    }
+
+    #[test]
+    fn closed_resource_detection() {
+        let mut project = Project::mock_empty();
+        for name in ["close", "read", "getpid"].iter() {
+            project
+                .program
+                .term
+                .extern_symbols
+                .push(ExternSymbol::mock_with_name(*name));
+        }
+        let mut block = Blk::mock();
+        block.term.jmps = vec![
+            mock_call("read_call", "read"),
+            mock_call("close_call", "close"),
+            mock_call("getpid_call", "getpid"),
+        ];
+        let mut sub = Sub::mock("func");
+        sub.term.blocks.push(block);
+        project.program.term.subs.push(sub);
+
+        let fd = Tid::new("open_call");
+        let mut resources = ResourceStateMap::default();
+        resources.add(fd.clone(), ResourceKind::FileDescriptor);
+        let mut closed_resources = resources.clone();
+        closed_resources.close(&ResourceSet::new(fd.clone()));
+        let mut call_sites = HashMap::new();
+        for call in ["read_call", "close_call", "getpid_call"].iter() {
+            call_sites.insert(
+                Tid::new(*call),
+                CallSiteInfo {
+                    parameters: vec![ResourceSet::new(fd.clone())],
+                    resources: closed_resources.clone(),
+                },
+            );
+        }
+        let config = Config {
+            use_symbols: vec!["read".to_string()],
+            resources: resource_tracking::Config::default(),
+        };
+        let warnings = check_calls(
+            &project,
+            &ResourceTracking::mock(call_sites.clone()),
+            &config,
+        );
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].name, "CWE672");
+        assert_eq!(warnings[1].name, "CWE1341");
+        // The resource was closed on all paths to the calls.
+        assert_eq!(warnings[0].verdict, Verdict::Proven);
+
+        for call_site in call_sites.values_mut() {
+            call_site.resources = resources.clone();
+        }
+        let warnings = check_calls(&project, &ResourceTracking::mock(call_sites), &config);
+        assert!(warnings.is_empty());
+    }
+}
@@ -24,7 +24,7 @@ use std::collections::HashMap;
 use crate::{
     intermediate_representation::{ExternSymbol, Program, Sub, Term, Tid},
     utils::{
-        log::{CweWarning, LogMessage},
+        log::{CweWarning, LogMessage, Remediation, Severity, Verdict},
         symbol_utils::get_calls_to_symbols,
     },
 };
@@ -35,7 +35,12 @@ const VERSION: &str = "0.1";
 pub static CWE_MODULE: crate::CweModule = crate::CweModule {
     name: "CWE676",
     version: VERSION,
+    cwes: &[676],
+    description: "Use of potentially dangerous functions.",
+    required_analyses: &[],
+    config_keys: &["symbols"],
     run: check_cwe,
+    validate_config: Some(crate::utils::config_validation::deserialize_section::<Config>),
 };
 
 /// struct containing dangerous symbols from config.json
@@ -77,13 +82,25 @@ pub fn generate_cwe_warnings<'a>(
             String::from(CWE_MODULE.version),
             description,
         )
+        .severity(Severity::Low)
+        .verdict(Verdict::Proven)
         .addresses(vec![address.clone()])
         .tids(vec![format!("{}", jmp_tid)])
         .symbols(vec![String::from(*sub_name)])
         .other(vec![vec![
             String::from("dangerous_function"),
             String::from(*target_name),
-        ]]);
+        ]])
+        .remediation(
+            Remediation::new(
+                676,
+                format!(
+                    "Replace the call to {} with a bounds-checked alternative.",
+                    target_name
+                ),
+            )
+            .reference("https://github.com/01org/safestringlib/wiki/SDL-List-of-Banned-Functions"),
+        );
 
         cwe_warnings.push(cwe_warning);
     }
@@ -109,7 +126,7 @@ pub fn resolve_symbols<'a>(
 pub fn check_cwe(
     analysis_results: &AnalysisResults,
     cwe_params: &serde_json::Value,
-) -> (Vec<LogMessage>, Vec<CweWarning>) {
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
     let project = analysis_results.project;
     let config: Config = serde_json::from_value(cwe_params.clone()).unwrap();
     let prog: &Term<Program> = &project.program;
@@ -118,5 +135,5 @@ pub fn check_cwe(
     let dangerous_symbols = resolve_symbols(external_symbols, &config.symbols);
     let dangerous_calls = get_calls(subfunctions, &dangerous_symbols);
 
-    (vec![], generate_cwe_warnings(dangerous_calls))
+    (vec![], generate_cwe_warnings(dangerous_calls), false)
 }
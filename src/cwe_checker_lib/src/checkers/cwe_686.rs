@@ -0,0 +1,605 @@
+//! This module implements a check for CWE-686: Function Call With Incorrect Argument Type.
+//!
+//! Functions with format strings like `printf` or `scanf` take a variable number of arguments,
+//! whose number and types are only given by the format string.
+//! If the arguments at a call site do not match the format string,
+//! the function reads arguments that were never passed (leaking or using stack contents)
+//! or interprets the passed arguments with the wrong size.
+//! Such mismatches are either bugs or indicators of a confusion about the variadic arguments of the function.
+//!
+//! See <https://cwe.mitre.org/data/definitions/686.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! For each call to one of the functions configured in the configuration file
+//! the format string parameter is evaluated in the basic block containing the call.
+//! If it points to a constant string, the string is parsed to get the number and sizes of the expected arguments.
+//! These are compared with the arguments set up in the basic block according to the calling convention of the function:
+//! - If the arguments are passed in registers, a warning is generated if fewer parameter registers are written
+//! than the format string expects arguments.
+//! - If the arguments are passed on the stack, a warning is generated if an expected argument is not written
+//! or if the written value does not match the size of the expected argument,
+//! e.g. if only 4 bytes are written for a `%lld` conversion on a 32-bit architecture.
+//!
+//! ## False Positives
+//!
+//! - Arguments that are set up in a different basic block than the call.
+//! To reduce these, call sites are only checked if the format string parameter itself is set up in the basic block of the call.
+//!
+//! ## False Negatives
+//!
+//! - Format strings that are not constant or that use positional arguments (e.g. `%1$d`) are not checked.
+//! - If the arguments are passed in registers, only the arguments fitting into the parameter registers are checked,
+//! and call sites with floating point arguments are skipped.
+//! - Superfluous arguments are not detected.
+
+use crate::abstract_domain::{BitvectorDomain, DataDomain};
+use crate::analysis::pointer_inference::State;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::binary::RuntimeMemoryImage;
+use crate::utils::log::{CweWarning, LogMessage, Remediation, Severity};
+use crate::utils::symbol_utils::{get_callsites, get_symbol_map};
+use crate::CweModule;
+use std::collections::{BTreeMap, HashSet};
+
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE686",
+    version: "0.1",
+    cwes: &[686],
+    description: "Calls to format string functions with arguments of incorrect types.",
+    required_analyses: &[],
+    config_keys: &["printf_symbols", "scanf_symbols"],
+    run: check_cwe,
+    validate_config: Some(crate::utils::config_validation::deserialize_section::<Config>),
+};
+
+/// The configuration struct of the check.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Config {
+    /// Functions with `printf`-style format strings and the index of their format string parameter.
+    printf_symbols: BTreeMap<String, usize>,
+    /// Functions with `scanf`-style format strings and the index of their format string parameter.
+    scanf_symbols: BTreeMap<String, usize>,
+}
+
+/// An argument expected by a conversion specification of a format string.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum FormatArgument {
+    /// An integer argument of the given size (after the default argument promotions).
+    Integer(ByteSize),
+    /// A floating point argument of the given size (after the default argument promotions).
+    Float(ByteSize),
+    /// A pointer argument, e.g. for `%s` or for all arguments of `scanf`.
+    Pointer,
+}
+
+impl FormatArgument {
+    /// Get the size of the argument.
+    fn bytesize(&self, pointer_size: ByteSize) -> ByteSize {
+        match self {
+            FormatArgument::Integer(size) | FormatArgument::Float(size) => *size,
+            FormatArgument::Pointer => pointer_size,
+        }
+    }
+}
+
+/// Parse a format string and return the arguments expected by it in the order of the arguments.
+///
+/// If `is_scanf` is set, the format string is parsed as a `scanf` format string,
+/// where all arguments are pointers and `*` suppresses the assignment instead of denoting a width argument.
+/// Returns an error for positional arguments and unknown conversion specifications.
+fn parse_format_string(
+    format: &str,
+    pointer_size: ByteSize,
+    is_scanf: bool,
+) -> Result<Vec<FormatArgument>, Error> {
+    let int_size = ByteSize::new(4);
+    let mut arguments = Vec::new();
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        if chars.peek() == Some(&'%') {
+            chars.next();
+            continue;
+        }
+        let mut is_suppressed = false;
+        while let Some(&c) = chars.peek() {
+            match c {
+                '*' if is_scanf => is_suppressed = true,
+                '-' | '+' | ' ' | '#' | '0' | '\'' if !is_scanf => (),
+                _ => break,
+            }
+            chars.next();
+        }
+        // Width and precision
+        while let Some(&c) = chars.peek() {
+            match c {
+                '0'..='9' | '.' => (),
+                '*' if !is_scanf => arguments.push(FormatArgument::Integer(int_size)),
+                '$' => return Err(anyhow!("Positional arguments are not supported")),
+                _ => break,
+            }
+            chars.next();
+        }
+        let mut length = String::new();
+        while let Some(&c) = chars.peek() {
+            if "hlLqjzt".contains(c) {
+                length.push(c);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        let conversion = chars
+            .next()
+            .ok_or_else(|| anyhow!("Incomplete conversion specification"))?;
+        let argument = match conversion {
+            'c' | 'C' => FormatArgument::Integer(int_size),
+            'd' | 'i' | 'u' | 'o' | 'x' | 'X' => match length.as_str() {
+                "" | "h" | "hh" => FormatArgument::Integer(int_size),
+                "l" | "z" | "t" => FormatArgument::Integer(pointer_size),
+                "ll" | "q" | "j" => FormatArgument::Integer(ByteSize::new(8)),
+                _ => return Err(anyhow!("Unsupported length modifier {}", length)),
+            },
+            'f' | 'F' | 'e' | 'E' | 'g' | 'G' | 'a' | 'A' => {
+                if length == "L" && !is_scanf {
+                    return Err(anyhow!("Long double arguments are not supported"));
+                }
+                FormatArgument::Float(ByteSize::new(8))
+            }
+            's' | 'S' | 'p' | 'n' => FormatArgument::Pointer,
+            '[' if is_scanf => {
+                // The first character of a scanset may be a closing bracket.
+                if chars.peek() == Some(&'^') {
+                    chars.next();
+                }
+                if chars.peek() == Some(&']') {
+                    chars.next();
+                }
+                if !chars.any(|c| c == ']') {
+                    return Err(anyhow!("Unterminated scanset"));
+                }
+                FormatArgument::Pointer
+            }
+            _ => {
+                return Err(anyhow!(
+                    "Unsupported conversion specification %{}{}",
+                    length,
+                    conversion
+                ))
+            }
+        };
+        if is_scanf {
+            if !is_suppressed {
+                arguments.push(FormatArgument::Pointer);
+            }
+        } else {
+            arguments.push(argument);
+        }
+    }
+    Ok(arguments)
+}
+
+/// Read the null-terminated string at the given address of the read-only memory of the binary.
+fn read_format_string(
+    address: &Bitvector,
+    global_memory: &RuntimeMemoryImage,
+) -> Result<String, Error> {
+    let (bytes, index) = global_memory.get_ro_data_pointer_at_address(address)?;
+    let length = bytes[index..]
+        .iter()
+        .position(|&byte| byte == 0)
+        .ok_or_else(|| anyhow!("Format string is not null-terminated"))?;
+    Ok(String::from_utf8_lossy(&bytes[index..index + length]).to_string())
+}
+
+/// The arguments of a call as set up in the basic block containing the call.
+struct CallSiteArguments {
+    /// The state at the call site, computed from the definitions in the basic block.
+    state: State,
+    /// The names of the registers written in the basic block.
+    written_registers: HashSet<String>,
+    /// The stack areas written in the basic block,
+    /// given by their offset relative to the stack pointer at the call site and their size.
+    stack_writes: Vec<(i64, u64)>,
+}
+
+impl CallSiteArguments {
+    /// Compute the arguments set up in the given basic block.
+    ///
+    /// The function uses the same `State` struct as the pointer inference analysis for the computation.
+    fn new(
+        block: &Term<Blk>,
+        project: &Project,
+        global_memory: &RuntimeMemoryImage,
+    ) -> CallSiteArguments {
+        let stack_register = &project.stack_pointer_register;
+        let mut state = State::new(stack_register, block.tid.clone());
+        let mut written_registers = HashSet::new();
+        let mut stack_writes = Vec::new();
+        for def in block.term.defs.iter() {
+            match &def.term {
+                Def::Store { address, value } => {
+                    if let Some(offset) = get_stack_offset(&state, address) {
+                        stack_writes.push((offset, u64::from(value.bytesize())));
+                    }
+                    let _ = state.handle_store(address, value, global_memory);
+                }
+                Def::Assign { var, value } => {
+                    written_registers.insert(var.name.clone());
+                    let _ = state.handle_register_assign(var, value);
+                }
+                Def::Load { var, address } => {
+                    written_registers.insert(var.name.clone());
+                    let _ = state.handle_load(var, address, global_memory);
+                }
+            }
+        }
+        let stack_writes = match get_stack_offset(&state, &Expression::Var(stack_register.clone()))
+        {
+            Some(stack_offset) => stack_writes
+                .into_iter()
+                .map(|(offset, size)| (offset - stack_offset, size))
+                .collect(),
+            None => Vec::new(),
+        };
+        CallSiteArguments {
+            state,
+            written_registers,
+            stack_writes,
+        }
+    }
+
+    /// Returns `true` if the given stack area is completely written in the basic block.
+    fn is_stack_area_written(&self, offset: i64, size: u64) -> bool {
+        covered_bytes(&self.stack_writes, offset, offset + size as i64) == size
+    }
+}
+
+/// If the expression evaluates to a pointer with known offset into the stack frame of the state, return the offset.
+fn get_stack_offset(state: &State, expression: &Expression) -> Option<i64> {
+    if let Ok(DataDomain::Pointer(pointer)) = state.eval(expression) {
+        if pointer.targets().len() == 1 {
+            if let Some((_id, BitvectorDomain::Value(offset))) = pointer.targets().iter().next() {
+                return offset.try_to_i64().ok();
+            }
+        }
+    }
+    None
+}
+
+/// Get the number of bytes in the area from `start` to `end` that are written by stores completely contained in the area.
+fn covered_bytes(stack_writes: &[(i64, u64)], start: i64, end: i64) -> u64 {
+    let mut bytes = HashSet::new();
+    for (offset, size) in stack_writes.iter() {
+        if *offset >= start && *offset + *size as i64 <= end {
+            bytes.extend(*offset..*offset + *size as i64);
+        }
+    }
+    bytes.len() as u64
+}
+
+/// Compare the expected arguments with the stack areas written at the call site,
+/// where the first variadic argument is located at `first_offset`.
+///
+/// Returns a description of the first mismatch if one is found.
+fn check_stack_arguments(
+    expected_arguments: &[FormatArgument],
+    first_offset: i64,
+    pointer_size: ByteSize,
+    stack_writes: &[(i64, u64)],
+) -> Option<String> {
+    let slot_size = u64::from(pointer_size);
+    let mut offset = first_offset;
+    for (index, argument) in expected_arguments.iter().enumerate() {
+        let size = u64::from(argument.bytesize(pointer_size));
+        let end = offset + size as i64;
+        let exceeds_argument = stack_writes.iter().any(|(write_offset, write_size)| {
+            *write_offset >= offset
+                && *write_offset < end
+                && write_offset + *write_size as i64 > end
+        });
+        let covered = covered_bytes(stack_writes, offset, end);
+        if covered == 0 && !exceeds_argument {
+            return Some(format!(
+                "The format string expects {} arguments, but only {} are passed",
+                expected_arguments.len(),
+                index
+            ));
+        } else if covered < size || exceeds_argument {
+            return Some(format!(
+                "Argument {} is expected to have a size of {} bytes, but the passed value has a different size",
+                index + 1,
+                size
+            ));
+        }
+        offset += ((size + slot_size - 1) / slot_size * slot_size) as i64;
+    }
+    None
+}
+
+/// Compare the number of expected arguments with the number of parameter registers written at the call site.
+///
+/// Only the arguments fitting into the given parameter registers are checked.
+/// Returns a description of the mismatch if one is found.
+fn check_register_arguments(
+    expected_arguments: &[FormatArgument],
+    parameter_registers: &[String],
+    written_registers: &HashSet<String>,
+) -> Option<String> {
+    let number_of_checked_arguments = expected_arguments.len().min(parameter_registers.len());
+    let number_of_set_up_arguments = parameter_registers[..number_of_checked_arguments]
+        .iter()
+        .take_while(|register| written_registers.contains(*register))
+        .count();
+    if number_of_set_up_arguments < number_of_checked_arguments {
+        Some(format!(
+            "The format string expects {} arguments, but only {} are passed",
+            expected_arguments.len(),
+            number_of_set_up_arguments
+        ))
+    } else {
+        None
+    }
+}
+
+/// Check the arguments of a call to a function with a format string.
+///
+/// Returns the format string and a description of the mismatch if the arguments do not match the format string.
+/// Returns `Ok(None)` if no mismatch was found or if the call site cannot be checked.
+fn check_call_site(
+    block: &Term<Blk>,
+    symbol: &ExternSymbol,
+    format_index: usize,
+    is_scanf: bool,
+    project: &Project,
+    global_memory: &RuntimeMemoryImage,
+) -> Result<Option<(String, String)>, Error> {
+    let pointer_size = project.get_pointer_bytesize();
+    let format_parameter = match symbol.parameters.get(format_index) {
+        Some(parameter) => parameter.clone(),
+        None => Arg::Register(Variable {
+            name: symbol
+                .get_calling_convention(project)
+                .parameter_register
+                .get(format_index)
+                .ok_or_else(|| anyhow!("Format string parameter register not found"))?
+                .clone(),
+            size: pointer_size,
+            is_temp: false,
+        }),
+    };
+    let call_site = CallSiteArguments::new(block, project, global_memory);
+    // Only check call sites where the format string itself is set up in the basic block of the call.
+    match &format_parameter {
+        Arg::Register(var) if !call_site.written_registers.contains(&var.name) => return Ok(None),
+        Arg::Stack { offset, size }
+            if !call_site.is_stack_area_written(*offset, u64::from(*size)) =>
+        {
+            return Ok(None)
+        }
+        _ => (),
+    }
+    let format_address = match call_site.state.eval_parameter_arg(
+        &format_parameter,
+        &project.stack_pointer_register,
+        global_memory,
+    )? {
+        DataDomain::Value(BitvectorDomain::Value(address)) => address,
+        _ => return Ok(None),
+    };
+    let format_string = read_format_string(&format_address, global_memory)?;
+    let expected_arguments = parse_format_string(&format_string, pointer_size, is_scanf)?;
+    let mismatch = match &format_parameter {
+        Arg::Register(var) => {
+            if expected_arguments
+                .iter()
+                .any(|argument| matches!(argument, FormatArgument::Float(_)))
+            {
+                return Ok(None);
+            }
+            let cconv = symbol.get_calling_convention(project);
+            let register_index = cconv
+                .parameter_register
+                .iter()
+                .position(|register| *register == var.name)
+                .ok_or_else(|| anyhow!("Format string register is not a parameter register"))?;
+            check_register_arguments(
+                &expected_arguments,
+                &cconv.parameter_register[register_index + 1..],
+                &call_site.written_registers,
+            )
+        }
+        Arg::Stack { offset, size } => {
+            let slot_size = u64::from(pointer_size);
+            let format_slot_size = (u64::from(*size) + slot_size - 1) / slot_size * slot_size;
+            check_stack_arguments(
+                &expected_arguments,
+                offset + format_slot_size as i64,
+                pointer_size,
+                &call_site.stack_writes,
+            )
+        }
+    };
+    Ok(mismatch.map(|mismatch| (format_string, mismatch)))
+}
+
+/// Generate the CWE warning for a detected instance of the CWE.
+fn generate_cwe_warning(
+    sub: &Term<Sub>,
+    jmp: &Term<Jmp>,
+    symbol: &ExternSymbol,
+    format_string: &str,
+    mismatch: &str,
+) -> CweWarning {
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Function Call With Incorrect Argument Type) Call to {} in function {} with format string {:?}: {}",
+            symbol.name, sub.term.name, format_string, mismatch
+        ),
+    )
+    .severity(Severity::Medium)
+    .tids(vec![format!("{}", jmp.tid)])
+    .addresses(vec![jmp.tid.address.clone()])
+    .symbols(vec![symbol.name.clone()])
+    .other(vec![vec!["format_string".to_string(), format_string.to_string()]])
+    .remediation(Remediation::new(
+        686,
+        "Pass exactly one argument of the matching type for each conversion specification of the format string.",
+    ))
+}
+
+/// Execute the CWE check.
+///
+/// For each call to a function with a format string, the arguments set up in the basic block of the call
+/// are compared with the arguments expected by the format string.
+/// If they do not match, a CWE warning is generated.
+/// If the format string cannot be parsed, a log message is generated.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone()).unwrap();
+    let mut cwes = Vec::new();
+    let mut log_messages = Vec::new();
+    for (symbols, is_scanf) in [
+        (&config.printf_symbols, false),
+        (&config.scanf_symbols, true),
+    ]
+    .iter()
+    {
+        for (symbol_name, format_index) in symbols.iter() {
+            let symbol_map = get_symbol_map(project, &[symbol_name.clone()]);
+            if symbol_map.is_empty() {
+                continue;
+            }
+            for sub in project.program.term.subs.iter() {
+                for (block, jmp, symbol) in get_callsites(sub, &symbol_map) {
+                    match check_call_site(
+                        block,
+                        symbol,
+                        *format_index,
+                        *is_scanf,
+                        project,
+                        analysis_results.runtime_memory_image,
+                    ) {
+                        Ok(Some((format_string, mismatch))) => cwes.push(generate_cwe_warning(
+                            sub,
+                            jmp,
+                            symbol,
+                            &format_string,
+                            &mismatch,
+                        )),
+                        Ok(None) => (),
+                        Err(err) => {
+                            let log = LogMessage::new_info(format!(
+                                "Could not check the format string arguments of a call to {}: {}",
+                                symbol.name, err
+                            ))
+                            .location(jmp.tid.clone())
+                            .source(CWE_MODULE.name);
+                            log_messages.push(log);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    (log_messages, cwes, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_string_parsing() {
+        let pointer_size = ByteSize::new(8);
+        assert_eq!(
+            parse_format_string("%d %5.2f %% %-10s %lx %p %*d\n", pointer_size, false).unwrap(),
+            vec![
+                FormatArgument::Integer(ByteSize::new(4)),
+                FormatArgument::Float(ByteSize::new(8)),
+                FormatArgument::Pointer,
+                FormatArgument::Integer(ByteSize::new(8)),
+                FormatArgument::Pointer,
+                FormatArgument::Integer(ByteSize::new(4)),
+                FormatArgument::Integer(ByteSize::new(4)),
+            ]
+        );
+        assert_eq!(
+            parse_format_string("%lld %hhu", ByteSize::new(4), false).unwrap(),
+            vec![
+                FormatArgument::Integer(ByteSize::new(8)),
+                FormatArgument::Integer(ByteSize::new(4)),
+            ]
+        );
+        assert_eq!(
+            parse_format_string("%d %*s %[^]x] %lf", pointer_size, true).unwrap(),
+            vec![FormatArgument::Pointer; 3]
+        );
+        assert!(parse_format_string("%1$d", pointer_size, false).is_err());
+        assert!(parse_format_string("%Lf", pointer_size, false).is_err());
+        assert!(parse_format_string("%", pointer_size, false).is_err());
+    }
+
+    #[test]
+    fn stack_arguments() {
+        let pointer_size = ByteSize::new(4);
+        let expected = parse_format_string("%d %lld", pointer_size, false).unwrap();
+        // Format string at offset 0, arguments at offsets 4 and 8.
+        assert_eq!(
+            check_stack_arguments(
+                &expected,
+                4,
+                pointer_size,
+                &[(0, 4), (4, 4), (8, 4), (12, 4)]
+            ),
+            None
+        );
+        assert_eq!(
+            check_stack_arguments(&expected, 4, pointer_size, &[(0, 4), (4, 4), (8, 8)]),
+            None
+        );
+        assert_eq!(
+            check_stack_arguments(&expected, 4, pointer_size, &[(0, 4), (4, 4), (8, 4)]),
+            Some("Argument 2 is expected to have a size of 8 bytes, but the passed value has a different size".to_string())
+        );
+        assert_eq!(
+            check_stack_arguments(&expected, 4, pointer_size, &[(0, 4), (4, 8)]),
+            Some("Argument 1 is expected to have a size of 4 bytes, but the passed value has a different size".to_string())
+        );
+        assert_eq!(
+            check_stack_arguments(&expected, 4, pointer_size, &[(0, 4), (4, 4)]),
+            Some("The format string expects 2 arguments, but only 1 are passed".to_string())
+        );
+    }
+
+    #[test]
+    fn register_arguments() {
+        let registers: Vec<String> = vec!["RSI".to_string(), "RDX".to_string()];
+        let expected = parse_format_string("%s %d %d", ByteSize::new(8), false).unwrap();
+        let written: HashSet<String> =
+            vec!["RDI".to_string(), "RSI".to_string(), "RDX".to_string()]
+                .into_iter()
+                .collect();
+        assert_eq!(
+            check_register_arguments(&expected, &registers, &written),
+            None
+        );
+        let written: HashSet<String> = vec!["RDI".to_string(), "RSI".to_string()]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            check_register_arguments(&expected, &registers, &written),
+            Some("The format string expects 3 arguments, but only 1 are passed".to_string())
+        );
+    }
+}
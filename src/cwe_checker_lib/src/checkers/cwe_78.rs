@@ -39,6 +39,8 @@
 //!
 //! - Missing Taints due to lost track of pointer targets
 //! - Non tracked function parameters cause incomplete taints that could miss possible dangerous inputs
+//! - If the module timeout is exceeded, the remaining taint sources are not analyzed completely
+//! - If the resource budget is exceeded, the analysis of taint sources is stopped early or skipped
 
 use std::collections::HashMap;
 
@@ -50,8 +52,11 @@ use crate::{
     },
     intermediate_representation::{Jmp, Project, Sub},
     prelude::*,
-    utils::log::{CweWarning, LogMessage},
-    AnalysisResults, CweModule,
+    utils::{
+        log::{CweWarning, LogMessage},
+        resource_budget::BudgetTracker,
+    },
+    AnalysisResults, CweModule, RequiredAnalysis,
 };
 
 use petgraph::{graph::NodeIndex, visit::EdgeRef};
@@ -64,7 +69,12 @@ use context::*;
 pub static CWE_MODULE: CweModule = CweModule {
     name: "CWE78",
     version: "0.1",
+    cwes: &[78],
+    description: "OS command injections through user input reaching system().",
+    required_analyses: &[RequiredAnalysis::PointerInference],
+    config_keys: &["system_symbols", "string_symbols", "user_input_symbols"],
     run: check_cwe,
+    validate_config: Some(crate::utils::config_validation::deserialize_section::<Config>),
 };
 
 /// The configuration struct
@@ -84,7 +94,7 @@ pub struct Config {
 pub fn check_cwe(
     analysis_results: &AnalysisResults,
     cwe_params: &serde_json::Value,
-) -> (Vec<LogMessage>, Vec<CweWarning>) {
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
     let project = analysis_results.project;
     let pointer_inference_results = analysis_results.pointer_inference.unwrap();
 
@@ -107,6 +117,11 @@ pub fn check_cwe(
     );
 
     let entry_sub_to_entry_node_map = get_entry_sub_to_entry_node_map(project, &general_context);
+    let mut cwe_warnings = HashMap::new();
+    let mut budget_tracker = BudgetTracker::new(
+        analysis_results.budget,
+        analysis_results.cancellation.clone(),
+    );
 
     for edge in general_context.get_pi_graph().edge_references() {
         if let Edge::ExternCallStub(jmp) = edge.weight() {
@@ -134,14 +149,26 @@ pub fn check_cwe(
                             current_sub,
                         )),
                     );
-                    computation.compute_with_max_steps(100);
+                    budget_tracker.compute(&mut computation, 100, &current_sub.tid);
 
                     for (sub_name, node_index) in entry_sub_to_entry_node_map.iter() {
                         if let Some(node_weight) = computation.get_node_value(*node_index) {
                             let state = node_weight.unwrap_value();
                             if !state.is_empty() {
-                                context.generate_cwe_warning(sub_name);
+                                context.generate_cwe_warning(sub_name, None);
+                            }
+                        }
+                    }
+                    // Report new warnings right away, since the whole check may run for a long time.
+                    for cwe in cwe_receiver.try_iter() {
+                        match &cwe.addresses[..] {
+                            [taint_source_address, ..] => {
+                                if !cwe_warnings.contains_key(taint_source_address) {
+                                    analysis_results.report_warning(&cwe);
+                                }
+                                cwe_warnings.insert(taint_source_address.clone(), cwe);
                             }
+                            _ => panic!(),
                         }
                     }
                 }
@@ -149,16 +176,13 @@ pub fn check_cwe(
         }
     }
 
-    let mut cwe_warnings = HashMap::new();
-    for cwe in cwe_receiver.try_iter() {
-        match &cwe.addresses[..] {
-            [taint_source_address, ..] => cwe_warnings.insert(taint_source_address.clone(), cwe),
-            _ => panic!(),
-        };
-    }
     let cwe_warnings = cwe_warnings.into_iter().map(|(_, cwe)| cwe).collect();
 
-    (Vec::new(), cwe_warnings)
+    (
+        budget_tracker.get_logs(CWE_MODULE.name),
+        cwe_warnings,
+        budget_tracker.was_interrupted(),
+    )
 }
 
 /// Returns a map from subroutine names to their corresponding start node index
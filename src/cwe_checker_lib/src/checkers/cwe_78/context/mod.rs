@@ -17,7 +17,10 @@ use crate::{
     },
     checkers::cwe_476::Taint,
     intermediate_representation::*,
-    utils::{binary::RuntimeMemoryImage, log::CweWarning},
+    utils::{
+        binary::RuntimeMemoryImage,
+        log::{CweWarning, ExploitabilityHint, Impact, Remediation, Severity},
+    },
 };
 
 #[derive(Clone)]
@@ -119,8 +122,12 @@ impl<'a> Context<'a> {
         }
     }
 
-    /// Generates the CWE Warning for the CWE 78 check
-    pub fn generate_cwe_warning(&self, sub_name: &str) {
+    /// Generates the CWE Warning for the CWE 78 check.
+    /// If the tainted input originates from a call to a user input function
+    /// (given by the TID of the call and the name of the called symbol),
+    /// the warning lists this call after the call to the taint source
+    /// and the exploitability hint is derived from the labels of the user input function.
+    pub fn generate_cwe_warning(&self, sub_name: &str, input_call: Option<(&Tid, &str)>) {
         let source = self.taint_source.unwrap();
         let name = self.taint_source_name.clone().unwrap();
         let description: String = format!(
@@ -132,10 +139,34 @@ impl<'a> Context<'a> {
             String::from(CWE_MODULE.version),
             description,
         )
-        .addresses(vec![source.tid.address.clone()])
-        .tids(vec![format!("{}", source.tid)])
+        .severity(Severity::High)
+        .addresses(
+            std::iter::once(&source.tid)
+                .chain(input_call.map(|(call_tid, _)| call_tid))
+                .map(|tid| tid.address.clone())
+                .collect(),
+        )
+        .tids(
+            std::iter::once(&source.tid)
+                .chain(input_call.map(|(call_tid, _)| call_tid))
+                .map(|tid| format!("{}", tid))
+                .collect(),
+        )
         .symbols(vec![String::from(sub_name)])
-        .other(vec![vec![String::from("OS Command Injection"), name]]);
+        .other(vec![vec![String::from("OS Command Injection"), name.clone()]])
+        .remediation(Remediation::new(
+            78,
+            format!(
+                "Validate or escape the input passed to {} in {} or avoid invoking a shell by using the exec family of functions with an argument vector instead.",
+                name, sub_name
+            ),
+        ))
+        .exploitability(match input_call {
+            Some((_, input_symbol)) => {
+                ExploitabilityHint::new(Impact::CodeExecution).taint_source(input_symbol)
+            }
+            None => ExploitabilityHint::new(Impact::CodeExecution),
+        });
         let _ = self.cwe_collector.send(cwe_warning);
     }
 
@@ -225,12 +256,14 @@ impl<'a> Context<'a> {
 
     /// This function taints the registers and stack positions of the parameter pointers of external functions
     /// If the function is one of the specified string functions, the processing of the call is transferred to
-    /// the string function processor
+    /// the string function processor.
+    /// `call_tid` is the TID of the call to the external function.
     pub fn taint_generic_function_parameters_and_remove_non_callee_saved(
         &self,
         state: &State,
         symbol: &ExternSymbol,
         call_source_node: NodeIndex,
+        call_tid: &Tid,
     ) -> State {
         let mut new_state = state.clone();
         // Check if the extern symbol is a string symbol, since the return register is not tainted for these.
@@ -257,6 +290,7 @@ impl<'a> Context<'a> {
                 if self.user_input_symbol_map.get(&symbol.tid).is_some() {
                     self.generate_cwe_warning(
                         &new_state.get_current_sub().as_ref().unwrap().term.name,
+                        Some((call_tid, symbol.name.as_str())),
                     );
                 }
                 return self.taint_parameters(
@@ -597,6 +631,7 @@ impl<'a> crate::analysis::backward_interprocedural_fixpoint::Context<'a> for Con
                         &new_state,
                         extern_symbol,
                         source_node,
+                        &call.tid,
                     )
                 } else {
                     panic!("Extern symbol not found.");
@@ -261,6 +261,7 @@ fn tainting_generic_function_parameters_and_removing_non_callee_saved() {
         &setup.state,
         &ExternSymbol::mock_string(),
         node_id.clone(),
+        &Tid::new("call_string"),
     );
 
     // Parameter
@@ -291,6 +292,7 @@ fn tainting_generic_function_parameters_and_removing_non_callee_saved() {
         &new_state,
         &ExternSymbol::mock(),
         node_id.clone(),
+        &Tid::new("call_string"),
     );
 
     // Parameter
@@ -7,7 +7,7 @@ use crate::{
     analysis::pointer_inference::{Data, State as PointerInferenceState},
     checkers::cwe_476::Taint,
     intermediate_representation::{
-        Arg, CallingConvention, Expression, ExternSymbol, Project, Sub, Variable,
+        Arg, BinOpType, CallingConvention, Expression, ExternSymbol, Project, Sub, Variable,
     },
     prelude::*,
 };
@@ -196,6 +196,9 @@ impl State {
     /// Taints input registers and evaluates constant memory addresses for simple assignments
     /// and taints memory if a pointer is overwritten.
     /// The taint on the result register is removed.
+    ///
+    /// Taint is propagated with byte granularity through variables and `Subpiece` and `Piece` operations,
+    /// so that only those parts of the inputs get tainted that correspond to tainted bytes of the result.
     pub fn set_expression_taint_and_store_constants(
         &mut self,
         def_tid: &Tid,
@@ -203,10 +206,24 @@ impl State {
         expression: &Expression,
         stack_pointer_register: &Variable,
     ) {
+        let result_taint = self
+            .get_register_taint(result)
+            .copied()
+            .unwrap_or(Taint::Tainted(result.size));
         self.remove_register_taint(result);
         match expression {
             Expression::Const(constant) => self.evaluate_constant(constant.clone()),
-            Expression::Var(var) => self.taint_variable_input(var, stack_pointer_register, def_tid),
+            Expression::Var(_)
+            | Expression::Subpiece { .. }
+            | Expression::BinOp {
+                op: BinOpType::Piece,
+                ..
+            } => self.taint_def_input_bytes(
+                expression,
+                result_taint,
+                stack_pointer_register,
+                def_tid,
+            ),
             Expression::BinOp { .. } => {
                 if let Some(pid_map) = self.pi_def_map.as_ref() {
                     if let Some(pi_state) = pid_map.get(def_tid) {
@@ -216,15 +233,57 @@ impl State {
                     }
                 }
             }
-            Expression::UnOp { arg, .. }
-            | Expression::Cast { arg, .. }
-            | Expression::Subpiece { arg, .. } => {
+            Expression::UnOp { arg, .. } | Expression::Cast { arg, .. } => {
                 self.taint_def_input_register(arg, stack_pointer_register, def_tid)
             }
             _ => (),
         }
     }
 
+    /// Taints the input registers of an expression whose result has the given taint.
+    ///
+    /// For `Subpiece` and `Piece` operations only the inputs corresponding to tainted bytes of the result get tainted.
+    /// The taint of input registers is merged with their already existing taint.
+    /// All other expressions are handled by [`taint_def_input_register`](State::taint_def_input_register).
+    pub fn taint_def_input_bytes(
+        &mut self,
+        expr: &Expression,
+        taint: Taint,
+        stack_pointer_register: &Variable,
+        def_tid: &Tid,
+    ) {
+        if !taint.is_tainted() {
+            return;
+        }
+        match expr {
+            Expression::Var(var) if var.name != stack_pointer_register.name => {
+                let taint = Taint::from_byte_mask(var.size, taint.get_byte_mask());
+                let merged_taint = match self.get_register_taint(var) {
+                    Some(old_taint) => old_taint.merge(&taint),
+                    None => taint,
+                };
+                self.set_register_taint(var, merged_taint);
+            }
+            Expression::Subpiece { low_byte, arg, .. } => self.taint_def_input_bytes(
+                arg,
+                taint.get_subpiece_input_taint(*low_byte, arg.bytesize()),
+                stack_pointer_register,
+                def_tid,
+            ),
+            Expression::BinOp {
+                op: BinOpType::Piece,
+                lhs,
+                rhs,
+            } => {
+                let (lhs_taint, rhs_taint) =
+                    taint.get_piece_input_taints(lhs.bytesize(), rhs.bytesize());
+                self.taint_def_input_bytes(lhs, lhs_taint, stack_pointer_register, def_tid);
+                self.taint_def_input_bytes(rhs, rhs_taint, stack_pointer_register, def_tid);
+            }
+            _ => self.taint_def_input_register(expr, stack_pointer_register, def_tid),
+        }
+    }
+
     /// Taints the input register of a store instruction and removes the memory taint at the target address.
     pub fn taint_value_to_be_stored(
         &mut self,
@@ -207,6 +207,29 @@ fn setting_expression_and_constants() {
         setup.state.get_register_taint(&setup.rdi),
         Some(&Taint::Tainted(setup.rdi.size))
     );
+
+    // Test Case 5: Sub-register assignment where only the lowest byte of the result is tainted
+    let rcx = Variable::mock("RCX", 8 as u64);
+    let piece_expr = Expression::BinOp {
+        op: BinOpType::Piece,
+        lhs: Box::new(Expression::var("RDI").subpiece(ByteSize::new(1), ByteSize::new(7))),
+        rhs: Box::new(Expression::var("RCX").subpiece(ByteSize::new(0), ByteSize::new(1))),
+    };
+    setup.state.set_register_taint(
+        &setup.rdi,
+        Taint::PartiallyTainted(setup.rdi.size, 0b0000_0001),
+    );
+    setup.state.set_expression_taint_and_store_constants(
+        &setup.def_tid,
+        &setup.rdi,
+        &piece_expr,
+        &setup.rsp,
+    );
+    assert_eq!(setup.state.get_register_taint(&setup.rdi), None);
+    assert_eq!(
+        setup.state.get_register_taint(&rcx),
+        Some(&Taint::PartiallyTainted(rcx.size, 0b0000_0001))
+    );
 }
 
 #[test]
@@ -21,7 +21,7 @@ use std::collections::HashMap;
 use crate::{
     intermediate_representation::{Program, Sub, Term, Tid},
     utils::{
-        log::{CweWarning, LogMessage},
+        log::{CweWarning, LogMessage, Remediation, Severity},
         symbol_utils::{find_symbol, get_calls_to_symbols},
     },
 };
@@ -31,7 +31,12 @@ const VERSION: &str = "0.1";
 pub static CWE_MODULE: crate::CweModule = crate::CweModule {
     name: "CWE782",
     version: VERSION,
+    cwes: &[782],
+    description: "Exposed IOCTLs with insufficient access control.",
+    required_analyses: &[],
+    config_keys: &[],
     run: check_cwe,
+    validate_config: None,
 };
 
 /// check whether the ioctl symbol is called by any subroutine. If so, generate the cwe warning.
@@ -57,9 +62,14 @@ pub fn generate_cwe_warning(calls: &[(&str, &Tid, &str)]) -> Vec<CweWarning> {
             String::from(CWE_MODULE.version),
             description,
         )
+        .severity(Severity::Low)
         .addresses(vec![address.clone()])
         .tids(vec![format!("{}", jmp_tid)])
-        .symbols(vec![String::from(*sub_name)]);
+        .symbols(vec![String::from(*sub_name)])
+        .remediation(Remediation::new(
+            782,
+            "Make sure that the driver handling the IOCTL request enforces sufficient access control.",
+        ));
 
         cwe_warnings.push(cwe_warning);
     }
@@ -69,7 +79,7 @@ pub fn generate_cwe_warning(calls: &[(&str, &Tid, &str)]) -> Vec<CweWarning> {
 pub fn check_cwe(
     analysis_results: &AnalysisResults,
     _cwe_params: &serde_json::Value,
-) -> (Vec<LogMessage>, Vec<CweWarning>) {
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
     let project = analysis_results.project;
     let prog: &Term<Program> = &project.program;
     let mut warnings: Vec<CweWarning> = Vec::new();
@@ -80,5 +90,5 @@ pub fn check_cwe(
             .iter()
             .for_each(|sub| warnings.append(&mut handle_sub(sub, symbol)));
     }
-    (vec![], warnings)
+    (vec![], warnings, false)
 }
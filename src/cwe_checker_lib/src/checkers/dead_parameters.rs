@@ -0,0 +1,593 @@
+//! This module reports internal functions with parameters that are never read
+//! or with return values that are ignored by every caller.
+//!
+//! Such parameters and return values are code-quality findings (see CWE-1164: Irrelevant Code).
+//! They are also hints that the calling convention used to recover the function signatures
+//! does not match the binary.
+//! Thus the number of findings doubles as a self-diagnostic of the signature recovery:
+//! If a large fraction of the checked functions have findings, the signatures are probably wrong.
+//!
+//! ## How the check works
+//!
+//! The parameter and return registers are taken from the standard calling convention of the project.
+//! For each function a backward liveness analysis on these registers is computed.
+//! Calls are assumed to read all parameter registers and to overwrite all return registers.
+//! - A parameter register is reported as dead if it is written in the basic block of every direct call to the function,
+//! but is not live at the entry of the function.
+//! - A return value is reported as ignored if the function writes the first return register
+//! in a block containing a return instruction (or in a block branching to such a block),
+//! but the register is not live at the return target of any direct call to the function.
+//!
+//! Only functions with direct calls are checked.
+//! A log message gives the number of checked functions and the number of functions with findings.
+//!
+//! ## False Positives
+//!
+//! - Registers that are written in the basic block of a call for other purposes than passing a parameter.
+//! - Functions that are also called indirectly, e.g. callbacks whose signature is determined by the type of a function pointer.
+//! - Registers used as scratch registers in the return block of a function without a return value.
+//!
+//! ## False Negatives
+//!
+//! - Calls are assumed to read all parameter registers,
+//! so parameters that are only passed on to other functions count as read.
+//! - Indirect jumps, jumps to other functions and `CallOther` instructions are assumed to read all tracked registers.
+//! - Parameters and return values passed in memory (e.g. on the stack) are not checked.
+//! - Functions where the liveness analysis does not stabilize are not checked.
+//! A log message is generated for them.
+
+use crate::analysis::block_dataflow::{self, Direction};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::cancellation::CancellationToken;
+use crate::utils::log::{CweWarning, LogMessage, Remediation, Severity};
+use crate::CweModule;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "DeadParameters",
+    version: "0.1",
+    cwes: &[],
+    description:
+        "Function parameters that are never read and return values that are ignored by all callers.",
+    required_analyses: &[],
+    config_keys: &[],
+    run: check_cwe,
+    validate_config: None,
+};
+
+/// The set of tracked registers that may be read before they are overwritten.
+type LiveRegisters = BTreeSet<String>;
+
+/// A direct call to an internal function.
+struct CallSite<'a> {
+    /// The TID of the calling function.
+    caller: &'a Tid,
+    /// The target of the call, i.e. the block where the execution continues after the call.
+    return_target: Option<&'a Tid>,
+    /// The registers written in the basic block of the call.
+    written_registers: HashSet<&'a String>,
+}
+
+/// Add the tracked registers read by the expression to the live registers.
+fn add_read_registers(live: &mut LiveRegisters, expression: &Expression, tracked: &LiveRegisters) {
+    for var in expression.input_vars() {
+        if tracked.contains(&var.name) {
+            live.insert(var.name.clone());
+        }
+    }
+}
+
+/// Compute the registers that are live immediately before the jump,
+/// given the registers that are live at the jump targets.
+fn get_live_before_jmp(
+    jmp: &Jmp,
+    live_at: &dyn Fn(&Tid) -> LiveRegisters,
+    cconv: &CallingConvention,
+    tracked: &LiveRegisters,
+) -> LiveRegisters {
+    let mut live = match jmp {
+        Jmp::Branch(target) | Jmp::CBranch { target, .. } => live_at(target),
+        Jmp::Call { return_, .. } | Jmp::CallInd { return_, .. } => {
+            let mut live = match return_ {
+                Some(return_target) => live_at(return_target),
+                None => LiveRegisters::new(),
+            };
+            for register in cconv.return_register.iter() {
+                live.remove(register);
+            }
+            live.extend(cconv.parameter_register.iter().cloned());
+            live
+        }
+        Jmp::Return(_) => cconv.return_register.iter().cloned().collect(),
+        Jmp::BranchInd(_) | Jmp::CallOther { .. } => tracked.clone(),
+    };
+    if let Jmp::CBranch {
+        condition: expression,
+        ..
+    }
+    | Jmp::BranchInd(expression)
+    | Jmp::CallInd {
+        target: expression, ..
+    }
+    | Jmp::Return(expression) = jmp
+    {
+        add_read_registers(&mut live, expression, tracked);
+    }
+    live
+}
+
+/// Compute the registers that are live at the start of the block,
+/// given the registers that are live at the jump targets of the block.
+fn get_live_at_block_start(
+    block: &Term<Blk>,
+    live_at: &dyn Fn(&Tid) -> LiveRegisters,
+    cconv: &CallingConvention,
+    tracked: &LiveRegisters,
+) -> LiveRegisters {
+    let mut live = LiveRegisters::new();
+    for jmp in block.term.jmps.iter() {
+        live.extend(get_live_before_jmp(&jmp.term, live_at, cconv, tracked));
+    }
+    for def in block.term.defs.iter().rev() {
+        match &def.term {
+            Def::Assign { var, value } => {
+                live.remove(&var.name);
+                add_read_registers(&mut live, value, tracked);
+            }
+            Def::Load { var, address } => {
+                live.remove(&var.name);
+                add_read_registers(&mut live, address, tracked);
+            }
+            Def::Store { address, value } => {
+                add_read_registers(&mut live, address, tracked);
+                add_read_registers(&mut live, value, tracked);
+            }
+        }
+    }
+    live
+}
+
+/// The backward liveness analysis of the tracked registers of a function.
+/// The value of a block is the set of tracked registers that are live at the start of the block.
+///
+/// Since the liveness transfer functions distribute over unions,
+/// the value of a block is the union of the values computed for each of its successors separately.
+struct Liveness<'a> {
+    block_tids: HashSet<&'a Tid>,
+    cconv: &'a CallingConvention,
+    tracked: LiveRegisters,
+}
+
+impl<'a> Liveness<'a> {
+    /// Compute the registers that are live at the start of the block,
+    /// if the given registers are live at the start of the given successor
+    /// and no registers are live at the start of all other blocks of the function.
+    fn get_live_at_block_start(
+        &self,
+        block: &Term<Blk>,
+        successor: Option<(&Tid, &LiveRegisters)>,
+    ) -> LiveRegisters {
+        let live_at = |target: &Tid| match successor {
+            Some((successor, live)) if successor == target => live.clone(),
+            // Targets outside of the function are assumed to read all tracked registers.
+            _ if !self.block_tids.contains(target) => self.tracked.clone(),
+            _ => LiveRegisters::new(),
+        };
+        get_live_at_block_start(block, &live_at, self.cconv, &self.tracked)
+    }
+}
+
+impl<'a> block_dataflow::Problem for Liveness<'a> {
+    type Value = LiveRegisters;
+
+    fn merge(&self, value1: &LiveRegisters, value2: &LiveRegisters) -> LiveRegisters {
+        value1.union(value2).cloned().collect()
+    }
+
+    fn update_edge(
+        &self,
+        value: &LiveRegisters,
+        block: &Term<Blk>,
+        successor: &Term<Blk>,
+    ) -> Option<LiveRegisters> {
+        Some(self.get_live_at_block_start(block, Some((&successor.tid, value))))
+    }
+}
+
+/// Compute the parameter and return registers of the calling convention
+/// that are live at the start of each block of the function.
+/// Returns a log message if the computation does not stabilize.
+fn compute_live_registers(
+    sub: &Term<Sub>,
+    cconv: &CallingConvention,
+) -> Result<HashMap<Tid, LiveRegisters>, LogMessage> {
+    let liveness = Liveness {
+        block_tids: sub.term.blocks.iter().map(|block| &block.tid).collect(),
+        cconv,
+        tracked: cconv
+            .parameter_register
+            .iter()
+            .chain(cconv.return_register.iter())
+            .cloned()
+            .collect(),
+    };
+    let start_values: Vec<(Tid, LiveRegisters)> = sub
+        .term
+        .blocks
+        .iter()
+        .map(|block| {
+            (
+                block.tid.clone(),
+                liveness.get_live_at_block_start(block, None),
+            )
+        })
+        .collect();
+    block_dataflow::compute(&liveness, sub, Direction::Backward, start_values)
+}
+
+/// Returns `true` if the function writes the register in a block containing a return instruction
+/// or in a block branching to such a block.
+fn writes_return_register(sub: &Term<Sub>, register: &str) -> bool {
+    let return_blocks: HashSet<&Tid> = sub
+        .term
+        .blocks
+        .iter()
+        .filter(|block| {
+            block
+                .term
+                .jmps
+                .iter()
+                .any(|jmp| matches!(jmp.term, Jmp::Return(_)))
+        })
+        .map(|block| &block.tid)
+        .collect();
+    sub.term.blocks.iter().any(|block| {
+        let leads_to_return = return_blocks.contains(&block.tid)
+            || block.term.jmps.iter().any(|jmp| match &jmp.term {
+                Jmp::Branch(target) => return_blocks.contains(target),
+                _ => false,
+            });
+        leads_to_return
+            && block.term.defs.iter().any(|def| {
+                matches!(&def.term, Def::Assign { var, .. } | Def::Load { var, .. } if var.name == register)
+            })
+    })
+}
+
+/// Get the parameter registers that are written at every call site but never read by the function.
+fn get_dead_parameters<'a>(
+    call_sites: &[CallSite],
+    live_at_entry: &LiveRegisters,
+    cconv: &'a CallingConvention,
+) -> Vec<&'a String> {
+    cconv
+        .parameter_register
+        .iter()
+        .filter(|register| {
+            !live_at_entry.contains(*register)
+                && call_sites
+                    .iter()
+                    .all(|call_site| call_site.written_registers.contains(register))
+        })
+        .collect()
+}
+
+/// Returns `true` if the return register is not live after any of the call sites.
+/// Call sites that do not return are ignored.
+/// If no call site returns, `false` is returned.
+fn is_return_value_ignored(
+    call_sites: &[CallSite],
+    register: &str,
+    live_registers: &HashMap<&Tid, HashMap<Tid, LiveRegisters>>,
+) -> bool {
+    let mut returning_call_sites = call_sites
+        .iter()
+        .filter(|call_site| call_site.return_target.is_some())
+        .peekable();
+    returning_call_sites.peek().is_some()
+        && returning_call_sites.all(|call_site| {
+            live_registers
+                .get(call_site.caller)
+                .and_then(|live_in| live_in.get(call_site.return_target.unwrap()))
+                .map(|live| !live.contains(register))
+                .unwrap_or(false)
+        })
+}
+
+/// Collect the direct calls to each function of the program.
+fn collect_call_sites(program: &Program) -> HashMap<&Tid, Vec<CallSite>> {
+    let mut call_sites: HashMap<&Tid, Vec<CallSite>> = HashMap::new();
+    for sub in program.subs.iter() {
+        for block in sub.term.blocks.iter() {
+            for jmp in block.term.jmps.iter() {
+                if let Jmp::Call { target, return_ } = &jmp.term {
+                    let written_registers = block
+                        .term
+                        .defs
+                        .iter()
+                        .filter_map(|def| match &def.term {
+                            Def::Assign { var, .. } | Def::Load { var, .. } => Some(&var.name),
+                            Def::Store { .. } => None,
+                        })
+                        .collect();
+                    call_sites.entry(target).or_default().push(CallSite {
+                        caller: &sub.tid,
+                        return_target: return_.as_ref(),
+                        written_registers,
+                    });
+                }
+            }
+        }
+    }
+    call_sites
+}
+
+/// Generate the warning for a function with dead parameters.
+fn generate_dead_parameters_warning(
+    sub: &Term<Sub>,
+    dead_parameters: &[&String],
+    number_of_call_sites: usize,
+) -> CweWarning {
+    let registers: Vec<String> = dead_parameters
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Dead Parameter) Function {} never reads the parameters {}, although they are set by all {} callers",
+            sub.term.name,
+            registers.join(", "),
+            number_of_call_sites
+        ),
+    )
+    .severity(Severity::Info)
+    .tids(vec![format!("{}", sub.tid)])
+    .addresses(vec![sub.tid.address.clone()])
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![[vec!["dead_parameters".to_string()], registers].concat()])
+    .remediation(Remediation::new(
+        1164,
+        "Remove the unused parameters or check whether the function signature was recovered correctly.",
+    ))
+}
+
+/// Generate the warning for a function whose return value is ignored by all callers.
+fn generate_ignored_return_warning(
+    sub: &Term<Sub>,
+    register: &str,
+    number_of_call_sites: usize,
+) -> CweWarning {
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Ignored Return Value) The return value of function {} in {} is ignored by all {} callers",
+            sub.term.name, register, number_of_call_sites
+        ),
+    )
+    .severity(Severity::Info)
+    .tids(vec![format!("{}", sub.tid)])
+    .addresses(vec![sub.tid.address.clone()])
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![vec![
+        "ignored_return_value".to_string(),
+        register.to_string(),
+    ]])
+    .remediation(Remediation::new(
+        1164,
+        "Remove the unused return value or check whether the function signature was recovered correctly.",
+    ))
+}
+
+/// Check all directly called functions of the program for dead parameters and ignored return values.
+///
+/// If the cancellation token gets cancelled, the check stops early with the warnings found so far
+/// and returns `true` as its last element.
+fn check_functions(
+    program: &Program,
+    cconv: &CallingConvention,
+    cancellation: &CancellationToken,
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
+    let mut logs = Vec::new();
+    let mut live_registers: HashMap<&Tid, HashMap<Tid, LiveRegisters>> = HashMap::new();
+    for sub in program.subs.iter() {
+        if cancellation.is_cancelled() {
+            return (logs, Vec::new(), true);
+        }
+        match compute_live_registers(sub, cconv) {
+            Ok(live_in) => {
+                live_registers.insert(&sub.tid, live_in);
+            }
+            Err(log) => logs.push(log.source(CWE_MODULE.name)),
+        }
+    }
+    let call_sites = collect_call_sites(program);
+    let mut cwe_warnings = Vec::new();
+    let mut number_of_checked_functions = 0;
+    let mut number_of_functions_with_findings = 0;
+    for sub in program.subs.iter() {
+        if cancellation.is_cancelled() {
+            return (logs, cwe_warnings, true);
+        }
+        let (sub_call_sites, live_at_entry) = match (
+            call_sites.get(&sub.tid),
+            live_registers.get(&sub.tid),
+            sub.term.blocks.first(),
+        ) {
+            (Some(sub_call_sites), Some(live_in), Some(entry_block)) => {
+                (sub_call_sites, &live_in[&entry_block.tid])
+            }
+            _ => continue,
+        };
+        number_of_checked_functions += 1;
+        let number_of_warnings = cwe_warnings.len();
+        let dead_parameters = get_dead_parameters(sub_call_sites, live_at_entry, cconv);
+        if !dead_parameters.is_empty() {
+            cwe_warnings.push(generate_dead_parameters_warning(
+                sub,
+                &dead_parameters,
+                sub_call_sites.len(),
+            ));
+        }
+        if let Some(register) = cconv.return_register.first() {
+            if writes_return_register(sub, register)
+                && is_return_value_ignored(sub_call_sites, register, &live_registers)
+            {
+                cwe_warnings.push(generate_ignored_return_warning(
+                    sub,
+                    register,
+                    sub_call_sites.len(),
+                ));
+            }
+        }
+        if cwe_warnings.len() > number_of_warnings {
+            number_of_functions_with_findings += 1;
+        }
+    }
+    if number_of_checked_functions > 0 {
+        logs.push(
+            LogMessage::new_info(format!(
+                "Signature diagnostics: {} of {} directly called functions have dead parameters or ignored return values. A high ratio hints at an incorrectly recovered calling convention.",
+                number_of_functions_with_findings, number_of_checked_functions
+            ))
+            .source(CWE_MODULE.name),
+        );
+    }
+    (logs, cwe_warnings, false)
+}
+
+/// Execute the check.
+///
+/// Generates a warning for each internal function with parameters that are never read
+/// or with a return value that is ignored by all callers.
+/// If the project has no standard calling convention, nothing is checked.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    _cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
+    let project = analysis_results.project;
+    match project.get_standard_calling_convention() {
+        Some(cconv) => {
+            check_functions(&project.program.term, cconv, &analysis_results.cancellation)
+        }
+        None => (Vec::new(), Vec::new(), false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_cconv() -> CallingConvention {
+        let mut cconv = CallingConvention::mock();
+        cconv.parameter_register = vec!["RDI".to_string(), "RSI".to_string()];
+        cconv
+    }
+
+    /// `func` returns its first parameter and ignores its second parameter.
+    /// `main` sets both parameters and overwrites the return value after the call.
+    fn mock_program(use_return_value: bool) -> Program {
+        let mut func = Sub::mock("func");
+        func.term.blocks = vec![Blk::mock_with_defs_and_jmp(
+            "func_entry",
+            vec![Def::assign(
+                "func_def",
+                Variable::mock("RAX", 8),
+                Expression::var("RDI"),
+            )],
+            Jmp::Return(Expression::var("RA")),
+        )];
+        let mut main = Sub::mock("main");
+        let after_call_def = if use_return_value {
+            Def::store("main_def_3", Expression::var("RSP"), Expression::var("RAX"))
+        } else {
+            Def::assign(
+                "main_def_3",
+                Variable::mock("RAX", 8),
+                Expression::const_from_i64(0),
+            )
+        };
+        main.term.blocks = vec![
+            Blk::mock_with_defs_and_jmp(
+                "main_call",
+                vec![
+                    Def::assign(
+                        "main_def_1",
+                        Variable::mock("RDI", 8),
+                        Expression::const_from_i64(1),
+                    ),
+                    Def::assign(
+                        "main_def_2",
+                        Variable::mock("RSI", 8),
+                        Expression::const_from_i64(2),
+                    ),
+                ],
+                Jmp::Call {
+                    target: Tid::new("func"),
+                    return_: Some(Tid::new("main_after_call")),
+                },
+            ),
+            Blk::mock_with_defs_and_jmp(
+                "main_after_call",
+                vec![after_call_def],
+                Jmp::Return(Expression::var("RA")),
+            ),
+        ];
+        let mut program = Program::mock_empty();
+        program.subs = vec![main, func];
+        program
+    }
+
+    #[test]
+    fn liveness() {
+        let program = mock_program(false);
+        let live_in = compute_live_registers(&program.subs[0], &mock_cconv()).unwrap();
+        assert!(live_in[&Tid::new("main_call")].is_empty());
+        assert!(live_in[&Tid::new("main_after_call")].is_empty());
+        let live_in = compute_live_registers(&program.subs[1], &mock_cconv()).unwrap();
+        assert_eq!(
+            live_in[&Tid::new("func_entry")],
+            vec!["RDI".to_string()]
+                .into_iter()
+                .collect::<LiveRegisters>()
+        );
+    }
+
+    #[test]
+    fn dead_parameters_and_ignored_return_values() {
+        let (logs, warnings, interrupted) = check_functions(
+            &mock_program(false),
+            &mock_cconv(),
+            &CancellationToken::new(),
+        );
+        assert!(!interrupted);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(
+            warnings[0].other,
+            vec![vec!["dead_parameters".to_string(), "RSI".to_string()]]
+        );
+        assert_eq!(
+            warnings[1].other,
+            vec![vec!["ignored_return_value".to_string(), "RAX".to_string()]]
+        );
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].text.starts_with("Signature diagnostics: 1 of 1"));
+
+        let (_, warnings, _) = check_functions(
+            &mock_program(true),
+            &mock_cconv(),
+            &CancellationToken::new(),
+        );
+        assert_eq!(warnings.len(), 1);
+
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+        let (_, warnings, interrupted) =
+            check_functions(&mock_program(false), &mock_cconv(), &cancellation);
+        assert!(interrupted);
+        assert!(warnings.is_empty());
+    }
+}
@@ -0,0 +1,262 @@
+//! This module reports indicators of packed, encrypted or self-modifying code.
+//!
+//! The static analyses of the cwe_checker only see the code contained in the binary file.
+//! If the actual program code is unpacked or decrypted at runtime,
+//! the analyses only see the unpacking stub and silently produce an empty or incomplete report.
+//! The warnings of this module indicate that the results of the other checks are not trustworthy.
+//!
+//! ## How the check works
+//!
+//! - The Shannon entropy of each executable segment is computed.
+//! Segments with an entropy above the configured threshold most likely contain compressed or encrypted code.
+//! - The binary file is scanned for signatures of known packers, e.g. `UPX!`.
+//! - Each basic block is emulated with concrete values (see [`crate::analysis::emulation`])
+//! to find memory writes to constant addresses in executable segments,
+//! which indicate self-modifying code.
+//!
+//! ## False Positives
+//!
+//! - Executable segments containing compressed data (e.g. embedded resources) instead of code.
+//! - Packer signatures contained in strings of the program.
+//!
+//! ## False Negatives
+//!
+//! - Packers without configured signatures, whose stubs produce segments with low entropy.
+//! - Writes to executable memory whose target address is computed outside of the basic block of the write
+//! or memory that is made executable at runtime (e.g. through `mprotect`).
+
+use crate::abstract_domain::BitvectorDomain;
+use crate::analysis::emulation::Emulator;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage, Severity};
+use crate::CweModule;
+
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "Packer",
+    version: "0.1",
+    cwes: &[],
+    description: "Indicators of packed, encrypted or self-modifying code.",
+    required_analyses: &[],
+    config_keys: &["entropy_threshold", "min_segment_size", "signatures"],
+    run: check_cwe,
+    validate_config: Some(crate::utils::config_validation::deserialize_section::<Config>),
+};
+
+/// A signature of a known packer.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Signature {
+    /// The name of the packer.
+    name: String,
+    /// A byte sequence (given as string) contained in binaries packed with the packer.
+    pattern: String,
+}
+
+/// The configuration struct of the check.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Config {
+    /// Executable segments with a higher entropy (in bits per byte) are reported.
+    entropy_threshold: f64,
+    /// Executable segments smaller than this number of bytes are ignored for the entropy check.
+    min_segment_size: usize,
+    /// Signatures of known packers.
+    signatures: Vec<Signature>,
+}
+
+/// Compute the Shannon entropy of the bytes in bits per byte.
+fn compute_entropy(bytes: &[u8]) -> f64 {
+    let mut counts = [0usize; 256];
+    for byte in bytes {
+        counts[*byte as usize] += 1;
+    }
+    let length = bytes.len() as f64;
+    counts
+        .iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let probability = *count as f64 / length;
+            -probability * probability.log2()
+        })
+        .sum()
+}
+
+/// Generate warnings for executable segments with an entropy above the threshold.
+fn check_segment_entropy(analysis_results: &AnalysisResults, config: &Config) -> Vec<CweWarning> {
+    let memory_image = analysis_results.runtime_memory_image;
+    let mut warnings = Vec::new();
+    for (base_address, bytes) in memory_image.iter_segments() {
+        if bytes.len() < config.min_segment_size
+            || !memory_image
+                .is_address_executable(&Bitvector::from_u64(base_address))
+                .unwrap_or(false)
+        {
+            continue;
+        }
+        let entropy = compute_entropy(bytes);
+        if entropy > config.entropy_threshold {
+            let address = format!("{:08x}", base_address);
+            warnings.push(
+                CweWarning::new(
+                    CWE_MODULE.name,
+                    CWE_MODULE.version,
+                    format!(
+                        "(Packed Code) Executable segment at {} has a high entropy of {:.2} bits per byte. It probably contains compressed or encrypted code, so the analysis results may be incomplete.",
+                        address, entropy
+                    ),
+                )
+                .severity(Severity::Info)
+                .addresses(vec![address])
+                .other(vec![
+                    vec!["indicator".to_string(), "entropy".to_string()],
+                    vec!["entropy".to_string(), format!("{:.2}", entropy)],
+                    vec!["segment_size".to_string(), bytes.len().to_string()],
+                ]),
+            );
+        }
+    }
+    warnings
+}
+
+/// Generate a warning for each packer whose signature is contained in the binary file.
+fn check_signatures(binary: &[u8], config: &Config) -> Vec<CweWarning> {
+    let mut warnings = Vec::new();
+    for signature in config.signatures.iter() {
+        let pattern = signature.pattern.as_bytes();
+        if pattern.is_empty() {
+            continue;
+        }
+        if let Some(offset) = binary
+            .windows(pattern.len())
+            .position(|window| window == pattern)
+        {
+            warnings.push(
+                CweWarning::new(
+                    CWE_MODULE.name,
+                    CWE_MODULE.version,
+                    format!(
+                        "(Packed Code) Found signature of the packer {} at file offset {:#x}. The analysis results may be incomplete.",
+                        signature.name, offset
+                    ),
+                )
+                .severity(Severity::Info)
+                .symbols(vec![signature.name.clone()])
+                .other(vec![
+                    vec!["indicator".to_string(), "signature".to_string()],
+                    vec!["file_offset".to_string(), format!("{:#x}", offset)],
+                ]),
+            );
+        }
+    }
+    warnings
+}
+
+/// Generate warnings for writes to constant addresses in executable segments.
+///
+/// On architectures with separate code and data address spaces store instructions cannot modify code,
+/// so no warnings are generated for them.
+fn check_self_modifying_code(analysis_results: &AnalysisResults) -> Vec<CweWarning> {
+    let memory_image = analysis_results.runtime_memory_image;
+    if memory_image.has_separate_address_spaces() {
+        return Vec::new();
+    }
+    let mut warnings = Vec::new();
+    for sub in analysis_results.project.program.term.subs.iter() {
+        for block in sub.term.blocks.iter() {
+            let mut emulator = Emulator::new(analysis_results.project, memory_image);
+            for def in block.term.defs.iter() {
+                if let Def::Store { address, .. } = &def.term {
+                    if let BitvectorDomain::Value(target) = emulator.eval(address) {
+                        if memory_image.is_address_executable(&target).unwrap_or(false) {
+                            warnings.push(generate_self_modification_warning(
+                                sub,
+                                def,
+                                target.try_to_u64().unwrap(),
+                            ));
+                        }
+                    }
+                }
+                if emulator.execute_def(def).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+    warnings
+}
+
+/// Generate the warning for a write to executable memory.
+fn generate_self_modification_warning(
+    sub: &Term<Sub>,
+    store: &Term<Def>,
+    target: u64,
+) -> CweWarning {
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Self-Modifying Code) Write to executable memory at {:08x} in function {} at {}. The analysis results may be incomplete.",
+            target, sub.term.name, store.tid.address
+        ),
+    )
+    .severity(Severity::Info)
+    .tids(vec![format!("{}", store.tid)])
+    .addresses(vec![store.tid.address.clone()])
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![
+        vec!["indicator".to_string(), "self_modification".to_string()],
+        vec!["target_address".to_string(), format!("{:08x}", target)],
+    ])
+}
+
+/// Run the check.
+///
+/// Reports executable segments with high entropy, signatures of known packers
+/// and writes to executable memory.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>, bool) {
+    let config: Config = serde_json::from_value(cwe_params.clone()).unwrap();
+    let mut warnings = check_segment_entropy(analysis_results, &config);
+    warnings.append(&mut check_signatures(analysis_results.binary, &config));
+    warnings.append(&mut check_self_modifying_code(analysis_results));
+    (Vec::new(), warnings, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entropy() {
+        assert!(compute_entropy(&[0u8; 64]).abs() < 1e-9);
+        let all_bytes: Vec<u8> = (0..=255u8).collect();
+        assert!((compute_entropy(&all_bytes) - 8.0).abs() < 1e-9);
+        assert!((compute_entropy(b"abab") - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn signature_scan() {
+        let config = Config {
+            entropy_threshold: 7.2,
+            min_segment_size: 512,
+            signatures: vec![
+                Signature {
+                    name: "UPX".to_string(),
+                    pattern: "UPX!".to_string(),
+                },
+                Signature {
+                    name: "MPRESS".to_string(),
+                    pattern: "MPRESS1".to_string(),
+                },
+            ],
+        };
+        let warnings = check_signatures(b"\x7fELF\0\0UPX!\0\0", &config);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].symbols, vec!["UPX".to_string()]);
+        assert_eq!(
+            warnings[0].other[1],
+            vec!["file_offset".to_string(), "0x6".to_string()]
+        );
+    }
+}
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use super::Variable;
 use super::{ByteSize, Def};
+use crate::utils::perf_stats::{self, Counter};
 use crate::{pcode::RegisterProperties, prelude::*};
 
 mod builder;
@@ -163,6 +164,21 @@ impl Expression {
         }
     }
 
+    /// Return all variables read by the expression.
+    pub fn input_vars(&self) -> Vec<&Variable> {
+        use Expression::*;
+        match self {
+            Var(var) => vec![var],
+            Const(_) | Unknown { .. } => Vec::new(),
+            BinOp { lhs, rhs, .. } => {
+                let mut vars = lhs.input_vars();
+                vars.append(&mut rhs.input_vars());
+                vars
+            }
+            UnOp { arg, .. } | Cast { arg, .. } | Subpiece { arg, .. } => arg.input_vars(),
+        }
+    }
+
     /// This function checks for sub registers in pcode instruction and casts them into
     /// SUBPIECE expressions with the base register as argument. It also checks whether
     /// the given Term<Def> has a output sub register and if so, casts it into its
@@ -283,6 +299,7 @@ impl Expression {
         lsb: ByteSize,
         register_map: &HashMap<&String, &RegisterProperties>,
     ) {
+        perf_stats::increment(Counter::ExpressionAllocations);
         *self = Expression::Subpiece {
             low_byte: lsb,
             size,
@@ -307,6 +324,7 @@ impl Expression {
         sub_register: Option<&RegisterProperties>,
     ) {
         if zero_extend.is_some() {
+            perf_stats::increment(Counter::ExpressionAllocations);
             *self = Expression::Cast {
                 op: CastOpType::IntZExt,
                 size: output_size.unwrap(),
@@ -341,6 +359,7 @@ impl Expression {
 
         // Build PIECE as PIECE(lhs:PIECE(lhs:higher subpiece, rhs:sub register), rhs:lower subpiece)
         if sub_register.lsb > ByteSize::new(0) {
+            perf_stats::add(Counter::ExpressionAllocations, 6);
             *self = Expression::BinOp {
                 op: BinOpType::Piece,
                 lhs: Box::new(Expression::BinOp {
@@ -361,6 +380,7 @@ impl Expression {
         }
         // Build PIECE as PIECE(lhs: high subpiece, rhs: sub register)
         else {
+            perf_stats::add(Counter::ExpressionAllocations, 3);
             *self = Expression::BinOp {
                 op: BinOpType::Piece,
                 lhs: Box::new(Expression::Subpiece {
@@ -6,6 +6,7 @@ use super::{CastOpType, Variable};
 
 use super::{BinOpType, Expression};
 use crate::prelude::*;
+use crate::utils::perf_stats::{self, Counter};
 
 /// ## Helper functions for building expressions
 impl Expression {
@@ -53,6 +54,7 @@ impl Expression {
 
     /// Shortcut for creating an `IntAdd`-expression
     pub fn plus(self, rhs: Expression) -> Expression {
+        perf_stats::add(Counter::ExpressionAllocations, 2);
         Expression::BinOp {
             lhs: Box::new(self),
             op: BinOpType::IntAdd,
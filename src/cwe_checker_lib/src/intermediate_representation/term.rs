@@ -1,10 +1,16 @@
 use super::{ByteSize, CastOpType, Expression, Variable};
 use crate::prelude::*;
+use crate::utils::artifact::VersionedArtifact;
 use crate::utils::log::LogMessage;
 use std::collections::HashSet;
 
 pub mod builder;
 
+mod inlining;
+pub use inlining::WrapperInliningConfig;
+mod normalization;
+pub use normalization::*;
+
 /// A term identifier consisting of an ID string (which is required to be unique)
 /// and an address to indicate where the term is located.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
@@ -365,6 +371,11 @@ pub struct Project {
     pub calling_conventions: Vec<CallingConvention>,
 }
 
+impl VersionedArtifact for Project {
+    const KIND: &'static str = "ir_project";
+    const VERSION: u32 = 1;
+}
+
 impl Project {
     /// Return the size (in bytes) for pointers of the given architecture.
     pub fn get_pointer_bytesize(&self) -> ByteSize {
@@ -466,17 +477,6 @@ impl Project {
         }
         log_messages
     }
-
-    /// Run some normalization passes over the project.
-    ///
-    /// Passes:
-    /// - Replace trivial expressions like `a XOR a` with their result.
-    /// - Replace jumps to nonexisting TIDs with jumps to an artificial sink target in the CFG.
-    #[must_use]
-    pub fn normalize(&mut self) -> Vec<LogMessage> {
-        self.substitute_trivial_expressions();
-        self.remove_references_to_nonexisting_tids()
-    }
 }
 
 #[cfg(test)]
@@ -495,6 +495,27 @@ mod tests {
                 },
             }
         }
+
+        /// Mock a block without definitions ending in the given jump.
+        /// The TID of the jump is the block name with a `_jmp` suffix.
+        pub fn mock_with_jmp(name: &str, jmp: Jmp) -> Term<Blk> {
+            Blk::mock_with_defs_and_jmp(name, Vec::new(), jmp)
+        }
+
+        /// Mock a block with the given definitions ending in the given jump.
+        /// The TID of the jump is the block name with a `_jmp` suffix.
+        pub fn mock_with_defs_and_jmp(name: &str, defs: Vec<Term<Def>>, jmp: Jmp) -> Term<Blk> {
+            Term {
+                tid: Tid::new(name),
+                term: Blk {
+                    defs,
+                    jmps: vec![Term {
+                        tid: Tid::new(format!("{}_jmp", name)),
+                        term: jmp,
+                    }],
+                },
+            }
+        }
     }
 
     impl Sub {
@@ -549,6 +570,14 @@ mod tests {
                 no_return: false,
             }
         }
+
+        pub fn mock_with_name(name: &str) -> ExternSymbol {
+            ExternSymbol {
+                tid: Tid::new(name),
+                name: name.to_string(),
+                ..ExternSymbol::mock()
+            }
+        }
     }
 
     impl Project {
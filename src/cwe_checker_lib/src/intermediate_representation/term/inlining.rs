@@ -0,0 +1,307 @@
+//! Inlining of trivial wrapper functions into their callers.
+//!
+//! Vendor SDKs often wrap extern functions into thin forwarding functions.
+//! To see through them, the interprocedural analyses would need an additional level of context sensitivity.
+//! The `inline_wrapper_functions` normalization pass instead replaces calls to such wrappers with the body of the wrapper,
+//! so that the callers call the extern function directly.
+//!
+//! A function is considered a wrapper if it contains at most `max_defs` definitions and either
+//! - consists of a single block ending with a tail call to an extern symbol or
+//! - consists of a block ending with a call to an extern symbol and a second block returning to the caller.
+//!
+//! The wrapper functions themselves are kept, since they may also be called indirectly.
+
+use super::{Blk, Def, Jmp, Project, Sub, Term, Tid};
+use crate::prelude::*;
+use crate::utils::log::LogMessage;
+use std::collections::{HashMap, HashSet};
+
+/// The configuration of the inlining of wrapper functions.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(default)]
+pub struct WrapperInliningConfig {
+    /// Functions with more definitions (i.e. assignments, loads and stores) are not inlined.
+    pub max_defs: usize,
+}
+
+impl Default for WrapperInliningConfig {
+    fn default() -> Self {
+        WrapperInliningConfig { max_defs: 8 }
+    }
+}
+
+/// The body of a wrapper function.
+struct Wrapper {
+    /// The definitions executed before the call to the extern symbol.
+    defs: Vec<Term<Def>>,
+    /// The TID of the called extern symbol.
+    target: Tid,
+    /// The block returning to the caller.
+    /// It is `None` if the extern symbol is tail-called.
+    return_block: Option<Term<Blk>>,
+}
+
+/// Return the body of the function if it is a wrapper around a call to an extern symbol
+/// with at most `max_defs` definitions.
+fn get_wrapper(sub: &Term<Sub>, extern_tids: &HashSet<&Tid>, max_defs: usize) -> Option<Wrapper> {
+    let num_defs: usize = sub
+        .term
+        .blocks
+        .iter()
+        .map(|block| block.term.defs.len())
+        .sum();
+    if num_defs > max_defs {
+        return None;
+    }
+    match &sub.term.blocks[..] {
+        [call_block] => match &call_block.term.jmps[..] {
+            [Term {
+                term:
+                    Jmp::Call {
+                        target,
+                        return_: None,
+                    },
+                ..
+            }] if extern_tids.contains(target) => Some(Wrapper {
+                defs: call_block.term.defs.clone(),
+                target: target.clone(),
+                return_block: None,
+            }),
+            _ => None,
+        },
+        [call_block, return_block] => {
+            match (&call_block.term.jmps[..], &return_block.term.jmps[..]) {
+                (
+                    [Term {
+                        term:
+                            Jmp::Call {
+                                target,
+                                return_: Some(return_tid),
+                            },
+                        ..
+                    }],
+                    [Term {
+                        term: Jmp::Return(_),
+                        ..
+                    }],
+                ) if extern_tids.contains(target) && *return_tid == return_block.tid => {
+                    Some(Wrapper {
+                        defs: call_block.term.defs.clone(),
+                        target: target.clone(),
+                        return_block: Some(return_block.clone()),
+                    })
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Copy the definitions with the given suffix added to their TIDs,
+/// so that the TIDs of the copies are unique.
+fn copy_defs(defs: &[Term<Def>], suffix: &str) -> Vec<Term<Def>> {
+    defs.iter()
+        .map(|def| Term {
+            tid: def.tid.clone().with_id_suffix(suffix),
+            term: def.term.clone(),
+        })
+        .collect()
+}
+
+impl Project {
+    /// Replace calls to wrapper functions around extern symbols with the body of the wrapper.
+    /// Returns a log message with the number of inlined calls.
+    ///
+    /// The definitions of the wrapper are appended to the calling block, which then calls the extern symbol directly.
+    /// If the wrapper returns to its caller in a separate block,
+    /// a copy of this block is added to the calling function, which jumps to the original return target.
+    #[must_use]
+    pub(super) fn inline_wrapper_functions(
+        &mut self,
+        config: &WrapperInliningConfig,
+    ) -> Vec<LogMessage> {
+        let extern_tids: HashSet<&Tid> = self
+            .program
+            .term
+            .extern_symbols
+            .iter()
+            .map(|symbol| &symbol.tid)
+            .collect();
+        let wrappers: HashMap<Tid, Wrapper> = self
+            .program
+            .term
+            .subs
+            .iter()
+            .filter_map(|sub| {
+                get_wrapper(sub, &extern_tids, config.max_defs)
+                    .map(|wrapper| (sub.tid.clone(), wrapper))
+            })
+            .collect();
+        let mut num_inlined_calls = 0;
+        let mut inlined_wrappers = HashSet::new();
+        for sub in self.program.term.subs.iter_mut() {
+            let mut return_blocks = Vec::new();
+            for block in sub.term.blocks.iter_mut() {
+                let (call_tid, wrapper_tid, return_target) = match &block.term.jmps[..] {
+                    [Term {
+                        tid,
+                        term:
+                            Jmp::Call {
+                                target,
+                                return_: Some(return_target),
+                            },
+                    }] if wrappers.contains_key(target) => {
+                        (tid.clone(), target.clone(), return_target.clone())
+                    }
+                    _ => continue,
+                };
+                let wrapper = &wrappers[&wrapper_tid];
+                let suffix = format!("_inlined_at_{}", call_tid);
+                block
+                    .term
+                    .defs
+                    .append(&mut copy_defs(&wrapper.defs, &suffix));
+                let return_ = match &wrapper.return_block {
+                    None => return_target,
+                    Some(return_block) => {
+                        let return_block_copy = Term {
+                            tid: return_block.tid.clone().with_id_suffix(&suffix),
+                            term: Blk {
+                                defs: copy_defs(&return_block.term.defs, &suffix),
+                                jmps: vec![Term {
+                                    tid: return_block.term.jmps[0]
+                                        .tid
+                                        .clone()
+                                        .with_id_suffix(&suffix),
+                                    term: Jmp::Branch(return_target),
+                                }],
+                            },
+                        };
+                        let return_block_tid = return_block_copy.tid.clone();
+                        return_blocks.push(return_block_copy);
+                        return_block_tid
+                    }
+                };
+                block.term.jmps[0].term = Jmp::Call {
+                    target: wrapper.target.clone(),
+                    return_: Some(return_),
+                };
+                num_inlined_calls += 1;
+                inlined_wrappers.insert(wrapper_tid);
+            }
+            sub.term.blocks.append(&mut return_blocks);
+        }
+        if num_inlined_calls > 0 {
+            vec![LogMessage::new_info(format!(
+                "Inlined {} calls to {} wrapper functions",
+                num_inlined_calls,
+                inlined_wrappers.len()
+            ))]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intermediate_representation::*;
+
+    fn call(target: &str, return_: Option<&str>) -> Jmp {
+        Jmp::Call {
+            target: Tid::new(target),
+            return_: return_.map(Tid::new),
+        }
+    }
+
+    fn mock_project() -> Project {
+        let mut project = Project::mock_empty();
+        project
+            .program
+            .term
+            .extern_symbols
+            .push(ExternSymbol::mock());
+        let def = Def::assign(
+            "def_wrapper",
+            Variable::mock("RDI", 8),
+            Expression::var("RSI"),
+        );
+        // A wrapper tail-calling the extern symbol
+        let mut tail_wrapper = Sub::mock("tail_wrapper");
+        tail_wrapper.term.blocks.push(Blk::mock_with_defs_and_jmp(
+            "blk_tail_wrapper",
+            vec![def.clone()],
+            call("mock_symbol", None),
+        ));
+        // A wrapper calling the extern symbol and returning afterwards
+        let mut wrapper = Sub::mock("wrapper");
+        wrapper.term.blocks.push(Blk::mock_with_defs_and_jmp(
+            "blk_wrapper",
+            vec![def.clone()],
+            call("mock_symbol", Some("blk_wrapper_return")),
+        ));
+        wrapper.term.blocks.push(Blk::mock_with_defs_and_jmp(
+            "blk_wrapper_return",
+            Vec::new(),
+            Jmp::Return(Expression::var("RSP")),
+        ));
+        let mut caller = Sub::mock("caller");
+        caller.term.blocks.push(Blk::mock_with_defs_and_jmp(
+            "blk_caller_1",
+            Vec::new(),
+            call("tail_wrapper", Some("blk_caller_2")),
+        ));
+        caller.term.blocks.push(Blk::mock_with_defs_and_jmp(
+            "blk_caller_2",
+            Vec::new(),
+            call("wrapper", Some("blk_caller_3")),
+        ));
+        caller.term.blocks.push(Blk::mock_with_defs_and_jmp(
+            "blk_caller_3",
+            Vec::new(),
+            Jmp::Return(Expression::var("RSP")),
+        ));
+        project.program.term.subs = vec![tail_wrapper, wrapper, caller];
+        project
+    }
+
+    #[test]
+    fn inline_wrappers() {
+        let mut project = mock_project();
+        let logs = project.inline_wrapper_functions(&WrapperInliningConfig { max_defs: 0 });
+        assert!(logs.is_empty());
+        assert_eq!(project, mock_project());
+
+        let logs = project.inline_wrapper_functions(&WrapperInliningConfig::default());
+        assert_eq!(logs.len(), 1);
+        let caller = &project.program.term.subs[2];
+        assert_eq!(caller.term.blocks.len(), 4);
+        assert_eq!(
+            caller.term.blocks[0].term.defs[0].tid,
+            Tid::new("def_wrapper_inlined_at_blk_caller_1_jmp")
+        );
+        assert_eq!(
+            caller.term.blocks[0].term.jmps[0].term,
+            call("mock_symbol", Some("blk_caller_2"))
+        );
+        assert_eq!(caller.term.blocks[1].term.defs.len(), 1);
+        assert_eq!(
+            caller.term.blocks[1].term.jmps[0].term,
+            call(
+                "mock_symbol",
+                Some("blk_wrapper_return_inlined_at_blk_caller_2_jmp")
+            )
+        );
+        assert_eq!(
+            caller.term.blocks[3].term.jmps[0].term,
+            Jmp::Branch(Tid::new("blk_caller_3"))
+        );
+        // The wrappers themselves are kept.
+        assert_eq!(
+            project.program.term.subs[0],
+            mock_project().program.term.subs[0]
+        );
+    }
+}
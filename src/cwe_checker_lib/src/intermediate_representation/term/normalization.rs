@@ -0,0 +1,249 @@
+//! The normalization passes run over a project before the analyses.
+//!
+//! Each pass has a unique name, under which it can be enabled or disabled in the configuration.
+//! The passes are run in the order of [`NORMALIZATION_PASSES`].
+//! Passes that later analyses rely on (e.g. for the construction of the control flow graph)
+//! are marked as required and cannot be disabled.
+//! Optional passes that change the structure of the program (e.g. by inlining functions)
+//! are disabled by default and only run if they are enabled in the configuration.
+
+use super::{Project, WrapperInliningConfig};
+use crate::prelude::*;
+use crate::utils::log::LogMessage;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// A normalization pass over the project.
+#[derive(Clone, Copy)]
+pub struct NormalizationPass {
+    /// The name of the pass used in the configuration.
+    pub name: &'static str,
+    /// A short description of the pass.
+    pub description: &'static str,
+    /// If set, the pass cannot be disabled, since later analyses rely on it.
+    pub required: bool,
+    /// If not set, the pass is only run if it is enabled in the configuration.
+    pub enabled_by_default: bool,
+    /// The function running the pass with the given configuration.
+    /// It returns log messages generated by the pass.
+    run: fn(&mut Project, &NormalizationConfig) -> Vec<LogMessage>,
+}
+
+impl std::fmt::Debug for NormalizationPass {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "NormalizationPass({})", self.name)
+    }
+}
+
+/// All normalization passes in the order in which they are run.
+pub static NORMALIZATION_PASSES: &[NormalizationPass] = &[
+    NormalizationPass {
+        name: "substitute_trivial_expressions",
+        description: "Replace trivial expressions like `a XOR a` with their result.",
+        required: false,
+        enabled_by_default: true,
+        run: |project, _| {
+            project.substitute_trivial_expressions();
+            Vec::new()
+        },
+    },
+    NormalizationPass {
+        name: "retarget_nonexisting_jumps",
+        description:
+            "Replace jumps to nonexisting TIDs with jumps to an artificial sink target in the CFG.",
+        required: true,
+        enabled_by_default: true,
+        run: |project, _| project.remove_references_to_nonexisting_tids(),
+    },
+    NormalizationPass {
+        name: "inline_wrapper_functions",
+        description:
+            "Inline small wrapper functions around calls to extern symbols into their callers.",
+        required: false,
+        enabled_by_default: false,
+        run: |project, config| project.inline_wrapper_functions(&config.wrapper_inlining),
+    },
+];
+
+/// The configuration of the normalization passes.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct NormalizationConfig {
+    /// Enable flags for the passes indexed by the pass names.
+    /// Passes missing from the map are enabled if they are enabled by default.
+    #[serde(default)]
+    pub passes: BTreeMap<String, bool>,
+    /// The configuration of the `inline_wrapper_functions` pass.
+    #[serde(default)]
+    pub wrapper_inlining: WrapperInliningConfig,
+}
+
+impl NormalizationConfig {
+    /// Returns `true` if the pass should be run according to the configuration.
+    pub fn is_enabled(&self, pass: &NormalizationPass) -> bool {
+        pass.required
+            || self
+                .passes
+                .get(pass.name)
+                .cloned()
+                .unwrap_or(pass.enabled_by_default)
+    }
+}
+
+/// Statistics on a single run of a normalization pass.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PassStatistics {
+    /// The name of the pass.
+    pub name: &'static str,
+    /// Whether the pass was run.
+    pub enabled: bool,
+    /// The time needed to run the pass.
+    pub duration: Duration,
+    /// The number of log messages generated by the pass.
+    pub num_log_messages: usize,
+}
+
+impl std::fmt::Display for PassStatistics {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.enabled {
+            write!(
+                formatter,
+                "{}: {} ms, {} log messages",
+                self.name,
+                self.duration.as_millis(),
+                self.num_log_messages
+            )
+        } else {
+            write!(formatter, "{}: disabled", self.name)
+        }
+    }
+}
+
+impl Project {
+    /// Run all normalization passes over the project.
+    ///
+    /// Passes:
+    /// - Replace trivial expressions like `a XOR a` with their result.
+    /// - Replace jumps to nonexisting TIDs with jumps to an artificial sink target in the CFG.
+    #[must_use]
+    pub fn normalize(&mut self) -> Vec<LogMessage> {
+        self.normalize_with_config(&NormalizationConfig::default())
+            .0
+    }
+
+    /// Run the normalization passes enabled in the configuration over the project.
+    /// Returns the log messages generated by the passes and statistics on each pass.
+    ///
+    /// Unknown pass names in the configuration and attempts to disable required passes
+    /// generate log messages.
+    #[must_use]
+    pub fn normalize_with_config(
+        &mut self,
+        config: &NormalizationConfig,
+    ) -> (Vec<LogMessage>, Vec<PassStatistics>) {
+        let mut logs = Vec::new();
+        for (name, enabled) in config.passes.iter() {
+            match NORMALIZATION_PASSES.iter().find(|pass| pass.name == name) {
+                None => logs.push(LogMessage::new_info(format!(
+                    "Unknown normalization pass {} in configuration",
+                    name
+                ))),
+                Some(pass) if pass.required && !enabled => {
+                    logs.push(LogMessage::new_info(format!(
+                        "Normalization pass {} is required and cannot be disabled",
+                        name
+                    )))
+                }
+                Some(_) => (),
+            }
+        }
+        let mut statistics = Vec::new();
+        for pass in NORMALIZATION_PASSES.iter() {
+            if !config.is_enabled(pass) {
+                statistics.push(PassStatistics {
+                    name: pass.name,
+                    enabled: false,
+                    duration: Duration::default(),
+                    num_log_messages: 0,
+                });
+                continue;
+            }
+            let start = Instant::now();
+            let mut pass_logs = (pass.run)(self, config);
+            statistics.push(PassStatistics {
+                name: pass.name,
+                enabled: true,
+                duration: start.elapsed(),
+                num_log_messages: pass_logs.len(),
+            });
+            logs.append(&mut pass_logs);
+        }
+        (logs, statistics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intermediate_representation::*;
+
+    fn mock_project() -> Project {
+        let mut project = Project::mock_empty();
+        let mut block = Blk::mock();
+        block.term.defs.push(Term {
+            tid: Tid::new("def"),
+            term: Def::Assign {
+                var: Variable::mock("RAX", 8u64),
+                value: Expression::BinOp {
+                    op: BinOpType::IntXOr,
+                    lhs: Box::new(Expression::var("RBX")),
+                    rhs: Box::new(Expression::var("RBX")),
+                },
+            },
+        });
+        block.term.jmps.push(Term {
+            tid: Tid::new("jmp"),
+            term: Jmp::Branch(Tid::new("nonexisting")),
+        });
+        let mut sub = Sub::mock("func");
+        sub.term.blocks.push(block);
+        project.program.term.subs.push(sub);
+        project
+    }
+
+    #[test]
+    fn pass_configuration() {
+        let mut project = mock_project();
+        let mut config = NormalizationConfig::default();
+        config
+            .passes
+            .insert("substitute_trivial_expressions".to_string(), false);
+        config
+            .passes
+            .insert("retarget_nonexisting_jumps".to_string(), false);
+        config.passes.insert("unknown".to_string(), true);
+        let (logs, statistics) = project.normalize_with_config(&config);
+        // Two configuration log messages and one message for the retargeted jump.
+        assert_eq!(logs.len(), 3);
+        assert_eq!(statistics.len(), NORMALIZATION_PASSES.len());
+        assert!(!statistics[0].enabled);
+        assert!(statistics[1].enabled);
+        assert_eq!(statistics[1].num_log_messages, 1);
+        assert_eq!(
+            format!("{}", statistics[0]),
+            "substitute_trivial_expressions: disabled"
+        );
+        // The trivial expression was not substituted.
+        match &project.program.term.subs[0].term.blocks[0].term.defs[0].term {
+            Def::Assign { value, .. } => assert!(matches!(value, Expression::BinOp { .. })),
+            _ => panic!(),
+        }
+
+        let mut project = mock_project();
+        let logs = project.normalize();
+        assert_eq!(logs.len(), 1);
+        match &project.program.term.subs[0].term.blocks[0].term.defs[0].term {
+            Def::Assign { value, .. } => assert!(matches!(value, Expression::Const(_))),
+            _ => panic!(),
+        }
+    }
+}
@@ -4,11 +4,16 @@
 Parts of the cwe_checker that are written in Rust.
 */
 
+use crate::abstract_domain::AbstractIdentifier;
 use crate::analysis::graph::Graph;
-use crate::analysis::pointer_inference::PointerInference;
+use crate::analysis::pointer_inference::allocation_sizes::AllocationSize;
+use crate::analysis::pointer_inference::{PointerInference, RunOptions};
 use crate::intermediate_representation::Project;
 use crate::utils::binary::RuntimeMemoryImage;
+use crate::utils::cancellation::CancellationToken;
 use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::resource_budget::ResourceBudget;
+use std::time::{Duration, Instant};
 
 pub mod abstract_domain;
 pub mod analysis;
@@ -27,16 +32,85 @@ mod prelude {
     pub use anyhow::{anyhow, Error};
 }
 
-/// The generic function signature for the main function of a CWE module
+/// The generic function signature for the main function of a CWE module.
+///
+/// Besides the log messages and CWE warnings the function returns whether the module was interrupted,
+/// i.e. whether it stopped early because the cancellation token in the [`AnalysisResults`] was cancelled.
+/// The results of an interrupted module may be incomplete.
 pub type CweModuleFn =
-    fn(&AnalysisResults, &serde_json::Value) -> (Vec<LogMessage>, Vec<CweWarning>);
+    fn(&AnalysisResults, &serde_json::Value) -> (Vec<LogMessage>, Vec<CweWarning>, bool);
+
+/// The function signature for validating the configuration section of a CWE module.
+///
+/// The function returns an error if the section cannot be deserialized into the configuration type of the module.
+pub type ConfigValidationFn = fn(&serde_json::Value) -> Result<(), serde_json::Error>;
+
+/// Analyses whose results a CWE module requires besides the control flow graph.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum RequiredAnalysis {
+    /// The [pointer inference analysis](crate::analysis::pointer_inference).
+    PointerInference,
+}
+
+impl std::fmt::Display for RequiredAnalysis {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RequiredAnalysis::PointerInference => write!(formatter, "pointer inference"),
+        }
+    }
+}
 
 /// A structure containing general information about a CWE analysis module,
 /// including the function to be called to run the analysis.
 pub struct CweModule {
     pub name: &'static str,
     pub version: &'static str,
+    /// The numbers of the CWEs that the module reports.
+    /// Empty for modules that do not check for specific CWEs.
+    pub cwes: &'static [u32],
+    /// A one-line description of what the module checks.
+    pub description: &'static str,
+    /// The analyses that have to be computed before the module can run.
+    pub required_analyses: &'static [RequiredAnalysis],
+    /// The keys that the module reads from its section of the configuration file.
+    pub config_keys: &'static [&'static str],
     pub run: CweModuleFn,
+    /// The function validating the configuration section of the module before the analysis.
+    /// `None` for modules without a configuration type.
+    pub validate_config: Option<ConfigValidationFn>,
+}
+
+impl CweModule {
+    /// Return whether the module requires the results of the given analysis.
+    pub fn requires(&self, analysis: RequiredAnalysis) -> bool {
+        self.required_analyses.contains(&analysis)
+    }
+
+    /// Get a description of the module on multiple lines,
+    /// containing its name, version, the checked CWEs, the required analyses and the configuration keys.
+    pub fn get_info(&self) -> String {
+        let join = |items: Vec<String>| {
+            if items.is_empty() {
+                "-".to_string()
+            } else {
+                items.join(", ")
+            }
+        };
+        format!(
+            "{} ({})\n  {}\n  CWEs: {}\n  Required analyses: {}\n  Configuration keys: {}",
+            self.name,
+            self.version,
+            self.description,
+            join(self.cwes.iter().map(|cwe| format!("CWE-{}", cwe)).collect()),
+            join(
+                self.required_analyses
+                    .iter()
+                    .map(|analysis| analysis.to_string())
+                    .collect()
+            ),
+            join(self.config_keys.iter().map(|key| key.to_string()).collect()),
+        )
+    }
 }
 
 impl std::fmt::Display for CweModule {
@@ -53,21 +127,127 @@ pub fn get_modules() -> Vec<&'static CweModule> {
         &crate::checkers::cwe_190::CWE_MODULE,
         &crate::checkers::cwe_215::CWE_MODULE,
         &crate::checkers::cwe_243::CWE_MODULE,
+        &crate::checkers::cwe_321::CWE_MODULE,
         &crate::checkers::cwe_332::CWE_MODULE,
         &crate::checkers::cwe_367::CWE_MODULE,
         &crate::checkers::cwe_426::CWE_MODULE,
         &crate::checkers::cwe_467::CWE_MODULE,
         &crate::checkers::cwe_476::CWE_MODULE,
         &crate::checkers::cwe_560::CWE_MODULE,
+        &crate::checkers::cwe_563::CWE_MODULE,
+        &crate::checkers::cwe_667::CWE_MODULE,
+        &crate::checkers::cwe_672::CWE_MODULE,
         &crate::checkers::cwe_676::CWE_MODULE,
+        &crate::checkers::cwe_686::CWE_MODULE,
         &crate::checkers::cwe_782::CWE_MODULE,
+        &crate::checkers::cwe_1327::CWE_MODULE,
+        &crate::checkers::cwe_1395::CWE_MODULE,
+        &crate::checkers::call_policy::CWE_MODULE,
+        &crate::checkers::dead_parameters::CWE_MODULE,
+        &crate::checkers::packer::CWE_MODULE,
+        &crate::checkers::analysis_gaps::CWE_MODULE,
         &crate::analysis::pointer_inference::CWE_MODULE,
     ]
 }
 
+/// Run the given modules and collect their log messages and CWE warnings.
+///
+/// The configuration of each module is taken from the entry with the module name in `config`.
+/// If the cancellation token gets cancelled, the remaining modules are skipped.
+/// The results of the modules that already finished are still returned,
+/// together with log messages on the skipped modules.
+///
+/// If `module_timeout` is set, each module is stopped after the given time
+/// and returns the results it found so far.
+/// The same holds for the module running when the deadline of the cancellation token expires.
+/// Only modules running fixpoint computations check for timeouts, all other modules always run to completion.
+pub fn run_modules(
+    modules: &[&CweModule],
+    analysis_results: &AnalysisResults,
+    config: &serde_json::Value,
+    cancellation: &CancellationToken,
+    module_timeout: Option<Duration>,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let mut all_logs = Vec::new();
+    let mut all_cwes = Vec::new();
+    run_modules_with_callback(
+        modules,
+        analysis_results,
+        config,
+        cancellation,
+        module_timeout,
+        |_module, mut logs, mut cwes| {
+            all_logs.append(&mut logs);
+            all_cwes.append(&mut cwes);
+        },
+    );
+    (all_logs, all_cwes)
+}
+
+/// Run the given modules and pass the log messages and CWE warnings of each module to the callback
+/// as soon as the module finishes.
+///
+/// This allows to report the results of long-running analyses incrementally instead of only at the end.
+/// Like for [`run_modules`], the remaining modules are skipped if the cancellation token gets cancelled.
+/// For each skipped module a log message is passed to the callback together with an empty list of warnings.
+/// Modules that were stopped because of a timeout pass their partial results together with a log message on the timeout.
+pub fn run_modules_with_callback<F>(
+    modules: &[&CweModule],
+    analysis_results: &AnalysisResults,
+    config: &serde_json::Value,
+    cancellation: &CancellationToken,
+    module_timeout: Option<Duration>,
+    mut on_module_finished: F,
+) where
+    F: FnMut(&CweModule, Vec<LogMessage>, Vec<CweWarning>),
+{
+    for module in modules {
+        if cancellation.is_cancelled() {
+            let log = LogMessage::new_info(format!(
+                "Skipped module {} since the analysis was stopped early.",
+                module.name
+            ));
+            on_module_finished(module, vec![log], Vec::new());
+            continue;
+        }
+        let module_cancellation = match module_timeout {
+            Some(timeout) => cancellation.child_with_deadline(Instant::now() + timeout),
+            None => cancellation.child(),
+        };
+        let module_analysis_results = analysis_results
+            .clone()
+            .set_cancellation_token(module_cancellation.clone());
+        let (mut logs, cwes, interrupted) =
+            (module.run)(&module_analysis_results, &config[&module.name]);
+        if interrupted {
+            logs.push(
+                LogMessage::new_info(format!(
+                    "Timeout: Module {} was stopped before it finished. Its results may be incomplete.",
+                    module.name
+                ))
+                .source(module.name),
+            );
+        }
+        on_module_finished(module, logs, cwes);
+    }
+}
+
 /// A struct containing pointers to all known analysis results
 /// that may be needed as input for other analyses and CWE checks.
-#[derive(Clone, Copy)]
+///
+/// The struct is `Send + Sync`, so that programs embedding the library can share the results
+/// between threads through a clone of it instead of cloning the project for each thread.
+/// Note that [`run_modules`] itself still runs the modules sequentially.
+/// All referenced results are immutable once computed.
+/// Their internally shared data (e.g. abstract identifiers and memory objects) is wrapped in `Arc`s
+/// and only modified through copy-on-write,
+/// and log messages are collected through channels.
+/// Thus no further synchronization is needed.
+///
+/// The only mutable global state of the library are the counters of [`utils::perf_stats`].
+/// They are atomic, so that they can be incremented from several threads,
+/// but they count the operations of all threads together.
+#[derive(Clone)]
 pub struct AnalysisResults<'a> {
     /// The content of the binary file
     pub binary: &'a [u8],
@@ -79,6 +259,15 @@ pub struct AnalysisResults<'a> {
     pub project: &'a Project,
     /// The result of the pointer inference analysis if already computed.
     pub pointer_inference: Option<&'a PointerInference<'a>>,
+    /// The cancellation token of the currently running module.
+    /// Once it is cancelled (or its deadline expired) the module should stop its computations
+    /// and return the results found so far.
+    pub cancellation: CancellationToken,
+    /// The resource budget within which expensive modules run their computations.
+    pub budget: ResourceBudget,
+    /// If set, long-running modules send their CWE warnings through this channel as soon as they find them,
+    /// see [`AnalysisResults::report_warning`].
+    pub warning_sender: Option<crossbeam_channel::Sender<CweWarning>>,
 }
 
 impl<'a> AnalysisResults<'a> {
@@ -95,17 +284,27 @@ impl<'a> AnalysisResults<'a> {
             control_flow_graph,
             project,
             pointer_inference: None,
+            cancellation: CancellationToken::new(),
+            budget: ResourceBudget::default(),
+            warning_sender: None,
         }
     }
 
     /// Compute the pointer inference analysis.
     /// The result gets returned, but not saved to the `AnalysisResults` struct itself.
-    pub fn compute_pointer_inference(&'a self, config: &serde_json::Value) -> PointerInference<'a> {
+    ///
+    /// The `options` control runtime behaviour like deadlines and checkpoints of the computation.
+    pub fn compute_pointer_inference(
+        &'a self,
+        config: &serde_json::Value,
+        options: RunOptions,
+    ) -> PointerInference<'a> {
         crate::analysis::pointer_inference::run(
             self.project,
             self.runtime_memory_image,
             self.control_flow_graph,
             serde_json::from_value(config.clone()).unwrap(),
+            options,
             false,
         )
     }
@@ -120,4 +319,68 @@ impl<'a> AnalysisResults<'a> {
             ..self
         }
     }
+
+    /// Get the possible sizes of the given heap object at its allocation site,
+    /// as computed by the pointer inference analysis.
+    ///
+    /// Returns `None` if the pointer inference analysis was not computed
+    /// or if the object was not allocated by a call to an allocation function.
+    pub fn get_allocation_size(
+        &self,
+        object_id: &AbstractIdentifier,
+    ) -> Option<&'a AllocationSize> {
+        self.pointer_inference?.get_allocation_size(object_id)
+    }
+
+    /// Create a new `AnalysisResults` struct with the given cancellation token for the module computations.
+    pub fn set_cancellation_token(self, cancellation: CancellationToken) -> AnalysisResults<'a> {
+        AnalysisResults {
+            cancellation,
+            ..self
+        }
+    }
+
+    /// Create a new `AnalysisResults` struct with the given resource budget for expensive modules.
+    pub fn set_budget(self, budget: ResourceBudget) -> AnalysisResults<'a> {
+        AnalysisResults { budget, ..self }
+    }
+
+    /// Create a new `AnalysisResults` struct with the given sender for warnings found by still running modules.
+    pub fn set_warning_sender(
+        self,
+        warning_sender: Option<crossbeam_channel::Sender<CweWarning>>,
+    ) -> AnalysisResults<'a> {
+        AnalysisResults {
+            warning_sender,
+            ..self
+        }
+    }
+
+    /// Report a CWE warning found by a still running module through the warning sender (if one is set).
+    ///
+    /// The reported warning is only preliminary:
+    /// The module still has to return it as part of its results after it finished,
+    /// where it may be replaced by a more precise warning for the same location.
+    pub fn report_warning(&self, cwe: &CweWarning) {
+        if let Some(ref sender) = self.warning_sender {
+            // The receiver may have already stopped listening, in which case the warning is only returned by the module.
+            let _ = sender.send(cwe.clone());
+        }
+    }
 }
+
+// Compile-time check that the analysis results and the types needed to run the modules
+// can be shared between threads, see [`AnalysisResults`].
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<AnalysisResults<'_>>();
+    assert_send_sync::<Project>();
+    assert_send_sync::<RuntimeMemoryImage>();
+    assert_send_sync::<Graph<'_>>();
+    assert_send_sync::<PointerInference<'_>>();
+    assert_send_sync::<CancellationToken>();
+    assert_send_sync::<CweModule>();
+    assert_send_sync::<serde_json::Value>();
+    assert_send_sync::<LogMessage>();
+    assert_send_sync::<CweWarning>();
+};
@@ -6,6 +6,7 @@ use crate::intermediate_representation::Expression as IrExpression;
 use crate::intermediate_representation::UnOpType as IrUnOpType;
 use crate::intermediate_representation::Variable as IrVariable;
 use crate::prelude::*;
+use crate::utils::perf_stats::{self, Counter};
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
 pub struct Variable {
@@ -137,16 +138,22 @@ impl From<Expression> for IrExpression {
             | INT_XOR | INT_AND | INT_OR | INT_LEFT | INT_RIGHT | INT_SRIGHT | INT_MULT
             | INT_DIV | INT_REM | INT_SDIV | INT_SREM | BOOL_XOR | BOOL_AND | BOOL_OR
             | FLOAT_EQUAL | FLOAT_NOTEQUAL | FLOAT_LESS | FLOAT_LESSEQUAL | FLOAT_ADD
-            | FLOAT_SUB | FLOAT_MULT | FLOAT_DIV => IrExpression::BinOp {
-                op: expr.mnemonic.into(),
-                lhs: Box::new(expr.input0.unwrap().into()),
-                rhs: Box::new(expr.input1.unwrap().into()),
-            },
+            | FLOAT_SUB | FLOAT_MULT | FLOAT_DIV => {
+                perf_stats::add(Counter::ExpressionAllocations, 2);
+                IrExpression::BinOp {
+                    op: expr.mnemonic.into(),
+                    lhs: Box::new(expr.input0.unwrap().into()),
+                    rhs: Box::new(expr.input1.unwrap().into()),
+                }
+            }
             INT_NEGATE | INT_2COMP | BOOL_NEGATE | FLOAT_NEG | FLOAT_ABS | FLOAT_SQRT
-            | FLOAT_CEIL | FLOAT_FLOOR | FLOAT_ROUND | FLOAT_NAN => IrExpression::UnOp {
-                op: expr.mnemonic.into(),
-                arg: Box::new(expr.input0.unwrap().into()),
-            },
+            | FLOAT_CEIL | FLOAT_FLOOR | FLOAT_ROUND | FLOAT_NAN => {
+                perf_stats::increment(Counter::ExpressionAllocations);
+                IrExpression::UnOp {
+                    op: expr.mnemonic.into(),
+                    arg: Box::new(expr.input0.unwrap().into()),
+                }
+            }
             INT_ZEXT | INT_SEXT | INT2FLOAT | FLOAT2FLOAT | TRUNC | POPCOUNT => panic!(),
         }
     }
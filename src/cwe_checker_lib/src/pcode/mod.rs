@@ -2,3 +2,7 @@ mod expressions;
 pub use expressions::*;
 mod term;
 pub use term::*;
+mod parsing;
+pub use parsing::parse_project;
+pub mod validation;
+pub mod register_overrides;
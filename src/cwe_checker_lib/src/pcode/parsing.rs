@@ -0,0 +1,320 @@
+//! Parsing of the JSON output of the Ghidra plugin with error context and recovery of truncated output.
+//!
+//! If Ghidra crashes or runs into a timeout, its output stream ends somewhere in the middle of the JSON document.
+//! Instead of failing with a generic deserialization error,
+//! [`parse_project`] reports where the stream ended or which function failed to parse.
+//! If partial input is allowed, it cuts a truncated stream after the last complete JSON element,
+//! drops all functions and extern symbols that are incomplete or malformed
+//! and continues with the successfully parsed rest of the project.
+
+use super::{ExternSymbol, Project, Sub};
+use crate::prelude::*;
+use crate::utils::log::LogMessage;
+use anyhow::Context;
+use serde_json::Value;
+use std::io::Read;
+
+/// The key that is added to all JSON objects that were cut off by the repair of a truncated stream.
+const TRUNCATION_MARKER: &str = "cwe_checker_truncated";
+
+/// Parse the JSON output of the Ghidra plugin into a project.
+///
+/// If `allow_partial` is set, truncated output and malformed functions or extern symbols
+/// are not treated as errors.
+/// Instead the affected elements are removed from the project and a log message is generated for each of them.
+pub fn parse_project(
+    mut reader: impl Read,
+    allow_partial: bool,
+) -> Result<(Project, Vec<LogMessage>), Error> {
+    let mut text = String::new();
+    reader
+        .read_to_string(&mut text)
+        .context("Could not read the Ghidra output")?;
+    let mut logs = Vec::new();
+    let mut value: Value = match serde_json::from_str(&text) {
+        Ok(value) => value,
+        Err(err) if err.is_eof() => {
+            if !allow_partial {
+                return Err(anyhow!(
+                    "The Ghidra output is truncated at line {}, column {}. It contains {} bytes.",
+                    err.line(),
+                    err.column(),
+                    text.len()
+                ));
+            }
+            let value = repair_truncated_json(&text).ok_or_else(|| {
+                anyhow!("The Ghidra output is truncated before its first complete element.")
+            })?;
+            logs.push(LogMessage::new_info(format!(
+                "The Ghidra output is truncated at line {}, column {}. Continuing with the complete part of the output.",
+                err.line(),
+                err.column()
+            )));
+            value
+        }
+        Err(err) => {
+            return Err(anyhow!(
+                "The Ghidra output is not valid JSON at line {}, column {}: {}",
+                err.line(),
+                err.column(),
+                err
+            ))
+        }
+    };
+    if let Ok(project) = serde_json::from_value::<Project>(value.clone()) {
+        if value.get(TRUNCATION_MARKER).is_none() {
+            return Ok((project, logs));
+        }
+    }
+    logs.append(&mut remove_invalid_elements(&mut value, allow_partial)?);
+    if allow_partial {
+        logs.append(&mut add_missing_lists(&mut value));
+    }
+    let project = serde_json::from_value(value).context("Could not parse the Ghidra output")?;
+    Ok((project, logs))
+}
+
+/// Check the functions and extern symbols of the program one by one.
+///
+/// If partial input is allowed, incomplete or malformed elements are removed with a log message for each.
+/// Else an error describing the first malformed element is returned.
+fn remove_invalid_elements(
+    value: &mut Value,
+    allow_partial: bool,
+) -> Result<Vec<LogMessage>, Error> {
+    let mut logs = Vec::new();
+    let program = match value.pointer_mut("/program/term") {
+        Some(program) => program,
+        None => return Ok(logs),
+    };
+    if let Some(Value::Array(subs)) = program.get_mut("subs") {
+        let mut valid_subs = Vec::new();
+        for sub in subs.drain(..) {
+            match check_element::<Term<Sub>>(&sub) {
+                Ok(()) => valid_subs.push(sub),
+                Err(reason) => {
+                    let message = format!(
+                        "Function {} at {} {}",
+                        sub.pointer("/term/name")
+                            .and_then(Value::as_str)
+                            .unwrap_or("<unknown>"),
+                        element_address(&sub),
+                        reason
+                    );
+                    if !allow_partial {
+                        return Err(anyhow!("{}", message));
+                    }
+                    logs.push(LogMessage::new_info(format!(
+                        "{}. It was removed.",
+                        message
+                    )));
+                }
+            }
+        }
+        *subs = valid_subs;
+    }
+    if let Some(Value::Array(symbols)) = program.get_mut("extern_symbols") {
+        let mut valid_symbols = Vec::new();
+        for symbol in symbols.drain(..) {
+            match check_element::<ExternSymbol>(&symbol) {
+                Ok(()) => valid_symbols.push(symbol),
+                Err(reason) => {
+                    let message = format!(
+                        "Extern symbol {} at {} {}",
+                        symbol
+                            .get("name")
+                            .and_then(Value::as_str)
+                            .unwrap_or("<unknown>"),
+                        element_address(&symbol),
+                        reason
+                    );
+                    if !allow_partial {
+                        return Err(anyhow!("{}", message));
+                    }
+                    logs.push(LogMessage::new_info(format!(
+                        "{}. It was removed.",
+                        message
+                    )));
+                }
+            }
+        }
+        *symbols = valid_symbols;
+    }
+    Ok(logs)
+}
+
+/// Check whether the element is complete and can be parsed as `T`.
+/// On failure return the reason as a string.
+fn check_element<T: serde::de::DeserializeOwned>(element: &Value) -> Result<(), String> {
+    if element.get(TRUNCATION_MARKER).is_some() {
+        return Err("is incomplete since the Ghidra output is truncated".to_string());
+    }
+    serde_json::from_value::<T>(element.clone())
+        .map(|_| ())
+        .map_err(|err| format!("could not be parsed: {}", err))
+}
+
+/// Get the address of a term or extern symbol from its TID.
+fn element_address(element: &Value) -> &str {
+    element
+        .pointer("/tid/address")
+        .and_then(Value::as_str)
+        .unwrap_or("<unknown address>")
+}
+
+/// Add empty lists for the lists of the project that are missing because the Ghidra output is truncated.
+///
+/// Missing required fields that are not lists (e.g. the CPU architecture) are not added,
+/// since the analysis cannot continue without them.
+fn add_missing_lists(value: &mut Value) -> Vec<LogMessage> {
+    let mut logs = Vec::new();
+    let mut add_list = |object: Option<&mut Value>, key: &str| {
+        if let Some(Value::Object(object)) = object {
+            if !object.contains_key(key) {
+                object.insert(key.to_string(), Value::Array(Vec::new()));
+                logs.push(LogMessage::new_info(format!(
+                    "The field {} is missing in the truncated Ghidra output. Continuing without it.",
+                    key
+                )));
+            }
+        }
+    };
+    for key in &["subs", "extern_symbols", "entry_points"] {
+        add_list(value.pointer_mut("/program/term"), key);
+    }
+    for key in &["register_properties", "register_calling_convention"] {
+        add_list(Some(&mut *value), key);
+    }
+    logs
+}
+
+/// Cut a truncated JSON document after its last complete element and close all open objects and arrays.
+///
+/// All objects that are closed this way are marked with the [`TRUNCATION_MARKER`] key,
+/// so that incomplete elements can be recognized afterwards.
+/// Returns `None` if the document contains no complete element.
+fn repair_truncated_json(text: &str) -> Option<Value> {
+    let mut open_brackets: Vec<u8> = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut last_cut: Option<(usize, Vec<u8>)> = None;
+    for (index, &byte) in text.as_bytes().iter().enumerate() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if byte == b'\\' {
+                escaped = true;
+            } else if byte == b'"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'{' => open_brackets.push(b'}'),
+            b'[' => open_brackets.push(b']'),
+            b'}' | b']' => {
+                open_brackets.pop();
+            }
+            b',' => last_cut = Some((index, open_brackets.clone())),
+            _ => (),
+        }
+    }
+    let (cut_index, open_brackets) = last_cut?;
+    let mut repaired = text[..cut_index].to_string();
+    for bracket in open_brackets.iter().rev() {
+        if *bracket == b'}' {
+            repaired.push_str(&format!(",\"{}\":true", TRUNCATION_MARKER));
+        }
+        repaired.push(*bracket as char);
+    }
+    serde_json::from_str(&repaired).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_project_json(subs: &str) -> String {
+        format!(
+            r#"{{
+                "program": {{
+                    "tid": {{"id": "prog_tid", "address": "UNKNOWN"}},
+                    "term": {{
+                        "subs": [{}],
+                        "extern_symbols": [],
+                        "entry_points": [],
+                        "image_base": "00100000"
+                    }}
+                }},
+                "cpu_architecture": "x86_64",
+                "stack_pointer_register": {{"name": "RSP", "value": null, "address": null, "size": 8, "is_virtual": false}},
+                "register_properties": [],
+                "register_calling_convention": []
+            }}"#,
+            subs
+        )
+    }
+
+    fn mock_sub_json(name: &str, address: &str) -> String {
+        format!(
+            r#"{{"tid": {{"id": "sub_{1}", "address": "{1}"}}, "term": {{"name": "{0}", "blocks": []}}}}"#,
+            name, address
+        )
+    }
+
+    #[test]
+    fn complete_input() {
+        let text = mock_project_json(&mock_sub_json("main", "00101000"));
+        let (project, logs) = parse_project(text.as_bytes(), false).unwrap();
+        assert_eq!(project.program.term.subs.len(), 1);
+        assert!(logs.is_empty());
+    }
+
+    #[test]
+    fn malformed_function() {
+        let subs = format!(
+            r#"{}, {{"tid": {{"id": "sub_00102000", "address": "00102000"}}, "term": {{"name": "broken"}}}}"#,
+            mock_sub_json("main", "00101000")
+        );
+        let text = mock_project_json(&subs);
+        let err = parse_project(text.as_bytes(), false).unwrap_err();
+        assert!(format!("{}", err).contains("Function broken at 00102000"));
+        let (project, logs) = parse_project(text.as_bytes(), true).unwrap();
+        assert_eq!(project.program.term.subs.len(), 1);
+        assert_eq!(project.program.term.subs[0].term.name, "main");
+        assert_eq!(logs.len(), 1);
+    }
+
+    #[test]
+    fn truncated_input() {
+        let subs = format!(
+            "{}, {}",
+            mock_sub_json("main", "00101000"),
+            mock_sub_json("other", "00102000")
+        );
+        let text = mock_project_json(&subs);
+        let truncated = &text[..text.find("\"other\"").unwrap() + 4];
+        let err = parse_project(truncated.as_bytes(), false).unwrap_err();
+        assert!(format!("{}", err).contains("truncated"));
+        // The project is cut in the middle of the second function, so the CPU architecture is missing.
+        assert!(parse_project(truncated.as_bytes(), true).is_err());
+
+        let truncated = &text[..text.find("\"register_properties\"").unwrap() + 10];
+        let (project, logs) = parse_project(truncated.as_bytes(), true).unwrap();
+        assert_eq!(project.program.term.subs.len(), 2);
+        assert!(project.register_properties.is_empty());
+        assert!(!logs.is_empty());
+    }
+
+    #[test]
+    fn repair_marks_incomplete_objects() {
+        let repaired =
+            repair_truncated_json(r#"{"a": [{"b": 1}, {"c": "x,y", "d": 2, "e"#).unwrap();
+        assert_eq!(repaired["a"][0]["b"], 1);
+        assert!(repaired["a"][0].get(TRUNCATION_MARKER).is_none());
+        assert_eq!(repaired["a"][1]["c"], "x,y");
+        assert_eq!(repaired["a"][1][TRUNCATION_MARKER], true);
+        assert_eq!(repaired[TRUNCATION_MARKER], true);
+    }
+}
@@ -0,0 +1,155 @@
+//! Overrides of the register properties generated by Ghidra.
+//!
+//! For custom or exotic processor modules the register properties exported by Ghidra
+//! may be incomplete or wrong, e.g. if a SLEIGH specification does not declare the base registers of sub-registers
+//! or marks the wrong register as stack pointer.
+//! The `RegisterOverrides` section of the configuration file allows to supplement or override
+//! the register properties, the stack pointer register and the return registers per CPU architecture,
+//! so that such processor modules can be used without recompiling the cwe_checker.
+//!
+//! The overrides are applied to the P-Code project before it is converted to the internally used IR.
+
+use super::{Project, RegisterProperties, Variable};
+use crate::prelude::*;
+use crate::utils::log::LogMessage;
+use std::collections::BTreeMap;
+
+/// A register given by its name and size.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Register {
+    /// The name of the register.
+    pub name: String,
+    /// The size of the register.
+    pub size: ByteSize,
+}
+
+/// The overrides for a single CPU architecture.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+#[serde(default)]
+pub struct RegisterOverride {
+    /// Register properties replacing the properties of the registers with the same name
+    /// or supplementing the properties of the project if no register with the same name is known.
+    pub register_properties: Vec<RegisterProperties>,
+    /// The stack pointer register replacing the one of the project.
+    pub stack_pointer_register: Option<Register>,
+    /// The return registers replacing those of all calling conventions of the project.
+    pub return_register: Option<Vec<String>>,
+}
+
+/// The configured register overrides.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+#[serde(default)]
+pub struct Config {
+    /// The overrides indexed by the CPU architecture (as given in the Ghidra output) that they apply to.
+    pub architectures: BTreeMap<String, RegisterOverride>,
+}
+
+impl Config {
+    /// Apply the overrides configured for the CPU architecture of the project.
+    ///
+    /// Returns a log message describing the applied overrides.
+    pub fn apply(&self, project: &mut Project) -> Vec<LogMessage> {
+        let overrides = match self.architectures.get(&project.cpu_architecture) {
+            Some(overrides) => overrides,
+            None => return Vec::new(),
+        };
+        let mut number_of_replaced_registers = 0;
+        for properties in overrides.register_properties.iter() {
+            match project
+                .register_properties
+                .iter_mut()
+                .find(|existing| existing.register == properties.register)
+            {
+                Some(existing) => {
+                    *existing = properties.clone();
+                    number_of_replaced_registers += 1;
+                }
+                None => project.register_properties.push(properties.clone()),
+            }
+        }
+        if let Some(register) = &overrides.stack_pointer_register {
+            project.stack_pointer_register = Variable {
+                name: Some(register.name.clone()),
+                value: None,
+                address: None,
+                size: register.size,
+                is_virtual: false,
+            };
+        }
+        if let Some(return_register) = &overrides.return_register {
+            for cconv in project.register_calling_convention.iter_mut() {
+                cconv.set_return_register(return_register.clone());
+            }
+        }
+        vec![LogMessage::new_info(format!(
+            "Applied register overrides for {}: {} replaced and {} added register properties",
+            project.cpu_architecture,
+            number_of_replaced_registers,
+            overrides.register_properties.len() - number_of_replaced_registers
+        ))
+        .source("Register Overrides")]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_register_properties(
+        register: &str,
+        base_register: &str,
+        size: u64,
+    ) -> RegisterProperties {
+        RegisterProperties {
+            register: register.to_string(),
+            base_register: base_register.to_string(),
+            lsb: ByteSize::new(0),
+            size: ByteSize::new(size),
+        }
+    }
+
+    #[test]
+    fn apply_overrides() {
+        let mut project: Project = serde_json::from_value(serde_json::json!({
+            "program": {
+                "tid": {"id": "prog_tid", "address": "UNKNOWN"},
+                "term": {"subs": [], "extern_symbols": [], "entry_points": [], "image_base": "0"}
+            },
+            "cpu_architecture": "custom",
+            "stack_pointer_register": {"name": "R13", "value": null, "address": null, "size": 4, "is_virtual": false},
+            "register_properties": [
+                {"register": "R0L", "base_register": "R0L", "lsb": 0, "size": 2}
+            ],
+            "register_calling_convention": []
+        }))
+        .unwrap();
+        let mut config = Config::default();
+        config.architectures.insert(
+            "custom".to_string(),
+            RegisterOverride {
+                register_properties: vec![
+                    mock_register_properties("R0L", "R0", 2),
+                    mock_register_properties("R1L", "R1", 2),
+                ],
+                stack_pointer_register: Some(Register {
+                    name: "SP".to_string(),
+                    size: ByteSize::new(4),
+                }),
+                return_register: None,
+            },
+        );
+        let logs = config.apply(&mut project);
+        assert_eq!(logs.len(), 1);
+        assert_eq!(
+            project.register_properties,
+            vec![
+                mock_register_properties("R0L", "R0", 2),
+                mock_register_properties("R1L", "R1", 2),
+            ]
+        );
+        assert_eq!(project.stack_pointer_register.name, Some("SP".to_string()));
+
+        project.cpu_architecture = "x86_64".to_string();
+        assert!(config.apply(&mut project).is_empty());
+    }
+}
@@ -367,8 +367,10 @@ impl Program {
             .into_iter()
             .map(|symbol| symbol.into())
             .collect();
-        let address_base_offset =
-            u64::from_str_radix(&self.image_base, 16).unwrap() - binary_base_address;
+        // The offset wraps around if Ghidra loaded the binary below its base address in the file.
+        let address_base_offset = u64::from_str_radix(&self.image_base, 16)
+            .unwrap()
+            .wrapping_sub(binary_base_address);
         IrProgram {
             subs,
             extern_symbols,
@@ -388,6 +390,13 @@ pub struct CallingConvention {
     killed_by_call_register: Vec<String>,
 }
 
+impl CallingConvention {
+    /// Replace the return registers of the calling convention.
+    pub fn set_return_register(&mut self, return_register: Vec<String>) {
+        self.return_register = return_register;
+    }
+}
+
 impl From<CallingConvention> for IrCallingConvention {
     fn from(cconv: CallingConvention) -> IrCallingConvention {
         IrCallingConvention {
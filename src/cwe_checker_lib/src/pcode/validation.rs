@@ -0,0 +1,398 @@
+//! Validation of saved Ghidra plugin output without running any analysis.
+//!
+//! The validation parses and normalizes the P-Code project and then checks it for
+//! - functions and extern symbols that cannot be parsed,
+//! - unknown P-Code operations,
+//! - instructions whose operand sizes do not fit to the operation,
+//! - registers that are not contained in the register properties of the project.
+//!
+//! This helps to decide whether a problem is caused by the Ghidra plugin or by the analyses.
+
+use super::{Def, ExpressionType, JmpType, Project, Variable};
+use crate::prelude::*;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::io::Read;
+
+/// The kind of a problem found by the validation.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub enum IssueKind {
+    /// A function or extern symbol could not be parsed.
+    ParseError,
+    /// An instruction uses a P-Code operation that is not known to the cwe_checker.
+    UnknownOp,
+    /// The sizes of the operands of an instruction do not fit to its operation.
+    SizeInconsistency,
+    /// A register that is not contained in the register properties of the project.
+    MissingRegister,
+}
+
+impl std::fmt::Display for IssueKind {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            IssueKind::ParseError => "parse error",
+            IssueKind::UnknownOp => "unknown op",
+            IssueKind::SizeInconsistency => "size inconsistency",
+            IssueKind::MissingRegister => "missing register",
+        };
+        write!(formatter, "{}", name)
+    }
+}
+
+/// A single problem found by the validation.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct ValidationIssue {
+    /// The kind of the problem.
+    pub kind: IssueKind,
+    /// The TID of the term where the problem was found, if known.
+    pub location: Option<String>,
+    /// A description of the problem.
+    pub message: String,
+}
+
+/// The result of the validation of a P-Code project.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct ValidationReport {
+    /// The number of successfully parsed functions.
+    pub number_of_functions: usize,
+    /// The number of successfully parsed instructions, i.e. `Def` and `Jmp` terms.
+    pub number_of_instructions: usize,
+    /// The problems found by the validation.
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Parse, normalize and check the P-Code project read from the reader.
+    ///
+    /// Returns an error only if the project cannot be parsed at all,
+    /// e.g. because it is not valid JSON or because required fields are missing.
+    pub fn validate(mut reader: impl Read) -> Result<ValidationReport, Error> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        let mut report = ValidationReport::default();
+        if let Ok(value) = serde_json::from_str::<Value>(&text) {
+            report.find_unknown_ops(&value);
+        }
+        let (mut project, logs) = super::parse_project(text.as_bytes(), true)?;
+        for log in logs {
+            report.add_issue(IssueKind::ParseError, None, log.text);
+        }
+        project.normalize();
+        report.check_project(&project);
+        Ok(report)
+    }
+
+    /// Returns `true` if no problems were found.
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Add an issue to the report.
+    fn add_issue(&mut self, kind: IssueKind, location: Option<&Tid>, message: impl Into<String>) {
+        self.issues.push(ValidationIssue {
+            kind,
+            location: location.map(|tid| tid.to_string()),
+            message: message.into(),
+        });
+    }
+
+    /// Find all operations in the JSON representation of the project that are not known to the cwe_checker.
+    ///
+    /// This has to be done before parsing the project,
+    /// since functions containing unknown operations cannot be parsed.
+    fn find_unknown_ops(&mut self, value: &Value) {
+        for sub in as_array(value.pointer("/program/term/subs")) {
+            for block in as_array(sub.pointer("/term/blocks")) {
+                for def in as_array(block.pointer("/term/defs")) {
+                    self.check_mnemonic::<ExpressionType>(def, "/term/rhs/mnemonic");
+                }
+                for jmp in as_array(block.pointer("/term/jmps")) {
+                    self.check_mnemonic::<JmpType>(jmp, "/term/mnemonic");
+                }
+            }
+        }
+    }
+
+    /// Add an issue if the mnemonic at the given path of the term cannot be parsed as `T`.
+    fn check_mnemonic<T: serde::de::DeserializeOwned>(&mut self, term: &Value, path: &str) {
+        if let Some(mnemonic) = term.pointer(path) {
+            if serde_json::from_value::<T>(mnemonic.clone()).is_err() {
+                let location = term
+                    .pointer("/tid/id")
+                    .and_then(Value::as_str)
+                    .map(|id| id.to_string());
+                self.issues.push(ValidationIssue {
+                    kind: IssueKind::UnknownOp,
+                    location,
+                    message: format!("Unknown operation {}", mnemonic),
+                });
+            }
+        }
+    }
+
+    /// Check the sizes of the operands and the used registers of all instructions of the project.
+    fn check_project(&mut self, project: &Project) {
+        let mut known_registers: HashSet<&str> = HashSet::new();
+        for properties in project.register_properties.iter() {
+            known_registers.insert(&properties.register);
+            known_registers.insert(&properties.base_register);
+        }
+        if let Some(name) = &project.stack_pointer_register.name {
+            known_registers.insert(name);
+        }
+        // For each missing register the location of its first use and the number of uses.
+        let mut missing_registers: BTreeMap<String, (Tid, usize)> = BTreeMap::new();
+        for sub in project.program.term.subs.iter() {
+            self.number_of_functions += 1;
+            for block in sub.term.blocks.iter() {
+                self.number_of_instructions += block.term.defs.len() + block.term.jmps.len();
+                for def in block.term.defs.iter() {
+                    if let Some(message) = check_operand_sizes(&def.term) {
+                        self.add_issue(IssueKind::SizeInconsistency, Some(&def.tid), message);
+                    }
+                    let inputs = [
+                        &def.term.rhs.input0,
+                        &def.term.rhs.input1,
+                        &def.term.rhs.input2,
+                    ];
+                    let variables = def
+                        .term
+                        .lhs
+                        .iter()
+                        .chain(inputs.iter().filter_map(|&input| input.as_ref()));
+                    for register in variables.filter_map(get_register_name) {
+                        if !known_registers.contains(register) {
+                            missing_registers
+                                .entry(register.to_string())
+                                .or_insert_with(|| (def.tid.clone(), 0))
+                                .1 += 1;
+                        }
+                    }
+                }
+            }
+        }
+        for (register, (first_use, number_of_uses)) in missing_registers {
+            self.add_issue(
+                IssueKind::MissingRegister,
+                Some(&first_use),
+                format!(
+                    "Register {} is not contained in the register properties ({} uses)",
+                    register, number_of_uses
+                ),
+            );
+        }
+    }
+}
+
+impl std::fmt::Display for ValidationReport {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            formatter,
+            "Parsed {} functions with {} instructions, found {} issues",
+            self.number_of_functions,
+            self.number_of_instructions,
+            self.issues.len()
+        )?;
+        for issue in self.issues.iter() {
+            match &issue.location {
+                Some(location) => writeln!(
+                    formatter,
+                    "[{}] @ {}: {}",
+                    issue.kind, location, issue.message
+                )?,
+                None => writeln!(formatter, "[{}] {}", issue.kind, issue.message)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Get the elements of a JSON array or an empty slice if the value is not an array.
+fn as_array(value: Option<&Value>) -> &[Value] {
+    value
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+/// Get the name of the variable if it represents a (non-temporary) register.
+fn get_register_name(variable: &Variable) -> Option<&str> {
+    match (&variable.name, variable.is_virtual) {
+        (Some(name), false) => Some(name),
+        _ => None,
+    }
+}
+
+/// Check whether the sizes of the operands of the definition fit to its operation.
+/// Returns a description of the problem if they do not fit.
+///
+/// Operations whose operands are not all present are not checked.
+fn check_operand_sizes(def: &Def) -> Option<String> {
+    use ExpressionType::*;
+    let size = |variable: &Option<Variable>| variable.as_ref().map(|var| var.size);
+    let (output, input0, input1) = (size(&def.lhs), size(&def.rhs.input0), size(&def.rhs.input1));
+    let one = ByteSize::new(1);
+    let consistent = match def.rhs.mnemonic {
+        INT_ADD | INT_SUB | INT_XOR | INT_AND | INT_OR | INT_MULT | INT_DIV | INT_REM
+        | INT_SDIV | INT_SREM | FLOAT_ADD | FLOAT_SUB | FLOAT_MULT | FLOAT_DIV => {
+            match (output, input0, input1) {
+                (Some(output), Some(input0), Some(input1)) => output == input0 && input0 == input1,
+                _ => true,
+            }
+        }
+        INT_EQUAL | INT_NOTEQUAL | INT_LESS | INT_SLESS | INT_LESSEQUAL | INT_SLESSEQUAL
+        | INT_CARRY | INT_SCARRY | INT_SBORROW | FLOAT_EQUAL | FLOAT_NOTEQUAL | FLOAT_LESS
+        | FLOAT_LESSEQUAL => match (output, input0, input1) {
+            (Some(output), Some(input0), Some(input1)) => output == one && input0 == input1,
+            _ => true,
+        },
+        BOOL_XOR | BOOL_AND | BOOL_OR => match (output, input0, input1) {
+            (Some(output), Some(input0), Some(input1)) => {
+                output == one && input0 == one && input1 == one
+            }
+            _ => true,
+        },
+        INT_LEFT | INT_RIGHT | INT_SRIGHT | COPY | INT_NEGATE | INT_2COMP | FLOAT_NEG
+        | FLOAT_ABS | FLOAT_SQRT | FLOAT_CEIL | FLOAT_FLOOR | FLOAT_ROUND => {
+            match (output, input0) {
+                (Some(output), Some(input0)) => output == input0,
+                _ => true,
+            }
+        }
+        BOOL_NEGATE => match (output, input0) {
+            (Some(output), Some(input0)) => output == one && input0 == one,
+            _ => true,
+        },
+        INT_ZEXT | INT_SEXT => match (output, input0) {
+            (Some(output), Some(input0)) => output >= input0,
+            _ => true,
+        },
+        SUBPIECE => match (output, input0) {
+            (Some(output), Some(input0)) => output <= input0,
+            _ => true,
+        },
+        PIECE => match (output, input0, input1) {
+            (Some(output), Some(input0), Some(input1)) => output == input0 + input1,
+            _ => true,
+        },
+        LOAD | STORE | POPCOUNT | FLOAT_NAN | INT2FLOAT | FLOAT2FLOAT | TRUNC => true,
+    };
+    if consistent {
+        None
+    } else {
+        let format_size =
+            |size: Option<ByteSize>| size.map_or("-".to_string(), |size| size.to_string());
+        Some(format!(
+            "Operand sizes do not fit to {:?}: output {}, inputs {} and {}",
+            def.rhs.mnemonic,
+            format_size(output),
+            format_size(input0),
+            format_size(input1)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_project_json(defs: &str) -> String {
+        format!(
+            r#"{{
+                "program": {{
+                    "tid": {{"id": "prog_tid", "address": "UNKNOWN"}},
+                    "term": {{
+                        "subs": [{{
+                            "tid": {{"id": "sub_main", "address": "00101000"}},
+                            "term": {{"name": "main", "blocks": [{{
+                                "tid": {{"id": "blk_main", "address": "00101000"}},
+                                "term": {{"defs": [{}], "jmps": []}}
+                            }}]}}
+                        }}],
+                        "extern_symbols": [],
+                        "entry_points": [],
+                        "image_base": "00100000"
+                    }}
+                }},
+                "cpu_architecture": "x86_64",
+                "stack_pointer_register": {},
+                "register_properties": [
+                    {{"register": "RAX", "base_register": "RAX", "lsb": 0, "size": 8}}
+                ],
+                "register_calling_convention": []
+            }}"#,
+            defs,
+            mock_variable("RSP", 8)
+        )
+    }
+
+    fn mock_variable(name: &str, size: u64) -> String {
+        format!(
+            r#"{{"name": "{}", "value": null, "address": null, "size": {}, "is_virtual": false}}"#,
+            name, size
+        )
+    }
+
+    fn mock_def(id: &str, mnemonic: &str, lhs: &str, input0: &str, input1: &str) -> String {
+        format!(
+            r#"{{"tid": {{"id": "{}", "address": "00101000"}}, "term": {{"lhs": {}, "rhs": {{"mnemonic": "{}", "input0": {}, "input1": {}, "input2": null}}}}}}"#,
+            id, lhs, mnemonic, input0, input1
+        )
+    }
+
+    #[test]
+    fn valid_project() {
+        let defs = mock_def(
+            "def_add",
+            "INT_ADD",
+            &mock_variable("RAX", 8),
+            &mock_variable("RAX", 8),
+            &mock_variable("RSP", 8),
+        );
+        let report = ValidationReport::validate(mock_project_json(&defs).as_bytes()).unwrap();
+        assert!(report.is_ok());
+        assert_eq!(report.number_of_functions, 1);
+        assert_eq!(report.number_of_instructions, 1);
+    }
+
+    #[test]
+    fn issues_are_found() {
+        let defs = [
+            mock_def(
+                "def_add",
+                "INT_ADD",
+                &mock_variable("RAX", 8),
+                &mock_variable("RAX", 8),
+                &mock_variable("RSP", 4),
+            ),
+            mock_def(
+                "def_unknown_register",
+                "COPY",
+                &mock_variable("RAX", 8),
+                &mock_variable("XYZ", 8),
+                "null",
+            ),
+        ]
+        .join(",");
+        let report = ValidationReport::validate(mock_project_json(&defs).as_bytes()).unwrap();
+        let kinds: Vec<IssueKind> = report.issues.iter().map(|issue| issue.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![IssueKind::SizeInconsistency, IssueKind::MissingRegister]
+        );
+        assert_eq!(report.issues[0].location, Some("def_add".to_string()));
+
+        let defs = mock_def(
+            "def_custom",
+            "CUSTOM_OP",
+            &mock_variable("RAX", 8),
+            &mock_variable("RAX", 8),
+            "null",
+        );
+        let report = ValidationReport::validate(mock_project_json(&defs).as_bytes()).unwrap();
+        assert_eq!(report.issues[0].kind, IssueKind::UnknownOp);
+        assert_eq!(report.issues[0].location, Some("def_custom".to_string()));
+        assert_eq!(report.issues[1].kind, IssueKind::ParseError);
+        assert_eq!(report.number_of_functions, 0);
+    }
+}
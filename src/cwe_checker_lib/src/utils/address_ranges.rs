@@ -0,0 +1,302 @@
+//! Restrict the analysis to configured address ranges of the binary.
+//!
+//! Firmware binaries often contain bundled third-party code that is not of interest for an analysis,
+//! or one is only interested in the code of a patched region.
+//! The address ranges to analyze are configured through a list of included and a list of excluded ranges.
+//! If no included range is given, the whole binary is included.
+//!
+//! Functions starting outside of the included ranges are removed from the project before the analyses run.
+//! Calls to them are treated like calls to extern functions without known semantics,
+//! so that the analysis of the remaining functions does not depend on the removed code.
+//! Warnings located outside of the included ranges are removed from the results.
+
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use std::str::FromStr;
+
+/// The configured address ranges.
+///
+/// The configuration is read from the `AddressRanges` section of the configuration file.
+/// Ranges are given as strings of the form `0x400000-0x408000`, where the end address is not included in the range.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+#[serde(default)]
+pub struct Config {
+    /// The address ranges to analyze. If empty, the whole binary is analyzed.
+    pub include: Vec<String>,
+    /// The address ranges to exclude from the analysis.
+    pub exclude: Vec<String>,
+}
+
+/// A range of memory addresses. The end address is not contained in the range.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct AddressRange {
+    /// The first address of the range.
+    pub start: u64,
+    /// The first address after the end of the range.
+    pub end: u64,
+}
+
+impl AddressRange {
+    /// Returns `true` if the address is contained in the range.
+    pub fn contains(&self, address: u64) -> bool {
+        self.start <= address && address < self.end
+    }
+}
+
+impl FromStr for AddressRange {
+    type Err = Error;
+
+    /// Parse a range of the form `0x400000-0x408000`.
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        let parse_address = |address: &str| {
+            u64::from_str_radix(address.trim().trim_start_matches("0x"), 16)
+                .map_err(|_| anyhow!("Invalid address {} in address range {}", address, range))
+        };
+        let mut bounds = range.splitn(2, '-');
+        let (start, end) = match (bounds.next(), bounds.next()) {
+            (Some(start), Some(end)) => (parse_address(start)?, parse_address(end)?),
+            _ => return Err(anyhow!("Invalid address range {}", range)),
+        };
+        if start >= end {
+            return Err(anyhow!("Empty address range {}", range));
+        }
+        Ok(AddressRange { start, end })
+    }
+}
+
+/// The part of the binary that should be analyzed.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct AddressScope {
+    /// The included ranges. If empty, all addresses not explicitly excluded are included.
+    include: Vec<AddressRange>,
+    /// The excluded ranges.
+    exclude: Vec<AddressRange>,
+}
+
+impl AddressScope {
+    /// Parse the address ranges of the configuration.
+    pub fn new(config: &Config) -> Result<AddressScope, Error> {
+        Ok(AddressScope {
+            include: config
+                .include
+                .iter()
+                .map(|range| range.parse())
+                .collect::<Result<_, _>>()?,
+            exclude: config
+                .exclude
+                .iter()
+                .map(|range| range.parse())
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Returns `true` if no address is excluded from the scope.
+    pub fn is_unrestricted(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Returns `true` if the address is contained in the scope.
+    pub fn contains(&self, address: u64) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|range| range.contains(address)))
+            && !self.exclude.iter().any(|range| range.contains(address))
+    }
+
+    /// Remove all functions starting outside of the scope from the project.
+    ///
+    /// The removed functions are replaced by extern symbols without parameters,
+    /// so that calls to them are handled like calls to unknown extern functions.
+    /// Removed functions without return instructions are marked as non-returning.
+    /// Functions with unknown addresses (e.g. artificial functions) are always kept.
+    #[must_use]
+    pub fn restrict_project(&self, project: &mut Project) -> Vec<LogMessage> {
+        if self.is_unrestricted() {
+            return Vec::new();
+        }
+        let base_offset = project.program.term.address_base_offset;
+        let removed_subs = remove_functions(project, |sub| {
+            match u64::from_str_radix(&sub.tid.address, 16) {
+                Ok(address) => self.contains(address.wrapping_sub(base_offset)),
+                Err(_) => true,
+            }
+        });
+        vec![LogMessage::new_info(format!(
+            "Excluded {} functions outside of the configured address ranges from the analysis.",
+            removed_subs.len()
+        ))
+        .source("Address Ranges")]
+    }
+
+    /// Remove all warnings whose location is outside of the scope.
+    ///
+    /// The location of a warning is its first address.
+    /// Warnings not referencing any instruction (e.g. findings in the data sections of the binary)
+    /// are always kept.
+    pub fn filter_warnings(&self, warnings: &mut Vec<CweWarning>, base_offset: u64) {
+        if self.is_unrestricted() {
+            return;
+        }
+        warnings.retain(|warning| {
+            if warning.tids.is_empty() {
+                return true;
+            }
+            match warning
+                .addresses
+                .get(0)
+                .and_then(|address| u64::from_str_radix(address, 16).ok())
+            {
+                Some(address) => self.contains(address.wrapping_sub(base_offset)),
+                None => true,
+            }
+        });
+    }
+}
+
+/// Remove all functions for which `keep` returns `false` from the project and return them.
+///
+/// The removed functions are replaced by extern symbols without parameters,
+/// so that calls to them are handled like calls to unknown extern functions.
+/// Removed functions without return instructions are marked as non-returning.
+/// The removed functions are also removed from the entry points of the project.
+pub fn remove_functions(
+    project: &mut Project,
+    keep: impl Fn(&Term<Sub>) -> bool,
+) -> Vec<Term<Sub>> {
+    let calling_convention = project
+        .get_standard_calling_convention()
+        .map(|cconv| cconv.name.clone());
+    let (kept_subs, removed_subs): (Vec<_>, Vec<_>) =
+        std::mem::take(&mut project.program.term.subs)
+            .into_iter()
+            .partition(keep);
+    project.program.term.subs = kept_subs;
+    for sub in removed_subs.iter() {
+        let no_return = !sub.term.blocks.iter().any(|block| {
+            block
+                .term
+                .jmps
+                .iter()
+                .any(|jmp| matches!(jmp.term, Jmp::Return(_)))
+        });
+        project.program.term.extern_symbols.push(to_extern_symbol(
+            sub,
+            calling_convention.clone(),
+            no_return,
+        ));
+    }
+    project
+        .program
+        .term
+        .entry_points
+        .retain(|tid| !removed_subs.iter().any(|sub| sub.tid == *tid));
+    removed_subs
+}
+
+/// Create an extern symbol without parameters replacing the given function,
+/// so that calls to the function are handled like calls to an unknown extern function.
+pub fn to_extern_symbol(
+    sub: &Term<Sub>,
+    calling_convention: Option<String>,
+    no_return: bool,
+) -> ExternSymbol {
+    ExternSymbol {
+        tid: sub.tid.clone(),
+        addresses: vec![sub.tid.address.clone()],
+        name: sub.term.name.clone(),
+        calling_convention,
+        parameters: Vec::new(),
+        return_values: Vec::new(),
+        no_return,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_sub(name: &str, address: &str, returns: bool) -> Term<Sub> {
+        let mut sub = Sub::mock(name);
+        sub.tid.address = address.to_string();
+        let mut block = Blk::mock();
+        if returns {
+            block.term.jmps.push(Term {
+                tid: Tid::new("ret"),
+                term: Jmp::Return(Expression::Const(Bitvector::zero(64.into()))),
+            });
+        }
+        sub.term.blocks.push(block);
+        sub
+    }
+
+    #[test]
+    fn range_parsing() {
+        assert_eq!(
+            "0x400000-0x408000".parse::<AddressRange>().unwrap(),
+            AddressRange {
+                start: 0x400000,
+                end: 0x408000
+            }
+        );
+        assert!("0x400000".parse::<AddressRange>().is_err());
+        assert!("0x408000-0x400000".parse::<AddressRange>().is_err());
+        assert!("0x40000g-0x408000".parse::<AddressRange>().is_err());
+    }
+
+    #[test]
+    fn scope_contains() {
+        let scope = AddressScope::new(&Config {
+            include: vec!["0x1000-0x3000".to_string()],
+            exclude: vec!["0x2000-0x2100".to_string()],
+        })
+        .unwrap();
+        assert!(scope.contains(0x1000));
+        assert!(!scope.contains(0x3000));
+        assert!(!scope.contains(0x2050));
+        assert!(AddressScope::default().contains(0x2050));
+    }
+
+    #[test]
+    fn project_restriction() {
+        let mut project = Project::mock_empty();
+        project.program.term.subs = vec![
+            mock_sub("main", "00001000", true),
+            mock_sub("blob_func", "00002000", true),
+            mock_sub("blob_exit", "00002010", false),
+        ];
+        project.program.term.entry_points = project.program.term.subs[0..2]
+            .iter()
+            .map(|sub| sub.tid.clone())
+            .collect();
+        let scope = AddressScope::new(&Config {
+            include: Vec::new(),
+            exclude: vec!["0x2000-0x3000".to_string()],
+        })
+        .unwrap();
+        let logs = scope.restrict_project(&mut project);
+        assert_eq!(logs.len(), 1);
+        assert_eq!(project.program.term.subs.len(), 1);
+        assert_eq!(
+            project.program.term.entry_points,
+            vec![project.program.term.subs[0].tid.clone()]
+        );
+        let extern_symbols = &project.program.term.extern_symbols;
+        assert_eq!(extern_symbols.len(), 2);
+        assert_eq!(extern_symbols[0].name, "blob_func");
+        assert!(!extern_symbols[0].no_return);
+        assert!(extern_symbols[1].no_return);
+
+        let mut warnings = vec![
+            CweWarning::new("CWE000", "0.1", "inside")
+                .tids(vec!["instr_00001004".to_string()])
+                .addresses(vec!["00001004".to_string()]),
+            CweWarning::new("CWE000", "0.1", "outside")
+                .tids(vec!["instr_00002004".to_string()])
+                .addresses(vec!["00002004".to_string()]),
+            CweWarning::new("CWE000", "0.1", "data").addresses(vec!["00002800".to_string()]),
+        ];
+        scope.filter_warnings(&mut warnings, 0);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].description, "inside");
+        assert_eq!(warnings[1].description, "data");
+    }
+}
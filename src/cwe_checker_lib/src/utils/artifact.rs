@@ -0,0 +1,279 @@
+//! Version stamps for serialized artifacts like checkpoints and reports.
+//!
+//! Every artifact written through [`write_artifact`] carries a [`VersionStamp`]
+//! containing the kind of the artifact, the version of its format
+//! and the version of the intermediate representation it was generated from.
+//! When an artifact is read through [`read_artifact`],
+//! the stamp is checked against the current versions:
+//! - Artifacts of another kind or generated from another IR version are rejected.
+//! - Artifacts with a newer format version are rejected.
+//! - Artifacts with an older format version are upgraded step by step through [`VersionedArtifact::upgrade`].
+//! If no upgrade path exists, the artifact is rejected.
+//!
+//! Artifacts without a stamp (written before the introduction of version stamps) are treated as format version 0.
+//!
+//! The stamp is stored in the `cwe_checker_artifact` field of the serialized JSON object,
+//! so that the remaining fields of the artifact stay unchanged for other consumers of the files.
+//!
+//! Large artifacts like the intermediate representation of a project can also be written in a compact binary format
+//! through [`write_binary_artifact`] and read through [`read_binary_artifact`].
+//! Binary artifacts start with magic bytes followed by the version stamp and the artifact, both encoded with `bincode`.
+//! Since the binary format is not self-describing, binary artifacts of older format versions cannot be upgraded
+//! and are rejected.
+
+use crate::prelude::*;
+use serde::de::DeserializeOwned;
+use std::io::{Read, Write};
+
+/// The version of the intermediate representation.
+///
+/// It has to be increased whenever the serialized form of the IR types changes,
+/// since artifacts containing IR terms generated by older versions cannot be read correctly.
+pub const IR_VERSION: u32 = 1;
+
+/// The name of the JSON field containing the version stamp of an artifact.
+const STAMP_FIELD: &str = "cwe_checker_artifact";
+
+/// The magic bytes at the start of binary artifacts.
+const BINARY_MAGIC: &[u8; 8] = b"CWECHK\x00\x01";
+
+/// The version information embedded in serialized artifacts.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct VersionStamp {
+    /// The kind of the artifact, e.g. `pointer_inference_checkpoint`.
+    pub kind: String,
+    /// The version of the format of the artifact.
+    pub version: u32,
+    /// The version of the intermediate representation that the artifact was generated from.
+    pub ir_version: u32,
+    /// The version of the cwe_checker library that generated the artifact.
+    pub generator: String,
+}
+
+impl VersionStamp {
+    /// Generate the stamp for the current version of the given artifact type.
+    pub fn new<T: VersionedArtifact>() -> VersionStamp {
+        VersionStamp {
+            kind: T::KIND.to_string(),
+            version: T::VERSION,
+            ir_version: IR_VERSION,
+            generator: format!("cwe_checker_lib {}", env!("CARGO_PKG_VERSION")),
+        }
+    }
+}
+
+/// A trait for types that are serialized as versioned artifacts.
+pub trait VersionedArtifact {
+    /// The kind of the artifact, used to reject artifacts of the wrong type.
+    const KIND: &'static str;
+    /// The current version of the format of the artifact.
+    /// It has to be increased whenever the serialized form of the artifact changes.
+    const VERSION: u32;
+
+    /// Upgrade the JSON representation of an artifact from the given format version to the next version.
+    ///
+    /// The default implementation provides no upgrade paths, i.e. older artifacts are rejected.
+    fn upgrade(version: u32, _content: serde_json::Value) -> Result<serde_json::Value, Error> {
+        Err(anyhow!(
+            "No upgrade path for {} artifacts of version {}",
+            Self::KIND,
+            version
+        ))
+    }
+}
+
+/// Helper struct for serializing an artifact together with its version stamp.
+#[derive(Serialize)]
+struct StampedArtifact<'a, T> {
+    #[serde(rename = "cwe_checker_artifact")]
+    stamp: VersionStamp,
+    #[serde(flatten)]
+    content: &'a T,
+}
+
+/// Write the artifact together with its version stamp as JSON to the writer.
+pub fn write_artifact<T: VersionedArtifact + Serialize>(
+    artifact: &T,
+    writer: impl Write,
+) -> Result<(), Error> {
+    let stamped = StampedArtifact {
+        stamp: VersionStamp::new::<T>(),
+        content: artifact,
+    };
+    serde_json::to_writer(writer, &stamped)?;
+    Ok(())
+}
+
+/// Read an artifact written by [`write_artifact`] from the reader.
+///
+/// Returns an error if the version stamp does not match the current versions
+/// and the artifact cannot be upgraded to the current version.
+pub fn read_artifact<T: VersionedArtifact + DeserializeOwned>(
+    reader: impl Read,
+) -> Result<T, Error> {
+    let value: serde_json::Value = serde_json::from_reader(reader)?;
+    from_stamped_value(value)
+}
+
+/// Write the artifact together with its version stamp in the binary format to the writer.
+pub fn write_binary_artifact<T: VersionedArtifact + Serialize>(
+    artifact: &T,
+    mut writer: impl Write,
+) -> Result<(), Error> {
+    writer.write_all(BINARY_MAGIC)?;
+    bincode::serialize_into(&mut writer, &VersionStamp::new::<T>())?;
+    bincode::serialize_into(&mut writer, artifact)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Read an artifact written by [`write_binary_artifact`] from the reader.
+///
+/// Returns an error if the reader does not contain a binary artifact
+/// or if the version stamp does not match the current versions.
+pub fn read_binary_artifact<T: VersionedArtifact + DeserializeOwned>(
+    mut reader: impl Read,
+) -> Result<T, Error> {
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic)?;
+    if magic != *BINARY_MAGIC {
+        return Err(anyhow!("Not a binary cwe_checker artifact"));
+    }
+    let stamp: VersionStamp = bincode::deserialize_from(&mut reader)?;
+    check_stamp::<T>(&stamp)?;
+    if stamp.version != T::VERSION {
+        return Err(anyhow!(
+            "Binary artifact version {} does not match the supported version {}",
+            stamp.version,
+            T::VERSION
+        ));
+    }
+    Ok(bincode::deserialize_from(reader)?)
+}
+
+/// Check that the kind and the IR version of the stamp match the artifact type and the current IR version.
+fn check_stamp<T: VersionedArtifact>(stamp: &VersionStamp) -> Result<(), Error> {
+    if stamp.kind != T::KIND {
+        return Err(anyhow!(
+            "Expected a {} artifact, found a {} artifact",
+            T::KIND,
+            stamp.kind
+        ));
+    }
+    if stamp.ir_version != IR_VERSION {
+        return Err(anyhow!(
+            "Artifact was generated for IR version {}, but the current IR version is {}",
+            stamp.ir_version,
+            IR_VERSION
+        ));
+    }
+    Ok(())
+}
+
+/// Check the version stamp of the JSON representation of an artifact,
+/// upgrade the artifact to the current version if necessary and deserialize it.
+fn from_stamped_value<T: VersionedArtifact + DeserializeOwned>(
+    mut value: serde_json::Value,
+) -> Result<T, Error> {
+    let stamp = match value.as_object_mut() {
+        Some(object) => object.remove(STAMP_FIELD),
+        None => return Err(anyhow!("Artifact is not a JSON object")),
+    };
+    let mut version = match stamp {
+        Some(stamp) => {
+            let stamp: VersionStamp = serde_json::from_value(stamp)?;
+            check_stamp::<T>(&stamp)?;
+            stamp.version
+        }
+        None => 0,
+    };
+    if version > T::VERSION {
+        return Err(anyhow!(
+            "Artifact version {} is newer than the supported version {}",
+            version,
+            T::VERSION
+        ));
+    }
+    while version < T::VERSION {
+        value = T::upgrade(version, value)?;
+        version += 1;
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intermediate_representation::Project;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct MockArtifact {
+        value: u64,
+    }
+
+    impl VersionedArtifact for MockArtifact {
+        const KIND: &'static str = "mock";
+        const VERSION: u32 = 2;
+
+        fn upgrade(version: u32, content: serde_json::Value) -> Result<serde_json::Value, Error> {
+            match version {
+                // Version 0 artifacts stored the value under another name.
+                0 => Ok(serde_json::json!({ "val": content["old_value"] })),
+                1 => Ok(serde_json::json!({ "value": content["val"] })),
+                _ => Err(anyhow!("Unknown version")),
+            }
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut buffer = Vec::new();
+        write_artifact(&MockArtifact { value: 42 }, &mut buffer).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&buffer).unwrap();
+        assert_eq!(value["value"], 42);
+        assert_eq!(value[STAMP_FIELD]["kind"], "mock");
+        assert_eq!(value[STAMP_FIELD]["version"], 2);
+        let artifact: MockArtifact = read_artifact(&buffer[..]).unwrap();
+        assert_eq!(artifact, MockArtifact { value: 42 });
+    }
+
+    #[test]
+    fn binary_roundtrip() {
+        let mut buffer = Vec::new();
+        write_binary_artifact(&MockArtifact { value: 42 }, &mut buffer).unwrap();
+        assert!(buffer.starts_with(BINARY_MAGIC));
+        let artifact: MockArtifact = read_binary_artifact(&buffer[..]).unwrap();
+        assert_eq!(artifact, MockArtifact { value: 42 });
+        // JSON artifacts are not accepted as binary artifacts.
+        let mut json_buffer = Vec::new();
+        write_artifact(&MockArtifact { value: 42 }, &mut json_buffer).unwrap();
+        assert!(read_binary_artifact::<MockArtifact>(&json_buffer[..]).is_err());
+
+        let mut buffer = Vec::new();
+        write_binary_artifact(&Project::mock_empty(), &mut buffer).unwrap();
+        let project: Project = read_binary_artifact(&buffer[..]).unwrap();
+        assert_eq!(project, Project::mock_empty());
+    }
+
+    #[test]
+    fn upgrade_and_rejection() {
+        let legacy = br#"{"old_value": 3}"#;
+        let artifact: MockArtifact = read_artifact(&legacy[..]).unwrap();
+        assert_eq!(artifact, MockArtifact { value: 3 });
+
+        let mut stamp = VersionStamp::new::<MockArtifact>();
+        stamp.version = 3;
+        let newer = serde_json::json!({ STAMP_FIELD: stamp, "value": 1 });
+        assert!(from_stamped_value::<MockArtifact>(newer).is_err());
+
+        let mut stamp = VersionStamp::new::<MockArtifact>();
+        stamp.ir_version = IR_VERSION + 1;
+        let other_ir = serde_json::json!({ STAMP_FIELD: stamp, "value": 1 });
+        assert!(from_stamped_value::<MockArtifact>(other_ir).is_err());
+
+        let mut stamp = VersionStamp::new::<MockArtifact>();
+        stamp.kind = "other".to_string();
+        let other_kind = serde_json::json!({ STAMP_FIELD: stamp, "value": 1 });
+        assert!(from_stamped_value::<MockArtifact>(other_kind).is_err());
+    }
+}
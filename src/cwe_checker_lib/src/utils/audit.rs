@@ -0,0 +1,249 @@
+//! A machine-readable audit log of an analysis run.
+//!
+//! The audit log records the provenance of the reported warnings:
+//! the version of the cwe_checker and of the executed checks, a hash of the configuration,
+//! hashes of the analyzed files, the version of Ghidra, the wall times of the analysis phases
+//! and the number of reported warnings.
+//! It is embedded in the JSON output (see [`crate::utils::log::JsonOutput`])
+//! and can additionally be written to a separate file.
+//!
+//! Timestamps are given in UTC in the RFC 3339 format with second precision, e.g. `2021-03-14T09:26:53Z`.
+
+use crate::prelude::*;
+use crate::utils::artifact::VersionedArtifact;
+use sha2::{Digest, Sha256};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// An input file of the analysis.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct FileAudit {
+    /// The path to the file as given on the command line.
+    pub path: String,
+    /// The size of the file in bytes.
+    pub size: u64,
+    /// The SHA-256 hash of the file content in hexadecimal.
+    pub sha256: String,
+}
+
+/// A phase of the analysis that is not a check module, e.g. the generation of the project by Ghidra.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct PhaseAudit {
+    /// The name of the phase.
+    pub name: String,
+    /// The wall time of the phase in milliseconds.
+    pub wall_time_ms: u64,
+}
+
+/// An executed check module.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct ModuleAudit {
+    /// The name of the module.
+    pub name: String,
+    /// The version of the module.
+    pub version: String,
+    /// The wall time of the module in milliseconds.
+    pub wall_time_ms: u64,
+    /// The number of warnings generated by the module before any filtering.
+    pub number_of_warnings: usize,
+}
+
+/// The audit log of an analysis run.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct AuditLog {
+    /// The version of the cwe_checker.
+    pub tool_version: String,
+    /// The command line arguments of the run.
+    pub command_line: Vec<String>,
+    /// The time when the run started.
+    pub started: String,
+    /// The time when the run finished. `None` if the run has not finished yet.
+    pub finished: Option<String>,
+    /// The SHA-256 hash of the configuration as read from the configuration file.
+    pub config_sha256: String,
+    /// The analyzed binary and other input files, e.g. a saved output of the Ghidra plugin.
+    pub files: Vec<FileAudit>,
+    /// The version of Ghidra. `None` if Ghidra was not executed during the run.
+    pub ghidra_version: Option<String>,
+    /// The analysis phases in the order of their execution.
+    pub phases: Vec<PhaseAudit>,
+    /// The executed check modules in the order of their execution.
+    pub modules: Vec<ModuleAudit>,
+    /// The wall time of the whole run in milliseconds.
+    pub total_wall_time_ms: u64,
+    /// The number of reported warnings, i.e. after filtering and suppression.
+    pub number_of_warnings: usize,
+}
+
+impl VersionedArtifact for AuditLog {
+    const KIND: &'static str = "audit_log";
+    const VERSION: u32 = 1;
+}
+
+impl AuditLog {
+    /// Start the audit log of a run with the given command line arguments and configuration.
+    pub fn new(command_line: Vec<String>, config: &serde_json::Value) -> AuditLog {
+        AuditLog {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            command_line,
+            started: format_timestamp(SystemTime::now()),
+            finished: None,
+            config_sha256: get_sha256(&serde_json::to_vec(config).unwrap()),
+            files: Vec::new(),
+            ghidra_version: None,
+            phases: Vec::new(),
+            modules: Vec::new(),
+            total_wall_time_ms: 0,
+            number_of_warnings: 0,
+        }
+    }
+
+    /// Add an input file with the given content.
+    pub fn add_file(&mut self, path: &str, content: &[u8]) {
+        self.files.push(FileAudit {
+            path: path.to_string(),
+            size: content.len() as u64,
+            sha256: get_sha256(content),
+        });
+    }
+
+    /// Add an analysis phase that just finished.
+    ///
+    /// If the phase already ran before (e.g. on another chunk of a chunked analysis),
+    /// the wall time is added to the existing entry.
+    pub fn add_phase(&mut self, name: &str, wall_time: Duration) {
+        let wall_time_ms = wall_time.as_millis() as u64;
+        if let Some(phase) = self.phases.iter_mut().find(|phase| phase.name == name) {
+            phase.wall_time_ms += wall_time_ms;
+        } else {
+            self.phases.push(PhaseAudit {
+                name: name.to_string(),
+                wall_time_ms,
+            });
+        }
+    }
+
+    /// Add a check module that just finished.
+    ///
+    /// If the module already ran before (e.g. on another chunk of a chunked analysis),
+    /// the wall time and the number of warnings are added to the existing entry.
+    pub fn add_module(
+        &mut self,
+        name: &str,
+        version: &str,
+        wall_time: Duration,
+        number_of_warnings: usize,
+    ) {
+        let wall_time_ms = wall_time.as_millis() as u64;
+        if let Some(module) = self.modules.iter_mut().find(|module| module.name == name) {
+            module.wall_time_ms += wall_time_ms;
+            module.number_of_warnings += number_of_warnings;
+        } else {
+            self.modules.push(ModuleAudit {
+                name: name.to_string(),
+                version: version.to_string(),
+                wall_time_ms,
+                number_of_warnings,
+            });
+        }
+    }
+
+    /// Record the end time, the total wall time and the number of reported warnings at the end of the run.
+    pub fn finish(&mut self, total_wall_time: Duration, number_of_warnings: usize) {
+        self.finished = Some(format_timestamp(SystemTime::now()));
+        self.total_wall_time_ms = total_wall_time.as_millis() as u64;
+        self.number_of_warnings = number_of_warnings;
+    }
+}
+
+/// Get the SHA-256 hash of the content in hexadecimal.
+pub fn get_sha256(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// Format the time in UTC in the RFC 3339 format with second precision.
+/// Times before the Unix epoch are formatted as the epoch.
+pub fn format_timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (year, month, day) = get_civil_date(seconds / 86400);
+    let seconds_of_day = seconds % 86400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    )
+}
+
+/// Convert the number of days since the Unix epoch to the year, month and day of the Gregorian calendar.
+///
+/// The algorithm counts in eras of 400 years starting on March 1st,
+/// so that the leap day is the last day of each year.
+fn get_civil_date(days_since_epoch: u64) -> (u64, u64, u64) {
+    // Shift the epoch to 0000-03-01.
+    let days = days_since_epoch + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // The months are counted starting with March.
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            format_timestamp(UNIX_EPOCH + Duration::from_secs(1_600_000_000)),
+            "2020-09-13T12:26:40Z"
+        );
+        // A leap day
+        assert_eq!(
+            format_timestamp(UNIX_EPOCH + Duration::from_secs(951_782_400)),
+            "2000-02-29T00:00:00Z"
+        );
+    }
+
+    #[test]
+    fn audit_log() {
+        let config = serde_json::json!({ "CWE476": { "symbols": ["malloc"] } });
+        let mut audit = AuditLog::new(vec!["cwe_checker".to_string()], &config);
+        audit.add_file("binary", b"abc");
+        audit.add_module("CWE476", "0.3", Duration::from_millis(1000), 2);
+        audit.add_module("CWE476", "0.3", Duration::from_millis(500), 1);
+        audit.finish(Duration::from_secs(3), 1);
+        assert_eq!(
+            audit.files[0].sha256,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(audit.files[0].size, 3);
+        assert_eq!(audit.modules.len(), 1);
+        assert_eq!(audit.modules[0].wall_time_ms, 1500);
+        assert_eq!(audit.modules[0].number_of_warnings, 3);
+        assert_eq!(audit.total_wall_time_ms, 3000);
+        assert!(audit.finished.is_some());
+        let other_config = serde_json::json!({ "CWE476": { "symbols": ["calloc"] } });
+        assert_ne!(
+            audit.config_sha256,
+            AuditLog::new(Vec::new(), &other_config).config_sha256
+        );
+    }
+}
@@ -0,0 +1,277 @@
+//! Analysis of bare-metal firmware images without ELF or PE headers.
+//!
+//! Raw firmware blobs contain no information about the CPU architecture, the address they are loaded to
+//! or the memory layout of the target device.
+//! The user supplies this information in a memory map file (given by the `--bare-metal` command line option).
+//! The memory image of the analysis is then built from the regions of the memory map
+//! (see [`RuntimeMemoryImage::new_bare_metal`](crate::utils::binary::RuntimeMemoryImage::new_bare_metal))
+//! instead of being parsed from the binary.
+//!
+//! Since raw firmware contains no symbols, the entry points of the firmware are read from its vector table
+//! (e.g. the reset handler and the interrupt handlers of ARM Cortex-M devices)
+//! or given explicitly in the memory map.
+//!
+//! An example memory map for an ARM Cortex-M microcontroller:
+//! ```json
+//! {
+//!     "architecture": "ARM:LE:32:Cortex",
+//!     "load_address": "0x08000000",
+//!     "regions": [
+//!         { "name": "flash", "kind": "rom", "start": "0x08000000", "size": "0x100000" },
+//!         { "name": "sram", "kind": "ram", "start": "0x20000000", "size": "0x20000" }
+//!     ],
+//!     "vector_table": { "address": "0x08000000", "entries": 98, "skip": 1 },
+//!     "entry_points": []
+//! }
+//! ```
+//! Addresses and sizes can be given as hexadecimal strings or as JSON numbers.
+
+use crate::prelude::*;
+
+/// The memory map of a bare-metal firmware image.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct MemoryMap {
+    /// The Ghidra language ID of the CPU architecture, e.g. `ARM:LE:32:Cortex`.
+    pub architecture: String,
+    /// The address that the start of the firmware image is loaded to.
+    #[serde(deserialize_with = "deserialize_address")]
+    pub load_address: u64,
+    /// The memory regions of the device.
+    /// If empty, the firmware image forms a single read-only and executable region at the load address.
+    #[serde(default)]
+    pub regions: Vec<MemoryRegion>,
+    /// The vector table containing the addresses of the reset handler and the interrupt handlers.
+    #[serde(default)]
+    pub vector_table: Option<VectorTable>,
+    /// Additional addresses of functions that should be treated as entry points.
+    #[serde(default, deserialize_with = "deserialize_addresses")]
+    pub entry_points: Vec<u64>,
+}
+
+/// A memory region of the device.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct MemoryRegion {
+    /// The name of the region, e.g. `flash`.
+    pub name: String,
+    /// The kind of the region.
+    pub kind: RegionKind,
+    /// The start address of the region.
+    #[serde(deserialize_with = "deserialize_address")]
+    pub start: u64,
+    /// The size of the region in bytes.
+    #[serde(deserialize_with = "deserialize_address")]
+    pub size: u64,
+}
+
+/// The kinds of memory regions.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum RegionKind {
+    /// Read-only and executable memory (e.g. flash memory) whose contents are taken from the firmware image.
+    Rom,
+    /// Writeable memory whose contents are unknown before the firmware runs.
+    Ram,
+}
+
+/// The location of the vector table in the firmware image.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct VectorTable {
+    /// The address of the vector table.
+    #[serde(deserialize_with = "deserialize_address")]
+    pub address: u64,
+    /// The number of entries of the vector table.
+    pub entries: usize,
+    /// The number of leading entries that are not addresses of handlers,
+    /// e.g. 1 for the initial stack pointer of ARM Cortex-M devices.
+    #[serde(default)]
+    pub skip: usize,
+}
+
+impl MemoryMap {
+    /// Return whether the CPU architecture uses little-endian byte order.
+    pub fn is_little_endian(&self) -> bool {
+        self.architecture.split(':').nth(1) != Some("BE")
+    }
+
+    /// Get the size of a pointer in bytes as given by the CPU architecture.
+    pub fn get_pointer_size(&self) -> Result<usize, Error> {
+        let bits: usize = self
+            .architecture
+            .split(':')
+            .nth(2)
+            .and_then(|bits| bits.parse().ok())
+            .ok_or_else(|| anyhow!("Invalid architecture {}", self.architecture))?;
+        Ok(bits / 8)
+    }
+
+    /// Get the memory regions of the firmware.
+    /// If the memory map contains no regions, the firmware image forms a single ROM region at the load address.
+    pub fn get_regions(&self, firmware: &[u8]) -> Vec<MemoryRegion> {
+        if self.regions.is_empty() {
+            vec![MemoryRegion {
+                name: "firmware".to_string(),
+                kind: RegionKind::Rom,
+                start: self.load_address,
+                size: firmware.len() as u64,
+            }]
+        } else {
+            self.regions.clone()
+        }
+    }
+
+    /// Get the addresses of the entry points of the firmware,
+    /// i.e. the handlers contained in the vector table and the explicitly given entry points.
+    ///
+    /// Empty vector table entries and entries not pointing into a ROM region are ignored.
+    /// On ARM the lowest bit of the handler addresses (marking Thumb code) is cleared.
+    pub fn get_entry_points(&self, firmware: &[u8]) -> Result<Vec<u64>, Error> {
+        let mut entry_points = Vec::new();
+        if let Some(vector_table) = &self.vector_table {
+            let pointer_size = self.get_pointer_size()?;
+            let table_offset = vector_table
+                .address
+                .checked_sub(self.load_address)
+                .ok_or_else(|| anyhow!("Vector table outside of the firmware image"))?
+                as usize;
+            let regions = self.get_regions(firmware);
+            for index in vector_table.skip..vector_table.entries {
+                let offset = table_offset + index * pointer_size;
+                let bytes = firmware
+                    .get(offset..offset + pointer_size)
+                    .ok_or_else(|| anyhow!("Vector table outside of the firmware image"))?;
+                let mut address = read_address(bytes, self.is_little_endian());
+                if self.architecture.starts_with("ARM") {
+                    address &= !1;
+                }
+                let is_code_address = regions.iter().any(|region| {
+                    region.kind == RegionKind::Rom
+                        && address >= region.start
+                        && address - region.start < region.size
+                });
+                if address != 0 && is_code_address && !entry_points.contains(&address) {
+                    entry_points.push(address);
+                }
+            }
+        }
+        for address in self.entry_points.iter() {
+            if !entry_points.contains(address) {
+                entry_points.push(*address);
+            }
+        }
+        Ok(entry_points)
+    }
+}
+
+/// Read an address of at most 8 bytes in the given byte order.
+fn read_address(bytes: &[u8], little_endian: bool) -> u64 {
+    let mut address = 0;
+    if little_endian {
+        for byte in bytes.iter().rev() {
+            address = (address << 8) | *byte as u64;
+        }
+    } else {
+        for byte in bytes.iter() {
+            address = (address << 8) | *byte as u64;
+        }
+    }
+    address
+}
+
+/// An address in the memory map, given either as a JSON number or as a hexadecimal string.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AddressValue {
+    Number(u64),
+    Hex(String),
+}
+
+impl AddressValue {
+    /// Convert the value to the address.
+    fn into_address(self) -> Result<u64, Error> {
+        match self {
+            AddressValue::Number(address) => Ok(address),
+            AddressValue::Hex(address) => u64::from_str_radix(address.trim_start_matches("0x"), 16)
+                .map_err(|_| anyhow!("Invalid address {}", address)),
+        }
+    }
+}
+
+/// Deserialize an address given either as a JSON number or as a hexadecimal string.
+fn deserialize_address<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    AddressValue::deserialize(deserializer)?
+        .into_address()
+        .map_err(serde::de::Error::custom)
+}
+
+/// Deserialize a list of addresses given either as JSON numbers or as hexadecimal strings.
+fn deserialize_addresses<'de, D>(deserializer: D) -> Result<Vec<u64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Vec::<AddressValue>::deserialize(deserializer)?
+        .into_iter()
+        .map(AddressValue::into_address)
+        .collect::<Result<_, _>>()
+        .map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::binary::RuntimeMemoryImage;
+
+    fn mock_memory_map() -> MemoryMap {
+        serde_json::from_str(
+            r#"{
+                "architecture": "ARM:LE:32:Cortex",
+                "load_address": "0x08000000",
+                "regions": [
+                    { "name": "flash", "kind": "rom", "start": "0x08000000", "size": "0x1000" },
+                    { "name": "sram", "kind": "ram", "start": 536870912, "size": "0x100" }
+                ],
+                "vector_table": { "address": "0x08000000", "entries": 4, "skip": 1 },
+                "entry_points": ["0x08000100"]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn entry_points_from_vector_table() {
+        let memory_map = mock_memory_map();
+        assert_eq!(memory_map.regions[1].start, 0x2000_0000);
+        let mut firmware = Vec::new();
+        for entry in [0x2000_0100u32, 0x0800_0041, 0, 0x0800_0081].iter() {
+            firmware.extend_from_slice(&entry.to_le_bytes());
+        }
+        assert_eq!(
+            memory_map.get_entry_points(&firmware).unwrap(),
+            vec![0x0800_0040, 0x0800_0080, 0x0800_0100]
+        );
+        firmware.truncate(8);
+        assert!(memory_map.get_entry_points(&firmware).is_err());
+    }
+
+    #[test]
+    fn memory_image_from_regions() {
+        let memory_map = mock_memory_map();
+        let firmware = vec![0xaau8; 0x20];
+        let memory_image = RuntimeMemoryImage::new_bare_metal(&firmware, &memory_map).unwrap();
+        assert!(memory_image.is_little_endian_byte_order());
+        assert!(memory_image
+            .is_address_executable(&Bitvector::from_u32(0x0800_0010))
+            .unwrap());
+        assert!(memory_image
+            .is_address_writeable(&Bitvector::from_u32(0x2000_0010))
+            .unwrap());
+        assert_eq!(
+            memory_image
+                .read(&Bitvector::from_u32(0x0800_0000), ByteSize::new(4))
+                .unwrap(),
+            Bitvector::from_u32(0xaaaa_aaaa).into()
+        );
+    }
+}
@@ -0,0 +1,408 @@
+//! Analysis of several binaries in one run, e.g. of all executables extracted from a firmware image.
+//!
+//! The binaries of a batch are collected from the given files and directories by [`collect_binaries`].
+//! Each binary is analyzed separately and its results are written to a file of its own.
+//! The [`BatchSummary`] aggregates the number of warnings of all binaries
+//! and lists the binaries whose analysis failed.
+//! The [`BatchDashboard`] generated from it contains the aggregated statistics of the whole batch,
+//! e.g. for rendering a dashboard of the analyzed firmware images.
+
+use crate::prelude::*;
+use crate::utils::artifact::VersionedArtifact;
+use crate::utils::log::CweWarning;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Collect the binaries to analyze from the given paths.
+///
+/// Files are always analyzed.
+/// Directories are searched recursively for ELF and PE files, other files inside them are ignored.
+/// Symbolic links inside directories are not followed.
+///
+/// Returns the path to each binary together with the path of its result file relative to the output directory,
+/// i.e. the file name for files and the path relative to the directory for files found in a directory.
+/// The binaries of each directory are sorted by their path.
+pub fn collect_binaries(paths: &[String]) -> Result<Vec<(PathBuf, PathBuf)>, Error> {
+    let mut binaries = Vec::new();
+    for path in paths.iter().map(PathBuf::from) {
+        if path.is_dir() {
+            let mut found_binaries = Vec::new();
+            collect_binaries_in_directory(&path, &mut found_binaries)?;
+            found_binaries.sort();
+            for binary in found_binaries {
+                let relative_path = binary.strip_prefix(&path)?.to_path_buf();
+                binaries.push((binary, relative_path));
+            }
+        } else {
+            let file_name: PathBuf = path
+                .file_name()
+                .ok_or_else(|| anyhow!("{} is not a file", path.display()))?
+                .into();
+            binaries.push((path, file_name));
+        }
+    }
+    Ok(binaries)
+}
+
+/// Recursively add the ELF and PE files contained in the directory to the list of binaries.
+fn collect_binaries_in_directory(
+    directory: &Path,
+    binaries: &mut Vec<PathBuf>,
+) -> Result<(), Error> {
+    for entry in std::fs::read_dir(directory)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_binaries_in_directory(&entry.path(), binaries)?;
+        } else if file_type.is_file() && is_executable_format(&entry.path()) {
+            binaries.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+/// Returns `true` if the file starts with the magic bytes of an ELF or a PE file.
+/// Files that cannot be read are not recognized as binaries.
+fn is_executable_format(path: &Path) -> bool {
+    let mut magic = [0u8; 4];
+    match std::fs::File::open(path).and_then(|mut file| file.read_exact(&mut magic)) {
+        Ok(()) => magic == *b"\x7fELF" || magic[..2] == *b"MZ",
+        Err(_) => false,
+    }
+}
+
+/// The result of the analysis of a single binary of a batch.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct BinarySummary {
+    /// The path to the binary.
+    pub binary: String,
+    /// The path to the file containing the results of the binary.
+    /// `None` if the analysis failed.
+    pub result_file: Option<String>,
+    /// The number of reported warnings of each check.
+    pub warnings: BTreeMap<String, usize>,
+    /// The number of reported warnings of each severity.
+    #[serde(default)]
+    pub severities: BTreeMap<String, usize>,
+    /// The time needed for the analysis of the binary in milliseconds.
+    #[serde(default)]
+    pub duration_ms: u64,
+    /// The error message if the analysis of the binary failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The aggregated results of the analysis of a batch of binaries.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct BatchSummary {
+    /// The results of the binaries in the order of their analysis.
+    pub binaries: Vec<BinarySummary>,
+}
+
+impl VersionedArtifact for BatchSummary {
+    const KIND: &'static str = "batch_summary";
+    const VERSION: u32 = 1;
+}
+
+impl BatchSummary {
+    /// Create an empty summary.
+    pub fn new() -> BatchSummary {
+        BatchSummary::default()
+    }
+
+    /// Add the reported warnings of a successfully analyzed binary
+    /// together with the time needed for its analysis.
+    pub fn add_result(
+        &mut self,
+        binary: &Path,
+        result_file: &Path,
+        warnings: &[CweWarning],
+        duration: Duration,
+    ) {
+        let mut warning_counts = BTreeMap::new();
+        let mut severity_counts = BTreeMap::new();
+        for warning in warnings {
+            *warning_counts.entry(warning.name.clone()).or_insert(0) += 1;
+            *severity_counts
+                .entry(warning.severity.to_string())
+                .or_insert(0) += 1;
+        }
+        self.binaries.push(BinarySummary {
+            binary: binary.display().to_string(),
+            result_file: Some(result_file.display().to_string()),
+            warnings: warning_counts,
+            severities: severity_counts,
+            duration_ms: duration.as_millis() as u64,
+            error: None,
+        });
+    }
+
+    /// Add a binary whose analysis failed with the given error message after the given time.
+    pub fn add_error(&mut self, binary: &Path, error: String, duration: Duration) {
+        self.binaries.push(BinarySummary {
+            binary: binary.display().to_string(),
+            result_file: None,
+            warnings: BTreeMap::new(),
+            severities: BTreeMap::new(),
+            duration_ms: duration.as_millis() as u64,
+            error: Some(error),
+        });
+    }
+
+    /// Get the number of reported warnings of each check summed up over all binaries.
+    pub fn get_total_warnings(&self) -> BTreeMap<&str, usize> {
+        let mut total = BTreeMap::new();
+        for binary in self.binaries.iter() {
+            for (name, count) in binary.warnings.iter() {
+                *total.entry(name.as_str()).or_insert(0) += count;
+            }
+        }
+        total
+    }
+
+    /// Aggregate the results of all binaries to the statistics of the batch.
+    pub fn get_dashboard(&self) -> BatchDashboard {
+        let mut dashboard = BatchDashboard {
+            number_of_binaries: self.binaries.len(),
+            binaries: self.binaries.clone(),
+            ..BatchDashboard::default()
+        };
+        for binary in self.binaries.iter() {
+            dashboard.total_duration_ms += binary.duration_ms;
+            dashboard.max_duration_ms =
+                std::cmp::max(dashboard.max_duration_ms, binary.duration_ms);
+            if let Some(error) = &binary.error {
+                dashboard.number_of_failures += 1;
+                *dashboard
+                    .failure_reasons
+                    .entry(get_failure_reason(error))
+                    .or_insert(0) += 1;
+                continue;
+            }
+            if !binary.warnings.is_empty() {
+                dashboard.number_of_binaries_with_warnings += 1;
+            }
+            for (name, count) in binary.warnings.iter() {
+                let check = dashboard.checks.entry(name.clone()).or_default();
+                check.warnings += count;
+                check.affected_binaries += 1;
+                dashboard.number_of_warnings += count;
+            }
+            for (severity, count) in binary.severities.iter() {
+                *dashboard.severities.entry(severity.clone()).or_insert(0) += count;
+            }
+        }
+        let number_of_analyzed = dashboard.number_of_binaries - dashboard.number_of_failures;
+        if number_of_analyzed > 0 {
+            dashboard.mean_duration_ms = self
+                .binaries
+                .iter()
+                .filter(|binary| binary.error.is_none())
+                .map(|binary| binary.duration_ms)
+                .sum::<u64>()
+                / number_of_analyzed as u64;
+        }
+        dashboard
+    }
+}
+
+/// The warnings of a check summed up over all binaries of a batch.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct CheckStatistics {
+    /// The number of warnings of the check in all binaries.
+    pub warnings: usize,
+    /// The number of binaries with at least one warning of the check.
+    pub affected_binaries: usize,
+}
+
+/// The aggregated statistics of the analysis of a batch of binaries,
+/// e.g. for rendering a dashboard of all analyzed binaries of a firmware image or of a fleet of devices.
+///
+/// Failed analyses are not counted in the warning statistics.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct BatchDashboard {
+    /// The number of binaries of the batch.
+    pub number_of_binaries: usize,
+    /// The number of binaries whose analysis failed.
+    pub number_of_failures: usize,
+    /// The number of successfully analyzed binaries with at least one warning.
+    pub number_of_binaries_with_warnings: usize,
+    /// The number of warnings in all binaries.
+    pub number_of_warnings: usize,
+    /// The warning statistics of each check.
+    pub checks: BTreeMap<String, CheckStatistics>,
+    /// The number of warnings of each severity in all binaries.
+    pub severities: BTreeMap<String, usize>,
+    /// The number of failed analyses for each failure reason,
+    /// i.e. for each error message without its details.
+    pub failure_reasons: BTreeMap<String, usize>,
+    /// The sum of the analysis times of all binaries in milliseconds.
+    pub total_duration_ms: u64,
+    /// The mean analysis time of the successfully analyzed binaries in milliseconds.
+    pub mean_duration_ms: u64,
+    /// The longest analysis time of a binary in milliseconds.
+    pub max_duration_ms: u64,
+    /// The results of the individual binaries.
+    pub binaries: Vec<BinarySummary>,
+}
+
+impl VersionedArtifact for BatchDashboard {
+    const KIND: &'static str = "batch_dashboard";
+    const VERSION: u32 = 1;
+}
+
+/// Get the reason of a failed analysis from its error message,
+/// i.e. the first line of the message without the details following the first colon
+/// and without a leading `Error: `.
+///
+/// For example, the reason of the error `Error while parsing the memory map: invalid address` is
+/// `Error while parsing the memory map`.
+fn get_failure_reason(error: &str) -> String {
+    let first_line = error.lines().next().unwrap_or("");
+    let message = first_line.trim_start_matches("Error: ");
+    match message.find(':') {
+        Some(index) => message[..index].trim().to_string(),
+        None => message.trim().to_string(),
+    }
+}
+
+impl std::fmt::Display for BatchSummary {
+    /// Print one line per binary with its number of warnings or its error, followed by the total number of warnings per check.
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let number_of_failures = self
+            .binaries
+            .iter()
+            .filter(|binary| binary.error.is_some())
+            .count();
+        writeln!(
+            formatter,
+            "Analyzed binaries: {} ({} failed)",
+            self.binaries.len(),
+            number_of_failures
+        )?;
+        for binary in self.binaries.iter() {
+            match &binary.error {
+                Some(error) => writeln!(formatter, "  {}: failed: {}", binary.binary, error)?,
+                None => writeln!(
+                    formatter,
+                    "  {}: {} warnings ({})",
+                    binary.binary,
+                    binary.warnings.values().sum::<usize>(),
+                    format_counts(
+                        binary
+                            .warnings
+                            .iter()
+                            .map(|(name, count)| (name.as_str(), *count))
+                    )
+                )?,
+            }
+        }
+        let total = self.get_total_warnings();
+        write!(
+            formatter,
+            "Total: {} warnings ({})",
+            total.values().sum::<usize>(),
+            format_counts(total.into_iter())
+        )
+    }
+}
+
+/// Format the number of warnings of each check as a comma separated list.
+fn format_counts<'a>(counts: impl Iterator<Item = (&'a str, usize)>) -> String {
+    counts
+        .map(|(name, count)| format!("{}: {}", name, count))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_collection() {
+        let directory =
+            std::env::temp_dir().join(format!("cwe_checker_batch_test_{}", std::process::id()));
+        std::fs::create_dir_all(directory.join("usr/bin")).unwrap();
+        std::fs::write(directory.join("usr/bin/busybox"), b"\x7fELF\x02\x01").unwrap();
+        std::fs::write(directory.join("setup.exe"), b"MZ\x90\x00").unwrap();
+        std::fs::write(directory.join("README"), b"Firmware").unwrap();
+        let binaries = collect_binaries(&[directory.display().to_string()]).unwrap();
+        let relative_paths: Vec<PathBuf> = binaries
+            .into_iter()
+            .map(|(_, relative_path)| relative_path)
+            .collect();
+        assert_eq!(
+            relative_paths,
+            vec![PathBuf::from("setup.exe"), PathBuf::from("usr/bin/busybox")]
+        );
+        let binaries = collect_binaries(&[directory.join("README").display().to_string()]).unwrap();
+        assert_eq!(binaries[0].1, PathBuf::from("README"));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn summary_aggregation() {
+        let mut summary = BatchSummary::new();
+        let warnings = vec![
+            CweWarning::new("CWE476", "0.3", "first"),
+            CweWarning::new("CWE476", "0.3", "second"),
+            CweWarning::new("CWE676", "0.1", "third"),
+        ];
+        summary.add_result(
+            Path::new("bin/a"),
+            Path::new("out/a.json"),
+            &warnings,
+            Duration::from_millis(300),
+        );
+        summary.add_result(
+            Path::new("bin/b"),
+            Path::new("out/b.json"),
+            &warnings[2..],
+            Duration::from_millis(100),
+        );
+        summary.add_error(
+            Path::new("bin/c"),
+            "Ghidra failed".to_string(),
+            Duration::from_millis(50),
+        );
+        let total = summary.get_total_warnings();
+        assert_eq!(total["CWE476"], 2);
+        assert_eq!(total["CWE676"], 2);
+        let output = format!("{}", summary);
+        assert!(output.starts_with("Analyzed binaries: 3 (1 failed)\n"));
+        assert!(output.contains("  bin/a: 3 warnings (CWE476: 2, CWE676: 1)\n"));
+        assert!(output.contains("  bin/c: failed: Ghidra failed\n"));
+        assert!(output.ends_with("Total: 4 warnings (CWE476: 2, CWE676: 2)"));
+
+        summary.add_error(
+            Path::new("bin/d"),
+            "Error while parsing the memory map: invalid address".to_string(),
+            Duration::from_millis(10),
+        );
+        let dashboard = summary.get_dashboard();
+        assert_eq!(dashboard.number_of_binaries, 4);
+        assert_eq!(dashboard.number_of_failures, 2);
+        assert_eq!(dashboard.number_of_binaries_with_warnings, 2);
+        assert_eq!(dashboard.number_of_warnings, 4);
+        assert_eq!(
+            dashboard.checks["CWE676"],
+            CheckStatistics {
+                warnings: 2,
+                affected_binaries: 2
+            }
+        );
+        assert_eq!(dashboard.checks["CWE476"].affected_binaries, 1);
+        assert_eq!(dashboard.severities["medium"], 4);
+        assert_eq!(dashboard.failure_reasons["Ghidra failed"], 1);
+        assert_eq!(
+            dashboard.failure_reasons["Error while parsing the memory map"],
+            1
+        );
+        assert_eq!(dashboard.total_duration_ms, 460);
+        assert_eq!(dashboard.mean_duration_ms, 200);
+        assert_eq!(dashboard.max_duration_ms, 300);
+    }
+}
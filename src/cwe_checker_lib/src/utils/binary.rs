@@ -5,15 +5,57 @@ use crate::abstract_domain::RegisterDomain;
 use crate::abstract_domain::SizedDomain;
 use crate::intermediate_representation::BinOpType;
 use crate::prelude::*;
+use crate::utils::bare_metal::{MemoryMap, RegionKind};
 use goblin::elf;
 use goblin::pe;
 use goblin::Object;
+use std::collections::BTreeMap;
 
 /// A representation of the runtime image of a binary after being loaded into memory by the loader.
+///
+/// The memory image is immutable once shared via `AnalysisResults`,
+/// so analyses in different threads can share it through shared references.
+///
+/// On Harvard architectures (e.g. AVR) code and data reside in separate address spaces,
+/// so that the same numeric address may denote both an instruction and a data byte.
+/// For such targets the executable segments form the code address space
+/// and all other segments form the data address space.
+/// Data accesses (reads and writes to global memory) then only consider the data address space,
+/// while checks for executable addresses only consider the code address space.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
 pub struct RuntimeMemoryImage {
     memory_segments: Vec<MemorySegment>,
     is_little_endian: bool,
+    #[serde(default)]
+    has_separate_address_spaces: bool,
+}
+
+/// The address spaces of a Harvard architecture.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum AddressSpace {
+    /// The address space containing the program code.
+    Code,
+    /// The address space containing the data memory.
+    Data,
+}
+
+/// The configuration of CPU architectures with separate code and data address spaces.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+#[serde(default)]
+pub struct HarvardConfig {
+    /// The address space layouts indexed by the CPU architecture (as given in the Ghidra output) that they apply to.
+    pub architectures: BTreeMap<String, HarvardAddressSpaces>,
+}
+
+/// The layout of the address spaces of a Harvard architecture.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+#[serde(default)]
+pub struct HarvardAddressSpaces {
+    /// The offset at which the data address space is placed in the virtual addresses of the binary file.
+    /// E.g. AVR toolchains place the data memory at `0x800000` in ELF files.
+    /// The offset is subtracted from the base addresses of the data segments
+    /// to obtain the addresses used by the instructions of the program.
+    pub data_space_offset: u64,
 }
 
 /// A continuous segment in the memory image.
@@ -92,6 +134,7 @@ impl RuntimeMemoryImage {
                 Ok(RuntimeMemoryImage {
                     memory_segments,
                     is_little_endian: elf_file.header.endianness().unwrap().is_little(),
+                    has_separate_address_spaces: false,
                 })
             }
             Object::PE(pe_file) => {
@@ -108,6 +151,7 @@ impl RuntimeMemoryImage {
                 let mut memory_image = RuntimeMemoryImage {
                     memory_segments,
                     is_little_endian: true,
+                    has_separate_address_spaces: false,
                 };
                 memory_image.add_global_memory_offset(pe_file.image_base as u64);
                 Ok(memory_image)
@@ -116,21 +160,162 @@ impl RuntimeMemoryImage {
         }
     }
 
+    /// Generate the runtime memory image of a bare-metal firmware image from its memory map
+    /// (see the [`bare_metal`](crate::utils::bare_metal) module).
+    ///
+    /// ROM regions contain the parts of the firmware image that they overlap with and are read-only and executable.
+    /// RAM regions are filled with null bytes and are writeable, but not executable.
+    pub fn new_bare_metal(firmware: &[u8], memory_map: &MemoryMap) -> Result<Self, Error> {
+        let mut memory_segments = Vec::new();
+        for region in memory_map.get_regions(firmware) {
+            match region.kind {
+                RegionKind::Rom => {
+                    let start = std::cmp::max(region.start, memory_map.load_address);
+                    let end = std::cmp::min(
+                        region.start.saturating_add(region.size),
+                        memory_map.load_address + firmware.len() as u64,
+                    );
+                    if start < end {
+                        let offset = (start - memory_map.load_address) as usize;
+                        memory_segments.push(MemorySegment {
+                            bytes: firmware[offset..offset + (end - start) as usize].to_vec(),
+                            base_address: start,
+                            read_flag: true,
+                            write_flag: false,
+                            execute_flag: true,
+                        });
+                    }
+                }
+                RegionKind::Ram => memory_segments.push(MemorySegment {
+                    bytes: vec![0u8; region.size as usize],
+                    base_address: region.start,
+                    read_flag: true,
+                    write_flag: true,
+                    execute_flag: false,
+                }),
+            }
+        }
+        if memory_segments.is_empty() {
+            return Err(anyhow!("No memory region contains the firmware image"));
+        }
+        Ok(RuntimeMemoryImage {
+            memory_segments,
+            is_little_endian: memory_map.is_little_endian(),
+            has_separate_address_spaces: false,
+        })
+    }
+
     /// Return whether values in the memory image should be interpreted in little-endian
     /// or big-endian byte order.
     pub fn is_little_endian_byte_order(&self) -> bool {
         self.is_little_endian
     }
 
+    /// Iterate over the memory segments of the image.
+    /// Yields the base address and the contents of each segment.
+    pub fn iter_segments(&self) -> impl Iterator<Item = (u64, &[u8])> {
+        self.memory_segments
+            .iter()
+            .map(|segment| (segment.base_address, &segment.bytes[..]))
+    }
+
     /// Add a global offset to the base addresses of all memory segments.
     /// Useful to align the addresses with those reported by Ghidra
     /// if the Ghidra backend added such an offset to all addresses.
+    ///
+    /// The addition wraps around, so that a negative offset can be given as its two's complement,
+    /// e.g. if the binary was rebased to an address below its preferred image base.
     pub fn add_global_memory_offset(&mut self, offset: u64) {
         for segment in self.memory_segments.iter_mut() {
-            segment.base_address += offset;
+            segment.base_address = segment.base_address.wrapping_add(offset);
         }
     }
 
+    /// Add the memory segments of an ELF core dump to the memory image,
+    /// so that the image reflects the memory of the process at the time of the dump
+    /// (see the [`core_dump`](crate::utils::core_dump) module).
+    ///
+    /// The segments of the core dump take precedence over the segments already contained in the image.
+    /// Only the parts of the segments that are actually contained in the core dump are added.
+    /// Returns the number of added segments.
+    pub fn add_core_dump_segments(&mut self, core: &[u8]) -> Result<usize, Error> {
+        let core_file = elf::Elf::parse(core)?;
+        if core_file.header.e_type != elf::header::ET_CORE {
+            return Err(anyhow!("Not an ELF core dump"));
+        }
+        let mut core_segments = Vec::new();
+        for header in core_file.program_headers.iter() {
+            if header.p_type == elf::program_header::PT_LOAD && header.p_filesz > 0 {
+                let bytes = core
+                    .get(header.file_range())
+                    .ok_or_else(|| anyhow!("Memory segment out of bounds"))?;
+                core_segments.push(MemorySegment {
+                    bytes: bytes.to_vec(),
+                    base_address: header.p_vaddr,
+                    read_flag: header.is_read(),
+                    write_flag: header.is_write(),
+                    execute_flag: header.is_executable(),
+                });
+            }
+        }
+        let number_of_segments = core_segments.len();
+        core_segments.append(&mut self.memory_segments);
+        self.memory_segments = core_segments;
+        Ok(number_of_segments)
+    }
+
+    /// Add a string recovered by the analysis (e.g. a string decoded at runtime, see the [`emulation`](crate::analysis::emulation) module)
+    /// to the memory image as a null-terminated string in a read-only segment at the given address.
+    ///
+    /// The segment takes precedence over the segments already contained in the image,
+    /// so that reads at the address return the recovered string instead of the original contents of the binary.
+    pub fn add_recovered_string(&mut self, address: u64, string: &str) {
+        let mut bytes = string.as_bytes().to_vec();
+        bytes.push(0);
+        self.memory_segments.insert(
+            0,
+            MemorySegment {
+                bytes,
+                base_address: address,
+                read_flag: true,
+                write_flag: false,
+                execute_flag: false,
+            },
+        );
+    }
+
+    /// Split the memory image into separate code and data address spaces,
+    /// as is the case for Harvard architectures.
+    ///
+    /// Executable segments are placed in the code address space and all other segments in the data address space.
+    /// The given offset is subtracted from the base addresses of all data segments.
+    pub fn separate_address_spaces(&mut self, data_space_offset: u64) {
+        for segment in self.memory_segments.iter_mut() {
+            if !segment.execute_flag && segment.base_address >= data_space_offset {
+                segment.base_address -= data_space_offset;
+            }
+        }
+        self.has_separate_address_spaces = true;
+    }
+
+    /// Return whether code and data reside in separate address spaces,
+    /// i.e. whether the same numeric address may point to both code and data.
+    pub fn has_separate_address_spaces(&self) -> bool {
+        self.has_separate_address_spaces
+    }
+
+    /// Return the segment of the given address space containing the given address range.
+    ///
+    /// If the memory image has no separate address spaces, all segments are considered.
+    fn get_segment(&self, address: u64, size: u64, space: AddressSpace) -> Option<&MemorySegment> {
+        self.memory_segments.iter().find(|segment| {
+            (!self.has_separate_address_spaces
+                || segment.execute_flag == (space == AddressSpace::Code))
+                && address >= segment.base_address
+                && address + size <= segment.base_address + segment.bytes.len() as u64
+        })
+    }
+
     /// Read the contents of the memory image at the given address into a `BitvectorDomain`,
     /// to emulate a read instruction to global data at runtime.
     ///
@@ -142,31 +327,27 @@ impl RuntimeMemoryImage {
     /// Returns an error if the address is not contained in the global data address range.
     pub fn read(&self, address: &Bitvector, size: ByteSize) -> Result<BitvectorDomain, Error> {
         let address = address.try_to_u64().unwrap();
-        for segment in self.memory_segments.iter() {
-            if address >= segment.base_address
-                && address + u64::from(size) <= segment.base_address + segment.bytes.len() as u64
-            {
-                if segment.write_flag {
-                    // The segment is writeable, thus we do not know the content at runtime.
-                    return Ok(BitvectorDomain::new_top(size));
-                }
-                let index = (address - segment.base_address) as usize;
-                let mut bytes = segment.bytes[index..index + u64::from(size) as usize].to_vec();
-                if self.is_little_endian {
-                    bytes = bytes.into_iter().rev().collect();
-                }
-                let mut bytes = bytes.into_iter();
-                let mut bitvector: BitvectorDomain =
-                    Bitvector::from_u8(bytes.next().unwrap()).into();
-                for byte in bytes {
-                    let new_byte: BitvectorDomain = Bitvector::from_u8(byte).into();
-                    bitvector = bitvector.bin_op(BinOpType::Piece, &new_byte);
-                }
-                return Ok(bitvector);
-            }
+        let segment = match self.get_segment(address, u64::from(size), AddressSpace::Data) {
+            Some(segment) => segment,
+            // No segment fully contains the read.
+            None => return Err(anyhow!("Address is not a valid global memory address.")),
+        };
+        if segment.write_flag {
+            // The segment is writeable, thus we do not know the content at runtime.
+            return Ok(BitvectorDomain::new_top(size));
+        }
+        let index = (address - segment.base_address) as usize;
+        let mut bytes = segment.bytes[index..index + u64::from(size) as usize].to_vec();
+        if self.is_little_endian {
+            bytes = bytes.into_iter().rev().collect();
+        }
+        let mut bytes = bytes.into_iter();
+        let mut bitvector: BitvectorDomain = Bitvector::from_u8(bytes.next().unwrap()).into();
+        for byte in bytes {
+            let new_byte: BitvectorDomain = Bitvector::from_u8(byte).into();
+            bitvector = bitvector.bin_op(BinOpType::Piece, &new_byte);
         }
-        // No segment fully contains the read.
-        Err(anyhow!("Address is not a valid global memory address."))
+        Ok(bitvector)
     }
 
     /// For an address to global read-only memory, return the memory segment it points to
@@ -179,33 +360,114 @@ impl RuntimeMemoryImage {
         address: &Bitvector,
     ) -> Result<(&[u8], usize), Error> {
         let address = address.try_to_u64().unwrap();
-        for segment in self.memory_segments.iter() {
-            if address >= segment.base_address
-                && address < segment.base_address + segment.bytes.len() as u64
-            {
-                if segment.write_flag {
-                    return Err(anyhow!("Target segment is writeable"));
-                } else {
-                    return Ok((&segment.bytes, (address - segment.base_address) as usize));
-                }
-            }
+        match self.get_segment(address, 1, AddressSpace::Data) {
+            Some(segment) if segment.write_flag => Err(anyhow!("Target segment is writeable")),
+            Some(segment) => Ok((&segment.bytes, (address - segment.base_address) as usize)),
+            None => Err(anyhow!("Pointer target not in global memory.")),
+        }
+    }
+
+    /// Check whether the given address points to an executable segment in the runtime memory image.
+    ///
+    /// If the memory image has separate address spaces, the address is interpreted as a code address.
+    /// Returns an error if the address does not point to global memory.
+    pub fn is_address_executable(&self, address: &Bitvector) -> Result<bool, Error> {
+        let address = address.try_to_u64().unwrap();
+        match self.get_segment(address, 1, AddressSpace::Code) {
+            Some(segment) => Ok(segment.execute_flag),
+            None => Err(anyhow!("Address not contained in runtime memory image")),
         }
-        Err(anyhow!("Pointer target not in global memory."))
     }
 
     /// Check whether the given address points to a writeable segment in the runtime memory image.
     ///
+    /// If the memory image has separate address spaces, the address is interpreted as a data address.
     /// Returns an error if the address does not point to global memory.
     pub fn is_address_writeable(&self, address: &Bitvector) -> Result<bool, Error> {
         let address = address.try_to_u64().unwrap();
-        for segment in self.memory_segments.iter() {
-            if address >= segment.base_address
-                && address < segment.base_address + segment.bytes.len() as u64
-            {
-                return Ok(segment.write_flag);
+        match self.get_segment(address, 1, AddressSpace::Data) {
+            Some(segment) => Ok(segment.write_flag),
+            None => Err(anyhow!("Address not contained in runtime memory image")),
+        }
+    }
+}
+
+/// Get the addresses of all functions referenced in the `.init_array` section of an ELF file,
+/// i.e. the addresses of the constructors that get called before the entry function of the binary.
+///
+/// The addresses are returned as specified in the binary, i.e. without any offset added by Ghidra.
+pub fn get_init_array_function_addresses(binary: &[u8]) -> Result<Vec<u64>, Error> {
+    let elf_file = match Object::parse(binary)? {
+        Object::Elf(elf_file) => elf_file,
+        _ => return Err(anyhow!("Binary type not supported")),
+    };
+    let pointer_size: usize = if elf_file.is_64 { 8 } else { 4 };
+    for header in elf_file.section_headers.iter() {
+        if let Some(Ok(".init_array")) = elf_file.shdr_strtab.get(header.sh_name) {
+            let start = header.sh_offset as usize;
+            let end = start + header.sh_size as usize;
+            if end > binary.len() {
+                return Err(anyhow!(".init_array section out of bounds"));
             }
+            let addresses = binary[start..end]
+                .chunks_exact(pointer_size)
+                .map(|bytes| {
+                    let mut bytes = bytes.to_vec();
+                    if elf_file.little_endian {
+                        bytes.reverse();
+                    }
+                    bytes
+                        .into_iter()
+                        .fold(0u64, |value, byte| (value << 8) | byte as u64)
+                })
+                // Skip the sentinel values `0` and `-1` that some linkers insert.
+                .filter(|address| *address != 0 && *address != u64::MAX >> (64 - pointer_size * 8))
+                .collect();
+            return Ok(addresses);
         }
-        Err(anyhow!("Address not contained in runtime memory image"))
+    }
+    Ok(Vec::new())
+}
+
+/// Returns `true` if the binary is a shared library, i.e. an ELF shared object or a PE DLL.
+///
+/// Position-independent ELF executables are also of type `ET_DYN`,
+/// but in contrast to shared libraries they request a program interpreter.
+pub fn is_shared_library(binary: &[u8]) -> bool {
+    match Object::parse(binary) {
+        Ok(Object::Elf(elf_file)) => elf_file.is_lib && elf_file.interpreter.is_none(),
+        Ok(Object::PE(pe_file)) => pe_file.is_lib,
+        _ => false,
+    }
+}
+
+/// Get the addresses of all functions that are exported by an ELF or a PE file.
+///
+/// For ELF files these are all defined global or weak function symbols in the dynamic symbol table.
+/// For PE files these are all exports that are not forwarded to other DLLs.
+///
+/// The addresses are returned as specified in the binary, i.e. without any offset added by Ghidra.
+pub fn get_exported_function_addresses(binary: &[u8]) -> Result<Vec<u64>, Error> {
+    use goblin::elf::sym::{STB_GLOBAL, STB_WEAK, STT_FUNC};
+    match Object::parse(binary)? {
+        Object::Elf(elf_file) => Ok(elf_file
+            .dynsyms
+            .iter()
+            .filter(|symbol| {
+                symbol.st_type() == STT_FUNC
+                    && (symbol.st_bind() == STB_GLOBAL || symbol.st_bind() == STB_WEAK)
+                    && !symbol.is_import()
+                    && symbol.st_value != 0
+            })
+            .map(|symbol| symbol.st_value)
+            .collect()),
+        Object::PE(pe_file) => Ok(pe_file
+            .exports
+            .iter()
+            .filter(|export| export.reexport.is_none() && export.rva != 0)
+            .map(|export| pe_file.image_base as u64 + export.rva as u64)
+            .collect()),
+        _ => Err(anyhow!("Binary type not supported")),
     }
 }
 
@@ -234,6 +496,7 @@ pub mod tests {
                     },
                 ],
                 is_little_endian: true,
+                has_separate_address_spaces: false,
             }
         }
     }
@@ -261,4 +524,47 @@ pub mod tests {
         assert_eq!(index, 2);
         assert_eq!(&slice[index..], &[0xb2u8, 0xb3, 0xb4]);
     }
+
+    #[test]
+    fn negative_global_memory_offset() {
+        let mut mem_image = RuntimeMemoryImage::mock();
+        mem_image.add_global_memory_offset(0u64.wrapping_sub(0x800));
+        let address = Bitvector::from_u32(0x801);
+        assert_eq!(
+            mem_image.read(&address, ByteSize::new(1)).unwrap(),
+            Bitvector::from_u8(0xb1).into()
+        );
+    }
+
+    #[test]
+    fn separate_address_spaces() {
+        let mut mem_image = RuntimeMemoryImage::mock();
+        mem_image.memory_segments.push(MemorySegment {
+            bytes: [0xc0u8, 0xc1, 0xc2, 0xc3].to_vec(),
+            base_address: 0x1000,
+            read_flag: true,
+            write_flag: false,
+            execute_flag: true,
+        });
+        mem_image.memory_segments[0].base_address = 0x801000;
+        mem_image.memory_segments[1].base_address = 0x802000;
+        mem_image.separate_address_spaces(0x800000);
+        assert!(mem_image.has_separate_address_spaces());
+
+        // Data accesses only see the data segments.
+        let address = Bitvector::from_u32(0x1001);
+        assert_eq!(
+            mem_image.read(&address, ByteSize::new(2)).unwrap(),
+            Bitvector::from_u16(0xb2b1).into()
+        );
+        assert!(!mem_image.is_address_writeable(&address).unwrap());
+        assert!(mem_image
+            .is_address_writeable(&Bitvector::from_u32(0x2000))
+            .unwrap());
+        // Code accesses only see the code segment.
+        assert!(mem_image.is_address_executable(&address).unwrap());
+        assert!(mem_image
+            .is_address_executable(&Bitvector::from_u32(0x2000))
+            .is_err());
+    }
 }
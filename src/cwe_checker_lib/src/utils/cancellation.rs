@@ -0,0 +1,121 @@
+//! Cancellation tokens for aborting analyses cleanly.
+//!
+//! A [`CancellationToken`] is shared between the code running an analysis and the code that may want to abort it
+//! (e.g. another thread of a program embedding the cwe_checker library).
+//! Long-running computations like fixpoint computations check the token regularly
+//! and stop early if it was cancelled, so that partial results and logs can still be returned.
+//!
+//! Tokens can be structured hierarchically:
+//! A child token is cancelled whenever its parent is cancelled,
+//! but cancelling a child does not affect its parent.
+//! Child tokens may additionally carry a deadline after which they count as cancelled,
+//! which allows setting timeouts for single analysis phases.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// A token signalling that an analysis should be aborted.
+///
+/// Clones of a token share the same cancellation state.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+    parent: Option<Box<CancellationToken>>,
+}
+
+impl CancellationToken {
+    /// Create a new token that is not cancelled.
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Cancel the token and all its children.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if the token or one of its parents was cancelled
+    /// or if the deadline of the token or one of its parents has expired.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+            || self
+                .deadline
+                .map(|deadline| Instant::now() >= deadline)
+                .unwrap_or(false)
+            || self
+                .parent
+                .as_ref()
+                .map(|parent| parent.is_cancelled())
+                .unwrap_or(false)
+    }
+
+    /// Get the earliest deadline of the token and its parents.
+    /// Returns `None` if neither the token nor its parents have a deadline.
+    pub fn get_deadline(&self) -> Option<Instant> {
+        let parent_deadline = self
+            .parent
+            .as_ref()
+            .and_then(|parent| parent.get_deadline());
+        match (self.deadline, parent_deadline) {
+            (Some(deadline), Some(parent_deadline)) => Some(deadline.min(parent_deadline)),
+            (deadline, parent_deadline) => deadline.or(parent_deadline),
+        }
+    }
+
+    /// Create a child token that is cancelled whenever `self` is cancelled.
+    pub fn child(&self) -> CancellationToken {
+        CancellationToken {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            deadline: None,
+            parent: Some(Box::new(self.clone())),
+        }
+    }
+
+    /// Create a child token that is cancelled whenever `self` is cancelled
+    /// and additionally counts as cancelled after the given deadline.
+    pub fn child_with_deadline(&self, deadline: Instant) -> CancellationToken {
+        CancellationToken {
+            deadline: Some(deadline),
+            ..self.child()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancellation() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        let child = token.child();
+        assert!(!child.is_cancelled());
+        child.cancel();
+        assert!(child.is_cancelled());
+        assert!(!token.is_cancelled());
+
+        let other_child = token.child();
+        clone.cancel();
+        assert!(token.is_cancelled());
+        assert!(other_child.is_cancelled());
+    }
+
+    #[test]
+    fn deadline() {
+        let token = CancellationToken::new();
+        let expired = token.child_with_deadline(Instant::now());
+        assert!(expired.is_cancelled());
+        assert!(!token.is_cancelled());
+        let later =
+            token.child_with_deadline(Instant::now() + std::time::Duration::from_secs(3600));
+        assert!(!later.is_cancelled());
+        assert_eq!(token.get_deadline(), None);
+        let earlier =
+            later.child_with_deadline(Instant::now() + std::time::Duration::from_secs(60));
+        assert!(earlier.get_deadline().unwrap() < later.get_deadline().unwrap());
+        assert_eq!(earlier.child().get_deadline(), earlier.get_deadline());
+    }
+}
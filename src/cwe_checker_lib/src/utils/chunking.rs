@@ -0,0 +1,276 @@
+//! Chunked analysis of very large binaries along the boundaries of compilation units.
+//!
+//! Statically linked images may contain hundreds of thousands of functions,
+//! which exceeds any practical budget for a single pass of the interprocedural analyses.
+//! If the symbol table of an ELF file contains `STT_FILE` symbols marking the compilation units
+//! (or the members of statically linked libraries),
+//! the functions of a large binary are grouped into chunks of consecutive compilation units.
+//!
+//! Each chunk is analyzed separately.
+//! The functions of the other chunks are replaced by extern symbols.
+//! No summaries of these functions are computed,
+//! so calls across chunk boundaries are treated like calls to unknown extern functions
+//! (see [`crate::utils::extern_stubs`]).
+//! In particular, side effects of the callee and values flowing between chunks are lost,
+//! so warnings depending on them may be missed.
+//! Functions of a chunk that are called from other chunks become entry points of the chunk.
+//! The warnings of all chunks are merged afterwards.
+//!
+//! Checks that scan the whole binary instead of the analyzed functions (see [`BINARY_WIDE_MODULES`])
+//! are not run per chunk but only once on the whole program.
+//!
+//! Chunking is configured in the `Chunking` section of the configuration file.
+
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::address_ranges::remove_functions;
+use goblin::elf::sym::{STB_LOCAL, STT_FILE, STT_FUNC};
+use std::collections::HashSet;
+
+/// The names of the check modules that do not depend on the control flow graph of the analyzed functions.
+///
+/// They scan the binary file, the memory image or all functions of the project,
+/// so they would generate the same warnings for each chunk.
+/// When analyzing in chunks, they run only once on the whole program.
+pub const BINARY_WIDE_MODULES: [&str; 4] = ["CWE1395", "CWE215", "CWE321", "Packer"];
+
+/// The configuration of the chunked analysis.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+#[serde(default)]
+pub struct Config {
+    /// Binaries with at least this number of functions are analyzed in chunks.
+    /// Chunking is disabled if the value is zero.
+    pub min_functions: usize,
+    /// The maximal number of functions of a chunk.
+    /// A compilation unit with more functions forms a chunk of its own.
+    pub max_functions_per_chunk: usize,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            min_functions: 50_000,
+            max_functions_per_chunk: 10_000,
+        }
+    }
+}
+
+/// A compilation unit of a binary as given by the symbol table.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct CompilationUnit {
+    /// The name of the source file or of the library member.
+    pub name: String,
+    /// The lowest address of a function of the compilation unit as specified in the binary.
+    pub start: u64,
+}
+
+/// A part of the program that is analyzed separately.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Chunk {
+    /// The names of the compilation units contained in the chunk.
+    pub compilation_units: Vec<String>,
+    /// The functions of the chunk.
+    pub functions: HashSet<Tid>,
+}
+
+/// Get the compilation units of the binary sorted by their start addresses.
+///
+/// The compilation units are read from the `STT_FILE` symbols of the symbol table of an ELF file.
+/// Each `STT_FILE` symbol is followed by the local symbols of its compilation unit,
+/// so the start of a compilation unit is the lowest address of the local functions following its symbol.
+/// Compilation units without local functions are omitted.
+/// Returns an empty list for other file formats and for stripped binaries.
+pub fn get_compilation_units(binary: &[u8]) -> Result<Vec<CompilationUnit>, Error> {
+    let elf_file = match goblin::Object::parse(binary)? {
+        goblin::Object::Elf(elf_file) => elf_file,
+        _ => return Ok(Vec::new()),
+    };
+    let mut units: Vec<CompilationUnit> = Vec::new();
+    for symbol in elf_file.syms.iter() {
+        if symbol.st_type() == STT_FILE {
+            let name = match elf_file.strtab.get(symbol.st_name) {
+                Some(Ok(name)) => name.to_string(),
+                _ => continue,
+            };
+            units.push(CompilationUnit {
+                name,
+                start: u64::MAX,
+            });
+        } else if symbol.st_type() == STT_FUNC
+            && symbol.st_bind() == STB_LOCAL
+            && symbol.st_value != 0
+        {
+            if let Some(unit) = units.last_mut() {
+                unit.start = unit.start.min(symbol.st_value);
+            }
+        }
+    }
+    units.retain(|unit| unit.start != u64::MAX);
+    units.sort_by_key(|unit| unit.start);
+    Ok(units)
+}
+
+impl Config {
+    /// Group the functions of the project into chunks of consecutive compilation units.
+    ///
+    /// Each function belongs to the compilation unit with the highest start address not above the function address.
+    /// Functions before the first compilation unit or with unknown addresses belong to the first compilation unit.
+    /// Returns no chunks if the program is too small for chunking
+    /// or if the compilation units do not split the program into at least two chunks.
+    pub fn get_chunks(&self, project: &Project, units: &[CompilationUnit]) -> Vec<Chunk> {
+        let subs = &project.program.term.subs;
+        if self.min_functions == 0 || subs.len() < self.min_functions || units.len() < 2 {
+            return Vec::new();
+        }
+        let base_offset = project.program.term.address_base_offset;
+        let mut functions_of_units: Vec<Vec<&Tid>> = vec![Vec::new(); units.len()];
+        for sub in subs.iter() {
+            let unit_index = match u64::from_str_radix(&sub.tid.address, 16) {
+                Ok(address) => match units
+                    .binary_search_by_key(&address.wrapping_sub(base_offset), |unit| unit.start)
+                {
+                    Ok(index) => index,
+                    Err(0) => 0,
+                    Err(index) => index - 1,
+                },
+                Err(_) => 0,
+            };
+            functions_of_units[unit_index].push(&sub.tid);
+        }
+        let mut chunks: Vec<Chunk> = Vec::new();
+        let mut current_chunk = Chunk::default();
+        for (unit, functions) in units.iter().zip(functions_of_units) {
+            if functions.is_empty() {
+                continue;
+            }
+            if !current_chunk.functions.is_empty()
+                && current_chunk.functions.len() + functions.len() > self.max_functions_per_chunk
+            {
+                chunks.push(std::mem::take(&mut current_chunk));
+            }
+            current_chunk.compilation_units.push(unit.name.clone());
+            current_chunk
+                .functions
+                .extend(functions.into_iter().cloned());
+        }
+        if !current_chunk.functions.is_empty() {
+            chunks.push(current_chunk);
+        }
+        if chunks.len() < 2 {
+            return Vec::new();
+        }
+        chunks
+    }
+}
+
+/// Get a copy of the project restricted to the functions of the chunk.
+///
+/// The functions of other chunks are replaced by extern symbols
+/// and the functions of the chunk called from other chunks are added to the entry points.
+pub fn restrict_project_to_chunk(project: &Project, chunk: &Chunk) -> Project {
+    let mut chunk_project = project.clone();
+    let removed_subs =
+        remove_functions(&mut chunk_project, |sub| chunk.functions.contains(&sub.tid));
+    let mut entry_points: HashSet<Tid> = chunk_project
+        .program
+        .term
+        .entry_points
+        .iter()
+        .cloned()
+        .collect();
+    for sub in removed_subs.iter() {
+        for block in sub.term.blocks.iter() {
+            for jmp in block.term.jmps.iter() {
+                if let Jmp::Call { target, .. } = &jmp.term {
+                    if chunk.functions.contains(target) && entry_points.insert(target.clone()) {
+                        chunk_project.program.term.entry_points.push(target.clone());
+                    }
+                }
+            }
+        }
+    }
+    chunk_project
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_project() -> Project {
+        let mut project = Project::mock_empty();
+        for (name, address, callee) in [
+            ("main", "00001000", Some("parse")),
+            ("parse", "00002000", None),
+            ("helper", "00002100", None),
+            ("unknown", "UNKNOWN", None),
+        ]
+        .iter()
+        {
+            let mut sub = Sub::mock(*name);
+            sub.tid.address = address.to_string();
+            if let Some(callee) = callee {
+                sub.term.blocks.push(Term {
+                    tid: Tid::new(format!("blk_{}", name)),
+                    term: Blk {
+                        defs: Vec::new(),
+                        jmps: vec![Term {
+                            tid: Tid::new(format!("call_{}", name)),
+                            term: Jmp::Call {
+                                target: Tid::new(*callee),
+                                return_: None,
+                            },
+                        }],
+                    },
+                });
+            }
+            project.program.term.subs.push(sub);
+        }
+        project.program.term.entry_points = vec![Tid::new("main")];
+        project
+    }
+
+    #[test]
+    fn chunk_generation() {
+        let project = mock_project();
+        let units = vec![
+            CompilationUnit {
+                name: "main.c".to_string(),
+                start: 0x1000,
+            },
+            CompilationUnit {
+                name: "parser.c".to_string(),
+                start: 0x2000,
+            },
+        ];
+        let config = Config {
+            min_functions: 2,
+            max_functions_per_chunk: 2,
+        };
+        let chunks = config.get_chunks(&project, &units);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].compilation_units, vec!["main.c".to_string()]);
+        assert!(chunks[0].functions.contains(&Tid::new("unknown")));
+        assert_eq!(chunks[1].functions.len(), 2);
+
+        let chunk_project = restrict_project_to_chunk(&project, &chunks[1]);
+        assert_eq!(chunk_project.program.term.subs.len(), 2);
+        assert_eq!(
+            chunk_project.program.term.entry_points,
+            vec![Tid::new("parse")]
+        );
+        assert!(chunk_project
+            .program
+            .term
+            .extern_symbols
+            .iter()
+            .any(|symbol| symbol.name == "main"));
+
+        // All functions fit into one chunk, so the binary is not chunked.
+        let config = Config {
+            min_functions: 2,
+            max_functions_per_chunk: 10,
+        };
+        assert!(config.get_chunks(&project, &units).is_empty());
+        assert!(Config::default().get_chunks(&project, &units).is_empty());
+    }
+}
@@ -0,0 +1,231 @@
+//! Tag parts of a binary as belonging to named components.
+//!
+//! Firmware binaries often bundle code from several sources,
+//! e.g. statically linked libraries or a vendor SDK.
+//! To route the findings to the responsible owners,
+//! address ranges and function name patterns can be assigned to named components in the configuration.
+//! Each warning is then annotated with the first component in the configuration that its location belongs to.
+//!
+//! The location of a warning is its first address.
+//! Function name patterns are matched against the name of the function containing this address
+//! and may contain `*` as a wildcard matching arbitrary (possibly empty) substrings, e.g. `inflate*`.
+
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::address_ranges::AddressRange;
+use crate::utils::log::CweWarning;
+use std::collections::{HashMap, HashSet};
+
+/// The configuration of a single component.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct ComponentConfig {
+    /// The name of the component, e.g. `zlib`.
+    pub name: String,
+    /// The address ranges belonging to the component, given as strings of the form `0x400000-0x408000`.
+    #[serde(default)]
+    pub ranges: Vec<String>,
+    /// Name patterns of the functions belonging to the component.
+    #[serde(default)]
+    pub functions: Vec<String>,
+}
+
+/// The configured components.
+///
+/// The configuration is read from the `Components` section of the configuration file.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+#[serde(default)]
+pub struct Config {
+    /// The components in the order in which they are matched against the warnings.
+    pub components: Vec<ComponentConfig>,
+}
+
+/// A component with parsed address ranges.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+struct Component {
+    name: String,
+    ranges: Vec<AddressRange>,
+    functions: Vec<String>,
+}
+
+impl Component {
+    /// Returns `true` if the address or the name of the function containing it belong to the component.
+    fn contains(&self, address: Option<u64>, function_name: Option<&str>) -> bool {
+        if let Some(address) = address {
+            if self.ranges.iter().any(|range| range.contains(address)) {
+                return true;
+            }
+        }
+        if let Some(function_name) = function_name {
+            if self
+                .functions
+                .iter()
+                .any(|pattern| matches_pattern(pattern, function_name))
+            {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Check whether the name matches the pattern,
+/// where `*` in the pattern matches arbitrary (possibly empty) substrings.
+pub(crate) fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    if !name.starts_with(first) {
+        return false;
+    }
+    let mut remaining = &name[first.len()..];
+    let parts: Vec<&str> = parts.collect();
+    let last = match parts.last() {
+        Some(last) => *last,
+        // The pattern contains no wildcard.
+        None => return remaining.is_empty(),
+    };
+    for part in parts[..parts.len() - 1].iter() {
+        match remaining.find(part) {
+            Some(index) => remaining = &remaining[index + part.len()..],
+            None => return false,
+        }
+    }
+    remaining.ends_with(last)
+}
+
+/// The components of a binary.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct Components {
+    components: Vec<Component>,
+}
+
+impl Components {
+    /// Parse the address ranges of the configured components.
+    pub fn new(config: &Config) -> Result<Components, Error> {
+        let mut components = Vec::new();
+        for component in config.components.iter() {
+            components.push(Component {
+                name: component.name.clone(),
+                ranges: component
+                    .ranges
+                    .iter()
+                    .map(|range| range.parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|err| anyhow!("Component {}: {}", component.name, err))?,
+                functions: component.functions.clone(),
+            });
+        }
+        Ok(Components { components })
+    }
+
+    /// Set the component of each warning to the first configured component that contains its location.
+    /// Warnings not belonging to any component are left unchanged.
+    pub fn annotate_warnings(&self, project: &Project, warnings: &mut [CweWarning]) {
+        if self.components.is_empty() {
+            return;
+        }
+        let base_offset = project.program.term.address_base_offset;
+        let function_names = get_function_names(project, warnings);
+        for warning in warnings.iter_mut() {
+            let address = warning.addresses.get(0);
+            let function_name = address
+                .and_then(|address| function_names.get(address.as_str()))
+                .cloned();
+            let address = address
+                .and_then(|address| u64::from_str_radix(address, 16).ok())
+                .map(|address| address.wrapping_sub(base_offset));
+            if let Some(component) = self
+                .components
+                .iter()
+                .find(|component| component.contains(address, function_name))
+            {
+                warning.component = Some(component.name.clone());
+            }
+        }
+    }
+}
+
+/// Map the first address of each warning to the name of the function containing an instruction at this address.
+fn get_function_names<'a>(
+    project: &'a Project,
+    warnings: &[CweWarning],
+) -> HashMap<&'a str, &'a str> {
+    let addresses: HashSet<&str> = warnings
+        .iter()
+        .filter_map(|warning| warning.addresses.get(0))
+        .map(|address| address.as_str())
+        .collect();
+    let mut function_names = HashMap::new();
+    for sub in project.program.term.subs.iter() {
+        for block in sub.term.blocks.iter() {
+            let term_addresses = block
+                .term
+                .defs
+                .iter()
+                .map(|def| &def.tid.address)
+                .chain(block.term.jmps.iter().map(|jmp| &jmp.tid.address));
+            for address in term_addresses {
+                if addresses.contains(address.as_str()) {
+                    function_names
+                        .entry(address.as_str())
+                        .or_insert_with(|| sub.term.name.as_str());
+                }
+            }
+        }
+    }
+    function_names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pattern_matching() {
+        assert!(matches_pattern("inflate", "inflate"));
+        assert!(!matches_pattern("inflate", "inflateInit"));
+        assert!(matches_pattern("inflate*", "inflateInit"));
+        assert!(matches_pattern("*_sdk_*", "vendor_sdk_init"));
+        assert!(matches_pattern("a*b*c", "abc"));
+        assert!(!matches_pattern("a*b*c", "acb"));
+        assert!(matches_pattern("*", "main"));
+    }
+
+    #[test]
+    fn warning_annotation() {
+        let mut project = Project::mock_empty();
+        let mut sub = Sub::mock("inflateInit");
+        let mut block = Blk::mock();
+        let mut jmp = Term {
+            tid: Tid::new("jmp"),
+            term: Jmp::Branch(Tid::new("block")),
+        };
+        jmp.tid.address = "00001010".to_string();
+        block.term.jmps.push(jmp);
+        sub.term.blocks.push(block);
+        project.program.term.subs.push(sub);
+        let components = Components::new(&Config {
+            components: vec![
+                ComponentConfig {
+                    name: "vendor SDK".to_string(),
+                    ranges: vec!["0x8000-0x9000".to_string()],
+                    functions: Vec::new(),
+                },
+                ComponentConfig {
+                    name: "zlib".to_string(),
+                    ranges: Vec::new(),
+                    functions: vec!["inflate*".to_string()],
+                },
+            ],
+        })
+        .unwrap();
+        let mut warnings = vec![
+            CweWarning::new("CWE000", "0.1", "zlib").addresses(vec!["00001010".to_string()]),
+            CweWarning::new("CWE000", "0.1", "sdk").addresses(vec!["00008004".to_string()]),
+            CweWarning::new("CWE000", "0.1", "none").addresses(vec!["00002000".to_string()]),
+        ];
+        components.annotate_warnings(&project, &mut warnings);
+        assert_eq!(warnings[0].component.as_deref(), Some("zlib"));
+        assert_eq!(warnings[1].component.as_deref(), Some("vendor SDK"));
+        assert_eq!(warnings[2].component, None);
+    }
+}
@@ -0,0 +1,517 @@
+//! Validation of the configuration before the analysis.
+//!
+//! The checks deserialize their configuration sections only when they are run,
+//! so that errors in the configuration file would otherwise surface as a panic deep inside a check.
+//! The configuration is therefore validated against the expected structure before the analysis starts:
+//! - The sections and keys of the default configuration (see [`default_config`](super::default_config))
+//! determine the known keys and the expected types of their values.
+//! - The configuration keys of the modules (see [`CweModule::config_keys`](crate::CweModule))
+//! determine the keys that have to be present in the sections of the modules to be run.
+//! - The sections of the modules to be run are deserialized into the configuration types of the modules,
+//! which catches invalid values of the correct JSON type (e.g. a fraction where an integer is expected).
+//!
+//! Keys starting with an underscore (e.g. `_comment`) are ignored.
+
+use super::default_config::get_default_config;
+use crate::CweModule;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// Keys that may be missing or whose value may be `null` to denote that no value is given, given as `(section, key)`.
+///
+/// The `extern_stubs` key of the `Memory` section is copied from the `ExternStubs` section during the analysis.
+const OPTIONAL_KEYS: &[(&str, &str)] = &[
+    ("CWE1395", "cve_mapping_file"),
+    ("Memory", "extern_stubs"),
+    ("Memory", "max_pointer_targets"),
+    ("ResourceBudget", "max_memory_mb"),
+    ("ResourceBudget", "max_states"),
+    ("ResourceBudget", "max_seconds_per_function"),
+];
+
+/// The maximal edit distance of a known key to an unknown key for the known key to be suggested as a correction.
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+/// The kinds of problems found in a configuration.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ConfigIssueKind {
+    /// The configuration section of a module to be run is missing.
+    MissingSection,
+    /// A key read by a module is missing in its configuration section.
+    MissingKey,
+    /// The section is neither contained in the default configuration nor the section of a module.
+    UnknownSection { suggestion: Option<String> },
+    /// The key is not contained in the section of the default configuration.
+    UnknownKey { suggestion: Option<String> },
+    /// The value has a different type than the corresponding value of the default configuration.
+    WrongType {
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// The section of a module cannot be deserialized into the configuration type of the module.
+    InvalidSection { error: String },
+}
+
+/// A problem found in a configuration.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ConfigIssue {
+    /// The name of the module whose configuration section contains the problem.
+    /// `None` for sections that do not belong to a module.
+    pub module: Option<String>,
+    /// The path to the problematic section, key or value, e.g. `CWE78.system_symbols[0]`.
+    pub key_path: String,
+    /// The kind of the problem.
+    pub kind: ConfigIssueKind,
+}
+
+impl ConfigIssue {
+    /// Return whether the problem makes the analysis fail or silently use default values.
+    /// Unknown sections and keys are ignored by the analyses and are thus not fatal.
+    pub fn is_fatal(&self) -> bool {
+        !matches!(
+            self.kind,
+            ConfigIssueKind::UnknownSection { .. } | ConfigIssueKind::UnknownKey { .. }
+        )
+    }
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(module) = &self.module {
+            write!(formatter, "Module {}: ", module)?;
+        }
+        let suggestion = match &self.kind {
+            ConfigIssueKind::MissingSection => {
+                write!(formatter, "Missing configuration section {}", self.key_path)?;
+                None
+            }
+            ConfigIssueKind::MissingKey => {
+                write!(formatter, "Missing key {}", self.key_path)?;
+                None
+            }
+            ConfigIssueKind::UnknownSection { suggestion } => {
+                write!(formatter, "Unknown section {}", self.key_path)?;
+                suggestion.as_ref()
+            }
+            ConfigIssueKind::UnknownKey { suggestion } => {
+                write!(formatter, "Unknown key {}", self.key_path)?;
+                suggestion.as_ref()
+            }
+            ConfigIssueKind::WrongType { expected, found } => {
+                write!(
+                    formatter,
+                    "Wrong type of {}: expected {}, found {}",
+                    self.key_path, expected, found
+                )?;
+                None
+            }
+            ConfigIssueKind::InvalidSection { error } => {
+                write!(formatter, "Invalid section {}: {}", self.key_path, error)?;
+                None
+            }
+        };
+        if let Some(suggestion) = suggestion {
+            write!(formatter, " (did you mean {}?)", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// Validate the configuration against the default configuration and the configuration keys of the given modules.
+///
+/// Missing sections and keys are only reported for the given modules, i.e. the modules to be run.
+/// The sections of other modules are not validated.
+pub fn validate_config(config: &Value, modules: &[&CweModule]) -> Vec<ConfigIssue> {
+    let config = match config.as_object() {
+        Some(config) => config,
+        None => {
+            return vec![ConfigIssue {
+                module: None,
+                key_path: "configuration".to_string(),
+                kind: ConfigIssueKind::WrongType {
+                    expected: type_name(&Value::Object(Default::default())),
+                    found: type_name(config),
+                },
+            }]
+        }
+    };
+    let defaults = get_default_config();
+    let all_modules = crate::get_modules();
+    let mut issues = Vec::new();
+    for module in modules.iter() {
+        if !module.config_keys.is_empty() && !config.contains_key(module.name) {
+            issues.push(ConfigIssue {
+                module: Some(module.name.to_string()),
+                key_path: module.name.to_string(),
+                kind: ConfigIssueKind::MissingSection,
+            });
+        }
+    }
+    let known_sections: Vec<&str> = defaults
+        .as_object()
+        .into_iter()
+        .flat_map(|defaults| defaults.keys().map(|key| key.as_str()))
+        .chain(all_modules.iter().map(|module| module.name))
+        .collect();
+    for (section_name, section) in config.iter() {
+        if section_name.starts_with('_') {
+            continue;
+        }
+        if !known_sections.contains(&section_name.as_str()) {
+            issues.push(ConfigIssue {
+                module: None,
+                key_path: section_name.clone(),
+                kind: ConfigIssueKind::UnknownSection {
+                    suggestion: get_suggestion(section_name, &known_sections),
+                },
+            });
+            continue;
+        }
+        let module = all_modules
+            .iter()
+            .find(|module| module.name == section_name);
+        if let Some(module) = module {
+            if !modules
+                .iter()
+                .any(|run_module| run_module.name == module.name)
+            {
+                continue;
+            }
+        }
+        let mut section_issues = validate_section(
+            section_name,
+            section,
+            &defaults[section_name],
+            module.map(|module| module.config_keys).unwrap_or_default(),
+            module.map(|module| module.name.to_string()),
+        );
+        // Only sections without other fatal problems are deserialized to avoid reporting the same problem twice.
+        if let Some(module) = module {
+            if !section_issues.iter().any(|issue| issue.is_fatal()) {
+                if let Some(Err(err)) = module.validate_config.map(|validate| validate(section)) {
+                    section_issues.push(ConfigIssue {
+                        module: Some(module.name.to_string()),
+                        key_path: section_name.clone(),
+                        kind: ConfigIssueKind::InvalidSection {
+                            error: err.to_string(),
+                        },
+                    });
+                }
+            }
+        }
+        issues.append(&mut section_issues);
+    }
+    issues
+}
+
+/// Deserialize the configuration section of a module into the configuration type `T` of the module.
+///
+/// Modules with a configuration type use this function as their [`CweModule::validate_config`] function.
+pub fn deserialize_section<T: DeserializeOwned>(section: &Value) -> Result<(), serde_json::Error> {
+    serde_json::from_value::<T>(section.clone()).map(|_| ())
+}
+
+/// Validate a configuration section against the section of the default configuration
+/// and check that the required keys are present.
+fn validate_section(
+    section_name: &str,
+    section: &Value,
+    default_section: &Value,
+    required_keys: &[&str],
+    module: Option<String>,
+) -> Vec<ConfigIssue> {
+    let new_issue = |key_path: String, kind: ConfigIssueKind| ConfigIssue {
+        module: module.clone(),
+        key_path,
+        kind,
+    };
+    let section = match section.as_object() {
+        Some(section) => section,
+        None => {
+            return vec![new_issue(
+                section_name.to_string(),
+                ConfigIssueKind::WrongType {
+                    expected: type_name(&Value::Object(Default::default())),
+                    found: type_name(section),
+                },
+            )]
+        }
+    };
+    let mut issues = Vec::new();
+    for key in required_keys.iter() {
+        if !section.contains_key(*key) && !OPTIONAL_KEYS.contains(&(section_name, *key)) {
+            issues.push(new_issue(
+                format!("{}.{}", section_name, key),
+                ConfigIssueKind::MissingKey,
+            ));
+        }
+    }
+    let known_keys: Vec<&str> = default_section
+        .as_object()
+        .into_iter()
+        .flat_map(|default_section| default_section.keys().map(|key| key.as_str()))
+        .chain(required_keys.iter().copied())
+        .collect();
+    let mut mismatches = Vec::new();
+    for (key, value) in section.iter() {
+        if key.starts_with('_') {
+            continue;
+        }
+        let key_path = format!("{}.{}", section_name, key);
+        match default_section.get(key) {
+            Some(_) if value.is_null() && OPTIONAL_KEYS.contains(&(section_name, key.as_str())) => {
+            }
+            Some(default_value) => check_type(key_path, value, default_value, &mut mismatches),
+            None if known_keys.contains(&key.as_str()) => (),
+            None => issues.push(new_issue(
+                key_path,
+                ConfigIssueKind::UnknownKey {
+                    suggestion: get_suggestion(key, &known_keys),
+                },
+            )),
+        }
+    }
+    issues.extend(mismatches.into_iter().map(|(key_path, expected, found)| {
+        new_issue(key_path, ConfigIssueKind::WrongType { expected, found })
+    }));
+    issues
+}
+
+/// Check that the value has the same type as the expected value.
+/// The elements of arrays and the values of objects are checked recursively.
+/// Mismatches are added to `mismatches` as `(key path, expected type, found type)`.
+///
+/// Objects may be maps indexed by user-defined names (e.g. symbol names).
+/// Therefore unknown keys of nested objects are not reported,
+/// but their values are checked if all values of the expected object have the same type.
+/// A `null` in the default configuration accepts values of any type.
+fn check_type(
+    key_path: String,
+    value: &Value,
+    expected: &Value,
+    mismatches: &mut Vec<(String, &'static str, &'static str)>,
+) {
+    if expected.is_null() {
+        return;
+    }
+    if type_name(value) != type_name(expected) {
+        mismatches.push((key_path, type_name(expected), type_name(value)));
+        return;
+    }
+    match (value, expected) {
+        (Value::Array(values), Value::Array(expected_values)) => {
+            if let Some(expected_value) = get_common_element(expected_values.iter()) {
+                for (index, value) in values.iter().enumerate() {
+                    check_type(
+                        format!("{}[{}]", key_path, index),
+                        value,
+                        expected_value,
+                        mismatches,
+                    );
+                }
+            }
+        }
+        (Value::Object(values), Value::Object(expected_values)) => {
+            let common_value = get_common_element(expected_values.values());
+            for (key, value) in values.iter() {
+                if key.starts_with('_') {
+                    continue;
+                }
+                if let Some(expected_value) = expected_values.get(key).or(common_value) {
+                    check_type(
+                        format!("{}.{}", key_path, key),
+                        value,
+                        expected_value,
+                        mismatches,
+                    );
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Return the first element if all elements have the same type.
+fn get_common_element<'a>(mut elements: impl Iterator<Item = &'a Value>) -> Option<&'a Value> {
+    let first = elements.next()?;
+    if elements.all(|element| type_name(element) == type_name(first)) {
+        Some(first)
+    } else {
+        None
+    }
+}
+
+/// Get a description of the type of a JSON value for error messages.
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+/// Get the known name closest to the unknown name if it is close enough to be a probable typo.
+fn get_suggestion(name: &str, known_names: &[&str]) -> Option<String> {
+    known_names
+        .iter()
+        .map(|known_name| {
+            (
+                edit_distance(&name.to_lowercase(), &known_name.to_lowercase()),
+                known_name,
+            )
+        })
+        .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, known_name)| known_name.to_string())
+}
+
+/// Compute the Levenshtein distance between two strings.
+fn edit_distance(left: &str, right: &str) -> usize {
+    let right: Vec<char> = right.chars().collect();
+    let mut distances: Vec<usize> = (0..=right.len()).collect();
+    for (i, left_char) in left.chars().enumerate() {
+        let mut previous_diagonal = distances[0];
+        distances[0] = i + 1;
+        for (j, right_char) in right.iter().enumerate() {
+            let substitution = previous_diagonal + if left_char == *right_char { 0 } else { 1 };
+            previous_diagonal = distances[j + 1];
+            distances[j + 1] = substitution
+                .min(distances[j] + 1)
+                .min(previous_diagonal + 1);
+        }
+    }
+    distances[right.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_module(name: &str) -> &'static CweModule {
+        crate::get_modules()
+            .into_iter()
+            .find(|module| module.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn default_config_is_valid() {
+        let issues = validate_config(&get_default_config(), &crate::get_modules());
+        assert_eq!(issues, Vec::new());
+    }
+
+    #[test]
+    fn modules_with_config_keys_validate_their_config() {
+        for module in crate::get_modules() {
+            assert_eq!(
+                module.config_keys.is_empty(),
+                module.validate_config.is_none(),
+                "{}",
+                module.name
+            );
+        }
+    }
+
+    #[test]
+    fn config_issues() {
+        let mut config = get_default_config();
+        let cwe_78 = config["CWE78"].as_object_mut().unwrap();
+        let system_symbols = cwe_78.remove("system_symbols").unwrap();
+        cwe_78.insert("system_symbol".to_string(), system_symbols);
+        config["CWE676"]["symbols"][1] = serde_json::json!(42);
+        config["ResourceBudget"]["max_states"] = Value::Null;
+        config["Chunking"]["min_functions"] = serde_json::json!("10");
+        config.as_object_mut().unwrap().remove("CWE190");
+        let modules = vec![
+            get_module("CWE78"),
+            get_module("CWE190"),
+            get_module("CWE676"),
+        ];
+        let issues = validate_config(&config, &modules);
+        assert_eq!(
+            issues,
+            vec![
+                ConfigIssue {
+                    module: Some("CWE190".to_string()),
+                    key_path: "CWE190".to_string(),
+                    kind: ConfigIssueKind::MissingSection,
+                },
+                ConfigIssue {
+                    module: Some("CWE676".to_string()),
+                    key_path: "CWE676.symbols[1]".to_string(),
+                    kind: ConfigIssueKind::WrongType {
+                        expected: "a string",
+                        found: "a number"
+                    },
+                },
+                ConfigIssue {
+                    module: Some("CWE78".to_string()),
+                    key_path: "CWE78.system_symbols".to_string(),
+                    kind: ConfigIssueKind::MissingKey,
+                },
+                ConfigIssue {
+                    module: Some("CWE78".to_string()),
+                    key_path: "CWE78.system_symbol".to_string(),
+                    kind: ConfigIssueKind::UnknownKey {
+                        suggestion: Some("system_symbols".to_string())
+                    },
+                },
+                ConfigIssue {
+                    module: None,
+                    key_path: "Chunking.min_functions".to_string(),
+                    kind: ConfigIssueKind::WrongType {
+                        expected: "a number",
+                        found: "a string"
+                    },
+                },
+            ]
+        );
+        assert!(!issues[3].is_fatal());
+        assert_eq!(
+            format!("{}", issues[1]),
+            "Module CWE676: Wrong type of CWE676.symbols[1]: expected a string, found a number"
+        );
+    }
+
+    #[test]
+    fn optional_keys_and_invalid_values() {
+        let mut config = get_default_config();
+        config["CWE1395"]
+            .as_object_mut()
+            .unwrap()
+            .remove("cve_mapping_file");
+        config["AnalysisGaps"]["min_cluster_size"] = serde_json::json!(2.5);
+        let issues = validate_config(
+            &config,
+            &[get_module("CWE1395"), get_module("AnalysisGaps")],
+        );
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].key_path, "AnalysisGaps");
+        assert!(matches!(
+            issues[0].kind,
+            ConfigIssueKind::InvalidSection { .. }
+        ));
+        assert!(issues[0].is_fatal());
+    }
+
+    #[test]
+    fn nested_maps() {
+        let mut config = get_default_config();
+        config["CWE686"]["printf_symbols"]["vsprintf"] = serde_json::json!("1");
+        config["Normalization"]["passes"]["unknown_pass"] = serde_json::json!(true);
+        config["Packerr"] = serde_json::json!({});
+        let issues = validate_config(&config, &[get_module("CWE686")]);
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].key_path, "CWE686.printf_symbols.vsprintf");
+        assert!(issues[0].is_fatal());
+        assert_eq!(
+            issues[1].kind,
+            ConfigIssueKind::UnknownSection {
+                suggestion: Some("Packer".to_string())
+            }
+        );
+    }
+}
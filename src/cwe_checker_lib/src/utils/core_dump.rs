@@ -0,0 +1,235 @@
+//! Analysis of binaries as they were loaded into the memory of a process, given by an ELF core dump.
+//!
+//! A core dump contains the memory mappings of a process at the time of the dump,
+//! e.g. the relocated global offset tables of the binary and the data of the loaded shared libraries.
+//! Together with the original binary the memory image of the process is reconstructed:
+//! The binary is rebased to the image base at which it was loaded into the process
+//! and the memory segments contained in the core dump take precedence over the segments of the binary file.
+//! Segments that are missing in the core dump (by default the kernel does not dump file-backed mappings)
+//! are taken from the binary file.
+
+use crate::prelude::*;
+use crate::utils::get_binary_base_address;
+use goblin::elf;
+
+/// The note type of the auxiliary vector of the process.
+const NT_AUXV: u32 = 6;
+/// The note type of the list of files mapped into the memory of the process.
+const NT_FILE: u32 = 0x4649_4c45;
+/// The auxiliary vector entry containing the entry point of the executable.
+const AT_ENTRY: u64 = 9;
+
+/// A file mapped into the memory of the process, as listed in the core dump.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct MappedFile {
+    /// The path of the file in the file system of the process.
+    pub path: String,
+    /// The start address of the mapping.
+    pub start: u64,
+    /// The end address (exclusive) of the mapping.
+    pub end: u64,
+    /// The offset of the mapping in the file.
+    pub file_offset: u64,
+}
+
+/// Returns `true` if the given file is an ELF core dump.
+pub fn is_core_dump(core: &[u8]) -> bool {
+    match elf::Elf::parse(core) {
+        Ok(core_file) => core_file.header.e_type == elf::header::ET_CORE,
+        Err(_) => false,
+    }
+}
+
+/// Get the image base at which the binary was loaded into the process of the core dump.
+///
+/// If a file with the given name was mapped into the process, the image base is the start of its first mapping.
+/// Otherwise the binary is assumed to be the executable of the process
+/// and the image base is computed from the entry point given in the auxiliary vector of the process.
+pub fn get_image_base(binary: &[u8], binary_name: Option<&str>, core: &[u8]) -> Result<u64, Error> {
+    if !is_core_dump(core) {
+        return Err(anyhow!("Not an ELF core dump"));
+    }
+    if let Some(binary_name) = binary_name {
+        let mapping = get_mapped_files(core)?.into_iter().find(|mapping| {
+            mapping.file_offset == 0
+                && std::path::Path::new(&mapping.path)
+                    .file_name()
+                    .map(|file_name| file_name.to_string_lossy() == binary_name)
+                    .unwrap_or(false)
+        });
+        if let Some(mapping) = mapping {
+            return Ok(mapping.start);
+        }
+    }
+    let binary_file = elf::Elf::parse(binary)?;
+    let (word_size, little_endian) = get_word_format(core)?;
+    let entry = get_notes(core)?
+        .into_iter()
+        .filter(|(note_type, _)| *note_type == NT_AUXV)
+        .find_map(|(_, desc)| get_auxv_value(&desc, AT_ENTRY, word_size, little_endian))
+        .ok_or_else(|| anyhow!("No entry point found in the core dump"))?;
+    let load_bias = entry.wrapping_sub(binary_file.entry);
+    Ok(get_binary_base_address(binary)?.wrapping_add(load_bias))
+}
+
+/// Get the files mapped into the memory of the process of the core dump.
+pub fn get_mapped_files(core: &[u8]) -> Result<Vec<MappedFile>, Error> {
+    let (word_size, little_endian) = get_word_format(core)?;
+    Ok(get_notes(core)?
+        .into_iter()
+        .filter(|(note_type, _)| *note_type == NT_FILE)
+        .filter_map(|(_, desc)| parse_file_note(&desc, word_size, little_endian))
+        .flatten()
+        .collect())
+}
+
+/// Get the word size in bytes and the byte order of the core dump.
+fn get_word_format(core: &[u8]) -> Result<(usize, bool), Error> {
+    let core_file = elf::Elf::parse(core)?;
+    Ok((if core_file.is_64 { 8 } else { 4 }, core_file.little_endian))
+}
+
+/// Get the types and contents of all notes in the note segments of the core dump.
+fn get_notes(core: &[u8]) -> Result<Vec<(u32, Vec<u8>)>, Error> {
+    let core_file = elf::Elf::parse(core)?;
+    let mut notes = Vec::new();
+    for header in core_file.program_headers.iter() {
+        if header.p_type != elf::program_header::PT_NOTE {
+            continue;
+        }
+        let segment = core
+            .get(header.file_range())
+            .ok_or_else(|| anyhow!("Note segment out of bounds"))?;
+        notes.append(&mut parse_notes(segment, core_file.little_endian));
+    }
+    Ok(notes)
+}
+
+/// Parse the notes of a note segment.
+/// Names and contents of the notes are aligned to 4 bytes.
+fn parse_notes(segment: &[u8], little_endian: bool) -> Vec<(u32, Vec<u8>)> {
+    let align = |size: usize| (size + 3) & !3;
+    let mut notes = Vec::new();
+    let mut offset = 0;
+    while offset + 12 <= segment.len() {
+        let name_size = read_word(segment, offset, 4, little_endian).unwrap() as usize;
+        let desc_size = read_word(segment, offset + 4, 4, little_endian).unwrap() as usize;
+        let note_type = read_word(segment, offset + 8, 4, little_endian).unwrap() as u32;
+        let desc_start = offset + 12 + align(name_size);
+        match segment.get(desc_start..desc_start + desc_size) {
+            Some(desc) => notes.push((note_type, desc.to_vec())),
+            None => break,
+        }
+        offset = desc_start + align(desc_size);
+    }
+    notes
+}
+
+/// Get the value of the given entry type of the auxiliary vector.
+fn get_auxv_value(
+    auxv: &[u8],
+    entry_type: u64,
+    word_size: usize,
+    little_endian: bool,
+) -> Option<u64> {
+    auxv.chunks_exact(2 * word_size).find_map(|entry| {
+        if read_word(entry, 0, word_size, little_endian)? == entry_type {
+            read_word(entry, word_size, word_size, little_endian)
+        } else {
+            None
+        }
+    })
+}
+
+/// Parse the list of mapped files of a `NT_FILE` note.
+///
+/// The note contains the number of mappings and the page size,
+/// followed by the start address, end address and file offset (in pages) of each mapping
+/// and the null-terminated paths of the mapped files.
+fn parse_file_note(desc: &[u8], word_size: usize, little_endian: bool) -> Option<Vec<MappedFile>> {
+    let count = read_word(desc, 0, word_size, little_endian)? as usize;
+    let page_size = read_word(desc, word_size, word_size, little_endian)?;
+    let paths_start = count
+        .checked_mul(3)?
+        .checked_add(2)?
+        .checked_mul(word_size)?;
+    let mut paths = desc
+        .get(paths_start..)?
+        .split(|byte| *byte == 0)
+        .map(|path| String::from_utf8_lossy(path).into_owned());
+    let mut mapped_files = Vec::new();
+    for index in 0..count {
+        let entry_start = word_size * (2 + 3 * index);
+        mapped_files.push(MappedFile {
+            start: read_word(desc, entry_start, word_size, little_endian)?,
+            end: read_word(desc, entry_start + word_size, word_size, little_endian)?,
+            file_offset: read_word(desc, entry_start + 2 * word_size, word_size, little_endian)?
+                .wrapping_mul(page_size),
+            path: paths.next()?,
+        });
+    }
+    Some(mapped_files)
+}
+
+/// Read an unsigned integer of the given size at the given offset.
+fn read_word(bytes: &[u8], offset: usize, size: usize, little_endian: bool) -> Option<u64> {
+    let mut word = bytes.get(offset..offset + size)?.to_vec();
+    if little_endian {
+        word.reverse();
+    }
+    Some(
+        word.into_iter()
+            .fold(0u64, |value, byte| (value << 8) | byte as u64),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_words(values: &[u64]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_le_bytes().to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn note_parsing() {
+        let auxv = to_words(&[3, 0x5555_0040, 9, 0x5555_1040, 0, 0]);
+        let mut segment = Vec::new();
+        segment.extend_from_slice(&5u32.to_le_bytes());
+        segment.extend_from_slice(&(auxv.len() as u32).to_le_bytes());
+        segment.extend_from_slice(&NT_AUXV.to_le_bytes());
+        segment.extend_from_slice(b"CORE\0\0\0\0");
+        segment.extend_from_slice(&auxv);
+        let notes = parse_notes(&segment, true);
+        assert_eq!(notes, vec![(NT_AUXV, auxv.clone())]);
+        assert_eq!(get_auxv_value(&auxv, AT_ENTRY, 8, true), Some(0x5555_1040));
+        assert_eq!(get_auxv_value(&auxv, 25, 8, true), None);
+
+        let mut file_note = to_words(&[
+            2,
+            0x1000,
+            0x5555_0000,
+            0x5555_2000,
+            0,
+            0x7f00_0000,
+            0x7f00_1000,
+            3,
+        ]);
+        file_note.extend_from_slice(b"/usr/bin/server\0/lib/libc.so.6\0");
+        let mapped_files = parse_file_note(&file_note, 8, true).unwrap();
+        assert_eq!(mapped_files.len(), 2);
+        assert_eq!(
+            mapped_files[1],
+            MappedFile {
+                path: "/lib/libc.so.6".to_string(),
+                start: 0x7f00_0000,
+                end: 0x7f00_1000,
+                file_offset: 0x3000,
+            }
+        );
+        assert_eq!(parse_file_note(&file_note[..20], 8, true), None);
+    }
+}
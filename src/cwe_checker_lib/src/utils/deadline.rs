@@ -0,0 +1,126 @@
+//! Deadlines for time-boxed analysis runs.
+//!
+//! If a deadline is set, the analysis pipeline distributes the remaining time among its phases.
+//! Phases that cannot be finished before their deadline are stopped early or skipped,
+//! so that the cwe_checker always produces (possibly incomplete) results in time.
+
+use crate::prelude::*;
+use std::time::{Duration, Instant};
+
+/// A point in time at which an analysis (or an analysis phase) should be finished.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Deadline {
+    end: Instant,
+}
+
+impl Deadline {
+    /// Create a deadline that expires after the given duration from now.
+    pub fn new(duration: Duration) -> Deadline {
+        Deadline {
+            end: Instant::now() + duration,
+        }
+    }
+
+    /// Create a deadline from a duration string like `30m`, `90s`, `2h` or `1h30m`.
+    /// Numbers without a unit are interpreted as seconds.
+    pub fn parse(duration: &str) -> Result<Deadline, Error> {
+        Ok(Deadline::new(parse_duration(duration)?))
+    }
+
+    /// Get the point in time when the deadline expires.
+    pub fn instant(&self) -> Instant {
+        self.end
+    }
+
+    /// Get the remaining time until the deadline expires.
+    pub fn remaining(&self) -> Duration {
+        self.end.saturating_duration_since(Instant::now())
+    }
+
+    /// Returns `true` if the deadline has already expired.
+    pub fn is_expired(&self) -> bool {
+        Instant::now() >= self.end
+    }
+
+    /// Get a deadline for an analysis phase that may use the given fraction of the remaining time.
+    pub fn portion(&self, fraction: f64) -> Deadline {
+        Deadline::new(self.remaining().mul_f64(fraction.max(0.0).min(1.0)))
+    }
+}
+
+/// The maximal duration accepted by [`parse_duration`] (100 years),
+/// so that adding a parsed duration to the current time cannot overflow.
+const MAX_DURATION_SECONDS: u64 = 100 * 365 * 24 * 60 * 60;
+
+/// Parse a duration string like `30m`, `90s`, `2h` or `1h30m`.
+/// Numbers without a unit are interpreted as seconds.
+/// The duration has to be positive and must not exceed 100 years.
+pub fn parse_duration(duration: &str) -> Result<Duration, Error> {
+    let mut components: Vec<(String, u64)> = Vec::new();
+    let mut number = String::new();
+    for character in duration.trim().chars() {
+        if character.is_ascii_digit() {
+            number.push(character);
+            continue;
+        }
+        let multiplier = match character {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            _ => return Err(anyhow!("Invalid unit '{}' in duration", character)),
+        };
+        if number.is_empty() {
+            return Err(anyhow!("Missing number before unit '{}'", character));
+        }
+        components.push((std::mem::take(&mut number), multiplier));
+    }
+    if !number.is_empty() {
+        components.push((number, 1));
+    }
+    if components.is_empty() {
+        return Err(anyhow!("Invalid duration: {}", duration));
+    }
+    let mut seconds: u64 = 0;
+    for (number, multiplier) in components {
+        // Parsing a string of digits only fails if the number is too large.
+        seconds = number
+            .parse::<u64>()
+            .ok()
+            .and_then(|number| number.checked_mul(multiplier))
+            .and_then(|component_seconds| seconds.checked_add(component_seconds))
+            .filter(|seconds| *seconds <= MAX_DURATION_SECONDS)
+            .ok_or_else(|| anyhow!("Duration too large: {}", duration))?;
+    }
+    if seconds == 0 {
+        return Err(anyhow!("Duration must be positive: {}", duration));
+    }
+    Ok(Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duration_parsing() {
+        assert_eq!(parse_duration("30m").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("45").unwrap(), Duration::from_secs(45));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("10x").is_err());
+        assert!(parse_duration("0").is_err());
+        assert!(parse_duration("0m").is_err());
+        assert!(parse_duration("999999999999999d").is_err());
+        assert!(parse_duration("99999999999999999999999").is_err());
+    }
+
+    #[test]
+    fn deadline_expiration() {
+        let deadline = Deadline::new(Duration::from_secs(3600));
+        assert!(!deadline.is_expired());
+        assert!(deadline.portion(0.5).remaining() <= Duration::from_secs(1800));
+        assert!(Deadline::new(Duration::from_secs(0)).is_expired());
+    }
+}
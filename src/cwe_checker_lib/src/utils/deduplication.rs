@@ -0,0 +1,146 @@
+//! Deduplication of CWE warnings.
+//!
+//! Different modules may report the same issue (e.g. the `Memory` and the `CWE416` module both report use-after-frees)
+//! and the fixpoint computations of some modules report an issue once per iteration at the same location.
+//! Two warnings are duplicates if they were generated by the same check (i.e. have the same name),
+//! have the same set of addresses and are located in the same function.
+//! Duplicates generated in the same batch of warnings are merged into the first of them,
+//! duplicates of warnings of previous batches are removed,
+//! since the previous warnings may already have been output.
+
+use crate::intermediate_representation::*;
+use crate::utils::fingerprint::{find_locations, get_location_key};
+use crate::utils::log::CweWarning;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// The properties identifying duplicate warnings:
+/// the name of the check, the set of addresses and the TID of the function containing the location of the warning.
+type WarningKey = (String, BTreeSet<String>, Option<String>);
+
+/// Removes duplicates from the warnings of several batches, e.g. the warnings of the different modules.
+#[derive(Debug, Clone, Default)]
+pub struct WarningDeduplicator {
+    /// The keys of all warnings of previously processed batches.
+    seen: HashSet<WarningKey>,
+    /// The number of duplicates removed so far.
+    number_of_duplicates: usize,
+}
+
+impl WarningDeduplicator {
+    /// Create a new deduplicator that has not seen any warnings yet.
+    pub fn new() -> WarningDeduplicator {
+        WarningDeduplicator::default()
+    }
+
+    /// Merge the duplicates among the warnings and remove duplicates of warnings of previous batches.
+    /// The order of the remaining warnings is preserved.
+    ///
+    /// Returns the number of removed warnings.
+    pub fn deduplicate(&mut self, project: &Project, warnings: &mut Vec<CweWarning>) -> usize {
+        if warnings.is_empty() {
+            return 0;
+        }
+        let locations = find_locations(project, warnings);
+        let mut batch_indices: HashMap<WarningKey, usize> = HashMap::new();
+        let mut deduplicated: Vec<CweWarning> = Vec::with_capacity(warnings.len());
+        let number_of_warnings = warnings.len();
+        for warning in warnings.drain(..) {
+            let function = get_location_key(&warning)
+                .and_then(|key| locations.get(key))
+                .map(|location| format!("{}", location.sub.tid));
+            let key = (
+                warning.name.clone(),
+                warning.addresses.iter().cloned().collect(),
+                function,
+            );
+            if self.seen.contains(&key) {
+                continue;
+            }
+            match batch_indices.get(&key) {
+                Some(index) => merge_warning(&mut deduplicated[*index], warning),
+                None => {
+                    batch_indices.insert(key, deduplicated.len());
+                    deduplicated.push(warning);
+                }
+            }
+        }
+        self.seen
+            .extend(batch_indices.into_iter().map(|(key, _)| key));
+        *warnings = deduplicated;
+        let number_of_removed = number_of_warnings - warnings.len();
+        self.number_of_duplicates += number_of_removed;
+        number_of_removed
+    }
+
+    /// Get the total number of duplicates removed so far.
+    pub fn get_number_of_duplicates(&self) -> usize {
+        self.number_of_duplicates
+    }
+}
+
+/// Merge the information of the duplicate into the warning.
+///
+/// The TIDs, symbols and other information of the duplicate that the warning does not contain yet are appended
+/// and the warning gets the higher severity of both warnings.
+/// The description of the warning is kept.
+fn merge_warning(warning: &mut CweWarning, duplicate: CweWarning) {
+    let append_missing = |target: &mut Vec<String>, source: Vec<String>| {
+        for item in source {
+            if !target.contains(&item) {
+                target.push(item);
+            }
+        }
+    };
+    append_missing(&mut warning.tids, duplicate.tids);
+    append_missing(&mut warning.symbols, duplicate.symbols);
+    for other in duplicate.other {
+        if !warning.other.contains(&other) {
+            warning.other.push(other);
+        }
+    }
+    warning.severity = warning.severity.max(duplicate.severity);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::log::Severity;
+
+    #[test]
+    fn merge_duplicates() {
+        let project = Project::mock_empty();
+        let warning = |name: &str, address: &str, tid: &str| {
+            CweWarning::new(name, "0.1", format!("Warning at {}", address))
+                .addresses(vec![address.to_string()])
+                .tids(vec![tid.to_string()])
+        };
+        let mut deduplicator = WarningDeduplicator::new();
+        let mut warnings = vec![
+            warning("CWE416", "00401000", "instr_00401000_1"),
+            warning("CWE416", "00401000", "instr_00401000_2").severity(Severity::High),
+            warning("CWE416", "00402000", "instr_00402000_1"),
+            warning("CWE415", "00401000", "instr_00401000_1"),
+        ];
+        assert_eq!(deduplicator.deduplicate(&project, &mut warnings), 1);
+        assert_eq!(warnings.len(), 3);
+        assert_eq!(
+            warnings[0].tids,
+            vec![
+                "instr_00401000_1".to_string(),
+                "instr_00401000_2".to_string()
+            ]
+        );
+        assert_eq!(warnings[0].severity, Severity::High);
+        assert_eq!(warnings[1].addresses, vec!["00402000".to_string()]);
+
+        // Duplicates of warnings of the previous batch are removed.
+        let mut warnings = vec![
+            warning("CWE416", "00402000", "instr_00402000_3"),
+            warning("CWE416", "00403000", "instr_00403000_1"),
+        ];
+        assert_eq!(deduplicator.deduplicate(&project, &mut warnings), 1);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].addresses, vec!["00403000".to_string()]);
+        assert_eq!(deduplicator.get_number_of_duplicates(), 2);
+    }
+}
@@ -0,0 +1,181 @@
+//! The default configuration of the cwe_checker and the documentation of its configuration keys.
+//!
+//! The default configuration (i.e. the `config.json` shipped with the cwe_checker) is embedded into the binary,
+//! so that it is available even if the configuration file was not installed.
+//! With the `--generate-config` command line option the configuration is written to a file
+//! with a comment on each documented configuration key.
+//! Since JSON does not support comments, these are added as additional keys named `_comment_KEY`,
+//! which are ignored by the analyses like the `_comment` keys of the configuration sections.
+
+/// The contents of the default configuration file.
+const DEFAULT_CONFIG: &str = include_str!("../../../config.json");
+
+/// The descriptions of the configuration keys, given as `(section, key, description)`.
+const KEY_DESCRIPTIONS: &[(&str, &str, &str)] = &[
+    ("CWE78", "system_symbols", "functions executing a command given as a string parameter"),
+    ("CWE78", "string_symbols", "string functions whose parameters are tracked when building the command string"),
+    ("CWE78", "user_input_symbols", "functions returning user input"),
+    ("CWE190", "symbols", "memory allocation functions whose size parameter is checked for integer overflows"),
+    ("CWE215", "symbols", "symbols of debugging functions"),
+    ("CWE243", "pairs", "valid orders of calls to chroot and to the functions changing the working directory"),
+    ("CWE243", "priviledge_dropping_functions", "functions that drop privileges after a chroot call"),
+    ("CWE248", "symbols", "functions that may throw uncaught exceptions"),
+    ("CWE321", "report_certificates", "whether embedded certificates are reported in addition to private keys"),
+    ("CWE332", "pairs", "pairs of a random number generator and the function seeding it"),
+    ("CWE367", "pairs", "pairs of a function checking a file (time of check) and a function using it (time of use)"),
+    ("CWE426", "symbols", "functions that change or drop privileges"),
+    ("CWE457", "symbols", "functions whose parameters are checked for uninitialized stack variables"),
+    ("CWE467", "symbols", "functions taking a size parameter that is checked for being the size of a pointer"),
+    ("CWE476", "parameters", "options of the analysis of unchecked return values"),
+    ("CWE476", "symbols", "functions whose return value may be NULL and must be checked before use"),
+    ("CWE667", "lock_symbols", "functions acquiring a lock"),
+    ("CWE667", "unlock_symbols", "functions releasing a lock"),
+    ("CWE672", "use_symbols", "functions reading from or writing to the resources given to them as parameters"),
+    ("CWE672", "resources", "functions creating and closing file descriptors and streams"),
+    ("CWE676", "symbols", "dangerous functions whose calls are reported"),
+    ("CWE686", "printf_symbols", "printf-like functions, given by the index of their format string parameter"),
+    ("CWE686", "scanf_symbols", "scanf-like functions, given by the index of their format string parameter"),
+    ("CWE782", "symbols", "functions issuing I/O control commands"),
+    ("CWE1327", "authentication_symbols", "functions indicating that accepted connections are authenticated"),
+    ("CWE1395", "libraries", "libraries whose version strings are searched for"),
+    ("CWE1395", "report_all_versions", "whether all found versions are reported, even if no CVEs are known for them"),
+    ("CWE1395", "cve_mapping_file", "path to an optional file mapping library versions to CVEs"),
+    ("check_path", "symbols", "functions returning user input whose paths to the reported warnings are searched"),
+    ("CallPolicy", "rules", "rules of the form 'every call to a trigger symbol must be preceded/followed by a call to a required symbol on all paths'"),
+    ("Packer", "entropy_threshold", "executable segments with a higher entropy (in bits per byte) are reported"),
+    ("Packer", "min_segment_size", "executable segments with less bytes are ignored by the entropy check"),
+    ("Packer", "signatures", "byte signatures and section names of known packers"),
+    ("AnalysisGaps", "known_side_effects", "name patterns of CALLOTHER instructions whose side effects are known to the analyses"),
+    ("AnalysisGaps", "min_cluster_size", "the minimal number of CALLOTHER instructions with unknown side effects in a reported cluster"),
+    ("AnalysisGaps", "max_cluster_distance", "the maximal distance in bytes between two CALLOTHER instructions of the same cluster"),
+    ("AnalysisGaps", "max_unknown_density", "functions with a higher share of assignments containing unknown values are reported"),
+    ("AnalysisGaps", "min_unknown_defs", "functions with less assignments containing unknown values are not reported"),
+    ("ExternStubs", "default", "the behavior of all extern functions without a configured behavior"),
+    ("ExternStubs", "symbols", "the behavior of specific extern functions, indexed by their names"),
+    ("SdkModels", "models", "the models of embedded SDKs with their taint sources, allocators, deallocators and non-returning functions"),
+    ("Syscalls", "taint_sources", "syscalls returning untrusted input"),
+    ("Syscalls", "allocators", "syscalls allocating memory"),
+    ("Syscalls", "command_sinks", "syscalls executing commands"),
+    ("Syscalls", "non_returning", "syscalls that never return"),
+    ("Syscalls", "architectures", "the syscall conventions indexed by CPU architecture"),
+    ("HarvardArchitectures", "architectures", "the address space layouts indexed by CPU architecture"),
+    ("RegisterOverrides", "architectures", "the register properties indexed by CPU architecture"),
+    ("Normalization", "passes", "enable flags of the normalization passes, indexed by the name of the pass"),
+    ("Normalization", "wrapper_inlining", "the configuration of the inline_wrapper_functions pass, i.e. the maximal number of definitions (max_defs) of inlined functions"),
+    ("Memory", "allocation_symbols", "functions allocating heap memory"),
+    ("Memory", "deallocation_symbols", "functions freeing heap memory"),
+    ("Memory", "main_functions", "names of the main function of a program"),
+    ("Memory", "numeric_domain", "the approximation of values that are not exactly known: 'constants', 'intervals', 'strided_intervals' or 'known_bits'"),
+    ("Memory", "max_pointer_targets", "pointers with more targets are replaced by Top"),
+    ("AddressRanges", "include", "address ranges to analyze, e.g. 0x400000-0x408000 (the whole binary if empty)"),
+    ("AddressRanges", "exclude", "address ranges excluded from the analysis"),
+    ("Chunking", "min_functions", "binaries with at least this number of functions are analyzed in chunks (0 disables chunking)"),
+    ("Chunking", "max_functions_per_chunk", "the maximal number of functions of a chunk"),
+    ("ResourceBudget", "max_memory_mb", "computations are stopped if the resident memory exceeds this number of megabytes (null for no limit)"),
+    ("ResourceBudget", "max_states", "computations storing more node states are stopped (null for no limit)"),
+    ("ResourceBudget", "max_seconds_per_function", "computations started in a function are stopped after this number of seconds in total (null for no limit)"),
+    ("Components", "components", "named components given by address ranges and function name patterns"),
+    ("GeneratedCode", "action", "what to do with warnings in compiler-generated functions: 'keep', 'downrank' or 'skip'"),
+    ("GeneratedCode", "functions", "name patterns of compiler-generated functions"),
+    ("GeneratedCode", "detect_thunks", "whether thunks are also treated as compiler-generated"),
+    ("Emulation", "enabled", "whether the concrete emulation of functions is run"),
+    ("Emulation", "max_steps", "the maximal number of emulated instructions per function"),
+    ("Emulation", "min_string_length", "decoded strings with less characters are ignored"),
+    ("EntryPoints", "symbols", "additional entry points given as function names or hexadecimal start addresses"),
+    ("EntryPoints", "init_array", "whether the functions referenced in the .init_array section are entry points"),
+    ("EntryPoints", "exported_functions", "whether all exported functions are entry points"),
+    ("EntryPoints", "shared_library_exports", "whether the exported functions of shared libraries are entry points"),
+    ("EntryPoints", "thread_start_routines", "whether functions started as threads via pthread_create are entry points"),
+    ("FunctionSummaries", "taint_sources", "functions returning untrusted input"),
+    ("FunctionSummaries", "sinks", "functions whose parameters must not be tainted"),
+    ("FactExport", "taint_sources", "functions whose calls are exported as sources of untrusted input"),
+    ("FactExport", "struct_layout", "the configuration of the recovery of structure layouts"),
+    ("OutputSinks", "sinks", "additional destinations of the results, each optionally followed by :FORMAT"),
+    ("OutputSinks", "rotate", "the number of previous result files kept when overwriting result files"),
+    ("OutputSinks", "webhook_timeout", "the timeout in seconds of webhook requests (30 by default)"),
+    ("Toolchains", "enabled", "whether the toolchain-specific tuning is applied"),
+    ("Toolchains", "profiles", "the tuning profiles indexed by compiler"),
+];
+
+/// Get the default configuration shipped with the cwe_checker.
+pub fn get_default_config() -> serde_json::Value {
+    serde_json::from_str(DEFAULT_CONFIG).expect("Invalid default configuration")
+}
+
+/// Add the sections and keys of the default configuration missing in the given configuration,
+/// e.g. the sections of modules added after the configuration file was installed.
+pub fn add_missing_defaults(config: &mut serde_json::Value) {
+    if let (Some(config), serde_json::Value::Object(defaults)) =
+        (config.as_object_mut(), get_default_config())
+    {
+        for (section_name, default_section) in defaults {
+            let section = config
+                .entry(section_name)
+                .or_insert_with(|| default_section.clone());
+            if let (Some(section), serde_json::Value::Object(default_section)) =
+                (section.as_object_mut(), default_section)
+            {
+                for (key, value) in default_section {
+                    section.entry(key).or_insert(value);
+                }
+            }
+        }
+    }
+}
+
+/// Add a comment key `_comment_KEY` for each documented key of the configuration.
+/// Existing comments are not overwritten.
+pub fn add_key_comments(config: &mut serde_json::Value) {
+    for (section_name, key, description) in KEY_DESCRIPTIONS.iter() {
+        if let Some(section) = config
+            .get_mut(*section_name)
+            .and_then(|section| section.as_object_mut())
+        {
+            if section.contains_key(*key) {
+                section
+                    .entry(format!("_comment_{}", key))
+                    .or_insert_with(|| serde_json::Value::String(description.to_string()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn all_default_keys_documented() {
+        let mut config = get_default_config();
+        add_key_comments(&mut config);
+        for (section_name, section) in config.as_object().unwrap() {
+            if let Some(section) = section.as_object() {
+                for key in section.keys().filter(|key| !key.starts_with('_')) {
+                    assert!(
+                        section.contains_key(&format!("_comment_{}", key)),
+                        "Undocumented configuration key {}.{}",
+                        section_name,
+                        key
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn missing_defaults() {
+        let mut config = serde_json::json!({
+            "Packer": { "entropy_threshold": 6.0 },
+        });
+        add_missing_defaults(&mut config);
+        assert_eq!(
+            config["Packer"]["entropy_threshold"],
+            serde_json::json!(6.0)
+        );
+        assert_eq!(
+            config["Packer"]["min_segment_size"],
+            get_default_config()["Packer"]["min_segment_size"]
+        );
+        assert_eq!(config["CWE676"], get_default_config()["CWE676"]);
+    }
+}
@@ -0,0 +1,128 @@
+//! Demangling of C++ and Rust symbol names.
+//!
+//! The symbol names of C++ and Rust binaries are mangled, e.g. `_ZN3foo3barEv` for the C++ function `foo::bar()`.
+//! Before the output of CWE warnings and log messages all mangled names contained in them are demangled,
+//! so that the reports show the names as they appear in the source code.
+//! Supported are the Itanium C++ ABI mangling (used by GCC and Clang)
+//! and both the legacy and the v0 mangling of Rust.
+
+use crate::utils::log::{CweWarning, LogMessage};
+
+/// Demangle a symbol name.
+/// Returns `None` if the name is not a mangled C++ or Rust name.
+///
+/// The hashes contained in legacy Rust names are removed from the demangled name.
+pub fn demangle(name: &str) -> Option<String> {
+    if name.starts_with("_R") || is_legacy_rust_name(name) {
+        rustc_demangle::try_demangle(name)
+            .ok()
+            .map(|demangled| format!("{:#}", demangled))
+    } else if name.starts_with("_Z") || name.starts_with("__Z") {
+        // Mach-O binaries add another underscore to the mangled name.
+        let name = name
+            .strip_prefix('_')
+            .filter(|name| name.starts_with("_Z"))
+            .unwrap_or(name);
+        let symbol = cpp_demangle::Symbol::new(name).ok()?;
+        symbol
+            .demangle(&cpp_demangle::DemangleOptions::default())
+            .ok()
+    } else {
+        None
+    }
+}
+
+/// Returns `true` if the name has the form of a legacy Rust name,
+/// i.e. if it is a C++-style mangled name ending with a hash like `17h0123456789abcdefE`.
+fn is_legacy_rust_name(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    let len = bytes.len();
+    name.starts_with("_ZN")
+        && len > 23
+        && bytes[len - 1] == b'E'
+        && &bytes[len - 20..len - 17] == b"17h"
+        && bytes[len - 17..len - 1].iter().all(u8::is_ascii_hexdigit)
+}
+
+/// Demangle all mangled symbol names contained in the text.
+///
+/// Symbol names are the maximal substrings consisting of alphanumeric characters, `_` and `$`.
+pub fn demangle_text(text: &str) -> String {
+    let is_symbol_char =
+        |character: char| character.is_ascii_alphanumeric() || character == '_' || character == '$';
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(character) = rest.chars().next() {
+        if !is_symbol_char(character) {
+            output.push(character);
+            rest = &rest[character.len_utf8()..];
+            continue;
+        }
+        let symbol_len = rest
+            .find(|character| !is_symbol_char(character))
+            .unwrap_or(rest.len());
+        let symbol = &rest[..symbol_len];
+        match demangle(symbol) {
+            Some(demangled) => output.push_str(&demangled),
+            None => output.push_str(symbol),
+        }
+        rest = &rest[symbol_len..];
+    }
+    output
+}
+
+/// Demangle all mangled symbol names contained in the descriptions, the symbols and the `other` field of the warnings.
+pub fn demangle_warnings(warnings: &mut [CweWarning]) {
+    for warning in warnings.iter_mut() {
+        warning.description = demangle_text(&warning.description);
+        for symbol in warning
+            .symbols
+            .iter_mut()
+            .chain(warning.other.iter_mut().flatten())
+        {
+            *symbol = demangle_text(symbol);
+        }
+    }
+}
+
+/// Demangle all mangled symbol names contained in the texts of the log messages.
+pub fn demangle_log_messages(logs: &mut [LogMessage]) {
+    for log in logs.iter_mut() {
+        log.text = demangle_text(&log.text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn demangle_names() {
+        assert_eq!(demangle("_ZN3foo3barEv"), Some("foo::bar()".to_string()));
+        assert_eq!(demangle("__ZN3foo3barEv"), Some("foo::bar()".to_string()));
+        assert_eq!(
+            demangle("_ZN4core3ptr13drop_in_place17h0123456789abcdefE"),
+            Some("core::ptr::drop_in_place".to_string())
+        );
+        assert_eq!(demangle("strcpy"), None);
+        assert_eq!(demangle("_RESERVED"), None);
+    }
+
+    #[test]
+    fn demangle_warning_texts() {
+        let mut warnings = vec![CweWarning::new(
+            "CWE676",
+            "0.1",
+            "(Use of Potentially Dangerous Function) _ZN3foo3barEv (00401000) -> strcpy",
+        )
+        .symbols(vec!["_ZN3foo3barEv".to_string()])
+        .other(vec![vec!["called_by".to_string(), "_Z4mainv".to_string()]])];
+        demangle_warnings(&mut warnings);
+        assert_eq!(
+            warnings[0].description,
+            "(Use of Potentially Dangerous Function) foo::bar() (00401000) -> strcpy"
+        );
+        assert_eq!(warnings[0].symbols, vec!["foo::bar()".to_string()]);
+        assert_eq!(warnings[0].other[0][1], "main()");
+    }
+}
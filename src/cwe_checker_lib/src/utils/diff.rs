@@ -0,0 +1,173 @@
+//! Compare the warnings of two analysis runs, e.g. of two builds of the same firmware.
+//!
+//! Warnings are matched by their fingerprint (see [`crate::utils::fingerprint`]).
+//! If several warnings share the same fingerprint, they are matched pairwise
+//! and the surplus warnings count as new or fixed.
+//!
+//! If the function signatures of both versions of the binary are known (see [`crate::analysis::function_matching`]),
+//! warnings whose fingerprints differ are also matched if they are located in matched functions
+//! and have the same check name and symbols.
+//! This pairs warnings in functions of stripped binaries that changed between the two versions.
+
+use crate::prelude::*;
+use crate::utils::fingerprint::fingerprint;
+use crate::utils::log::CweWarning;
+use std::collections::HashMap;
+
+/// The result of comparing the warnings of an old and a new analysis run.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct ResultsDiff {
+    /// Warnings of the new run without a matching warning in the old run.
+    pub new: Vec<CweWarning>,
+    /// Warnings of the old run without a matching warning in the new run.
+    pub fixed: Vec<CweWarning>,
+    /// Warnings of the new run with a matching warning in the old run.
+    pub persisting: Vec<CweWarning>,
+}
+
+impl ResultsDiff {
+    /// Compare the warnings of the old and the new analysis run.
+    pub fn new(old: &[CweWarning], new: &[CweWarning]) -> ResultsDiff {
+        let mut unmatched_old: HashMap<String, Vec<&CweWarning>> = HashMap::new();
+        for warning in old.iter().rev() {
+            unmatched_old
+                .entry(fingerprint(warning))
+                .or_insert_with(Vec::new)
+                .push(warning);
+        }
+        let mut diff = ResultsDiff::default();
+        for warning in new {
+            match unmatched_old
+                .get_mut(&fingerprint(warning))
+                .and_then(|warnings| warnings.pop())
+            {
+                Some(_) => diff.persisting.push(warning.clone()),
+                None => diff.new.push(warning.clone()),
+            }
+        }
+        // Keep the order of the fixed warnings from the old run.
+        for warning in old {
+            if let Some(warnings) = unmatched_old.get_mut(&fingerprint(warning)) {
+                if warnings.pop().is_some() {
+                    diff.fixed.push(warning.clone());
+                }
+            }
+        }
+        diff
+    }
+
+    /// Compare the warnings of the old and the new analysis run
+    /// and additionally match warnings located in matched functions of the two versions of the binary.
+    ///
+    /// A new warning is matched to a fixed warning
+    /// if both have the same check name and symbols and their functions are matched.
+    pub fn with_function_matching(
+        old: &[CweWarning],
+        new: &[CweWarning],
+        old_functions: &FunctionSignatures,
+        new_functions: &FunctionSignatures,
+    ) -> ResultsDiff {
+        let mut diff = ResultsDiff::new(old, new);
+        let matching = FunctionMatching::new(old_functions, new_functions);
+        let old_warning_functions = old_functions.get_warning_functions();
+        let new_warning_functions = new_functions.get_warning_functions();
+        let mut unmatched_new = Vec::new();
+        for warning in std::mem::take(&mut diff.new) {
+            let old_function = new_warning_functions
+                .get(fingerprint(&warning).as_str())
+                .and_then(|new_function| matching.get_old_function(new_function));
+            let fixed_index = old_function.and_then(|old_function| {
+                diff.fixed.iter().position(|fixed_warning| {
+                    fixed_warning.name == warning.name
+                        && fixed_warning.symbols == warning.symbols
+                        && old_warning_functions.get(fingerprint(fixed_warning).as_str())
+                            == Some(&old_function)
+                })
+            });
+            match fixed_index {
+                Some(index) => {
+                    diff.fixed.remove(index);
+                    diff.persisting.push(warning);
+                }
+                None => unmatched_new.push(warning),
+            }
+        }
+        diff.new = unmatched_new;
+        diff
+    }
+}
+
+impl std::fmt::Display for ResultsDiff {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            formatter,
+            "{} new, {} fixed, {} persisting warnings",
+            self.new.len(),
+            self.fixed.len(),
+            self.persisting.len()
+        )?;
+        for warning in self.new.iter() {
+            writeln!(formatter, "NEW: {}", warning)?;
+        }
+        for warning in self.fixed.iter() {
+            writeln!(formatter, "FIXED: {}", warning)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn warning(name: &str, address: &str) -> CweWarning {
+        CweWarning::new(name, "0.1", "description").addresses(vec![address.to_string()])
+    }
+
+    #[test]
+    fn diff_of_runs() {
+        let old = vec![
+            warning("CWE476", "00401000"),
+            warning("CWE476", "00401000"),
+            warning("CWE676", "00402000"),
+        ];
+        let new = vec![
+            warning("CWE476", "00401000"),
+            warning("CWE676", "00402000"),
+            warning("CWE190", "00403000"),
+        ];
+        let diff = ResultsDiff::new(&old, &new);
+        assert_eq!(diff.new, vec![warning("CWE190", "00403000")]);
+        assert_eq!(diff.fixed, vec![warning("CWE476", "00401000")]);
+        assert_eq!(diff.persisting.len(), 2);
+    }
+
+    #[test]
+    fn diff_with_function_matching() {
+        use crate::analysis::function_matching::FunctionSignature;
+        let old = vec![warning("CWE476", "00401000"), warning("CWE676", "00401100")];
+        let new = vec![warning("CWE476", "00501000"), warning("CWE676", "00501100")];
+        let signatures = |warnings: &[CweWarning], address: &str, structure: &str| {
+            FunctionSignatures {
+                functions: vec![FunctionSignature {
+                    name: format!("FUN_{}", address),
+                    address: address.to_string(),
+                    structural_hash: structure.to_string(),
+                    cfg_hash: "cfg".to_string(),
+                    callees: Vec::new(),
+                    // Only the first warning is located inside the function.
+                    warning_fingerprints: vec![fingerprint(&warnings[0])],
+                }],
+            }
+        };
+        let diff = ResultsDiff::with_function_matching(
+            &old,
+            &new,
+            &signatures(&old, "00400f00", "old"),
+            &signatures(&new, "00500f00", "new"),
+        );
+        assert_eq!(diff.persisting, vec![warning("CWE476", "00501000")]);
+        assert_eq!(diff.new, vec![warning("CWE676", "00501100")]);
+        assert_eq!(diff.fixed, vec![warning("CWE676", "00401100")]);
+    }
+}
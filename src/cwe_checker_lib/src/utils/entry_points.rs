@@ -0,0 +1,173 @@
+//! Configuration of additional entry points for the interprocedural analyses.
+//!
+//! By default the interprocedural analyses only start at the entry points reported by the disassembler,
+//! which usually means `main` or the entry function of the binary.
+//! For shared libraries and plugins most of the code is only reachable
+//! through exported functions, constructors in the `.init_array` section or registered callbacks.
+//! Similarly, functions started in a new thread are never called directly by the program.
+//! The functions in this module mark such functions as additional entry points of the project.
+//!
+//! Shared libraries (`.so` and `.dll` files) are usually analyzed as the primary target without a `main` function,
+//! so their exported functions are added as entry points by default.
+//! Since the callers of a library are unknown, the parameters of these functions are treated like untrusted input,
+//! e.g. the CWE-78 check reports command injections whose input can be traced back to the parameters of an entry point.
+
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::LogMessage;
+
+/// Configurable sources of additional entry points.
+///
+/// The configuration is read from the `EntryPoints` section of the configuration file.
+/// Missing fields default to adding no additional entry points.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+#[serde(default)]
+pub struct Config {
+    /// Names or (hexadecimal) start addresses of functions that should be treated as entry points,
+    /// e.g. plugin registration callbacks.
+    pub symbols: Vec<String>,
+    /// If set to `true`, all functions referenced in the `.init_array` section of an ELF file
+    /// are added as entry points.
+    pub init_array: bool,
+    /// If set to `true`, all functions exported by the binary are added as entry points.
+    pub exported_functions: bool,
+    /// If set to `true` and the binary is a shared library,
+    /// all functions exported by the binary are added as entry points (regardless of `exported_functions`).
+    pub shared_library_exports: bool,
+    /// If set to `true`, all functions that are started as new threads via `pthread_create`
+    /// are added as entry points.
+    pub thread_start_routines: bool,
+}
+
+/// Add all entry points specified by the given configuration to the project.
+///
+/// Returns log messages for configured entry points that could not be found in the project.
+#[must_use]
+pub fn add_entry_points(project: &mut Project, binary: &[u8], config: &Config) -> Vec<LogMessage> {
+    let mut logs = Vec::new();
+    for symbol in config.symbols.iter() {
+        if let Some(sub_tid) = find_sub_by_name_or_address(project, symbol) {
+            add_entry_point(project, sub_tid);
+        } else {
+            logs.push(
+                LogMessage::new_error(format!("Entry point {} not found.", symbol))
+                    .source("Entry Points"),
+            );
+        }
+    }
+    let mut addresses = Vec::new();
+    if config.init_array {
+        match crate::utils::binary::get_init_array_function_addresses(binary) {
+            Ok(mut init_array_addresses) => addresses.append(&mut init_array_addresses),
+            Err(err) => logs.push(
+                LogMessage::new_info(format!("Could not parse .init_array: {}", err))
+                    .source("Entry Points"),
+            ),
+        }
+    }
+    let is_shared_library =
+        config.shared_library_exports && crate::utils::binary::is_shared_library(binary);
+    if config.exported_functions || is_shared_library {
+        match crate::utils::binary::get_exported_function_addresses(binary) {
+            Ok(mut exported_addresses) => {
+                if is_shared_library {
+                    logs.push(
+                        LogMessage::new_info(format!(
+                            "The binary is a shared library. Its {} exported functions are analyzed as entry points.",
+                            exported_addresses.len()
+                        ))
+                        .source("Entry Points"),
+                    );
+                }
+                addresses.append(&mut exported_addresses);
+            }
+            Err(err) => logs.push(
+                LogMessage::new_info(format!("Could not parse exported functions: {}", err))
+                    .source("Entry Points"),
+            ),
+        }
+    }
+    if config.thread_start_routines {
+        for thread_entry_point in crate::analysis::concurrency::find_thread_entry_points(project) {
+            add_entry_point(project, thread_entry_point.start_routine);
+        }
+    }
+    let base_offset = project.program.term.address_base_offset;
+    for address in addresses {
+        if let Some(sub_tid) = find_sub_by_address(project, address + base_offset) {
+            add_entry_point(project, sub_tid);
+        }
+    }
+    logs
+}
+
+/// Add the given TID to the entry points of the project if it is not already contained in them.
+fn add_entry_point(project: &mut Project, sub_tid: Tid) {
+    if !project.program.term.entry_points.contains(&sub_tid) {
+        project.program.term.entry_points.push(sub_tid);
+    }
+}
+
+/// Find the TID of the function with the given name.
+/// If no function with the name exists, the identifier is interpreted as a hexadecimal start address of the function.
+pub fn find_sub_by_name_or_address(project: &Project, identifier: &str) -> Option<Tid> {
+    if let Some(sub) = project
+        .program
+        .term
+        .subs
+        .iter()
+        .find(|sub| sub.term.name == identifier)
+    {
+        return Some(sub.tid.clone());
+    }
+    let address = u64::from_str_radix(identifier.trim_start_matches("0x"), 16).ok()?;
+    find_sub_by_address(project, address)
+}
+
+/// Find the TID of the function starting at the given address.
+pub(crate) fn find_sub_by_address(project: &Project, address: u64) -> Option<Tid> {
+    project
+        .program
+        .term
+        .subs
+        .iter()
+        .find(|sub| u64::from_str_radix(&sub.tid.address, 16).ok() == Some(address))
+        .map(|sub| sub.tid.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_configured_entry_points() {
+        let mut project = Project::mock_empty();
+        let mut plugin_init = Sub::mock("plugin_init");
+        plugin_init.tid.address = "00101000".to_string();
+        let mut callback = Sub::mock("callback");
+        callback.tid.address = "00102000".to_string();
+        project.program.term.subs = vec![plugin_init, callback];
+        let config = Config {
+            symbols: vec![
+                "plugin_init".to_string(),
+                "0x102000".to_string(),
+                "plugin_init".to_string(),
+                "nonexisting".to_string(),
+            ],
+            init_array: false,
+            exported_functions: false,
+            shared_library_exports: true,
+            thread_start_routines: false,
+        };
+        let logs = add_entry_points(&mut project, &[], &config);
+        assert_eq!(logs.len(), 1);
+        let entry_point_names: Vec<String> = project
+            .program
+            .term
+            .entry_points
+            .iter()
+            .map(|tid| format!("{}", tid))
+            .collect();
+        assert_eq!(entry_point_names, vec!["plugin_init", "callback"]);
+    }
+}
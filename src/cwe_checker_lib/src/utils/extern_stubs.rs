@@ -0,0 +1,201 @@
+//! Default behavior of calls to extern functions that are not modeled by the analyses.
+//!
+//! Most calls to extern functions are not modeled explicitly, e.g. as allocations or as sources of untrusted input.
+//! And for imports that Ghidra could not resolve even the parameters and return values are unknown.
+//! The analyses handle such calls by a stub behavior that is read from the `ExternStubs` section of the configuration file.
+//! The stub behavior determines
+//! - whether the registers that are not callee-saved in the calling convention of the function are clobbered by the call,
+//! - whether the return registers contain unknown values after the call,
+//! - whether the function may write to all memory reachable through its parameters,
+//! - and whether the return value of the function is untrusted input.
+//!
+//! The default behavior can be overridden for single functions.
+//! The configuration is copied to the configuration sections of the analyses using it,
+//! and functions returning untrusted input are added to the lists of taint sources of the checks.
+
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::sdk_models::{add_symbols_to_config_lists, TAINT_SOURCE_LISTS};
+use std::collections::BTreeMap;
+
+/// The assumed behavior of a call to an extern function that is not modeled otherwise.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+#[serde(default)]
+pub struct StubBehavior {
+    /// Whether the registers that are not callee-saved contain unknown values after the call.
+    pub clobber_caller_saved: bool,
+    /// Whether the return registers contain unknown values after the call.
+    pub return_unknown: bool,
+    /// Whether the function may write to all memory objects reachable through its parameters.
+    pub write_to_parameters: bool,
+    /// Whether the return value of the function is untrusted input.
+    pub taint_return: bool,
+}
+
+impl Default for StubBehavior {
+    /// The conservative behavior of an unknown function: all caller-saved registers are clobbered
+    /// and the function may write to all memory reachable through its parameters.
+    fn default() -> StubBehavior {
+        StubBehavior {
+            clobber_caller_saved: true,
+            return_unknown: true,
+            write_to_parameters: true,
+            taint_return: false,
+        }
+    }
+}
+
+impl StubBehavior {
+    /// Returns `true` if the register contains an unknown value after a call with this behavior
+    /// to a function with the given calling convention.
+    pub fn clobbers_register(
+        &self,
+        register_name: &str,
+        calling_convention: &CallingConvention,
+    ) -> bool {
+        let contains = |registers: &[String]| registers.iter().any(|name| name == register_name);
+        (self.clobber_caller_saved && !contains(&calling_convention.callee_saved_register))
+            || (self.return_unknown && contains(&calling_convention.return_register))
+    }
+}
+
+/// The configured stub behaviors.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+#[serde(default)]
+pub struct Config {
+    /// The behavior of all extern functions without a configured behavior.
+    pub default: StubBehavior,
+    /// The behaviors of single extern functions indexed by the names of the functions.
+    pub symbols: BTreeMap<String, StubBehavior>,
+}
+
+/// The locations in the configuration, given as a configuration section and the keys of nested objects inside it,
+/// to which the stub configuration is copied.
+const STUB_CONFIG_LOCATIONS: [&[&str]; 2] = [&["Memory"], &["CWE672", "resources"]];
+
+/// The key of the stub configuration in the configuration sections of the analyses.
+const STUB_CONFIG_KEY: &str = "extern_stubs";
+
+impl Config {
+    /// Get the behavior of a call to the extern function with the given name.
+    pub fn get_behavior(&self, symbol_name: &str) -> &StubBehavior {
+        self.symbols.get(symbol_name).unwrap_or(&self.default)
+    }
+
+    /// Copy the stub configuration to the configuration sections of the analyses using it
+    /// and add the extern functions of the project whose return value is untrusted input
+    /// to the symbol lists of the taint sources.
+    ///
+    /// The default behavior only makes imports with unknown parameters and return values to taint sources,
+    /// since otherwise every extern function would be a taint source.
+    /// Configuration sections that do not exist in the configuration are not created.
+    pub fn apply_to_config(&self, project: &Project, config: &mut serde_json::Value) {
+        let stub_config = serde_json::to_value(self).unwrap();
+        for location in STUB_CONFIG_LOCATIONS.iter() {
+            if let Some(object) = get_config_object(config, location) {
+                object.insert(STUB_CONFIG_KEY.to_string(), stub_config.clone());
+            }
+        }
+        let taint_sources: Vec<String> = project
+            .program
+            .term
+            .extern_symbols
+            .iter()
+            .filter(|symbol| match self.symbols.get(&symbol.name) {
+                Some(behavior) => behavior.taint_return,
+                None => self.default.taint_return && is_unresolved(symbol),
+            })
+            .map(|symbol| symbol.name.clone())
+            .collect();
+        add_symbols_to_config_lists(config, &taint_sources, &TAINT_SOURCE_LISTS);
+    }
+}
+
+/// Get the object at the given location in the configuration.
+/// Nested objects inside the configuration section are created if they do not exist.
+/// Returns `None` if the configuration section does not exist or if the location does not contain an object.
+fn get_config_object<'a>(
+    config: &'a mut serde_json::Value,
+    location: &[&str],
+) -> Option<&'a mut serde_json::Map<String, serde_json::Value>> {
+    let mut object = config.get_mut(location[0])?.as_object_mut()?;
+    for key in location[1..].iter() {
+        object = object
+            .entry(key.to_string())
+            .or_insert_with(|| serde_json::json!({}))
+            .as_object_mut()?;
+    }
+    Some(object)
+}
+
+/// Returns `true` if neither the parameters nor the return values of the extern symbol are known,
+/// e.g. because Ghidra could not resolve the import.
+pub fn is_unresolved(symbol: &ExternSymbol) -> bool {
+    symbol.parameters.is_empty() && symbol.return_values.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_clobbering() {
+        let cconv = CallingConvention {
+            name: "__cdecl".to_string(),
+            parameter_register: vec!["RDI".to_string()],
+            return_register: vec!["RAX".to_string()],
+            callee_saved_register: vec!["RBX".to_string()],
+        };
+        let behavior = StubBehavior::default();
+        assert!(behavior.clobbers_register("RAX", &cconv));
+        assert!(behavior.clobbers_register("RDI", &cconv));
+        assert!(!behavior.clobbers_register("RBX", &cconv));
+        let behavior = StubBehavior {
+            clobber_caller_saved: false,
+            ..StubBehavior::default()
+        };
+        assert!(behavior.clobbers_register("RAX", &cconv));
+        assert!(!behavior.clobbers_register("RDI", &cconv));
+    }
+
+    #[test]
+    fn config_extension() {
+        let mut project = Project::mock_empty();
+        let mut unresolved = ExternSymbol::mock_with_name("unresolved");
+        unresolved.parameters = Vec::new();
+        unresolved.return_values = Vec::new();
+        let resolved = ExternSymbol::mock_with_name("resolved");
+        project.program.term.extern_symbols = vec![unresolved, resolved];
+        let mut stub_config = Config::default();
+        stub_config.default.taint_return = true;
+        let mut config = serde_json::json!({
+            "check_path": { "symbols": ["recv"] },
+            "CWE672": { "use_symbols": ["read"] }
+        });
+        stub_config.apply_to_config(&project, &mut config);
+        assert_eq!(
+            config["check_path"]["symbols"],
+            serde_json::json!(["recv", "unresolved"])
+        );
+        assert_eq!(
+            config["CWE672"]["resources"]["extern_stubs"],
+            serde_json::to_value(&stub_config).unwrap()
+        );
+        assert!(config.get("Memory").is_none());
+
+        stub_config.symbols.insert(
+            "resolved".to_string(),
+            StubBehavior {
+                taint_return: true,
+                ..StubBehavior::default()
+            },
+        );
+        stub_config.apply_to_config(&project, &mut config);
+        assert_eq!(
+            config["check_path"]["symbols"],
+            serde_json::json!(["recv", "unresolved", "resolved"])
+        );
+        assert!(stub_config.get_behavior("resolved").taint_return);
+        assert!(stub_config.get_behavior("unknown").taint_return);
+    }
+}
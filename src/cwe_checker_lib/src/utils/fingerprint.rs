@@ -0,0 +1,419 @@
+//! Fingerprints identifying CWE warnings across different builds of a program.
+//!
+//! Baselines (see [`crate::utils::suppression`]) and comparisons of analysis runs (see [`crate::utils::diff`])
+//! identify warnings by their fingerprint.
+//! Since recompiling a program changes most addresses,
+//! the stable fingerprint computed by [`add_fingerprints`] does not contain any addresses.
+//! Instead it is computed from
+//! - the name of the check,
+//! - the name of the function containing the location of the warning
+//!   or, if the binary is stripped and Ghidra generated the function name (e.g. `FUN_00101234`),
+//!   a hash of the normalized IR of the whole function,
+//! - the normalized IR of the basic block containing the location of the warning
+//!   together with the position of the location inside the block,
+//! - the symbols of the warning.
+//!
+//! The normalized IR contains the kinds of the terms, the operations, the names of non-temporary registers
+//! and the names of called functions, but no addresses and no constants.
+//!
+//! Warnings whose location is not contained in any function get a fingerprint computed from the offsets of their addresses
+//! relative to the lowest function address of the program.
+//! It does not change if the binary is loaded at another base address,
+//! but it changes if the code containing the warning moves to another offset.
+
+use crate::intermediate_representation::*;
+use crate::utils::log::CweWarning;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hasher;
+
+/// Get the fingerprint of a warning.
+///
+/// Returns the stable fingerprint of the warning if it was computed by [`add_fingerprints`].
+/// Otherwise the fingerprint is computed from the name of the check, the addresses and the symbols of the warning.
+/// In both cases it does not depend on the language of the description or the component of the warning.
+pub fn fingerprint(warning: &CweWarning) -> String {
+    if let Some(fingerprint) = &warning.fingerprint {
+        return fingerprint.clone();
+    }
+    let mut hasher = fnv::FnvHasher::default();
+    hasher.write(warning.name.as_bytes());
+    for address in warning.addresses.iter() {
+        hasher.write_u8(0);
+        hasher.write(address.as_bytes());
+    }
+    for symbol in warning.symbols.iter() {
+        hasher.write_u8(1);
+        hasher.write(symbol.as_bytes());
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+/// Compute the stable fingerprint of each warning and store it in the `fingerprint` field of the warning.
+///
+/// The location of a warning is given by its first TID or, if no TID of the warning is known, by its first address.
+/// Warnings whose location is not contained in any function of the project get a fingerprint computed from their address offsets.
+/// Warnings without location and address are left unchanged.
+pub fn add_fingerprints(project: &Project, warnings: &mut [CweWarning]) {
+    if warnings.is_empty() {
+        return;
+    }
+    let locations = find_locations(project, warnings);
+    let function_names = get_function_names(project);
+    let program_base = get_program_base(project);
+    let mut function_identities: HashMap<&Tid, String> = HashMap::new();
+    for warning in warnings.iter_mut() {
+        let location = match get_location_key(warning).and_then(|key| locations.get(key)) {
+            Some(location) => location,
+            None => {
+                if let Some(program_base) = program_base {
+                    warning.fingerprint = get_offset_fingerprint(warning, program_base);
+                }
+                continue;
+            }
+        };
+        let function_identity = function_identities
+            .entry(&location.sub.tid)
+            .or_insert_with(|| get_function_identity(location.sub, &function_names));
+        let mut hasher = fnv::FnvHasher::default();
+        hasher.write(warning.name.as_bytes());
+        hasher.write_u8(0);
+        hasher.write(function_identity.as_bytes());
+        if let Some((block, index)) = location.block {
+            hasher.write_u8(0);
+            hasher.write(normalize_block(block, &function_names).as_bytes());
+            hasher.write_usize(index);
+        }
+        for symbol in warning.symbols.iter() {
+            hasher.write_u8(1);
+            hasher.write(symbol.as_bytes());
+        }
+        warning.fingerprint = Some(format!("{:016x}", hasher.finish()));
+    }
+}
+
+/// Get the lowest address of a function in the program.
+/// Offsets relative to it do not change if the whole binary is loaded at another base address.
+fn get_program_base(project: &Project) -> Option<u64> {
+    project
+        .program
+        .term
+        .subs
+        .iter()
+        .filter_map(|sub| u64::from_str_radix(&sub.tid.address, 16).ok())
+        .min()
+}
+
+/// Compute the fingerprint of a warning outside of all functions
+/// from the name of the check, the offsets of its addresses relative to the program base and its symbols.
+/// Returns `None` if the warning has no address.
+fn get_offset_fingerprint(warning: &CweWarning, program_base: u64) -> Option<String> {
+    let offsets: Vec<u64> = warning
+        .addresses
+        .iter()
+        .filter_map(|address| u64::from_str_radix(address, 16).ok())
+        .map(|address| address.wrapping_sub(program_base))
+        .collect();
+    if offsets.is_empty() {
+        return None;
+    }
+    let mut hasher = fnv::FnvHasher::default();
+    hasher.write(warning.name.as_bytes());
+    for offset in offsets {
+        hasher.write_u8(2);
+        hasher.write_u64(offset);
+    }
+    for symbol in warning.symbols.iter() {
+        hasher.write_u8(1);
+        hasher.write(symbol.as_bytes());
+    }
+    Some(format!("{:016x}", hasher.finish()))
+}
+
+/// Map the TIDs of all functions and extern symbols of the project to their names.
+pub(crate) fn get_function_names(project: &Project) -> HashMap<&Tid, &str> {
+    project
+        .program
+        .term
+        .subs
+        .iter()
+        .map(|sub| (&sub.tid, sub.term.name.as_str()))
+        .chain(
+            project
+                .program
+                .term
+                .extern_symbols
+                .iter()
+                .map(|symbol| (&symbol.tid, symbol.name.as_str())),
+        )
+        .collect()
+}
+
+/// The location of a warning inside the program.
+pub(crate) struct Location<'a> {
+    /// The function containing the location.
+    pub sub: &'a Term<Sub>,
+    /// The basic block containing the location and the index of the term inside the block,
+    /// where jumps are counted after the definitions of the block.
+    /// `None` if the location is the function itself.
+    pub block: Option<(&'a Term<Blk>, usize)>,
+}
+
+/// Get the key identifying the location of the warning, i.e. its first TID or its first address.
+pub(crate) fn get_location_key(warning: &CweWarning) -> Option<&str> {
+    warning
+        .tids
+        .get(0)
+        .or_else(|| warning.addresses.get(0))
+        .map(|key| key.as_str())
+}
+
+/// Find the locations of the warnings in the program.
+///
+/// The returned map maps the TID or address identifying the location of a warning to the location.
+/// If several terms have the same address, the first term is used.
+pub(crate) fn find_locations<'a>(
+    project: &'a Project,
+    warnings: &[CweWarning],
+) -> HashMap<String, Location<'a>> {
+    let keys: HashSet<&str> = warnings.iter().filter_map(get_location_key).collect();
+    let mut locations = HashMap::new();
+    for sub in project.program.term.subs.iter() {
+        let sub_tid = format!("{}", sub.tid);
+        if keys.contains(sub_tid.as_str()) {
+            locations.insert(sub_tid, Location { sub, block: None });
+        }
+        for block in sub.term.blocks.iter() {
+            let term_tids = block
+                .term
+                .defs
+                .iter()
+                .map(|def| &def.tid)
+                .chain(block.term.jmps.iter().map(|jmp| &jmp.tid));
+            for (index, tid) in term_tids.enumerate() {
+                for key in [format!("{}", tid), tid.address.clone()].iter() {
+                    if keys.contains(key.as_str()) && !locations.contains_key(key) {
+                        locations.insert(
+                            key.clone(),
+                            Location {
+                                sub,
+                                block: Some((block, index)),
+                            },
+                        );
+                    }
+                }
+            }
+        }
+    }
+    locations
+}
+
+/// Returns `true` if the function name was generated by Ghidra because the binary contains no symbol for the function.
+/// Generated names contain the address of the function and thus change on recompilation.
+pub(crate) fn is_generated_name(name: &str) -> bool {
+    name.starts_with("FUN_") || name.starts_with("thunk_FUN_")
+}
+
+/// Get the string identifying the function in the fingerprint,
+/// i.e. the name of the function or a hash of its normalized IR if the name was generated by Ghidra.
+fn get_function_identity(sub: &Term<Sub>, function_names: &HashMap<&Tid, &str>) -> String {
+    if !is_generated_name(&sub.term.name) {
+        return sub.term.name.clone();
+    }
+    format!(
+        "structure_{:016x}",
+        get_structural_hash(sub, function_names)
+    )
+}
+
+/// Compute a hash of the normalized IR of the function.
+/// The hash does not depend on the addresses of the function
+/// and of the functions called by it, as long as they have non-generated names.
+pub(crate) fn get_structural_hash(sub: &Term<Sub>, function_names: &HashMap<&Tid, &str>) -> u64 {
+    let mut hasher = fnv::FnvHasher::default();
+    for block in sub.term.blocks.iter() {
+        hasher.write(normalize_block(block, function_names).as_bytes());
+        hasher.write_u8(0);
+    }
+    hasher.finish()
+}
+
+/// Get the name of the called function as used in the normalized IR.
+fn normalize_call_target(target: &Tid, function_names: &HashMap<&Tid, &str>) -> String {
+    match function_names.get(target) {
+        Some(name) if !is_generated_name(name) => name.to_string(),
+        _ => "FUN".to_string(),
+    }
+}
+
+/// Get the normalized IR of a basic block with one term per line.
+fn normalize_block(block: &Term<Blk>, function_names: &HashMap<&Tid, &str>) -> String {
+    let mut output = String::new();
+    for def in block.term.defs.iter() {
+        match &def.term {
+            Def::Load { var, address } => {
+                output.push_str(&format!("{} = load ", normalize_variable(var)));
+                normalize_expression(address, &mut output);
+            }
+            Def::Store { address, value } => {
+                output.push_str("store ");
+                normalize_expression(address, &mut output);
+                output.push_str(" = ");
+                normalize_expression(value, &mut output);
+            }
+            Def::Assign { var, value } => {
+                output.push_str(&format!("{} = ", normalize_variable(var)));
+                normalize_expression(value, &mut output);
+            }
+        }
+        output.push('\n');
+    }
+    for jmp in block.term.jmps.iter() {
+        match &jmp.term {
+            Jmp::Branch(_) => output.push_str("branch"),
+            Jmp::BranchInd(target) => {
+                output.push_str("branch_ind ");
+                normalize_expression(target, &mut output);
+            }
+            Jmp::CBranch { condition, .. } => {
+                output.push_str("cbranch ");
+                normalize_expression(condition, &mut output);
+            }
+            Jmp::Call { target, .. } => {
+                output.push_str("call ");
+                output.push_str(&normalize_call_target(target, function_names));
+            }
+            Jmp::CallInd { target, .. } => {
+                output.push_str("call_ind ");
+                normalize_expression(target, &mut output);
+            }
+            Jmp::Return(_) => output.push_str("return"),
+            Jmp::CallOther { description, .. } => {
+                output.push_str("call_other ");
+                output.push_str(description);
+            }
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Get the normalized name of a variable.
+/// The names of temporary registers are generated by Ghidra and thus replaced by a placeholder.
+fn normalize_variable(var: &Variable) -> &str {
+    if var.is_temp {
+        "tmp"
+    } else {
+        &var.name
+    }
+}
+
+/// Append the normalized form of the expression to the output.
+/// Constants are replaced by a placeholder, since they may contain addresses.
+fn normalize_expression(expression: &Expression, output: &mut String) {
+    match expression {
+        Expression::Var(var) => output.push_str(normalize_variable(var)),
+        Expression::Const(_) => output.push_str("const"),
+        Expression::BinOp { op, lhs, rhs } => {
+            output.push_str(&format!("{:?}(", op));
+            normalize_expression(lhs, output);
+            output.push_str(", ");
+            normalize_expression(rhs, output);
+            output.push(')');
+        }
+        Expression::UnOp { op, arg } => {
+            output.push_str(&format!("{:?}(", op));
+            normalize_expression(arg, output);
+            output.push(')');
+        }
+        Expression::Cast { op, arg, .. } => {
+            output.push_str(&format!("{:?}(", op));
+            normalize_expression(arg, output);
+            output.push(')');
+        }
+        Expression::Unknown { description, .. } => output.push_str(description),
+        Expression::Subpiece { arg, .. } => {
+            output.push_str("Subpiece(");
+            normalize_expression(arg, output);
+            output.push(')');
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Generate a project with a function `main` and a stripped function `FUN_00401000`.
+    /// Both functions and the first term of their blocks are located at the given address.
+    /// The blocks end with a call to `strcpy`.
+    fn mock_project(address: &str) -> Project {
+        let mut project = Project::mock_empty();
+        project
+            .program
+            .term
+            .extern_symbols
+            .push(ExternSymbol::mock_with_name("strcpy"));
+        for (name, offset) in [("main", 0), ("FUN_00401000", 0x100)].iter() {
+            let mut def = Def::assign(
+                &format!("def_{}", name),
+                Variable::mock("RDI", 8),
+                Expression::Var(Variable::mock("RSP", 8)).plus_const(*offset),
+            );
+            def.tid.address = address.to_string();
+            let mut sub = Sub::mock(name);
+            sub.tid.address = address.to_string();
+            sub.term.blocks.push(Term {
+                tid: Tid::new(format!("blk_{}", name)),
+                term: Blk {
+                    defs: vec![def],
+                    jmps: vec![Term {
+                        tid: Tid::new(format!("call_{}", name)),
+                        term: Jmp::Call {
+                            target: Tid::new("strcpy"),
+                            return_: None,
+                        },
+                    }],
+                },
+            });
+            project.program.term.subs.push(sub);
+        }
+        project
+    }
+
+    #[test]
+    fn stable_fingerprints() {
+        let mut warnings = vec![
+            CweWarning::new("CWE676", "0.1", "named").tids(vec!["call_main".to_string()]),
+            CweWarning::new("CWE676", "0.1", "stripped")
+                .tids(vec!["call_FUN_00401000".to_string()]),
+            CweWarning::new("CWE676", "0.1", "by address").addresses(vec!["00401000".to_string()]),
+            CweWarning::new("CWE676", "0.1", "unknown location")
+                .addresses(vec!["00409000".to_string()]),
+        ];
+        add_fingerprints(&mock_project("00401000"), &mut warnings);
+        assert!(warnings.iter().all(|warning| warning.fingerprint.is_some()));
+        assert_ne!(fingerprint(&warnings[0]), fingerprint(&warnings[1]));
+        // The fingerprints do not change if the code moves to another address.
+        let mut moved_warnings = warnings.clone();
+        for warning in moved_warnings.iter_mut() {
+            warning.fingerprint = None;
+        }
+        moved_warnings[2].addresses = vec!["00402000".to_string()];
+        moved_warnings[3].addresses = vec!["0040a000".to_string()];
+        add_fingerprints(&mock_project("00402000"), &mut moved_warnings);
+        for (warning, moved_warning) in warnings.iter().zip(moved_warnings.iter()) {
+            assert_eq!(warning.fingerprint, moved_warning.fingerprint);
+        }
+    }
+
+    #[test]
+    fn address_based_fingerprints() {
+        let warning =
+            CweWarning::new("CWE476", "0.3", "first").addresses(vec!["00401000".to_string()]);
+        let other_warning = warning.clone().addresses(vec!["00401020".to_string()]);
+        assert_ne!(fingerprint(&warning), fingerprint(&other_warning));
+        let mut translated = warning.clone();
+        translated.description = "erste".to_string();
+        assert_eq!(fingerprint(&warning), fingerprint(&translated));
+    }
+}
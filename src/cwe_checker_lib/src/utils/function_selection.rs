@@ -0,0 +1,103 @@
+//! Restrict the analysis to selected functions and their callees.
+//!
+//! Auditors are often interested only in a handful of functions of a large binary, e.g. its parsers.
+//! If functions are selected, all functions that are not reachable from them through direct calls
+//! are removed from the project before the analyses run.
+//! Like for the restriction to address ranges (see [`crate::utils::address_ranges`]),
+//! calls to removed functions are treated like calls to extern functions without known semantics.
+//! The selected functions become the entry points of the analysis.
+
+use crate::analysis::call_graph::CallGraph;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::address_ranges::remove_functions;
+use crate::utils::entry_points::find_sub_by_name_or_address;
+use crate::utils::log::LogMessage;
+use std::collections::HashSet;
+
+/// Remove all functions that are not reachable from the selected functions through direct calls from the project.
+///
+/// The functions are selected by their names or (hexadecimal) start addresses.
+/// The selected functions are added to the entry points of the project.
+/// Returns an error if one of the selected functions does not exist.
+pub fn restrict_project_to_functions(
+    project: &mut Project,
+    selection: &[String],
+) -> Result<Vec<LogMessage>, Error> {
+    let selected_tids = selection
+        .iter()
+        .map(|identifier| {
+            find_sub_by_name_or_address(project, identifier)
+                .ok_or_else(|| anyhow!("Function {} not found", identifier))
+        })
+        .collect::<Result<Vec<Tid>, Error>>()?;
+    let reachable: HashSet<Tid> =
+        CallGraph::new(&project.program).get_reachable_functions(&selected_tids);
+    let removed_subs = remove_functions(project, |sub| reachable.contains(&sub.tid));
+    for tid in selected_tids.iter() {
+        if !project.program.term.entry_points.contains(tid) {
+            project.program.term.entry_points.push(tid.clone());
+        }
+    }
+    Ok(vec![LogMessage::new_info(format!(
+        "Restricted the analysis to {} selected functions and their callees. Excluded {} functions from the analysis.",
+        selected_tids.len(),
+        removed_subs.len()
+    ))
+    .source("Function Selection")])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn function_selection() {
+        let mut project = Project::mock_empty();
+        for (name, callee) in [
+            ("main", Some("parse_header")),
+            ("parse_header", Some("read_field")),
+            ("read_field", None),
+            ("unrelated", None),
+        ]
+        .iter()
+        {
+            let mut sub = Sub::mock(*name);
+            if let Some(callee) = callee {
+                sub.term.blocks.push(Term {
+                    tid: Tid::new(format!("blk_{}", name)),
+                    term: Blk {
+                        defs: Vec::new(),
+                        jmps: vec![Term {
+                            tid: Tid::new(format!("call_{}", name)),
+                            term: Jmp::Call {
+                                target: Tid::new(*callee),
+                                return_: None,
+                            },
+                        }],
+                    },
+                });
+            }
+            project.program.term.subs.push(sub);
+        }
+        project.program.term.entry_points = vec![Tid::new("main")];
+
+        let logs =
+            restrict_project_to_functions(&mut project, &["parse_header".to_string()]).unwrap();
+        assert_eq!(logs.len(), 1);
+        let names: Vec<&str> = project
+            .program
+            .term
+            .subs
+            .iter()
+            .map(|sub| sub.term.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["parse_header", "read_field"]);
+        assert_eq!(
+            project.program.term.entry_points,
+            vec![Tid::new("parse_header")]
+        );
+        assert_eq!(project.program.term.extern_symbols.len(), 2);
+        assert!(restrict_project_to_functions(&mut project, &["main".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,196 @@
+//! Detection of compiler-generated functions.
+//!
+//! Compilers and linkers add functions to a binary that do not correspond to any code written by the developers,
+//! e.g. static initializers, the `__do_global_dtors_aux` family of C runtime functions, thunks or profiling stubs.
+//! Warnings in these functions recur in almost every binary and are rarely actionable.
+//!
+//! Compiler-generated functions are identified by configured name patterns
+//! (which may contain `*` as a wildcard matching arbitrary substrings)
+//! and optionally by their structure, i.e. functions consisting only of a jump to another function.
+//! Warnings located in compiler-generated functions are either removed or down-ranked by one severity level.
+
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::components::matches_pattern;
+use crate::utils::fingerprint::{find_locations, get_location_key};
+use crate::utils::log::{CweWarning, Severity};
+
+/// What to do with warnings in compiler-generated functions.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    /// Report the warnings unchanged.
+    Keep,
+    /// Lower the severity of the warnings by one level.
+    Downrank,
+    /// Remove the warnings from the results.
+    Skip,
+}
+
+impl Default for Action {
+    fn default() -> Action {
+        Action::Keep
+    }
+}
+
+/// The configuration of the detection of compiler-generated functions.
+///
+/// The configuration is read from the `GeneratedCode` section of the configuration file.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+#[serde(default)]
+pub struct Config {
+    /// What to do with warnings in compiler-generated functions.
+    pub action: Action,
+    /// Name patterns of compiler-generated functions, e.g. `_GLOBAL__sub_I_*`.
+    pub functions: Vec<String>,
+    /// If set to `true`, thunks (i.e. functions consisting only of a jump to another function)
+    /// are also treated as compiler-generated.
+    pub detect_thunks: bool,
+}
+
+impl Config {
+    /// Returns `true` if the function is compiler-generated according to its name or its structure.
+    pub fn is_generated_function(&self, sub: &Term<Sub>) -> bool {
+        self.functions
+            .iter()
+            .any(|pattern| matches_pattern(pattern, &sub.term.name))
+            || (self.detect_thunks && is_thunk(sub))
+    }
+
+    /// Remove or down-rank the warnings located in compiler-generated functions according to the configured action.
+    /// Warnings whose location is not inside a function are left unchanged.
+    ///
+    /// Returns the number of removed or down-ranked warnings.
+    pub fn process_warnings(&self, project: &Project, warnings: &mut Vec<CweWarning>) -> usize {
+        if self.action == Action::Keep || warnings.is_empty() {
+            return 0;
+        }
+        let locations = find_locations(project, warnings);
+        let is_generated: Vec<bool> = warnings
+            .iter()
+            .map(|warning| {
+                get_location_key(warning)
+                    .and_then(|key| locations.get(key))
+                    .map(|location| self.is_generated_function(location.sub))
+                    .unwrap_or(false)
+            })
+            .collect();
+        let number_of_generated = is_generated.iter().filter(|generated| **generated).count();
+        match self.action {
+            Action::Keep => (),
+            Action::Downrank => {
+                for (warning, generated) in warnings.iter_mut().zip(is_generated) {
+                    if generated {
+                        warning.severity = downrank(warning.severity);
+                    }
+                }
+            }
+            Action::Skip => {
+                let mut is_generated = is_generated.into_iter();
+                warnings.retain(|_| !is_generated.next().unwrap());
+            }
+        }
+        number_of_generated
+    }
+}
+
+/// Returns `true` if the function consists of a single block without definitions
+/// that jumps to another function without returning, i.e. if the function is a thunk.
+fn is_thunk(sub: &Term<Sub>) -> bool {
+    match &sub.term.blocks[..] {
+        [block] => {
+            block.term.defs.is_empty()
+                && matches!(
+                    &block.term.jmps[..],
+                    [Term {
+                        term: Jmp::Call { return_: None, .. },
+                        ..
+                    }] | [Term {
+                        term: Jmp::CallInd { return_: None, .. },
+                        ..
+                    }]
+                )
+        }
+        _ => false,
+    }
+}
+
+/// Get the next lower severity level. The lowest level stays unchanged.
+fn downrank(severity: Severity) -> Severity {
+    match severity {
+        Severity::High => Severity::Medium,
+        Severity::Medium => Severity::Low,
+        Severity::Low | Severity::Info => Severity::Info,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_project() -> Project {
+        let mut project = Project::mock_empty();
+        let mut thunk = Sub::mock("thunk_target");
+        thunk.term.blocks.push(Term {
+            tid: Tid::new("blk_thunk"),
+            term: Blk {
+                defs: Vec::new(),
+                jmps: vec![Term {
+                    tid: Tid::new("jmp_thunk"),
+                    term: Jmp::Call {
+                        target: Tid::new("target"),
+                        return_: None,
+                    },
+                }],
+            },
+        });
+        let mut initializer = Sub::mock("_GLOBAL__sub_I_main");
+        let mut block = Blk::mock();
+        block.term.defs.push(Def::assign(
+            "def_init",
+            Variable::mock("RAX", 8),
+            Expression::Const(Bitvector::zero(64.into())),
+        ));
+        initializer.term.blocks.push(block);
+        let mut main = Sub::mock("main");
+        let mut block = Blk::mock();
+        block.term.defs.push(Def::assign(
+            "def_main",
+            Variable::mock("RAX", 8),
+            Expression::Const(Bitvector::zero(64.into())),
+        ));
+        main.term.blocks.push(block);
+        project.program.term.subs = vec![thunk, initializer, main];
+        project
+    }
+
+    #[test]
+    fn generated_function_warnings() {
+        let project = mock_project();
+        let warning = |tid: &str| {
+            CweWarning::new("CWE476", "0.3", "warning")
+                .tids(vec![tid.to_string()])
+                .severity(Severity::High)
+        };
+        let mut config = Config {
+            action: Action::Downrank,
+            functions: vec!["_GLOBAL__sub_I_*".to_string()],
+            detect_thunks: true,
+        };
+        let mut warnings = vec![
+            warning("jmp_thunk"),
+            warning("def_init"),
+            warning("def_main"),
+        ];
+        assert_eq!(config.process_warnings(&project, &mut warnings), 2);
+        assert_eq!(warnings[0].severity, Severity::Medium);
+        assert_eq!(warnings[1].severity, Severity::Medium);
+        assert_eq!(warnings[2].severity, Severity::High);
+
+        config.action = Action::Skip;
+        config.detect_thunks = false;
+        assert_eq!(config.process_warnings(&project, &mut warnings), 1);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].tids, vec!["jmp_thunk".to_string()]);
+    }
+}
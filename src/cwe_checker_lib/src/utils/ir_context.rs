@@ -0,0 +1,233 @@
+//! Snippets of the intermediate representation around the locations of CWE warnings.
+//!
+//! The context of a warning contains the `Def` and `Jmp` terms of the basic block containing the location of the warning,
+//! restricted to the given number of terms before and after the location.
+//! It shows which operation triggered the warning without having to open the binary in Ghidra.
+//!
+//! Checks can attach the context to their warnings themselves with [`get_ir_context`].
+//! Otherwise [`add_ir_context`] can be used to add it to all warnings after the analysis.
+
+use crate::intermediate_representation::*;
+use crate::utils::fingerprint::{find_locations, get_function_names, get_location_key};
+use crate::utils::log::{ContextTerm, CweWarning};
+use std::collections::HashMap;
+
+/// Get the context of the term with the given TID,
+/// i.e. up to `radius` terms before and after the term inside its basic block.
+///
+/// Returns `None` if the term is not contained in any basic block of the project.
+pub fn get_ir_context(project: &Project, tid: &Tid, radius: usize) -> Option<Vec<ContextTerm>> {
+    let function_names = get_function_names(project);
+    for sub in project.program.term.subs.iter() {
+        for block in sub.term.blocks.iter() {
+            let position = block
+                .term
+                .defs
+                .iter()
+                .map(|def| &def.tid)
+                .chain(block.term.jmps.iter().map(|jmp| &jmp.tid))
+                .position(|term_tid| term_tid == tid);
+            if let Some(index) = position {
+                return Some(get_block_context(block, index, radius, &function_names));
+            }
+        }
+    }
+    None
+}
+
+/// Add the context of the location of each warning to the warning.
+///
+/// The location of a warning is given by its first TID or, if no TID of the warning is known, by its first address.
+/// Warnings that already contain context and warnings whose location is not a term inside a basic block are left unchanged.
+pub fn add_ir_context(project: &Project, warnings: &mut [CweWarning], radius: usize) {
+    if warnings.iter().all(|warning| warning.ir_context.is_some()) {
+        return;
+    }
+    let locations = find_locations(project, warnings);
+    let function_names = get_function_names(project);
+    for warning in warnings.iter_mut() {
+        if warning.ir_context.is_some() {
+            continue;
+        }
+        let location = get_location_key(warning).and_then(|key| locations.get(key));
+        if let Some((block, index)) = location.and_then(|location| location.block) {
+            warning.ir_context = Some(get_block_context(block, index, radius, &function_names));
+        }
+    }
+}
+
+/// Get the terms of the block from `radius` terms before up to `radius` terms after the term with the given index,
+/// where jumps are counted after the definitions of the block.
+fn get_block_context(
+    block: &Term<Blk>,
+    index: usize,
+    radius: usize,
+    function_names: &HashMap<&Tid, &str>,
+) -> Vec<ContextTerm> {
+    let defs = block
+        .term
+        .defs
+        .iter()
+        .map(|def| (&def.tid, format_def(&def.term)));
+    let jmps = block
+        .term
+        .jmps
+        .iter()
+        .map(|jmp| (&jmp.tid, format_jmp(&jmp.term, function_names)));
+    defs.chain(jmps)
+        .enumerate()
+        .skip(index.saturating_sub(radius))
+        .take_while(|(term_index, _)| *term_index <= index.saturating_add(radius))
+        .map(|(term_index, (tid, term))| ContextTerm {
+            tid: format!("{}", tid),
+            address: tid.address.clone(),
+            term,
+            is_location: term_index == index,
+        })
+        .collect()
+}
+
+/// Get a human-readable representation of the definition.
+fn format_def(def: &Def) -> String {
+    match def {
+        Def::Load { var, address } => {
+            format!("{} := Load({})", var.name, format_expression(address))
+        }
+        Def::Store { address, value } => format!(
+            "Store({}) := {}",
+            format_expression(address),
+            format_expression(value)
+        ),
+        Def::Assign { var, value } => format!("{} := {}", var.name, format_expression(value)),
+    }
+}
+
+/// Get a human-readable representation of the jump.
+/// Calls to functions are represented by the name of the called function.
+fn format_jmp(jmp: &Jmp, function_names: &HashMap<&Tid, &str>) -> String {
+    match jmp {
+        Jmp::Branch(target) => format!("Branch {}", target),
+        Jmp::BranchInd(target) => format!("BranchInd {}", format_expression(target)),
+        Jmp::CBranch { target, condition } => {
+            format!("CBranch {} if {}", target, format_expression(condition))
+        }
+        Jmp::Call { target, .. } => match function_names.get(target) {
+            Some(name) => format!("Call {}", name),
+            None => format!("Call {}", target),
+        },
+        Jmp::CallInd { target, .. } => format!("CallInd {}", format_expression(target)),
+        Jmp::Return(target) => format!("Return {}", format_expression(target)),
+        Jmp::CallOther { description, .. } => format!("CallOther {}", description),
+    }
+}
+
+/// Get a human-readable representation of the expression.
+/// Constants are printed in hexadecimal, negative constants with a sign.
+pub(crate) fn format_expression(expression: &Expression) -> String {
+    match expression {
+        Expression::Var(var) => var.name.clone(),
+        Expression::Const(bitvector) => match bitvector.try_to_i64() {
+            Ok(value) if value < 0 => format!("-0x{:x}", -i128::from(value)),
+            Ok(value) => format!("0x{:x}", value),
+            Err(_) => format!("0x{:x}", bitvector),
+        },
+        Expression::BinOp { op, lhs, rhs } => format!(
+            "{:?}({}, {})",
+            op,
+            format_expression(lhs),
+            format_expression(rhs)
+        ),
+        Expression::UnOp { op, arg } => format!("{:?}({})", op, format_expression(arg)),
+        Expression::Cast { op, arg, .. } => format!("{:?}({})", op, format_expression(arg)),
+        Expression::Unknown { description, .. } => description.clone(),
+        Expression::Subpiece {
+            low_byte,
+            size,
+            arg,
+        } => format!(
+            "Subpiece({}, {}, {})",
+            format_expression(arg),
+            u64::from(*low_byte),
+            u64::from(*size)
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Generate a project with a function `main` containing a block with three definitions and a call to `strcpy`.
+    fn mock_project() -> Project {
+        let mut project = Project::mock_empty();
+        project
+            .program
+            .term
+            .extern_symbols
+            .push(ExternSymbol::mock_with_name("strcpy"));
+        let defs = vec![
+            Def::assign(
+                "def_0",
+                Variable::mock("RSP", 8),
+                Expression::Var(Variable::mock("RSP", 8)).plus_const(-16),
+            ),
+            Def::assign(
+                "def_1",
+                Variable::mock("RDI", 8),
+                Expression::Var(Variable::mock("RSP", 8)),
+            ),
+            Def::assign(
+                "def_2",
+                Variable::mock("RSI", 8),
+                Expression::const_from_i64(0x1000),
+            ),
+        ];
+        let mut sub = Sub::mock("main");
+        sub.term.blocks.push(Term {
+            tid: Tid::new("blk"),
+            term: Blk {
+                defs,
+                jmps: vec![Term {
+                    tid: Tid::new("call"),
+                    term: Jmp::Call {
+                        target: Tid::new("strcpy"),
+                        return_: None,
+                    },
+                }],
+            },
+        });
+        project.program.term.subs.push(sub);
+        project
+    }
+
+    #[test]
+    fn context_of_term() {
+        let project = mock_project();
+        let context = get_ir_context(&project, &Tid::new("def_2"), 1).unwrap();
+        let terms: Vec<&str> = context.iter().map(|term| term.term.as_str()).collect();
+        assert_eq!(terms, vec!["RDI := RSP", "RSI := 0x1000", "Call strcpy"]);
+        assert!(context[1].is_location);
+        assert!(!context[0].is_location && !context[2].is_location);
+
+        let context = get_ir_context(&project, &Tid::new("def_0"), 1).unwrap();
+        assert_eq!(context.len(), 2);
+        assert_eq!(context[0].term, "RSP := IntAdd(RSP, -0x10)");
+        assert!(get_ir_context(&project, &Tid::new("unknown"), 1).is_none());
+    }
+
+    #[test]
+    fn context_of_warnings() {
+        let project = mock_project();
+        let mut warnings = vec![
+            CweWarning::new("CWE676", "0.1", "Call to strcpy").tids(vec!["call".to_string()]),
+            CweWarning::new("CWE676", "0.1", "Outside of the program")
+                .tids(vec!["unknown".to_string()]),
+        ];
+        add_ir_context(&project, &mut warnings, 0);
+        let context = warnings[0].ir_context.as_ref().unwrap();
+        assert_eq!(context.len(), 1);
+        assert_eq!(context[0].tid, "call");
+        assert!(context[0].is_location);
+        assert!(warnings[1].ir_context.is_none());
+    }
+}
@@ -0,0 +1,221 @@
+//! Localization of the descriptions of CWE warnings.
+//!
+//! A message catalog contains translations for the (English) description templates of the check modules.
+//! A template is the format string used by the check module to generate the description,
+//! where each `{}` is a placeholder for a generated value (e.g. an address or a function name).
+//! In the translation the placeholders are referenced by their index, i.e. `{0}`, `{1}`, etc.,
+//! so that the order of the values may change in the translated sentence.
+//!
+//! Only the description of a warning gets translated.
+//! All machine-readable fields (name, addresses, TIDs, symbols, etc.) stay language-independent.
+//! Descriptions without a matching template in the catalog are kept in English.
+//!
+//! Message catalogs are JSON files located in the `locales` subdirectory of the configuration directory
+//! and are named after the language code, e.g. `locales/de.json`.
+
+use super::get_config_dir;
+use crate::prelude::*;
+use crate::utils::log::CweWarning;
+use std::path::Path;
+
+/// The language of the descriptions generated by the check modules.
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// The translation of a single description template.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct MessageTemplate {
+    /// The English description template with `{}` as placeholders.
+    pub template: String,
+    /// The translated template with `{0}`, `{1}`, etc. referencing the placeholders of the English template.
+    pub translation: String,
+}
+
+/// A catalog of translated description templates for one language.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct MessageCatalog {
+    /// The language code of the catalog, e.g. `de`.
+    pub language: String,
+    /// The translated description templates.
+    pub messages: Vec<MessageTemplate>,
+}
+
+impl MessageCatalog {
+    /// Load the message catalog for the given language.
+    ///
+    /// The language is either given as a language code, in which case the catalog is read from the configuration directory,
+    /// or as a path to a message catalog file.
+    pub fn load(language: &str) -> Result<MessageCatalog, Error> {
+        let path = Path::new(language);
+        let path = if path.is_file() {
+            path.to_path_buf()
+        } else {
+            get_config_dir()
+                .join("locales")
+                .join(format!("{}.json", language))
+        };
+        let file = std::fs::read_to_string(&path)
+            .map_err(|err| anyhow!("Could not read message catalog {}: {}", path.display(), err))?;
+        Ok(serde_json::from_str(&file)?)
+    }
+
+    /// Get the translation of the given description.
+    /// Returns `None` if no template of the catalog matches the description.
+    pub fn translate(&self, description: &str) -> Option<String> {
+        self.messages.iter().find_map(|message| {
+            let values = match_template(&message.template, description)?;
+            Some(fill_translation(&message.translation, &values))
+        })
+    }
+
+    /// Translate the descriptions of the given CWE warnings.
+    pub fn localize_warnings(&self, warnings: &mut [CweWarning]) {
+        for warning in warnings.iter_mut() {
+            if let Some(description) = self.translate(&warning.description) {
+                warning.description = description;
+            }
+        }
+    }
+}
+
+/// Match the description against the template
+/// and return the values that were inserted for the placeholders of the template.
+/// Returns `None` if the description does not match the template.
+fn match_template<'a>(template: &str, description: &'a str) -> Option<Vec<&'a str>> {
+    let mut literals = template.split("{}");
+    let first_literal = literals.next().unwrap();
+    let literals: Vec<&str> = literals.collect();
+    let mut remaining = description.strip_prefix(first_literal)?;
+    if literals.is_empty() {
+        return if remaining.is_empty() {
+            Some(Vec::new())
+        } else {
+            None
+        };
+    }
+    let mut values = Vec::new();
+    for (index, literal) in literals.iter().enumerate() {
+        if index + 1 == literals.len() {
+            values.push(remaining.strip_suffix(literal)?);
+        } else if literal.is_empty() {
+            // Two adjacent placeholders cannot be separated.
+            return None;
+        } else {
+            let position = remaining.find(literal)?;
+            values.push(&remaining[..position]);
+            remaining = &remaining[position + literal.len()..];
+        }
+    }
+    Some(values)
+}
+
+/// Replace the indexed placeholders `{0}`, `{1}`, etc. in the translation with the given values.
+/// Placeholders with an index without a corresponding value are kept as they are.
+fn fill_translation(translation: &str, values: &[&str]) -> String {
+    let mut result = String::new();
+    let mut remaining = translation;
+    while let Some(start) = remaining.find('{') {
+        result.push_str(&remaining[..start]);
+        remaining = &remaining[start..];
+        let value = remaining.find('}').and_then(|end| {
+            let index: usize = remaining[1..end].parse().ok()?;
+            Some((values.get(index)?, end))
+        });
+        match value {
+            Some((value, end)) => {
+                result.push_str(value);
+                remaining = &remaining[end + 1..];
+            }
+            None => {
+                result.push('{');
+                remaining = &remaining[1..];
+            }
+        }
+    }
+    result.push_str(remaining);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_catalog() -> MessageCatalog {
+        MessageCatalog {
+            language: "de".to_string(),
+            messages: vec![
+                MessageTemplate {
+                    template: "(Use After Free) Call to {} may access freed memory at {}".to_string(),
+                    translation: "(Use After Free) Aufruf bei {1} von {0} greift eventuell auf freigegebenen Speicher zu".to_string(),
+                },
+                MessageTemplate {
+                    template: "(Information Exposure Through Debug Information) The binary contains debug symbols.".to_string(),
+                    translation: "(Informationspreisgabe durch Debug-Informationen) Die Binärdatei enthält Debug-Symbole.".to_string(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn template_matching() {
+        assert_eq!(
+            match_template("Call to {} at {}.", "Call to free at 00401000."),
+            Some(vec!["free", "00401000"])
+        );
+        assert_eq!(match_template("Call to {} at {}.", "Call to free."), None);
+        assert_eq!(
+            match_template("No placeholder", "No placeholder"),
+            Some(vec![])
+        );
+        assert_eq!(match_template("No placeholder", "No placeholder!"), None);
+        assert_eq!(match_template("{}", "anything"), Some(vec!["anything"]));
+    }
+
+    #[test]
+    fn translation() {
+        let catalog = mock_catalog();
+        assert_eq!(
+            catalog.translate("(Use After Free) Call to free may access freed memory at 00401000"),
+            Some(
+                "(Use After Free) Aufruf bei 00401000 von free greift eventuell auf freigegebenen Speicher zu"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            catalog.translate("(Double Free) Object may have been freed before at 00401000"),
+            None
+        );
+        assert_eq!(
+            fill_translation("{1} {x} {5} {0}", &["a", "b"]),
+            "b {x} {5} a"
+        );
+    }
+
+    #[test]
+    fn localize_warnings() {
+        let catalog = mock_catalog();
+        let mut warnings = vec![
+            CweWarning::new(
+                "CWE215",
+                "0.2",
+                "(Information Exposure Through Debug Information) The binary contains debug symbols.",
+            )
+            .symbols(vec!["main".to_string()]),
+            CweWarning::new(
+                "CWE415",
+                "0.3",
+                "(Double Free) Object may have been freed before at 00401000",
+            ),
+        ];
+        catalog.localize_warnings(&mut warnings);
+        assert_eq!(
+            warnings[0].description,
+            "(Informationspreisgabe durch Debug-Informationen) Die Binärdatei enthält Debug-Symbole."
+        );
+        assert_eq!(warnings[0].name, "CWE215");
+        assert_eq!(warnings[0].symbols, vec!["main".to_string()]);
+        assert_eq!(
+            warnings[1].description,
+            "(Double Free) Object may have been freed before at 00401000"
+        );
+    }
+}
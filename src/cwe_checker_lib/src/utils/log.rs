@@ -1,4 +1,9 @@
 use crate::prelude::*;
+use crate::utils::audit::AuditLog;
+use crate::utils::fingerprint::fingerprint;
+use crate::utils::output_sinks::OutputSink;
+use crate::utils::path_witness::PathWitness;
+use std::collections::{BTreeMap, BTreeSet};
 use std::thread::JoinHandle;
 
 /// A CWE warning message.
@@ -11,6 +16,36 @@ pub struct CweWarning {
     pub symbols: Vec<String>,
     pub other: Vec<Vec<String>>,
     pub description: String,
+    /// Advice on how to fix the reported issue, if provided by the check.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<Remediation>,
+    /// Hints on the exploitability of the reported issue, if provided by the check.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exploitability: Option<ExploitabilityHint>,
+    /// An approximate CVSS v3.1 vector synthesized from the exploitability hints.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cvss_vector: Option<String>,
+    /// The name of the component of the binary that the warning belongs to, if configured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+    /// The severity of the reported issue as assessed by the check.
+    #[serde(default)]
+    pub severity: Severity,
+    /// Whether the reported issue was proven to exist or only possibly exists.
+    #[serde(default)]
+    pub verdict: Verdict,
+    /// A fingerprint identifying the warning across different builds of the analyzed program,
+    /// see [`crate::utils::fingerprint`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    /// The terms of the intermediate representation around the location of the warning,
+    /// see [`crate::utils::ir_context`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ir_context: Option<Vec<ContextTerm>>,
+    /// A path in the control flow graph from the source to the sink of the warning,
+    /// see [`crate::utils::path_witness`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_witness: Option<PathWitness>,
 }
 
 impl CweWarning {
@@ -28,6 +63,15 @@ impl CweWarning {
             symbols: Vec::new(),
             other: Vec::new(),
             description: description.to_string(),
+            remediation: None,
+            exploitability: None,
+            cvss_vector: None,
+            component: None,
+            severity: Severity::default(),
+            verdict: Verdict::default(),
+            fingerprint: None,
+            ir_context: None,
+            path_witness: None,
         }
     }
 
@@ -54,6 +98,233 @@ impl CweWarning {
         self.other = other;
         self
     }
+
+    /// Sets the remediation advice of the CweWarning
+    pub fn remediation(mut self, remediation: Remediation) -> CweWarning {
+        self.remediation = Some(remediation);
+        self
+    }
+
+    /// Sets the severity of the CweWarning
+    pub fn severity(mut self, severity: Severity) -> CweWarning {
+        self.severity = severity;
+        self
+    }
+
+    /// Sets the verdict of the CweWarning
+    pub fn verdict(mut self, verdict: Verdict) -> CweWarning {
+        self.verdict = verdict;
+        self
+    }
+
+    /// Sets the terms of the intermediate representation around the location of the CweWarning
+    pub fn ir_context(mut self, context: Vec<ContextTerm>) -> CweWarning {
+        self.ir_context = Some(context);
+        self
+    }
+
+    /// Sets the exploitability hints of the CweWarning
+    /// and the CVSS vector synthesized from them.
+    pub fn exploitability(mut self, hint: ExploitabilityHint) -> CweWarning {
+        self.cvss_vector = Some(hint.to_cvss_vector());
+        self.exploitability = Some(hint);
+        self
+    }
+}
+
+/// The severity of the issue reported by a CWE warning.
+///
+/// The severities are ordered from `Info` (lowest) to `High` (highest).
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The warning is informational and does not indicate a weakness by itself.
+    Info,
+    /// The reported weakness is unlikely to be exploitable or has only minor impact.
+    Low,
+    /// The reported weakness may be exploitable under certain conditions.
+    Medium,
+    /// The reported weakness is likely exploitable and has a severe impact.
+    High,
+}
+
+impl Default for Severity {
+    fn default() -> Severity {
+        Severity::Medium
+    }
+}
+
+impl std::str::FromStr for Severity {
+    type Err = Error;
+
+    /// Parse a severity from its lowercase name, e.g. `medium`.
+    fn from_str(severity: &str) -> Result<Severity, Error> {
+        match severity {
+            "info" => Ok(Severity::Info),
+            "low" => Ok(Severity::Low),
+            "medium" => Ok(Severity::Medium),
+            "high" => Ok(Severity::High),
+            _ => Err(anyhow!("Unknown severity: {}", severity)),
+        }
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Info => write!(formatter, "info"),
+            Severity::Low => write!(formatter, "low"),
+            Severity::Medium => write!(formatter, "medium"),
+            Severity::High => write!(formatter, "high"),
+        }
+    }
+}
+
+/// The verdict of a check on a reported issue.
+///
+/// Most checks over-approximate the behavior of the analyzed program,
+/// so that an issue found on some path may not exist in the actual program,
+/// e.g. because the path is infeasible.
+/// Checks mark issues that hold on all modeled paths (e.g. a call to a banned function
+/// or a parameter known to be a constant) as proven.
+///
+/// The verdicts are ordered from `Possible` (lowest) to `Proven` (highest).
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Verdict {
+    /// The issue exists on some path of the over-approximated program behavior.
+    Possible,
+    /// The issue exists on all modeled paths.
+    Proven,
+}
+
+impl Default for Verdict {
+    fn default() -> Verdict {
+        Verdict::Possible
+    }
+}
+
+impl std::str::FromStr for Verdict {
+    type Err = Error;
+
+    /// Parse a verdict from its lowercase name, e.g. `proven`.
+    fn from_str(verdict: &str) -> Result<Verdict, Error> {
+        match verdict {
+            "possible" => Ok(Verdict::Possible),
+            "proven" => Ok(Verdict::Proven),
+            _ => Err(anyhow!("Unknown verdict: {}", verdict)),
+        }
+    }
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Possible => write!(formatter, "possible"),
+            Verdict::Proven => write!(formatter, "proven"),
+        }
+    }
+}
+
+/// A policy deciding which CWE warnings should cause a non-zero exit code,
+/// e.g. when the cwe_checker is used as a gate in continuous integration.
+///
+/// A warning matches the policy if its severity is at least the minimum severity of the policy
+/// or if it was generated by one of the checks listed in the policy.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct FailOnPolicy {
+    /// Warnings with at least this severity match the policy.
+    pub min_severity: Option<Severity>,
+    /// Warnings of these checks (e.g. `CWE476`) match the policy regardless of their severity.
+    pub cwes: BTreeSet<String>,
+}
+
+impl FailOnPolicy {
+    /// Return whether the given warning matches the policy.
+    pub fn matches(&self, warning: &CweWarning) -> bool {
+        self.min_severity
+            .map_or(false, |min_severity| warning.severity >= min_severity)
+            || self.cwes.contains(&warning.name)
+    }
+
+    /// Return whether any of the given warnings matches the policy.
+    pub fn matches_any(&self, warnings: &[CweWarning]) -> bool {
+        warnings.iter().any(|warning| self.matches(warning))
+    }
+}
+
+impl std::str::FromStr for FailOnPolicy {
+    type Err = Error;
+
+    /// Parse a policy from a comma separated list of a severity and check names, e.g. `high` or `CWE416,CWE476`.
+    fn from_str(policy: &str) -> Result<FailOnPolicy, Error> {
+        let mut fail_on = FailOnPolicy::default();
+        for item in policy
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+        {
+            if let Ok(severity) = item.parse::<Severity>() {
+                if fail_on.min_severity.is_some() {
+                    return Err(anyhow!("More than one severity given: {}", policy));
+                }
+                fail_on.min_severity = Some(severity);
+            } else if item.starts_with("CWE") && item.len() > 3 {
+                fail_on.cwes.insert(item.to_string());
+            } else {
+                return Err(anyhow!(
+                    "{} is neither a severity nor a check name like 'CWE476'",
+                    item
+                ));
+            }
+        }
+        if fail_on == FailOnPolicy::default() {
+            return Err(anyhow!("Empty policy"));
+        }
+        Ok(fail_on)
+    }
+}
+
+/// Structured advice on how to fix the issue reported by a CWE warning.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord, Default)]
+pub struct Remediation {
+    /// A short description of how to fix the reported issue.
+    pub advice: String,
+    /// Links to further information about the weakness and its mitigation.
+    pub references: Vec<String>,
+}
+
+impl Remediation {
+    /// Create new remediation advice for the CWE with the given number.
+    /// A link to the description of the CWE on <https://cwe.mitre.org> is added to the references.
+    pub fn new(cwe_number: u32, advice: impl ToString) -> Remediation {
+        Remediation {
+            advice: advice.to_string(),
+            references: vec![format!(
+                "https://cwe.mitre.org/data/definitions/{}.html",
+                cwe_number
+            )],
+        }
+    }
+
+    /// Add a link to the references of the remediation advice.
+    pub fn reference(mut self, link: impl ToString) -> Remediation {
+        self.references.push(link.to_string());
+        self
+    }
+}
+
+/// A term of the intermediate representation near the location of a CWE warning.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub struct ContextTerm {
+    /// The TID of the term.
+    pub tid: String,
+    /// The address of the instruction that the term belongs to.
+    pub address: String,
+    /// A human-readable representation of the term.
+    pub term: String,
+    /// Whether the term is the location reported by the warning.
+    pub is_location: bool,
 }
 
 impl std::fmt::Display for CweWarning {
@@ -66,6 +337,157 @@ impl std::fmt::Display for CweWarning {
     }
 }
 
+/// The worst-case impact of a reported issue if it gets exploited.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub enum Impact {
+    /// The attacker may be able to execute arbitrary code or commands.
+    CodeExecution,
+    /// The attacker may be able to write to memory that should not be writeable.
+    MemoryWrite,
+    /// The attacker may be able to read memory that should not be readable.
+    MemoryRead,
+    /// The attacker may be able to crash the program.
+    DenialOfService,
+    /// The attacker may gain information that helps to exploit other issues.
+    InformationExposure,
+}
+
+/// Taint sources returning input received from the network.
+const NETWORK_TAINT_SOURCES: &[&str] = &[
+    "recv",
+    "recvfrom",
+    "recvmsg",
+    "lwip_recv",
+    "lwip_recvfrom",
+    "lwip_read",
+    "netconn_recv",
+    "netconn_recv_tcp_pbuf",
+    "netbuf_data",
+    "netbuf_copy_partial",
+    "pbuf_copy_partial",
+];
+
+/// Taint sources returning input received through an established TLS session.
+/// Since the handshake has to succeed before such input can be sent, it is treated as requiring authentication.
+const AUTHENTICATED_TAINT_SOURCES: &[&str] = &[
+    "SSL_read",
+    "SSL_read_ex",
+    "SSL_peek",
+    "gnutls_record_recv",
+    "mbedtls_ssl_read",
+    "wolfSSL_read",
+];
+
+/// The labels of a taint source, i.e. of a function returning input that may be controlled by an attacker.
+///
+/// Other taint sources (e.g. `scanf`, `fgets` or `getenv`) are labeled as local input.
+/// Note that `read` is also labeled as local input, since it cannot be decided statically whether it reads from a socket.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub struct TaintSourceLabels {
+    /// The input may originate from the network.
+    pub network_input: bool,
+    /// The input can only be sent by an authenticated peer.
+    pub authenticated: bool,
+}
+
+impl TaintSourceLabels {
+    /// Get the labels of the taint source with the given symbol name.
+    pub fn of(symbol_name: &str) -> TaintSourceLabels {
+        let authenticated = AUTHENTICATED_TAINT_SOURCES.contains(&symbol_name);
+        TaintSourceLabels {
+            network_input: authenticated || NETWORK_TAINT_SOURCES.contains(&symbol_name),
+            authenticated,
+        }
+    }
+}
+
+/// Hints on the exploitability of a reported issue.
+///
+/// The hints are used to synthesize an approximate CVSS v3.1 vector for the warning.
+/// Since the cwe_checker cannot know the deployment context of a binary,
+/// the resulting vector is only a rough estimate.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub struct ExploitabilityHint {
+    /// Whether attacker-controlled input is known to reach the reported location.
+    pub attacker_controlled_input: bool,
+    /// Whether the attacker-controlled input may originate from the network.
+    pub network_input: bool,
+    /// Whether the attacker needs to be authenticated to reach the reported location.
+    pub authentication_required: bool,
+    /// The worst-case impact of the issue.
+    pub impact: Impact,
+}
+
+impl ExploitabilityHint {
+    /// Create a new hint with the given impact
+    /// and without known attacker-controlled input reaching the reported location.
+    pub fn new(impact: Impact) -> ExploitabilityHint {
+        ExploitabilityHint {
+            attacker_controlled_input: false,
+            network_input: false,
+            authentication_required: false,
+            impact,
+        }
+    }
+
+    /// Mark that attacker-controlled input reaches the reported location.
+    /// If `network_input` is set, the input may originate from the network.
+    pub fn attacker_controlled(mut self, network_input: bool) -> ExploitabilityHint {
+        self.attacker_controlled_input = true;
+        self.network_input = network_input;
+        self
+    }
+
+    /// Mark that the attacker has to be authenticated to reach the reported location.
+    pub fn authentication_required(mut self) -> ExploitabilityHint {
+        self.authentication_required = true;
+        self
+    }
+
+    /// Mark that input returned by the taint source with the given symbol name reaches the reported location.
+    /// Whether the input may originate from the network and whether authentication is required
+    /// is derived from the [labels](TaintSourceLabels) of the taint source.
+    pub fn taint_source(self, symbol_name: &str) -> ExploitabilityHint {
+        let labels = TaintSourceLabels::of(symbol_name);
+        let hint = self.attacker_controlled(labels.network_input);
+        if labels.authenticated {
+            hint.authentication_required()
+        } else {
+            hint
+        }
+    }
+
+    /// Synthesize an approximate CVSS v3.1 vector from the hints.
+    pub fn to_cvss_vector(&self) -> String {
+        let attack_vector = if self.network_input { "N" } else { "L" };
+        let attack_complexity = if self.attacker_controlled_input {
+            "L"
+        } else {
+            "H"
+        };
+        let privileges_required = if self.authentication_required {
+            "L"
+        } else {
+            "N"
+        };
+        let (confidentiality, integrity, availability) = match self.impact {
+            Impact::CodeExecution | Impact::MemoryWrite => ("H", "H", "H"),
+            Impact::MemoryRead => ("H", "N", "L"),
+            Impact::DenialOfService => ("N", "N", "H"),
+            Impact::InformationExposure => ("L", "N", "N"),
+        };
+        format!(
+            "CVSS:3.1/AV:{}/AC:{}/PR:{}/UI:N/S:U/C:{}/I:{}/A:{}",
+            attack_vector,
+            attack_complexity,
+            privileges_required,
+            confidentiality,
+            integrity,
+            availability
+        )
+    }
+}
+
 /// A generic log message.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
 pub struct LogMessage {
@@ -134,6 +556,51 @@ pub enum LogLevel {
     Info,
 }
 
+/// The verbosity of the log output, given by the number of `-v` flags on the command line.
+///
+/// Error messages are shown at all verbosity levels.
+/// The verbosity only affects the log messages printed to the console,
+/// the JSON output always contains all log messages.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Only error messages are shown.
+    Errors,
+    /// Error and info messages are shown.
+    Info,
+    /// Additionally debug messages that are not related to a specific location in the binary are shown.
+    Debug,
+    /// All log messages are shown, including debug messages for single blocks or instructions.
+    Trace,
+}
+
+impl Verbosity {
+    /// Get the verbosity for the given number of `-v` flags.
+    pub fn from_occurrences(occurrences: u64) -> Verbosity {
+        match occurrences {
+            0 => Verbosity::Errors,
+            1 => Verbosity::Info,
+            2 => Verbosity::Debug,
+            _ => Verbosity::Trace,
+        }
+    }
+
+    /// Returns `true` if the log message is shown at this verbosity level.
+    pub fn shows(&self, log: &LogMessage) -> bool {
+        match log.level {
+            LogLevel::Error => true,
+            LogLevel::Info => *self >= Verbosity::Info,
+            LogLevel::Debug if log.location.is_some() => *self >= Verbosity::Trace,
+            LogLevel::Debug => *self >= Verbosity::Debug,
+        }
+    }
+}
+
+impl Default for Verbosity {
+    fn default() -> Verbosity {
+        Verbosity::Errors
+    }
+}
+
 impl std::fmt::Display for LogMessage {
     fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.level {
@@ -151,35 +618,510 @@ impl std::fmt::Display for LogMessage {
     }
 }
 
-/// Print all provided log- and CWE-messages.
+/// The output format for CWE warnings.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum OutputFormat {
+    /// Human-readable text with the warnings grouped by check, see [`to_text`].
+    /// If `color` is set, the output contains ANSI escape sequences highlighting the severities.
+    Text {
+        /// Whether to colorize the output.
+        color: bool,
+    },
+    /// One line of the form `[CWExxx] (version) description` per warning,
+    /// e.g. for scripts counting the warnings of a check.
+    PlainText,
+    /// A versioned JSON object containing the warnings, the log messages and the given audit log, see [`JsonOutput`].
+    Json(Option<Box<AuditLog>>),
+    /// One CSV row per warning, see [`to_csv`].
+    Csv,
+    /// A SARIF 2.1.0 log, where the locations of the warnings refer to the binary at the given path.
+    Sarif(String),
+}
+
+/// The version of the JSON output format as `MAJOR.MINOR`.
 ///
-/// Log-messages will always be printed to `stdout`.
-/// CWE-warnings will either be printed to `stdout` or to the file path provided in `out_path`.
+/// The minor version is incremented when fields are added to the output,
+/// the major version only on incompatible changes like the removal or renaming of fields.
+pub const JSON_OUTPUT_VERSION: &str = "1.2";
+
+/// The JSON output of an analysis run.
 ///
-/// If `emit_json` is set, the CWE-warnings will be converted to json for the output.
-pub fn print_all_messages(
-    logs: Vec<LogMessage>,
-    cwes: Vec<CweWarning>,
-    out_path: Option<&str>,
-    emit_json: bool,
-) {
-    for log in logs {
-        println!("{}", log);
-    }
-    let output: String = if emit_json {
-        serde_json::to_string_pretty(&cwes).unwrap()
+/// The JSON schema of the output is published in `doc/json_output_schema.json`.
+/// New fields are only added to the output (including the warnings and log messages),
+/// so parsers of the output should ignore unknown fields.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct JsonOutput {
+    /// The version of the output format, see [`JSON_OUTPUT_VERSION`].
+    pub version: String,
+    /// The CWE warnings found by the analysis.
+    pub warnings: Vec<CweWarning>,
+    /// The log messages generated during the analysis.
+    pub logs: Vec<LogMessage>,
+    /// The audit log of the analysis run, see [`crate::utils::audit`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audit: Option<AuditLog>,
+}
+
+impl JsonOutput {
+    /// Create the JSON output of the current version for the given warnings and log messages.
+    pub fn new(warnings: Vec<CweWarning>, logs: Vec<LogMessage>) -> JsonOutput {
+        JsonOutput {
+            version: JSON_OUTPUT_VERSION.to_string(),
+            warnings,
+            logs,
+            audit: None,
+        }
+    }
+
+    /// Parse the warnings from the JSON output of an analysis run.
+    ///
+    /// Besides the current output format the plain array of warnings
+    /// generated by older versions of the cwe_checker is also accepted.
+    /// Outputs with an unknown major version are rejected.
+    pub fn read_warnings(output: serde_json::Value) -> Result<Vec<CweWarning>, Error> {
+        if output.is_array() {
+            return Ok(serde_json::from_value(output)?);
+        }
+        let output: JsonOutput = serde_json::from_value(output)?;
+        let major_version = |version: &str| version.split('.').next().unwrap_or("").to_string();
+        if major_version(&output.version) != major_version(JSON_OUTPUT_VERSION) {
+            return Err(anyhow!(
+                "Unsupported version {} of the JSON output",
+                output.version
+            ));
+        }
+        Ok(output.warnings)
+    }
+}
+
+/// A single line of the streamed output of an analysis run in the newline-delimited JSON format.
+///
+/// Each line is a JSON object with either a `warning`, a `preliminary_warning` or a `log` key.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamedMessage {
+    /// A CWE warning.
+    Warning(CweWarning),
+    /// A CWE warning reported by a module that is still running.
+    /// Once the module finished, the warning is reported again as a `warning` unless it gets filtered out
+    /// (e.g. because it is suppressed or a duplicate of another warning).
+    #[serde(rename = "preliminary_warning")]
+    PreliminaryWarning(CweWarning),
+    /// A log message.
+    Log(LogMessage),
+}
+
+impl StreamedMessage {
+    /// Write the message as a single line of JSON and flush the writer,
+    /// so that the message is visible to the reader immediately.
+    pub fn write_line(&self, writer: &mut dyn std::io::Write) -> Result<(), Error> {
+        writeln!(writer, "{}", serde_json::to_string(self)?)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// A thread writing the streamed output of an analysis run.
+///
+/// Messages sent through [`StreamWriter::write`] are written in the order in which they were sent.
+/// Additionally, still running modules may send CWE warnings through the sender returned by
+/// [`StreamWriter::get_warning_sender`] (see [`AnalysisResults::report_warning`](crate::AnalysisResults::report_warning)).
+/// These are written as preliminary warnings as soon as they arrive,
+/// but always before the messages that were sent after them.
+pub struct StreamWriter {
+    message_sender: Option<crossbeam_channel::Sender<StreamedMessage>>,
+    warning_sender: crossbeam_channel::Sender<CweWarning>,
+    thread_handle: Option<JoinHandle<Result<(), Error>>>,
+}
+
+impl Drop for StreamWriter {
+    /// Wait until the writer thread has written all messages, so that no output gets lost.
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+impl StreamWriter {
+    /// Spawn a thread writing the streamed messages to the given writer.
+    pub fn spawn(writer: Box<dyn std::io::Write + Send>) -> StreamWriter {
+        let (message_sender, message_receiver) = crossbeam_channel::unbounded();
+        let (warning_sender, warning_receiver) = crossbeam_channel::unbounded();
+        let thread_handle = std::thread::spawn(move || {
+            write_streamed_messages(writer, message_receiver, warning_receiver)
+        });
+        StreamWriter {
+            message_sender: Some(message_sender),
+            warning_sender,
+            thread_handle: Some(thread_handle),
+        }
+    }
+
+    /// Get a sender for the warnings of still running modules.
+    pub fn get_warning_sender(&self) -> crossbeam_channel::Sender<CweWarning> {
+        self.warning_sender.clone()
+    }
+
+    /// Write the given message.
+    pub fn write(&self, message: StreamedMessage) {
+        if let Some(ref message_sender) = self.message_sender {
+            // If the writer thread stopped because of an error, the error is returned by `finish()`.
+            let _ = message_sender.send(message);
+        }
+    }
+
+    /// Wait until all messages sent so far are written and stop the writer thread.
+    /// Returns an error if writing a message failed.
+    pub fn finish(mut self) -> Result<(), Error> {
+        self.stop()
+    }
+
+    /// Disconnect the message channel, which stops the writer thread after it has written all messages,
+    /// and wait for the thread to finish.
+    fn stop(&mut self) -> Result<(), Error> {
+        self.message_sender = None;
+        match self.thread_handle.take() {
+            Some(handle) => handle
+                .join()
+                .map_err(|_| anyhow!("The thread writing the output panicked"))?,
+            None => Ok(()),
+        }
+    }
+}
+
+/// Write the messages and preliminary warnings received through the channels until the message channel disconnects.
+fn write_streamed_messages(
+    mut writer: Box<dyn std::io::Write + Send>,
+    message_receiver: crossbeam_channel::Receiver<StreamedMessage>,
+    warning_receiver: crossbeam_channel::Receiver<CweWarning>,
+) -> Result<(), Error> {
+    loop {
+        let (message, is_preliminary) = crossbeam_channel::select! {
+            recv(warning_receiver) -> warning => (warning.map(StreamedMessage::PreliminaryWarning), true),
+            recv(message_receiver) -> message => (message, false),
+        };
+        match message {
+            Ok(message) => {
+                if !is_preliminary {
+                    // Warnings sent before the message have to be written first to keep the order of the messages.
+                    for warning in warning_receiver.try_iter() {
+                        StreamedMessage::PreliminaryWarning(warning).write_line(writer.as_mut())?;
+                    }
+                }
+                message.write_line(writer.as_mut())?;
+            }
+            Err(_) if is_preliminary => {
+                // No more preliminary warnings can arrive.
+                for message in message_receiver.iter() {
+                    message.write_line(writer.as_mut())?;
+                }
+                return Ok(());
+            }
+            Err(_) => {
+                for warning in warning_receiver.try_iter() {
+                    StreamedMessage::PreliminaryWarning(warning).write_line(writer.as_mut())?;
+                }
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Escape a field of a CSV file according to RFC 4180.
+fn escape_csv_field(field: &str) -> String {
+    if field.contains(|character| matches!(character, ',' | '"' | '\n' | '\r')) {
+        format!("\"{}\"", field.replace('"', "\"\""))
     } else {
-        cwes.iter()
-            .map(|cwe| format!("{}", cwe))
-            .collect::<Vec<String>>()
-            .join("\n")
-            + "\n"
+        field.to_string()
+    }
+}
+
+/// Convert the CWE warnings to CSV with one row per warning.
+///
+/// The columns are the name of the check, the severity, the verdict,
+/// the addresses and the symbols (e.g. the affected functions) of the warning,
+/// each separated by spaces, and the description of the warning.
+pub fn to_csv(cwes: &[CweWarning]) -> String {
+    let mut csv = String::from("cwe_id,severity,verdict,addresses,function,description\n");
+    for cwe in cwes {
+        let fields = [
+            cwe.name.clone(),
+            cwe.severity.to_string(),
+            cwe.verdict.to_string(),
+            cwe.addresses.join(" "),
+            cwe.symbols.join(" "),
+            cwe.description.clone(),
+        ];
+        let fields: Vec<String> = fields.iter().map(|field| escape_csv_field(field)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// The ANSI escape sequence resetting all text attributes.
+const ANSI_RESET: &str = "\x1b[0m";
+/// The ANSI escape sequence for bold text.
+const ANSI_BOLD: &str = "\x1b[1m";
+
+/// Get the ANSI escape sequence for the color of the given severity.
+fn severity_color(severity: Severity) -> &'static str {
+    match severity {
+        Severity::High => "\x1b[1;31m",
+        Severity::Medium => "\x1b[33m",
+        Severity::Low => "\x1b[36m",
+        Severity::Info => "\x1b[2m",
+    }
+}
+
+/// Convert the CWE warnings to human-readable text for the terminal.
+///
+/// The warnings are grouped by the check that generated them, with the groups sorted by the name of the check.
+/// Inside a group the warnings are sorted by their severity in descending order.
+/// Each warning is printed on one line containing its severity, its addresses,
+/// its symbols (e.g. the affected functions) and its description.
+/// The descriptions of proven warnings are prefixed with `(proven)`.
+/// If the warning contains IR context, the context terms are printed on the following lines,
+/// with the reported location marked by `>`.
+/// If `color` is set, the check names and severities are highlighted with ANSI escape sequences.
+pub fn to_text(cwes: &[CweWarning], color: bool) -> String {
+    let paint = |text: &str, style: &str| {
+        if color {
+            format!("{}{}{}", style, text, ANSI_RESET)
+        } else {
+            text.to_string()
+        }
     };
-    if let Some(file_path) = out_path {
-        std::fs::write(file_path, output).unwrap();
-    } else {
-        print!("{}", output);
+    let mut groups: BTreeMap<&str, Vec<&CweWarning>> = BTreeMap::new();
+    for cwe in cwes {
+        groups.entry(cwe.name.as_str()).or_default().push(cwe);
+    }
+    let mut text = String::new();
+    for (name, mut warnings) in groups {
+        warnings.sort_by(|first, second| second.severity.cmp(&first.severity));
+        let plural = if warnings.len() == 1 { "" } else { "s" };
+        text.push_str(&format!(
+            "{} ({} warning{})\n",
+            paint(name, ANSI_BOLD),
+            warnings.len(),
+            plural
+        ));
+        for cwe in warnings {
+            let severity = format!("[{}]", cwe.severity);
+            let severity = format!("{:<8}", severity);
+            let mut location = cwe.addresses.join(", ");
+            if !cwe.symbols.is_empty() {
+                if !location.is_empty() {
+                    location.push(' ');
+                }
+                location.push_str(&format!("({})", cwe.symbols.join(", ")));
+            }
+            if !location.is_empty() {
+                location.push_str(": ");
+            }
+            if cwe.verdict == Verdict::Proven {
+                location.push_str("(proven) ");
+            }
+            text.push_str(&format!(
+                "  {} {}{}\n",
+                paint(&severity, severity_color(cwe.severity)),
+                location,
+                cwe.description
+            ));
+            for context_term in cwe.ir_context.iter().flatten() {
+                let marker = if context_term.is_location { '>' } else { ' ' };
+                text.push_str(&format!(
+                    "           {} {}: {}\n",
+                    marker, context_term.address, context_term.term
+                ));
+            }
+        }
+    }
+    text
+}
+
+/// Convert the CWE warnings to a [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html) log.
+///
+/// Each CWE module is a rule with the module name as stable rule ID.
+/// Each address of a warning is converted to a location referencing the given binary,
+/// the symbols of the warning are added as logical locations.
+/// The severity of a warning determines the level of the result.
+/// The fingerprint of a warning (see [`crate::utils::fingerprint`]) is added as partial fingerprint,
+/// so that SARIF viewers can track the result across different builds of the binary.
+/// The remaining fields of the warnings are added to the properties of the results.
+pub fn to_sarif(cwes: &[CweWarning], binary_path: &str) -> serde_json::Value {
+    let mut rules: BTreeMap<&str, &str> = BTreeMap::new();
+    for cwe in cwes {
+        rules
+            .entry(cwe.name.as_str())
+            .or_insert(cwe.version.as_str());
     }
+    let rule_indices: BTreeMap<&str, usize> = rules
+        .keys()
+        .enumerate()
+        .map(|(index, name)| (*name, index))
+        .collect();
+    let rules: Vec<serde_json::Value> = rules
+        .iter()
+        .map(|(name, version)| {
+            let mut rule = serde_json::json!({
+                "id": name,
+                "name": name,
+                "properties": { "version": version },
+            });
+            if let Ok(cwe_number) = name.trim_start_matches("CWE").parse::<u32>() {
+                rule["helpUri"] =
+                    format!("https://cwe.mitre.org/data/definitions/{}.html", cwe_number).into();
+            }
+            rule
+        })
+        .collect();
+    let results: Vec<serde_json::Value> = cwes
+        .iter()
+        .map(|cwe| {
+            let logical_locations: Vec<serde_json::Value> = cwe
+                .symbols
+                .iter()
+                .map(|symbol| serde_json::json!({ "name": symbol, "kind": "function" }))
+                .collect();
+            let mut locations: Vec<serde_json::Value> = cwe
+                .addresses
+                .iter()
+                .filter_map(|address| u64::from_str_radix(address, 16).ok())
+                .map(|address| {
+                    serde_json::json!({
+                        "physicalLocation": {
+                            "artifactLocation": { "uri": binary_path },
+                            "address": { "absoluteAddress": address },
+                        },
+                        "logicalLocations": logical_locations,
+                    })
+                })
+                .collect();
+            if locations.is_empty() && !logical_locations.is_empty() {
+                locations.push(serde_json::json!({ "logicalLocations": logical_locations }));
+            }
+            let mut result = serde_json::json!({
+                "ruleId": cwe.name,
+                "ruleIndex": rule_indices[cwe.name.as_str()],
+                "level": match cwe.severity {
+                    Severity::High => "error",
+                    Severity::Medium | Severity::Low => "warning",
+                    Severity::Info => "note",
+                },
+                "message": { "text": cwe.description },
+                "locations": locations,
+                "partialFingerprints": { "cweCheckerFingerprint/v1": fingerprint(cwe) },
+                "properties": {
+                    "tids": cwe.tids,
+                    "other": cwe.other,
+                    "remediation": cwe.remediation,
+                    "exploitability": cwe.exploitability,
+                    "cvssVector": cwe.cvss_vector,
+                    "component": cwe.component,
+                    "severity": cwe.severity,
+                    "verdict": cwe.verdict,
+                    "irContext": cwe.ir_context,
+                    "pathWitness": cwe.path_witness,
+                },
+            });
+            if let Some(witness) = &cwe.path_witness {
+                let thread_flow_locations: Vec<serde_json::Value> = witness
+                    .blocks
+                    .iter()
+                    .filter_map(|block| {
+                        let address = u64::from_str_radix(&block.address, 16).ok()?;
+                        Some(serde_json::json!({
+                            "location": {
+                                "physicalLocation": {
+                                    "artifactLocation": { "uri": binary_path },
+                                    "address": { "absoluteAddress": address },
+                                },
+                                "logicalLocations": [{ "name": block.function, "kind": "function" }],
+                            },
+                        }))
+                    })
+                    .collect();
+                result["codeFlows"] =
+                    serde_json::json!([{ "threadFlows": [{ "locations": thread_flow_locations }] }]);
+            }
+            result
+        })
+        .collect();
+    serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "cwe_checker",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/fkie-cad/cwe_checker",
+                    "rules": rules,
+                }
+            },
+            "artifacts": [{ "location": { "uri": binary_path } }],
+            "results": results,
+        }]
+    })
+}
+
+impl OutputFormat {
+    /// The names of the output formats as accepted on the command line.
+    pub const NAMES: [&'static str; 5] = ["text", "text-plain", "json", "csv", "sarif"];
+
+    /// Render the CWE warnings (and for the JSON format also the log messages) in the output format.
+    pub fn render(&self, logs: &[LogMessage], cwes: &[CweWarning]) -> String {
+        match self {
+            OutputFormat::Json(audit) => {
+                let mut output = JsonOutput::new(cwes.to_vec(), logs.to_vec());
+                output.audit = audit.as_deref().cloned();
+                serde_json::to_string_pretty(&output).unwrap()
+            }
+            OutputFormat::Csv => to_csv(cwes),
+            OutputFormat::Sarif(binary_path) => {
+                serde_json::to_string_pretty(&to_sarif(cwes, binary_path)).unwrap()
+            }
+            OutputFormat::Text { color } => to_text(cwes, *color),
+            OutputFormat::PlainText => cwes.iter().map(|cwe| format!("{}\n", cwe)).collect(),
+        }
+    }
+
+    /// Returns `true` if the output format contains the log messages.
+    pub fn contains_logs(&self) -> bool {
+        matches!(self, OutputFormat::Json(_))
+    }
+}
+
+/// Write all provided log- and CWE-messages to the given output sinks.
+///
+/// The log messages are contained in the output of sinks with the JSON output format.
+/// If no sink contains them, the log messages shown at the given verbosity level are printed to `stdout`,
+/// or to `stderr` if a sink prints a format other than text to `stdout`.
+///
+/// A failing sink does not prevent writing to the remaining sinks.
+/// The errors of all failing sinks are returned.
+pub fn print_all_messages(
+    logs: &[LogMessage],
+    cwes: &[CweWarning],
+    sinks: &[Box<dyn OutputSink>],
+    verbosity: Verbosity,
+) -> Vec<Error> {
+    if !sinks.iter().any(|sink| sink.format().contains_logs()) {
+        let stdout_is_machine_readable = sinks
+            .iter()
+            .any(|sink| sink.is_stdout() && !matches!(sink.format(), OutputFormat::Text { .. }));
+        for log in logs.iter().filter(|log| verbosity.shows(log)) {
+            if stdout_is_machine_readable {
+                eprintln!("{}", log);
+            } else {
+                println!("{}", log);
+            }
+        }
+    }
+    sinks
+        .iter()
+        .filter_map(|sink| sink.write(logs, cwes).err())
+        .collect()
 }
 
 /// The message types a logging thread can receive.
@@ -272,3 +1214,311 @@ impl LogThread {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verbosity_levels() {
+        let logs = vec![
+            LogMessage::new_error("error"),
+            LogMessage::new_info("info"),
+            LogMessage::new_debug("debug"),
+            LogMessage::new_debug("block debug").location(Tid::new("block")),
+        ];
+        let number_of_shown_logs =
+            |verbosity: Verbosity| logs.iter().filter(|log| verbosity.shows(log)).count();
+        assert_eq!(number_of_shown_logs(Verbosity::from_occurrences(2)), 3);
+        assert_eq!(number_of_shown_logs(Verbosity::default()), 1);
+        assert!(Verbosity::from_occurrences(5)
+            .shows(&LogMessage::new_debug("debug").location(Tid::new("block"))));
+    }
+
+    #[test]
+    fn fail_on_policy() {
+        let policy: FailOnPolicy = "medium".parse().unwrap();
+        assert!(policy.matches(&CweWarning::new("CWE476", "0.1", "").severity(Severity::High)));
+        assert!(!policy.matches(&CweWarning::new("CWE476", "0.1", "").severity(Severity::Low)));
+
+        let policy: FailOnPolicy = "CWE416, CWE78".parse().unwrap();
+        assert!(policy.matches(&CweWarning::new("CWE78", "0.1", "").severity(Severity::Info)));
+        assert!(
+            !policy.matches_any(&[CweWarning::new("CWE476", "0.1", "").severity(Severity::High)])
+        );
+
+        let policy: FailOnPolicy = "high,CWE78".parse().unwrap();
+        assert_eq!(policy.min_severity, Some(Severity::High));
+        assert_eq!(policy.cwes.len(), 1);
+
+        assert!("".parse::<FailOnPolicy>().is_err());
+        assert!("low,high".parse::<FailOnPolicy>().is_err());
+        assert!("critical".parse::<FailOnPolicy>().is_err());
+    }
+
+    #[test]
+    fn cvss_vector_synthesis() {
+        let warning = CweWarning::new("CWE78", "0.1", "description").exploitability(
+            ExploitabilityHint::new(Impact::CodeExecution)
+                .attacker_controlled(true)
+                .authentication_required(),
+        );
+        assert_eq!(
+            warning.cvss_vector.as_deref(),
+            Some("CVSS:3.1/AV:N/AC:L/PR:L/UI:N/S:U/C:H/I:H/A:H")
+        );
+        assert_eq!(
+            ExploitabilityHint::new(Impact::DenialOfService).to_cvss_vector(),
+            "CVSS:3.1/AV:L/AC:H/PR:N/UI:N/S:U/C:N/I:N/A:H"
+        );
+        assert_eq!(
+            ExploitabilityHint::new(Impact::CodeExecution)
+                .taint_source("scanf")
+                .to_cvss_vector(),
+            "CVSS:3.1/AV:L/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"
+        );
+        assert_eq!(
+            ExploitabilityHint::new(Impact::CodeExecution)
+                .taint_source("recv")
+                .to_cvss_vector(),
+            "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"
+        );
+        assert_eq!(
+            ExploitabilityHint::new(Impact::CodeExecution)
+                .taint_source("SSL_read")
+                .to_cvss_vector(),
+            "CVSS:3.1/AV:N/AC:L/PR:L/UI:N/S:U/C:H/I:H/A:H"
+        );
+    }
+
+    #[test]
+    fn severity_parsing() {
+        assert_eq!("high".parse::<Severity>().unwrap(), Severity::High);
+        assert!("critical".parse::<Severity>().is_err());
+        assert!(Severity::Info < Severity::Low && Severity::Medium < Severity::High);
+        assert_eq!(
+            serde_json::to_value(Severity::Medium).unwrap(),
+            serde_json::json!("medium")
+        );
+    }
+
+    #[test]
+    fn json_output() {
+        let mut warning = CweWarning::new("CWE476", "0.3", "description")
+            .addresses(vec!["00401000".to_string()])
+            .remediation(Remediation::new(476, "advice"))
+            .exploitability(ExploitabilityHint::new(Impact::DenialOfService));
+        warning.component = Some("component".to_string());
+        let output = JsonOutput::new(
+            vec![warning.clone()],
+            vec![LogMessage::new_info("log").location(Tid::new("tid"))],
+        );
+        let value = serde_json::to_value(&output).unwrap();
+        assert_eq!(
+            JsonOutput::read_warnings(value.clone()).unwrap(),
+            vec![warning.clone()]
+        );
+        // The plain array of warnings of older versions is accepted, too.
+        let legacy = serde_json::to_value(&vec![warning.clone()]).unwrap();
+        assert_eq!(JsonOutput::read_warnings(legacy).unwrap(), vec![warning]);
+        let mut future = value.clone();
+        future["version"] = serde_json::Value::String("2.0".to_string());
+        assert!(JsonOutput::read_warnings(future).is_err());
+
+        // All fields of the output have to be declared in the published schema.
+        let schema: serde_json::Value =
+            serde_json::from_str(include_str!("../../../../doc/json_output_schema.json")).unwrap();
+        let assert_declared = |object: &serde_json::Value, properties: &serde_json::Value| {
+            for key in object.as_object().unwrap().keys() {
+                assert!(properties.get(key).is_some(), "{} is not declared", key);
+            }
+        };
+        assert_declared(&value, &schema["properties"]);
+        assert_declared(
+            &value["warnings"][0],
+            &schema["definitions"]["warning"]["properties"],
+        );
+        assert_declared(
+            &value["logs"][0],
+            &schema["definitions"]["log_message"]["properties"],
+        );
+    }
+
+    #[test]
+    fn streamed_messages() {
+        let mut output = Vec::new();
+        StreamedMessage::Warning(CweWarning::new("CWE476", "0.3", "description"))
+            .write_line(&mut output)
+            .unwrap();
+        StreamedMessage::Log(LogMessage::new_info("log"))
+            .write_line(&mut output)
+            .unwrap();
+        let lines: Vec<serde_json::Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["warning"]["name"], "CWE476");
+        assert_eq!(lines[1]["log"]["text"], "log");
+    }
+
+    /// A writer into a shared buffer, so that the output of the stream writer thread can be inspected.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            std::io::Write::write(&mut *self.0.lock().unwrap(), buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn stream_writer() {
+        let buffer = SharedBuffer::default();
+        let stream_writer = StreamWriter::spawn(Box::new(buffer.clone()));
+        let warning_sender = stream_writer.get_warning_sender();
+        stream_writer.write(StreamedMessage::Log(LogMessage::new_info("log")));
+        warning_sender
+            .send(CweWarning::new("CWE78", "0.1", "preliminary"))
+            .unwrap();
+        stream_writer.write(StreamedMessage::Warning(CweWarning::new(
+            "CWE78", "0.1", "final",
+        )));
+        stream_writer.finish().unwrap();
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        // The preliminary warning may be written before or after the log message sent before it,
+        // but always before the final warning sent after it.
+        assert!(lines[..2]
+            .iter()
+            .any(|line| line["preliminary_warning"]["description"] == "preliminary"));
+        assert!(lines[..2].iter().any(|line| line["log"]["text"] == "log"));
+        assert_eq!(lines[2]["warning"]["description"], "final");
+    }
+
+    #[test]
+    fn output_format_rendering() {
+        assert!(OutputFormat::Json(None).contains_logs());
+        assert!(!OutputFormat::Csv.contains_logs());
+        assert_eq!(
+            OutputFormat::PlainText.render(&[], &[CweWarning::new("CWE78", "0.1", "description")]),
+            "[CWE78] (0.1) description\n"
+        );
+        let logs = vec![LogMessage::new_info("log")];
+        let cwes = vec![CweWarning::new("CWE476", "0.3", "description")];
+        let json = OutputFormat::Json(None).render(&logs, &cwes);
+        assert_eq!(
+            JsonOutput::read_warnings(serde_json::from_str(&json).unwrap()).unwrap(),
+            cwes
+        );
+    }
+
+    #[test]
+    fn csv_conversion() {
+        let cwes = vec![
+            CweWarning::new("CWE676", "0.1", "call to strcpy")
+                .severity(Severity::Low)
+                .verdict(Verdict::Proven)
+                .addresses(vec!["00401000".to_string(), "00401010".to_string()])
+                .symbols(vec!["main".to_string()]),
+            CweWarning::new("CWE476", "0.3", "NULL dereference, \"ptr\" unchecked"),
+        ];
+        assert_eq!(
+            to_csv(&cwes),
+            "cwe_id,severity,verdict,addresses,function,description\n\
+             CWE676,low,proven,00401000 00401010,main,call to strcpy\n\
+             CWE476,medium,possible,,,\"NULL dereference, \"\"ptr\"\" unchecked\"\n"
+        );
+    }
+
+    #[test]
+    fn text_conversion() {
+        let cwes = vec![
+            CweWarning::new("CWE476", "0.3", "Possible NULL dereference")
+                .addresses(vec!["00401000".to_string()])
+                .symbols(vec!["main".to_string()])
+                .severity(Severity::Low),
+            CweWarning::new("CWE134", "0.1", "Non-constant format string"),
+            CweWarning::new("CWE476", "0.3", "Unchecked return value")
+                .addresses(vec!["00402000".to_string()])
+                .severity(Severity::High)
+                .verdict(Verdict::Proven),
+        ];
+        assert_eq!(
+            to_text(&cwes, false),
+            "CWE134 (1 warning)\n\
+             \x20 [medium] Non-constant format string\n\
+             CWE476 (2 warnings)\n\
+             \x20 [high]   00402000: (proven) Unchecked return value\n\
+             \x20 [low]    00401000 (main): Possible NULL dereference\n"
+        );
+        let colored = to_text(&cwes, true);
+        assert!(colored.contains("\x1b[1;31m[high]  \x1b[0m"));
+        assert_eq!(to_text(&[], true), "");
+
+        let context_term = |address: &str, term: &str, is_location: bool| ContextTerm {
+            tid: format!("instr_{}", address),
+            address: address.to_string(),
+            term: term.to_string(),
+            is_location,
+        };
+        let cwes = vec![
+            CweWarning::new("CWE476", "0.3", "Possible NULL dereference")
+                .addresses(vec!["00401004".to_string()])
+                .ir_context(vec![
+                    context_term("00401000", "RDI := RAX", false),
+                    context_term("00401004", "Call strcpy", true),
+                ]),
+        ];
+        assert_eq!(
+            to_text(&cwes, false),
+            "CWE476 (1 warning)\n\
+             \x20 [medium] 00401004: Possible NULL dereference\n\
+             \x20            00401000: RDI := RAX\n\
+             \x20          > 00401004: Call strcpy\n"
+        );
+    }
+
+    #[test]
+    fn sarif_conversion() {
+        let cwes = vec![
+            CweWarning::new("CWE676", "0.1", "call to strcpy")
+                .addresses(vec!["00401000".to_string()])
+                .symbols(vec!["main".to_string()]),
+            CweWarning::new("CWE476", "0.3", "NULL dereference")
+                .addresses(vec!["00401010".to_string(), "00401020".to_string()]),
+        ];
+        let sarif = to_sarif(&cwes, "/tmp/binary");
+        let run = &sarif["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "CWE476");
+        assert_eq!(
+            run["tool"]["driver"]["rules"][1]["helpUri"],
+            "https://cwe.mitre.org/data/definitions/676.html"
+        );
+        let result = &run["results"][0];
+        assert_eq!(result["ruleId"], "CWE676");
+        assert_eq!(result["ruleIndex"], 1);
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["address"]["absoluteAddress"],
+            0x401000
+        );
+        assert_eq!(
+            result["locations"][0]["logicalLocations"][0]["name"],
+            "main"
+        );
+        assert_eq!(run["results"][1]["locations"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            result["partialFingerprints"]["cweCheckerFingerprint/v1"],
+            fingerprint(&cwes[0])
+        );
+    }
+}
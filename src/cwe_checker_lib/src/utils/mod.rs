@@ -1,37 +1,269 @@
+pub mod address_ranges;
+pub mod artifact;
+pub mod audit;
+pub mod bare_metal;
+pub mod batch;
 pub mod binary;
+pub mod cancellation;
+pub mod chunking;
+pub mod components;
+pub mod config_validation;
+pub mod core_dump;
+pub mod deadline;
+pub mod deduplication;
+pub mod default_config;
+pub mod demangle;
+pub mod diff;
+pub mod entry_points;
+pub mod extern_stubs;
+pub mod fingerprint;
+pub mod function_selection;
+pub mod generated_code;
 pub mod graph_utils;
+pub mod ir_context;
+pub mod localization;
 pub mod log;
+pub mod output_sinks;
+pub mod path_witness;
+pub mod perf_stats;
+pub mod report;
+pub mod resource_budget;
+pub mod run_statistics;
+pub mod sdk_models;
+pub mod shared_map;
+pub mod suppression;
 pub mod symbol_utils;
+pub mod syscalls;
+pub mod taint_coverage;
+pub mod toolchain;
 
 use crate::prelude::*;
 
-/// Get the contents of a configuration file.
-pub fn read_config_file(filename: &str) -> serde_json::Value {
+/// Get the path to the directory containing the configuration files.
+pub fn get_config_dir() -> std::path::PathBuf {
     let project_dirs = directories::ProjectDirs::from("", "", "cwe_checker")
         .expect("Could not discern location of configuration files.");
-    let config_dir = project_dirs.config_dir();
-    let config_path = config_dir.join(filename);
+    project_dirs.config_dir().to_path_buf()
+}
+
+/// Get the path to a configuration file in the configuration directory.
+/// If a file with the same name but with the extension `.toml` exists, its path is returned instead,
+/// so that an installed `config.toml` takes precedence over the `config.json`.
+pub fn get_config_file_path(filename: &str) -> std::path::PathBuf {
+    let config_path = get_config_dir().join(filename);
+    let toml_path = config_path.with_extension("toml");
+    if toml_path.exists() {
+        toml_path
+    } else {
+        config_path
+    }
+}
+
+/// Get the contents of a configuration file in the configuration directory,
+/// see [`get_config_file_path`] and [`parse_config_file`].
+pub fn read_config_file(filename: &str) -> serde_json::Value {
+    let config_path = get_config_file_path(filename);
     println!("{}", config_path.to_str().unwrap());
-    let config_file =
-        std::fs::read_to_string(config_path).expect("Could not read configuration file");
-    serde_json::from_str(&config_file).unwrap()
+    parse_config_file(&config_path).expect("Could not read configuration file")
+}
+
+/// Read and parse a configuration file in the JSON or in the TOML format.
+///
+/// The format is detected by the file extension: Files ending with `.toml` are parsed as TOML, all other files as JSON.
+/// Both formats are converted to the same JSON representation,
+/// so that the configuration sections can be deserialized regardless of the format of the file.
+/// Since TOML has no null values, optional keys are omitted in TOML files instead of being set to null.
+pub fn parse_config_file(path: &std::path::Path) -> Result<serde_json::Value, Error> {
+    let contents = std::fs::read_to_string(path)?;
+    let is_toml = path
+        .extension()
+        .map_or(false, |extension| extension == "toml");
+    parse_config(&contents, is_toml)
 }
 
-/// Get the base address for the image of a binary when loaded into memory.
+/// Parse the contents of a configuration file in the TOML or in the JSON format.
+fn parse_config(contents: &str, is_toml: bool) -> Result<serde_json::Value, Error> {
+    if is_toml {
+        Ok(toml::from_str(contents)?)
+    } else {
+        Ok(serde_json::from_str(contents)?)
+    }
+}
+
+/// Override a single value of the configuration, given as `KEY_PATH=VALUE`, e.g. `Packer.entropy_threshold=7.5`.
+///
+/// The key path consists of the section name followed by dot-separated keys of (nested) objects or indices of arrays,
+/// e.g. `Packer.signatures.0.pattern`. Missing keys of objects are added.
+/// The value is parsed as JSON if possible, e.g. `false`, `42` or `["malloc","calloc"]`,
+/// and used as a string otherwise, so that strings need not be quoted on the command line.
+///
+/// Parameters contained in the `parameters` list of a section as `NAME=VALUE` strings (e.g. of the CWE476 check)
+/// can be overridden like keys of the section, e.g. `CWE476.strict_call_policy=false`.
+pub fn apply_config_override(
+    config: &mut serde_json::Value,
+    assignment: &str,
+) -> Result<(), Error> {
+    let mut parts = assignment.splitn(2, '=');
+    let key_path = parts.next().unwrap_or_default();
+    let raw_value = parts.next().ok_or_else(|| {
+        anyhow!(
+            "Invalid configuration override {}: Expected KEY=VALUE",
+            assignment
+        )
+    })?;
+    let keys: Vec<&str> = key_path.split('.').collect();
+    if keys.iter().any(|key| key.is_empty()) {
+        return Err(anyhow!(
+            "Invalid key path {} in configuration override",
+            key_path
+        ));
+    }
+    let value = serde_json::from_str(raw_value)
+        .unwrap_or_else(|_| serde_json::Value::String(raw_value.to_string()));
+    let cannot_set = |index: usize| {
+        anyhow!(
+            "Cannot set {}: {} is neither an object nor an existing array element",
+            key_path,
+            keys[..=index].join(".")
+        )
+    };
+    let mut target = config;
+    for (index, key) in keys.iter().enumerate() {
+        if target.is_null() {
+            *target = serde_json::Value::Object(serde_json::Map::new());
+        }
+        if index == keys.len() - 1 {
+            if let Some(parameter) = get_parameter_mut(target, key) {
+                *parameter = serde_json::Value::String(format!("{}={}", key, raw_value));
+                return Ok(());
+            }
+        }
+        target = match target {
+            serde_json::Value::Object(map) => map.entry(*key).or_insert(serde_json::Value::Null),
+            serde_json::Value::Array(array) => key
+                .parse::<usize>()
+                .ok()
+                .and_then(move |array_index| array.get_mut(array_index))
+                .ok_or_else(|| cannot_set(index))?,
+            _ => return Err(cannot_set(index)),
+        };
+    }
+    *target = value;
+    Ok(())
+}
+
+/// Get the entry of the `parameters` list of a configuration section that sets the parameter with the given name,
+/// provided that the section has no key of the same name.
+fn get_parameter_mut<'a>(
+    section: &'a mut serde_json::Value,
+    name: &str,
+) -> Option<&'a mut serde_json::Value> {
+    let section = section.as_object_mut()?;
+    if section.contains_key(name) {
+        return None;
+    }
+    let prefix = format!("{}=", name);
+    section
+        .get_mut("parameters")?
+        .as_array_mut()?
+        .iter_mut()
+        .find(|parameter| {
+            parameter
+                .as_str()
+                .map_or(false, |parameter| parameter.starts_with(&prefix))
+        })
+}
+
+/// Get the base address for the image of a binary when loaded into memory,
+/// as specified by the binary file.
+///
+/// For ELF files this is the smallest virtual address of all loadable segments,
+/// which is zero for position-independent executables and shared objects.
+/// For PE files this is the preferred image base of the optional header.
+/// Ghidra may load the binary at another image base (e.g. for position-independent executables),
+/// so the addresses reported by Ghidra differ from the addresses in the file by the difference of both image bases.
 pub fn get_binary_base_address(binary: &[u8]) -> Result<u64, Error> {
     use goblin::Object;
     match Object::parse(binary)? {
-        Object::Elf(elf_file) => {
-            for header in elf_file.program_headers.iter() {
-                let vm_range = header.vm_range();
-                if !vm_range.is_empty() && header.p_type == goblin::elf::program_header::PT_LOAD {
-                    // The loadable segments have to occur in order in the program header table.
-                    // So the start address of the first loadable segment is the base offset of the binary.
-                    return Ok(vm_range.start as u64);
-                }
-            }
-            Err(anyhow!("No loadable segment bounds found."))
-        }
+        Object::Elf(elf_file) => elf_file
+            .program_headers
+            .iter()
+            .filter(|header| {
+                header.p_type == goblin::elf::program_header::PT_LOAD
+                    && !header.vm_range().is_empty()
+            })
+            .map(|header| header.p_vaddr)
+            .min()
+            .ok_or_else(|| anyhow!("No loadable segment bounds found.")),
+        Object::PE(pe_file) => Ok(pe_file.image_base as u64),
         _ => Err(anyhow!("Binary type not yet supported")),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toml_config() {
+        let json_config = r#"{
+            "CWE676": { "symbols": ["strcpy", "gets"] },
+            "CWE243": { "pairs": [["chroot", "chdir"]] },
+            "Packer": { "entropy_threshold": 7.2, "min_segment_size": 512 }
+        }"#;
+        let toml_config = r#"
+            [CWE676]
+            symbols = ["strcpy", "gets"]
+
+            [CWE243]
+            pairs = [["chroot", "chdir"]]
+
+            [Packer]
+            entropy_threshold = 7.2
+            min_segment_size = 512
+        "#;
+        assert_eq!(
+            parse_config(toml_config, true).unwrap(),
+            parse_config(json_config, false).unwrap()
+        );
+        assert!(parse_config(toml_config, false).is_err());
+    }
+
+    #[test]
+    fn config_overrides() {
+        let mut config = serde_json::json!({
+            "CWE476": { "parameters": ["strict_call_policy=true", "max_steps=100"] },
+            "Packer": { "entropy_threshold": 7.2, "signatures": [{ "name": "UPX" }] },
+            "ResourceBudget": { "max_states": null }
+        });
+        for assignment in [
+            "CWE476.strict_call_policy=false",
+            "Packer.entropy_threshold=7.5",
+            "Packer.signatures.0.name=MPRESS",
+            "Packer.min_segment_size=512",
+            "ResourceBudget.max_states=1000",
+            "Memory.main_functions=[\"main\"]",
+        ]
+        .iter()
+        {
+            apply_config_override(&mut config, assignment).unwrap();
+        }
+        assert_eq!(
+            config,
+            serde_json::json!({
+                "CWE476": { "parameters": ["strict_call_policy=false", "max_steps=100"] },
+                "Packer": {
+                    "entropy_threshold": 7.5,
+                    "signatures": [{ "name": "MPRESS" }],
+                    "min_segment_size": 512
+                },
+                "ResourceBudget": { "max_states": 1000 },
+                "Memory": { "main_functions": ["main"] }
+            })
+        );
+        assert!(apply_config_override(&mut config, "Packer.entropy_threshold").is_err());
+        assert!(apply_config_override(&mut config, "Packer..name=UPX").is_err());
+        assert!(apply_config_override(&mut config, "Packer.signatures.1.name=UPX").is_err());
+        assert!(apply_config_override(&mut config, "Packer.entropy_threshold.max=8").is_err());
+    }
+}
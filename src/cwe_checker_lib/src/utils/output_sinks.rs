@@ -0,0 +1,441 @@
+//! Destinations for the results of an analysis run.
+//!
+//! The results are emitted through the [`OutputSink`] trait, so that they can be written to several destinations at once.
+//! Besides files and `stdout` the results can be pushed to a collector,
+//! which is useful when the cwe_checker scans a fleet of hosts:
+//! - [`FileSink`] writes the results to a file and optionally keeps the results of previous runs as rotated files.
+//! - [`StdoutSink`] prints the results to `stdout`.
+//! - [`SyslogSink`] sends each warning (and for the JSON format each log message) as a message to a syslog daemon.
+//! - [`WebhookSink`] sends the results as the body of an HTTP POST request to an URL.
+//!
+//! Sinks are given by specifications of the form `DESTINATION[:FORMAT]`, see [`new_output_sink`].
+//! The streamed output only supports `stdout` and files as destinations, see [`open_stream_destination`].
+
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage, OutputFormat, Severity, StreamedMessage};
+
+/// The syslog facility of the messages, i.e. `user`.
+const SYSLOG_FACILITY: u8 = 1;
+/// The path of the socket of the local syslog daemon.
+#[cfg(unix)]
+const LOCAL_SYSLOG_SOCKET: &str = "/dev/log";
+/// The default timeout in seconds for requests to webhooks.
+const DEFAULT_WEBHOOK_TIMEOUT: u64 = 30;
+
+/// The configuration of the output sinks.
+///
+/// The configuration is read from the `OutputSinks` section of the configuration file.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+#[serde(default)]
+pub struct Config {
+    /// Specifications of sinks that the results are written to in addition to the sinks given on the command line,
+    /// e.g. `https://collector.example.com/reports` or `syslog`.
+    pub sinks: Vec<String>,
+    /// The number of previous results kept by file sinks.
+    /// If it is not zero, an existing file is renamed to `PATH.1` (and `PATH.1` to `PATH.2`, etc.) before it is overwritten.
+    pub rotate: usize,
+    /// The timeout in seconds for requests to webhooks, 30 seconds by default.
+    /// A request that does not finish in time fails, so that an unreachable collector cannot block the analysis.
+    pub webhook_timeout: u64,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            sinks: Vec::new(),
+            rotate: 0,
+            webhook_timeout: DEFAULT_WEBHOOK_TIMEOUT,
+        }
+    }
+}
+
+/// A destination for the results of an analysis run.
+pub trait OutputSink {
+    /// The format of the results written to the sink.
+    fn format(&self) -> &OutputFormat;
+
+    /// Returns `true` if the sink prints the results to `stdout`.
+    fn is_stdout(&self) -> bool {
+        false
+    }
+
+    /// Write the results to the sink.
+    fn write(&self, logs: &[LogMessage], cwes: &[CweWarning]) -> Result<(), Error>;
+}
+
+/// A sink printing the results to `stdout`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct StdoutSink {
+    /// The format of the results.
+    pub format: OutputFormat,
+}
+
+impl OutputSink for StdoutSink {
+    fn format(&self) -> &OutputFormat {
+        &self.format
+    }
+
+    fn is_stdout(&self) -> bool {
+        true
+    }
+
+    fn write(&self, logs: &[LogMessage], cwes: &[CweWarning]) -> Result<(), Error> {
+        print!("{}", self.format.render(logs, cwes));
+        Ok(())
+    }
+}
+
+/// A sink writing the results to a file.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct FileSink {
+    /// The path of the file.
+    pub path: String,
+    /// The format of the results.
+    pub format: OutputFormat,
+    /// The number of previous versions of the file that are kept, see [`Config::rotate`].
+    pub rotate: usize,
+}
+
+impl FileSink {
+    /// Rename the existing versions of the file, so that the file can be overwritten.
+    /// The oldest version is overwritten if more than `rotate` previous versions exist.
+    fn rotate_files(&self) -> Result<(), Error> {
+        if self.rotate == 0 || !std::path::Path::new(&self.path).exists() {
+            return Ok(());
+        }
+        let rotated_path = |index: usize| format!("{}.{}", self.path, index);
+        for index in (1..self.rotate).rev() {
+            if std::path::Path::new(&rotated_path(index)).exists() {
+                std::fs::rename(rotated_path(index), rotated_path(index + 1))?;
+            }
+        }
+        std::fs::rename(&self.path, rotated_path(1))?;
+        Ok(())
+    }
+}
+
+impl OutputSink for FileSink {
+    fn format(&self) -> &OutputFormat {
+        &self.format
+    }
+
+    fn write(&self, logs: &[LogMessage], cwes: &[CweWarning]) -> Result<(), Error> {
+        self.rotate_files()?;
+        std::fs::write(&self.path, self.format.render(logs, cwes))?;
+        Ok(())
+    }
+}
+
+/// A sink sending the results to a syslog daemon.
+///
+/// Each warning is sent as a separate message in the BSD syslog format (RFC 3164)
+/// with a priority corresponding to the severity of the warning.
+/// For the JSON format each warning and each log message is sent as a line of the streamed output (see [`StreamedMessage`]),
+/// for all other formats only the warnings are sent as text.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct SyslogSink {
+    /// The address `HOST:PORT` of the syslog server that the messages are sent to via UDP.
+    /// If `None`, the messages are sent to the local syslog daemon.
+    pub server: Option<String>,
+    /// The format of the results.
+    pub format: OutputFormat,
+}
+
+impl SyslogSink {
+    /// Get the syslog messages for the results.
+    fn get_messages(&self, logs: &[LogMessage], cwes: &[CweWarning]) -> Vec<String> {
+        let mut messages = Vec::new();
+        if let OutputFormat::Json(_) = self.format {
+            for cwe in cwes {
+                let line = serde_json::to_string(&StreamedMessage::Warning(cwe.clone())).unwrap();
+                messages.push(format_syslog_message(
+                    get_syslog_severity(cwe.severity),
+                    &line,
+                ));
+            }
+            for log in logs {
+                let line = serde_json::to_string(&StreamedMessage::Log(log.clone())).unwrap();
+                messages.push(format_syslog_message(6, &line));
+            }
+        } else {
+            for cwe in cwes {
+                messages.push(format_syslog_message(
+                    get_syslog_severity(cwe.severity),
+                    &cwe.to_string(),
+                ));
+            }
+        }
+        messages
+    }
+
+    /// Send the messages to the local syslog daemon.
+    #[cfg(unix)]
+    fn send_to_local_daemon(messages: &[String]) -> Result<(), Error> {
+        let socket = std::os::unix::net::UnixDatagram::unbound()?;
+        for message in messages {
+            socket.send_to(message.as_bytes(), LOCAL_SYSLOG_SOCKET)?;
+        }
+        Ok(())
+    }
+
+    /// Send the messages to the local syslog daemon.
+    #[cfg(not(unix))]
+    fn send_to_local_daemon(_messages: &[String]) -> Result<(), Error> {
+        Err(anyhow!(
+            "No local syslog daemon available. Give the syslog server as syslog://HOST:PORT instead."
+        ))
+    }
+}
+
+impl OutputSink for SyslogSink {
+    fn format(&self) -> &OutputFormat {
+        &self.format
+    }
+
+    fn write(&self, logs: &[LogMessage], cwes: &[CweWarning]) -> Result<(), Error> {
+        let messages = self.get_messages(logs, cwes);
+        match &self.server {
+            Some(server) => {
+                let socket = std::net::UdpSocket::bind("0.0.0.0:0")?;
+                for message in messages {
+                    socket.send_to(message.as_bytes(), server.as_str())?;
+                }
+                Ok(())
+            }
+            None => SyslogSink::send_to_local_daemon(&messages),
+        }
+    }
+}
+
+/// A sink sending the results as the body of an HTTP POST request to an URL.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct WebhookSink {
+    /// The URL that the results are posted to.
+    pub url: String,
+    /// The format of the results.
+    pub format: OutputFormat,
+    /// The timeout of the request in seconds.
+    pub timeout: u64,
+}
+
+impl OutputSink for WebhookSink {
+    fn format(&self) -> &OutputFormat {
+        &self.format
+    }
+
+    fn write(&self, logs: &[LogMessage], cwes: &[CweWarning]) -> Result<(), Error> {
+        let content_type = match self.format {
+            OutputFormat::Json(_) | OutputFormat::Sarif(_) => "application/json",
+            OutputFormat::Csv => "text/csv",
+            OutputFormat::Text { .. } | OutputFormat::PlainText => "text/plain",
+        };
+        ureq::post(&self.url)
+            .set("Content-Type", content_type)
+            .timeout(std::time::Duration::from_secs(self.timeout))
+            .send_string(&self.format.render(logs, cwes))
+            .map_err(|err| anyhow!("Request to the webhook {} failed: {}", self.url, err))?;
+        Ok(())
+    }
+}
+
+/// Get the syslog severity level corresponding to the severity of a warning.
+fn get_syslog_severity(severity: Severity) -> u8 {
+    match severity {
+        Severity::High => 3,   // error
+        Severity::Medium => 4, // warning
+        Severity::Low => 5,    // notice
+        Severity::Info => 6,   // informational
+    }
+}
+
+/// Format a message in the BSD syslog format (RFC 3164) without timestamp and host name,
+/// which are added by the syslog daemon.
+fn format_syslog_message(syslog_severity: u8, text: &str) -> String {
+    format!(
+        "<{}>cwe_checker[{}]: {}",
+        SYSLOG_FACILITY * 8 + syslog_severity,
+        std::process::id(),
+        text
+    )
+}
+
+/// Split a sink specification of the form `DESTINATION[:FORMAT]` into the destination and the name of the format.
+///
+/// The suffix after the last colon is only treated as the format if it is a known format name,
+/// so that paths and URLs containing colons can still be given without a format.
+pub fn split_specification(specification: &str) -> (&str, Option<&str>) {
+    if let Some(index) = specification.rfind(':') {
+        let format = &specification[index + 1..];
+        if OutputFormat::NAMES.contains(&format) {
+            return (&specification[..index], Some(format));
+        }
+    }
+    (specification, None)
+}
+
+/// Create the output sink for a specification of the form `DESTINATION[:FORMAT]`.
+///
+/// The destination is one of
+/// - `-` for `stdout`,
+/// - `syslog` for the local syslog daemon or `syslog://HOST:PORT` for a syslog server,
+/// - an URL starting with `http://` or `https://` for a webhook,
+/// - otherwise the path of a file.
+///
+/// The function `get_format` returns the output format for the given format name
+/// (`None` if the specification contains no format) and whether the sink prints to `stdout`.
+/// Webhooks receive the JSON report unless the specification contains another format.
+pub fn new_output_sink(
+    specification: &str,
+    config: &Config,
+    get_format: &dyn Fn(Option<&str>, bool) -> OutputFormat,
+) -> Result<Box<dyn OutputSink>, Error> {
+    let (destination, format_name) = split_specification(specification);
+    if destination.is_empty() {
+        return Err(anyhow!("Empty output destination in {}", specification));
+    }
+    let sink: Box<dyn OutputSink> = if destination == "-" {
+        Box::new(StdoutSink {
+            format: get_format(format_name, true),
+        })
+    } else if destination == "syslog" {
+        Box::new(SyslogSink {
+            server: None,
+            format: get_format(format_name, false),
+        })
+    } else if let Some(server) = destination.strip_prefix("syslog://") {
+        Box::new(SyslogSink {
+            server: Some(server.to_string()),
+            format: get_format(format_name, false),
+        })
+    } else if destination.starts_with("http://") || destination.starts_with("https://") {
+        Box::new(WebhookSink {
+            url: destination.to_string(),
+            format: get_format(Some(format_name.unwrap_or("json")), false),
+            timeout: config.webhook_timeout,
+        })
+    } else {
+        Box::new(FileSink {
+            path: destination.to_string(),
+            format: get_format(format_name, false),
+            rotate: config.rotate,
+        })
+    };
+    Ok(sink)
+}
+
+/// Open the destination of a sink specification for the streamed output (see [`StreamedMessage`]).
+///
+/// The streamed output is always newline-delimited JSON and is written while the analysis is still running,
+/// so only `-` for `stdout` and file paths are supported as destinations
+/// and the specification must not contain a format.
+pub fn open_stream_destination(
+    specification: &str,
+) -> Result<Box<dyn std::io::Write + Send>, Error> {
+    let (destination, format_name) = split_specification(specification);
+    if let Some(format_name) = format_name {
+        return Err(anyhow!(
+            "The streamed output is always newline-delimited JSON and cannot be written in the {} format",
+            format_name
+        ));
+    }
+    if destination.is_empty() {
+        return Err(anyhow!("Empty output destination in {}", specification));
+    }
+    if destination == "-" {
+        Ok(Box::new(std::io::stdout()))
+    } else if destination == "syslog"
+        || destination.starts_with("syslog://")
+        || destination.starts_with("http://")
+        || destination.starts_with("https://")
+    {
+        Err(anyhow!(
+            "The streamed output can only be written to stdout or a file, not to {}",
+            destination
+        ))
+    } else {
+        Ok(Box::new(std::fs::File::create(destination)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_format(format_name: Option<&str>, to_stdout: bool) -> OutputFormat {
+        match format_name {
+            Some("json") => OutputFormat::Json(None),
+            Some("csv") => OutputFormat::Csv,
+            _ => OutputFormat::Text { color: to_stdout },
+        }
+    }
+
+    #[test]
+    fn sink_specification() {
+        assert_eq!(
+            split_specification("report.sarif:sarif"),
+            ("report.sarif", Some("sarif"))
+        );
+        assert_eq!(
+            split_specification("findings.json"),
+            ("findings.json", None)
+        );
+        assert_eq!(
+            split_specification("C:\\results\\out.txt"),
+            ("C:\\results\\out.txt", None)
+        );
+        assert_eq!(
+            split_specification("https://collector:8443/reports:csv"),
+            ("https://collector:8443/reports", Some("csv"))
+        );
+
+        let config = Config::default();
+        assert_eq!(config.webhook_timeout, 30);
+        let sink = new_output_sink("-", &config, &get_format).unwrap();
+        assert!(sink.is_stdout());
+        assert_eq!(*sink.format(), OutputFormat::Text { color: true });
+        let sink = new_output_sink("https://collector:8443/reports", &config, &get_format).unwrap();
+        assert!(!sink.is_stdout());
+        assert_eq!(*sink.format(), OutputFormat::Json(None));
+        let sink = new_output_sink("syslog://loghost:514:csv", &config, &get_format).unwrap();
+        assert_eq!(*sink.format(), OutputFormat::Csv);
+        assert!(new_output_sink(":json", &config, &get_format).is_err());
+    }
+
+    #[test]
+    fn stream_destinations() {
+        assert!(open_stream_destination("-").is_ok());
+        assert!(open_stream_destination("findings.json:json").is_err());
+        assert!(open_stream_destination("syslog").is_err());
+        assert!(open_stream_destination("syslog://loghost:514").is_err());
+        assert!(open_stream_destination("https://collector:8443/reports").is_err());
+        assert!(open_stream_destination("").is_err());
+    }
+
+    #[test]
+    fn syslog_messages() {
+        let sink = SyslogSink {
+            server: None,
+            format: OutputFormat::Text { color: false },
+        };
+        let logs = vec![LogMessage::new_info("log")];
+        let cwes = vec![CweWarning::new("CWE476", "0.3", "description").severity(Severity::High)];
+        let messages = sink.get_messages(&logs, &cwes);
+        assert_eq!(
+            messages,
+            vec![format!(
+                "<11>cwe_checker[{}]: [CWE476] (0.3) description",
+                std::process::id()
+            )]
+        );
+
+        let sink = SyslogSink {
+            server: None,
+            format: OutputFormat::Json(None),
+        };
+        let messages = sink.get_messages(&logs, &cwes);
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].starts_with("<11>cwe_checker["));
+        assert!(messages[1].starts_with("<14>cwe_checker["));
+        assert!(messages[1].contains(r#""text":"log""#));
+    }
+}
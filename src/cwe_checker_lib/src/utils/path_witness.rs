@@ -0,0 +1,384 @@
+//! Witness paths of flow warnings for visualization.
+//!
+//! Checks like the checks for CWE-476 or CWE-367 report a flow from a source (e.g. a call to `malloc`)
+//! to a sink (e.g. a memory access without a preceding NULL check).
+//! The witness of such a warning is a shortest path in the interprocedural control flow graph
+//! from the basic block of the source to the basic block of the sink.
+//! It is exported as a minimal sub-graph of the control flow graph,
+//! containing only the basic blocks and edges along the path together with the branch conditions of conditional jumps.
+//! So a user interface can render exactly the path that the analysis assumes to exist,
+//! either from the JSON representation attached to the warning or from its representation in the DOT format of Graphviz.
+//!
+//! The source and the sink of a warning are given by its first and its last TID.
+//! Since some checks (e.g. the check for CWE-78) list the sink before the source,
+//! a path in the reverse direction is searched if there is no path from the first to the last TID.
+//! Calls and returns along the path are not matched,
+//! i.e. the path may return to a different caller than the one that called the function.
+
+use crate::analysis::graph::{Edge, Graph, Node};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::fingerprint::fingerprint;
+use crate::utils::ir_context::format_expression;
+use crate::utils::log::CweWarning;
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
+
+/// A basic block on a witness path.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub struct WitnessBlock {
+    /// The TID of the block.
+    pub tid: String,
+    /// The address of the block.
+    pub address: String,
+    /// The name of the function containing the block.
+    pub function: String,
+}
+
+/// The kind of a transition between two basic blocks on a witness path.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum WitnessEdgeKind {
+    /// An intraprocedural jump.
+    Jump,
+    /// A call to a function of the program.
+    Call,
+    /// A return from a function of the program.
+    Return,
+    /// A call to a function of the program that is stepped over.
+    CallReturn,
+    /// A call to an extern function.
+    ExternCall,
+}
+
+/// A transition between two basic blocks on a witness path.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub struct WitnessEdge {
+    /// The index of the start block in the blocks of the witness.
+    pub source: usize,
+    /// The index of the target block in the blocks of the witness.
+    pub target: usize,
+    /// The kind of the transition.
+    pub kind: WitnessEdgeKind,
+    /// The TID of the jump or call instruction causing the transition.
+    pub jump: String,
+    /// The condition that has to hold for the transition, if the transition is a conditional jump.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+}
+
+/// A path in the control flow graph from the source to the sink of a warning.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub struct PathWitness {
+    /// The basic blocks along the path in the order of the path,
+    /// beginning with the block of the source and ending with the block of the sink.
+    pub blocks: Vec<WitnessBlock>,
+    /// The transitions between consecutive blocks of the path.
+    pub edges: Vec<WitnessEdge>,
+}
+
+impl PathWitness {
+    /// Get the witness in the DOT format of Graphviz.
+    /// The blocks of the source and the sink are drawn with a double border.
+    pub fn to_dot(&self) -> String {
+        let escape = |text: &str| text.replace('"', "\\\"");
+        let mut dot = String::from("digraph witness {\n    node [shape=box];\n");
+        for (index, block) in self.blocks.iter().enumerate() {
+            let border = if index == 0 || index + 1 == self.blocks.len() {
+                ", peripheries=2"
+            } else {
+                ""
+            };
+            dot.push_str(&format!(
+                "    n{} [label=\"{}\\n{} @ {}\"{}];\n",
+                index,
+                escape(&block.function),
+                escape(&block.tid),
+                escape(&block.address),
+                border
+            ));
+        }
+        for edge in self.edges.iter() {
+            let mut label = format!("{:?} {}", edge.kind, edge.jump);
+            if let Some(condition) = &edge.condition {
+                label.push_str(&format!("\\nif {}", condition));
+            }
+            dot.push_str(&format!(
+                "    n{} -> n{} [label=\"{}\"];\n",
+                edge.source,
+                edge.target,
+                escape(&label)
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Add a witness path to each warning with at least two TIDs,
+/// i.e. to each warning reporting a flow from a source to a sink.
+///
+/// Warnings that already contain a witness or for which no path is found are left unchanged.
+pub fn add_path_witnesses(graph: &Graph, warnings: &mut [CweWarning]) {
+    let flow_warnings = || {
+        warnings
+            .iter()
+            .filter(|warning| warning.path_witness.is_none() && warning.tids.len() > 1)
+    };
+    if flow_warnings().next().is_none() {
+        return;
+    }
+    let endpoints: HashSet<&str> = flow_warnings()
+        .flat_map(|warning| {
+            vec![
+                warning.tids[0].as_str(),
+                warning.tids.last().unwrap().as_str(),
+            ]
+        })
+        .collect();
+    let block_nodes = get_block_start_nodes(graph, &endpoints);
+    for warning in warnings.iter_mut() {
+        if warning.path_witness.is_some() || warning.tids.len() < 2 {
+            continue;
+        }
+        let first = &warning.tids[0];
+        let last = warning.tids.last().unwrap();
+        let witness = find_path(graph, &block_nodes, first, last)
+            .or_else(|| find_path(graph, &block_nodes, last, first))
+            .map(|path| get_witness(graph, &path));
+        warning.path_witness = witness;
+    }
+}
+
+/// Write the witness of each warning to a file in the DOT format of Graphviz in the given directory.
+/// The files are named after the fingerprints of the warnings.
+/// The directory is created if it does not exist.
+pub fn write_dot_files(directory: &Path, warnings: &[CweWarning]) -> Result<(), Error> {
+    std::fs::create_dir_all(directory)?;
+    for warning in warnings.iter() {
+        if let Some(witness) = &warning.path_witness {
+            std::fs::write(
+                directory.join(format!("{}.dot", fingerprint(warning))),
+                witness.to_dot(),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Map the TIDs of the given terms to the `BlkStart` nodes of the blocks containing the terms.
+fn get_block_start_nodes(graph: &Graph, tids: &HashSet<&str>) -> HashMap<String, Vec<NodeIndex>> {
+    let mut block_nodes: HashMap<String, Vec<NodeIndex>> = HashMap::new();
+    for node in graph.node_indices() {
+        if let Node::BlkStart(block, _) = graph[node] {
+            let term_tids = block
+                .term
+                .defs
+                .iter()
+                .map(|def| &def.tid)
+                .chain(block.term.jmps.iter().map(|jmp| &jmp.tid));
+            for tid in term_tids {
+                let tid = format!("{}", tid);
+                if tids.contains(tid.as_str()) {
+                    block_nodes.entry(tid).or_default().push(node);
+                }
+            }
+        }
+    }
+    block_nodes
+}
+
+/// Find a shortest path from a block containing the source term to a block containing the sink term
+/// through a breadth-first search.
+///
+/// Returns the start node of the path and the edges along the path.
+fn find_path(
+    graph: &Graph,
+    block_nodes: &HashMap<String, Vec<NodeIndex>>,
+    source: &str,
+    sink: &str,
+) -> Option<(NodeIndex, Vec<EdgeIndex>)> {
+    let sink_nodes: HashSet<NodeIndex> = block_nodes.get(sink)?.iter().cloned().collect();
+    let mut predecessors: HashMap<NodeIndex, Option<EdgeIndex>> = HashMap::new();
+    let mut worklist = VecDeque::new();
+    for node in block_nodes.get(source)? {
+        predecessors.insert(*node, None);
+        worklist.push_back(*node);
+    }
+    while let Some(node) = worklist.pop_front() {
+        if sink_nodes.contains(&node) {
+            let mut path = Vec::new();
+            let mut current = node;
+            while let Some(edge) = predecessors[&current] {
+                path.push(edge);
+                current = graph.edge_endpoints(edge).unwrap().0;
+            }
+            path.reverse();
+            return Some((current, path));
+        }
+        for edge in graph.edges(node) {
+            if !predecessors.contains_key(&edge.target()) {
+                predecessors.insert(edge.target(), Some(edge.id()));
+                worklist.push_back(edge.target());
+            }
+        }
+    }
+    None
+}
+
+/// Get the block and the name of the function of a `BlkStart` node.
+fn get_witness_block(node: &Node) -> Option<WitnessBlock> {
+    match node {
+        Node::BlkStart(block, sub) => Some(WitnessBlock {
+            tid: format!("{}", block.tid),
+            address: block.tid.address.clone(),
+            function: sub.term.name.clone(),
+        }),
+        _ => None,
+    }
+}
+
+/// Convert a path in the control flow graph into a witness
+/// containing only the basic blocks along the path and the transitions between them.
+fn get_witness(graph: &Graph, (start, path): &(NodeIndex, Vec<EdgeIndex>)) -> PathWitness {
+    let mut blocks = vec![get_witness_block(&graph[*start]).unwrap()];
+    let mut edges = Vec::new();
+    let mut previous_edge: Option<&Edge> = None;
+    for edge in path.iter() {
+        let target = graph.edge_endpoints(*edge).unwrap().1;
+        let transition = match &graph[*edge] {
+            Edge::Jump(jump, untaken_conditional) => {
+                let condition = match (&jump.term, untaken_conditional) {
+                    (Jmp::CBranch { condition, .. }, _) => Some(format_expression(condition)),
+                    (_, Some(untaken)) => match &untaken.term {
+                        Jmp::CBranch { condition, .. } => {
+                            Some(format!("not {}", format_expression(condition)))
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                Some((WitnessEdgeKind::Jump, jump, condition))
+            }
+            Edge::Call(call) => Some((WitnessEdgeKind::Call, call, None)),
+            Edge::ExternCallStub(call) => Some((WitnessEdgeKind::ExternCall, call, None)),
+            Edge::ReturnCombine(call) => match previous_edge {
+                Some(Edge::CRReturnStub) => Some((WitnessEdgeKind::Return, call, None)),
+                _ => Some((WitnessEdgeKind::CallReturn, call, None)),
+            },
+            Edge::Block | Edge::CRCallStub | Edge::CRReturnStub | Edge::CallCombine(_) => None,
+        };
+        if let Some((kind, jump, condition)) = transition {
+            if let Some(block) = get_witness_block(&graph[target]) {
+                blocks.push(block);
+                edges.push(WitnessEdge {
+                    source: blocks.len() - 2,
+                    target: blocks.len() - 1,
+                    kind,
+                    jump: format!("{}", jump.tid),
+                    condition,
+                });
+            }
+        }
+        previous_edge = Some(&graph[*edge]);
+    }
+    PathWitness { blocks, edges }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::graph::get_program_cfg;
+
+    /// `main` calls `malloc` and then either returns or branches to a block dereferencing the returned pointer.
+    fn mock_program() -> Term<Program> {
+        let malloc = ExternSymbol::mock_with_name("malloc");
+        let mut main = Sub::mock("main");
+        main.term.blocks = vec![
+            Term {
+                tid: Tid::new("blk_call"),
+                term: Blk {
+                    defs: Vec::new(),
+                    jmps: vec![Jmp::call("call_malloc", "malloc", Some("blk_branch"))],
+                },
+            },
+            Term {
+                tid: Tid::new("blk_branch"),
+                term: Blk {
+                    defs: Vec::new(),
+                    jmps: vec![
+                        Term {
+                            tid: Tid::new("cbranch"),
+                            term: Jmp::CBranch {
+                                target: Tid::new("blk_return"),
+                                condition: Expression::var("ZF"),
+                            },
+                        },
+                        Jmp::branch("branch", "blk_deref"),
+                    ],
+                },
+            },
+            Term {
+                tid: Tid::new("blk_deref"),
+                term: Blk {
+                    defs: vec![Def::load(
+                        "deref",
+                        Variable::mock("RBX", 8),
+                        Expression::var("RAX"),
+                    )],
+                    jmps: vec![Jmp::branch("jmp_return", "blk_return")],
+                },
+            },
+            Term {
+                tid: Tid::new("blk_return"),
+                term: Blk {
+                    defs: Vec::new(),
+                    jmps: vec![Term {
+                        tid: Tid::new("return"),
+                        term: Jmp::Return(Expression::var("RA")),
+                    }],
+                },
+            },
+        ];
+        let mut program = Program::mock_empty();
+        program.subs = vec![main];
+        program.extern_symbols = vec![malloc];
+        Term {
+            tid: Tid::new("program"),
+            term: program,
+        }
+    }
+
+    #[test]
+    fn witness_path() {
+        let program = mock_program();
+        let graph = get_program_cfg(&program, vec![Tid::new("malloc")].into_iter().collect());
+        let mut warnings = vec![
+            CweWarning::new("CWE476", "0.1", "flow")
+                .tids(vec!["call_malloc".to_string(), "deref".to_string()]),
+            CweWarning::new("CWE78", "0.1", "reversed flow")
+                .tids(vec!["deref".to_string(), "call_malloc".to_string()]),
+            CweWarning::new("CWE676", "0.1", "no flow").tids(vec!["call_malloc".to_string()]),
+        ];
+        add_path_witnesses(&graph, &mut warnings);
+        let witness = warnings[0].path_witness.as_ref().unwrap();
+        let block_tids: Vec<&str> = witness
+            .blocks
+            .iter()
+            .map(|block| block.tid.as_str())
+            .collect();
+        assert_eq!(block_tids, vec!["blk_call", "blk_branch", "blk_deref"]);
+        assert_eq!(witness.edges[0].kind, WitnessEdgeKind::ExternCall);
+        assert_eq!(witness.edges[0].jump, "call_malloc");
+        assert_eq!(witness.edges[1].kind, WitnessEdgeKind::Jump);
+        assert_eq!(witness.edges[1].condition, Some("not ZF".to_string()));
+        assert_eq!(warnings[1].path_witness, warnings[0].path_witness);
+        assert!(warnings[2].path_witness.is_none());
+
+        let dot = witness.to_dot();
+        assert!(dot.contains("n0 [label=\"main\\nblk_call @ UNKNOWN\", peripheries=2];"));
+        assert!(dot.contains("n1 -> n2 [label=\"Jump branch\\nif not ZF\"];"));
+    }
+}
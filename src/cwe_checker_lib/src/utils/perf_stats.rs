@@ -0,0 +1,130 @@
+//! Lightweight counters for performance-relevant operations in the hot paths of the analyses.
+//!
+//! The counters are only collected if the crate is built with the `perf-stats` feature.
+//! Without the feature all counting functions are empty and get optimized away,
+//! so that the counters do not slow down normal analysis runs.
+//!
+//! The counters are global and shared between all threads.
+//! They are incremented with relaxed atomic operations,
+//! since only the final counts are of interest and not their order.
+
+use crate::prelude::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The operations that are counted.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Counter {
+    /// Merges of node values in fixpoint computations.
+    DomainJoins,
+    /// Merges of node values in fixpoint computations that widened the old value instead of joining the values.
+    Widenings,
+    /// Nodes of fixpoint computations that were not stabilized after the maximum number of steps.
+    UnstabilizedNodes,
+    /// Copies of shared memory regions and abstract objects before their modification
+    /// and mutable accesses to [`SharedMap`](crate::utils::shared_map::SharedMap)s,
+    /// which copy the path to the modified entry if the map is shared with a clone.
+    MapClones,
+    /// Allocations of boxed subexpressions while building expressions.
+    ExpressionAllocations,
+}
+
+/// The counter values, indexed by `Counter as usize`.
+static COUNTERS: [AtomicU64; 5] = [
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+];
+
+/// Returns `true` if the crate was built with the `perf-stats` feature.
+#[inline(always)]
+pub fn is_enabled() -> bool {
+    cfg!(feature = "perf-stats")
+}
+
+/// Add the given amount to the counter.
+#[inline(always)]
+pub fn add(counter: Counter, amount: u64) {
+    if cfg!(feature = "perf-stats") {
+        COUNTERS[counter as usize].fetch_add(amount, Ordering::Relaxed);
+    }
+}
+
+/// Increment the counter by one.
+#[inline(always)]
+pub fn increment(counter: Counter) {
+    add(counter, 1)
+}
+
+/// Reset all counters to zero.
+pub fn reset() {
+    for counter in COUNTERS.iter() {
+        counter.store(0, Ordering::Relaxed);
+    }
+}
+
+/// A snapshot of the counter values.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct PerfStats {
+    /// The number of merges of node values in fixpoint computations.
+    pub domain_joins: u64,
+    /// The number of widenings of node values in fixpoint computations.
+    pub widenings: u64,
+    /// The number of fixpoint nodes that were not stabilized after the maximum number of steps.
+    pub unstabilized_nodes: u64,
+    /// The number of copies of shared memory regions and abstract objects
+    /// and of mutable accesses to shared maps.
+    pub map_clones: u64,
+    /// The number of boxed subexpressions allocated while building expressions.
+    pub expression_allocations: u64,
+}
+
+impl PerfStats {
+    /// Get the current values of all counters.
+    pub fn snapshot() -> PerfStats {
+        let get = |counter: Counter| COUNTERS[counter as usize].load(Ordering::Relaxed);
+        PerfStats {
+            domain_joins: get(Counter::DomainJoins),
+            widenings: get(Counter::Widenings),
+            unstabilized_nodes: get(Counter::UnstabilizedNodes),
+            map_clones: get(Counter::MapClones),
+            expression_allocations: get(Counter::ExpressionAllocations),
+        }
+    }
+}
+
+impl std::fmt::Display for PerfStats {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(formatter, "Domain joins: {}", self.domain_joins)?;
+        writeln!(formatter, "Widenings: {}", self.widenings)?;
+        writeln!(formatter, "Unstabilized nodes: {}", self.unstabilized_nodes)?;
+        writeln!(formatter, "Map clones: {}", self.map_clones)?;
+        write!(
+            formatter,
+            "Expression allocations: {}",
+            self.expression_allocations
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counting() {
+        let before = PerfStats::snapshot();
+        add(Counter::MapClones, 3);
+        increment(Counter::Widenings);
+        increment(Counter::UnstabilizedNodes);
+        let after = PerfStats::snapshot();
+        if is_enabled() {
+            assert!(after.map_clones >= before.map_clones + 3);
+            assert!(after.widenings > before.widenings);
+            assert!(after.unstabilized_nodes > before.unstabilized_nodes);
+        } else {
+            assert_eq!(after, PerfStats::default());
+        }
+    }
+}
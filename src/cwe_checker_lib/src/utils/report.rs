@@ -0,0 +1,203 @@
+//! Generate self-contained HTML reports of the analysis results.
+//!
+//! The report is a single HTML file without external resources, so that it can be shared easily.
+//! It contains
+//! - a summary of the number of warnings per check,
+//! - a sortable table of all warnings,
+//! - the warnings grouped by check,
+//! - an index of all addresses referenced by warnings, which the addresses in the tables link to,
+//! - and all log messages.
+
+use crate::utils::log::{CweWarning, LogMessage};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// The style sheet of the report.
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 2em; }
+th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; vertical-align: top; }
+th { background: #eee; }
+table.sortable th { cursor: pointer; }
+tr:nth-child(even) { background: #f8f8f8; }
+code { font-family: monospace; }
+";
+
+/// The script that makes tables with the `sortable` class sortable by clicking on the column headers.
+const SCRIPT: &str = "
+document.querySelectorAll('table.sortable th').forEach(function (header) {
+  header.addEventListener('click', function () {
+    var table = header.closest('table');
+    var body = table.tBodies[0];
+    var index = Array.prototype.indexOf.call(header.parentNode.children, header);
+    var ascending = header.dataset.order !== 'asc';
+    header.dataset.order = ascending ? 'asc' : 'desc';
+    Array.from(body.rows)
+      .sort(function (a, b) {
+        var left = a.cells[index].textContent, right = b.cells[index].textContent;
+        return ascending ? left.localeCompare(right) : right.localeCompare(left);
+      })
+      .forEach(function (row) { body.appendChild(row); });
+  });
+});
+";
+
+/// Escape the special characters of HTML in the given text.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+/// Generate the links to the address index for the addresses of a warning.
+fn address_links(cwe: &CweWarning) -> String {
+    cwe.addresses
+        .iter()
+        .map(|address| {
+            format!(
+                "<a href=\"#address-{0}\"><code>{0}</code></a>",
+                escape(address)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Generate a table row for a warning.
+fn warning_row(cwe: &CweWarning) -> String {
+    format!(
+        "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+        escape(&cwe.name),
+        cwe.severity,
+        address_links(cwe),
+        escape(&cwe.symbols.join(", ")),
+        escape(cwe.component.as_deref().unwrap_or("")),
+        escape(&cwe.description)
+    )
+}
+
+/// The header row of the warning tables.
+const WARNING_TABLE_HEADER: &str =
+    "<thead><tr><th>Check</th><th>Severity</th><th>Addresses</th><th>Symbols</th><th>Component</th><th>Description</th></tr></thead>\n";
+
+/// Generate a self-contained HTML report of the given warnings and log messages.
+pub fn generate_html_report(title: &str, logs: &[LogMessage], cwes: &[CweWarning]) -> String {
+    let mut groups: BTreeMap<&str, Vec<&CweWarning>> = BTreeMap::new();
+    let mut addresses: BTreeMap<&str, Vec<&CweWarning>> = BTreeMap::new();
+    for cwe in cwes {
+        groups
+            .entry(cwe.name.as_str())
+            .or_insert_with(Vec::new)
+            .push(cwe);
+        for address in cwe.addresses.iter() {
+            addresses
+                .entry(address.as_str())
+                .or_insert_with(Vec::new)
+                .push(cwe);
+        }
+    }
+    let mut html = String::new();
+    // Writing to a string cannot fail, so the results of `write!` are ignored.
+    let _ = writeln!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n<style>{1}</style>\n</head>\n<body>\n<h1>{0}</h1>",
+        escape(title),
+        STYLE
+    );
+
+    let _ = writeln!(
+        html,
+        "<h2>Summary</h2>\n<p>{} warnings, {} log messages</p>\n<table class=\"sortable\">\n<thead><tr><th>Check</th><th>Warnings</th></tr></thead>\n<tbody>",
+        cwes.len(),
+        logs.len()
+    );
+    for (name, group) in groups.iter() {
+        let _ = writeln!(
+            html,
+            "<tr><td><a href=\"#check-{0}\">{0}</a></td><td>{1}</td></tr>",
+            escape(name),
+            group.len()
+        );
+    }
+    html.push_str("</tbody>\n</table>\n");
+
+    html.push_str("<h2>All Warnings</h2>\n<table class=\"sortable\">\n");
+    html.push_str(WARNING_TABLE_HEADER);
+    html.push_str("<tbody>\n");
+    for cwe in cwes {
+        html.push_str(&warning_row(cwe));
+    }
+    html.push_str("</tbody>\n</table>\n");
+
+    html.push_str("<h2>Warnings by Check</h2>\n");
+    for (name, group) in groups.iter() {
+        let _ = writeln!(
+            html,
+            "<h3 id=\"check-{0}\">{0}</h3>\n<table class=\"sortable\">\n{1}<tbody>",
+            escape(name),
+            WARNING_TABLE_HEADER
+        );
+        for cwe in group {
+            html.push_str(&warning_row(cwe));
+        }
+        html.push_str("</tbody>\n</table>\n");
+    }
+
+    html.push_str("<h2>Addresses</h2>\n<table>\n<thead><tr><th>Address</th><th>Warnings</th></tr></thead>\n<tbody>\n");
+    for (address, group) in addresses.iter() {
+        let descriptions: Vec<String> = group
+            .iter()
+            .map(|cwe| format!("[{}] {}", escape(&cwe.name), escape(&cwe.description)))
+            .collect();
+        let _ = writeln!(
+            html,
+            "<tr id=\"address-{0}\"><td><code>{0}</code></td><td>{1}</td></tr>",
+            escape(address),
+            descriptions.join("<br>")
+        );
+    }
+    html.push_str("</tbody>\n</table>\n");
+
+    html.push_str("<h2>Log Messages</h2>\n<table class=\"sortable\">\n<thead><tr><th>Message</th></tr></thead>\n<tbody>\n");
+    for log in logs {
+        let _ = writeln!(html, "<tr><td>{}</td></tr>", escape(&format!("{}", log)));
+    }
+    html.push_str("</tbody>\n</table>\n");
+
+    let _ = writeln!(html, "<script>{}</script>\n</body>\n</html>", SCRIPT);
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_report() {
+        let cwes = vec![
+            CweWarning::new("CWE676", "0.1", "call to <strcpy>")
+                .addresses(vec!["00401000".to_string()])
+                .symbols(vec!["strcpy".to_string()]),
+            CweWarning::new("CWE476", "0.3", "NULL dereference")
+                .addresses(vec!["00401000".to_string()]),
+        ];
+        let logs = vec![LogMessage::new_info("some info")];
+        let html = generate_html_report("Report for a&b", &logs, &cwes);
+        assert!(html.contains("<title>Report for a&amp;b</title>"));
+        assert!(html.contains("call to &lt;strcpy&gt;"));
+        assert!(html.contains("<h3 id=\"check-CWE476\">CWE476</h3>"));
+        assert!(html.contains("<a href=\"#address-00401000\"><code>00401000</code></a>"));
+        assert!(html.contains("<tr id=\"address-00401000\">"));
+        assert!(html.contains("some info"));
+        assert!(html.contains("<p>2 warnings, 1 log messages</p>"));
+    }
+}
@@ -0,0 +1,278 @@
+//! Resource budgets for expensive checks.
+//!
+//! Some checks (e.g. the check for CWE-78) run a separate fixpoint computation for each taint source.
+//! On some binaries single computations use up huge amounts of memory and computation time.
+//! Instead of disabling these checks completely, they run within a resource budget:
+//! - The resident memory of the process may not exceed a maximum.
+//!   If it is exceeded, the remaining computations of the check are skipped.
+//! - Each computation may not store more than a maximum number of node states.
+//! - The computations started in a function may not take longer than a maximum time in total.
+//! If a computation exceeds one of the limits, it is stopped and its intermediate results are used,
+//! so that the check degrades gracefully instead of running out of resources.
+//!
+//! The budget is read from the `ResourceBudget` section of the configuration file.
+//! The `--thorough` command line flag lifts all limits.
+
+use crate::analysis::fixpoint::{Computation, Context};
+use crate::intermediate_representation::Tid;
+use crate::prelude::*;
+use crate::utils::cancellation::CancellationToken;
+use crate::utils::log::LogMessage;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+/// The interval in which running computations check whether they exceed the state or memory limits.
+const CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The resource limits for expensive checks.
+///
+/// Limits that are not set are not enforced,
+/// i.e. the default budget is unlimited.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+#[serde(default)]
+pub struct ResourceBudget {
+    /// The maximal resident memory of the cwe_checker process in megabytes.
+    /// The memory usage can only be measured on Linux, so the limit is ignored on other systems.
+    pub max_memory_mb: Option<u64>,
+    /// The maximal number of node states a single fixpoint computation may store.
+    pub max_states: Option<usize>,
+    /// The maximal time in seconds that may be spent on computations started in the same function.
+    pub max_seconds_per_function: Option<u64>,
+}
+
+impl ResourceBudget {
+    /// Returns `true` if the resident memory of the process exceeds the memory limit.
+    pub fn is_memory_exceeded(&self) -> bool {
+        match (self.max_memory_mb, get_resident_memory_mb()) {
+            (Some(max_memory), Some(memory)) => memory > max_memory,
+            _ => false,
+        }
+    }
+}
+
+/// The limits of a resource budget.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub enum Limit {
+    /// The memory limit of the process.
+    Memory,
+    /// The limit on the number of node states of a computation.
+    States,
+    /// The time limit per function.
+    Time,
+}
+
+impl std::fmt::Display for Limit {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Limit::Memory => write!(formatter, "memory limit"),
+            Limit::States => write!(formatter, "state limit"),
+            Limit::Time => write!(formatter, "time limit per function"),
+        }
+    }
+}
+
+/// Runs the fixpoint computations of a check within a resource budget
+/// and keeps track of the resources used and the computations stopped because of exceeded limits.
+pub struct BudgetTracker {
+    budget: ResourceBudget,
+    /// The cancellation token of the module running the computations.
+    module_cancellation: CancellationToken,
+    /// The time spent on computations started in each function.
+    time_per_function: HashMap<Tid, Duration>,
+    /// Set to `true` once the memory limit was exceeded.
+    memory_exhausted: bool,
+    /// The number of computations that were stopped or skipped because of each limit.
+    stopped_computations: BTreeMap<Limit, u64>,
+    /// The total number of computations.
+    number_of_computations: u64,
+    /// Set to `true` once a computation was stopped or skipped because the module cancellation token was cancelled.
+    interrupted: bool,
+}
+
+impl BudgetTracker {
+    /// Create a new tracker for the given budget.
+    /// Computations are always stopped once the given module cancellation token is cancelled.
+    pub fn new(budget: ResourceBudget, module_cancellation: CancellationToken) -> BudgetTracker {
+        BudgetTracker {
+            budget,
+            module_cancellation,
+            time_per_function: HashMap::new(),
+            memory_exhausted: false,
+            stopped_computations: BTreeMap::new(),
+            number_of_computations: 0,
+            interrupted: false,
+        }
+    }
+
+    /// Compute the fixpoint of a computation started in the given function within the resource budget.
+    /// Each node is visited at most `max_steps` times.
+    ///
+    /// If a limit is exceeded, the computation is stopped (or not started at all)
+    /// and its intermediate results remain in the computation.
+    /// Returns the exceeded limit in that case.
+    ///
+    /// If the module cancellation token is cancelled, the computation is not started (or stopped)
+    /// and the module counts as interrupted (see [`BudgetTracker::was_interrupted`]).
+    pub fn compute<T: Context>(
+        &mut self,
+        computation: &mut Computation<T>,
+        max_steps: u64,
+        function: &Tid,
+    ) -> Option<Limit> {
+        if self.module_cancellation.is_cancelled() {
+            self.interrupted = true;
+            return None;
+        }
+        self.number_of_computations += 1;
+        if !self.memory_exhausted && self.budget.is_memory_exceeded() {
+            self.memory_exhausted = true;
+        }
+        if self.memory_exhausted {
+            return Some(self.add_stopped_computation(Limit::Memory));
+        }
+        let function_deadline = match self.budget.max_seconds_per_function {
+            Some(max_seconds) => {
+                let time_spent = self
+                    .time_per_function
+                    .get(function)
+                    .cloned()
+                    .unwrap_or_default();
+                match Duration::from_secs(max_seconds).checked_sub(time_spent) {
+                    Some(remaining_time) if remaining_time > Duration::from_secs(0) => {
+                        Some(Instant::now() + remaining_time)
+                    }
+                    _ => return Some(self.add_stopped_computation(Limit::Time)),
+                }
+            }
+            None => None,
+        };
+        let cancellation = match function_deadline {
+            Some(deadline) => self.module_cancellation.child_with_deadline(deadline),
+            None => self.module_cancellation.child(),
+        };
+        let budget = self.budget;
+        let start_time = Instant::now();
+        let mut exceeded_limit = None;
+        {
+            let mut check_limits = |computation: &Computation<T>| {
+                if let Some(max_states) = budget.max_states {
+                    if computation.node_values().len() > max_states {
+                        exceeded_limit = Some(Limit::States);
+                        cancellation.cancel();
+                    }
+                }
+                if budget.is_memory_exceeded() {
+                    exceeded_limit = Some(Limit::Memory);
+                    cancellation.cancel();
+                }
+            };
+            computation.compute_with_checkpoints(
+                max_steps,
+                Some(&cancellation),
+                Some((CHECK_INTERVAL, &mut check_limits)),
+            );
+        }
+        *self
+            .time_per_function
+            .entry(function.clone())
+            .or_insert_with(Duration::default) += start_time.elapsed();
+        if !computation.has_stabilized() && self.module_cancellation.is_cancelled() {
+            self.interrupted = true;
+        }
+        if exceeded_limit.is_none() && !computation.has_stabilized() {
+            if let Some(function_deadline) = function_deadline {
+                if Instant::now() >= function_deadline && !self.module_cancellation.is_cancelled() {
+                    exceeded_limit = Some(Limit::Time);
+                }
+            }
+        }
+        match exceeded_limit {
+            Some(Limit::Memory) => {
+                self.memory_exhausted = true;
+                Some(self.add_stopped_computation(Limit::Memory))
+            }
+            Some(limit) => Some(self.add_stopped_computation(limit)),
+            None => None,
+        }
+    }
+
+    /// Returns `true` if a computation was stopped or skipped because the module cancellation token was cancelled.
+    pub fn was_interrupted(&self) -> bool {
+        self.interrupted
+    }
+
+    /// Generate log messages on the computations that were stopped because of exceeded limits.
+    pub fn get_logs(&self, module_name: &str) -> Vec<LogMessage> {
+        self.stopped_computations
+            .iter()
+            .map(|(limit, count)| {
+                LogMessage::new_info(format!(
+                    "Resource budget: {} of {} computations were stopped or skipped because of the {}. Results may be incomplete. Use --thorough to lift the limits.",
+                    count, self.number_of_computations, limit
+                ))
+                .source(module_name)
+            })
+            .collect()
+    }
+
+    /// Count a computation that was stopped because of the given limit and return the limit.
+    fn add_stopped_computation(&mut self, limit: Limit) -> Limit {
+        *self.stopped_computations.entry(limit).or_insert(0) += 1;
+        limit
+    }
+}
+
+/// Get the resident memory of the process in megabytes.
+/// Returns `None` if it cannot be determined, e.g. on systems other than Linux.
+pub fn get_resident_memory_mb() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    parse_resident_memory_mb(&status)
+}
+
+/// Parse the resident memory in megabytes from the contents of a `/proc/[pid]/status` file.
+fn parse_resident_memory_mb(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kilobytes: u64 = line
+        .trim_start_matches("VmRSS:")
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kilobytes / 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resident_memory_parsing() {
+        let status = "Name:\tcwe_checker\nVmPeak:\t  409600 kB\nVmRSS:\t  204800 kB\nThreads:\t1\n";
+        assert_eq!(parse_resident_memory_mb(status), Some(200));
+        assert_eq!(parse_resident_memory_mb("Name:\tcwe_checker\n"), None);
+        assert!(!ResourceBudget::default().is_memory_exceeded());
+    }
+
+    #[test]
+    fn stopped_computation_logs() {
+        let budget = ResourceBudget {
+            max_memory_mb: Some(0),
+            max_states: None,
+            max_seconds_per_function: Some(10),
+        };
+        let mut tracker = BudgetTracker::new(budget, CancellationToken::new());
+        tracker.number_of_computations = 3;
+        tracker.add_stopped_computation(Limit::Time);
+        tracker.add_stopped_computation(Limit::Memory);
+        tracker.add_stopped_computation(Limit::Memory);
+        let logs = tracker.get_logs("CWE78");
+        assert_eq!(logs.len(), 2);
+        assert_eq!(
+            logs[0].text,
+            "Resource budget: 2 of 3 computations were stopped or skipped because of the memory limit. Results may be incomplete. Use --thorough to lift the limits."
+        );
+        assert_eq!(logs[1].source, Some("CWE78".to_string()));
+        assert!(!tracker.was_interrupted());
+    }
+}
@@ -0,0 +1,171 @@
+//! Timing and resource statistics of an analysis run.
+//!
+//! The statistics show which check modules dominate the analysis time and memory usage on a binary.
+//! In contrast to the [performance counters](crate::utils::perf_stats)
+//! they are always available and only measured at the granularity of whole modules.
+
+use crate::prelude::*;
+use std::time::Duration;
+
+/// The statistics of a single check module.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct ModuleStatistics {
+    /// The name of the module.
+    pub name: String,
+    /// The runtime of the module in seconds.
+    pub runtime: f64,
+    /// The number of warnings generated by the module before any filtering.
+    pub number_of_warnings: usize,
+    /// The peak memory usage (resident set size in KiB) of the process up to the end of the module.
+    /// `None` if the memory usage cannot be determined on the operating system.
+    pub peak_memory: Option<u64>,
+}
+
+/// The statistics of a whole analysis run.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct RunStatistics {
+    /// The number of analyzed functions.
+    pub number_of_functions: usize,
+    /// The runtime of the pointer inference analysis in seconds, if it was computed.
+    pub pointer_inference_runtime: Option<f64>,
+    /// The statistics of the check modules in the order of their execution.
+    pub modules: Vec<ModuleStatistics>,
+    /// The total runtime of the analysis in seconds, including the runtime of Ghidra.
+    pub total_runtime: f64,
+    /// The peak memory usage (resident set size in KiB) of the process.
+    /// `None` if the memory usage cannot be determined on the operating system.
+    pub peak_memory: Option<u64>,
+}
+
+impl RunStatistics {
+    /// Create empty statistics for a run analyzing the given number of functions.
+    pub fn new(number_of_functions: usize) -> RunStatistics {
+        RunStatistics {
+            number_of_functions,
+            ..RunStatistics::default()
+        }
+    }
+
+    /// Add the statistics of a module that just finished.
+    ///
+    /// If the module already ran before (e.g. on another chunk of a chunked analysis),
+    /// the runtime and the number of warnings are added to the existing entry
+    /// and the peak memory usage is updated.
+    pub fn add_module(&mut self, name: &str, runtime: Duration, number_of_warnings: usize) {
+        let peak_memory = get_peak_memory_usage();
+        if let Some(module) = self.modules.iter_mut().find(|module| module.name == name) {
+            module.runtime += runtime.as_secs_f64();
+            module.number_of_warnings += number_of_warnings;
+            module.peak_memory = module.peak_memory.max(peak_memory);
+        } else {
+            self.modules.push(ModuleStatistics {
+                name: name.to_string(),
+                runtime: runtime.as_secs_f64(),
+                number_of_warnings,
+                peak_memory,
+            });
+        }
+    }
+
+    /// Record the total runtime and the peak memory usage at the end of the run.
+    pub fn finish(&mut self, total_runtime: Duration) {
+        self.total_runtime = total_runtime.as_secs_f64();
+        self.peak_memory = get_peak_memory_usage();
+    }
+}
+
+impl std::fmt::Display for RunStatistics {
+    /// Print the statistics as a table, with the modules sorted by their runtime in descending order.
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let format_memory = |memory: Option<u64>| match memory {
+            Some(memory) => format!("{} MiB", memory / 1024),
+            None => "unknown".to_string(),
+        };
+        writeln!(
+            formatter,
+            "Analyzed functions: {}",
+            self.number_of_functions
+        )?;
+        if let Some(runtime) = self.pointer_inference_runtime {
+            writeln!(formatter, "Pointer inference: {:.2}s", runtime)?;
+        }
+        let mut modules: Vec<&ModuleStatistics> = self.modules.iter().collect();
+        modules.sort_by(|first, second| {
+            second
+                .runtime
+                .partial_cmp(&first.runtime)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        writeln!(
+            formatter,
+            "{:<16} {:>10} {:>10} {:>12}",
+            "Module", "Runtime", "Warnings", "Peak memory"
+        )?;
+        for module in modules {
+            writeln!(
+                formatter,
+                "{:<16} {:>9.2}s {:>10} {:>12}",
+                module.name,
+                module.runtime,
+                module.number_of_warnings,
+                format_memory(module.peak_memory)
+            )?;
+        }
+        writeln!(formatter, "Total runtime: {:.2}s", self.total_runtime)?;
+        write!(
+            formatter,
+            "Peak memory: {}",
+            format_memory(self.peak_memory)
+        )
+    }
+}
+
+/// Get the peak memory usage (resident set size in KiB) of the process.
+///
+/// The value is read from `/proc/self/status`, so it is only available on Linux.
+pub fn get_peak_memory_usage() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    parse_peak_memory_usage(&status)
+}
+
+/// Parse the peak resident set size (the `VmHWM` line) from the contents of `/proc/self/status`.
+fn parse_peak_memory_usage(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    line["VmHWM:".len()..]
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peak_memory_parsing() {
+        let status =
+            "Name:\tcwe_checker\nVmPeak:\t  123456 kB\nVmHWM:\t   20480 kB\nVmRSS:\t   10240 kB\n";
+        assert_eq!(parse_peak_memory_usage(status), Some(20480));
+        assert_eq!(parse_peak_memory_usage("Name:\tcwe_checker\n"), None);
+    }
+
+    #[test]
+    fn statistics_output() {
+        let mut statistics = RunStatistics::new(42);
+        statistics.add_module("CWE476", Duration::from_millis(500), 3);
+        statistics.add_module("CWE78", Duration::from_secs(1), 1);
+        // A module running again on another chunk is aggregated into its first entry.
+        statistics.add_module("CWE78", Duration::from_secs(1), 2);
+        statistics.finish(Duration::from_secs(5));
+        assert_eq!(statistics.modules.len(), 2);
+        assert_eq!(statistics.modules[1].runtime, 2.0);
+        assert_eq!(statistics.modules[1].number_of_warnings, 3);
+        let output = format!("{}", statistics);
+        assert!(output.starts_with("Analyzed functions: 42\n"));
+        // The modules are sorted by their runtime.
+        assert!(output.find("CWE78").unwrap() < output.find("CWE476").unwrap());
+        assert!(output.contains("Total runtime: 5.00s"));
+    }
+}
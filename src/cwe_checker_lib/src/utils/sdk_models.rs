@@ -0,0 +1,215 @@
+//! Models of the functions of embedded SDKs and hardware abstraction layers.
+//!
+//! Bare-metal and RTOS firmware is usually statically linked,
+//! so the I/O and memory management functions of its SDKs (e.g. lwIP, FreeRTOS or mbedTLS)
+//! are ordinary functions of the binary instead of calls to known extern symbols.
+//! Since the checks only recognize sources of untrusted input, allocators and non-returning functions by name,
+//! they find almost nothing in such binaries.
+//!
+//! The models are read from the `SdkModels` section of the configuration file.
+//! Each modelled function
+//! - is added to the symbol lists of the configuration sections of the corresponding checks
+//! (taint sources, allocators and deallocators)
+//! - and replaced by an extern symbol in the project, so that calls to it are handled like calls to the modelled library function.
+//! Non-returning functions (e.g. assertion handlers) are marked as such.
+
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::address_ranges::to_extern_symbol;
+use crate::utils::log::LogMessage;
+use std::collections::HashSet;
+
+/// The model of the functions of a single SDK.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+#[serde(default)]
+pub struct SdkModel {
+    /// The name of the SDK, e.g. `lwIP`.
+    pub name: String,
+    /// Functions returning untrusted input, e.g. data received from the network.
+    pub taint_sources: Vec<String>,
+    /// Functions allocating heap memory, where the size of the allocation is given by the parameters.
+    pub allocators: Vec<String>,
+    /// Functions freeing memory allocated by one of the allocators.
+    pub deallocators: Vec<String>,
+    /// Functions that never return, e.g. assertion failure handlers.
+    pub non_returning: Vec<String>,
+}
+
+/// The configured SDK models.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+#[serde(default)]
+pub struct Config {
+    /// The models of the SDKs.
+    pub models: Vec<SdkModel>,
+}
+
+/// The configuration sections and the names of their symbol lists
+/// to which the taint sources of the models are added.
+pub(crate) const TAINT_SOURCE_LISTS: [(&str, &str); 4] = [
+    ("check_path", "symbols"),
+    ("CWE78", "user_input_symbols"),
+    ("FactExport", "taint_sources"),
+    ("FunctionSummaries", "taint_sources"),
+];
+
+/// The configuration sections and the names of their symbol lists
+/// to which the allocators of the models are added.
+pub(crate) const ALLOCATOR_LISTS: [(&str, &str); 3] = [
+    ("Memory", "allocation_symbols"),
+    ("CWE190", "symbols"),
+    ("CWE476", "symbols"),
+];
+
+/// The configuration sections and the names of their symbol lists
+/// to which the deallocators of the models are added.
+pub(crate) const DEALLOCATOR_LISTS: [(&str, &str); 1] = [("Memory", "deallocation_symbols")];
+
+impl Config {
+    /// Add the modelled functions to the symbol lists of the corresponding checks in the configuration.
+    ///
+    /// Symbol lists that do not exist in the configuration are not created.
+    pub fn apply_to_config(&self, config: &mut serde_json::Value) {
+        for model in self.models.iter() {
+            add_symbols_to_config_lists(config, &model.taint_sources, &TAINT_SOURCE_LISTS);
+            add_symbols_to_config_lists(config, &model.allocators, &ALLOCATOR_LISTS);
+            add_symbols_to_config_lists(config, &model.deallocators, &DEALLOCATOR_LISTS);
+        }
+    }
+
+    /// Replace the modelled functions contained in the project by extern symbols
+    /// and mark the non-returning functions among the extern symbols as non-returning.
+    #[must_use]
+    pub fn apply_to_project(&self, project: &mut Project) -> Vec<LogMessage> {
+        let mut modelled_functions = HashSet::new();
+        let mut non_returning = HashSet::new();
+        for model in self.models.iter() {
+            modelled_functions.extend(
+                model
+                    .taint_sources
+                    .iter()
+                    .chain(model.allocators.iter())
+                    .chain(model.deallocators.iter())
+                    .map(|name| name.as_str()),
+            );
+            non_returning.extend(model.non_returning.iter().map(|name| name.as_str()));
+        }
+        modelled_functions.extend(non_returning.iter());
+        if modelled_functions.is_empty() {
+            return Vec::new();
+        }
+        let calling_convention = project
+            .get_standard_calling_convention()
+            .map(|cconv| cconv.name.clone());
+        let (modelled_subs, kept_subs): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut project.program.term.subs)
+                .into_iter()
+                .partition(|sub| modelled_functions.contains(sub.term.name.as_str()));
+        project.program.term.subs = kept_subs;
+        for symbol in project.program.term.extern_symbols.iter_mut() {
+            if non_returning.contains(symbol.name.as_str()) {
+                symbol.no_return = true;
+            }
+        }
+        for sub in modelled_subs.iter() {
+            project.program.term.extern_symbols.push(to_extern_symbol(
+                sub,
+                calling_convention.clone(),
+                non_returning.contains(sub.term.name.as_str()),
+            ));
+        }
+        project
+            .program
+            .term
+            .entry_points
+            .retain(|tid| !modelled_subs.iter().any(|sub| sub.tid == *tid));
+        if modelled_subs.is_empty() {
+            return Vec::new();
+        }
+        vec![LogMessage::new_info(format!(
+            "Replaced {} functions by the configured SDK models.",
+            modelled_subs.len()
+        ))
+        .source("SDK Models")]
+    }
+}
+
+/// Add the symbols to the given symbol lists of the configuration.
+///
+/// The lists are given as pairs of a configuration section and the name of the list inside the section.
+/// Symbol lists that do not exist in the configuration are not created.
+pub(crate) fn add_symbols_to_config_lists(
+    config: &mut serde_json::Value,
+    symbols: &[String],
+    lists: &[(&str, &str)],
+) {
+    for (section, list) in lists.iter() {
+        if let Some(list) = config
+            .get_mut(*section)
+            .and_then(|section| section.get_mut(*list))
+            .and_then(|list| list.as_array_mut())
+        {
+            for symbol in symbols.iter() {
+                let symbol = serde_json::Value::String(symbol.clone());
+                if !list.contains(&symbol) {
+                    list.push(symbol);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_config() -> Config {
+        Config {
+            models: vec![SdkModel {
+                name: "FreeRTOS".to_string(),
+                taint_sources: vec!["xQueueReceive".to_string()],
+                allocators: vec!["pvPortMalloc".to_string()],
+                deallocators: vec!["vPortFree".to_string()],
+                non_returning: vec!["vAssertCalled".to_string()],
+            }],
+        }
+    }
+
+    #[test]
+    fn config_extension() {
+        let mut config = serde_json::json!({
+            "check_path": { "symbols": ["recv"] },
+            "Memory": {
+                "allocation_symbols": ["malloc", "pvPortMalloc"],
+                "deallocation_symbols": ["free"]
+            }
+        });
+        mock_config().apply_to_config(&mut config);
+        assert_eq!(
+            config,
+            serde_json::json!({
+                "check_path": { "symbols": ["recv", "xQueueReceive"] },
+                "Memory": {
+                    "allocation_symbols": ["malloc", "pvPortMalloc"],
+                    "deallocation_symbols": ["free", "vPortFree"]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn project_modification() {
+        let mut project = Project::mock_empty();
+        project.program.term.subs.push(Sub::mock("main"));
+        project.program.term.subs.push(Sub::mock("pvPortMalloc"));
+        project.program.term.subs.push(Sub::mock("vAssertCalled"));
+        let logs = mock_config().apply_to_project(&mut project);
+        assert_eq!(logs.len(), 1);
+        assert_eq!(project.program.term.subs.len(), 1);
+        let extern_symbols = &project.program.term.extern_symbols;
+        assert_eq!(extern_symbols.len(), 2);
+        assert_eq!(extern_symbols[0].name, "pvPortMalloc");
+        assert!(!extern_symbols[0].no_return);
+        assert_eq!(extern_symbols[1].name, "vAssertCalled");
+        assert!(extern_symbols[1].no_return);
+    }
+}
@@ -0,0 +1,142 @@
+//! A persistent map whose clones share their memory.
+//!
+//! Fixpoint computations clone the abstract states for every edge of the control flow graph
+//! and for every split of a state (e.g. at call sites),
+//! but most of these clones are never modified or only modified in few places.
+//! A [`SharedMap`] wraps a persistent B-tree map (see `im::OrdMap`), so that cloning it is cheap.
+//! Modifying a clone only copies the tree nodes on the path to the modified entry,
+//! all other nodes stay shared with the other clones of the map.
+//! Since the values of the maps in abstract states (e.g. abstract objects) are themselves wrapped in `Arc`s,
+//! even the copied nodes do not duplicate the values.
+//!
+//! Read and write access is provided through `Deref` and `DerefMut` to the wrapped `OrdMap`,
+//! whose interface is similar to the interface of a `BTreeMap`.
+//! Mutable accesses are counted as `MapClones` in the [performance counters](crate::utils::perf_stats).
+
+use crate::utils::perf_stats::{self, Counter};
+use derive_more::Deref;
+use im::OrdMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::DerefMut;
+
+/// A persistent map whose clones share all unmodified parts of their memory.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Deref)]
+#[deref(forward)]
+pub struct SharedMap<K: Ord + Clone, V: Clone>(OrdMap<K, V>);
+
+impl<K: Ord + Clone, V: Clone> SharedMap<K, V> {
+    /// Create a new empty map.
+    pub fn new() -> SharedMap<K, V> {
+        SharedMap(OrdMap::new())
+    }
+
+    /// Returns `true` if both maps share the same memory, which implies that they are equal.
+    ///
+    /// This allows operations on two maps (e.g. merging) to short-circuit without comparing the map contents.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        self.0.ptr_eq(&other.0)
+    }
+
+    /// Apply the `update` function to all values of the map for which `needs_update` returns `true`.
+    ///
+    /// Mutable access to an entry copies the path to it (and copy-on-write values like abstract objects),
+    /// so the read-only `needs_update` check should filter out all values that the update would not change.
+    /// The entries of all other values stay shared with the clones of the map.
+    pub fn update_values(
+        &mut self,
+        needs_update: impl Fn(&V) -> bool,
+        mut update: impl FnMut(&mut V),
+    ) {
+        let keys: Vec<K> = self
+            .0
+            .iter()
+            .filter_map(|(key, value)| {
+                if needs_update(value) {
+                    Some(key.clone())
+                } else {
+                    None
+                }
+            })
+            .collect();
+        for key in keys {
+            perf_stats::increment(Counter::MapClones);
+            if let Some(value) = self.0.get_mut(&key) {
+                update(value);
+            }
+        }
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> Default for SharedMap<K, V> {
+    fn default() -> SharedMap<K, V> {
+        SharedMap::new()
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> DerefMut for SharedMap<K, V> {
+    /// Mutable access to the map.
+    ///
+    /// Since `OrdMap` does not reveal whether its nodes are shared with other clones,
+    /// every mutable access is counted as a (potential) copy in the performance counters.
+    fn deref_mut(&mut self) -> &mut OrdMap<K, V> {
+        perf_stats::increment(Counter::MapClones);
+        &mut self.0
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> From<BTreeMap<K, V>> for SharedMap<K, V> {
+    fn from(map: BTreeMap<K, V>) -> SharedMap<K, V> {
+        map.into_iter().collect()
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> std::iter::FromIterator<(K, V)> for SharedMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> SharedMap<K, V> {
+        SharedMap(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn structural_sharing() {
+        let mut map: SharedMap<u64, u64> = vec![(1, 10), (2, 20)].into_iter().collect();
+        let clone = map.clone();
+        assert!(map.ptr_eq(&clone));
+        map.insert(3, 30);
+        assert!(!map.ptr_eq(&clone));
+        assert_eq!(map.len(), 3);
+        assert_eq!(clone.len(), 2);
+        assert_eq!(clone.get(&1), Some(&10));
+        let unchanged = clone.clone();
+        assert_eq!(unchanged, clone);
+
+        map.update_values(|_| true, |value| *value += 1);
+        assert_eq!(map.values().cloned().collect::<Vec<_>>(), vec![11, 21, 31]);
+        assert_eq!(clone.get(&1), Some(&10));
+    }
+
+    #[test]
+    fn update_values_keeps_unaffected_entries_shared() {
+        let map: SharedMap<u64, Arc<u64>> = (0..100).map(|i| (i, Arc::new(i))).collect();
+        let mut updated = map.clone();
+        updated.update_values(|value| **value == 42, |value| *Arc::make_mut(value) += 1);
+        assert_eq!(**updated.get(&42).unwrap(), 43);
+        assert_eq!(**map.get(&42).unwrap(), 42);
+        for i in (0..100).filter(|i| *i != 42) {
+            assert!(Arc::ptr_eq(map.get(&i).unwrap(), updated.get(&i).unwrap()));
+        }
+        assert!(!Arc::ptr_eq(
+            map.get(&42).unwrap(),
+            updated.get(&42).unwrap()
+        ));
+
+        let mut unchanged = map.clone();
+        unchanged.update_values(|_| false, |value| *Arc::make_mut(value) += 1);
+        assert!(unchanged.ptr_eq(&map));
+    }
+}
@@ -0,0 +1,225 @@
+//! Suppression of known warnings through baseline files.
+//!
+//! When the cwe_checker is introduced into the CI pipeline of a legacy code base,
+//! the existing findings have to be triaged before new findings can fail the build.
+//! A suppression file lists warnings that should not be reported anymore,
+//! either by their fingerprint (see [`crate::utils::fingerprint`]) or by their check name and address.
+//! A baseline, i.e. a suppression file containing all current findings,
+//! can be generated from the warnings of an analysis run with [`SuppressionFile::from_warnings`].
+//!
+//! Suppression files are written and read as versioned artifacts (see [`crate::utils::artifact`]).
+//! Hand-written suppression files without version stamp are accepted, too.
+
+use crate::prelude::*;
+use crate::utils::artifact::VersionedArtifact;
+use crate::utils::fingerprint::fingerprint;
+use crate::utils::log::CweWarning;
+
+/// Compare two hexadecimal addresses independently of leading zeros.
+fn addresses_match(lhs: &str, rhs: &str) -> bool {
+    let lhs = lhs.trim_start_matches("0x");
+    let rhs = rhs.trim_start_matches("0x");
+    match (u64::from_str_radix(lhs, 16), u64::from_str_radix(rhs, 16)) {
+        (Ok(lhs), Ok(rhs)) => lhs == rhs,
+        _ => lhs == rhs,
+    }
+}
+
+/// A single entry of a suppression file.
+///
+/// A warning is suppressed if it matches all fields that are set.
+/// Entries without any of the fields `fingerprint`, `cwe` and `address` match no warnings.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+#[serde(default)]
+pub struct Suppression {
+    /// The fingerprint of the suppressed warning.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    /// The name of the check of the suppressed warning, e.g. `CWE476`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwe: Option<String>,
+    /// One of the addresses of the suppressed warning as hexadecimal number.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// A free-form comment, e.g. the reason for the suppression.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+impl Suppression {
+    /// Returns `true` if the warning matches all fields of the suppression that are set.
+    pub fn matches(&self, warning: &CweWarning, warning_fingerprint: &str) -> bool {
+        if self.fingerprint.is_none() && self.cwe.is_none() && self.address.is_none() {
+            return false;
+        }
+        if let Some(fingerprint) = &self.fingerprint {
+            if fingerprint != warning_fingerprint {
+                return false;
+            }
+        }
+        if let Some(cwe) = &self.cwe {
+            if *cwe != warning.name {
+                return false;
+            }
+        }
+        if let Some(address) = &self.address {
+            if !warning
+                .addresses
+                .iter()
+                .any(|warning_address| addresses_match(address, warning_address))
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// The contents of a suppression file.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct SuppressionFile {
+    /// The suppressed warnings.
+    pub suppressions: Vec<Suppression>,
+}
+
+impl VersionedArtifact for SuppressionFile {
+    const KIND: &'static str = "suppression_file";
+    const VERSION: u32 = 2;
+
+    /// Version 0 denotes hand-written suppression files without version stamp,
+    /// which already have the format of version 1.
+    ///
+    /// The fingerprints of version 1 were computed from the addresses of the warnings
+    /// and thus do not match the stable fingerprints of version 2.
+    /// They are removed from all suppressions that also contain a check name or an address,
+    /// so that these suppressions still match the warnings by their check name and address.
+    fn upgrade(version: u32, mut content: serde_json::Value) -> Result<serde_json::Value, Error> {
+        match version {
+            0 => Ok(content),
+            1 => {
+                if let Some(suppressions) = content["suppressions"].as_array_mut() {
+                    for suppression in suppressions.iter_mut() {
+                        if let Some(suppression) = suppression.as_object_mut() {
+                            if suppression.contains_key("cwe")
+                                || suppression.contains_key("address")
+                            {
+                                suppression.remove("fingerprint");
+                            }
+                        }
+                    }
+                }
+                Ok(content)
+            }
+            _ => Err(anyhow!(
+                "No upgrade path for {} artifacts of version {}",
+                Self::KIND,
+                version
+            )),
+        }
+    }
+}
+
+impl SuppressionFile {
+    /// Generate a baseline suppressing all given warnings.
+    ///
+    /// Each warning is suppressed through its fingerprint.
+    /// The check name and the description of the warning are added for readability.
+    /// The first address of the warning is only added if the warning has no stable fingerprint,
+    /// since otherwise the suppression would not match the warning anymore after recompilation.
+    pub fn from_warnings(warnings: &[CweWarning]) -> SuppressionFile {
+        let suppressions = warnings
+            .iter()
+            .map(|warning| Suppression {
+                fingerprint: Some(fingerprint(warning)),
+                cwe: Some(warning.name.clone()),
+                address: match warning.fingerprint {
+                    Some(_) => None,
+                    None => warning.addresses.get(0).cloned(),
+                },
+                comment: Some(warning.description.clone()),
+            })
+            .collect();
+        SuppressionFile { suppressions }
+    }
+
+    /// Remove all suppressed warnings and return the number of removed warnings.
+    pub fn filter_warnings(&self, warnings: &mut Vec<CweWarning>) -> usize {
+        let number_of_warnings = warnings.len();
+        warnings.retain(|warning| {
+            let warning_fingerprint = fingerprint(warning);
+            !self
+                .suppressions
+                .iter()
+                .any(|suppression| suppression.matches(warning, &warning_fingerprint))
+        });
+        number_of_warnings - warnings.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_warnings() -> Vec<CweWarning> {
+        vec![
+            CweWarning::new("CWE476", "0.3", "first").addresses(vec!["00401000".to_string()]),
+            CweWarning::new("CWE476", "0.3", "second").addresses(vec!["00401020".to_string()]),
+            CweWarning::new("CWE676", "0.1", "third")
+                .addresses(vec!["00401020".to_string()])
+                .symbols(vec!["strcpy".to_string()]),
+        ]
+    }
+
+    #[test]
+    fn baseline_suppresses_all_warnings() {
+        let mut warnings = mock_warnings();
+        let baseline = SuppressionFile::from_warnings(&warnings);
+        assert_eq!(baseline.filter_warnings(&mut warnings), 3);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn address_and_cwe_suppression() {
+        let mut warnings = mock_warnings();
+        let suppression_file = SuppressionFile {
+            suppressions: vec![
+                Suppression {
+                    cwe: Some("CWE476".to_string()),
+                    address: Some("0x401020".to_string()),
+                    ..Default::default()
+                },
+                Suppression {
+                    comment: Some("matches nothing".to_string()),
+                    ..Default::default()
+                },
+            ],
+        };
+        assert_eq!(suppression_file.filter_warnings(&mut warnings), 1);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[1].name, "CWE676");
+    }
+
+    #[test]
+    fn upgrade_address_based_fingerprints() {
+        use crate::utils::artifact::{read_artifact, IR_VERSION};
+        let content = serde_json::json!({
+            "cwe_checker_artifact": {
+                "kind": "suppression_file",
+                "version": 1,
+                "ir_version": IR_VERSION,
+                "generator": "cwe_checker_lib 0.5.0"
+            },
+            "suppressions": [
+                {"fingerprint": "0123456789abcdef", "cwe": "CWE476", "address": "00401000"},
+                {"fingerprint": "fedcba9876543210"}
+            ]
+        });
+        let suppression_file: SuppressionFile =
+            read_artifact(content.to_string().as_bytes()).unwrap();
+        assert_eq!(suppression_file.suppressions[0].fingerprint, None);
+        assert_eq!(
+            suppression_file.suppressions[1].fingerprint,
+            Some("fedcba9876543210".to_string())
+        );
+    }
+}
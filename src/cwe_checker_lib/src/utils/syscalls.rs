@@ -0,0 +1,302 @@
+//! Models of raw system calls for statically linked binaries.
+//!
+//! Statically linked binaries often contain no calls to extern symbols at all,
+//! since the libc wrappers like `read` or `execve` are part of the binary and end in raw syscall instructions
+//! (e.g. `syscall` on x86_64, `int 0x80` on x86 or `svc` on ARM).
+//! Ghidra translates these instructions to `CallOther` jumps,
+//! which are not recognized as sources of untrusted input, allocations or command execution by any check.
+//!
+//! The `Syscalls` section of the configuration file maps syscall numbers per CPU architecture
+//! to the names of the corresponding libc wrappers.
+//! Each syscall instruction whose syscall number is a constant set in the same basic block
+//! is replaced by a call to an extern symbol with the name of the wrapper
+//! and with the parameters and return value given by the syscall ABI of the architecture.
+//! The wrapper names are also added to the symbol lists of the corresponding checks
+//! (taint sources, allocators and command execution sinks),
+//! so that syscalls are handled like calls to their libc wrappers.
+
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::LogMessage;
+use crate::utils::sdk_models::{add_symbols_to_config_lists, ALLOCATOR_LISTS, TAINT_SOURCE_LISTS};
+use std::collections::{BTreeMap, HashMap};
+
+/// The syscall ABI and the syscall numbers of a single CPU architecture.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+#[serde(default)]
+pub struct SyscallConvention {
+    /// The descriptions of the `CallOther` jumps that Ghidra generates for syscall instructions.
+    pub instructions: Vec<String>,
+    /// The register containing the syscall number.
+    pub number_register: String,
+    /// The registers containing the syscall parameters in the order of the parameters.
+    pub parameter_register: Vec<String>,
+    /// The register containing the return value of a syscall.
+    pub return_register: String,
+    /// The syscall numbers indexed by the names of the corresponding libc wrappers.
+    pub syscalls: BTreeMap<String, u64>,
+}
+
+/// The configured syscall models.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Default)]
+#[serde(default)]
+pub struct Config {
+    /// Syscalls returning untrusted input, e.g. `read`.
+    pub taint_sources: Vec<String>,
+    /// Syscalls allocating memory, e.g. `mmap`.
+    pub allocators: Vec<String>,
+    /// Syscalls executing commands, e.g. `execve`.
+    pub command_sinks: Vec<String>,
+    /// Syscalls that never return, e.g. `exit`.
+    pub non_returning: Vec<String>,
+    /// The syscall conventions indexed by the CPU architecture (as given in the Ghidra output) that they apply to.
+    pub architectures: BTreeMap<String, SyscallConvention>,
+}
+
+/// The configuration sections and the names of their symbol lists
+/// to which the command execution syscalls are added.
+const COMMAND_SINK_LISTS: [(&str, &str); 1] = [("CWE78", "system_symbols")];
+
+impl Config {
+    /// Add the modelled syscalls to the symbol lists of the corresponding checks in the configuration.
+    ///
+    /// Symbol lists that do not exist in the configuration are not created.
+    pub fn apply_to_config(&self, config: &mut serde_json::Value) {
+        add_symbols_to_config_lists(config, &self.taint_sources, &TAINT_SOURCE_LISTS);
+        add_symbols_to_config_lists(config, &self.allocators, &ALLOCATOR_LISTS);
+        add_symbols_to_config_lists(config, &self.command_sinks, &COMMAND_SINK_LISTS);
+    }
+
+    /// Replace all syscall instructions with a known syscall number by calls to extern symbols
+    /// named after the libc wrappers of the syscalls.
+    ///
+    /// Does nothing if no syscall convention is configured for the CPU architecture of the project.
+    #[must_use]
+    pub fn apply_to_project(&self, project: &mut Project) -> Vec<LogMessage> {
+        let convention = match self.architectures.get(&project.cpu_architecture) {
+            Some(convention) => convention,
+            None => return Vec::new(),
+        };
+        let syscall_names: HashMap<u64, &String> = convention
+            .syscalls
+            .iter()
+            .map(|(name, number)| (*number, name))
+            .collect();
+        let register_size = project.get_pointer_bytesize();
+        let mut syscall_symbols: BTreeMap<String, ExternSymbol> = BTreeMap::new();
+        let mut number_of_unresolved_syscalls = 0;
+        for sub in project.program.term.subs.iter_mut() {
+            for block in sub.term.blocks.iter_mut() {
+                let syscall_number =
+                    get_syscall_number(&block.term.defs, &convention.number_register);
+                for jmp in block.term.jmps.iter_mut() {
+                    let return_ = match &jmp.term {
+                        Jmp::CallOther {
+                            description,
+                            return_,
+                        } if convention.instructions.contains(description) => return_.clone(),
+                        _ => continue,
+                    };
+                    let name = match syscall_number.and_then(|number| syscall_names.get(&number)) {
+                        Some(name) => *name,
+                        None => {
+                            number_of_unresolved_syscalls += 1;
+                            continue;
+                        }
+                    };
+                    let symbol = syscall_symbols.entry(name.clone()).or_insert_with(|| {
+                        convention.to_extern_symbol(
+                            name,
+                            register_size,
+                            self.non_returning.contains(name),
+                        )
+                    });
+                    jmp.term = Jmp::Call {
+                        target: symbol.tid.clone(),
+                        return_,
+                    };
+                }
+            }
+        }
+        if syscall_symbols.is_empty() && number_of_unresolved_syscalls == 0 {
+            return Vec::new();
+        }
+        let number_of_modelled_syscalls = syscall_symbols.len();
+        project
+            .program
+            .term
+            .extern_symbols
+            .extend(syscall_symbols.into_iter().map(|(_name, symbol)| symbol));
+        vec![LogMessage::new_info(format!(
+            "Modelled {} different syscalls by their libc wrappers. {} syscalls with unknown syscall number were not modelled.",
+            number_of_modelled_syscalls, number_of_unresolved_syscalls
+        ))
+        .source("Syscalls")]
+    }
+}
+
+impl SyscallConvention {
+    /// Generate the extern symbol representing the syscall with the given name.
+    ///
+    /// All parameter registers of the syscall ABI are assumed to be parameters of the syscall.
+    fn to_extern_symbol(
+        &self,
+        name: &str,
+        register_size: ByteSize,
+        no_return: bool,
+    ) -> ExternSymbol {
+        let register = |name: &String| {
+            Arg::Register(Variable {
+                name: name.clone(),
+                size: register_size,
+                is_temp: false,
+            })
+        };
+        ExternSymbol {
+            tid: Tid::new(format!("syscall_{}", name)),
+            addresses: Vec::new(),
+            name: name.to_string(),
+            calling_convention: None,
+            parameters: self.parameter_register.iter().map(register).collect(),
+            return_values: vec![register(&self.return_register)],
+            no_return,
+        }
+    }
+}
+
+/// Get the syscall number if the last assignment to the number register in the given block
+/// assigns a constant to it.
+fn get_syscall_number(defs: &[Term<Def>], number_register: &str) -> Option<u64> {
+    for def in defs.iter().rev() {
+        match &def.term {
+            Def::Assign { var, value } if var.name == number_register => {
+                return get_constant(value)
+            }
+            Def::Load { var, .. } if var.name == number_register => return None,
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Get the value of the expression if it is a (possibly zero-extended) constant.
+fn get_constant(expression: &Expression) -> Option<u64> {
+    match expression {
+        Expression::Const(value) => value.try_to_u64().ok(),
+        Expression::Cast {
+            op: CastOpType::IntZExt,
+            arg,
+            ..
+        } => get_constant(arg),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_convention() -> SyscallConvention {
+        SyscallConvention {
+            instructions: vec!["syscall".to_string()],
+            number_register: "RAX".to_string(),
+            parameter_register: vec!["RDI".to_string(), "RSI".to_string()],
+            return_register: "RAX".to_string(),
+            syscalls: vec![("read".to_string(), 0), ("execve".to_string(), 59)]
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    fn mock_syscall_block(name: &str, number: Option<u64>) -> Term<Blk> {
+        let mut defs = vec![Def::assign(
+            &format!("{}_def", name),
+            Variable::mock("RDI", 8),
+            Expression::const_from_i64(0),
+        )];
+        if let Some(number) = number {
+            defs.push(Def::assign(
+                &format!("{}_number", name),
+                Variable::mock("RAX", 8),
+                Expression::Cast {
+                    op: CastOpType::IntZExt,
+                    size: ByteSize::new(8),
+                    arg: Box::new(Expression::Const(Bitvector::from_u32(number as u32))),
+                },
+            ));
+        }
+        Term {
+            tid: Tid::new(name),
+            term: Blk {
+                defs,
+                jmps: vec![Term {
+                    tid: Tid::new(format!("{}_jmp", name)),
+                    term: Jmp::CallOther {
+                        description: "syscall".to_string(),
+                        return_: Some(Tid::new("return_blk")),
+                    },
+                }],
+            },
+        }
+    }
+
+    #[test]
+    fn syscalls_replaced_by_calls() {
+        let mut project = Project::mock_empty();
+        let mut sub = Sub::mock("main");
+        sub.term.blocks = vec![
+            mock_syscall_block("blk_read", Some(0)),
+            mock_syscall_block("blk_execve", Some(59)),
+            mock_syscall_block("blk_unknown_number", Some(1000)),
+            mock_syscall_block("blk_no_number", None),
+        ];
+        project.program.term.subs.push(sub);
+        let mut config = Config {
+            command_sinks: vec!["execve".to_string()],
+            ..Config::default()
+        };
+        assert!(config.apply_to_project(&mut project).is_empty());
+        config
+            .architectures
+            .insert(project.cpu_architecture.clone(), mock_convention());
+
+        let logs = config.apply_to_project(&mut project);
+        assert_eq!(logs.len(), 1);
+        let blocks = &project.program.term.subs[0].term.blocks;
+        assert_eq!(
+            blocks[0].term.jmps[0].term,
+            Jmp::Call {
+                target: Tid::new("syscall_read"),
+                return_: Some(Tid::new("return_blk")),
+            }
+        );
+        assert_eq!(
+            blocks[1].term.jmps[0].term,
+            Jmp::Call {
+                target: Tid::new("syscall_execve"),
+                return_: Some(Tid::new("return_blk")),
+            }
+        );
+        for block in &blocks[2..] {
+            assert!(matches!(block.term.jmps[0].term, Jmp::CallOther { .. }));
+        }
+        let symbols = &project.program.term.extern_symbols;
+        assert_eq!(symbols.len(), 2);
+        let execve = symbols
+            .iter()
+            .find(|symbol| symbol.name == "execve")
+            .unwrap();
+        assert_eq!(execve.parameters.len(), 2);
+        assert_eq!(
+            execve.get_unique_return_register().unwrap(),
+            &Variable::mock("RAX", 8)
+        );
+
+        let mut check_config = serde_json::json!({"CWE78": {"system_symbols": ["system"]}});
+        config.apply_to_config(&mut check_config);
+        assert_eq!(
+            check_config,
+            serde_json::json!({"CWE78": {"system_symbols": ["system", "execve"]}})
+        );
+    }
+}
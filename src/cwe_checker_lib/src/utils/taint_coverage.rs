@@ -0,0 +1,150 @@
+//! Coverage diagnostics for the configured sources and sinks of the taint-based checks.
+//!
+//! If a taint-based check finds nothing, this may either mean that the binary is free of the weakness
+//! or that the analysis never saw the sources or sinks of the weakness.
+//! To distinguish both cases, the diagnostics list for each taint-based check that was executed
+//! - the configured sources and sinks that are imported by the binary but not connected to any warning of the check
+//! - and the configured sources and sinks that are not imported by the binary at all.
+//!
+//! A source or sink is connected to a warning if its name is contained in the symbols or in the other information of the warning.
+
+use crate::intermediate_representation::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use std::collections::{BTreeSet, HashSet};
+
+/// The role of a configured symbol in a taint-based check.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum SymbolRole {
+    /// The symbol introduces tainted values, e.g. untrusted input.
+    Source,
+    /// The symbol must not be reached by tainted values, e.g. a function executing commands.
+    Sink,
+}
+
+impl std::fmt::Display for SymbolRole {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SymbolRole::Source => write!(formatter, "sources"),
+            SymbolRole::Sink => write!(formatter, "sinks"),
+        }
+    }
+}
+
+/// The symbol lists of the taint-based checks, given as the name of the check,
+/// the name of the symbol list in the configuration section of the check and the role of the symbols.
+const TAINT_SYMBOL_LISTS: [(&str, &str, SymbolRole); 3] = [
+    ("CWE78", "system_symbols", SymbolRole::Sink),
+    ("CWE78", "user_input_symbols", SymbolRole::Source),
+    ("CWE476", "symbols", SymbolRole::Source),
+];
+
+/// Generate the coverage diagnostics for the sources and sinks of the given check as log messages with info level.
+///
+/// The warnings should be the unfiltered warnings generated by the check.
+/// Returns no log messages if the check is not a taint-based check.
+pub fn get_coverage_logs(
+    project: &Project,
+    config: &serde_json::Value,
+    module_name: &str,
+    warnings: &[CweWarning],
+) -> Vec<LogMessage> {
+    let imported_symbols: HashSet<&str> = project
+        .program
+        .term
+        .extern_symbols
+        .iter()
+        .map(|symbol| symbol.name.as_str())
+        .collect();
+    let connected_symbols: HashSet<&str> = warnings
+        .iter()
+        .flat_map(|warning| {
+            warning
+                .symbols
+                .iter()
+                .chain(warning.other.iter().flatten())
+                .map(|symbol| symbol.as_str())
+        })
+        .collect();
+    let mut logs = Vec::new();
+    for (_, list, role) in TAINT_SYMBOL_LISTS
+        .iter()
+        .filter(|(check, _, _)| *check == module_name)
+    {
+        let configured_symbols: BTreeSet<&str> = match config[module_name][list].as_array() {
+            Some(symbols) => symbols
+                .iter()
+                .filter_map(|symbol| symbol.as_str())
+                .collect(),
+            None => continue,
+        };
+        let (present, absent): (Vec<&str>, Vec<&str>) = configured_symbols
+            .into_iter()
+            .partition(|symbol| imported_symbols.contains(symbol));
+        let unconnected: Vec<&str> = present
+            .into_iter()
+            .filter(|symbol| !connected_symbols.contains(symbol))
+            .collect();
+        if !unconnected.is_empty() {
+            logs.push(
+                LogMessage::new_info(format!(
+                    "Configured {} imported by the binary but not connected to any warning: {}",
+                    role,
+                    unconnected.join(", ")
+                ))
+                .source(module_name),
+            );
+        }
+        if !absent.is_empty() {
+            logs.push(
+                LogMessage::new_info(format!(
+                    "Configured {} not imported by the binary: {}",
+                    role,
+                    absent.join(", ")
+                ))
+                .source(module_name),
+            );
+        }
+    }
+    logs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coverage_of_sinks_and_sources() {
+        let mut project = Project::mock_empty();
+        for name in ["system", "popen", "scanf"].iter() {
+            project
+                .program
+                .term
+                .extern_symbols
+                .push(ExternSymbol::mock_with_name(*name));
+        }
+        let config = serde_json::json!({
+            "CWE78": {
+                "system_symbols": ["system", "popen", "execl"],
+                "user_input_symbols": ["scanf"]
+            }
+        });
+        let warnings = vec![
+            CweWarning::new("CWE78", "0.1", "Command injection").other(vec![vec![
+                "OS Command Injection".to_string(),
+                "system".to_string(),
+            ]]),
+        ];
+        let logs = get_coverage_logs(&project, &config, "CWE78", &warnings);
+        let texts: Vec<&str> = logs.iter().map(|log| log.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "Configured sinks imported by the binary but not connected to any warning: popen",
+                "Configured sinks not imported by the binary: execl",
+                "Configured sources imported by the binary but not connected to any warning: scanf",
+            ]
+        );
+        assert_eq!(logs[0].source, Some("CWE78".to_string()));
+        assert!(get_coverage_logs(&project, &config, "CWE416", &warnings).is_empty());
+    }
+}
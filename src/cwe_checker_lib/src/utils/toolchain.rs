@@ -0,0 +1,425 @@
+//! Identification of the toolchain that produced a binary and toolchain-specific tuning of the analysis.
+//!
+//! Each compiler generates its own support code and idioms,
+//! e.g. the security cookie checks of MSVC or the fortified `__memcpy_chk` family of functions of GCC.
+//! Heuristics that work well for one toolchain cause noise for binaries of another toolchain.
+//!
+//! The toolchain is identified from
+//! - the `.comment` section of ELF files, which contains the version strings of the compilers,
+//! - the Rich header of PE files, which contains the build numbers of the Microsoft tools,
+//! - the build information embedded by the Go compiler,
+//! - idioms, i.e. support functions characteristic for a toolchain.
+//!
+//! For each identified toolchain the profile of the toolchain in the configuration is applied:
+//! Its compiler-generated functions are added to the [`generated_code`](crate::utils::generated_code) configuration,
+//! its normalization pass flags override the normalization configuration
+//! and warnings for symbols matching its known false positive patterns are removed.
+
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::components::matches_pattern;
+use crate::utils::generated_code;
+use crate::utils::log::CweWarning;
+use goblin::{elf, Object};
+use std::collections::BTreeMap;
+
+/// The magic bytes at the start of the build information embedded by the Go compiler.
+const GO_BUILDINFO_MAGIC: &[u8] = b"\xff Go buildinf:";
+/// The marker ending the Rich header of PE files.
+const RICH_MARKER: &[u8] = b"Rich";
+/// The (unencrypted) marker starting the Rich header of PE files, i.e. `DanS`.
+const DANS_MARKER: u32 = 0x536e_6144;
+
+/// Support functions characteristic for a compiler,
+/// used to identify the compiler if no version information is contained in the binary.
+const IDIOMS: &[(&str, Compiler)] = &[
+    ("__security_check_cookie", Compiler::Msvc),
+    ("__GSHandlerCheck", Compiler::Msvc),
+    ("_RTC_CheckStackVars", Compiler::Msvc),
+    ("rust_begin_unwind", Compiler::Rustc),
+    ("__rust_alloc", Compiler::Rustc),
+    ("runtime.morestack", Compiler::Go),
+    ("runtime.main", Compiler::Go),
+];
+
+/// A compiler producing binaries.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Compiler {
+    /// The GNU Compiler Collection.
+    Gcc,
+    /// The Clang frontend of LLVM.
+    Clang,
+    /// The Microsoft Visual C++ compiler.
+    Msvc,
+    /// The Rust compiler.
+    Rustc,
+    /// The Go compiler.
+    Go,
+}
+
+impl Compiler {
+    /// The name of the compiler as used for the profiles in the configuration.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Compiler::Gcc => "gcc",
+            Compiler::Clang => "clang",
+            Compiler::Msvc => "msvc",
+            Compiler::Rustc => "rustc",
+            Compiler::Go => "go",
+        }
+    }
+}
+
+impl std::fmt::Display for Compiler {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Compiler::Gcc => "GCC",
+            Compiler::Clang => "Clang",
+            Compiler::Msvc => "MSVC",
+            Compiler::Rustc => "rustc",
+            Compiler::Go => "Go",
+        };
+        write!(formatter, "{}", name)
+    }
+}
+
+/// A toolchain identified for a binary.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Toolchain {
+    /// The compiler that produced (parts of) the binary.
+    pub compiler: Compiler,
+    /// The version of the compiler, if known.
+    pub version: Option<String>,
+    /// A short description of where the toolchain was identified from, e.g. `the .comment section`.
+    pub source: String,
+}
+
+impl std::fmt::Display for Toolchain {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.version {
+            Some(version) => write!(formatter, "{} {}", self.compiler, version)?,
+            None => write!(formatter, "{}", self.compiler)?,
+        }
+        write!(formatter, " (identified from {})", self.source)
+    }
+}
+
+/// The toolchain-specific tuning of the analysis.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+#[serde(default)]
+pub struct ToolchainProfile {
+    /// Name patterns of support functions generated by the toolchain (which may contain `*` as a wildcard).
+    /// They are treated as compiler-generated functions, see [`generated_code`].
+    pub generated_functions: Vec<String>,
+    /// Name patterns of symbols whose warnings are known false positives for binaries of the toolchain.
+    /// Warnings containing a matching symbol are removed.
+    pub false_positive_symbols: Vec<String>,
+    /// Enable flags of normalization passes indexed by the pass names,
+    /// overriding the flags of the `Normalization` section.
+    pub normalization_passes: BTreeMap<String, bool>,
+}
+
+impl ToolchainProfile {
+    /// Add the compiler-generated functions of the profile to the configuration of the detection of compiler-generated functions.
+    pub fn apply_to_generated_code(&self, config: &mut generated_code::Config) {
+        config
+            .functions
+            .extend(self.generated_functions.iter().cloned());
+    }
+
+    /// Override the enable flags of the normalization passes with the flags of the profile.
+    pub fn apply_to_normalization(&self, config: &mut NormalizationConfig) {
+        config.passes.extend(
+            self.normalization_passes
+                .iter()
+                .map(|(pass, enabled)| (pass.clone(), *enabled)),
+        );
+    }
+
+    /// Remove the warnings containing a symbol that matches a known false positive pattern of the profile.
+    /// Returns the number of removed warnings.
+    pub fn filter_warnings(&self, warnings: &mut Vec<CweWarning>) -> usize {
+        if self.false_positive_symbols.is_empty() {
+            return 0;
+        }
+        let number_of_warnings = warnings.len();
+        warnings.retain(|warning| {
+            !warning.symbols.iter().any(|symbol| {
+                self.false_positive_symbols
+                    .iter()
+                    .any(|pattern| matches_pattern(pattern, symbol))
+            })
+        });
+        number_of_warnings - warnings.len()
+    }
+
+    /// Merge the other profile into this profile.
+    /// The normalization pass flags of the other profile take precedence.
+    fn merge(&mut self, other: &ToolchainProfile) {
+        self.generated_functions
+            .extend(other.generated_functions.iter().cloned());
+        self.false_positive_symbols
+            .extend(other.false_positive_symbols.iter().cloned());
+        self.normalization_passes.extend(
+            other
+                .normalization_passes
+                .iter()
+                .map(|(pass, enabled)| (pass.clone(), *enabled)),
+        );
+    }
+}
+
+/// The configuration of the toolchain identification.
+///
+/// The configuration is read from the `Toolchains` section of the configuration file.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+#[serde(default)]
+pub struct Config {
+    /// If set to `false`, no toolchain-specific tuning is applied.
+    pub enabled: bool,
+    /// The profiles of the toolchains indexed by the compiler names, e.g. `gcc` or `msvc`.
+    pub profiles: BTreeMap<String, ToolchainProfile>,
+}
+
+impl Config {
+    /// Get the combined profile of the given toolchains.
+    /// If the tuning is disabled, an empty profile is returned.
+    pub fn get_profile(&self, toolchains: &[Toolchain]) -> ToolchainProfile {
+        let mut profile = ToolchainProfile::default();
+        if self.enabled {
+            for toolchain in toolchains {
+                if let Some(toolchain_profile) = self.profiles.get(toolchain.compiler.name()) {
+                    profile.merge(toolchain_profile);
+                }
+            }
+        }
+        profile
+    }
+}
+
+/// Identify the toolchains that produced the binary.
+///
+/// Binaries may contain code of several toolchains,
+/// e.g. C runtime objects compiled by GCC linked into a binary compiled by Clang.
+/// Each compiler is contained at most once in the result.
+pub fn identify_toolchains(binary: &[u8], project: &Project) -> Vec<Toolchain> {
+    let mut toolchains = Vec::new();
+    match Object::parse(binary) {
+        Ok(Object::Elf(elf_file)) => toolchains.extend(get_toolchains_from_elf(binary, &elf_file)),
+        Ok(Object::PE(_)) => toolchains.extend(get_toolchain_from_rich_header(binary)),
+        _ => (),
+    }
+    toolchains.extend(get_go_toolchain(binary));
+    let program = &project.program.term;
+    let names = program
+        .extern_symbols
+        .iter()
+        .map(|symbol| &symbol.name)
+        .chain(program.subs.iter().map(|sub| &sub.term.name));
+    for name in names {
+        if let Some((_, compiler)) = IDIOMS.iter().find(|(idiom, _)| *idiom == name.as_str()) {
+            toolchains.push(Toolchain {
+                compiler: *compiler,
+                version: None,
+                source: format!("the function {}", name),
+            });
+        }
+    }
+    let mut identified_compilers = Vec::new();
+    toolchains.retain(|toolchain| {
+        if identified_compilers.contains(&toolchain.compiler) {
+            false
+        } else {
+            identified_compilers.push(toolchain.compiler);
+            true
+        }
+    });
+    toolchains
+}
+
+/// Get the toolchains from the compiler version strings in the `.comment` section of an ELF file.
+fn get_toolchains_from_elf(binary: &[u8], elf_file: &elf::Elf) -> Vec<Toolchain> {
+    let comment = elf_file.section_headers.iter().find_map(|header| {
+        match elf_file.shdr_strtab.get(header.sh_name) {
+            Some(Ok(".comment")) => binary.get(header.file_range()),
+            _ => None,
+        }
+    });
+    match comment {
+        Some(comment) => comment
+            .split(|byte| *byte == 0)
+            .filter_map(|entry| parse_comment_entry(&String::from_utf8_lossy(entry)))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Parse a compiler version string of the `.comment` section,
+/// e.g. `GCC: (Ubuntu 9.3.0-17ubuntu1~20.04) 9.3.0` or `clang version 11.0.0`.
+fn parse_comment_entry(entry: &str) -> Option<Toolchain> {
+    let (compiler, version) = if entry.starts_with("GCC:") {
+        (Compiler::Gcc, entry.split_whitespace().last())
+    } else if let Some(index) = entry.find("clang version ") {
+        (
+            Compiler::Clang,
+            entry[index + "clang version ".len()..]
+                .split_whitespace()
+                .next(),
+        )
+    } else if let Some(rest) = entry.strip_prefix("rustc version ") {
+        (Compiler::Rustc, rest.split_whitespace().next())
+    } else {
+        return None;
+    };
+    Some(Toolchain {
+        compiler,
+        version: version.map(|version| version.to_string()),
+        source: "the .comment section".to_string(),
+    })
+}
+
+/// Get the Microsoft toolchain from the Rich header of a PE file.
+///
+/// The Rich header is located between the DOS stub and the PE header.
+/// It ends with the `Rich` marker followed by a key,
+/// with which all entries of the header (starting with the `DanS` marker) are XOR-encrypted.
+/// Each entry contains the product ID and build number of a tool and the number of objects built with it.
+/// The version is given as the highest build number contained in the header.
+fn get_toolchain_from_rich_header(binary: &[u8]) -> Option<Toolchain> {
+    let read_u32 = |offset: usize| -> Option<u32> {
+        let bytes = binary.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    };
+    let pe_header_offset = read_u32(0x3c)? as usize;
+    let stub = binary.get(..pe_header_offset)?;
+    let rich_offset = stub
+        .windows(RICH_MARKER.len())
+        .position(|window| window == RICH_MARKER)?;
+    let key = read_u32(rich_offset + 4)?;
+    let mut dans_offset = rich_offset.checked_sub(4)?;
+    while read_u32(dans_offset)? ^ key != DANS_MARKER {
+        dans_offset = dans_offset.checked_sub(4)?;
+    }
+    // The `DanS` marker is followed by three padding fields.
+    let build = (dans_offset + 16..rich_offset)
+        .step_by(8)
+        .filter_map(|offset| read_u32(offset).map(|comp_id| comp_id ^ key))
+        .filter(|comp_id| comp_id >> 16 != 0)
+        .map(|comp_id| comp_id & 0xffff)
+        .max();
+    Some(Toolchain {
+        compiler: Compiler::Msvc,
+        version: build.map(|build| format!("build {}", build)),
+        source: "the Rich header".to_string(),
+    })
+}
+
+/// Get the Go toolchain from the build information embedded by the Go compiler.
+fn get_go_toolchain(binary: &[u8]) -> Option<Toolchain> {
+    let offset = binary
+        .windows(GO_BUILDINFO_MAGIC.len())
+        .position(|window| window == GO_BUILDINFO_MAGIC)?;
+    let buildinfo = &binary[offset..binary.len().min(offset + 64)];
+    let version = buildinfo
+        .windows(4)
+        .position(|window| window == b"go1.")
+        .map(|start| {
+            buildinfo[start..]
+                .iter()
+                .take_while(|byte| byte.is_ascii_alphanumeric() || **byte == b'.')
+                .map(|byte| *byte as char)
+                .collect()
+        });
+    Some(Toolchain {
+        compiler: Compiler::Go,
+        version,
+        source: "the Go build information".to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comment_parsing() {
+        let gcc = parse_comment_entry("GCC: (Ubuntu 9.3.0-17ubuntu1~20.04) 9.3.0").unwrap();
+        assert_eq!(gcc.compiler, Compiler::Gcc);
+        assert_eq!(gcc.version.as_deref(), Some("9.3.0"));
+        let clang =
+            parse_comment_entry("Ubuntu clang version 11.0.0-2 (https://github.com/llvm)").unwrap();
+        assert_eq!(clang.compiler, Compiler::Clang);
+        assert_eq!(clang.version.as_deref(), Some("11.0.0-2"));
+        assert_eq!(
+            clang.to_string(),
+            "Clang 11.0.0-2 (identified from the .comment section)"
+        );
+        assert_eq!(parse_comment_entry("Linker: LLD 11.0.0"), None);
+    }
+
+    #[test]
+    fn rich_header() {
+        let key = 0x1234_5678u32;
+        let mut binary = vec![0u8; 0x80];
+        for value in [
+            DANS_MARKER,
+            0,
+            0,
+            0,
+            (0x0105 << 16) | 30133,
+            3,
+            (0x0104 << 16) | 29335,
+            1,
+        ]
+        .iter()
+        {
+            binary.extend_from_slice(&(value ^ key).to_le_bytes());
+        }
+        binary.extend_from_slice(RICH_MARKER);
+        binary.extend_from_slice(&key.to_le_bytes());
+        let pe_header_offset = binary.len() as u32;
+        binary[0x3c..0x40].copy_from_slice(&pe_header_offset.to_le_bytes());
+        binary.extend_from_slice(b"PE\0\0");
+        let toolchain = get_toolchain_from_rich_header(&binary).unwrap();
+        assert_eq!(toolchain.compiler, Compiler::Msvc);
+        assert_eq!(toolchain.version.as_deref(), Some("build 30133"));
+    }
+
+    #[test]
+    fn profiles() {
+        let mut project = Project::mock_empty();
+        project
+            .program
+            .term
+            .extern_symbols
+            .push(ExternSymbol::mock_with_name("__security_check_cookie"));
+        let toolchains = identify_toolchains(&[], &project);
+        assert_eq!(toolchains.len(), 1);
+        assert_eq!(toolchains[0].compiler, Compiler::Msvc);
+
+        let msvc_profile = ToolchainProfile {
+            generated_functions: vec!["__scrt_*".to_string()],
+            false_positive_symbols: vec!["__*_chk".to_string()],
+            normalization_passes: BTreeMap::new(),
+        };
+        let mut config = Config {
+            enabled: true,
+            profiles: BTreeMap::new(),
+        };
+        config
+            .profiles
+            .insert("msvc".to_string(), msvc_profile.clone());
+        assert_eq!(config.get_profile(&toolchains), msvc_profile);
+        config.enabled = false;
+        assert_eq!(config.get_profile(&toolchains), ToolchainProfile::default());
+
+        let mut warnings = vec![
+            CweWarning::new("CWE119", "0.1", "description")
+                .symbols(vec!["main".to_string(), "__memcpy_chk".to_string()]),
+            CweWarning::new("CWE119", "0.1", "description").symbols(vec!["memcpy".to_string()]),
+        ];
+        assert_eq!(msvc_profile.filter_warnings(&mut warnings), 1);
+        assert_eq!(warnings[0].symbols, vec!["memcpy".to_string()]);
+    }
+}
@@ -55,6 +55,8 @@ impl CweTestCase {
             .arg("--partial")
             .arg(self.check_name)
             .arg("--quiet")
+            .arg("--format")
+            .arg("text-plain")
             .output()
             .unwrap();
         if output.status.success() {